@@ -0,0 +1,150 @@
+/// Compare the circuit cost of hashing two Pasta `Fp` elements with Poseidon
+/// (`halo2_gadgets`' `Pow5Chip`, P128Pow5T3: width 3, rate 2, x^5, 8 full and
+/// 56 partial rounds) and with MiMC (`gadgets::mimc`, x^5, 110 rounds,
+/// Miyaguchi-Preneel). `docs/hash_comparison.md` tabulates the output.
+///
+/// $ cargo bench --bench hash_comparison
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_gadgets::poseidon::{
+    primitives::{ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::{CircuitCost, MockProver},
+    pasta::{Eq, Fp},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+};
+use halo2_tutorials::gadgets::{
+    mimc::{MimcChip, MimcConfig},
+    Number,
+};
+
+const K: u32 = 9;
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+#[derive(Default)]
+struct PoseidonCircuit {
+    msg: [Value<Fp>; 2],
+}
+
+impl Circuit<Fp> for PoseidonCircuit {
+    type Config = (Pow5Config<Fp, WIDTH, RATE>, [Column<Advice>; WIDTH]);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let state = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let rc_a = [(); WIDTH].map(|_| meta.fixed_column());
+        let rc_b: [Column<Fixed>; WIDTH] = [(); WIDTH].map(|_| meta.fixed_column());
+        meta.enable_constant(rc_b[0]);
+        for col in state {
+            meta.enable_equality(col);
+        }
+        let config = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+        (config, state)
+    }
+
+    fn synthesize(
+        &self,
+        (config, state): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let msg = layouter.assign_region(
+            || "load msg",
+            |mut region| {
+                let a = region.assign_advice(|| "m_0", state[0], 0, || self.msg[0])?;
+                let b = region.assign_advice(|| "m_1", state[1], 0, || self.msg[1])?;
+                Ok([a, b])
+            },
+        )?;
+        let chip = Pow5Chip::construct(config);
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher.hash(layouter.namespace(|| "hash"), msg)?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct MimcCircuit {
+    msg: [Value<Fp>; 2],
+}
+
+impl Circuit<Fp> for MimcCircuit {
+    type Config = MimcConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        MimcChip::configure(meta, advice, constant)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let advice = config.advice;
+        let msg = layouter.assign_region(
+            || "load msg",
+            |mut region| {
+                let a = region.assign_advice(|| "m_0", advice[2], 0, || self.msg[0])?;
+                let b = region.assign_advice(|| "m_1", advice[2], 1, || self.msg[1])?;
+                Ok([Number(a), Number(b)])
+            },
+        )?;
+        let chip = MimcChip::construct(config);
+        chip.hash(layouter.namespace(|| "hash"), &msg)?;
+        Ok(())
+    }
+}
+
+/// Print the static shape of `C`: its gate polynomials and columns, and the
+/// row and proof-size estimate of `CircuitCost`.
+fn report<C: Circuit<Fp>>(name: &str, circuit: &C) {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    let polys: Vec<_> = cs.gates().iter().flat_map(|g| g.polynomials()).collect();
+    println!(
+        "{}: {} gate constraints (max degree {}), {} advice, {} fixed columns",
+        name,
+        polys.len(),
+        polys.iter().map(|p| p.degree()).max().unwrap_or(0),
+        cs.num_advice_columns(),
+        cs.num_fixed_columns(),
+    );
+    println!("{}: {:#?}", name, CircuitCost::<Eq, _>::measure(K, circuit));
+}
+
+fn bench_hash_comparison(c: &mut Criterion) {
+    let msg = [Value::known(Fp::from(1)), Value::known(Fp::from(2))];
+    let poseidon = PoseidonCircuit { msg };
+    let mimc = MimcCircuit { msg };
+    report("poseidon", &poseidon);
+    report("mimc", &mimc);
+
+    let mut group = c.benchmark_group("hash 2 elements");
+    group.bench_function("poseidon", |b| {
+        b.iter(|| MockProver::run(K, &poseidon, vec![]).unwrap())
+    });
+    group.bench_function("mimc", |b| {
+        b.iter(|| MockProver::run(K, &mimc, vec![]).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash_comparison);
+criterion_main!(benches);
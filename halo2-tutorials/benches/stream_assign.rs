@@ -0,0 +1,99 @@
+/// Compare `StreamAssignChip::assign_stream` against the usual
+/// "clone the `Vec` into the region" approach.
+///
+/// $ cargo bench --bench stream_assign
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use halo2_tutorials::gadgets::stream_assign::{StreamAssignChip, StreamAssignConfig};
+
+const K: u32 = 12;
+
+#[derive(Default)]
+struct StreamCircuit {
+    len: u64,
+}
+
+impl Circuit<Fp> for StreamCircuit {
+    type Config = StreamAssignConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = meta.advice_column();
+        StreamAssignChip::configure(meta, advice)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let chip = StreamAssignChip::construct(config);
+        let values = (0..self.len).map(|i| Value::known(Fp::from(i)));
+        chip.assign_stream(layouter, values)?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct CloningCircuit {
+    values: Vec<Value<Fp>>,
+}
+
+impl Circuit<Fp> for CloningCircuit {
+    type Config = StreamAssignConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = meta.advice_column();
+        StreamAssignChip::configure(meta, advice)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "load vec",
+            |mut region| {
+                // The closure runs once per floor planner pass, every pass clones.
+                let values = self.values.clone();
+                for (offset, value) in values.into_iter().enumerate() {
+                    region.assign_advice(|| "value", config.advice, offset, || value)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn bench_stream_assign(c: &mut Criterion) {
+    let mut group = c.benchmark_group("assign 1000 values");
+    let len = 1000;
+
+    let circuit = StreamCircuit { len };
+    group.bench_function(BenchmarkId::new("assign_stream", len), |b| {
+        b.iter(|| MockProver::run(K, &circuit, vec![]).unwrap())
+    });
+
+    let circuit = CloningCircuit {
+        values: (0..len).map(|i| Value::known(Fp::from(i))).collect(),
+    };
+    group.bench_function(BenchmarkId::new("clone vec", len), |b| {
+        b.iter(|| MockProver::run(K, &circuit, vec![]).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_stream_assign);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Fuzz chap 2 exercise 5: for any private `a`, `b` and constant `c`, the
+//! circuit verifies with `out = (a^2 * b^2 * c + c)^3` and with nothing else.
+//!
+//! $ cargo +nightly fuzz run exercise_5
+//!
+//! See `docs/fuzzing.md`.
+#![no_main]
+
+use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+use halo2_tutorials::Exercise5Circuit;
+use libfuzzer_sys::fuzz_target;
+
+const K: u32 = 5;
+
+/// Read 32 little-endian bytes, zero padded, as an integer below 2^256 and
+/// reduce it mod p.
+fn field_element(bytes: &[u8]) -> Fp {
+    let mut buf = [0u8; 32];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(buf.chunks(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Fp::from_raw(limbs)
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first 96 bytes are a, b and c; shorter inputs are zero padded.
+    let mut chunks = data.chunks(32);
+    let [a, b, c] = [(); 3].map(|_| field_element(chunks.next().unwrap_or(&[])));
+
+    let e = a.square() * b.square() * c + c;
+    let out = e.square() * e;
+
+    let circuit = Exercise5Circuit {
+        c,
+        a: Value::known(a),
+        b: Value::known(b),
+    };
+    let prover = MockProver::run(K, &circuit, vec![vec![out]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "a = {a:?}, b = {b:?}, c = {c:?}");
+
+    let prover = MockProver::run(K, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err(), "a = {a:?}, b = {b:?}, c = {c:?}");
+});
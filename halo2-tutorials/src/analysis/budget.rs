@@ -0,0 +1,58 @@
+/// Guard a circuit against growing past a row budget.
+///
+/// A change to a chip can quietly double the rows every circuit built on it
+/// fills, and nothing fails until `k` runs out. A test pinning the budget
+/// with `assert_within_rows` fails at the change instead, with the count.
+use halo2_proofs::{pasta::Fp, plonk::Circuit};
+
+use super::rows::advice_rows;
+
+/// Panic if `circuit`, laid out over `2^k` rows, assigns advice past row
+/// `max_used`.
+pub fn assert_within_rows<C: Circuit<Fp>>(circuit: &C, k: u32, max_used: usize) {
+    let used = advice_rows(k, circuit).expect("circuit synthesizes");
+    assert!(
+        used <= max_used,
+        "circuit uses {} advice rows, over the budget of {}",
+        used,
+        max_used
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::MyCircuit;
+    use halo2_proofs::circuit::Value;
+
+    fn simple_chip() -> MyCircuit<Fp> {
+        MyCircuit {
+            c: Fp::from(2),
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+        }
+    }
+
+    #[test]
+    fn test_within_rows() {
+        assert_within_rows(&simple_chip(), 5, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "over the budget of 7")]
+    fn test_over_budget() {
+        assert_within_rows(&simple_chip(), 5, 7);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_budget() {
+        // The inputs on one row and out on the next, in a single region.
+        let circuit = crate::chap_2::exercise_5::MyCircuit {
+            c: Fp::from(2),
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+        };
+        assert_within_rows(&circuit, 5, 2);
+    }
+}
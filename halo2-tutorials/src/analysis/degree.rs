@@ -0,0 +1,75 @@
+/// The degree of a circuit's custom gates.
+///
+/// Every gate polynomial must vanish on the evaluation domain, and the prover
+/// divides the combined constraint polynomial by the vanishing polynomial. A
+/// gate of degree `d` over `2^k` rows makes that quotient about `(d - 1) * 2^k`
+/// coefficients long, so the prover works on an extended domain sized for the
+/// *largest* gate: one high-degree gate makes every column more expensive.
+///
+/// A selector counts as degree 1, so `s * (a * b - c)` is degree 3. Lookups
+/// and the permutation argument add their own degree on top of this; the
+/// overall figure the prover uses is `ConstraintSystem::degree()`.
+/// `max_constraint_degree` leaves the selectors out, for the degree of the
+/// constraints themselves.
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem},
+};
+
+/// Configure `C` and return the highest degree among its gate polynomials.
+pub fn max_gate_degree<C: Circuit<Fp>>(_circuit: &C) -> usize {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    cs.gates()
+        .iter()
+        .flat_map(|gate| gate.polynomials())
+        .map(|poly| poly.degree())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Like `max_gate_degree`, but with selectors counted as degree 0, so
+/// `s * (a * b - c)` is degree 2.
+pub fn max_constraint_degree<C: Circuit<Fp>>(_circuit: &C) -> usize {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    cs.gates()
+        .iter()
+        .flat_map(|gate| gate.polynomials())
+        .map(|poly| {
+            poly.evaluate(
+                &|_| 0,
+                &|_| 0,
+                &|_| 1,
+                &|_| 1,
+                &|_| 1,
+                &|d| d,
+                &|a, b| a.max(b),
+                &|a, b| a + b,
+                &|d, _| d,
+            )
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_chip_degree() {
+        // s_mul * (lhs * rhs - out) is 3, s_cub * (lhs^3 - out) is 4.
+        let circuit = crate::chap_2::simple_chip::MyCircuit::<Fp>::default();
+        assert_eq!(max_gate_degree(&circuit), 4);
+        assert_eq!(max_constraint_degree(&circuit), 3);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_degree() {
+        // e = (a * b)^2 * c + c is degree 5, e^3 is 15, and s_cpx adds one.
+        let circuit = crate::chap_2::exercise_5::MyCircuit::<Fp>::default();
+        assert_eq!(max_gate_degree(&circuit), 16);
+    }
+}
@@ -0,0 +1,405 @@
+/// How often each row of a lookup table is queried.
+///
+/// A table row that no input ever looks up is not wrong in itself, but it is
+/// worth a second look: a table padded with an unintended entry (say a `0`
+/// left over from `fill_from_row`) widens what the lookup accepts, and only
+/// shows up as a row that honest witnesses never use.
+///
+/// `MockProver` keeps its cell values to itself, so this replays synthesis the
+/// same way it does: the circuit's floor planner drives a recording
+/// `Assignment`, and the lookup expressions are then evaluated row by row. An
+/// input row counts as a query when one of the selectors in its expressions is
+/// enabled there; rows where the lookup is switched off are not queries, even
+/// though they look up the table's default entry.
+///
+/// The crate has no circuit printer to hang the report on; `format_histogram`
+/// renders it as text, for a test or a binary to print.
+use std::collections::{HashMap, HashSet};
+
+use halo2_proofs::{
+    circuit::Value,
+    pasta::group::ff::PrimeField,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Expression,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+    poly::Rotation,
+};
+
+/// The cells written during synthesis. Instance values are not known here,
+/// and unassigned cells read as zero.
+pub(crate) struct Recorder<F> {
+    n: usize,
+    /// One past the last advice row assigned, known value or not.
+    pub(super) advice_rows: usize,
+    advice: HashMap<(usize, usize), F>,
+    pub(crate) fixed: HashMap<(usize, usize), F>,
+    pub(crate) selectors: HashSet<(Selector, usize)>,
+}
+
+impl<F: PrimeField> Recorder<F> {
+    /// Configure `C` and replay the synthesis of `circuit` over `2^k` rows.
+    pub(crate) fn synthesize<C: Circuit<F>>(
+        k: u32,
+        circuit: &C,
+    ) -> Result<(Self, ConstraintSystem<F>), Error> {
+        let mut cs = ConstraintSystem::default();
+        let config = C::configure(&mut cs);
+        let mut recorder = Recorder {
+            n: 1 << k,
+            advice_rows: 0,
+            advice: HashMap::new(),
+            fixed: HashMap::new(),
+            selectors: HashSet::new(),
+        };
+        C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())?;
+        Ok((recorder, cs))
+    }
+
+    fn record<VR: Into<Assigned<F>>>(
+        cells: &mut HashMap<(usize, usize), F>,
+        column: usize,
+        row: usize,
+        value: Value<VR>,
+    ) {
+        value.map(|v| {
+            cells.insert((column, row), v.into().evaluate());
+        });
+    }
+
+    fn rotate(&self, row: usize, rotation: Rotation) -> usize {
+        (row as i64 + rotation.0 as i64).rem_euclid(self.n as i64) as usize
+    }
+
+    pub(crate) fn evaluate(&self, expr: &Expression<F>, row: usize) -> F {
+        let cell = |cells: &HashMap<(usize, usize), F>, column, rotation| {
+            let row = self.rotate(row, rotation);
+            cells.get(&(column, row)).copied().unwrap_or(F::ZERO)
+        };
+        expr.evaluate(
+            &|c| c,
+            &|s| F::from(self.selectors.contains(&(s, row)) as u64),
+            &|q| cell(&self.fixed, q.column_index(), q.rotation()),
+            &|q| cell(&self.advice, q.column_index(), q.rotation()),
+            &|_| F::ZERO,
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, s| a * s,
+        )
+    }
+}
+
+impl<F: PrimeField> Assignment<F> for Recorder<F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.selectors.insert((*selector, row));
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.advice_rows = self.advice_rows.max(row + 1);
+        Self::record(&mut self.advice, column.index(), row, to());
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Self::record(&mut self.fixed, column.index(), row, to());
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        from_row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        for row in from_row..self.n {
+            Self::record(&mut self.fixed, column.index(), row, to);
+        }
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// The selectors an expression queries.
+fn selectors<F: PrimeField>(expr: &Expression<F>) -> Vec<Selector> {
+    expr.evaluate(
+        &|_| vec![],
+        &|s| vec![s],
+        &|_| vec![],
+        &|_| vec![],
+        &|_| vec![],
+        &|a| a,
+        &|mut a, b| {
+            a.extend(b);
+            a
+        },
+        &|mut a, b| {
+            a.extend(b);
+            a
+        },
+        &|a, _| a,
+    )
+}
+
+/// Synthesize `circuit` over `2^k` rows and count, for every distinct table
+/// row, how many enabled lookup inputs hit it. Rows never hit are present with
+/// a count of 0; rows repeating an earlier row's entry are left out. The map
+/// is keyed by `(lookup, row)`, `lookup` being the index of the lookup in the
+/// constraint system, so that two lookups into different tables, or into the
+/// same one, are counted apart.
+pub fn lookup_usage_histogram<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+) -> Result<HashMap<(usize, usize), usize>, Error> {
+    let n = 1 << k;
+    let (recorder, cs) = Recorder::synthesize(k, circuit)?;
+
+    let key = |values: Vec<F>| -> Vec<Vec<u8>> {
+        values
+            .iter()
+            .map(|v| v.to_repr().as_ref().to_vec())
+            .collect()
+    };
+
+    let mut histogram = HashMap::new();
+    for (index, lookup) in cs.lookups().iter().enumerate() {
+        let table_columns: Vec<usize> = lookup
+            .table_expressions()
+            .iter()
+            .filter_map(|expr| match expr {
+                Expression::Fixed(q) => Some(q.column_index()),
+                _ => None,
+            })
+            .collect();
+
+        // The first row holding each entry of the table.
+        let mut entries = HashMap::new();
+        for row in 0..n {
+            let assigned = table_columns
+                .iter()
+                .all(|col| recorder.fixed.contains_key(&(*col, row)));
+            if !assigned {
+                continue;
+            }
+            let entry = lookup
+                .table_expressions()
+                .iter()
+                .map(|expr| recorder.evaluate(expr, row))
+                .collect();
+            entries.entry(key(entry)).or_insert(row);
+        }
+        for row in entries.values() {
+            histogram.entry((index, *row)).or_insert(0);
+        }
+
+        let switches: Vec<Selector> = lookup
+            .input_expressions()
+            .iter()
+            .flat_map(selectors)
+            .collect();
+        for row in 0..n {
+            let enabled = switches.is_empty()
+                || switches
+                    .iter()
+                    .any(|s| recorder.selectors.contains(&(*s, row)));
+            if !enabled {
+                continue;
+            }
+            let input = lookup
+                .input_expressions()
+                .iter()
+                .map(|expr| recorder.evaluate(expr, row))
+                .collect();
+            if let Some(table_row) = entries.get(&key(input)) {
+                *histogram.entry((index, *table_row)).or_insert(0) += 1;
+            }
+        }
+    }
+    Ok(histogram)
+}
+
+/// One line per table row, `lookup row: count`, with unused rows flagged.
+pub fn format_histogram(histogram: &HashMap<(usize, usize), usize>) -> String {
+    let mut rows: Vec<_> = histogram.iter().collect();
+    rows.sort();
+    rows.iter()
+        .map(|((lookup, row), count)| match **count {
+            0 => format!("{:>3} {:>5}: 0 (unused)\n", lookup, row),
+            count => format!("{:>3} {:>5}: {}\n", lookup, row, count),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::byte::{ByteChip, ByteConfig};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        pasta::Fp,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit {
+        values: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = ByteConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            ByteChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = ByteChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            for v in &self.values {
+                chip.assign_byte(layouter.namespace(|| "byte"), Value::known(Fp::from(*v)))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_byte_lookup_histogram() {
+        let k = 9;
+        let circuit = MyCircuit {
+            values: vec![3, 7, 3, 255, 0],
+        };
+        let histogram = lookup_usage_histogram(k, &circuit).unwrap();
+
+        // The byte table holds value `i` in row `i`.
+        let mut expected: HashMap<(usize, usize), usize> =
+            (0..256).map(|row| ((0, row), 0)).collect();
+        expected.insert((0, 0), 1);
+        expected.insert((0, 3), 2);
+        expected.insert((0, 7), 1);
+        expected.insert((0, 255), 1);
+        assert_eq!(histogram, expected);
+
+        let report = format_histogram(&histogram);
+        assert!(report.contains("  0     3: 2\n"));
+        assert!(report.contains("  0     4: 0 (unused)\n"));
+    }
+
+    /// Two byte chips, each with its own table, over the same values.
+    #[derive(Default)]
+    struct TwoTables {
+        first: Vec<u64>,
+        second: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for TwoTables {
+        type Config = [ByteConfig; 2];
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            [(); 2].map(|_| {
+                let advice = meta.advice_column();
+                ByteChip::configure(meta, advice)
+            })
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            for (config, values) in config.into_iter().zip([&self.first, &self.second]) {
+                let chip = ByteChip::construct(config);
+                chip.load_table(layouter.namespace(|| "table"))?;
+                for v in values {
+                    chip.assign_byte(layouter.namespace(|| "byte"), Value::known(Fp::from(*v)))?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lookup_histogram_per_lookup() {
+        let k = 9;
+        let circuit = TwoTables {
+            first: vec![3, 3],
+            second: vec![3, 9],
+        };
+        let histogram = lookup_usage_histogram(k, &circuit).unwrap();
+
+        // Row 3 is hit twice in the first table and once in the second, not
+        // three times in a shared count.
+        assert_eq!(histogram[&(0, 3)], 2);
+        assert_eq!(histogram[&(1, 3)], 1);
+        assert_eq!(histogram[&(0, 9)], 0);
+        assert_eq!(histogram[&(1, 9)], 1);
+        assert_eq!(histogram.len(), 2 * 256);
+    }
+}
@@ -0,0 +1,6 @@
+/// Tools for inspecting a circuit's shape and what it costs to prove.
+pub mod budget;
+pub mod degree;
+pub mod lookup_analysis;
+pub mod rows;
+pub mod sizes;
@@ -0,0 +1,39 @@
+/// How many rows a circuit's advice actually uses.
+///
+/// `k` only bounds a circuit from above, and lookup tables alone often force
+/// it up to the table size. Comparing two constructions needs the number of
+/// advice rows they fill, which is what this counts: synthesis is replayed
+/// the same way `lookup_usage_histogram` does, and the highest advice row
+/// assigned is recorded, whether its value is known or not.
+use halo2_proofs::{
+    pasta::group::ff::PrimeField,
+    plonk::{Circuit, Error},
+};
+
+use super::lookup_analysis::Recorder;
+
+/// One past the last advice row `circuit` assigns when laid out over `2^k`
+/// rows.
+pub fn advice_rows<F: PrimeField, C: Circuit<F>>(k: u32, circuit: &C) -> Result<usize, Error> {
+    Recorder::synthesize(k, circuit).map(|(recorder, _)| recorder.advice_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::MyCircuit;
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    #[test]
+    fn test_simple_chip_rows() {
+        // 3 rows loading a, b and c, then 5 for ab, (ab)^2, d, e and out.
+        let circuit = MyCircuit {
+            c: Fp::from(2),
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+        };
+        assert_eq!(advice_rows(5, &circuit).unwrap(), 8);
+        // Unknown witnesses take the same rows.
+        assert_eq!(advice_rows(5, &MyCircuit::<Fp>::default()).unwrap(), 8);
+    }
+}
@@ -0,0 +1,72 @@
+/// The size of what a verifier receives: the verifying key and the proof.
+///
+/// On chain, both are paid for by the byte. The verifying key is mostly one
+/// commitment per fixed column (selectors included, once `keygen_vk` has
+/// compressed them) and one per column in the permutation argument; the rest
+/// of it, the domain and the gates, is rebuilt from the circuit's code. The
+/// proof grows with the number of advice columns, lookups and the gate degree,
+/// and, for the inner product argument used here, with `k`.
+use halo2_proofs::{
+    pasta::{group::GroupEncoding, EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, Error},
+    poly::commitment::Params,
+};
+
+use crate::utils::prover::{Prover, RealProverBackend};
+
+/// The serialized length, in bytes, of the commitments in `circuit`'s
+/// verifying key.
+pub fn vk_size<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<usize, Error> {
+    let params: Params<EqAffine> = Params::new(k);
+    let vk = keygen_vk(&params, circuit)?;
+    let size = vk
+        .fixed_commitments()
+        .iter()
+        .chain(vk.permutation().commitments())
+        .map(|c| c.to_bytes().as_ref().len())
+        .sum();
+    Ok(size)
+}
+
+/// The length, in bytes, of a proof of `circuit`, with `instances` holding
+/// the values of each instance column.
+pub fn proof_size<C: Circuit<Fp>>(k: u32, circuit: C, instances: &[&[Fp]]) -> Result<usize, Error> {
+    let backend = RealProverBackend::<EqAffine, C>::new(k, &circuit)?;
+    Ok(backend.prove(circuit, instances)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_sizes() {
+        use halo2_proofs::circuit::Value;
+
+        let k = 5;
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let circuit = crate::chap_2::exercise_5::MyCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+
+        let vk = vk_size(k, &circuit).unwrap();
+        let proof = proof_size(k, circuit, &[&[out]]).unwrap();
+        println!(
+            "exercise_5 at k = {}: vk {} bytes, proof {} bytes",
+            k, vk, proof
+        );
+
+        // About seven commitments: two fixed columns (the constant and s_cpx)
+        // and five permuted ones (three advice, the instance and the constant).
+        assert!(vk > 0);
+        assert!(vk <= 1024);
+        // The degree 16 gate splits the quotient into 15 commitments, which
+        // dominate the proof.
+        assert!(proof > 15 * 32);
+        assert!(proof < 8 * 1024);
+    }
+}
@@ -0,0 +1,25 @@
+/// Draw the layout of every registered circuit to `circuit_layouter_plots/`,
+/// or to the directory given as the only argument.
+///
+/// $ cargo run --bin render_all --features dev-graph,chap_2_exercise_4,chap_2_exercise_5,chap_3_exercise_6
+use std::path::PathBuf;
+
+use halo2_tutorials::utils::plot::render_all;
+
+fn main() {
+    let dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("circuit_layouter_plots"));
+    match render_all(&dir) {
+        Ok(paths) => {
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("render_all: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
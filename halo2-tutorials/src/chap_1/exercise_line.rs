@@ -0,0 +1,172 @@
+/// Prove knowing a private point (x, y) on a public line
+/// s.t: y = m * x + c
+///
+/// The slope m and the intercept c are public: they sit on the instance
+/// column. A gate cannot query the instance column like an advice column
+/// here, so `assign_advice_from_instance` copies them into advice cells,
+/// next to the point, and adds a copy constraint from each instance cell to
+/// its copy. The prover cannot put another m or c into the gate's row
+/// without breaking that constraint.
+///
+/// A vertical line x = k has no slope, so it has no `y = m * x + c` form:
+/// for a given x this circuit accepts exactly one y.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+/// Circuit design:
+/// | ins   | a0    | a1    | a2    | a3    | s_line |
+/// |-------|-------|-------|-------|-------|--------|
+/// |   m   |   x   |   y   |   m   |   c   |   1    |
+/// |   c   |       |       |       |       |        |
+
+#[derive(Debug, Clone)]
+pub(crate) struct LineConfig {
+    advice: [Column<Advice>; 4],
+    instance: Column<Instance>,
+    s_line: Selector,
+}
+
+#[derive(Default)]
+pub(crate) struct LineCircuit<F: Field> {
+    x: Value<F>,
+    y: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for LineCircuit<F> {
+    type Config = LineConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+
+        // Copying from the instance column needs equality on both ends.
+        meta.enable_equality(instance);
+        meta.enable_equality(advice[2]);
+        meta.enable_equality(advice[3]);
+        let s_line = meta.selector();
+
+        meta.create_gate("on line", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            let m = meta.query_advice(advice[2], Rotation::cur());
+            let c = meta.query_advice(advice[3], Rotation::cur());
+            let s_line = meta.query_selector(s_line);
+            vec![s_line * (y - m * x - c)]
+        });
+
+        LineConfig {
+            advice,
+            instance,
+            s_line,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "point on line",
+            |mut region| {
+                config.s_line.enable(&mut region, 0)?;
+                region.assign_advice(|| "x", config.advice[0], 0, || self.x)?;
+                region.assign_advice(|| "y", config.advice[1], 0, || self.y)?;
+                // m and c take their values from the instance column, and are
+                // bound to it by a copy constraint.
+                region.assign_advice_from_instance(
+                    || "m",
+                    config.instance,
+                    0,
+                    config.advice[2],
+                    0,
+                )?;
+                region.assign_advice_from_instance(
+                    || "c",
+                    config.instance,
+                    1,
+                    config.advice[3],
+                    0,
+                )?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    fn verify(x: u64, y: u64, m: Fp, c: Fp) -> bool {
+        let circuit = LineCircuit {
+            x: Value::known(Fp::from(x)),
+            y: Value::known(Fp::from(y)),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![m, c]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_point_on_line() {
+        // y = 3x + 2
+        let (m, c) = (Fp::from(3), Fp::from(2));
+        assert!(verify(0, 2, m, c));
+        assert!(verify(5, 17, m, c));
+        // y = -x + 10
+        assert!(verify(4, 6, -Fp::one(), Fp::from(10)));
+    }
+
+    #[test]
+    fn test_point_off_line() {
+        let (m, c) = (Fp::from(3), Fp::from(2));
+        assert!(!verify(5, 18, m, c));
+        assert!(!verify(1, 2, m, c));
+        // Swapping the public inputs is another line.
+        assert!(!verify(5, 17, c, m));
+    }
+
+    #[test]
+    fn test_vertical_line() {
+        // (2, 0) and (2, 1) are on the vertical line x = 2. Whatever m and c
+        // the verifier publishes, at most one of them is accepted: the line
+        // through (2, 0) with slope m misses (2, 1).
+        for m in [0, 1, 7, 1 << 20].map(Fp::from) {
+            let c = -m * Fp::from(2);
+            assert!(verify(2, 0, m, c));
+            assert!(!verify(2, 1, m, c));
+        }
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_chap_1_exercise_line() {
+        let circuit = LineCircuit::<Fp>::default();
+        // Create the area you want to draw on.
+        // Use SVGBackend if you want to render to .svg instead.
+        use plotters::prelude::*;
+        let root = BitMapBackend::new(
+            "./circuit_layouter_plots/chap_1_exercise_line.png",
+            (1024, 768),
+        )
+        .into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root.titled("Point on Line", ("sans-serif", 60)).unwrap();
+        halo2_proofs::dev::CircuitLayout::default()
+            .show_labels(true)
+            .render(K, &circuit, &root)
+            .unwrap();
+    }
+}
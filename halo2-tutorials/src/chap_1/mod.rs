@@ -1,4 +1,5 @@
-mod simple;
+pub(crate) mod exercise_line;
+pub(crate) mod simple;
 
 #[cfg(feature = "chap_1_exercise_1")]
 pub mod exercise_1;
@@ -21,7 +21,7 @@ use halo2_proofs::{
 /// |       |  out  |       |   0   |
 
 #[derive(Debug, Clone)]
-struct CircuitConfig {
+pub(crate) struct CircuitConfig {
     advice: [Column<Advice>; 2],
     instance: Column<Instance>,
     s_mul: Selector,
@@ -31,7 +31,7 @@ struct CircuitConfig {
 struct Number<F: Field>(AssignedCell<F, F>);
 
 #[derive(Default)]
-struct MyCircuit<F: Field> {
+pub(crate) struct MyCircuit<F: Field> {
     c: F,
     a: Value<F>,
     b: Value<F>,
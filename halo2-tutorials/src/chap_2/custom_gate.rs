@@ -28,7 +28,7 @@ use halo2_proofs::{
 // / |       |   e   |  out  |   0   |   0   |   1   |
 
 #[derive(Debug, Clone)]
-struct CircuitConfig {
+pub(crate) struct CircuitConfig {
     advice: [Column<Advice>; 2],
     instance: Column<Instance>,
     s_mul: Selector,
@@ -40,7 +40,7 @@ struct CircuitConfig {
 struct Number<F: Field>(AssignedCell<F, F>);
 
 #[derive(Default)]
-struct MyCircuit<F: Field> {
+pub(crate) struct MyCircuit<F: Field> {
     c: F,
     a: Value<F>,
     b: Value<F>,
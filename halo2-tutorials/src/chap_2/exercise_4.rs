@@ -28,7 +28,7 @@ use halo2_proofs::{
 // / |       |   e   |  out  |   0   |   0   |   1   |
 
 #[derive(Debug, Clone)]
-struct SimpleConfig {
+pub(crate) struct SimpleConfig {
     advice: [Column<Advice>; 2],
     instance: Column<Instance>,
     s_mul: Selector,
@@ -207,7 +207,7 @@ impl<F: Field> SimpleChip<F> {
 }
 
 #[derive(Default)]
-struct MyCircuit<F: Field> {
+pub(crate) struct MyCircuit<F: Field> {
     c: F,
     a: Value<F>,
     b: Value<F>,
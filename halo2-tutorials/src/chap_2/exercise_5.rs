@@ -10,7 +10,7 @@ use std::marker::PhantomData;
 ///     out = e^3
 use halo2_proofs::{
     arithmetic::Field,
-    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Chip, Layouter, SimpleFloorPlanner, Value},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Instance, Selector},
     poly::Rotation,
 };
@@ -22,17 +22,17 @@ use halo2_proofs::{
 // / |       |  out  |      |      |       |
 
 #[derive(Debug, Clone)]
-struct SimpleConfig {
-    advice: [Column<Advice>; 3],
-    instance: Column<Instance>,
-    s_cpx: Selector,
+pub struct SimpleConfig {
+    pub(crate) advice: [Column<Advice>; 3],
+    pub(crate) instance: Column<Instance>,
+    pub(crate) s_cpx: Selector,
 }
 
 #[derive(Clone)]
-struct Number<F: Field>(AssignedCell<F, F>);
+pub(crate) struct Number<F: Field>(AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct SimpleChip<F: Field> {
+pub(crate) struct SimpleChip<F: Field> {
     config: SimpleConfig,
     _marker: PhantomData<F>,
 }
@@ -121,7 +121,7 @@ impl<F: Field> SimpleChip<F> {
         )
     }
 
-    fn expose_public(
+    pub(crate) fn expose_public(
         &self,
         mut layouter: impl Layouter<F>,
         out: Number<F>,
@@ -131,11 +131,24 @@ impl<F: Field> SimpleChip<F> {
     }
 }
 
+impl<F: Field> Chip<F> for SimpleChip<F> {
+    type Config = SimpleConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
 #[derive(Default)]
-struct MyCircuit<F: Field> {
-    c: F,
-    a: Value<F>,
-    b: Value<F>,
+pub struct MyCircuit<F: Field> {
+    pub c: F,
+    pub a: Value<F>,
+    pub b: Value<F>,
 }
 
 impl<F: Field> Circuit<F> for MyCircuit<F> {
@@ -171,7 +184,8 @@ impl<F: Field> Circuit<F> for MyCircuit<F> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use crate::utils::prover::{MockProverBackend, Prover};
+    use halo2_proofs::pasta::Fp;
 
     fn circuit() -> (MyCircuit<Fp>, Fp) {
         // Prepare the private and public inputs to the circuit!
@@ -204,14 +218,15 @@ mod tests {
         // of the instance column, so we position it there in our public inputs.
         let mut public_inputs = vec![out];
 
-        // Given the correct public input, our circuit will verify.
-        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-        assert_eq!(prover.verify(), Ok(()));
+        // Given the correct public input, our circuit will verify. Swap in
+        // `RealProverBackend` for a real proof: see tests/prover.rs.
+        let backend = MockProverBackend::new(k);
+        let proof = backend.prove(circuit, &[&public_inputs]).unwrap();
+        assert!(backend.verify(&proof, &[&public_inputs]).is_ok());
 
         // If we try some other public input, the proof will fail!
         public_inputs[0] += Fp::one();
-        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
-        assert!(prover.verify().is_err());
+        assert!(backend.verify(&proof, &[&public_inputs]).is_err());
         println!("simple_ship success!")
         // ANCHOR_END: test-circuit
     }
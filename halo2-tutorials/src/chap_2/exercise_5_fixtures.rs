@@ -0,0 +1,71 @@
+/// A broken exercise 5, for the tests of the debugging helpers in `utils`.
+///
+/// `CorruptedCircuit` lays out exercise 5's region, but witnesses `b + 1`
+/// under the gate while computing `out` from `b`. With the honest `out` as
+/// the public input, the copy to the instance holds and `complex_gate` at
+/// offset 0 is the only thing that fails.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::Fp,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use super::exercise_5::{SimpleChip, SimpleConfig};
+
+#[derive(Default)]
+pub(crate) struct CorruptedCircuit {
+    pub c: Fp,
+    pub a: Value<Fp>,
+    pub b: Value<Fp>,
+}
+
+impl Circuit<Fp> for CorruptedCircuit {
+    type Config = SimpleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        CorruptedCircuit {
+            c: self.c,
+            a: Value::unknown(),
+            b: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        SimpleChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let out = layouter.assign_region(
+            || "load private & witness",
+            |mut region| {
+                config.s_cpx.enable(&mut region, 0)?;
+                let [a_col, b_col, c_col] = config.advice;
+                region.assign_advice(|| "private input a", a_col, 0, || self.a)?;
+                let corrupted = self.b + Value::known(Fp::ONE);
+                region.assign_advice(|| "private input b", b_col, 0, || corrupted)?;
+                region.assign_advice_from_constant(|| "private input c", c_col, 0, self.c)?;
+                let ab = self.a * self.b;
+                let e = ab * ab * Value::known(self.c) + Value::known(self.c);
+                region.assign_advice(|| "out", a_col, 1, || e * e * e)
+            },
+        )?;
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+/// `CorruptedCircuit` for `a = 2, b = 3, c = 2`, and the honest `out`.
+pub(crate) fn corrupted_circuit() -> (CorruptedCircuit, Fp) {
+    let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+    let circuit = CorruptedCircuit {
+        c,
+        a: Value::known(a),
+        b: Value::known(b),
+    };
+    (circuit, (c * a.square() * b.square() + c).cube())
+}
@@ -0,0 +1,370 @@
+/// chap2: instructions
+/// Exercise 5's statement again: for private a, b and a constant c,
+///
+///     d = a^2 * b^2 * c
+///     e = c + d
+///     out = e^3
+///
+/// this time following the simple example of the halo2 book. The chip's
+/// operations are the methods of a trait, `ComplexInstructions`, and
+/// `synthesize` only calls them, one line per step of the statement. Every
+/// instruction assigns a region of its own, copies its inputs in and returns
+/// its output cell for the next one to copy.
+///
+/// | ins   |  a0    |  a1  | s_mul | s_add | s_cub |
+/// |-------|--------|------|-------|-------|-------|
+/// |  out  |   a    |      |       |       |       |  load_private
+/// |       |   b    |      |       |       |       |  load_private
+/// |       |   c    |      |       |       |       |  load_constant
+/// |       |   a    |  a   |   1   |   0   |   0   |  square
+/// |       |  a^2   |      |       |       |       |
+/// |       |   b    |  b   |   1   |   0   |   0   |  square
+/// |       |  b^2   |      |       |       |       |
+/// |       |  a^2   | b^2  |   1   |   0   |   0   |  mul
+/// |       | a^2b^2 |      |       |       |       |
+/// |       | a^2b^2 |  c   |   1   |   0   |   0   |  mul
+/// |       |   d    |      |       |       |       |
+/// |       |   c    |  d   |   0   |   1   |   0   |  add
+/// |       |   e    |      |       |       |       |
+/// |       |   e    |      |   0   |   0   |   1   |  cube
+/// |       |  out   |      |       |       |       |
+///
+/// Exercise 5 fits all of it in one region of two rows, with one gate of
+/// degree 10; this takes fifteen rows, one gate per operation and a copy
+/// constraint per operand. The regions are what make the instructions
+/// reusable in any order, and the rows are what that costs.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Chip, Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+/// The operations `synthesize` is written in.
+trait ComplexInstructions<F: Field>: Chip<F> {
+    type Num;
+
+    fn load_private(&self, layouter: impl Layouter<F>, a: Value<F>) -> Result<Self::Num, Error>;
+
+    fn load_constant(&self, layouter: impl Layouter<F>, c: F) -> Result<Self::Num, Error>;
+
+    /// `a^2`
+    fn square(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    /// `a * b`
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// `a + b`
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// `a^3`
+    fn cube(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error>;
+
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        num: Self::Num,
+        row: usize,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone)]
+struct FieldConfig {
+    advice: [Column<Advice>; 2],
+    instance: Column<Instance>,
+    s_mul: Selector,
+    s_add: Selector,
+    s_cub: Selector,
+}
+
+struct FieldChip<F: Field> {
+    config: FieldConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Chip<F> for FieldChip<F> {
+    type Config = FieldConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: Field> FieldChip<F> {
+    fn construct(config: FieldConfig) -> Self {
+        FieldChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> FieldConfig {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_mul = meta.selector();
+        let s_add = meta.selector();
+        let s_cub = meta.selector();
+
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(s_mul);
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            Constraints::with_selector(s, vec![lhs * rhs - out])
+        });
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            Constraints::with_selector(s, vec![lhs + rhs - out])
+        });
+
+        meta.create_gate("cube", |meta| {
+            let s = meta.query_selector(s_cub);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            Constraints::with_selector(s, vec![a.clone() * a.clone() * a - out])
+        });
+
+        FieldConfig {
+            advice,
+            instance,
+            s_mul,
+            s_add,
+            s_cub,
+        }
+    }
+
+    /// One binary operation: `lhs` and `rhs` copied into the first row,
+    /// `out` on the second, under `selector`.
+    fn binary(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &'static str,
+        selector: Selector,
+        lhs: Number<F>,
+        rhs: Number<F>,
+        out: impl Fn(Value<F>, Value<F>) -> Value<F>,
+    ) -> Result<Number<F>, Error> {
+        let [a0, a1] = self.config.advice;
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                let lhs = lhs.0.copy_advice(|| "lhs", &mut region, a0, 0)?;
+                let rhs = rhs.0.copy_advice(|| "rhs", &mut region, a1, 0)?;
+                let value = out(lhs.value().copied(), rhs.value().copied());
+                region.assign_advice(|| "out", a0, 1, || value).map(Number)
+            },
+        )
+    }
+}
+
+impl<F: Field> ComplexInstructions<F> for FieldChip<F> {
+    type Num = Number<F>;
+
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+    ) -> Result<Self::Num, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "private input", self.config.advice[0], 0, || a)
+                    .map(Number)
+            },
+        )
+    }
+
+    fn load_constant(&self, mut layouter: impl Layouter<F>, c: F) -> Result<Self::Num, Error> {
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", self.config.advice[0], 0, c)
+                    .map(Number)
+            },
+        )
+    }
+
+    fn square(&self, layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error> {
+        let s_mul = self.config.s_mul;
+        self.binary(layouter, "square", s_mul, a.clone(), a, |a, b| a * b)
+    }
+
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let s_mul = self.config.s_mul;
+        self.binary(layouter, "mul", s_mul, a, b, |a, b| a * b)
+    }
+
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Num,
+        b: Self::Num,
+    ) -> Result<Self::Num, Error> {
+        let s_add = self.config.s_add;
+        self.binary(layouter, "add", s_add, a, b, |a, b| a + b)
+    }
+
+    fn cube(&self, mut layouter: impl Layouter<F>, a: Self::Num) -> Result<Self::Num, Error> {
+        let a0 = self.config.advice[0];
+        layouter.assign_region(
+            || "cube",
+            |mut region| {
+                self.config.s_cub.enable(&mut region, 0)?;
+                let a = a.0.copy_advice(|| "a", &mut region, a0, 0)?;
+                let value = a.value().map(|a| a.cube());
+                region.assign_advice(|| "a^3", a0, 1, || value).map(Number)
+            },
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: Self::Num,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MyCircuit<F: Field> {
+    c: F,
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for MyCircuit<F> {
+    type Config = FieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MyCircuit {
+            c: self.c,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FieldChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FieldChip::construct(config);
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let c = chip.load_constant(layouter.namespace(|| "c"), self.c)?;
+
+        let a2 = chip.square(layouter.namespace(|| "a^2"), a)?;
+        let b2 = chip.square(layouter.namespace(|| "b^2"), b)?;
+        let a2b2 = chip.mul(layouter.namespace(|| "a^2 * b^2"), a2, b2)?;
+        let d = chip.mul(layouter.namespace(|| "d = a^2 * b^2 * c"), a2b2, c.clone())?;
+        let e = chip.add(layouter.namespace(|| "e = c + d"), c, d)?;
+        let out = chip.cube(layouter.namespace(|| "out = e^3"), e)?;
+
+        chip.expose_public(layouter.namespace(|| "out"), out, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    /// exercise 5's inputs, and its output.
+    fn circuit() -> (MyCircuit<Fp>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let circuit = MyCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, (a.square() * b.square() * c + c).cube())
+    }
+
+    #[test]
+    fn test_chap_2_exercise_5_instructions() {
+        let (circuit, out) = circuit();
+        assert_eq!(out, Fp::from(74u64.pow(3)));
+        let prover = MockProver::run(K, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_chap_2_exercise_5_instructions_matches_exercise_5() {
+        use crate::chap_2::exercise_5;
+
+        let (circuit, out) = circuit();
+        let monolithic = exercise_5::MyCircuit {
+            c: circuit.c,
+            a: circuit.a,
+            b: circuit.b,
+        };
+        for public in [out, out + Fp::one()] {
+            let verify = |prover: MockProver<Fp>| prover.verify().is_ok();
+            assert_eq!(
+                verify(MockProver::run(K, &circuit, vec![vec![public]]).unwrap()),
+                verify(MockProver::run(K, &monolithic, vec![vec![public]]).unwrap()),
+            );
+        }
+
+        // One region against nine: two rows against fifteen.
+        assert_eq!(advice_rows(K, &monolithic).unwrap(), 2);
+        assert_eq!(advice_rows(K, &circuit).unwrap(), 15);
+    }
+
+    #[test]
+    fn test_chap_2_exercise_5_instructions_rows() {
+        // Three loads of one row, six operations of two.
+        assert_eq!(advice_rows(K, &circuit().0).unwrap(), 3 + 6 * 2);
+        assert_eq!(advice_rows(K, &MyCircuit::<Fp>::default()).unwrap(), 15);
+    }
+}
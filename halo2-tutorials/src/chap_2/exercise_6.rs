@@ -0,0 +1,343 @@
+/// chap2: chaining a chip
+/// Exercise 5's relation, iterated `N` times: for private `a`, `b` and a
+/// constant `c`,
+///
+///     a_0 = a,    a_{i+1} = (a_i^2 * b^2 * c + c)^3
+///
+/// and `out = a_N` is public. Each step is one call of the chip, in a region
+/// of its own: the complex gate on the first row, the step's output on the
+/// second. The output is an `AssignedCell`, and the next call takes it as its
+/// `a` and copies it into its first row, which adds a copy constraint
+/// between the two cells.
+///
+/// | ins   |  a0   |  a1  |  a2  | s_cpx |
+/// |-------|-------|------|------|-------|
+/// |  out  |   a   |  b   |  c   |   1   |  step 0
+/// |       |  a_1  |      |      |       |
+/// |       |  a_1  |  b   |  c   |   1   |  step 1
+/// |       |  a_2  |      |      |       |
+/// |       |  ...  |      |      |       |
+/// |       |  a_N  |      |      |       |
+///
+/// The two `a_1` cells are in different regions, and no gate relates them.
+/// Without the copy constraint, step 1 would start from whatever its prover
+/// writes there: see `UnchainedCircuit`.
+///
+/// Every step takes two rows, so the circuit needs `2N` rows plus the ones
+/// halo2 reserves for blinding, and `k` grows with `log2(N)`: see `min_k`.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+#[derive(Debug, Clone)]
+pub struct ChainConfig {
+    advice: [Column<Advice>; 3],
+    instance: Column<Instance>,
+    s_cpx: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct ChainChip<F: Field> {
+    config: ChainConfig,
+    _marker: PhantomData<F>,
+}
+
+/// One step natively.
+pub fn step<F: Field>(a: F, b: F, c: F) -> F {
+    let ab = a * b;
+    (ab * ab * c + c).cube()
+}
+
+impl<F: Field> ChainChip<F> {
+    fn construct(config: ChainConfig) -> Self {
+        ChainChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ChainConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_cpx = meta.selector();
+
+        meta.create_gate("complex_gate", |meta| {
+            let s = meta.query_selector(s_cpx);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let e = (a.clone() * b.clone()) * (a * b) * c.clone() + c;
+            Constraints::with_selector(s, vec![e.clone() * e.clone() * e - out])
+        });
+
+        ChainConfig {
+            advice,
+            instance,
+            s_cpx,
+        }
+    }
+
+    /// One step from the `a` already in `a0` of the region's first row.
+    fn step_from(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: impl Fn(&mut Region<'_, F>) -> Result<AssignedCell<F, F>, Error>,
+        b: Value<F>,
+        c: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "step",
+            |mut region| {
+                config.s_cpx.enable(&mut region, 0)?;
+                let a = a(&mut region)?;
+                region.assign_advice(|| "b", config.advice[1], 0, || b)?;
+                region.assign_advice_from_constant(|| "c", config.advice[2], 0, c)?;
+                let out = a.value().copied().zip(b).map(|(a, b)| step(a, b, c));
+                region.assign_advice(|| "out", config.advice[0], 1, || out)
+            },
+        )
+    }
+
+    /// A step from a fresh witness `a`.
+    fn step_private(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+        c: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let col = self.config.advice[0];
+        self.step_from(
+            layouter,
+            |region| region.assign_advice(|| "a", col, 0, || a),
+            b,
+            c,
+        )
+    }
+
+    /// A step from the output of the previous one, copied in.
+    fn step_chained(
+        &self,
+        layouter: impl Layouter<F>,
+        prev: &AssignedCell<F, F>,
+        b: Value<F>,
+        c: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let col = self.config.advice[0];
+        self.step_from(
+            layouter,
+            |region| prev.copy_advice(|| "a", region, col, 0),
+            b,
+            c,
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        out: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(out.cell(), self.config.instance, 0)
+    }
+}
+
+/// `out = a_N`. `N` must be at least 1.
+#[derive(Default)]
+pub struct MyCircuit<F: Field, const N: usize> {
+    pub c: F,
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: Field, const N: usize> Circuit<F> for MyCircuit<F, N> {
+    type Config = ChainConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MyCircuit {
+            c: self.c,
+            a: Value::unknown(),
+            b: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ChainChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ChainChip::construct(config);
+        let mut out = chip.step_private(layouter.namespace(|| "step 0"), self.a, self.b, self.c)?;
+        for i in 1..N {
+            let name = format!("step {}", i);
+            out = chip.step_chained(layouter.namespace(|| name), &out, self.b, self.c)?;
+        }
+        chip.expose_public(layouter.namespace(|| "out"), &out)
+    }
+}
+
+/// The wrong way: every step witnesses its own `a`, with nothing tying it to
+/// the previous output. The circuit then only proves that each step is
+/// consistent on its own, and `out` can be the result of any last step.
+pub struct UnchainedCircuit<F: Field, const N: usize> {
+    pub c: F,
+    pub a: [Value<F>; N],
+    pub b: Value<F>,
+}
+
+impl<F: Field, const N: usize> Circuit<F> for UnchainedCircuit<F, N> {
+    type Config = ChainConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        UnchainedCircuit {
+            c: self.c,
+            a: [Value::unknown(); N],
+            b: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ChainChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ChainChip::construct(config);
+        let mut out = None;
+        for (i, a) in self.a.iter().enumerate() {
+            let name = format!("step {}", i);
+            out = Some(chip.step_private(layouter.namespace(|| name), *a, self.b, self.c)?);
+        }
+        chip.expose_public(layouter.namespace(|| "out"), &out.expect("N > 0"))
+    }
+}
+
+/// The smallest `k` whose usable rows fit the `2N` rows of `MyCircuit<F, N>`.
+pub fn min_k<F: Field, const N: usize>() -> u32 {
+    let mut meta = ConstraintSystem::<F>::default();
+    MyCircuit::<F, N>::configure(&mut meta);
+    // The last `blinding_factors() + 1` rows are not for the circuit.
+    let reserved = meta.blinding_factors() + 1;
+    (1..).find(|k| 1 << k >= 2 * N + reserved).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    /// exercise 5's inputs.
+    fn circuit<const N: usize>() -> (MyCircuit<Fp, N>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (0..N).fold(a, |a, _| step(a, b, c));
+        let circuit = MyCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, out)
+    }
+
+    fn verify<C: Circuit<Fp>>(k: u32, circuit: &C, out: Fp) -> bool {
+        let prover = MockProver::run(k, circuit, vec![vec![out]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_chap_2_exercise_6() {
+        // One step is exercise 5.
+        let (circuit, out) = circuit::<1>();
+        assert_eq!(out, Fp::from((36 * 2 + 2u64).pow(3)));
+        assert!(verify(min_k::<Fp, 1>(), &circuit, out));
+        assert!(!verify(min_k::<Fp, 1>(), &circuit, out + Fp::one()));
+
+        let (circuit, out) = circuit::<16>();
+        assert!(verify(min_k::<Fp, 16>(), &circuit, out));
+        assert!(!verify(min_k::<Fp, 16>(), &circuit, out + Fp::one()));
+        // Nor does an intermediate value pass as the output.
+        let (_, out_15) = circuit::<15>();
+        assert!(!verify(min_k::<Fp, 16>(), &circuit, out_15));
+    }
+
+    #[test]
+    fn test_chap_2_exercise_6_unchained() {
+        const N: usize = 4;
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let k = min_k::<Fp, N>();
+        // Restart the chain from 5 at step 2: each step is still correct.
+        let mut starts = [a; N];
+        for i in 1..N {
+            starts[i] = if i == 2 {
+                Fp::from(5)
+            } else {
+                step(starts[i - 1], b, c)
+            };
+        }
+        let forged = step(starts[N - 1], b, c);
+        let (chained, out) = circuit::<N>();
+        assert_ne!(forged, out);
+
+        let unchained = UnchainedCircuit {
+            c,
+            a: starts.map(Value::known),
+            b: Value::known(b),
+        };
+        // Without the copy constraints the forged output goes through...
+        assert!(verify(k, &unchained, forged));
+        // ...and with them it does not.
+        assert!(!verify(k, &chained, forged));
+        assert!(verify(k, &chained, out));
+    }
+
+    #[test]
+    fn test_chap_2_exercise_6_rows() {
+        // Two rows a step, and 6 reserved on top: 2 + 6 rows fit in 2^3,
+        // 32 + 6 in 2^6.
+        assert_eq!(advice_rows(3, &circuit::<1>().0).unwrap(), 2);
+        assert_eq!(advice_rows(6, &circuit::<16>().0).unwrap(), 32);
+        assert_eq!(min_k::<Fp, 1>(), 3);
+        assert_eq!(min_k::<Fp, 16>(), 6);
+
+        // One k less is not enough.
+        let (circuit, out) = circuit::<16>();
+        assert!(MockProver::run(5, &circuit, vec![vec![out]]).is_err());
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_chap_2_exercise_6_matches_exercise_5() {
+        use crate::chap_2::exercise_5;
+
+        let (chained, out) = circuit::<1>();
+        let single = exercise_5::MyCircuit {
+            c: chained.c,
+            a: chained.a,
+            b: chained.b,
+        };
+        for public in [out, out + Fp::one()] {
+            assert_eq!(verify(5, &chained, public), verify(5, &single, public));
+        }
+    }
+}
@@ -0,0 +1,330 @@
+/// chap2: composing chips
+/// Prove knowing private a, b, c, d s.t.
+///
+///     out = (a + b) * (c + d) * a
+///
+/// with two single-purpose chips, `AddChip` and `MulChip`, and an `ArithChip`
+/// built out of them. Each sub-chip has its own config, holding its own
+/// selector and gate; `ArithConfig` holds the two configs, and `ArithChip`
+/// constructs one sub-chip of each from them. Nothing is shared but what the
+/// caller passes to `configure`.
+///
+/// Columns are a circuit-wide resource, so two chips can use the same ones:
+/// each gate only applies on the rows where its own selector is on. Given the
+/// same three columns, the circuit is
+///
+/// | a0  | a1  | a2   | s_add | s_mul |
+/// |-----|-----|------|-------|-------|
+/// |  a  |     |      |   0   |   0   |
+/// |  b  |     |      |   0   |   0   |
+/// |  c  |     |      |   0   |   0   |
+/// |  d  |     |      |   0   |   0   |
+/// |  a  |  b  | a+b  |   1   |   0   |
+/// |  c  |  d  | c+d  |   1   |   0   |
+/// | a+b | c+d |  p   |   0   |   1   |
+/// |  p  |  a  | out  |   0   |   1   |
+///
+/// Given disjoint columns instead, the floor planner can put the products
+/// next to the loads, since no column of theirs is taken there: fewer rows,
+/// twice the columns. See `test_compose_footprint`.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+struct AddConfig {
+    advice: [Column<Advice>; 3],
+    s_add: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct AddChip<F: Field> {
+    config: AddConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> AddChip<F> {
+    fn construct(config: AddConfig) -> Self {
+        AddChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> AddConfig {
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_add = meta.selector();
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let [lhs, rhs, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(s, vec![lhs + rhs - out])
+        });
+        AddConfig { advice, s_add }
+    }
+
+    fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lhs: &Number<F>,
+        rhs: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [lhs_col, rhs_col, out_col] = self.config.advice;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                let lhs = lhs.0.copy_advice(|| "lhs", &mut region, lhs_col, 0)?;
+                let rhs = rhs.0.copy_advice(|| "rhs", &mut region, rhs_col, 0)?;
+                let value = lhs.value().copied() + rhs.value();
+                region
+                    .assign_advice(|| "lhs + rhs", out_col, 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MulConfig {
+    advice: [Column<Advice>; 3],
+    s_mul: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct MulChip<F: Field> {
+    config: MulConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> MulChip<F> {
+    fn construct(config: MulConfig) -> Self {
+        MulChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> MulConfig {
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_mul = meta.selector();
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(s_mul);
+            let [lhs, rhs, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(s, vec![lhs * rhs - out])
+        });
+        MulConfig { advice, s_mul }
+    }
+
+    fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lhs: &Number<F>,
+        rhs: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [lhs_col, rhs_col, out_col] = self.config.advice;
+        layouter.assign_region(
+            || "mul",
+            |mut region| {
+                self.config.s_mul.enable(&mut region, 0)?;
+                let lhs = lhs.0.copy_advice(|| "lhs", &mut region, lhs_col, 0)?;
+                let rhs = rhs.0.copy_advice(|| "rhs", &mut region, rhs_col, 0)?;
+                let value = lhs.value().copied() * rhs.value();
+                region
+                    .assign_advice(|| "lhs * rhs", out_col, 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+}
+
+/// The configs of both sub-chips, nothing of its own.
+#[derive(Debug, Clone)]
+struct ArithConfig {
+    add: AddConfig,
+    mul: MulConfig,
+}
+
+#[derive(Debug, Clone)]
+struct ArithChip<F: Field> {
+    config: ArithConfig,
+    add: AddChip<F>,
+    mul: MulChip<F>,
+}
+
+impl<F: Field> ArithChip<F> {
+    fn construct(config: ArithConfig) -> Self {
+        ArithChip {
+            add: AddChip::construct(config.add.clone()),
+            mul: MulChip::construct(config.mul.clone()),
+            config,
+        }
+    }
+
+    /// Pass the same columns twice to share them between the sub-chips.
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        add_advice: [Column<Advice>; 3],
+        mul_advice: [Column<Advice>; 3],
+    ) -> ArithConfig {
+        ArithConfig {
+            add: AddChip::configure(meta, add_advice),
+            mul: MulChip::configure(meta, mul_advice),
+        }
+    }
+
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "private", self.config.add.advice[0], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// `(a + b) * (c + d) * a`, one sub-chip call per node of the tree.
+    fn assign_expression(
+        &self,
+        mut layouter: impl Layouter<F>,
+        [a, b, c, d]: [&Number<F>; 4],
+    ) -> Result<Number<F>, Error> {
+        let a_b = self.add.add(layouter.namespace(|| "a + b"), a, b)?;
+        let c_d = self.add.add(layouter.namespace(|| "c + d"), c, d)?;
+        let p = self
+            .mul
+            .mul(layouter.namespace(|| "(a + b) * (c + d)"), &a_b, &c_d)?;
+        self.mul.mul(layouter.namespace(|| "* a"), &p, a)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ComposeConfig {
+    arith: ArithConfig,
+    instance: Column<Instance>,
+}
+
+/// `out = (a + b) * (c + d) * a`, with the sub-chips on the same columns
+/// when `SHARED`, on three columns each otherwise.
+#[derive(Default)]
+struct ComposeCircuit<F: Field, const SHARED: bool> {
+    inputs: [Value<F>; 4],
+}
+
+impl<F: Field, const SHARED: bool> Circuit<F> for ComposeCircuit<F, SHARED> {
+    type Config = ComposeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let add_advice = [(); 3].map(|_| meta.advice_column());
+        let mul_advice = if SHARED {
+            add_advice
+        } else {
+            [(); 3].map(|_| meta.advice_column())
+        };
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ComposeConfig {
+            arith: ArithChip::configure(meta, add_advice, mul_advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ArithChip::construct(config.arith);
+        let [a, b, c, d] = self.inputs;
+        let a = chip.load_private(layouter.namespace(|| "a"), a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), b)?;
+        let c = chip.load_private(layouter.namespace(|| "c"), c)?;
+        let d = chip.load_private(layouter.namespace(|| "d"), d)?;
+        let out = chip.assign_expression(layouter.namespace(|| "expression"), [&a, &b, &c, &d])?;
+        layouter.constrain_instance(out.0.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    fn expression([a, b, c, d]: [u64; 4]) -> u64 {
+        (a + b) * (c + d) * a
+    }
+
+    fn circuit<const SHARED: bool>(inputs: [u64; 4]) -> ComposeCircuit<Fp, SHARED> {
+        ComposeCircuit {
+            inputs: inputs.map(|x| Value::known(Fp::from(x))),
+        }
+    }
+
+    fn verify<const SHARED: bool>(inputs: [u64; 4], out: u64) -> bool {
+        let prover = MockProver::run(K, &circuit::<SHARED>(inputs), vec![vec![Fp::from(out)]]);
+        prover.unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn test_compose() {
+        for inputs in [[2, 3, 4, 5], [0, 7, 1, 1], [9, 0, 0, 0]] {
+            let out = expression(inputs);
+            assert!(verify::<true>(inputs, out));
+            assert!(verify::<false>(inputs, out));
+        }
+        assert_eq!(expression([2, 3, 4, 5]), 90);
+    }
+
+    #[test]
+    fn test_compose_wrong_output() {
+        let inputs = [2, 3, 4, 5];
+        assert!(!verify::<true>(inputs, 91));
+        assert!(!verify::<false>(inputs, 91));
+        // (a + b) * (c + d) * b is another expression.
+        assert!(!verify::<true>(inputs, (2 + 3) * (4 + 5) * 3));
+    }
+
+    #[test]
+    fn test_compose_footprint() {
+        let inputs = [2, 3, 4, 5];
+        let columns = |shared| {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            if shared {
+                ComposeCircuit::<Fp, true>::configure(&mut meta);
+            } else {
+                ComposeCircuit::<Fp, false>::configure(&mut meta);
+            }
+            meta.num_advice_columns()
+        };
+
+        // Shared: the four loads, then the four operations, stacked.
+        assert_eq!(columns(true), 3);
+        assert_eq!(advice_rows(K, &circuit::<true>(inputs)).unwrap(), 8);
+        // Disjoint: the products go on the rows of the first two loads.
+        assert_eq!(columns(false), 6);
+        assert_eq!(advice_rows(K, &circuit::<false>(inputs)).unwrap(), 6);
+    }
+}
@@ -0,0 +1,146 @@
+/// chap2: keys from the circuit's shape
+/// Prove knowledge of private a, b s.t. out = a * b + c, for a constant c.
+///
+/// `keygen_vk` and `keygen_pk` run `synthesize` like the prover does, but
+/// through an assignment that drops every advice value: they only keep the
+/// fixed columns, the selectors and the copy constraints. So the key is made
+/// from `circuit.without_witnesses()`, a copy that has the same shape and no
+/// secrets, and whoever generates keys never needs to see a witness.
+///
+/// "The same shape" includes everything that ends up fixed, and here that is
+/// `c`, assigned with `assign_advice_from_constant`. `without_witnesses` must
+/// keep it: chap 2's `MyCircuit` returns `Self::default()`, which resets `c`
+/// to zero, and a key made from that shape rejects every honest proof.
+///
+/// Keygen from a witness-bearing circuit goes through without complaint: the
+/// witness is dropped all the same. But whatever else that instance carries
+/// is not, and a key generated from one prover's circuit is a key for its
+/// constants, not for the circuit.
+///
+/// | a0  | a1  | a2  | s_mul | s_add | constant |
+/// |-----|-----|-----|-------|-------|----------|
+/// |  a  |     |     |   0   |   0   |          |
+/// |  b  |     |     |   0   |   0   |          |
+/// |  c  |     |     |   0   |   0   |    c     |
+/// |  a  |  b  |  ab |   1   |   0   |          |
+/// |  ab |  c  | out |   0   |   1   |          |
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+};
+
+use crate::gadgets::arith::{ArithChip, ArithConfig};
+
+#[derive(Debug, Clone)]
+pub struct ShapeConfig {
+    arith: ArithConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct ShapeCircuit<F: Field> {
+    pub c: F,
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for ShapeCircuit<F> {
+    type Config = ShapeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    /// The witnesses go, the constant stays.
+    fn without_witnesses(&self) -> Self {
+        ShapeCircuit {
+            c: self.c,
+            a: Value::unknown(),
+            b: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ShapeConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ArithChip::construct(config.arith);
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let c = chip.load_constant(layouter.namespace(|| "c"), self.c)?;
+        let ab = chip.mul(layouter.namespace(|| "a * b"), a, b)?;
+        let out = chip.add(layouter.namespace(|| "ab + c"), ab, c)?;
+        chip.expose_public(layouter.namespace(|| "out"), out, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::prover::{Prover, RealProverBackend};
+    use halo2_proofs::{
+        pasta::{EqAffine, Fp},
+        poly::commitment::Params,
+    };
+
+    const K: u32 = 5;
+
+    fn circuit(a: u64, b: u64, c: u64) -> (ShapeCircuit<Fp>, Fp) {
+        let circuit = ShapeCircuit {
+            c: Fp::from(c),
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+        (circuit, Fp::from(a * b + c))
+    }
+
+    /// Prove `circuit` under the backend's keys and verify the proof.
+    fn prove_and_verify(
+        backend: &RealProverBackend<EqAffine, ShapeCircuit<Fp>>,
+        circuit: ShapeCircuit<Fp>,
+        out: Fp,
+    ) -> bool {
+        let proof = backend.prove(circuit, &[&[out]]).unwrap();
+        backend.verify(&proof, &[&[out]]).is_ok()
+    }
+
+    #[test]
+    fn test_keygen_from_shape() {
+        let params: Params<EqAffine> = Params::new(K);
+        let (circuit, out) = circuit(2, 3, 4);
+        let backend = RealProverBackend::with_params(params, &circuit.without_witnesses()).unwrap();
+        assert!(prove_and_verify(&backend, circuit, out));
+    }
+
+    #[test]
+    fn test_keygen_from_witness() {
+        let params: Params<EqAffine> = Params::new(K);
+
+        // Keygen from a prover's circuit: the witness is ignored, the key is
+        // the same as from the shape...
+        let (theirs, _) = circuit(5, 7, 3);
+        let backend = RealProverBackend::with_params(params.clone(), &theirs).unwrap();
+        let shape = RealProverBackend::with_params(params, &theirs.without_witnesses()).unwrap();
+        let (vk, shape_vk) = (backend.vk(), shape.vk());
+        assert_eq!(vk.fixed_commitments(), shape_vk.fixed_commitments());
+        assert_eq!(
+            vk.permutation().commitments(),
+            shape_vk.permutation().commitments()
+        );
+
+        // ...but it is the shape of that circuit, c = 3 and all, and a proof
+        // for c = 4 does not verify under it.
+        let (ours, out) = circuit(2, 3, 4);
+        assert!(!prove_and_verify(&backend, ours, out));
+    }
+}
@@ -0,0 +1,182 @@
+/// chap2: the complex gate, one product at a time
+/// The formula of exercise 5, for private `a`, `b` and a constant `c`:
+///
+///   out = (a^2 * b^2 * c + c)^3
+///
+/// Exercise 5 checks it with one gate of degree 15, 16 with its selector.
+/// Here every intermediate value gets a cell of its own and each step is an
+/// `ArithChip` row, `lhs * rhs = out` or `lhs + rhs = out`, degree 2 before
+/// the selector:
+///
+/// | a0  | a1  | a2  | s_add | s_mul |
+/// |-----|-----|-----|-------|-------|
+/// |  a  |  b  | ab  |   0   |   1   |
+/// | ab  | ab  | ab2 |   0   |   1   |
+/// | ab2 |  c  |  d  |   0   |   1   |
+/// |  d  |  c  |  e  |   1   |   0   |
+/// |  e  |  e  | e2  |   0   |   1   |
+/// | e2  |  e  | out |   0   |   1   |
+///
+/// (after the rows that load `a`, `b` and `c`). The trade-off, for one use
+/// of the formula:
+///
+/// | layout     | max degree, without / with selector | rows |
+/// |------------|-------------------------------------|------|
+/// | exercise 5 |               15 / 16               |  2   |
+/// | low degree |                2 / 3                |  9   |
+///
+/// The prover's extended domain grows with the largest gate, about
+/// `(d - 1) * 2^k` for degree `d`, and it is sized for every column alike.
+/// Trading one degree-16 gate for six rows keeps that domain small, at the
+/// cost of a taller circuit and more copy constraints.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct LowDegreeConfig {
+    arith: ArithConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct LowDegreeCircuit<F: Field> {
+    pub c: F,
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+/// `out` for the cells `a`, `b` and `c`, one degree-2 step per row.
+pub fn low_degree_formula<F: Field>(
+    chip: &ArithChip<F>,
+    mut layouter: impl Layouter<F>,
+    a: Number<F>,
+    b: Number<F>,
+    c: Number<F>,
+) -> Result<Number<F>, Error> {
+    let ab = chip.mul(layouter.namespace(|| "ab"), a, b)?;
+    let ab2 = chip.mul(layouter.namespace(|| "ab^2"), ab.clone(), ab)?;
+    let d = chip.mul(layouter.namespace(|| "d"), ab2, c.clone())?;
+    let e = chip.add(layouter.namespace(|| "e"), d, c)?;
+    let e2 = chip.mul(layouter.namespace(|| "e^2"), e.clone(), e.clone())?;
+    chip.mul(layouter.namespace(|| "out"), e2, e)
+}
+
+impl<F: Field> Circuit<F> for LowDegreeCircuit<F> {
+    type Config = LowDegreeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        LowDegreeConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ArithChip::construct(config.arith);
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let c = chip.load_constant(layouter.namespace(|| "c"), self.c)?;
+        let out = low_degree_formula(&chip, layouter.namespace(|| "formula"), a, b, c)?;
+        chip.expose_public(layouter.namespace(|| "out"), out, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{
+        degree::{max_constraint_degree, max_gate_degree},
+        rows::advice_rows,
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    fn circuit() -> (LowDegreeCircuit<Fp>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let circuit = LowDegreeCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, out)
+    }
+
+    #[test]
+    fn test_low_degree() {
+        let (circuit, out) = circuit();
+        MockProver::run(K, &circuit, vec![vec![out]])
+            .unwrap()
+            .assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_low_degree_shape() {
+        let (circuit, _) = circuit();
+        // lhs * rhs - out is degree 2, and the selector adds one.
+        assert_eq!(max_constraint_degree(&circuit), 2);
+        assert_eq!(max_gate_degree(&circuit), 3);
+        // Three loads and six steps.
+        assert_eq!(advice_rows(K, &circuit).unwrap(), 9);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_low_degree_matches_exercise_5() {
+        use crate::chap_2::exercise_5::MyCircuit;
+
+        // The chain of steps is the complex gate factored: both accept the
+        // formula's output, zero factors and a zero constant included.
+        for (a, b, c) in [(2, 3, 2), (0, 5, 7), (11, 1, 0)] {
+            let (a, b, c) = (Fp::from(a), Fp::from(b), Fp::from(c));
+            let out = (c * a.square() * b.square() + c).cube();
+            let (a, b) = (Value::known(a), Value::known(b));
+            let low = LowDegreeCircuit { c, a, b };
+            let complex = MyCircuit { c, a, b };
+            MockProver::run(K, &low, vec![vec![out]])
+                .unwrap()
+                .assert_satisfied();
+            MockProver::run(K, &complex, vec![vec![out]])
+                .unwrap()
+                .assert_satisfied();
+        }
+
+        // The table above: a degree-15 constraint on 2 rows against
+        // degree-2 ones on 9.
+        let (low, _) = circuit();
+        let complex = MyCircuit {
+            c: low.c,
+            a: low.a,
+            b: low.b,
+        };
+        assert_eq!(max_constraint_degree(&complex), 15);
+        assert_eq!(max_gate_degree(&complex), 16);
+        assert_eq!(advice_rows(K, &complex).unwrap(), 2);
+        assert_eq!(advice_rows(K, &low).unwrap(), 9);
+    }
+}
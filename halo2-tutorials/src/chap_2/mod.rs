@@ -1,10 +1,19 @@
-mod custom_gate;
-mod simple_chip;
+pub(crate) mod custom_gate;
+mod exercise_5_instructions;
+mod exercise_6;
+mod exercise_compose;
+mod keygen_shape;
+mod lowdegree;
+mod poly_from_instance;
+pub(crate) mod simple_chip;
 
 #[cfg(feature = "chap_2_exercise_4")]
-mod exercise_4;
+pub(crate) mod exercise_4;
 
 #[cfg(feature = "chap_2_exercise_5")]
-mod exercise_5;
+pub(crate) mod exercise_5;
+
+#[cfg(all(test, feature = "chap_2_exercise_5"))]
+pub(crate) mod exercise_5_fixtures;
 
 // mod exercise_4_;
@@ -0,0 +1,123 @@
+/// chap2: a polynomial read from the instance
+/// The instance column is a column like the others: the verifier fills as
+/// many rows of it as the circuit asks for. Here the `N` coefficients of
+///
+///   p(X) = c_0 + c_1 * X + ... + c_{N-1} * X^{N-1}
+///
+/// are public, one per instance row, and the point `x` is private. Each
+/// coefficient is copied into an advice cell by `assign_advice_from_instance`,
+/// which also constrains the copy to its instance cell, and the
+/// `PolynomialEvalChip` evaluates at `x`. The value is exposed on the row
+/// after the coefficients:
+///
+/// | instance  | a0      | a1 | a2  |
+/// |-----------|---------|----|-----|
+/// | c_0       | c_0     | x  |     |
+/// | c_1       | c_1     |    |     |
+/// | ...       | ...     |    |     |
+/// | c_{N-1}   | c_{N-1} |    |     |
+/// | p(x)      |         |    |     |
+///
+/// followed by the `N` rows of Horner's rule, whose last cell is copied to
+/// the instance row `N`. The verifier learns `p(x)` but not `x`.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+};
+
+use crate::gadgets::{
+    polynomial_eval::{PolynomialEvalChip, PolynomialEvalConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct PolyFromInstanceConfig {
+    polynomial_eval: PolynomialEvalConfig,
+    instance: Column<Instance>,
+}
+
+/// `instance = [c_0, .., c_{N-1}, p(x)]` for the private `x`.
+#[derive(Default)]
+pub struct PolyFromInstanceCircuit<F: Field, const N: usize> {
+    pub x: Value<F>,
+}
+
+impl<F: Field, const N: usize> Circuit<F> for PolyFromInstanceCircuit<F, N> {
+    type Config = PolyFromInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PolyFromInstanceConfig {
+            polynomial_eval: PolynomialEvalChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PolynomialEvalChip::construct(config.polynomial_eval.clone());
+        let [coeff_col, x_col, _] = config.polynomial_eval.advice;
+        let (coeffs, x) = layouter.assign_region(
+            || "load",
+            |mut region| {
+                // Instance row `i` holds `c_i`.
+                let coeffs = (0..N)
+                    .map(|row| {
+                        region
+                            .assign_advice_from_instance(
+                                || "c",
+                                config.instance,
+                                row,
+                                coeff_col,
+                                row,
+                            )
+                            .map(Number)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let x = region.assign_advice(|| "x", x_col, 0, || self.x)?;
+                Ok((coeffs, Number(x)))
+            },
+        )?;
+        let value = chip.eval(layouter.namespace(|| "p(x)"), &coeffs, &x)?;
+        layouter.constrain_instance(value.0.cell(), config.instance, N)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    fn verify(coeffs: [u64; 3], x: u64, value: u64) -> bool {
+        let circuit = PolyFromInstanceCircuit::<Fp, 3> {
+            x: Value::known(Fp::from(x)),
+        };
+        let mut public = coeffs.map(Fp::from).to_vec();
+        public.push(Fp::from(value));
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_poly_from_instance() {
+        // 1 + 2 * 2 + 3 * 2^2
+        assert!(verify([1, 2, 3], 2, 17));
+        assert!(!verify([1, 2, 3], 2, 18));
+        // The same value for other coefficients is another statement.
+        assert!(!verify([3, 2, 1], 2, 17));
+        assert!(verify([3, 2, 1], 2, 11));
+    }
+}
@@ -26,30 +26,52 @@ use halo2_proofs::{
 // / |       |   e   |  out  |   0   |   0   |   1   |
 
 #[derive(Debug, Clone)]
-struct SimpleConfig {
-    advice: [Column<Advice>; 2],
-    instance: Column<Instance>,
-    s_mul: Selector,
-    s_add: Selector,
-    s_cub: Selector,
+pub(crate) struct SimpleConfig {
+    pub(crate) advice: [Column<Advice>; 2],
+    pub(crate) instance: Column<Instance>,
+    pub(crate) s_mul: Selector,
+    pub(crate) s_add: Selector,
+    pub(crate) s_cub: Selector,
 }
 
 #[derive(Clone)]
-struct Number<F: Field>(AssignedCell<F, F>);
+pub(crate) struct Number<F: Field>(AssignedCell<F, F>);
 
+/// The config is `None` only in a `SimpleChip::default()` placeholder.
 #[derive(Debug, Clone)]
-struct SimpleChip<F: Field> {
-    config: SimpleConfig,
+pub(crate) struct SimpleChip<F: Field> {
+    config: Option<SimpleConfig>,
     _marker: PhantomData<F>,
 }
 
+/// A placeholder for a circuit struct to hold before `configure` has run,
+/// e.g. in `MyCircuit::default()`. It has no columns: use it to assign
+/// anything and it panics. The chip to assign with is the one `synthesize`
+/// constructs from the config it is handed.
+impl<F: Field> Default for SimpleChip<F> {
+    fn default() -> Self {
+        SimpleChip {
+            config: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<F: Field> SimpleChip<F> {
     pub fn construct(config: SimpleConfig) -> Self {
         SimpleChip {
-            config,
+            config: Some(config),
             _marker: PhantomData,
         }
     }
+
+    fn config(&self) -> &SimpleConfig {
+        self.config.as_ref().expect(
+            "SimpleChip::default() is a placeholder: construct the chip from the config \
+             SimpleChip::configure returns before assigning with it",
+        )
+    }
+
     pub fn configure(meta: &mut ConstraintSystem<F>) -> SimpleConfig {
         let advice = [meta.advice_column(), meta.advice_column()];
         let instance = meta.instance_column();
@@ -103,43 +125,36 @@ impl<F: Field> SimpleChip<F> {
         b: Value<F>,
         c: F,
     ) -> Result<Number<F>, Error> {
-        let cells = layouter
-            .assign_region(
-                || "load private",
-                |mut region| {
-                    let a_cell = region
-                        .assign_advice(|| "private input a", self.config.advice[0], 0, || a)
-                        .map(Number)?;
-                    let b_cell = region
-                        .assign_advice(|| "private input b", self.config.advice[0], 1, || b)
-                        .map(Number)?;
-                    let c_cell = region
-                        .assign_advice_from_constant(
-                            || "private input c",
-                            self.config.advice[0],
-                            2,
-                            c,
-                        )
-                        .map(Number)?;
-                    Ok((a_cell, b_cell, c_cell))
-                },
-            )
-            .unwrap();
+        let config = self.config();
+        let cells = layouter.assign_region(
+            || "load private",
+            |mut region| {
+                let a_cell = region
+                    .assign_advice(|| "private input a", config.advice[0], 0, || a)
+                    .map(Number)?;
+                let b_cell = region
+                    .assign_advice(|| "private input b", config.advice[0], 1, || b)
+                    .map(Number)?;
+                let c_cell = region
+                    .assign_advice_from_constant(|| "private input c", config.advice[0], 2, c)
+                    .map(Number)?;
+                Ok((a_cell, b_cell, c_cell))
+            },
+        )?;
 
         layouter.assign_region(
             || "load witness",
             move |mut region| {
-                let config = &self.config;
                 let mut offset = 0;
 
                 // load a, b
                 let (a, b, c) = &cells;
                 config.s_mul.enable(&mut region, offset)?;
                 let a =
-                    a.0.copy_advice(|| "lhs", &mut region, self.config.advice[0], offset)
+                    a.0.copy_advice(|| "lhs", &mut region, config.advice[0], offset)
                         .map(Number)?;
                 let b =
-                    b.0.copy_advice(|| "rhs", &mut region, self.config.advice[1], offset)
+                    b.0.copy_advice(|| "rhs", &mut region, config.advice[1], offset)
                         .map(Number)?;
 
                 // fill ab, ab
@@ -151,7 +166,7 @@ impl<F: Field> SimpleChip<F> {
                     .map(Number)?;
                 let ab_1 = ab_0
                     .0
-                    .copy_advice(|| "ab rhs", &mut region, self.config.advice[1], offset)
+                    .copy_advice(|| "ab rhs", &mut region, config.advice[1], offset)
                     .map(Number)?;
 
                 // fill absq, c
@@ -162,7 +177,7 @@ impl<F: Field> SimpleChip<F> {
                     .assign_advice(|| "absq", config.advice[0], offset, || value)
                     .map(Number)?;
                 let c =
-                    c.0.copy_advice(|| "c", &mut region, self.config.advice[1], offset)
+                    c.0.copy_advice(|| "c", &mut region, config.advice[1], offset)
                         .map(Number)?;
 
                 // fill c, d
@@ -173,7 +188,7 @@ impl<F: Field> SimpleChip<F> {
                     .assign_advice(|| "d", config.advice[0], offset, || value)
                     .map(Number)?;
                 let c =
-                    c.0.copy_advice(|| "c", &mut region, self.config.advice[1], offset)
+                    c.0.copy_advice(|| "c", &mut region, config.advice[1], offset)
                         .map(Number)?;
 
                 // fill e
@@ -199,15 +214,15 @@ impl<F: Field> SimpleChip<F> {
         out: Number<F>,
         row: usize,
     ) -> Result<(), Error> {
-        layouter.constrain_instance(out.0.cell(), self.config.instance, row)
+        layouter.constrain_instance(out.0.cell(), self.config().instance, row)
     }
 }
 
 #[derive(Default)]
-struct MyCircuit<F: Field> {
-    c: F,
-    a: Value<F>,
-    b: Value<F>,
+pub(crate) struct MyCircuit<F: Field> {
+    pub(crate) c: F,
+    pub(crate) a: Value<F>,
+    pub(crate) b: Value<F>,
 }
 
 impl<F: Field> Circuit<F> for MyCircuit<F> {
@@ -283,6 +298,90 @@ mod tests {
         // ANCHOR_END: test-circuit
     }
 
+    /// `MyCircuit` holding its chip, which it must have before `configure`
+    /// has run. With `use_placeholder` it assigns with that placeholder
+    /// instead of the chip built from the config.
+    #[derive(Default)]
+    struct ChipCircuit<F: Field> {
+        chip: SimpleChip<F>,
+        c: F,
+        a: Value<F>,
+        b: Value<F>,
+        use_placeholder: bool,
+    }
+
+    impl<F: Field> Circuit<F> for ChipCircuit<F> {
+        type Config = SimpleConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ChipCircuit {
+                c: self.c,
+                use_placeholder: self.use_placeholder,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            SimpleChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = if self.use_placeholder {
+                self.chip.clone()
+            } else {
+                SimpleChip::construct(config)
+            };
+            let out = chip.assign(layouter.namespace(|| "simple ship"), self.a, self.b, self.c)?;
+            chip.expose_public(layouter, out, 0)
+        }
+    }
+
+    #[test]
+    fn test_placeholder_chip() {
+        let k = 5;
+        let (circuit, out) = circuit();
+        let circuit = ChipCircuit {
+            c: circuit.c,
+            a: circuit.a,
+            b: circuit.b,
+            ..ChipCircuit::default()
+        };
+        MockProver::run(k, &circuit, vec![vec![out]])
+            .unwrap()
+            .assert_satisfied();
+
+        // The witness-free copies, placeholder and all, synthesize up to
+        // the first private input: MockProver needs its value, so that is an
+        // `Error::Synthesis` rather than the placeholder's panic.
+        let result = MockProver::run(k, &circuit.without_witnesses(), vec![vec![out]]);
+        assert!(matches!(result, Err(Error::Synthesis)));
+        let result = MockProver::run(
+            k,
+            &MyCircuit::<Fp>::default().without_witnesses(),
+            vec![vec![out]],
+        );
+        assert!(matches!(result, Err(Error::Synthesis)));
+    }
+
+    #[test]
+    #[should_panic(expected = "SimpleChip::default() is a placeholder")]
+    fn test_placeholder_chip_assign() {
+        let (circuit, out) = circuit();
+        let circuit = ChipCircuit {
+            c: circuit.c,
+            a: circuit.a,
+            b: circuit.b,
+            use_placeholder: true,
+            ..ChipCircuit::default()
+        };
+        let _ = MockProver::run(5, &circuit, vec![vec![out]]);
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_chip_circuit() {
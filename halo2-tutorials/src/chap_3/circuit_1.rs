@@ -22,7 +22,7 @@ use halo2_proofs::{
 /// |       | f(n)=out|   0    |
 
 #[derive(Debug, Clone)]
-struct FiboChipConfig {
+pub(crate) struct FiboChipConfig {
     advice: Column<Advice>,
     instance: Column<Instance>,
     selector: Selector,
@@ -106,8 +106,8 @@ impl<F: Field> FiboChip<F> {
 }
 
 #[derive(Debug, Clone, Default)]
-struct FiboCircuit<F: Field> {
-    nrow: usize,
+pub(crate) struct FiboCircuit<F: Field> {
+    pub(crate) nrow: usize,
     _marker: PhantomData<F>,
 }
 
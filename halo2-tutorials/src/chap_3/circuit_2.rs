@@ -19,7 +19,7 @@ use halo2_proofs::{
 /// out = n % 2 == 0 ? f(2n/2) : f(2n/2 + 1)
 
 #[derive(Clone, Debug)]
-struct FiboChipConfig {
+pub(crate) struct FiboChipConfig {
     advice: [Column<Advice>; 2],
     selector: Selector,
     instance: Column<Instance>,
@@ -118,8 +118,8 @@ impl<F: Field> FiboChip<F> {
 }
 
 #[derive(Debug, Default)]
-struct FiboCircuit<F: Field> {
-    nrow: usize,
+pub(crate) struct FiboCircuit<F: Field> {
+    pub(crate) nrow: usize,
     _marker: PhantomData<F>,
 }
 
@@ -0,0 +1,185 @@
+// Problem to prove: every row satisfies c = a + b or c = a * b,
+// where which one is decided per row when the circuit is built.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+/// Circuit design:
+/// |  a0   |  a1   |  a2   | s_add | s_mul |
+/// |-------|-------|-------|-------|-------|
+/// |   2   |   3   |   5   |   1   |   0   |
+/// |   2   |   3   |   6   |   0   |   1   |
+/// |   4   |   4   |   8   |   1   |   0   |
+/// |  ...  |  ...  |  ...  |       |       |
+///
+/// Both gates are always part of the circuit, each multiplied by its own
+/// selector. On a row where `s_add` is 1 and `s_mul` is 0 the "mul" gate
+/// collapses to `0 * (a * b - c) = 0` and only "add" has a say, and the other
+/// way around. Selectors are fixed columns, so the choice of gate per row is
+/// baked into the verifying key: the prover cannot switch a row to whichever
+/// gate its values happen to satisfy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GateKind {
+    Add,
+    Mul,
+}
+
+#[derive(Debug, Clone)]
+struct ConditionalConfig {
+    advice: [Column<Advice>; 3],
+    s_add: Selector,
+    s_mul: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct ConditionalChip<F: Field> {
+    config: ConditionalConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ConditionalChip<F> {
+    fn construct(config: ConditionalConfig) -> Self {
+        ConditionalChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ConditionalConfig {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let s_add = meta.selector();
+        let s_mul = meta.selector();
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            Constraints::with_selector(s, vec![a + b - c])
+        });
+
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(s_mul);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            Constraints::with_selector(s, vec![a * b - c])
+        });
+
+        ConditionalConfig {
+            advice,
+            s_add,
+            s_mul,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rows: &[(GateKind, Value<F>, Value<F>, Value<F>)],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "conditional region",
+            |mut region| {
+                let [a_col, b_col, c_col] = self.config.advice;
+                for (row, (kind, a, b, c)) in rows.iter().enumerate() {
+                    match kind {
+                        GateKind::Add => self.config.s_add.enable(&mut region, row)?,
+                        GateKind::Mul => self.config.s_mul.enable(&mut region, row)?,
+                    }
+                    region.assign_advice(|| "a", a_col, row, || *a)?;
+                    region.assign_advice(|| "b", b_col, row, || *b)?;
+                    region.assign_advice(|| "c", c_col, row, || *c)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConditionalCircuit<F: Field> {
+    rows: Vec<(GateKind, Value<F>, Value<F>, Value<F>)>,
+}
+
+impl<F: Field> Circuit<F> for ConditionalCircuit<F> {
+    type Config = ConditionalConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ConditionalCircuit {
+            rows: self
+                .rows
+                .iter()
+                .map(|(kind, ..)| (*kind, Value::unknown(), Value::unknown(), Value::unknown()))
+                .collect(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ConditionalChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ConditionalChip::<F>::construct(config);
+        chip.assign(layouter.namespace(|| "conditional"), &self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn circuit(rows: &[(GateKind, u64, u64, u64)]) -> ConditionalCircuit<Fp> {
+        ConditionalCircuit {
+            rows: rows
+                .iter()
+                .map(|(kind, a, b, c)| {
+                    (
+                        *kind,
+                        Value::known(Fp::from(*a)),
+                        Value::known(Fp::from(*b)),
+                        Value::known(Fp::from(*c)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_conditional_gates() {
+        let k = 4;
+        let circuit = circuit(&[
+            (GateKind::Add, 2, 3, 5),
+            (GateKind::Mul, 2, 3, 6),
+            (GateKind::Add, 4, 4, 8),
+            (GateKind::Mul, 7, 0, 0),
+        ]);
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_conditional_gates_wrong_gate() {
+        let k = 4;
+        // 2 + 3 = 5 is fine on its own, but this row is wired to multiply.
+        let circuit = circuit(&[(GateKind::Add, 2, 3, 5), (GateKind::Mul, 2, 3, 5)]);
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
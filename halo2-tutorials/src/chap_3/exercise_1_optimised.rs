@@ -0,0 +1,377 @@
+// Problem to prove: f(n) = f(n-1) + f(n-2), f(0) = a, f(1) = b
+// with one addition per row, and what a copy constraint saves there.
+//
+// Both chips here keep their gate on a single row, or a row and the next,
+// and start each row from copies of the previous one. That is not the least
+// a Fibonacci circuit needs: `circuit_1` gets by with one advice column by
+// reading three rows with `Rotation(2)`, and `circuit_2` puts two terms on
+// each row of two columns, so it takes half the rows and no copies at all.
+// The point here is the step from three columns to two, and the copy
+// constraint that makes it.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::*,
+    poly::Rotation,
+};
+
+#[derive(Debug, Clone)]
+struct ACell<F: Field>(AssignedCell<F, F>);
+
+/// The naive layout keeps the two summands *and* the sum on the same row:
+///
+/// | ins   | a0     | a1     | a2     | seletor|
+/// |-------|--------|--------|--------|--------|
+/// |   a   | f(0)=a | f(1)=b | f(2)   |    1   |
+/// |   b   | f(1)   | f(2)   | f(3)   |    1   |
+/// |  out  | f(2)   | f(3)   | f(4)   |    1   |
+/// |       |  ...   |  ...   |  ...   |        |
+/// |       | f(n-2) | f(n-1) | f(n)   |    1   |
+///
+/// Every row after the first copies `a1 -> a0` and `a2 -> a1` from the row above.
+#[derive(Debug, Clone)]
+struct NaiveFiboConfig {
+    advice: [Column<Advice>; 3],
+    selector: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+struct NaiveFibonacciChip<F: Field> {
+    config: NaiveFiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> NaiveFibonacciChip<F> {
+    fn construct(config: NaiveFiboConfig) -> Self {
+        NaiveFibonacciChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> NaiveFiboConfig {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+
+        meta.create_gate("naive fibo gate", |meta| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            Constraints::with_selector(s, vec![a + b - c])
+        });
+
+        NaiveFiboConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn assign(&self, mut layouter: impl Layouter<F>, nrow: usize) -> Result<ACell<F>, Error> {
+        layouter.assign_region(
+            || "naive fibo region",
+            |mut region| {
+                let [a_col, b_col, c_col] = self.config.advice;
+                let instance = self.config.instance;
+
+                let mut a = region
+                    .assign_advice_from_instance(|| "f0", instance, 0, a_col, 0)
+                    .map(ACell)?;
+                let mut b = region
+                    .assign_advice_from_instance(|| "f1", instance, 1, b_col, 0)
+                    .map(ACell)?;
+                let mut c = None;
+
+                for row in 0..nrow - 1 {
+                    if row > 0 {
+                        let prev_c: &ACell<F> = c.as_ref().unwrap();
+                        a =
+                            b.0.copy_advice(|| "a", &mut region, a_col, row)
+                                .map(ACell)?;
+                        b = prev_c
+                            .0
+                            .copy_advice(|| "b", &mut region, b_col, row)
+                            .map(ACell)?;
+                    }
+                    self.config.selector.enable(&mut region, row)?;
+                    let value = a.0.value().copied() + b.0.value().copied();
+                    c = Some(
+                        region
+                            .assign_advice(|| "c", c_col, row, || value)
+                            .map(ACell)?,
+                    );
+                }
+
+                Ok(c.unwrap())
+            },
+        )
+    }
+}
+
+/// The efficient layout writes the sum straight into the *next* row:
+///
+/// | ins   | a0       | a1       | seletor|
+/// |-------|----------|----------|--------|
+/// |   a   | f(0)=a   | f(1)=b   |    1   |
+/// |   b   | f(1)     | f(2)     |    1   |
+/// |  out  | f(2)     | f(3)     |    1   |
+/// |       |  ...     |  ...     |        |
+/// |       | f(n-1)   | f(n)=out |    0   |
+///
+/// The gate reads `b_next = a_cur + b_cur`, and a copy constraint enforces
+/// `a_next = b_cur`. The sum never needs a cell of its own on the current row:
+/// it *is* the next row's `a1`, and the copy constraint lets the old `b_cur`
+/// reappear as `a_next` without recomputing it. That is exactly the job the
+/// third column was doing in the naive layout, so it can go. Gates that
+/// reach further down, as in `circuit_1` and `circuit_2`, do away with the
+/// copies too.
+#[derive(Debug, Clone)]
+struct EfficientFiboConfig {
+    advice: [Column<Advice>; 2],
+    selector: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+struct EfficientFibonacciChip<F: Field> {
+    config: EfficientFiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> EfficientFibonacciChip<F> {
+    fn construct(config: EfficientFiboConfig) -> Self {
+        EfficientFibonacciChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> EfficientFiboConfig {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+
+        // | a0     |   a1   | seletor|
+        // |--------|--------|--------|
+        // | a_cur  | b_cur  |    1   |
+        // | a_next | b_next |        |
+        meta.create_gate("efficient fibo gate", |meta| {
+            let s = meta.query_selector(selector);
+            let a_cur = meta.query_advice(advice[0], Rotation::cur());
+            let b_cur = meta.query_advice(advice[1], Rotation::cur());
+            let b_next = meta.query_advice(advice[1], Rotation::next());
+            Constraints::with_selector(s, vec![a_cur + b_cur - b_next])
+        });
+
+        EfficientFiboConfig {
+            advice,
+            selector,
+            instance,
+        }
+    }
+
+    fn assign(&self, mut layouter: impl Layouter<F>, nrow: usize) -> Result<ACell<F>, Error> {
+        layouter.assign_region(
+            || "efficient fibo region",
+            |mut region| {
+                let [a_col, b_col] = self.config.advice;
+                let instance = self.config.instance;
+
+                let mut a = region
+                    .assign_advice_from_instance(|| "f0", instance, 0, a_col, 0)
+                    .map(ACell)?;
+                let mut b = region
+                    .assign_advice_from_instance(|| "f1", instance, 1, b_col, 0)
+                    .map(ACell)?;
+
+                for row in 1..nrow {
+                    self.config.selector.enable(&mut region, row - 1)?;
+                    let value = a.0.value().copied() + b.0.value().copied();
+                    // a_next = b_cur is a copy constraint, not a gate.
+                    let a_next =
+                        b.0.copy_advice(|| "a_next", &mut region, a_col, row)
+                            .map(ACell)?;
+                    let b_next = region
+                        .assign_advice(|| "b_next", b_col, row, || value)
+                        .map(ACell)?;
+                    a = a_next;
+                    b = b_next;
+                }
+
+                Ok(b)
+            },
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct NaiveFiboCircuit<F: Field> {
+    nrow: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for NaiveFiboCircuit<F> {
+    type Config = NaiveFiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        NaiveFiboCircuit {
+            nrow: self.nrow,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        NaiveFibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = NaiveFibonacciChip::<F>::construct(config);
+        let out = chip.assign(layouter.namespace(|| "naive fibo"), self.nrow)?;
+        layouter
+            .namespace(|| "out")
+            .constrain_instance(out.0.cell(), chip.config.instance, 2)
+    }
+}
+
+#[derive(Debug, Default)]
+struct EfficientFiboCircuit<F: Field> {
+    nrow: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for EfficientFiboCircuit<F> {
+    type Config = EfficientFiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        EfficientFiboCircuit {
+            nrow: self.nrow,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        EfficientFibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = EfficientFibonacciChip::<F>::construct(config);
+        let out = chip.assign(layouter.namespace(|| "efficient fibo"), self.nrow)?;
+        layouter
+            .namespace(|| "out")
+            .constrain_instance(out.0.cell(), chip.config.instance, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn fib(n: u64) -> u64 {
+        match n {
+            0 => 1,
+            1 => 1,
+            _ => fib(n - 1) + fib(n - 2),
+        }
+    }
+
+    #[test]
+    fn test_efficient_fibo() {
+        let k = 5;
+        let n = 10;
+        let circuit = EfficientFiboCircuit::<Fp> {
+            nrow: n as usize,
+            _marker: PhantomData,
+        };
+
+        let mut public_inputs = vec![Fp::from(1), Fp::from(1), Fp::from(fib(n))];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        public_inputs[2] += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_naive_fibo() {
+        let k = 5;
+        let n = 10;
+        let circuit = NaiveFiboCircuit::<Fp> {
+            nrow: n as usize,
+            _marker: PhantomData,
+        };
+
+        let public_inputs = vec![Fp::from(1), Fp::from(1), Fp::from(fib(n))];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_advice_column_count() {
+        use crate::analysis::rows::advice_rows;
+        use crate::chap_3::{circuit_1, circuit_2};
+
+        fn advice_columns<C: Circuit<Fp>>() -> usize {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            C::configure(&mut meta);
+            meta.num_advice_columns()
+        }
+
+        let k = 5;
+        let nrow = 10;
+        let naive = NaiveFiboCircuit::<Fp> {
+            nrow,
+            _marker: PhantomData,
+        };
+        let efficient = EfficientFiboCircuit::<Fp> {
+            nrow,
+            _marker: PhantomData,
+        };
+        let mut one_column = circuit_1::FiboCircuit::<Fp>::default();
+        one_column.nrow = nrow;
+        let mut two_per_row = circuit_2::FiboCircuit::<Fp>::default();
+        two_per_row.nrow = nrow;
+
+        // The copy constraint takes a column off the naive layout.
+        assert_eq!(advice_columns::<NaiveFiboCircuit<Fp>>(), 3);
+        assert_eq!(advice_columns::<EfficientFiboCircuit<Fp>>(), 2);
+        assert_eq!(advice_rows(k, &naive).unwrap(), nrow - 1);
+        assert_eq!(advice_rows(k, &efficient).unwrap(), nrow);
+
+        // Gates over several rows need no copies: one column as tall as the
+        // sequence, or two columns half as tall.
+        assert_eq!(advice_columns::<circuit_1::FiboCircuit<Fp>>(), 1);
+        assert_eq!(advice_rows(k, &one_column).unwrap(), nrow);
+        assert_eq!(advice_columns::<circuit_2::FiboCircuit<Fp>>(), 2);
+        assert_eq!(advice_rows(k, &two_per_row).unwrap(), nrow / 2 + 1);
+    }
+}
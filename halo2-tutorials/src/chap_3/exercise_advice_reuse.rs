@@ -0,0 +1,224 @@
+// Problem to prove: for private masks a_i ∈ {0, 1} and private values b_i,
+// the public outputs are out_i = a_i * b_i.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// Circuit design:
+/// |  a0    |  a1  | s_a | s_out |
+/// |--------|------|-----|-------|
+/// |  a_0   | b_0  |  1  |   0   |
+/// |  out_0 |      |  0  |   1   |
+/// |  a_1   | b_1  |  1  |   0   |
+/// |  out_1 |      |  0  |   1   |
+/// |  ...   | ...  |     |       |
+///
+/// The usual layout would put `a`, `b` and `out` side by side in three
+/// columns. Here `a0` means `a` on even rows and `out` on odd rows: a column
+/// holds whatever the gates say it holds, and the selectors say it per row.
+/// Each gate reads `a0` at `Rotation::cur()` under its own name, so "a is
+/// boolean" only ever sees masks and "out = a * b" only ever sees outputs,
+/// reaching one row up for the mask and value it was computed from.
+///
+/// The price is rows: two per output instead of one, trading height for
+/// width, which is the right trade when columns, not rows, are scarce.
+#[derive(Debug, Clone)]
+struct ReuseConfig {
+    value: Column<Advice>,
+    b: Column<Advice>,
+    s_a: Selector,
+    s_out: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+struct ReuseChip<F: Field> {
+    config: ReuseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ReuseChip<F> {
+    fn construct(config: ReuseConfig) -> Self {
+        ReuseChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ReuseConfig {
+        let value = meta.advice_column();
+        let b = meta.advice_column();
+        let s_a = meta.selector();
+        let s_out = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(value);
+        meta.enable_equality(instance);
+
+        meta.create_gate("a is boolean", |meta| {
+            let s = meta.query_selector(s_a);
+            let a = meta.query_advice(value, Rotation::cur());
+            Constraints::with_selector(s, vec![a.clone() * (Expression::Constant(F::ONE) - a)])
+        });
+
+        meta.create_gate("out = a * b", |meta| {
+            let s = meta.query_selector(s_out);
+            let out = meta.query_advice(value, Rotation::cur());
+            let a = meta.query_advice(value, Rotation::prev());
+            let b = meta.query_advice(b, Rotation::prev());
+            Constraints::with_selector(s, vec![a * b - out])
+        });
+
+        ReuseConfig {
+            value,
+            b,
+            s_a,
+            s_out,
+            instance,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rows: &[(Value<F>, Value<F>, Value<F>)],
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let outs = layouter.assign_region(
+            || "masked values",
+            |mut region| {
+                let mut outs = vec![];
+                for (i, (a, b, out)) in rows.iter().enumerate() {
+                    config.s_a.enable(&mut region, 2 * i)?;
+                    region.assign_advice(|| "a", config.value, 2 * i, || *a)?;
+                    region.assign_advice(|| "b", config.b, 2 * i, || *b)?;
+
+                    config.s_out.enable(&mut region, 2 * i + 1)?;
+                    outs.push(region.assign_advice(|| "out", config.value, 2 * i + 1, || *out)?);
+                }
+                Ok(outs)
+            },
+        )?;
+
+        for (row, out) in outs.iter().enumerate() {
+            layouter.constrain_instance(out.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// `out` is witnessed separately from `a * b`, so tests can break it.
+#[derive(Debug, Default)]
+struct ReuseCircuit<F: Field> {
+    rows: Vec<(Value<F>, Value<F>, Value<F>)>,
+}
+
+impl<F: Field> Circuit<F> for ReuseCircuit<F> {
+    type Config = ReuseConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ReuseCircuit {
+            rows: vec![(Value::unknown(), Value::unknown(), Value::unknown()); self.rows.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ReuseChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ReuseChip::<F>::construct(config);
+        chip.assign(layouter.namespace(|| "reuse"), &self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+    };
+
+    fn circuit(rows: &[(u64, u64, u64)]) -> ReuseCircuit<Fp> {
+        ReuseCircuit {
+            rows: rows
+                .iter()
+                .map(|(a, b, out)| {
+                    (
+                        Value::known(Fp::from(*a)),
+                        Value::known(Fp::from(*b)),
+                        Value::known(Fp::from(*out)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn public(rows: &[(u64, u64, u64)]) -> Vec<Vec<Fp>> {
+        vec![rows.iter().map(|(_, _, out)| Fp::from(*out)).collect()]
+    }
+
+    /// The failing constraints, as the gate's description and the row.
+    fn failures(prover: &MockProver<Fp>) -> Vec<(String, usize)> {
+        prover
+            .verify()
+            .unwrap_err()
+            .iter()
+            .filter_map(|e| match e {
+                VerifyFailure::ConstraintNotSatisfied {
+                    constraint,
+                    location: FailureLocation::InRegion { offset, .. },
+                    ..
+                } => Some((constraint.to_string(), *offset)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_advice_reuse() {
+        let k = 4;
+        let rows = [(1, 5, 5), (0, 7, 0), (1, 9, 9)];
+        let prover = MockProver::run(k, &circuit(&rows), public(&rows)).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_advice_reuse_column_count() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        ReuseChip::configure(&mut cs);
+        assert_eq!(cs.num_advice_columns(), 2);
+    }
+
+    #[test]
+    fn test_advice_reuse_gates_by_row() {
+        let k = 4;
+
+        // A mask of 2 with a matching out: only the even row of pair 1 fails.
+        let rows = [(1, 5, 5), (2, 7, 14), (1, 9, 9)];
+        let prover = MockProver::run(k, &circuit(&rows), public(&rows)).unwrap();
+        let failures = failures(&prover);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].0.contains("'a is boolean'"));
+        assert_eq!(failures[0].1, 2);
+
+        // A boolean mask with a wrong out: only the odd row of pair 1 fails.
+        let rows = [(1, 5, 5), (1, 7, 8), (1, 9, 9)];
+        let prover = MockProver::run(k, &circuit(&rows), public(&rows)).unwrap();
+        let failures = failures(&prover);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].0.contains("'out = a * b'"));
+        assert_eq!(failures[0].1, 3);
+    }
+}
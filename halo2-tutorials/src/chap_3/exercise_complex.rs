@@ -0,0 +1,406 @@
+/// chap3: complex numbers
+/// A pair of cells `(re, im)` is an element `re + im * i` of
+/// `F[i] / (i^2 + 1)`. `ComplexChip` adds and multiplies them a row at a
+/// time, with gates that have two outputs, one per part:
+///
+///   a + b         = (a.re + b.re,            a.im + b.im)
+///   a * b         = (a.re * b.re - a.im * b.im,  a.re * b.im + a.im * b.re)
+///   a * conj(b)   = (a.re * b.re + a.im * b.im,  a.im * b.re - a.re * b.im)
+///
+/// `conj_mul` saves computing `conj(b)` in a row of its own. With `a = b = z`
+/// it is the squared modulus `|z|^2 = re^2 + im^2`, with imaginary part zero,
+/// which the circuit below proves for a private `z`.
+///
+/// If `-1` is a square in `F` this "complex" ring is not a field: it splits
+/// into two copies of `F`, and some nonzero elements have no inverse. Adding
+/// and multiplying do not care.
+///
+/// | a0   | a1   | a2   | a3   | a4     | a5     | s_add | s_mul | s_conj_mul |
+/// |------|------|------|------|--------|--------|-------|-------|------------|
+/// | z.re | z.im |      |      |        |        |   0   |   0   |     0      |
+/// | a.re | a.im | b.re | b.im | out.re | out.im |   1   |   0   |     0      |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+/// `re + im * i`, as two assigned cells.
+#[derive(Debug, Clone)]
+pub struct Complex<F: Field> {
+    pub re: Number<F>,
+    pub im: Number<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplexConfig {
+    advice: [Column<Advice>; 6],
+    s_add: Selector,
+    s_mul: Selector,
+    s_conj_mul: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplexChip<F: Field> {
+    config: ComplexConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ComplexChip<F> {
+    pub fn construct(config: ComplexConfig) -> Self {
+        ComplexChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 6]) -> ComplexConfig {
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_add = meta.selector();
+        let s_mul = meta.selector();
+        let s_conj_mul = meta.selector();
+
+        meta.create_gate("complex", |meta| {
+            let [a_re, a_im, b_re, b_im, out_re, out_im] =
+                advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            let s_add = meta.query_selector(s_add);
+            let s_mul = meta.query_selector(s_mul);
+            let s_conj_mul = meta.query_selector(s_conj_mul);
+            vec![
+                s_add.clone() * (a_re.clone() + b_re.clone() - out_re.clone()),
+                s_add * (a_im.clone() + b_im.clone() - out_im.clone()),
+                s_mul.clone()
+                    * (a_re.clone() * b_re.clone() - a_im.clone() * b_im.clone() - out_re.clone()),
+                s_mul
+                    * (a_re.clone() * b_im.clone() + a_im.clone() * b_re.clone() - out_im.clone()),
+                s_conj_mul.clone()
+                    * (a_re.clone() * b_re.clone() + a_im.clone() * b_im.clone() - out_re),
+                s_conj_mul * (a_im * b_re - a_re * b_im - out_im),
+            ]
+        });
+
+        ComplexConfig {
+            advice,
+            s_add,
+            s_mul,
+            s_conj_mul,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        re: Value<F>,
+        im: Value<F>,
+    ) -> Result<Complex<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                let re = region.assign_advice(|| "re", self.config.advice[0], 0, || re)?;
+                let im = region.assign_advice(|| "im", self.config.advice[1], 0, || im)?;
+                Ok(Complex {
+                    re: Number(re),
+                    im: Number(im),
+                })
+            },
+        )
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Complex<F>,
+        b: Complex<F>,
+    ) -> Result<Complex<F>, Error> {
+        let out = Self::values(&a, &b).map(|(a_re, a_im, b_re, b_im)| (a_re + b_re, a_im + b_im));
+        self.binary_op(layouter, "complex add", self.config.s_add, a, b, out)
+    }
+
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Complex<F>,
+        b: Complex<F>,
+    ) -> Result<Complex<F>, Error> {
+        let out = Self::values(&a, &b)
+            .map(|(a_re, a_im, b_re, b_im)| (a_re * b_re - a_im * b_im, a_re * b_im + a_im * b_re));
+        self.binary_op(layouter, "complex mul", self.config.s_mul, a, b, out)
+    }
+
+    /// `a * conj(b)`.
+    pub fn conj_mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Complex<F>,
+        b: Complex<F>,
+    ) -> Result<Complex<F>, Error> {
+        let out = Self::values(&a, &b)
+            .map(|(a_re, a_im, b_re, b_im)| (a_re * b_re + a_im * b_im, a_im * b_re - a_re * b_im));
+        self.binary_op(
+            layouter,
+            "complex conj mul",
+            self.config.s_conj_mul,
+            a,
+            b,
+            out,
+        )
+    }
+
+    /// Constrain `z.re` to `instance[row]` and `z.im` to `instance[row + 1]`.
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        z: Complex<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(z.re.0.cell(), instance, row)?;
+        layouter.constrain_instance(z.im.0.cell(), instance, row + 1)
+    }
+
+    fn values(a: &Complex<F>, b: &Complex<F>) -> Value<(F, F, F, F)> {
+        let a = a.re.0.value().copied().zip(a.im.0.value().copied());
+        let b = b.re.0.value().copied().zip(b.im.0.value().copied());
+        a.zip(b)
+            .map(|((a_re, a_im), (b_re, b_im))| (a_re, a_im, b_re, b_im))
+    }
+
+    fn binary_op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &'static str,
+        selector: Selector,
+        a: Complex<F>,
+        b: Complex<F>,
+        out: Value<(F, F)>,
+    ) -> Result<Complex<F>, Error> {
+        let [a_re, a_im, b_re, b_im, out_re, out_im] = self.config.advice;
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                a.re.0.copy_advice(|| "a.re", &mut region, a_re, 0)?;
+                a.im.0.copy_advice(|| "a.im", &mut region, a_im, 0)?;
+                b.re.0.copy_advice(|| "b.re", &mut region, b_re, 0)?;
+                b.im.0.copy_advice(|| "b.im", &mut region, b_im, 0)?;
+                let re = region.assign_advice(|| "out.re", out_re, 0, || out.map(|out| out.0))?;
+                let im = region.assign_advice(|| "out.im", out_im, 0, || out.map(|out| out.1))?;
+                Ok(Complex {
+                    re: Number(re),
+                    im: Number(im),
+                })
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ModulusConfig {
+    complex: ComplexConfig,
+    instance: Column<Instance>,
+}
+
+/// `z * conj(z) = instance[0] + instance[1] * i` for a private `z`.
+#[derive(Default)]
+struct ModulusCircuit<F: Field> {
+    re: Value<F>,
+    im: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for ModulusCircuit<F> {
+    type Config = ModulusConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 6].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ModulusConfig {
+            complex: ComplexChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ComplexChip::construct(config.complex);
+        let z = chip.load_private(layouter.namespace(|| "z"), self.re, self.im)?;
+        let modulus = chip.conj_mul(layouter.namespace(|| "z * conj(z)"), z.clone(), z)?;
+        chip.expose_public(layouter, modulus, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    /// Off-circuit complex numbers, as pairs.
+    fn add(a: (Fp, Fp), b: (Fp, Fp)) -> (Fp, Fp) {
+        (a.0 + b.0, a.1 + b.1)
+    }
+
+    fn mul(a: (Fp, Fp), b: (Fp, Fp)) -> (Fp, Fp) {
+        (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+    }
+
+    fn conj(a: (Fp, Fp)) -> (Fp, Fp) {
+        (a.0, -a.1)
+    }
+
+    fn complex(re: i64, im: i64) -> (Fp, Fp) {
+        let from = |v: i64| {
+            if v < 0 {
+                -Fp::from(v.unsigned_abs())
+            } else {
+                Fp::from(v as u64)
+            }
+        };
+        (from(re), from(im))
+    }
+
+    /// `a + b`, `a * b` and `a * conj(b)`, exposed in that order. With
+    /// `forge`, the product row is assigned by hand with `forge` as output.
+    #[derive(Default)]
+    struct ArithmeticCircuit {
+        a: (Fp, Fp),
+        b: (Fp, Fp),
+        forge: Option<(Fp, Fp)>,
+    }
+
+    impl Circuit<Fp> for ArithmeticCircuit {
+        type Config = ModulusConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            ModulusCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = ComplexChip::construct(config.complex.clone());
+            let a = chip.load_private(
+                layouter.namespace(|| "a"),
+                Value::known(self.a.0),
+                Value::known(self.a.1),
+            )?;
+            let b = chip.load_private(
+                layouter.namespace(|| "b"),
+                Value::known(self.b.0),
+                Value::known(self.b.1),
+            )?;
+            let sum = chip.add(layouter.namespace(|| "a + b"), a.clone(), b.clone())?;
+            let product = match self.forge {
+                None => chip.mul(layouter.namespace(|| "a * b"), a.clone(), b.clone())?,
+                Some(forged) => chip.binary_op(
+                    layouter.namespace(|| "a * b"),
+                    "forged mul",
+                    config.complex.s_mul,
+                    a.clone(),
+                    b.clone(),
+                    Value::known(forged),
+                )?,
+            };
+            let conj_product = chip.conj_mul(layouter.namespace(|| "a * conj(b)"), a, b)?;
+            chip.expose_public(layouter.namespace(|| "sum"), sum, config.instance, 0)?;
+            chip.expose_public(
+                layouter.namespace(|| "product"),
+                product,
+                config.instance,
+                2,
+            )?;
+            chip.expose_public(layouter, conj_product, config.instance, 4)
+        }
+    }
+
+    fn verify_arithmetic(a: (Fp, Fp), b: (Fp, Fp), forge: Option<(Fp, Fp)>) -> bool {
+        let circuit = ArithmeticCircuit { a, b, forge };
+        let product = forge.unwrap_or_else(|| mul(a, b));
+        let (s, p, c) = (add(a, b), product, mul(a, conj(b)));
+        let public = vec![s.0, s.1, p.0, p.1, c.0, c.1];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn verify_modulus(z: (Fp, Fp), public: (Fp, Fp)) -> bool {
+        let circuit = ModulusCircuit {
+            re: Value::known(z.0),
+            im: Value::known(z.1),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![public.0, public.1]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_complex_arithmetic() {
+        for (a, b) in [
+            (complex(3, 4), complex(1, -2)),
+            (complex(-5, 7), complex(-2, -9)),
+            (complex(0, 1), complex(0, 1)),
+        ] {
+            assert!(verify_arithmetic(a, b, None));
+        }
+        // i * i = -1.
+        assert_eq!(mul(complex(0, 1), complex(0, 1)), complex(-1, 0));
+    }
+
+    #[test]
+    fn test_complex_modulus() {
+        // |3 + 4i|^2 = 25
+        assert!(verify_modulus(complex(3, 4), complex(25, 0)));
+        assert!(verify_modulus(complex(-3, 4), complex(25, 0)));
+        assert!(!verify_modulus(complex(3, 4), complex(5, 0)));
+        // The modulus is real: no imaginary part passes.
+        assert!(!verify_modulus(complex(3, 4), complex(25, 1)));
+    }
+
+    #[test]
+    fn test_complex_real_and_imaginary() {
+        // Purely real numbers multiply like field elements.
+        let (a, b) = (complex(6, 0), complex(-7, 0));
+        assert_eq!(mul(a, b), complex(-42, 0));
+        assert!(verify_arithmetic(a, b, None));
+        assert!(verify_modulus(complex(-7, 0), complex(49, 0)));
+
+        // Purely imaginary ones multiply to a real number, with a sign flip.
+        let (a, b) = (complex(0, 6), complex(0, 7));
+        assert_eq!(mul(a, b), complex(-42, 0));
+        assert!(verify_arithmetic(a, b, None));
+        assert!(verify_modulus(complex(0, -7), complex(49, 0)));
+    }
+
+    #[test]
+    fn test_complex_forged_product() {
+        let (a, b) = (complex(3, 4), complex(1, -2));
+        assert!(verify_arithmetic(a, b, Some(mul(a, b))));
+        // (a.re * b.re + a.im * b.im, ...): the sign of i^2 forgotten.
+        let forged = (a.0 * b.0 + a.1 * b.1, a.0 * b.1 + a.1 * b.0);
+        assert!(!verify_arithmetic(a, b, Some(forged)));
+        // Parts swapped.
+        let product = mul(a, b);
+        assert!(!verify_arithmetic(a, b, Some((product.1, product.0))));
+    }
+}
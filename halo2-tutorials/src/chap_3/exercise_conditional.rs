@@ -0,0 +1,220 @@
+// Problem to prove: out = if is_doubling { 2 * x } else { x + y }, with the
+// flag a private witness, and out public.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Expression, Instance,
+        Selector,
+    },
+    poly::Rotation,
+};
+
+/// Circuit design:
+/// |  a0   |  a1   |   a2   |  a3   | s_cond | instance |
+/// |-------|-------|--------|-------|--------|----------|
+/// |   x   |   y   |  flag  |  out  |   1    |   out    |
+///
+/// In `conditional_gate` the branch is a selector, a fixed column: it is
+/// chosen when the circuit is built, the same for every proof. Here the
+/// branch depends on a witness, so it has to be an advice cell, `flag`, and
+/// both branches live in one gate, each multiplied by the flag value that
+/// switches it on:
+///
+///   flag * (1 - flag)           = 0    flag is 0 or 1
+///   flag * (out - 2 * x)        = 0    if flag { out = 2 * x }
+///   (1 - flag) * (out - x - y)  = 0    else { out = x + y }
+///
+/// With `flag = 1` the third constraint is `0 = 0`, with `flag = 0` the
+/// second one is. The booleanity constraint is what makes this an if-else:
+/// for any other flag both factors are nonzero, and both branches are
+/// enforced at once. Each constraint goes up a degree, to 3 with the
+/// selector, but the conditional takes no rows beyond the one it is on.
+#[derive(Debug, Clone)]
+pub(crate) struct ConditionalConfig {
+    advice: [Column<Advice>; 4],
+    instance: Column<Instance>,
+    s_cond: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct ConditionalChip<F: Field> {
+    config: ConditionalConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ConditionalChip<F> {
+    fn construct(config: ConditionalConfig) -> Self {
+        ConditionalChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ConditionalConfig {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(advice[3]);
+        meta.enable_equality(instance);
+        let s_cond = meta.selector();
+
+        meta.create_gate("if is_doubling", |meta| {
+            let s = meta.query_selector(s_cond);
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            let flag = meta.query_advice(advice[2], Rotation::cur());
+            let out = meta.query_advice(advice[3], Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            let not_flag = one - flag.clone();
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("flag is boolean", flag.clone() * not_flag.clone()),
+                    (
+                        "then out = 2x",
+                        flag * (out.clone() - x.clone() - x.clone()),
+                    ),
+                    ("else out = x + y", not_flag * (out - x - y)),
+                ],
+            )
+        });
+
+        ConditionalConfig {
+            advice,
+            instance,
+            s_cond,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Value<F>,
+        y: Value<F>,
+        flag: Value<F>,
+        out: Value<F>,
+    ) -> Result<(), Error> {
+        let out = layouter.assign_region(
+            || "conditional",
+            |mut region| {
+                let [x_col, y_col, flag_col, out_col] = self.config.advice;
+                self.config.s_cond.enable(&mut region, 0)?;
+                region.assign_advice(|| "x", x_col, 0, || x)?;
+                region.assign_advice(|| "y", y_col, 0, || y)?;
+                region.assign_advice(|| "flag", flag_col, 0, || flag)?;
+                region.assign_advice(|| "out", out_col, 0, || out)
+            },
+        )?;
+        layouter.constrain_instance(out.cell(), self.config.instance, 0)
+    }
+}
+
+/// `out` is witnessed as given, not computed: the tests put in outputs of
+/// the wrong branch to see the gate reject them.
+#[derive(Default)]
+pub(crate) struct ConditionalCircuit<F: Field> {
+    x: Value<F>,
+    y: Value<F>,
+    is_doubling: Value<F>,
+    out: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for ConditionalCircuit<F> {
+    type Config = ConditionalConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ConditionalChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ConditionalChip::<F>::construct(config);
+        chip.assign(
+            layouter.namespace(|| "if-else"),
+            self.x,
+            self.y,
+            self.is_doubling,
+            self.out,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    fn circuit(x: u64, y: u64, flag: u64, out: u64) -> ConditionalCircuit<Fp> {
+        ConditionalCircuit {
+            x: Value::known(Fp::from(x)),
+            y: Value::known(Fp::from(y)),
+            is_doubling: Value::known(Fp::from(flag)),
+            out: Value::known(Fp::from(out)),
+        }
+    }
+
+    fn verify(x: u64, y: u64, flag: u64, out: u64) -> bool {
+        let prover =
+            MockProver::run(K, &circuit(x, y, flag, out), vec![vec![Fp::from(out)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_conditional_branches() {
+        // then: 2 * 5
+        assert!(verify(5, 7, 1, 10));
+        // else: 5 + 7
+        assert!(verify(5, 7, 0, 12));
+    }
+
+    #[test]
+    fn test_conditional_wrong_branch() {
+        // Each output satisfies the other branch, not the flagged one.
+        assert!(!verify(5, 7, 1, 12));
+        assert!(!verify(5, 7, 0, 10));
+    }
+
+    #[test]
+    fn test_conditional_non_boolean_flag() {
+        // With x = y both branches agree, and any flag satisfies them: only
+        // the booleanity constraint rejects a flag of 2.
+        assert!(verify(3, 3, 1, 6));
+        assert!(verify(3, 3, 0, 6));
+        assert!(!verify(3, 3, 2, 6));
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_chap_3_exercise_conditional() {
+        let circuit = circuit(5, 7, 1, 10);
+        // Both branches, and the flag, fit in one row: no extra rows for the
+        // conditional.
+        use plotters::prelude::*;
+        let root = BitMapBackend::new(
+            "./circuit_layouter_plots/chap_3_exercise_conditional.png",
+            (1024, 768),
+        )
+        .into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("If-else in one gate", ("sans-serif", 60))
+            .unwrap();
+        halo2_proofs::dev::CircuitLayout::default()
+            .show_labels(true)
+            .render(K, &circuit, &root)
+            .unwrap();
+    }
+}
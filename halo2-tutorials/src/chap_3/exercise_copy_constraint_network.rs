@@ -0,0 +1,334 @@
+// Problem to prove: out = (x + y + u) * ((x + y) * v) + x for private x, y,
+// u, v and a public out, computed in five regions wired by copy constraints.
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// Circuit design, one row per region:
+/// | a0 | a1 | a2  | s_add | s_mul |
+/// |----|----|-----|-------|-------|
+/// | x  | y  | s   |   1   |   0   |  A: s = x + y
+/// | s  | u  | l   |   1   |   0   |  B: l = s + u
+/// | s  | v  | r   |   0   |   1   |  C: r = s * v
+/// | l  | r  | d   |   0   |   1   |  D: d = l * r
+/// | d  | x  | out |   1   |   0   |  E: out = d + x
+///
+/// The regions form a diamond, A feeding B and C and both feeding D, with a
+/// tail E that also takes `x` straight from A:
+///
+///        A
+///       / \
+///      B   C
+///       \ /
+///        D
+///        |
+///        E
+///
+/// Six copy constraints carry the values along the edges:
+///
+/// | link | from  | to    |
+/// |------|-------|-------|
+/// |  0   | A.a2  | B.a0  |   s
+/// |  1   | A.a2  | C.a0  |   s
+/// |  2   | B.a2  | D.a0  |   l
+/// |  3   | C.a2  | D.a1  |   r
+/// |  4   | D.a2  | E.a0  |   d
+/// |  5   | A.a0  | E.a1  |   x
+///
+/// The permutation argument does not keep the links as pairs. It merges the
+/// cells they connect into cycles, one per value, and checks that every cell
+/// of a cycle holds the same value:
+///
+///   s:   (A.a2  B.a0  C.a0)
+///   l:   (B.a2  D.a0)
+///   r:   (C.a2  D.a1)
+///   d:   (D.a2  E.a0)
+///   x:   (A.a0  E.a1)
+///   out: (E.a2  instance[0])
+///
+/// Links 0 and 1 share a cell and so a cycle: six links, five cycles in the
+/// advice columns. The `dev-graph` plot below draws them.
+#[derive(Debug, Clone)]
+pub(crate) struct NetworkConfig {
+    advice: [Column<Advice>; 3],
+    s_add: Selector,
+    s_mul: Selector,
+    instance: Column<Instance>,
+}
+
+/// The private inputs. For the tests, link `broken` copies a value off by
+/// one, and link `dropped` has no copy constraint.
+#[derive(Default)]
+pub(crate) struct NetworkCircuit<F: Field> {
+    x: Value<F>,
+    y: Value<F>,
+    u: Value<F>,
+    v: Value<F>,
+    broken: Option<usize>,
+    dropped: Option<usize>,
+}
+
+impl<F: Field> NetworkCircuit<F> {
+    /// Copy `from` into `(col, 0)` of `region` as link `link`.
+    fn link(
+        &self,
+        region: &mut Region<'_, F>,
+        link: usize,
+        from: &AssignedCell<F, F>,
+        col: Column<Advice>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut value = from.value().copied();
+        if self.broken == Some(link) {
+            value = value + Value::known(F::ONE);
+        }
+        let to = region.assign_advice(|| format!("link {}", link), col, 0, || value)?;
+        if self.dropped != Some(link) {
+            region.constrain_equal(from.cell(), to.cell())?;
+        }
+        Ok(to)
+    }
+
+    /// One row `a2 = a0 + a1` or `a2 = a0 * a1` of the inputs already in
+    /// `a0` and `a1`.
+    fn output(
+        config: &NetworkConfig,
+        region: &mut Region<'_, F>,
+        mul: bool,
+        lhs: &AssignedCell<F, F>,
+        rhs: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (lhs, rhs) = (lhs.value().copied(), rhs.value().copied());
+        let value = if mul {
+            config.s_mul.enable(region, 0)?;
+            lhs * rhs
+        } else {
+            config.s_add.enable(region, 0)?;
+            lhs + rhs
+        };
+        region.assign_advice(|| "out", config.advice[2], 0, || value)
+    }
+}
+
+impl<F: Field> Circuit<F> for NetworkCircuit<F> {
+    type Config = NetworkConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        NetworkCircuit {
+            broken: self.broken,
+            dropped: self.dropped,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        meta.enable_equality(instance);
+        let s_add = meta.selector();
+        let s_mul = meta.selector();
+        let [a, b, c] = advice;
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            Constraints::with_selector(s, vec![c - a - b])
+        });
+
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(s_mul);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            Constraints::with_selector(s, vec![c - a * b])
+        });
+
+        NetworkConfig {
+            advice,
+            s_add,
+            s_mul,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let [a0, a1, _] = config.advice;
+
+        let (x, s) = layouter.assign_region(
+            || "A: s = x + y",
+            |mut region| {
+                let x = region.assign_advice(|| "x", a0, 0, || self.x)?;
+                let y = region.assign_advice(|| "y", a1, 0, || self.y)?;
+                let s = Self::output(&config, &mut region, false, &x, &y)?;
+                Ok((x, s))
+            },
+        )?;
+
+        let l = layouter.assign_region(
+            || "B: l = s + u",
+            |mut region| {
+                let s = self.link(&mut region, 0, &s, a0)?;
+                let u = region.assign_advice(|| "u", a1, 0, || self.u)?;
+                Self::output(&config, &mut region, false, &s, &u)
+            },
+        )?;
+
+        let r = layouter.assign_region(
+            || "C: r = s * v",
+            |mut region| {
+                let s = self.link(&mut region, 1, &s, a0)?;
+                let v = region.assign_advice(|| "v", a1, 0, || self.v)?;
+                Self::output(&config, &mut region, true, &s, &v)
+            },
+        )?;
+
+        let d = layouter.assign_region(
+            || "D: d = l * r",
+            |mut region| {
+                let l = self.link(&mut region, 2, &l, a0)?;
+                let r = self.link(&mut region, 3, &r, a1)?;
+                Self::output(&config, &mut region, true, &l, &r)
+            },
+        )?;
+
+        let out = layouter.assign_region(
+            || "E: out = d + x",
+            |mut region| {
+                let d = self.link(&mut region, 4, &d, a0)?;
+                let x = self.link(&mut region, 5, &x, a1)?;
+                Self::output(&config, &mut region, false, &d, &x)
+            },
+        )?;
+
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        dev::{metadata, FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+    };
+
+    const K: u32 = 4;
+    const INPUTS: [u64; 4] = [2, 3, 1, 2];
+
+    /// The destination of each link: its region, and its advice column.
+    const LINKS: [(&str, usize); 6] = [
+        ("B: l = s + u", 0),
+        ("C: r = s * v", 0),
+        ("D: d = l * r", 0),
+        ("D: d = l * r", 1),
+        ("E: out = d + x", 0),
+        ("E: out = d + x", 1),
+    ];
+
+    /// `out` natively, with link `broken` adding one as in the circuit.
+    fn network([x, y, u, v]: [u64; 4], broken: Option<usize>) -> u64 {
+        let copy = |link: usize, value: u64| value + (broken == Some(link)) as u64;
+        let s = x + y;
+        let l = copy(0, s) + u;
+        let r = copy(1, s) * v;
+        let d = copy(2, l) * copy(3, r);
+        copy(4, d) + copy(5, x)
+    }
+
+    fn prover(broken: Option<usize>, dropped: Option<usize>) -> MockProver<Fp> {
+        let [x, y, u, v] = INPUTS.map(|i| Value::known(Fp::from(i)));
+        let circuit = NetworkCircuit {
+            x,
+            y,
+            u,
+            v,
+            broken,
+            dropped,
+        };
+        // The public output follows the broken link, so that only the link
+        // itself can fail.
+        let out = Fp::from(network(INPUTS, broken));
+        MockProver::run(K, &circuit, vec![vec![out]]).unwrap()
+    }
+
+    #[test]
+    fn test_copy_constraint_network() {
+        // (2 + 3 + 1) * ((2 + 3) * 2) + 2
+        assert_eq!(network(INPUTS, None), 62);
+        prover(None, None).assert_satisfied();
+        // Dropping a link changes nothing for an honest prover.
+        for link in 0..LINKS.len() {
+            prover(None, Some(link)).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_copy_constraint_network_broken_link() {
+        for (link, (region, col)) in LINKS.iter().enumerate() {
+            // Every gate still holds: only the equality of link `link` fails,
+            // in the cycle of the value it copies.
+            let errors = prover(Some(link), None).verify().unwrap_err();
+            let cells: Vec<(metadata::Column, String)> = errors
+                .iter()
+                .map(|e| match e {
+                    VerifyFailure::Permutation {
+                        column,
+                        location: location @ FailureLocation::InRegion { .. },
+                    } => (column.clone(), location.to_string()),
+                    e => panic!("link {}: unexpected failure {}", link, e),
+                })
+                .collect();
+            let column = metadata::Column::from((Any::Advice, *col));
+            assert!(
+                cells
+                    .iter()
+                    .any(|(c, location)| *c == column && location.contains(region)),
+                "link {} did not fail at its destination: {:?}",
+                link,
+                cells
+            );
+
+            // Without its copy constraint, the same prover proves the wrong
+            // output.
+            assert!(prover(Some(link), Some(link)).verify().is_ok());
+            assert_ne!(network(INPUTS, Some(link)), network(INPUTS, None));
+        }
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_chap_3_copy_constraint_network() {
+        let circuit = NetworkCircuit::<Fp>::default();
+        use plotters::prelude::*;
+        let root = BitMapBackend::new(
+            "./circuit_layouter_plots/chap_3_copy_constraint_network.png",
+            (1024, 768),
+        )
+        .into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Copy Constraint Network", ("sans-serif", 60))
+            .unwrap();
+        halo2_proofs::dev::CircuitLayout::default()
+            .show_labels(true)
+            // Draw a line for every copy constraint, and mark the cells in
+            // the permutation.
+            .show_equality_constraints(true)
+            .mark_equality_cells(true)
+            .render(K, &circuit, &root)
+            .unwrap();
+    }
+}
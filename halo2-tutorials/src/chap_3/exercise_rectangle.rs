@@ -0,0 +1,182 @@
+// Problem to prove: a private point (x, y) lies in the public rectangle
+// [x_min, x_max] x [y_min, y_max], all coordinates bytes, with the bounds
+// strict or inclusive.
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    lt::{LtChip, LtConfig},
+    Number,
+};
+
+/// Circuit design:
+/// | a0  | a1  | a2 | a3   | q_byte | q_lt | constant | instance |
+/// |-----|-----|----|------|--------|------|----------|----------|
+/// | x, y, bounds        |        |      |          |  x_min   |
+/// | v   |     |    |      |   1    |      |          |  x_max   |   per coordinate
+/// | lo  | hi  | lt | diff |        |  1   |          |  y_min   |   per comparison
+/// | lt = expected       |        |      |   0, 1   |  y_max   |
+///
+/// `LtChip` compares bytes through a lookup into a 256-row table. Four
+/// comparisons, and the byte checks of the six inputs, all need that table;
+/// a naive circuit would configure a table per comparator and load each. But
+/// a lookup argument is about the table column, not the chip that uses it:
+/// `LtChip::configure` takes the `ByteConfig` whose table it looks up into,
+/// so there is one `TableColumn`, loaded once, and every `q_lt` and `q_byte`
+/// row of the circuit looks into it. One `LtChip` serves all four
+/// comparisons, as four regions.
+///
+/// The two modes differ in which comparisons are asserted:
+///
+///   strict:     x_min < x,       x < x_max      (both lt = 1)
+///   inclusive:  not x < x_min,   not x_max < x  (both lt = 0)
+///
+/// and the same for y.
+#[derive(Debug, Clone)]
+pub(crate) struct RectangleConfig {
+    arith: ArithConfig,
+    byte: ByteConfig,
+    lt: LtConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub(crate) struct RectangleCircuit<F: PrimeField, const STRICT: bool> {
+    x: Value<F>,
+    y: Value<F>,
+}
+
+impl<F: PrimeField, const STRICT: bool> RectangleCircuit<F, STRICT> {
+    /// `lo <= v <= hi`, or `lo < v < hi` when `STRICT`.
+    fn check_between(
+        config: &RectangleConfig,
+        mut layouter: impl Layouter<F>,
+        lo: Number<F>,
+        v: Number<F>,
+        hi: Number<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith.clone());
+        let lt = LtChip::construct(config.lt.clone());
+        let (below, above, expected) = if STRICT {
+            let below = lt.less_than(layouter.namespace(|| "lo < v"), lo, v.clone())?;
+            let above = lt.less_than(layouter.namespace(|| "v < hi"), v, hi)?;
+            (below, above, F::ONE)
+        } else {
+            let below = lt.less_than(layouter.namespace(|| "v < lo"), v.clone(), lo)?;
+            let above = lt.less_than(layouter.namespace(|| "hi < v"), hi, v)?;
+            (below, above, F::ZERO)
+        };
+        let expected = arith.load_constant(layouter.namespace(|| "expected"), expected)?;
+        arith.assert_equal(layouter.namespace(|| "lower"), below, expected.clone())?;
+        arith.assert_equal(layouter.namespace(|| "upper"), above, expected)
+    }
+}
+
+impl<F: PrimeField, const STRICT: bool> Circuit<F> for RectangleCircuit<F, STRICT> {
+    type Config = RectangleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let byte = ByteChip::configure(meta, advice[0]);
+        // The comparator looks up into the byte chip's table, not its own.
+        let lt = LtChip::configure(meta, advice, &byte);
+        RectangleConfig {
+            arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+            byte,
+            lt,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith.clone());
+        let byte = ByteChip::construct(config.byte.clone());
+        // Loaded once, for every lookup below.
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        let x = arith.load_private(layouter.namespace(|| "x"), self.x)?;
+        let y = arith.load_private(layouter.namespace(|| "y"), self.y)?;
+        let [x_min, x_max, y_min, y_max] = [0, 1, 2, 3]
+            .map(|row| arith.load_instance(layouter.namespace(|| "bound"), config.instance, row));
+        let (x_min, x_max, y_min, y_max) = (x_min?, x_max?, y_min?, y_max?);
+
+        // LtChip needs bytes.
+        for v in [&x, &y, &x_min, &x_max, &y_min, &y_max] {
+            byte.check_byte(layouter.namespace(|| "byte"), v.clone())?;
+        }
+
+        Self::check_between(&config, layouter.namespace(|| "x"), x_min, x, x_max)?;
+        Self::check_between(&config, layouter.namespace(|| "y"), y_min, y, y_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    /// The rectangle [10, 20] x [30, 40].
+    fn verify<const STRICT: bool>(x: u64, y: u64) -> bool {
+        let circuit = RectangleCircuit::<Fp, STRICT> {
+            x: Value::known(Fp::from(x)),
+            y: Value::known(Fp::from(y)),
+        };
+        let public = [10, 20, 30, 40].map(Fp::from).to_vec();
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_rectangle_interior() {
+        assert!(verify::<true>(15, 35));
+        assert!(verify::<false>(15, 35));
+        assert!(verify::<true>(11, 39));
+    }
+
+    #[test]
+    fn test_rectangle_edges() {
+        for (x, y) in [(10, 35), (20, 35), (15, 30), (15, 40)] {
+            assert!(verify::<false>(x, y), "({}, {}) is on an edge", x, y);
+            assert!(!verify::<true>(x, y), "({}, {}) is on an edge", x, y);
+        }
+    }
+
+    #[test]
+    fn test_rectangle_corners() {
+        for (x, y) in [(10, 30), (10, 40), (20, 30), (20, 40)] {
+            assert!(verify::<false>(x, y), "({}, {}) is a corner", x, y);
+            assert!(!verify::<true>(x, y), "({}, {}) is a corner", x, y);
+        }
+    }
+
+    #[test]
+    fn test_rectangle_exterior() {
+        for (x, y) in [(9, 35), (21, 35), (15, 29), (15, 41)] {
+            assert!(!verify::<false>(x, y), "({}, {}) is outside", x, y);
+            assert!(!verify::<true>(x, y), "({}, {}) is outside", x, y);
+        }
+        // 271 = 15 + 256 is no byte.
+        assert!(!verify::<false>(271, 35));
+    }
+}
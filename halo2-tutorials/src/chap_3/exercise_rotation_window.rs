@@ -0,0 +1,186 @@
+// Problem to prove: for private values v_0..v_{N-1} and fixed weights
+// w_0..w_{N-1}, every 3-row window sums to the public
+// y_i = w_i * v_i + w_{i+1} * v_{i+1} + w_{i+2} * v_{i+2}.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// The gate on row `i` reaches two rows down with `Rotation::custom(2)`, so a
+/// whole window is read without copying any value:
+///
+/// | a0      | f0      | a1      | selector|
+/// |---------|---------|---------|--------|
+/// | v_0     | w_0     | y_0     |    1   |
+/// | v_1     | w_1     | y_1     |    1   |
+/// |  ...    |  ...    |  ...    |        |
+/// | v_{N-3} | w_{N-3} | y_{N-3} |    1   |
+/// | v_{N-2} | w_{N-2} |         |    0   |
+/// | v_{N-1} | w_{N-1} |         |    0   |
+///
+/// The last two rows only feed the windows above them, so their selector is
+/// off: enabling it would read past the end of the region.
+#[derive(Debug, Clone)]
+struct WindowConfig {
+    value: Column<Advice>,
+    weight: Column<Fixed>,
+    sum: Column<Advice>,
+    selector: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+struct WindowChip<F: Field> {
+    config: WindowConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> WindowChip<F> {
+    fn construct(config: WindowConfig) -> Self {
+        WindowChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WindowConfig {
+        let value = meta.advice_column();
+        let weight = meta.fixed_column();
+        let sum = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(sum);
+        meta.enable_equality(instance);
+
+        meta.create_gate("weighted window", |meta| {
+            let s = meta.query_selector(selector);
+            let [t0, t1, t2] = [Rotation::cur(), Rotation::next(), Rotation::custom(2)]
+                .map(|rot| meta.query_advice(value, rot) * meta.query_fixed(weight, rot));
+            let y = meta.query_advice(sum, Rotation::cur());
+            Constraints::with_selector(s, vec![t0 + t1 + t2 - y])
+        });
+
+        WindowConfig {
+            value,
+            weight,
+            sum,
+            selector,
+            instance,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<F>],
+        weights: &[F],
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let sums = layouter.assign_region(
+            || "weighted windows",
+            |mut region| {
+                for (row, (v, w)) in values.iter().zip(weights).enumerate() {
+                    region.assign_advice(|| "v", config.value, row, || *v)?;
+                    region.assign_fixed(|| "w", config.weight, row, || Value::known(*w))?;
+                }
+                let mut sums = vec![];
+                for row in 0..values.len() - 2 {
+                    config.selector.enable(&mut region, row)?;
+                    let y = (0..3).fold(Value::known(F::ZERO), |acc, j| {
+                        acc + values[row + j] * Value::known(weights[row + j])
+                    });
+                    sums.push(region.assign_advice(|| "y", config.sum, row, || y)?);
+                }
+                Ok(sums)
+            },
+        )?;
+
+        for (row, y) in sums.iter().enumerate() {
+            layouter.constrain_instance(y.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct WindowCircuit<F: Field> {
+    values: Vec<Value<F>>,
+    weights: Vec<F>,
+}
+
+impl<F: Field> Circuit<F> for WindowCircuit<F> {
+    type Config = WindowConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        WindowCircuit {
+            values: vec![Value::unknown(); self.values.len()],
+            weights: self.weights.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        WindowChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        assert_eq!(self.values.len(), self.weights.len());
+        assert!(self.values.len() >= 3, "need at least one full window");
+        let chip = WindowChip::<F>::construct(config);
+        chip.assign(
+            layouter.namespace(|| "windows"),
+            &self.values,
+            &self.weights,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn circuit(values: &[u64]) -> WindowCircuit<Fp> {
+        WindowCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+            weights: (1..=6).map(Fp::from).collect(),
+        }
+    }
+
+    #[test]
+    fn test_weighted_window() {
+        let k = 4;
+        // w = [1, 2, 3, 4, 5, 6], v = [1, 1, 2, 3, 5, 8]
+        //   y_0 = 1*1 + 2*1 + 3*2 = 9, and so on.
+        let circuit = circuit(&[1, 1, 2, 3, 5, 8]);
+        let public_inputs = vec![Fp::from(9), Fp::from(20), Fp::from(43), Fp::from(85)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_off_by_one_row() {
+        let k = 4;
+        let honest = circuit(&[1, 1, 2, 3, 5, 8]);
+
+        // Sums read one row too late, as a window at rows i+1..i+3 would give.
+        let public_inputs = vec![Fp::from(20), Fp::from(43), Fp::from(85), Fp::from(0)];
+        let prover = MockProver::run(k, &honest, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // Values shifted down by one row against the fixed weights.
+        let shifted = circuit(&[0, 1, 1, 2, 3, 5]);
+        let public_inputs = vec![Fp::from(9), Fp::from(20), Fp::from(43), Fp::from(85)];
+        let prover = MockProver::run(k, &shifted, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
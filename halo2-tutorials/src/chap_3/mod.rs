@@ -1,5 +1,14 @@
-mod circuit_1;
-mod circuit_2;
+pub(crate) mod circuit_1;
+pub(crate) mod circuit_2;
+mod conditional_gate;
+mod exercise_1_optimised;
+mod exercise_advice_reuse;
+pub(crate) mod exercise_conditional;
+pub(crate) mod exercise_copy_constraint_network;
+pub(crate) mod exercise_complex;
+pub(crate) mod exercise_rectangle;
+mod exercise_rotation_window;
+mod multiple_regions;
 
 #[cfg(feature = "chap_3_exercise_6")]
-mod exercise_6;
+pub(crate) mod exercise_6;
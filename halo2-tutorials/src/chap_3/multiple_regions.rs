@@ -0,0 +1,366 @@
+// Problem to prove: out = 2 * (x^2 + 2) for the public x and out, computed
+// in three regions with the value threaded through by copy constraints.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// Circuit design, as `SimpleFloorPlanner` lays it out:
+/// | a0      | a1  | s_square | s_inc | s_double |
+/// |---------|-----|----------|-------|----------|
+/// | x       |     |    1     |   0   |    0     |  region 0 "square"
+/// | y = x^2 |     |    0     |   0   |    0     |
+/// | y       |     |    0     |   1   |    0     |  region 1 "add two"
+/// | y + 1   |     |    0     |   1   |    0     |
+/// | z       |     |    0     |   0   |    0     |
+/// | z       | out |    0     |   0   |    1     |  region 2 "double"
+///
+/// Each `assign_region` call numbers its rows from 0; the floor planner then
+/// places the region at the first row where all the columns it uses are
+/// still free. Every region here uses `a0`, so they stack one after the
+/// other and the circuit is as tall as the three of them together.
+///
+/// Nothing but copy constraints connects them: a gate only sees the rows of
+/// the region it is enabled in. `y` and `z` are each assigned twice, once as
+/// a region's output and once as the next region's input, and
+/// `copy_advice` ties the two cells together.
+///
+/// halo2's `Region` does not know where it ends up, nor its own name, so
+/// with `TRACE_REGIONS` set in the environment each region prints the name it
+/// was given, the index the layouter gave it and the offsets it used, all
+/// relative to the region.
+#[derive(Debug, Clone)]
+struct RegionsConfig {
+    advice: [Column<Advice>; 2],
+    s_square: Selector,
+    s_inc: Selector,
+    s_double: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+struct MultipleRegionsChip<F: Field> {
+    config: RegionsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> MultipleRegionsChip<F> {
+    fn construct(config: RegionsConfig) -> Self {
+        MultipleRegionsChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RegionsConfig {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        meta.enable_equality(instance);
+        let s_square = meta.selector();
+        let s_inc = meta.selector();
+        let s_double = meta.selector();
+
+        meta.create_gate("square", |meta| {
+            let s = meta.query_selector(s_square);
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[0], Rotation::next());
+            Constraints::with_selector(s, vec![x.clone() * x - y])
+        });
+
+        meta.create_gate("increment", |meta| {
+            let s = meta.query_selector(s_inc);
+            let cur = meta.query_advice(advice[0], Rotation::cur());
+            let next = meta.query_advice(advice[0], Rotation::next());
+            Constraints::with_selector(s, vec![cur + Expression::Constant(F::ONE) - next])
+        });
+
+        meta.create_gate("double", |meta| {
+            let s = meta.query_selector(s_double);
+            let z = meta.query_advice(advice[0], Rotation::cur());
+            let out = meta.query_advice(advice[1], Rotation::cur());
+            Constraints::with_selector(s, vec![z.clone() + z - out])
+        });
+
+        RegionsConfig {
+            advice,
+            s_square,
+            s_inc,
+            s_double,
+            instance,
+        }
+    }
+
+    /// Region 0: `x` from the instance, and its square.
+    fn square(&self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.s_square.enable(&mut region, 0)?;
+                let x = region.assign_advice_from_instance(
+                    || "x",
+                    config.instance,
+                    0,
+                    config.advice[0],
+                    0,
+                )?;
+                let y = x.value().map(|x| x.square());
+                let y = region.assign_advice(|| "y", config.advice[0], 1, || y)?;
+                trace("square", &[&x, &y]);
+                Ok(y)
+            },
+        )
+    }
+
+    /// Region 1: `y + 2`, one at a time.
+    fn add_two(
+        &self,
+        mut layouter: impl Layouter<F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "add two",
+            |mut region| {
+                let mut cells = vec![y.copy_advice(|| "y", &mut region, config.advice[0], 0)?];
+                for row in 1..3 {
+                    config.s_inc.enable(&mut region, row - 1)?;
+                    let next = cells[row - 1].value().map(|v| *v + F::ONE);
+                    cells.push(region.assign_advice(|| "inc", config.advice[0], row, || next)?);
+                }
+                trace("add two", &cells.iter().collect::<Vec<_>>());
+                Ok(cells.pop().unwrap())
+            },
+        )
+    }
+
+    /// Region 2: `2z`, on a single row.
+    fn double(
+        &self,
+        mut layouter: impl Layouter<F>,
+        z: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "double",
+            |mut region| {
+                config.s_double.enable(&mut region, 0)?;
+                let z = z.copy_advice(|| "z", &mut region, config.advice[0], 0)?;
+                let out = z.value().map(|z| z.double());
+                let out = region.assign_advice(|| "out", config.advice[1], 0, || out)?;
+                trace("double", &[&z, &out]);
+                Ok(out)
+            },
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Print where a region's cells went, relative to the region, if
+/// `TRACE_REGIONS` is set.
+fn trace<F: Field>(name: &str, cells: &[&AssignedCell<F, F>]) {
+    if std::env::var("TRACE_REGIONS").is_ok() {
+        let offsets: Vec<usize> = cells.iter().map(|c| c.cell().row_offset).collect();
+        let index = cells.first().map(|c| *c.cell().region_index);
+        println!("region {:?} {:?}: offsets {:?}", index, name, offsets);
+    }
+}
+
+#[derive(Default)]
+struct RegionsCircuit<F: Field> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for RegionsCircuit<F> {
+    type Config = RegionsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MultipleRegionsChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = MultipleRegionsChip::<F>::construct(config);
+        let y = chip.square(layouter.namespace(|| "square"))?;
+        let z = chip.add_two(layouter.namespace(|| "add two"), &y)?;
+        let out = chip.double(layouter.namespace(|| "double"), &z)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    /// Which copy `ForgedCircuit` forges, and the value the prover puts in
+    /// the copied cell instead.
+    #[derive(Clone, Copy)]
+    enum Forge {
+        /// Region 1's `y`, copied from region 0.
+        Y(Value<Fp>),
+        /// Region 2's `z`, copied from region 1.
+        Z(Value<Fp>),
+    }
+
+    /// `RegionsCircuit` with the forged region written out by hand, starting
+    /// from a value of the prover's choice. With `linked` that value is tied
+    /// to the previous region's output the way `copy_advice` would; without,
+    /// nothing connects them.
+    struct ForgedCircuit {
+        forge: Forge,
+        linked: bool,
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = RegionsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ForgedCircuit {
+                forge: match self.forge {
+                    Forge::Y(_) => Forge::Y(Value::unknown()),
+                    Forge::Z(_) => Forge::Z(Value::unknown()),
+                },
+                linked: self.linked,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MultipleRegionsChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = MultipleRegionsChip::<Fp>::construct(config.clone());
+            let y = chip.square(layouter.namespace(|| "square"))?;
+            let z = match self.forge {
+                Forge::Y(forged) => layouter.assign_region(
+                    || "add two",
+                    |mut region| {
+                        let mut cell =
+                            region.assign_advice(|| "y", config.advice[0], 0, || forged)?;
+                        if self.linked {
+                            region.constrain_equal(y.cell(), cell.cell())?;
+                        }
+                        for row in 1..3 {
+                            config.s_inc.enable(&mut region, row - 1)?;
+                            let next = cell.value().map(|v| *v + Fp::ONE);
+                            cell =
+                                region.assign_advice(|| "inc", config.advice[0], row, || next)?;
+                        }
+                        Ok(cell)
+                    },
+                )?,
+                Forge::Z(_) => chip.add_two(layouter.namespace(|| "add two"), &y)?,
+            };
+            let out = match self.forge {
+                Forge::Z(forged) => layouter.assign_region(
+                    || "double",
+                    |mut region| {
+                        config.s_double.enable(&mut region, 0)?;
+                        let cell = region.assign_advice(|| "z", config.advice[0], 0, || forged)?;
+                        if self.linked {
+                            region.constrain_equal(z.cell(), cell.cell())?;
+                        }
+                        let out = forged.map(|z| z.double());
+                        region.assign_advice(|| "out", config.advice[1], 0, || out)
+                    },
+                )?,
+                Forge::Y(_) => chip.double(layouter.namespace(|| "double"), &z)?,
+            };
+            chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+        }
+    }
+
+    fn public(x: u64) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(x), Fp::from(2 * (x * x + 2))]]
+    }
+
+    #[test]
+    fn test_multiple_regions() {
+        let k = 4;
+        let circuit = RegionsCircuit::<Fp>::default();
+        MockProver::run(k, &circuit, public(3))
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_multiple_regions_stacked() {
+        // 2 + 3 + 1 rows, one region after the other.
+        let k = 4;
+        let circuit = RegionsCircuit::<Fp>::default();
+        assert_eq!(advice_rows(k, &circuit).unwrap(), 2 + 3 + 1);
+    }
+
+    #[test]
+    fn test_multiple_regions_linked() {
+        use crate::utils::failures::expect_failures;
+
+        let k = 4;
+        // x = 3 gives y = 9 and z = 11. Each forgery restarts one region
+        // from a value of its own and computes honestly from there, up to a
+        // public out that matches: every gate holds, only the copy into the
+        // forged region can tell.
+        for (forge, region) in [
+            // y = 16, z = 18, out = 36
+            (
+                Forge::Y(Value::known(Fp::from(16))),
+                "('add two') at offset 0",
+            ),
+            // z = 18, out = 36
+            (
+                Forge::Z(Value::known(Fp::from(18))),
+                "('double') at offset 0",
+            ),
+        ] {
+            let instances = vec![vec![Fp::from(3), Fp::from(36)]];
+            let linked = ForgedCircuit {
+                forge,
+                linked: true,
+            };
+            expect_failures(
+                k,
+                &linked,
+                instances.clone(),
+                &["Equality constraint not satisfied", region],
+            );
+
+            // Without the copy nothing stops it.
+            let unlinked = ForgedCircuit {
+                forge,
+                linked: false,
+            };
+            expect_failures(k, &unlinked, instances, &[]);
+        }
+    }
+}
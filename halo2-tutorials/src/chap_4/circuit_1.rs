@@ -21,7 +21,7 @@ use super::table_2::*;
 
 struct ACell<F: PrimeField>(AssignedCell<Assigned<F>, F>);
 #[derive(Debug, Clone)]
-struct RangeConfig<F: PrimeField, const RANGE: usize, const NUM: usize> {
+pub(crate) struct RangeConfig<F: PrimeField, const RANGE: usize, const NUM: usize> {
     value: Column<Advice>,
     table: LookUpTable<F, RANGE>,
     q_lookup: Selector,
@@ -70,12 +70,12 @@ impl<F: PrimeField, const RANGE: usize, const NUM: usize> RangeConfig<F, RANGE,
 }
 
 #[derive(Debug)]
-struct MyCircuit<F: PrimeField, const RANGE: usize, const NUM: usize> {
+pub(crate) struct MyCircuit<F: PrimeField, const RANGE: usize, const NUM: usize> {
     value: [Value<Assigned<F>>; NUM],
 }
 
 impl<F: PrimeField, const RANGE: usize, const NUM: usize> MyCircuit<F, RANGE, NUM> {
-    fn default() -> Self {
+    pub(crate) fn default() -> Self {
         let mut values = vec![];
         for i in 0..NUM {
             values.push(Value::known(Assigned::from(F::from(i as u64))));
@@ -26,7 +26,7 @@ use halo2_proofs::{circuit::*, pasta::group::ff::PrimeField, plonk::*, poly::Rot
 use super::table_3::*;
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+pub(crate) struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
     value: Column<Advice>,
     bit: Column<Advice>,
     q_lookup: Selector,
@@ -88,9 +88,9 @@ impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize>
 }
 
 #[derive(Debug, Default)]
-struct MyCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
-    num_bits: Vec<u8>,
-    values: Vec<Value<Assigned<F>>>,
+pub(crate) struct MyCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+    pub(crate) num_bits: Vec<u8>,
+    pub(crate) values: Vec<Value<Assigned<F>>>,
 }
 
 impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
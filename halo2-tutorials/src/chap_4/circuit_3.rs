@@ -22,7 +22,7 @@ use halo2_proofs::{
 /// - next_b ∈ t2
 
 #[derive(Clone)]
-struct LookupConfig {
+pub(crate) struct LookupConfig {
     a: Column<Advice>,
     b: Column<Advice>,
     s: Selector,
@@ -114,9 +114,9 @@ impl<F: PrimeField> LookupChip<F> {
 }
 
 #[derive(Default)]
-struct MyCircuit<F: PrimeField> {
-    a: Vec<Value<F>>,
-    b: Vec<Value<F>>,
+pub(crate) struct MyCircuit<F: PrimeField> {
+    pub(crate) a: Vec<Value<F>>,
+    pub(crate) b: Vec<Value<F>>,
 }
 
 impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
@@ -0,0 +1,244 @@
+/// chap4: character classes
+/// Check that bytes belong to a given charset, e.g. `[a-zA-Z0-9_]` for
+/// identifiers, with one lookup table shared by every charset in the circuit.
+///
+/// Each `CharsetChip` is built from its own `&[u8]` and a tag. The table holds
+/// `(tag, byte)` for every byte of every charset, and a check looks up the
+/// byte together with the chip's tag, so a byte allowed by one charset does
+/// not pass as another's:
+///
+/// | byte | tag (fixed) | q_check | t_tag | t_byte |
+/// |------|-------------|---------|-------|--------|
+/// | 'u'  |      1      |    1    |   0   |   0    |
+/// | '7'  |      2      |    1    |   1   |  'a'   |
+/// |      |             |         |  ...  |  ...   |
+/// |      |             |         |   2   |  '0'   |
+/// |      |             |         |  ...  |  ...   |
+///
+/// Tag 0 is reserved for the `(0, 0)` row that disabled rows look up, so no
+/// charset can ever match through it; an empty charset rejects everything.
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::group::ff::PrimeField, plonk::*, poly::Rotation};
+
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+pub struct CharsetConfig {
+    byte: Column<Advice>,
+    tag: Column<Fixed>,
+    q_check: Selector,
+    t_tag: TableColumn,
+    t_byte: TableColumn,
+}
+
+impl CharsetConfig {
+    pub fn configure<F: PrimeField>(
+        meta: &mut ConstraintSystem<F>,
+        byte: Column<Advice>,
+    ) -> CharsetConfig {
+        meta.enable_equality(byte);
+        let tag = meta.fixed_column();
+        let q_check = meta.complex_selector();
+        let t_tag = meta.lookup_table_column();
+        let t_byte = meta.lookup_table_column();
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_check);
+            let tag = meta.query_fixed(tag, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            vec![(q.clone() * tag, t_tag), (q * byte, t_byte)]
+        });
+
+        CharsetConfig {
+            byte,
+            tag,
+            q_check,
+            t_tag,
+            t_byte,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharsetChip<F: PrimeField> {
+    config: CharsetConfig,
+    tag: u64,
+    charset: Vec<u8>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> CharsetChip<F> {
+    pub fn construct(config: CharsetConfig, tag: u64, charset: &[u8]) -> Self {
+        assert_ne!(tag, 0, "tag 0 is reserved");
+        CharsetChip {
+            config,
+            tag,
+            charset: charset.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the shared table for `chips`, which must all use the same config.
+    pub fn load_table(mut layouter: impl Layouter<F>, chips: &[&Self]) -> Result<(), Error> {
+        let Some(first) = chips.first() else {
+            return Ok(());
+        };
+        let mut tags: Vec<_> = chips.iter().map(|chip| chip.tag).collect();
+        tags.sort();
+        tags.dedup();
+        assert_eq!(tags.len(), chips.len(), "charset tags must be distinct");
+
+        let (t_tag, t_byte) = (first.config.t_tag, first.config.t_byte);
+        layouter.assign_table(
+            || "charsets",
+            |mut table| {
+                let rows = std::iter::once((0, 0)).chain(
+                    chips
+                        .iter()
+                        .flat_map(|chip| chip.charset.iter().map(|b| (chip.tag, *b as u64))),
+                );
+                for (row, (tag, byte)) in rows.enumerate() {
+                    table.assign_cell(|| "tag", t_tag, row, || Value::known(F::from(tag)))?;
+                    table.assign_cell(|| "byte", t_byte, row, || Value::known(F::from(byte)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Check that `byte` is in this chip's charset.
+    pub fn check(&self, mut layouter: impl Layouter<F>, byte: Number<F>) -> Result<(), Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "check charset",
+            |mut region| {
+                config.q_check.enable(&mut region, 0)?;
+                region.assign_fixed(|| "tag", config.tag, 0, || Value::known(F::from(self.tag)))?;
+                byte.0.copy_advice(|| "byte", &mut region, config.byte, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const IDENT: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+    const DIGITS: &[u8] = b"0123456789";
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        value: Column<Advice>,
+        charset: CharsetConfig,
+    }
+
+    /// Check each `(charset, byte)` pair, charset `i` being tagged `i + 1`.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        charsets: Vec<&'static [u8]>,
+        checks: Vec<(usize, Value<F>)>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                charsets: self.charsets.clone(),
+                checks: self
+                    .checks
+                    .iter()
+                    .map(|(i, _)| (*i, Value::unknown()))
+                    .collect(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let value = meta.advice_column();
+            meta.enable_equality(value);
+            let byte = meta.advice_column();
+            TestConfig {
+                value,
+                charset: CharsetConfig::configure(meta, byte),
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chips: Vec<_> = self
+                .charsets
+                .iter()
+                .enumerate()
+                .map(|(i, charset)| {
+                    CharsetChip::construct(config.charset.clone(), i as u64 + 1, charset)
+                })
+                .collect();
+            CharsetChip::load_table(
+                layouter.namespace(|| "charsets"),
+                &chips.iter().collect::<Vec<_>>(),
+            )?;
+
+            for (i, value) in &self.checks {
+                let byte = layouter.assign_region(
+                    || "load byte",
+                    |mut region| {
+                        region
+                            .assign_advice(|| "byte", config.value, 0, || *value)
+                            .map(Number)
+                    },
+                )?;
+                chips[*i].check(layouter.namespace(|| "check"), byte)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prove(
+        charsets: &[&'static [u8]],
+        checks: &[(usize, u8)],
+    ) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 8;
+        let circuit = MyCircuit::<Fp> {
+            charsets: charsets.to_vec(),
+            checks: checks
+                .iter()
+                .map(|(i, b)| (*i, Value::known(Fp::from(*b as u64))))
+                .collect(),
+        };
+        MockProver::run(k, &circuit, vec![]).unwrap().verify()
+    }
+
+    #[test]
+    fn test_charset_allowed() {
+        let checks: Vec<_> = b"user_42".iter().map(|b| (0, *b)).collect();
+        assert!(prove(&[IDENT], &checks).is_ok());
+    }
+
+    #[test]
+    fn test_charset_disallowed() {
+        assert!(prove(&[IDENT], &[(0, b'a'), (0, b'-')]).is_err());
+        assert!(prove(&[IDENT], &[(0, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_two_charsets() {
+        assert!(prove(&[IDENT, DIGITS], &[(0, b'a'), (0, b'7'), (1, b'7')]).is_ok());
+        // 'a' is an identifier character, but not a digit.
+        assert!(prove(&[IDENT, DIGITS], &[(1, b'a')]).is_err());
+    }
+
+    #[test]
+    fn test_empty_charset() {
+        assert!(prove(&[IDENT, b""], &[(0, b'a')]).is_ok());
+        assert!(prove(&[IDENT, b""], &[(1, b'a')]).is_err());
+        assert!(prove(&[IDENT, b""], &[(1, 0)]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+/// chap4: proximity proof
+/// Prove that a private point is within a public distance of a public
+/// anchor, without saying where:
+///
+///   (x - ax)^2 + (y - ay)^2 <= r2,    instance = [ax, ay, r2]
+///
+/// Coordinates are signed, in `[-128, 128)`, and sit in the circuit in
+/// offset encoding: `c` is the byte `c + OFFSET`. The byte lookup then bounds
+/// a coordinate from both sides, where a field element has no sign to check.
+/// The offsets cancel in a difference, so `dx = x - ax` is the field element
+/// of an integer in `(-256, 256)`: `p - |dx|` when negative. Squaring takes
+/// the sign away,
+///
+///   dx^2 + dy^2 <= 2 * 255^2 < 2^17,
+///
+/// and nothing wraps around `p`. The comparison witnesses the slack
+///
+///   slack = r2 - d2,    d2 = dx^2 + dy^2
+///
+/// and checks it to three bytes. That alone is the comparison: with both
+/// `d2` and `slack` far below `p`, `r2 = d2 + slack` holds over the integers,
+/// whatever field element `r2` is. When `d2 > r2` the slack is `p` minus
+/// something small, and has no three bytes; a "negative" threshold `p - 1`
+/// leaves the slack `p - 1 - d2`. Thresholds are meant to be below `2^24`,
+/// which covers every distance on the grid.
+///
+/// | a0 .. a2                          | q_byte | constant  | instance |
+/// |-----------------------------------|--------|-----------|----------|
+/// | x, y, ax, ay                      |   1    |           |    ax    |
+/// | dx, dx^2, dy, dy^2, d2, slack     |        |           |    ay    |
+/// | b0, b1, b2                        |   1    |           |    r2    |
+/// | b0 + 256 * b1 + 2^16 * b2 = slack |        | 256, 2^16 |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    Number,
+};
+
+/// The offset of the coordinate encoding.
+pub const OFFSET: i64 = 128;
+
+/// The byte of a coordinate in `[-128, 128)`.
+pub fn encode(c: i64) -> u64 {
+    (c + OFFSET) as u64
+}
+
+#[derive(Debug, Clone)]
+struct DistanceConfig {
+    arith: ArithConfig,
+    byte: ByteConfig,
+    instance: Column<Instance>,
+}
+
+/// The private point, encoded.
+#[derive(Default)]
+struct DistanceCircuit<F: PrimeField> {
+    x: Value<F>,
+    y: Value<F>,
+}
+
+impl<F: PrimeField> DistanceCircuit<F> {
+    /// Check `v < 2^24` by its three little-endian bytes.
+    fn check_three_bytes(
+        config: &DistanceConfig,
+        mut layouter: impl Layouter<F>,
+        v: Number<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith.clone());
+        let byte = ByteChip::construct(config.byte.clone());
+        let limbs = [0, 1, 2].map(|i| {
+            let limb = v.0.value().map(|v| F::from(v.to_repr().as_ref()[i] as u64));
+            byte.assign_byte(layouter.namespace(|| "limb"), limb)
+        });
+        let [b0, b1, b2] = limbs;
+        let (b0, b1, b2) = (b0?, b1?, b2?);
+
+        let base = arith.load_constant(layouter.namespace(|| "256"), F::from(256))?;
+        let base2 = arith.load_constant(layouter.namespace(|| "2^16"), F::from(1 << 16))?;
+        let b1 = arith.mul(layouter.namespace(|| "256 * b1"), b1, base)?;
+        let b2 = arith.mul(layouter.namespace(|| "2^16 * b2"), b2, base2)?;
+        let sum = arith.add(layouter.namespace(|| "b0 + 256 * b1"), b0, b1)?;
+        let sum = arith.add(layouter.namespace(|| "+ 2^16 * b2"), sum, b2)?;
+        arith.assert_equal(layouter.namespace(|| "limbs = v"), sum, v)
+    }
+
+    /// `(v - anchor)^2`.
+    fn square_difference(
+        arith: &ArithChip<F>,
+        mut layouter: impl Layouter<F>,
+        v: Number<F>,
+        anchor: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let d = arith.sub(layouter.namespace(|| "v - anchor"), v, anchor)?;
+        arith.mul(layouter.namespace(|| "d^2"), d.clone(), d)
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for DistanceCircuit<F> {
+    type Config = DistanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        DistanceConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            byte: ByteChip::configure(meta, advice[0]),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith.clone());
+        let byte = ByteChip::construct(config.byte.clone());
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        // Bytes, i.e. coordinates in [-128, 128).
+        let x = byte.assign_byte(layouter.namespace(|| "x"), self.x)?;
+        let y = byte.assign_byte(layouter.namespace(|| "y"), self.y)?;
+        let [ax, ay, r2] = [0, 1, 2]
+            .map(|row| arith.load_instance(layouter.namespace(|| "public"), config.instance, row));
+        let (ax, ay, r2) = (ax?, ay?, r2?);
+        byte.check_byte(layouter.namespace(|| "ax"), ax.clone())?;
+        byte.check_byte(layouter.namespace(|| "ay"), ay.clone())?;
+
+        let dx2 = Self::square_difference(&arith, layouter.namespace(|| "dx^2"), x, ax)?;
+        let dy2 = Self::square_difference(&arith, layouter.namespace(|| "dy^2"), y, ay)?;
+        let d2 = arith.add(layouter.namespace(|| "d2"), dx2, dy2)?;
+        let slack = arith.sub(layouter.namespace(|| "r2 - d2"), r2, d2)?;
+        Self::check_three_bytes(&config, layouter.namespace(|| "slack"), slack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn verify(point: (i64, i64), anchor: (i64, i64), r2: u64) -> bool {
+        let circuit = DistanceCircuit {
+            x: Value::known(Fp::from(encode(point.0))),
+            y: Value::known(Fp::from(encode(point.1))),
+        };
+        let public = vec![
+            Fp::from(encode(anchor.0)),
+            Fp::from(encode(anchor.1)),
+            Fp::from(r2),
+        ];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_distance_inside() {
+        assert!(verify((1, 2), (0, 0), 25));
+        // Negative differences square like positive ones.
+        assert!(verify((-3, 4), (0, 0), 26));
+        assert!(verify((-10, -10), (-12, -7), 100));
+        // The anchor itself, at radius zero.
+        assert!(verify((5, -5), (5, -5), 0));
+    }
+
+    #[test]
+    fn test_distance_on_radius() {
+        // 3^2 + 4^2 = 25, in either direction.
+        assert!(verify((3, 4), (0, 0), 25));
+        assert!(verify((-3, -4), (0, 0), 25));
+        assert!(verify((10, 20), (13, 16), 25));
+    }
+
+    #[test]
+    fn test_distance_outside() {
+        assert!(!verify((3, 4), (0, 0), 24));
+        assert!(!verify((-3, 5), (0, 0), 33));
+        assert!(!verify((100, 100), (-100, -100), 1000));
+    }
+
+    #[test]
+    fn test_distance_encoding_bounds() {
+        // Opposite corners of the grid: the largest squared distance there is.
+        let max = 2 * 255 * 255;
+        assert!(verify((-128, -128), (127, 127), max));
+        assert!(!verify((-128, -128), (127, 127), max - 1));
+        assert!(verify((127, -128), (-128, 127), max));
+
+        // 128 is out of the encoding: its byte would be 256.
+        assert!(!verify((128, 0), (127, 0), 1));
+        assert!(!verify((0, 0), (0, 128), 16384));
+        // Nor is a threshold of 2^24, even for the anchor itself.
+        assert!(!verify((0, 0), (0, 0), 1 << 24));
+    }
+
+    #[test]
+    fn test_distance_wraparound() {
+        // r2 = -1 does not wrap around to "every point is close": the slack
+        // p - 1 - d2 has no three bytes.
+        let circuit = DistanceCircuit {
+            x: Value::known(Fp::from(encode(1))),
+            y: Value::known(Fp::from(encode(1))),
+        };
+        let public = vec![Fp::from(encode(0)), Fp::from(encode(0)), -Fp::one()];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+/// chap4: folding rows into columns
+/// The formula of chap 2's exercise 5, for private `a`, `b` and a constant `c`:
+///
+///   out = (a^2 * b^2 * c + c)^3
+///
+/// Exercise 5 reads `a, b, c` at `Rotation::cur()` and `out` at
+/// `Rotation::next()`, under `a`, so its one gate spans two rows. Here `out`
+/// gets a column of its own and every query is `Rotation::cur()`: the gate
+/// fits in one row, and a region of it is as tall as one use of the formula.
+///
+/// | a0 | a1 | a2 | a3  | s_cpx |
+/// |----|----|----|-----|-------|
+/// | a  | b  | c  | out |   1   |
+///
+/// The trade-off, for one use of the formula:
+///
+/// | layout     | advice columns | rows | cells       |
+/// |------------|----------------|------|-------------|
+/// | exercise 5 |       3        |  2   | 6 (2 empty) |
+/// | folded     |       4        |  1   | 4           |
+///
+/// For `n` uses that is `2n` rows against `n`: when the rows are what decide
+/// `k`, folding halves the height of the circuit and may save a `k`. It is
+/// not free, every advice column is one more commitment in the proof and one
+/// more polynomial the prover handles, over all `2^k` rows whether they are
+/// used or not. A row's worth of empty cells in exercise 5 is what buys its
+/// one column less.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+pub struct FoldedConfig {
+    advice: [Column<Advice>; 4],
+    instance: Column<Instance>,
+    s_cpx: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoldedSimpleChip<F: Field> {
+    config: FoldedConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> FoldedSimpleChip<F> {
+    pub fn construct(config: FoldedConfig) -> Self {
+        FoldedSimpleChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FoldedConfig {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let s_cpx = meta.selector();
+
+        meta.create_gate("folded complex_gate", |meta| {
+            let s = meta.query_selector(s_cpx);
+            let [a, b, c, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            let ab = a * b;
+            let e = ab.clone() * ab * c.clone() + c;
+            Constraints::with_selector(s, vec![e.clone() * e.clone() * e - out])
+        });
+
+        FoldedConfig {
+            advice,
+            instance,
+            s_cpx,
+        }
+    }
+
+    /// `out` for private `a`, `b` and the constant `c`, on a single row.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+        c: F,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "folded formula",
+            |mut region| {
+                config.s_cpx.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.advice[0], 0, || a)?;
+                region.assign_advice(|| "b", config.advice[1], 0, || b)?;
+                region.assign_advice_from_constant(|| "c", config.advice[2], 0, c)?;
+                let ab = a * b;
+                let e = ab * ab * Value::known(c) + Value::known(c);
+                region
+                    .assign_advice(|| "out", config.advice[3], 0, || e * e * e)
+                    .map(Number)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        out: &Number<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(out.0.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+pub struct FoldedCircuit<F: Field> {
+    pub c: F,
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for FoldedCircuit<F> {
+    type Config = FoldedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FoldedSimpleChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FoldedSimpleChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "folded"), self.a, self.b, self.c)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    fn circuit() -> (FoldedCircuit<Fp>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let circuit = FoldedCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, out)
+    }
+
+    #[test]
+    fn test_folded() {
+        let (circuit, out) = circuit();
+        MockProver::run(K, &circuit, vec![vec![out]])
+            .unwrap()
+            .assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_folded_shape() {
+        let (circuit, _) = circuit();
+        assert_eq!(advice_rows(K, &circuit).unwrap(), 1);
+        let mut meta = ConstraintSystem::<Fp>::default();
+        FoldedCircuit::<Fp>::configure(&mut meta);
+        assert_eq!(meta.num_advice_columns(), 4);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_folded_matches_exercise_5() {
+        use crate::chap_2::exercise_5::MyCircuit;
+
+        let (folded, out) = circuit();
+        let unfolded = MyCircuit {
+            c: folded.c,
+            a: folded.a,
+            b: folded.b,
+        };
+        // The same public output satisfies both, and a wrong one neither.
+        for public in [out, out + Fp::one()] {
+            let folded_ok = MockProver::run(K, &folded, vec![vec![public]])
+                .unwrap()
+                .verify()
+                .is_ok();
+            let unfolded_ok = MockProver::run(K, &unfolded, vec![vec![public]])
+                .unwrap()
+                .verify()
+                .is_ok();
+            assert_eq!(folded_ok, unfolded_ok);
+            assert_eq!(folded_ok, public == out);
+        }
+
+        // Twice the rows, one column less.
+        assert_eq!(advice_rows(K, &unfolded).unwrap(), 2);
+        let mut meta = ConstraintSystem::<Fp>::default();
+        MyCircuit::<Fp>::configure(&mut meta);
+        assert_eq!(meta.num_advice_columns(), 3);
+    }
+}
@@ -0,0 +1,221 @@
+/// chap4: gcd by Bezout witnesses
+/// Prove that a public `g` is the greatest common divisor of two private
+/// `N`-bit integers `a` and `b`. Euclid's algorithm loops a data-dependent
+/// number of times, so the prover runs the extended version out of circuit
+/// and witnesses its results: quotients `qa`, `qb` and Bezout coefficients
+/// `u`, `v`. The circuit checks
+///
+///   a = g * qa,    b = g * qb,    g = u * a + v * b
+///
+/// over the integers. Each check is needed:
+///
+///   - `a = g * qa` and `b = g * qb` say `g` divides both. Without them any
+///     combination of `a` and `b` passes, `g = a` with `u = 1, v = 0`.
+///   - `g = u * a + v * b` says every common divisor of `a` and `b` divides
+///     `g`, so none is greater. Without it `g = 1` passes for any inputs.
+///   - The range checks make the equations about integers. In the field
+///     every nonzero `g` divides everything, `qa = a / g`, and some `u`
+///     reaches any `g`: see `BezoutChip`.
+///
+/// `a`, `b`, `g`, `qa` and `qb` are checked to `N` bits, so `g * qa` stays
+/// below `2^(2N)`. Bezout coefficients from the extended algorithm are
+/// bounded by the inputs but have signs; `u` and `v` are `N + 1`-bit two's
+/// complement numbers, multiplied through `SignedMulChip` as signs and
+/// magnitudes. `u * a + v * b` is then below `2^(2N + 1)` in absolute value,
+/// far from wrapping around `p`, and equal to `g` in the field only if it is
+/// over the integers.
+///
+/// `SignedMulChip::sign` decomposes an input into `N + 1` bits; a value
+/// whose sign bit is constrained to 0 is an unsigned `N`-bit integer.
+///
+/// | a0 .. a3                                      | constant | instance |
+/// |-----------------------------------------------|----------|----------|
+/// | a, b, qa, qb, u, v                            |          |    g     |
+/// | bits of a, b, g, qa, qb, sign = 0             |    0     |          |
+/// | g * qa = a, g * qb = b                        |          |          |
+/// | u * a, v * b: signs, magnitudes, products     |          |          |
+/// | u * a + v * b = g                             |          |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::{
+    chap_5::signed_mul::{SignedMulChip, SignedMulConfig},
+    gadgets::Number,
+};
+
+/// Bits of `a` and `b`.
+pub const N: usize = 32;
+/// Bits of the signed coefficients.
+const SIGNED_BITS: usize = N + 1;
+
+type Chip<F> = SignedMulChip<F, SIGNED_BITS>;
+
+#[derive(Debug, Clone)]
+pub struct GcdConfig {
+    signed_mul: SignedMulConfig,
+    instance: Column<Instance>,
+}
+
+/// `g = gcd(a, b)` for private `a`, `b` and the public `g`. `u` and `v` are
+/// the `N + 1`-bit two's complement encodings.
+#[derive(Default)]
+pub struct GcdCircuit<F: PrimeField> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub qa: Value<F>,
+    pub qb: Value<F>,
+    pub u: Value<F>,
+    pub v: Value<F>,
+}
+
+/// Check that `x` is an unsigned `N`-bit integer.
+fn range_check<F: PrimeField>(
+    chip: &Chip<F>,
+    mut layouter: impl Layouter<F>,
+    x: &Number<F>,
+) -> Result<(), Error> {
+    let sign = chip.sign(layouter.namespace(|| "bits"), x)?;
+    layouter.assign_region(
+        || "sign = 0",
+        |mut region| region.constrain_constant(sign.0.cell(), F::ZERO),
+    )
+}
+
+impl<F: PrimeField> Circuit<F> for GcdCircuit<F> {
+    type Config = GcdConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        GcdConfig {
+            signed_mul: Chip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = Chip::construct(config.signed_mul);
+        let arith = chip.arith();
+        let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+        let qa = arith.load_private(layouter.namespace(|| "qa"), self.qa)?;
+        let qb = arith.load_private(layouter.namespace(|| "qb"), self.qb)?;
+        let u = arith.load_private(layouter.namespace(|| "u"), self.u)?;
+        let v = arith.load_private(layouter.namespace(|| "v"), self.v)?;
+        let g = arith.load_instance(layouter.namespace(|| "g"), config.instance, 0)?;
+
+        for (name, x) in [("a", &a), ("b", &b), ("g", &g), ("qa", &qa), ("qb", &qb)] {
+            range_check(&chip, layouter.namespace(|| name), x)?;
+        }
+
+        // g divides a and b.
+        let g_qa = arith.mul(layouter.namespace(|| "g * qa"), g.clone(), qa)?;
+        arith.assert_equal(layouter.namespace(|| "a = g * qa"), g_qa, a.clone())?;
+        let g_qb = arith.mul(layouter.namespace(|| "g * qb"), g.clone(), qb)?;
+        arith.assert_equal(layouter.namespace(|| "b = g * qb"), g_qb, b.clone())?;
+
+        // Every common divisor divides g.
+        let ua = chip.mul(layouter.namespace(|| "u * a"), u, a)?;
+        let vb = chip.mul(layouter.namespace(|| "v * b"), v, b)?;
+        let sum = arith.add(layouter.namespace(|| "u * a + v * b"), ua, vb)?;
+        arith.assert_equal(layouter.namespace(|| "g = u * a + v * b"), sum, g)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+
+    /// `(gcd, u, v)` with `u * a + v * b = gcd`.
+    fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+        if b == 0 {
+            (a, 1, 0)
+        } else {
+            let (g, u, v) = extended_gcd(b, a % b);
+            (g, v, u - (a / b) * v)
+        }
+    }
+
+    /// The `N + 1`-bit two's complement encoding of `x`.
+    fn encode(x: i64) -> Fp {
+        Fp::from((x as u64) & ((1 << SIGNED_BITS) - 1))
+    }
+
+    /// The witnesses an honest prover would use to claim `g`: Bezout
+    /// coefficients of the true gcd, scaled up to `g` when it is a
+    /// multiple of it.
+    fn circuit(a: u64, b: u64, g: u64) -> GcdCircuit<Fp> {
+        let (d, u, v) = extended_gcd(a as i64, b as i64);
+        let k = if d != 0 && g as i64 % d == 0 {
+            g as i64 / d
+        } else {
+            1
+        };
+        let q = |x: u64| if g == 0 { 0 } else { x / g };
+        GcdCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            qa: Value::known(Fp::from(q(a))),
+            qb: Value::known(Fp::from(q(b))),
+            u: Value::known(encode(u * k)),
+            v: Value::known(encode(v * k)),
+        }
+    }
+
+    fn verify(a: u64, b: u64, g: u64) -> bool {
+        let prover = MockProver::run(K, &circuit(a, b, g), vec![vec![Fp::from(g)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_gcd_coprime() {
+        assert!(verify(35, 64, 1));
+        assert!(verify(4_294_967_291, 4_294_967_279, 1));
+    }
+
+    #[test]
+    fn test_gcd_common_factor() {
+        // 65537 * 40503 and 65537 * 30011
+        assert!(verify(2_654_445_111, 1_966_830_907, 65537));
+        assert!(verify(12, 18, 6));
+    }
+
+    #[test]
+    fn test_gcd_too_small() {
+        // 3 divides both, but 3 is no combination of 12 and 18.
+        assert!(!verify(12, 18, 3));
+        assert!(!verify(2_654_445_111, 1_966_830_907, 1));
+    }
+
+    #[test]
+    fn test_gcd_not_a_divisor() {
+        // 12 = 1 * 12 + 0 * 18, but 12 does not divide 18.
+        assert!(!verify(12, 18, 12));
+        assert!(!verify(12, 18, 5));
+    }
+
+    #[test]
+    fn test_gcd_zero() {
+        // gcd(0, b) = b: qa = 0, u = 0, v = 1.
+        assert!(verify(0, 42, 42));
+        assert!(!verify(0, 42, 21));
+        assert!(!verify(0, 42, 84));
+    }
+}
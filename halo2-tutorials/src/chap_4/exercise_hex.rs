@@ -0,0 +1,261 @@
+/// chap4: hex decoding
+/// Prove that a public ASCII hex string decodes to a private byte array, and
+/// that the array is the one committed to by a public MiMC hash:
+///
+///   instance = [mimc_hash(bytes), len(hex), c_0, c_1, ..., c_{2n-1}]
+///
+/// Each byte is one row. Both of its characters are looked up in a 22-entry
+/// table of `(char, nibble)` pairs, upper and lower case, and one gate
+/// recombines them:
+///
+///   b = 16 * n_hi + n_lo
+///
+/// Two nibbles below 16 always make a byte, so `b` needs no range check of its
+/// own. The circuit is sized for `n` bytes and pins `len(hex) = 2n`, so an
+/// odd-length string is rejected by its length alone, whatever its characters.
+///
+/// | c_hi | c_lo | n_hi | n_lo | b    | q_hex | tag | char | nibble |
+/// |------|------|------|------|------|-------|-----|------|--------|
+/// | 'd'  | 'e'  |  13  |  14  | 0xde |   1   |  0  |  0   |   0    |
+/// | 'A'  | 'd'  |  10  |  13  | 0xad |   1   |  1  | '0'  |   0    |
+/// |      |      |      |      |      |       | ... | ...  |  ...   |
+///
+/// As in the base64 exercise, the table starts with an untagged `(0, 0, 0)`
+/// row for disabled rows to look up.
+use halo2_proofs::{circuit::*, pasta::group::ff::PrimeField, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    mimc::{mimc_hash, MimcChip, MimcConfig},
+    Number,
+};
+
+pub const HEX_CHARS: &[u8; 22] = b"0123456789abcdefABCDEF";
+
+/// The value of a hex digit, in either case.
+pub fn nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// `[mimc_hash(bytes), len(hex), c_0, ...]`
+pub fn public_inputs<F: PrimeField>(bytes: &[u8], hex: &[u8]) -> Vec<F> {
+    let bytes: Vec<F> = bytes.iter().map(|b| F::from(*b as u64)).collect();
+    let mut out = vec![mimc_hash(&bytes), F::from(hex.len() as u64)];
+    out.extend(hex.iter().map(|c| F::from(*c as u64)));
+    out
+}
+
+#[derive(Debug, Clone)]
+pub struct HexConfig {
+    chars: [Column<Advice>; 2],
+    nibbles: [Column<Advice>; 2],
+    byte: Column<Advice>,
+    q_hex: Selector,
+    t_tag: TableColumn,
+    t_char: TableColumn,
+    t_nibble: TableColumn,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+impl HexConfig {
+    fn load_table<F: PrimeField>(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "hex table",
+            |mut table| {
+                let rows = std::iter::once((0, 0, 0)).chain(
+                    HEX_CHARS
+                        .iter()
+                        .map(|c| (1, *c as u64, nibble(*c).unwrap() as u64)),
+                );
+                for (row, (tag, c, n)) in rows.enumerate() {
+                    table.assign_cell(|| "tag", self.t_tag, row, || Value::known(F::from(tag)))?;
+                    table.assign_cell(|| "char", self.t_char, row, || Value::known(F::from(c)))?;
+                    table.assign_cell(
+                        || "nibble",
+                        self.t_nibble,
+                        row,
+                        || Value::known(F::from(n)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct HexCircuit<F: PrimeField> {
+    pub bytes: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> HexCircuit<F> {
+    pub fn new(bytes: &[u8]) -> Self {
+        HexCircuit {
+            bytes: bytes
+                .iter()
+                .map(|b| Value::known(F::from(*b as u64)))
+                .collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for HexCircuit<F> {
+    type Config = HexConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        HexCircuit {
+            bytes: vec![Value::unknown(); self.bytes.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let chars = [meta.advice_column(), meta.advice_column()];
+        let nibbles = [meta.advice_column(), meta.advice_column()];
+        let byte = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in [chars[0], chars[1], byte] {
+            meta.enable_equality(col);
+        }
+        let q_hex = meta.complex_selector();
+        let t_tag = meta.lookup_table_column();
+        let t_char = meta.lookup_table_column();
+        let t_nibble = meta.lookup_table_column();
+
+        meta.create_gate("b = 16 * n_hi + n_lo", |meta| {
+            let q = meta.query_selector(q_hex);
+            let n_hi = meta.query_advice(nibbles[0], Rotation::cur());
+            let n_lo = meta.query_advice(nibbles[1], Rotation::cur());
+            let b = meta.query_advice(byte, Rotation::cur());
+            Constraints::with_selector(q, vec![Expression::Constant(F::from(16)) * n_hi + n_lo - b])
+        });
+
+        for i in 0..2 {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_hex);
+                let c = meta.query_advice(chars[i], Rotation::cur());
+                let n = meta.query_advice(nibbles[i], Rotation::cur());
+                vec![
+                    (q.clone(), t_tag),
+                    (q.clone() * c, t_char),
+                    (q * n, t_nibble),
+                ]
+            });
+        }
+
+        HexConfig {
+            chars,
+            nibbles,
+            byte,
+            q_hex,
+            t_tag,
+            t_char,
+            t_nibble,
+            mimc: MimcChip::configure(meta, [chars[0], chars[1], byte], constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let n = self.bytes.len();
+        let mimc = MimcChip::construct(config.mimc.clone());
+        config.load_table(layouter.namespace(|| "hex table"))?;
+
+        let len = layouter.assign_region(
+            || "len",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "2n",
+                    config.chars[0],
+                    0,
+                    F::from(2 * n as u64),
+                )
+            },
+        )?;
+        layouter.constrain_instance(len.cell(), config.instance, 1)?;
+
+        let bytes = layouter.assign_region(
+            || "decode",
+            |mut region| {
+                let mut bytes = vec![];
+                for (row, b) in self.bytes.iter().enumerate() {
+                    config.q_hex.enable(&mut region, row)?;
+                    for i in 0..2 {
+                        let c = region.assign_advice_from_instance(
+                            || "char",
+                            config.instance,
+                            2 + 2 * row + i,
+                            config.chars[i],
+                            row,
+                        )?;
+                        // Non-hex characters get 0 and fail the lookup.
+                        let n = c.value().map(|c| {
+                            let c = c.to_repr().as_ref()[0];
+                            F::from(nibble(c).unwrap_or(0) as u64)
+                        });
+                        region.assign_advice(|| "nibble", config.nibbles[i], row, || n)?;
+                    }
+                    let b = region.assign_advice(|| "byte", config.byte, row, || *b)?;
+                    bytes.push(Number(b));
+                }
+                Ok(bytes)
+            },
+        )?;
+
+        let digest = mimc.hash(layouter.namespace(|| "commit"), &bytes)?;
+        layouter.constrain_instance(digest.0.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const BYTES: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+    fn prove(hex: &[u8]) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 9;
+        let circuit = HexCircuit::<Fp>::new(&BYTES);
+        let prover = MockProver::run(k, &circuit, vec![public_inputs(&BYTES, hex)]).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_hex_lowercase() {
+        assert!(prove(b"deadbeef").is_ok());
+        assert!(prove(b"deadbeee").is_err());
+    }
+
+    #[test]
+    fn test_hex_uppercase() {
+        assert!(prove(b"DEADBEEF").is_ok());
+    }
+
+    #[test]
+    fn test_hex_mixed_case() {
+        assert!(prove(b"DeAdbEeF").is_ok());
+    }
+
+    #[test]
+    fn test_hex_odd_length() {
+        assert!(prove(b"deadbee").is_err());
+        assert!(prove(b"deadbeef0").is_err());
+    }
+
+    #[test]
+    fn test_hex_non_hex_char() {
+        assert!(prove(b"deadbeeg").is_err());
+    }
+}
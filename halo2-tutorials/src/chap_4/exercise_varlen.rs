@@ -0,0 +1,282 @@
+/// chap4: variable-length input
+/// Sum a private list of at most `N` values whose length `len` is itself a
+/// witness, exposed publicly with the sum:
+///
+///   instance = [len, x_0 + ... + x_{len-1}]
+///
+/// A circuit has a fixed number of rows, so the list takes all `N` of them,
+/// and an `active` bit per row says which ones are in it. For that to mean
+/// "the first `len` rows", the bits are constrained to a prefix mask:
+///
+///   active_i * (1 - active_i)        = 0    boolean
+///   active_{i+1} * (1 - active_i)    = 0    active_{i+1} <= active_i
+///   (1 - active_i) * x_i             = 0    padding is zero
+///
+/// and a running count of the bits, next to the running sum, ends at `len`.
+/// The padding could instead be multiplied by its bit in the sum; forcing it
+/// to zero keeps the sum gate linear, and lets any later gadget read the
+/// padded list as it is. Every variable-length gadget needs some form of
+/// this mask: without it a prover could leave out an inconvenient element in
+/// the middle, or sum a value past the end.
+///
+/// | a0  | a1       | a2    | a3      | s_first | s_next | s_row | s_prefix |
+/// |-----|----------|-------|---------|---------|--------|-------|----------|
+/// | x_0 | active_0 | sum_0 | count_0 |    1    |   0    |   1   |    1     |
+/// | x_1 | active_1 | sum_1 | count_1 |    0    |   1    |   1   |    1     |
+/// | ... |   ...    |  ...  |   ...   |         |        |       |          |
+/// | x_n | active_n | sum_n | count_n |    0    |   1    |   1   |    0     |
+/// | len |          |       |         |         |        |       |          |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// The most values the list can hold.
+pub const N: usize = 4;
+
+#[derive(Debug, Clone)]
+struct VarLenConfig {
+    advice: [Column<Advice>; 4],
+    instance: Column<Instance>,
+    s_first: Selector,
+    s_next: Selector,
+    s_row: Selector,
+    s_prefix: Selector,
+}
+
+#[derive(Debug, Clone)]
+struct VarLenChip<F: Field> {
+    config: VarLenConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> VarLenChip<F> {
+    fn construct(config: VarLenConfig) -> Self {
+        VarLenChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> VarLenConfig {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_first = meta.selector();
+        let s_next = meta.selector();
+        let s_row = meta.selector();
+        let s_prefix = meta.selector();
+        let [x, active, sum, count] = advice;
+        let one = Expression::Constant(F::ONE);
+
+        meta.create_gate("masked row", |meta| {
+            let s = meta.query_selector(s_row);
+            let x = meta.query_advice(x, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            let inactive = one.clone() - active.clone();
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("active is boolean", active * inactive.clone()),
+                    ("padding is zero", inactive * x),
+                ],
+            )
+        });
+
+        meta.create_gate("prefix mask", |meta| {
+            let s = meta.query_selector(s_prefix);
+            let active = meta.query_advice(active, Rotation::cur());
+            let active_next = meta.query_advice(active, Rotation::next());
+            Constraints::with_selector(s, vec![active_next * (one.clone() - active)])
+        });
+
+        meta.create_gate("first row", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let count = meta.query_advice(count, Rotation::cur());
+            Constraints::with_selector(s, vec![sum - x, count - active])
+        });
+
+        meta.create_gate("next row", |meta| {
+            let s = meta.query_selector(s_next);
+            let x = meta.query_advice(x, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let count = meta.query_advice(count, Rotation::cur());
+            let sum_prev = meta.query_advice(sum, Rotation::prev());
+            let count_prev = meta.query_advice(count, Rotation::prev());
+            Constraints::with_selector(s, vec![sum - sum_prev - x, count - count_prev - active])
+        });
+
+        VarLenConfig {
+            advice,
+            instance,
+            s_first,
+            s_next,
+            s_row,
+            s_prefix,
+        }
+    }
+
+    /// Lay out the list under its mask and return the total `(sum, count)`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[Value<F>; N],
+        active: &[Value<F>; N],
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let [x_col, active_col, sum_col, count_col] = self.config.advice;
+        layouter.assign_region(
+            || "masked list",
+            |mut region| {
+                let mut sum = Value::known(F::ZERO);
+                let mut count = Value::known(F::ZERO);
+                let mut cells = None;
+                for row in 0..N {
+                    self.config.s_row.enable(&mut region, row)?;
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_next.enable(&mut region, row)?;
+                    }
+                    if row + 1 < N {
+                        self.config.s_prefix.enable(&mut region, row)?;
+                    }
+                    region.assign_advice(|| "x", x_col, row, || xs[row])?;
+                    region.assign_advice(|| "active", active_col, row, || active[row])?;
+                    sum = sum + xs[row];
+                    count = count + active[row];
+                    cells = Some((
+                        region.assign_advice(|| "sum", sum_col, row, || sum)?,
+                        region.assign_advice(|| "count", count_col, row, || count)?,
+                    ));
+                }
+                Ok(cells.expect("N > 0"))
+            },
+        )
+    }
+
+    /// Witness `len`, constrain it to the count, and expose it and the sum.
+    fn expose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        len: Value<F>,
+        sum: AssignedCell<F, F>,
+        count: AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let len = layouter.assign_region(
+            || "len",
+            |mut region| {
+                let len = region.assign_advice(|| "len", self.config.advice[0], 0, || len)?;
+                region.constrain_equal(len.cell(), count.cell())?;
+                Ok(len)
+            },
+        )?;
+        layouter.constrain_instance(len.cell(), self.config.instance, 0)?;
+        layouter.constrain_instance(sum.cell(), self.config.instance, 1)
+    }
+}
+
+#[derive(Default)]
+struct VarLenCircuit<F: Field> {
+    xs: [Value<F>; N],
+    active: [Value<F>; N],
+    len: Value<F>,
+}
+
+impl<F: Field> Circuit<F> for VarLenCircuit<F> {
+    type Config = VarLenConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        VarLenChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = VarLenChip::construct(config);
+        let (sum, count) = chip.assign(layouter.namespace(|| "list"), &self.xs, &self.active)?;
+        chip.expose(layouter.namespace(|| "public"), self.len, sum, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 4;
+
+    /// `xs` padded with zeros, under the mask of their length.
+    fn circuit(xs: &[u64]) -> VarLenCircuit<Fp> {
+        let mut padded = [0; N];
+        padded[..xs.len()].copy_from_slice(xs);
+        let mut active = [0; N];
+        active[..xs.len()].fill(1);
+        masked(padded, active, xs.len() as u64)
+    }
+
+    fn masked(xs: [u64; N], active: [u64; N], len: u64) -> VarLenCircuit<Fp> {
+        VarLenCircuit {
+            xs: xs.map(|x| Value::known(Fp::from(x))),
+            active: active.map(|a| Value::known(Fp::from(a))),
+            len: Value::known(Fp::from(len)),
+        }
+    }
+
+    fn verify(circuit: &VarLenCircuit<Fp>, len: u64, sum: u64) -> bool {
+        let prover = MockProver::run(K, circuit, vec![vec![Fp::from(len), Fp::from(sum)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_varlen_lengths() {
+        assert!(verify(&circuit(&[]), 0, 0));
+        assert!(verify(&circuit(&[5]), 1, 5));
+        assert!(verify(&circuit(&[5, 6, 7]), 3, 18));
+        assert!(verify(&circuit(&[5, 6, 7, 8]), N as u64, 26));
+    }
+
+    #[test]
+    fn test_varlen_non_prefix_mask() {
+        // Skipping the 6: two active rows, but not the first two.
+        let circuit = masked([5, 0, 7, 0], [1, 0, 1, 0], 2);
+        assert!(!verify(&circuit, 2, 12));
+        let circuit = masked([0, 6, 7, 0], [0, 1, 1, 0], 2);
+        assert!(!verify(&circuit, 2, 13));
+    }
+
+    #[test]
+    fn test_varlen_nonzero_padding() {
+        // A value past the end would still be summed.
+        let circuit = masked([5, 6, 7, 8], [1, 1, 0, 0], 2);
+        assert!(!verify(&circuit, 2, 26));
+        assert!(!verify(&circuit, 2, 11));
+    }
+
+    #[test]
+    fn test_varlen_wrong_length() {
+        let circuit = circuit(&[5, 6, 7]);
+        assert!(!verify(&circuit, 2, 18));
+        assert!(!verify(&circuit, 4, 18));
+        // Nor can the witnessed length disagree with the mask.
+        let circuit = masked([5, 6, 7, 0], [1, 1, 1, 0], 4);
+        assert!(!verify(&circuit, 4, 18));
+    }
+}
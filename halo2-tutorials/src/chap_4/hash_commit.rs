@@ -0,0 +1,279 @@
+/// chap4: hash commitments
+/// Commit to a value with a random blinding factor:
+///
+///   cm = Poseidon(value, randomness)
+///
+/// `cm` hides `value` as long as `randomness` stays secret, and binds it
+/// since a second opening would be a Poseidon collision. A Pedersen
+/// commitment `value * G + randomness * H` does the same with two scalar
+/// multiplications, hundreds of rows; Poseidon takes a few dozen, and no
+/// curve. What it gives up is homomorphism: two hash commitments cannot be
+/// added to commit to the sum.
+///
+/// `commit` computes `cm` from assigned cells, and `open` checks that a
+/// commitment cell opens to a value under a randomness. The two circuits
+/// below use them to publish a commitment, and to reveal what it holds.
+///
+/// | a0         | a1 | a2 | a3 (partial sbox) | rc_a[3] | rc_b[3] | instance |
+/// |------------|----|----|-------------------|---------|---------|----------|
+/// | value      |    |    |                   |         |         |  cm      |
+/// | randomness |    |    |                   |         |         | (value)  |
+/// |      Poseidon permutation rows ...                                      |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{poseidon::configure_pow5, Number};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// Compute the commitment natively.
+pub fn commitment<F: PrimeField>(value: F, randomness: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init()
+        .hash([value, randomness])
+}
+
+#[derive(Debug, Clone)]
+pub struct HashCommitConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+pub struct HashCommitChip<F: PrimeField> {
+    config: HashCommitConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> HashCommitChip<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: HashCommitConfig<F>) -> Self {
+        HashCommitChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> HashCommitConfig<F> {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        HashCommitConfig {
+            advice,
+            poseidon,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "private input", self.config.advice[0], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// Copy a cell of the instance column.
+    pub fn load_instance(
+        &self,
+        mut layouter: impl Layouter<F>,
+        row: usize,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load instance",
+            |mut region| {
+                region
+                    .assign_advice_from_instance(
+                        || "public input",
+                        self.config.instance,
+                        row,
+                        self.config.advice[0],
+                        0,
+                    )
+                    .map(Number)
+            },
+        )
+    }
+
+    /// cm = Poseidon(value, randomness)
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Number<F>,
+        randomness: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher
+            .hash(layouter.namespace(|| "cm"), [value.0, randomness.0])
+            .map(Number)
+    }
+
+    /// Check that `cm` opens to `value` under `randomness`.
+    pub fn open(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cm: &Number<F>,
+        value: Number<F>,
+        randomness: Number<F>,
+    ) -> Result<(), Error> {
+        let expected = self.commit(layouter.namespace(|| "commit"), value, randomness)?;
+        layouter.assign_region(
+            || "cm opens",
+            |mut region| region.constrain_equal(cm.0.cell(), expected.0.cell()),
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: &Number<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), self.config.instance, row)
+    }
+}
+
+/// Commit to a private value: `instance = [cm]`.
+#[derive(Default)]
+pub struct HashCommitCircuit<F: PrimeField> {
+    pub value: Value<F>,
+    pub randomness: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for HashCommitCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = HashCommitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        HashCommitChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = HashCommitChip::construct(config);
+        let value = chip.load_private(layouter.namespace(|| "value"), self.value)?;
+        let randomness = chip.load_private(layouter.namespace(|| "randomness"), self.randomness)?;
+        let cm = chip.commit(layouter.namespace(|| "commit"), value, randomness)?;
+        chip.expose_public(layouter.namespace(|| "cm"), &cm, 0)
+    }
+}
+
+/// Open a public commitment to a public value: `instance = [cm, value]`,
+/// the randomness stays private.
+#[derive(Default)]
+pub struct HashOpenCircuit<F: PrimeField> {
+    pub randomness: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for HashOpenCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = HashCommitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        HashCommitChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = HashCommitChip::construct(config);
+        let cm = chip.load_instance(layouter.namespace(|| "cm"), 0)?;
+        let value = chip.load_instance(layouter.namespace(|| "value"), 1)?;
+        let randomness = chip.load_private(layouter.namespace(|| "randomness"), self.randomness)?;
+        chip.open(layouter.namespace(|| "open"), &cm, value, randomness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 7;
+
+    #[test]
+    fn test_hash_commit() {
+        let (value, randomness) = (Fp::from(42), Fp::from(0xdead_beef));
+        let cm = commitment(value, randomness);
+        // Another blinding factor hides the same value differently.
+        assert_ne!(cm, commitment(value, randomness + Fp::one()));
+
+        let circuit = HashCommitCircuit {
+            value: Value::known(value),
+            randomness: Value::known(randomness),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![cm]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(
+            K,
+            &circuit,
+            vec![vec![commitment(value + Fp::one(), randomness)]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_hash_open() {
+        let (value, randomness) = (Fp::from(42), Fp::from(0xdead_beef));
+        let cm = commitment(value, randomness);
+        let circuit = HashOpenCircuit {
+            randomness: Value::known(randomness),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![cm, value]]).unwrap();
+        prover.assert_satisfied();
+
+        // The commitment does not open to another value.
+        let prover = MockProver::run(K, &circuit, vec![vec![cm, value + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -1,5 +1,13 @@
-mod circuit_1;
-mod circuit_2;
-mod circuit_3;
+pub(crate) mod circuit_1;
+pub(crate) mod circuit_2;
+pub(crate) mod circuit_3;
+mod exercise_charset;
+mod exercise_distance;
+mod exercise_folding_hint;
+mod exercise_gcd;
+mod exercise_hex;
+mod exercise_varlen;
+mod hash_commit;
+mod prng;
 mod table_2;
 mod table_3;
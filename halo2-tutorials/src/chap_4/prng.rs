@@ -0,0 +1,242 @@
+/// chap4: verifiable randomness
+/// A pseudo-random generator whose every step can be proven. From a private
+/// seed `s_0`, each step hashes the state twice, with a different domain tag:
+///
+///   out_i     = Poseidon(s_i, 0)
+///   s_{i + 1} = Poseidon(s_i, 1)
+///
+/// Publishing `out_0, out_1, ...` and a proof convinces a verifier that the
+/// numbers came from one seed without revealing it, and since the state is a
+/// hash of itself, no out_i says anything about the outputs after it.
+///
+/// | a0  | a1  | a2 | a3 (partial sbox) | rc_a[3] | rc_b[3] | instance |
+/// |-----|-----|----|-------------------|---------|---------|----------|
+/// | s_i |  0  |    |                   |         |         |  out_0   |
+/// |      Poseidon permutation rows ...                     |  out_1   |
+/// | s_i |  1  |    |                   |         |         |  ...     |
+/// |      Poseidon permutation rows ...                                |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{poseidon::configure_pow5, Number};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+const OUTPUT_TAG: u64 = 0;
+const STATE_TAG: u64 = 1;
+
+fn hash<F: PrimeField>(state: F, tag: u64) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init()
+        .hash([state, F::from(tag)])
+}
+
+/// Take one step natively, returning the output and the next state.
+pub fn next_native<F: PrimeField>(state: F) -> (F, F)
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    (hash(state, OUTPUT_TAG), hash(state, STATE_TAG))
+}
+
+#[derive(Debug, Clone)]
+pub struct PrngConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+}
+
+pub struct PrngChip<F: PrimeField> {
+    config: PrngConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PrngChip<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: PrngConfig<F>) -> Self {
+        PrngChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PrngConfig<F> {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        // The domain tags go in Poseidon's constant column.
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        PrngConfig { advice, poseidon }
+    }
+
+    pub fn load_seed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seed: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load seed",
+            |mut region| {
+                region
+                    .assign_advice(|| "seed", self.config.advice[0], 0, || seed)
+                    .map(Number)
+            },
+        )
+    }
+
+    fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &Number<F>,
+        tag: u64,
+    ) -> Result<Number<F>, Error> {
+        let advice = self.config.advice;
+        let message = layouter.assign_region(
+            || "message",
+            |mut region| {
+                let state = state.0.copy_advice(|| "state", &mut region, advice[0], 0)?;
+                let tag =
+                    region.assign_advice_from_constant(|| "tag", advice[1], 0, F::from(tag))?;
+                Ok([state, tag])
+            },
+        )?;
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher
+            .hash(layouter.namespace(|| "hash"), message)
+            .map(Number)
+    }
+
+    /// Take one step, returning the output and the next state.
+    pub fn next(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Number<F>,
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let out = self.hash(layouter.namespace(|| "output"), &state, OUTPUT_TAG)?;
+        let state = self.hash(layouter.namespace(|| "state"), &state, STATE_TAG)?;
+        Ok((out, state))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrngCircuitConfig<F: PrimeField> {
+    prng: PrngConfig<F>,
+    instance: Column<Instance>,
+}
+
+/// Prove that the instance column holds the first `steps` outputs from a
+/// private seed.
+#[derive(Default)]
+pub struct PrngCircuit<F: PrimeField> {
+    pub seed: Value<F>,
+    pub steps: usize,
+}
+
+impl<F: PrimeField> Circuit<F> for PrngCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = PrngCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        PrngCircuit {
+            seed: Value::unknown(),
+            steps: self.steps,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PrngCircuitConfig {
+            prng: PrngChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PrngChip::construct(config.prng);
+        let mut state = chip.load_seed(layouter.namespace(|| "seed"), self.seed)?;
+        for row in 0..self.steps {
+            let (out, next) = chip.next(layouter.namespace(|| "step"), state)?;
+            layouter.constrain_instance(out.0.cell(), config.instance, row)?;
+            state = next;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const STEPS: usize = 3;
+
+    fn outputs(seed: Fp) -> Vec<Fp> {
+        let mut state = seed;
+        (0..STEPS)
+            .map(|_| {
+                let (out, next) = next_native(state);
+                state = next;
+                out
+            })
+            .collect()
+    }
+
+    fn prove(seed: Fp, outputs: Vec<Fp>) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 9;
+        let circuit = PrngCircuit {
+            seed: Value::known(seed),
+            steps: STEPS,
+        };
+        MockProver::run(k, &circuit, vec![outputs])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn test_prng_deterministic() {
+        let seed = Fp::from(42);
+        assert_eq!(outputs(seed), outputs(seed));
+        assert!(prove(seed, outputs(seed)).is_ok());
+        assert!(prove(seed, outputs(seed)).is_ok());
+    }
+
+    #[test]
+    fn test_prng_wrong_seed() {
+        let seed = Fp::from(42);
+        assert_ne!(outputs(seed), outputs(seed + Fp::one()));
+        assert!(prove(seed + Fp::one(), outputs(seed)).is_err());
+    }
+
+    #[test]
+    fn test_prng_wrong_output() {
+        let seed = Fp::from(42);
+        let mut outs = outputs(seed);
+        outs[2] += Fp::one();
+        assert!(prove(seed, outs).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+/// chap5: base64 decoding
+/// Prove that a public base64 string decodes to a private payload, and that
+/// the payload is the one committed to by a public MiMC hash:
+///
+///   instance = [mimc_hash(payload), c_0, c_1, ..., c_{4G-1}]
+///
+/// Each group of 4 characters is one row. A character maps to its 6-bit value
+/// through a lookup into the 64-entry alphabet table, and the group's 24 bits
+/// are repacked into 3 bytes with one fixed-coefficient gate:
+///
+///   2^18 v_0 + 2^12 v_1 + 2^6 v_2 + v_3 = 2^16 b_0 + 2^8 b_1 + b_2
+///
+/// With every `v_i < 64` and every `b_i < 256` both sides are the same 24-bit
+/// integer, so the bytes are unique. The last group may be padded, and its
+/// selector picks the case:
+///
+///   q_full: no padding, all four characters are looked up
+///   q_pad1: `xyz=`, c_3 = '=', v_3 = 0, b_2 = 0
+///   q_pad2: `xy==`, c_2 = c_3 = '=', v_2 = v_3 = 0, b_1 = b_2 = 0
+///
+/// The zero bytes also force the unused low bits of the last real character to
+/// zero, so only the canonical encoding is accepted.
+///
+/// | c0..c3 | v0..v3 | b0..b2 | q_full | q_pad1 | q_pad2 | tag | char | value |
+/// |--------|--------|--------|--------|--------|--------|-----|------|-------|
+/// | T W F u| 19 22..| M a n  |   1    |   0    |   0    |  0  |  0   |   0   |
+/// | T Q = =| 19 16..| M 0 0  |   0    |   0    |   1    |  1  | 'A'  |   0   |
+/// |        |        |        |        |        |        | ... | ...  |  ...  |
+///
+/// The table starts with an untagged `(0, 0, 0)` row, which is what a
+/// disabled position looks up.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    byte::{ByteChip, ByteConfig},
+    mimc::{mimc_hash, MimcChip, MimcConfig},
+};
+
+pub const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+pub const PAD: u8 = b'=';
+
+/// The 6-bit value of a base64 character.
+pub fn sextet(c: u8) -> Option<u8> {
+    ALPHABET.iter().position(|a| *a == c).map(|v| v as u8)
+}
+
+/// Base64-encode `payload` natively, with `=` padding.
+pub fn base64_encode(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for chunk in payload.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = ((group[0] as u32) << 16) | ((group[1] as u32) << 8) | group[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize]);
+            } else {
+                out.push(PAD);
+            }
+        }
+    }
+    out
+}
+
+/// `[mimc_hash(payload), c_0, ...]`
+pub fn public_inputs<F: PrimeField>(payload: &[u8], encoded: &[u8]) -> Vec<F> {
+    let payload: Vec<F> = payload.iter().map(|b| F::from(*b as u64)).collect();
+    let mut out = vec![mimc_hash(&payload)];
+    out.extend(encoded.iter().map(|c| F::from(*c as u64)));
+    out
+}
+
+#[derive(Debug, Clone)]
+pub struct Base64Config {
+    chars: [Column<Advice>; 4],
+    sextets: [Column<Advice>; 4],
+    bytes: [Column<Advice>; 3],
+    q_full: Selector,
+    q_pad1: Selector,
+    q_pad2: Selector,
+    t_tag: TableColumn,
+    t_char: TableColumn,
+    t_value: TableColumn,
+    byte: ByteConfig,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+impl Base64Config {
+    /// The untagged `(0, 0, 0)` row, then `(1, ALPHABET[v], v)` for each `v`.
+    fn load_alphabet<F: PrimeField>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "alphabet",
+            |mut table| {
+                let rows = std::iter::once((0, 0, 0)).chain(
+                    ALPHABET
+                        .iter()
+                        .enumerate()
+                        .map(|(v, c)| (1, *c as u64, v as u64)),
+                );
+                for (row, (tag, c, v)) in rows.enumerate() {
+                    table.assign_cell(|| "tag", self.t_tag, row, || Value::known(F::from(tag)))?;
+                    table.assign_cell(|| "char", self.t_char, row, || Value::known(F::from(c)))?;
+                    table.assign_cell(
+                        || "value",
+                        self.t_value,
+                        row,
+                        || Value::known(F::from(v)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Base64Circuit<F: PrimeField> {
+    pub payload: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> Base64Circuit<F> {
+    pub fn new(payload: &[u8]) -> Self {
+        Base64Circuit {
+            payload: payload
+                .iter()
+                .map(|b| Value::known(F::from(*b as u64)))
+                .collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for Base64Circuit<F> {
+    type Config = Base64Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Base64Circuit {
+            payload: vec![Value::unknown(); self.payload.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let chars = [(); 4].map(|_| meta.advice_column());
+        let sextets = [(); 4].map(|_| meta.advice_column());
+        let bytes = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in chars.iter().chain(bytes.iter()) {
+            meta.enable_equality(*col);
+        }
+        let q_full = meta.complex_selector();
+        let q_pad1 = meta.complex_selector();
+        let q_pad2 = meta.complex_selector();
+        let t_tag = meta.lookup_table_column();
+        let t_char = meta.lookup_table_column();
+        let t_value = meta.lookup_table_column();
+
+        meta.create_gate("repack", |meta| {
+            let q_any = meta.query_selector(q_full)
+                + meta.query_selector(q_pad1)
+                + meta.query_selector(q_pad2);
+            let [v0, v1, v2, v3] = sextets.map(|col| meta.query_advice(col, Rotation::cur()));
+            let [b0, b1, b2] = bytes.map(|col| meta.query_advice(col, Rotation::cur()));
+            let c = |x: u64| Expression::Constant(F::from(x));
+            Constraints::with_selector(
+                q_any,
+                vec![
+                    c(1 << 18) * v0 + c(1 << 12) * v1 + c(1 << 6) * v2 + v3
+                        - (c(1 << 16) * b0 + c(1 << 8) * b1 + b2),
+                ],
+            )
+        });
+
+        meta.create_gate("one padding char", |meta| {
+            let q = meta.query_selector(q_pad1);
+            let c3 = meta.query_advice(chars[3], Rotation::cur());
+            let v3 = meta.query_advice(sextets[3], Rotation::cur());
+            let b2 = meta.query_advice(bytes[2], Rotation::cur());
+            let pad = Expression::Constant(F::from(PAD as u64));
+            Constraints::with_selector(q, vec![c3 - pad, v3, b2])
+        });
+
+        meta.create_gate("two padding chars", |meta| {
+            let q = meta.query_selector(q_pad2);
+            let c2 = meta.query_advice(chars[2], Rotation::cur());
+            let c3 = meta.query_advice(chars[3], Rotation::cur());
+            let v2 = meta.query_advice(sextets[2], Rotation::cur());
+            let v3 = meta.query_advice(sextets[3], Rotation::cur());
+            let b1 = meta.query_advice(bytes[1], Rotation::cur());
+            let b2 = meta.query_advice(bytes[2], Rotation::cur());
+            let pad = Expression::Constant(F::from(PAD as u64));
+            Constraints::with_selector(q, vec![c2 - pad.clone(), c3 - pad, v2, v3, b1, b2])
+        });
+
+        // Position i is a real character in every case that does not pad it.
+        for i in 0..4 {
+            meta.lookup(|meta| {
+                let mut enabled = meta.query_selector(q_full);
+                if i < 3 {
+                    enabled = enabled + meta.query_selector(q_pad1);
+                }
+                if i < 2 {
+                    enabled = enabled + meta.query_selector(q_pad2);
+                }
+                let c = meta.query_advice(chars[i], Rotation::cur());
+                let v = meta.query_advice(sextets[i], Rotation::cur());
+                vec![
+                    (enabled.clone(), t_tag),
+                    (enabled.clone() * c, t_char),
+                    (enabled * v, t_value),
+                ]
+            });
+        }
+
+        Base64Config {
+            chars,
+            sextets,
+            bytes,
+            q_full,
+            q_pad1,
+            q_pad2,
+            t_tag,
+            t_char,
+            t_value,
+            byte: ByteChip::configure(meta, chars[0]),
+            mimc: MimcChip::configure(meta, [chars[0], chars[1], chars[2]], constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let byte = ByteChip::construct(config.byte.clone());
+        let mimc = MimcChip::construct(config.mimc.clone());
+        config.load_alphabet(&mut layouter)?;
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        let payload = self
+            .payload
+            .iter()
+            .map(|b| byte.assign_byte(layouter.namespace(|| "payload byte"), *b))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for (i, group) in payload.chunks(3).enumerate() {
+            layouter.assign_region(
+                || format!("group {}", i),
+                |mut region| {
+                    match group.len() {
+                        3 => config.q_full.enable(&mut region, 0)?,
+                        2 => config.q_pad1.enable(&mut region, 0)?,
+                        _ => config.q_pad2.enable(&mut region, 0)?,
+                    }
+                    for j in 0..4 {
+                        let c = region.assign_advice_from_instance(
+                            || "char",
+                            config.instance,
+                            1 + 4 * i + j,
+                            config.chars[j],
+                            0,
+                        )?;
+                        // Characters outside the alphabet, and padding, get 0;
+                        // the lookup or the padding gate decides.
+                        let v = c.value().map(|c| {
+                            let c = c.to_repr().as_ref()[0];
+                            F::from(sextet(c).unwrap_or(0) as u64)
+                        });
+                        region.assign_advice(|| "sextet", config.sextets[j], 0, || v)?;
+                    }
+                    for j in 0..3 {
+                        match group.get(j) {
+                            Some(b) => {
+                                b.0.copy_advice(|| "byte", &mut region, config.bytes[j], 0)?;
+                            }
+                            None => {
+                                region.assign_advice(
+                                    || "pad",
+                                    config.bytes[j],
+                                    0,
+                                    || Value::known(F::ZERO),
+                                )?;
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+
+        let commitment = mimc.hash(layouter.namespace(|| "commit"), &payload)?;
+        layouter.constrain_instance(commitment.0.cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prove(payload: &[u8], encoded: &[u8]) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 10;
+        let circuit = Base64Circuit::<Fp>::new(payload);
+        let prover = MockProver::run(k, &circuit, vec![public_inputs(payload, encoded)]).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_base64_no_padding() {
+        assert_eq!(base64_encode(b"Man"), b"TWFu");
+        assert!(prove(b"Man", b"TWFu").is_ok());
+        assert_eq!(base64_encode(b"foobar"), b"Zm9vYmFy");
+        assert!(prove(b"foobar", b"Zm9vYmFy").is_ok());
+    }
+
+    #[test]
+    fn test_base64_one_padding_char() {
+        assert_eq!(base64_encode(b"Ma"), b"TWE=");
+        assert!(prove(b"Ma", b"TWE=").is_ok());
+        // Lenient decoders read "TWF=" as "Ma" too, but its unused low bits
+        // are not zero.
+        assert!(prove(b"Ma", b"TWF=").is_err());
+    }
+
+    #[test]
+    fn test_base64_two_padding_chars() {
+        assert_eq!(base64_encode(b"M"), b"TQ==");
+        assert!(prove(b"M", b"TQ==").is_ok());
+        assert!(prove(b"M", b"TQ=A").is_err());
+    }
+
+    #[test]
+    fn test_base64_invalid_char() {
+        assert!(prove(b"Man", b"TW*u").is_err());
+    }
+
+    #[test]
+    fn test_base64_wrong_commitment() {
+        let k = 10;
+        let circuit = Base64Circuit::<Fp>::new(b"Man");
+        let mut public_inputs = public_inputs::<Fp>(b"Man", b"TWFu");
+        public_inputs[0] += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+/// chap5: Diffie-Hellman shared secret
+/// Prove that the shared secret derived from a private exponent `a` and the
+/// other party's public value `B` hashes to a public digest, and that `a` is
+/// the exponent behind our own public value `A`:
+///
+///   A = g^a,    S = B^a,    digest = mimc_hash([S])
+///
+///   instance = [A, B, digest]
+///
+/// This is how a party shows it holds the session key agreed with `B`
+/// without revealing `a` or `S`. Both exponentiations must use the same `a`:
+/// the first one decomposes `a` into bits, and the second one copies those bit
+/// cells instead of decomposing `a` again, so there is nothing to swap in.
+///
+/// | a0    | a1   | a2    | a3    | instance |
+/// |-------|------|-------|-------|----------|
+/// | bit_i |  g   | acc_i | exp_i |    A     |
+/// |     ... g^a rows ...          |    B     |
+/// | bit_i |  B   | acc_i | exp_i |  digest  |
+/// |     ... B^a rows, bits copied ...        |
+/// |     ... mimc rows ...         |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    mimc::{MimcChip, MimcConfig},
+    pow::{PowChip, PowConfig},
+};
+
+const A_BITS: usize = 64;
+
+/// `(A, S)` for the private exponent `a` and the other party's `B`, natively.
+pub fn derive<F: PrimeField>(a: u64, b: F) -> (F, F) {
+    (
+        F::MULTIPLICATIVE_GENERATOR.pow_vartime([a]),
+        b.pow_vartime([a]),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct DhConfig {
+    arith: ArithConfig,
+    pow: PowConfig,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct DhCircuit<F: PrimeField> {
+    pub a: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for DhCircuit<F> {
+    type Config = DhConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let three = [advice[0], advice[1], advice[2]];
+        DhConfig {
+            arith: ArithChip::configure(meta, three, constant),
+            pow: PowChip::configure(meta, advice, constant),
+            mimc: MimcChip::configure(meta, three, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith);
+        let pow = PowChip::construct(config.pow);
+        let mimc = MimcChip::construct(config.mimc);
+
+        let g = arith.load_constant(layouter.namespace(|| "g"), F::MULTIPLICATIVE_GENERATOR)?;
+        let b = arith.load_instance(layouter.namespace(|| "B"), config.instance, 1)?;
+        let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+
+        let (a_pub, bits) = pow.pow_with_bits(layouter.namespace(|| "g^a"), g, a, A_BITS)?;
+        let (s, _) = pow.pow_by_bits(layouter.namespace(|| "B^a"), b, &bits)?;
+        let digest = mimc.hash(layouter.namespace(|| "hash S"), &[s])?;
+
+        arith.expose_public(layouter.namespace(|| "A"), a_pub, config.instance, 0)?;
+        arith.expose_public(layouter.namespace(|| "digest"), digest, config.instance, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::mimc::mimc_hash;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn b() -> Fp {
+        Fp::MULTIPLICATIVE_GENERATOR.pow_vartime([4321u64])
+    }
+
+    fn circuit(a: u64) -> DhCircuit<Fp> {
+        DhCircuit {
+            a: Value::known(Fp::from(a)),
+        }
+    }
+
+    #[test]
+    fn test_dh_consistent() {
+        let a = 1_234_567;
+        let (a_pub, s) = derive(a, b());
+        let instance = vec![a_pub, b(), mimc_hash(&[s])];
+        let prover = MockProver::run(K, &circuit(a), vec![instance]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    /// `DhCircuit` with the bits of `forged` under the copies of `a`'s bits
+    /// in `B^a`.
+    struct ForgedCircuit {
+        a: Value<Fp>,
+        forged: u64,
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = DhConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ForgedCircuit {
+                a: Value::unknown(),
+                forged: self.forged,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DhCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let pow = PowChip::construct(config.pow);
+            let mimc = MimcChip::construct(config.mimc);
+
+            let g =
+                arith.load_constant(layouter.namespace(|| "g"), Fp::MULTIPLICATIVE_GENERATOR)?;
+            let b = arith.load_instance(layouter.namespace(|| "B"), config.instance, 1)?;
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+
+            let (a_pub, bits) = pow.pow_with_bits(layouter.namespace(|| "g^a"), g, a, A_BITS)?;
+            let forged: Vec<_> = (0..A_BITS)
+                .rev()
+                .map(|i| Value::known(Fp::from((self.forged >> i) & 1)))
+                .collect();
+            let (s, _) = pow.pow_by_bits_with(layouter.namespace(|| "B^a"), b, &bits, &forged)?;
+            let digest = mimc.hash(layouter.namespace(|| "hash S"), &[s])?;
+
+            arith.expose_public(layouter.namespace(|| "A"), a_pub, config.instance, 0)?;
+            arith.expose_public(layouter.namespace(|| "digest"), digest, config.instance, 2)
+        }
+    }
+
+    #[test]
+    fn test_dh_inconsistent_exponents() {
+        use crate::utils::failures::expect_failures;
+
+        // A is g^a1 and the digest that of B^a2, each computed honestly from
+        // its own bits: only the copies of a1's bits into B^a catch it.
+        let (a1, a2) = (1_234_567, 7_654_321);
+        let (a_pub, _) = derive(a1, b());
+        let (_, s) = derive(a2, b());
+        let instance = vec![a_pub, b(), mimc_hash(&[s])];
+        let forged = ForgedCircuit {
+            a: Value::known(Fp::from(a1)),
+            forged: a2,
+        };
+        expect_failures(
+            K,
+            &forged,
+            vec![instance],
+            &["Equality constraint not satisfied"],
+        );
+
+        // Under the same bits the copies hold.
+        let (_, s) = derive(a1, b());
+        let instance = vec![a_pub, b(), mimc_hash(&[s])];
+        let honest = ForgedCircuit {
+            a: Value::known(Fp::from(a1)),
+            forged: a1,
+        };
+        expect_failures(K, &honest, vec![instance], &[]);
+    }
+
+    #[test]
+    fn test_dh_wrong_digest() {
+        let a = 1_234_567;
+        let (a_pub, s) = derive(a, b());
+        let instance = vec![a_pub, b(), mimc_hash(&[s + Fp::one()])];
+        let prover = MockProver::run(K, &circuit(a), vec![instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
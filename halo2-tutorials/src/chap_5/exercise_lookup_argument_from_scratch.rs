@@ -0,0 +1,283 @@
+/// chap5: a lookup argument from scratch
+/// `meta.lookup` proves that every input value is an entry of a table. Under
+/// the hood that is a statement about multisets: the inputs `a_i` are the
+/// table entries `t_j`, each taken `m_j` times. Multisets are equal when the
+/// polynomials that have them as roots are, so with a challenge `gamma`
+///
+///   prod_i (a_i + gamma) = prod_j (t_j + gamma)^{m_j}
+///
+/// By Schwartz-Zippel, if the multisets differ the two sides are different
+/// polynomials of degree at most `N + T * 2^M_BITS`, and agree on a random
+/// `gamma` with probability at most that degree over `p`. Here the left side
+/// is a `GrandProductChip` running product over the inputs, and the right a
+/// `PowChip` power per table entry, the multiplicities being witnessed and
+/// range-checked by their bits.
+///
+/// The argument is only sound if `gamma` is drawn after the prover is bound
+/// to `a` and `m`. halo2 draws its own lookup challenges from the transcript
+/// once the advice is committed; `halo2_proofs` 0.3 has no API to hand such
+/// a challenge to a circuit, so this one takes `gamma` as a public input and
+/// the tests draw it after the witness. A prover who knows `gamma` first
+/// solves the equation for one input and passes any value: see
+/// `test_known_gamma`, which `meta.lookup` catches.
+///
+/// | a0   | a1    | a2    | a3  |
+/// |------|-------|-------|-----|
+/// | gamma, a_i, t_j, m_j loads  |
+/// | a_i  | gamma | acc_i |     |   GrandProductChip, per input
+/// | t_j + gamma                 |   ArithChip, per entry
+/// | bit  | base  | acc   | exp |   PowChip, per entry and bit
+/// | the powers multiplied, then num = den
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    grand_product::{GrandProductChip, GrandProductConfig},
+    pow::{PowChip, PowConfig},
+};
+
+/// The table; a disabled `meta.lookup` row looks up 0, so it is an entry.
+pub const TABLE: [u64; 5] = [0, 2, 3, 5, 7];
+/// Bits of a multiplicity.
+pub const M_BITS: usize = 3;
+
+/// How often each table entry occurs in `values`.
+pub fn multiplicities(values: &[u64]) -> Vec<u64> {
+    TABLE
+        .iter()
+        .map(|t| values.iter().filter(|v| *v == t).count() as u64)
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ScratchLookupConfig {
+    arith: ArithConfig,
+    grand_product: GrandProductConfig,
+    pow: PowConfig,
+    instance: Column<Instance>,
+}
+
+/// Check that `values` are table entries with a grand product, for the
+/// public `gamma`.
+#[derive(Default)]
+pub struct ScratchLookupCircuit<F: PrimeField> {
+    pub values: Vec<Value<F>>,
+    pub multiplicities: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> Circuit<F> for ScratchLookupCircuit<F> {
+    type Config = ScratchLookupConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ScratchLookupCircuit {
+            values: vec![Value::unknown(); self.values.len()],
+            multiplicities: vec![Value::unknown(); self.multiplicities.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ScratchLookupConfig {
+            arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+            grand_product: GrandProductChip::configure(meta, [advice[0], advice[1], advice[2]]),
+            pow: PowChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        assert_eq!(self.multiplicities.len(), TABLE.len());
+        let arith = ArithChip::construct(config.arith);
+        let grand_product = GrandProductChip::construct(config.grand_product);
+        let pow = PowChip::construct(config.pow);
+
+        let gamma = arith.load_instance(layouter.namespace(|| "gamma"), config.instance, 0)?;
+        let values = self
+            .values
+            .iter()
+            .map(|v| arith.load_private(layouter.namespace(|| "a_i"), *v))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // prod_i (a_i + gamma)
+        let num = grand_product.product(layouter.namespace(|| "num"), &values, &gamma)?;
+
+        // prod_j (t_j + gamma)^{m_j}
+        let mut den = None;
+        for (t, m) in TABLE.iter().zip(&self.multiplicities) {
+            let t = arith.load_constant(layouter.namespace(|| "t_j"), F::from(*t))?;
+            let m = arith.load_private(layouter.namespace(|| "m_j"), *m)?;
+            let base = arith.add(layouter.namespace(|| "t_j + gamma"), t, gamma.clone())?;
+            let power = pow.pow(layouter.namespace(|| "^m_j"), base, m, M_BITS)?;
+            den = Some(match den {
+                None => power,
+                Some(den) => arith.mul(layouter.namespace(|| "den"), den, power)?,
+            });
+        }
+
+        arith.assert_equal(layouter.namespace(|| "num = den"), num, den.unwrap())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetaLookupConfig {
+    value: Column<Advice>,
+    table: TableColumn,
+    q_lookup: Selector,
+}
+
+/// The same check through `meta.lookup`.
+#[derive(Default)]
+pub struct MetaLookupCircuit<F: PrimeField> {
+    pub values: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> Circuit<F> for MetaLookupCircuit<F> {
+    type Config = MetaLookupConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MetaLookupCircuit {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        let table = meta.lookup_table_column();
+        let q_lookup = meta.complex_selector();
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let v = meta.query_advice(value, Rotation::cur());
+            vec![(q * v, table)]
+        });
+        MetaLookupConfig {
+            value,
+            table,
+            q_lookup,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "table",
+            |mut table| {
+                for (row, t) in TABLE.iter().enumerate() {
+                    table.assign_cell(|| "t", config.table, row, || Value::known(F::from(*t)))?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "values",
+            |mut region| {
+                for (row, v) in self.values.iter().enumerate() {
+                    config.q_lookup.enable(&mut region, row)?;
+                    region.assign_advice(|| "a_i", config.value, row, || *v)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+    use rand_core::OsRng;
+
+    const K: u32 = 7;
+
+    fn known(xs: &[Fp]) -> Vec<Value<Fp>> {
+        xs.iter().map(|x| Value::known(*x)).collect()
+    }
+
+    fn scratch_ok(values: &[Fp], multiplicities: &[u64], gamma: Fp) -> bool {
+        let m: Vec<Fp> = multiplicities.iter().map(|m| Fp::from(*m)).collect();
+        let circuit = ScratchLookupCircuit {
+            values: known(values),
+            multiplicities: known(&m),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![gamma]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn meta_ok(values: &[Fp]) -> bool {
+        let circuit = MetaLookupCircuit {
+            values: known(values),
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn fps(values: &[u64]) -> Vec<Fp> {
+        values.iter().map(|v| Fp::from(*v)).collect()
+    }
+
+    #[test]
+    fn test_lookup_from_scratch() {
+        let values = [3, 5, 5, 7, 2, 3];
+        // gamma is drawn once the witness is fixed.
+        let gamma = Fp::random(OsRng);
+        assert!(scratch_ok(&fps(&values), &multiplicities(&values), gamma));
+        assert!(meta_ok(&fps(&values)));
+    }
+
+    #[test]
+    fn test_lookup_from_scratch_not_in_table() {
+        // 4 is not an entry, whatever the multiplicities claim.
+        let values = [3, 5, 4, 7, 2, 3];
+        let gamma = Fp::random(OsRng);
+        assert!(!scratch_ok(&fps(&values), &multiplicities(&values), gamma));
+        assert!(!scratch_ok(
+            &fps(&values),
+            &multiplicities(&[3, 5, 5, 7, 2, 3]),
+            gamma
+        ));
+        assert!(!meta_ok(&fps(&values)));
+    }
+
+    #[test]
+    fn test_lookup_from_scratch_wrong_multiplicities() {
+        let values = [3, 5, 5, 7, 2, 3];
+        let gamma = Fp::random(OsRng);
+        assert!(!scratch_ok(
+            &fps(&values),
+            &multiplicities(&[3, 5, 7, 7, 2, 3]),
+            gamma
+        ));
+    }
+
+    #[test]
+    fn test_known_gamma() {
+        // Knowing gamma, the prover claims one more 2 and one more 3 than
+        // the other inputs hold, and solves for the first input:
+        //   a_0 + gamma = (2 + gamma)(3 + gamma)
+        let gamma = Fp::from(1000);
+        let rest = [5, 5, 7, 2];
+        let m = multiplicities(&[5, 5, 7, 2, 2, 3]);
+        let a_0 = (Fp::from(2) + gamma) * (Fp::from(3) + gamma) - gamma;
+        let mut values = vec![a_0];
+        values.extend(fps(&rest));
+
+        assert!(scratch_ok(&values, &m, gamma));
+        assert!(!meta_ok(&values));
+    }
+}
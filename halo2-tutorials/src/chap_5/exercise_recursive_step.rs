@@ -0,0 +1,240 @@
+/// chap5: one step of incrementally verifiable computation
+/// IVC proves a long computation one step at a time: step `i` takes the
+/// public state `x_i` left by step `i - 1`, a private input `w_i`, and
+/// publishes the next state. Here a step is
+///
+///   x_{i + 1} = Poseidon(x_i, w_i)
+///
+/// and the circuit exposes `x_prev` and `x_next` as its two instance values.
+/// Real IVC (Nova, Halo's accumulation) also verifies the previous proof
+/// inside the step; this exercise only shows the state threading. Running
+/// several steps in one circuit, the `x_next` cell of step `i` is copied into
+/// the `x_prev` cell of step `i + 1`, which is the in-circuit analogue of
+/// feeding one proof's public output to the next proof's public input.
+///
+/// | a0     | a1  | a2 | a3 (partial sbox) | rc_a[3] | rc_b[3] | instance |
+/// |--------|-----|----|-------------------|---------|---------|----------|
+/// | x_0    |     |    |                   |         |         |  x_prev  |
+/// | x_0'   | w_0 |    |                   |         |         |  x_next  |
+/// |      Poseidon permutation rows ...                                |
+/// | x_1'   | w_1 |    |                   |         |         |          |
+/// |      Poseidon permutation rows ...                                |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{poseidon::configure_pow5, Number};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+const X_PREV_ROW: usize = 0;
+const X_NEXT_ROW: usize = 1;
+
+/// Take one step natively.
+pub fn step_native<F: PrimeField>(x_prev: F, w: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash([x_prev, w])
+}
+
+#[derive(Debug, Clone)]
+pub struct StepConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+pub struct StepChip<F: PrimeField> {
+    config: StepConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> StepChip<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: StepConfig<F>) -> Self {
+        StepChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> StepConfig<F> {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        StepConfig {
+            advice,
+            poseidon,
+            instance,
+        }
+    }
+
+    /// Read the public starting state `x_prev` from the instance column.
+    pub fn load_state(&self, mut layouter: impl Layouter<F>) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load x_prev",
+            |mut region| {
+                region
+                    .assign_advice_from_instance(
+                        || "x_prev",
+                        self.config.instance,
+                        X_PREV_ROW,
+                        self.config.advice[0],
+                        0,
+                    )
+                    .map(Number)
+            },
+        )
+    }
+
+    /// x_next = Poseidon(x_prev, w). `x_prev` is copied in, so chaining
+    /// `step` calls links each step's output to the next step's input.
+    pub fn step(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x_prev: &Number<F>,
+        w: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        let advice = self.config.advice;
+        let message = layouter.assign_region(
+            || "step input",
+            |mut region| {
+                let x_prev = x_prev.0.copy_advice(|| "x_prev", &mut region, advice[0], 0)?;
+                let w = region.assign_advice(|| "w", advice[1], 0, || w)?;
+                Ok([x_prev, w])
+            },
+        )?;
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher
+            .hash(layouter.namespace(|| "x_next"), message)
+            .map(Number)
+    }
+
+    pub fn expose_next(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x_next: Number<F>,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(x_next.0.cell(), self.config.instance, X_NEXT_ROW)
+    }
+}
+
+/// Prove `x_next` follows from `x_prev` after one step per private input in
+/// `ws`. With a single input this is exactly one IVC step.
+///
+/// instance = [x_prev, x_next]
+#[derive(Default)]
+pub struct StepCircuit<F: PrimeField> {
+    pub ws: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> Circuit<F> for StepCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = StepConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        StepCircuit {
+            ws: vec![Value::unknown(); self.ws.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        StepChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = StepChip::construct(config);
+        let mut x = chip.load_state(layouter.namespace(|| "x_prev"))?;
+        for &w in self.ws.iter() {
+            x = chip.step(layouter.namespace(|| "step"), &x, w)?;
+        }
+        chip.expose_next(layouter.namespace(|| "x_next"), x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn ws() -> Vec<Fp> {
+        vec![Fp::from(3), Fp::from(5), Fp::from(7)]
+    }
+
+    fn circuit(ws: &[Fp]) -> StepCircuit<Fp> {
+        StepCircuit {
+            ws: ws.iter().map(|&w| Value::known(w)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_recursive_step_chained_proofs() {
+        // Three separate one-step proofs, where each step's public x_next is
+        // the next step's public x_prev.
+        let mut x = Fp::from(1);
+        for w in ws() {
+            let x_next = step_native(x, w);
+            let prover = MockProver::run(K, &circuit(&[w]), vec![vec![x, x_next]]).unwrap();
+            prover.assert_satisfied();
+            x = x_next;
+        }
+    }
+
+    #[test]
+    fn test_recursive_step_three_steps() {
+        let x_0 = Fp::from(1);
+        let x_3 = ws().into_iter().fold(x_0, step_native);
+
+        let prover = MockProver::run(K, &circuit(&ws()), vec![vec![x_0, x_3]]).unwrap();
+        prover.assert_satisfied();
+
+        // Stopping one step early is not the final state.
+        let x_2 = ws()[..2].iter().fold(x_0, |x, &w| step_native(x, w));
+        let prover = MockProver::run(K, &circuit(&ws()), vec![vec![x_0, x_2]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_recursive_step_wrong_input() {
+        let x_0 = Fp::from(1);
+        let x_3 = ws().into_iter().fold(x_0, step_native);
+
+        let mut wrong = ws();
+        wrong[1] += Fp::one();
+        let prover = MockProver::run(K, &circuit(&wrong), vec![vec![x_0, x_3]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        let prover =
+            MockProver::run(K, &circuit(&ws()), vec![vec![x_0 + Fp::one(), x_3]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,178 @@
+/// chap5: Shamir secret sharing
+/// A secret `s` is split with a random polynomial of degree `t - 1`,
+///
+///   p(x) = s + a_1 x + ... + a_{t-1} x^{t-1},    share_i = (i, p(i))
+///
+/// and any `t` shares pin `p` down, so `s = p(0)`. Prove that `t` public
+/// shares reconstruct to a secret whose MiMC hash is public, without the
+/// secret ever appearing in the instance column:
+///
+///   instance = [x_0, y_0, ..., x_{t-1}, y_{t-1}, mimc_hash([p(0)])]
+///
+/// `LagrangeChip` evaluates the interpolating polynomial at `z = 0`. Its
+/// inverse gates reject two shares with the same `x`, which would not pin
+/// `p` down, and a share at `x = 0`, which would be the secret itself.
+///
+/// | a0 | a1  | a2   | constant | instance |
+/// |----|-----|------|----------|----------|
+/// | x_i|     |      |          |   x_0    |
+/// | y_i|     |      |          |   y_0    |
+/// |  ... lagrange rows ...     |   ...    |
+/// |  ... mimc rows ...         |  digest  |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    lagrange::{LagrangeChip, LagrangeConfig},
+    mimc::{MimcChip, MimcConfig},
+};
+
+/// The shares `(x, p(x))` for `x = 1..=n`, where `coeffs` holds `p`'s
+/// coefficients from the constant term (the secret) up.
+pub fn share<F: PrimeField>(coeffs: &[F], n: u64) -> Vec<(F, F)> {
+    (1..=n)
+        .map(|x| {
+            let x = F::from(x);
+            let y = coeffs.iter().rev().fold(F::ZERO, |acc, c| acc * x + c);
+            (x, y)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ShamirConfig {
+    lagrange: LagrangeConfig,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+/// Everything is public except the reconstructed secret, which is computed in
+/// the circuit, so there is no witness to carry.
+#[derive(Default)]
+pub struct ShamirCircuit<F: PrimeField, const T: usize> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const T: usize> Circuit<F> for ShamirCircuit<F, T> {
+    type Config = ShamirConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ShamirConfig {
+            lagrange: LagrangeChip::configure(meta, advice, constant),
+            mimc: MimcChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let lagrange = LagrangeChip::construct(config.lagrange);
+        let mimc = MimcChip::construct(config.mimc);
+        let arith = lagrange.arith();
+
+        let shares = (0..T)
+            .map(|i| {
+                let x = arith.load_instance(layouter.namespace(|| "x"), config.instance, 2 * i)?;
+                let y =
+                    arith.load_instance(layouter.namespace(|| "y"), config.instance, 2 * i + 1)?;
+                Ok((x, y))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let zero = arith.load_constant(layouter.namespace(|| "z = 0"), F::ZERO)?;
+        let secret = lagrange.evaluate(layouter.namespace(|| "p(0)"), &shares, zero)?;
+        let digest = mimc.hash(layouter.namespace(|| "hash secret"), &[secret])?;
+        arith.expose_public(
+            layouter.namespace(|| "digest"),
+            digest,
+            config.instance,
+            2 * T,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::mimc::mimc_hash;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 8;
+    const T: usize = 3;
+
+    fn secret() -> Fp {
+        Fp::from(1234)
+    }
+
+    /// A 3-of-5 sharing of `secret()`.
+    fn shares() -> Vec<(Fp, Fp)> {
+        share(&[secret(), Fp::from(166), Fp::from(94)], 5)
+    }
+
+    fn instance(shares: &[(Fp, Fp)], secret: Fp) -> Vec<Fp> {
+        shares
+            .iter()
+            .flat_map(|(x, y)| [*x, *y])
+            .chain([mimc_hash(&[secret])])
+            .collect()
+    }
+
+    fn verify(shares: &[(Fp, Fp)], secret: Fp) -> bool {
+        let circuit = ShamirCircuit::<Fp, T>::default();
+        let prover = MockProver::run(K, &circuit, vec![instance(shares, secret)]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_shamir_reconstruct() {
+        let shares = shares();
+        assert!(verify(&[shares[0], shares[2], shares[4]], secret()));
+        assert!(verify(&[shares[3], shares[1], shares[0]], secret()));
+        assert!(!verify(
+            &[shares[0], shares[2], shares[4]],
+            secret() + Fp::one()
+        ));
+    }
+
+    #[test]
+    fn test_shamir_corrupted_share() {
+        let shares = shares();
+        let mut corrupted = [shares[0], shares[2], shares[4]];
+        corrupted[1].1 += Fp::one();
+        assert!(!verify(&corrupted, secret()));
+    }
+
+    #[test]
+    fn test_shamir_duplicate_x() {
+        let shares = shares();
+        assert!(!verify(&[shares[0], shares[0], shares[4]], secret()));
+    }
+
+    #[test]
+    fn test_shamir_other_polynomial() {
+        // A different sharing of a different secret cannot open `secret()`.
+        let other = share(&[Fp::from(4321), Fp::from(7), Fp::from(11)], 5);
+        assert!(!verify(&[other[0], other[2], other[4]], secret()));
+        assert!(verify(&[other[0], other[2], other[4]], Fp::from(4321)));
+    }
+}
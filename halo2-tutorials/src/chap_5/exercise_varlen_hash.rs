@@ -0,0 +1,439 @@
+/// chap5: hashing a variable-length message
+/// Hash a private message of `len <= MAX_LEN` bytes, `len` itself a witness,
+/// and publish
+///
+///   instance = [digest, len]
+///
+/// The message is padded the way Merkle-Damgard hashes do it:
+///
+///   msg || 0x80 || 0x00 .. 0x00 || len
+///
+/// with just enough zeros for a whole number of `B`-byte blocks. Each block is
+/// packed big-endian into one field element and absorbed by the MiMC chain.
+/// The `0x80` marks where the message ends, and the length byte keeps
+/// messages that differ only in trailing zeros apart.
+///
+/// The padding is done by the circuit, not trusted from the prover. As in the
+/// variable-length sum of chapter 4, the circuit has room for `BLOCKS`
+/// blocks, and a prefix mask `active` says which bytes are the message. The
+/// byte after the last active one is the `0x80`, and a second flag `end`
+/// marks the length byte. It may only be set on the last byte of a block,
+/// and only of the block that also holds byte `len + 1`:
+///
+///   end_i * active_{i-1}           = 0    the 0x80 comes before the length
+///   end_i * (1 - active_{i-B-1})   = 0    and not a whole block before it
+///
+/// The second constraint only applies from the second block on. With exactly
+/// one `end` set, each padded byte is
+///
+///   p_i = m_i + (active_{i-1} - active_i) * 0x80 + end_i * len
+///
+/// All `BLOCKS` blocks are hashed, whatever the length. The digest is the
+/// chain value after the block holding `end`, selected as
+/// `sum_j end_j * h_{j+1}`. A message of `MAX_LEN` bytes fills every block;
+/// a longer one would leave no room for the length byte.
+///
+/// | a0  | a1       | a2    | a3  | a4  | a5    | a6      | s_byte | s_start | s_mid | s_end | s_late |
+/// |-----|----------|-------|-----|-----|-------|---------|--------|---------|-------|-------|--------|
+/// |     | 1        |       |     |     |       | 0       |        |         |       |       |        |
+/// | m_0 | active_0 | end_0 | len | p_0 | acc_0 | count_0 |   1    |    1    |   0   |   0   |   0    |
+/// | m_1 | active_1 | end_1 | len | p_1 | acc_1 | count_1 |   1    |    0    |   1   |   0   |   0    |
+/// | ... |   ...    |  ...  | ... | ... |  ...  |   ...   |        |         |       |       |        |
+/// | m_7 | active_7 | end_7 | len | p_7 | acc_7 | count_7 |   1    |    0    |   0   |   1   |   1    |
+///
+/// The first row holds the constants for the byte before the message: it
+/// counts as active, and the count starts at zero. `acc` packs each block,
+/// and its value on a block's last row is what the chain absorbs.
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    mimc::{mimc_hash, MimcChip, MimcConfig},
+    Number,
+};
+
+/// Bytes in a block.
+pub const B: usize = 4;
+/// Blocks the circuit hashes.
+pub const BLOCKS: usize = 3;
+/// Bytes of padded message.
+pub const P: usize = B * BLOCKS;
+/// The longest message: it leaves room for the `0x80` and the length only.
+pub const MAX_LEN: usize = P - 2;
+
+/// `msg || 0x80 || 0x00 .. 0x00 || len`, in whole blocks.
+pub fn pad(msg: &[u8]) -> Vec<u8> {
+    assert!(msg.len() <= MAX_LEN, "message too long");
+    let mut out = msg.to_vec();
+    out.push(0x80);
+    while (out.len() + 1) % B != 0 {
+        out.push(0);
+    }
+    out.push(msg.len() as u8);
+    out
+}
+
+/// The MiMC chain over the blocks of an already padded message.
+pub fn hash_padded<F: PrimeField>(padded: &[u8]) -> F {
+    let blocks: Vec<F> = padded
+        .chunks(B)
+        .map(|block| {
+            block
+                .iter()
+                .fold(F::ZERO, |acc, p| acc * F::from(256) + F::from(*p as u64))
+        })
+        .collect();
+    mimc_hash(&blocks)
+}
+
+/// The reference hasher.
+pub fn varlen_hash<F: PrimeField>(msg: &[u8]) -> F {
+    hash_padded(&pad(msg))
+}
+
+#[derive(Debug, Clone)]
+pub struct VarLenHashConfig {
+    advice: [Column<Advice>; 7],
+    s_byte: Selector,
+    s_start: Selector,
+    s_mid: Selector,
+    s_end: Selector,
+    s_late: Selector,
+    arith: ArithConfig,
+    byte: ByteConfig,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+/// The cells of the padding region the rest of the circuit needs.
+struct Padded<F: PrimeField> {
+    msg: Vec<Number<F>>,
+    /// `end` on the last byte of each block.
+    ends: Vec<Number<F>>,
+    /// The packed blocks.
+    blocks: Vec<Number<F>>,
+    count: AssignedCell<F, F>,
+}
+
+/// The witness, one entry per padded byte: the message `m` (zero past its
+/// end), the two masks, and the padded bytes `p`.
+#[derive(Default)]
+pub struct VarLenHashCircuit<F: PrimeField> {
+    pub m: [Value<F>; P],
+    pub active: [Value<F>; P],
+    pub end: [Value<F>; P],
+    pub padded: [Value<F>; P],
+    pub len: Value<F>,
+}
+
+impl<F: PrimeField> VarLenHashCircuit<F> {
+    /// The honest witness for `msg`.
+    pub fn new(msg: &[u8]) -> Self {
+        let padded = pad(msg);
+        let last = padded.len() - 1;
+        let byte = |b: u8| Value::known(F::from(b as u64));
+        let flag = |b: bool| Value::known(F::from(b as u64));
+        VarLenHashCircuit {
+            m: std::array::from_fn(|i| byte(msg.get(i).copied().unwrap_or(0))),
+            active: std::array::from_fn(|i| flag(i < msg.len())),
+            end: std::array::from_fn(|i| flag(i == last)),
+            padded: std::array::from_fn(|i| byte(padded.get(i).copied().unwrap_or(0))),
+            len: byte(msg.len() as u8),
+        }
+    }
+
+    fn assign_padding(
+        &self,
+        config: &VarLenHashConfig,
+        mut layouter: impl Layouter<F>,
+        len: &Number<F>,
+    ) -> Result<Padded<F>, Error> {
+        let [m_col, active_col, end_col, len_col, p_col, acc_col, count_col] = config.advice;
+        layouter.assign_region(
+            || "padding",
+            |mut region| {
+                region.assign_advice_from_constant(|| "active_-1", active_col, 0, F::ONE)?;
+                let mut count =
+                    region.assign_advice_from_constant(|| "count_-1", count_col, 0, F::ZERO)?;
+                let mut acc = Value::known(F::ZERO);
+                let (mut msg, mut ends, mut blocks) = (vec![], vec![], vec![]);
+                for i in 0..P {
+                    let row = i + 1;
+                    config.s_byte.enable(&mut region, row)?;
+                    match i % B {
+                        0 => config.s_start.enable(&mut region, row)?,
+                        t if t < B - 1 => config.s_mid.enable(&mut region, row)?,
+                        _ => {
+                            config.s_end.enable(&mut region, row)?;
+                            if i >= B {
+                                config.s_late.enable(&mut region, row)?;
+                            }
+                        }
+                    }
+                    let m = region.assign_advice(|| "m", m_col, row, || self.m[i])?;
+                    region.assign_advice(|| "active", active_col, row, || self.active[i])?;
+                    let end = region.assign_advice(|| "end", end_col, row, || self.end[i])?;
+                    len.0.copy_advice(|| "len", &mut region, len_col, row)?;
+                    region.assign_advice(|| "p", p_col, row, || self.padded[i])?;
+                    if i % B == 0 {
+                        acc = Value::known(F::ZERO);
+                    }
+                    acc = acc * Value::known(F::from(256)) + self.padded[i];
+                    let block = region.assign_advice(|| "acc", acc_col, row, || acc)?;
+                    let value = count.value().copied() + self.active[i];
+                    count = region.assign_advice(|| "count", count_col, row, || value)?;
+                    msg.push(Number(m));
+                    if i % B == B - 1 {
+                        ends.push(Number(end));
+                        blocks.push(Number(block));
+                    }
+                }
+                Ok(Padded {
+                    msg,
+                    ends,
+                    blocks,
+                    count,
+                })
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for VarLenHashCircuit<F> {
+    type Config = VarLenHashConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 7].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let s_byte = meta.selector();
+        let s_start = meta.selector();
+        let s_mid = meta.selector();
+        let s_end = meta.selector();
+        let s_late = meta.selector();
+        let [m, active, end, len, p, acc, count] = advice;
+        let one = Expression::Constant(F::ONE);
+        let base = Expression::Constant(F::from(256));
+
+        meta.create_gate("padded byte", |meta| {
+            let s = meta.query_selector(s_byte);
+            let m = meta.query_advice(m, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            let active_prev = meta.query_advice(active, Rotation::prev());
+            let end = meta.query_advice(end, Rotation::cur());
+            let len = meta.query_advice(len, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            let count = meta.query_advice(count, Rotation::cur());
+            let count_prev = meta.query_advice(count, Rotation::prev());
+            let inactive = one.clone() - active.clone();
+            let marker =
+                (active_prev.clone() - active.clone()) * Expression::Constant(F::from(0x80));
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("active is boolean", active.clone() * inactive.clone()),
+                    ("end is boolean", end.clone() * (one.clone() - end.clone())),
+                    ("prefix mask", active.clone() * (one.clone() - active_prev)),
+                    ("message is zero past len", inactive * m.clone()),
+                    ("count", count - count_prev - active),
+                    ("padding", p - m - marker - end * len),
+                ],
+            )
+        });
+
+        meta.create_gate("block start", |meta| {
+            let s = meta.query_selector(s_start);
+            let end = meta.query_advice(end, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            Constraints::with_selector(s, vec![("acc = p", acc - p), ("no end", end)])
+        });
+
+        meta.create_gate("block middle", |meta| {
+            let s = meta.query_selector(s_mid);
+            let end = meta.query_advice(end, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("acc = 256 * acc' + p", acc - base.clone() * acc_prev - p),
+                    ("no end", end),
+                ],
+            )
+        });
+
+        meta.create_gate("block end", |meta| {
+            let s = meta.query_selector(s_end);
+            let end = meta.query_advice(end, Rotation::cur());
+            let active_prev = meta.query_advice(active, Rotation::prev());
+            let p = meta.query_advice(p, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("acc = 256 * acc' + p", acc - base.clone() * acc_prev - p),
+                    ("0x80 before the length", end * active_prev),
+                ],
+            )
+        });
+
+        meta.create_gate("no empty block", |meta| {
+            let s = meta.query_selector(s_late);
+            let end = meta.query_advice(end, Rotation::cur());
+            let active_back = meta.query_advice(active, Rotation(-(B as i32) - 1));
+            Constraints::with_selector(s, vec![end * (one.clone() - active_back)])
+        });
+
+        let arith_advice = [advice[0], advice[1], advice[2]];
+        VarLenHashConfig {
+            advice,
+            s_byte,
+            s_start,
+            s_mid,
+            s_end,
+            s_late,
+            arith: ArithChip::configure(meta, arith_advice, constant),
+            byte: ByteChip::configure(meta, advice[0]),
+            mimc: MimcChip::configure(meta, arith_advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith.clone());
+        let byte = ByteChip::construct(config.byte.clone());
+        let mimc = MimcChip::construct(config.mimc.clone());
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        let len = arith.load_private(layouter.namespace(|| "len"), self.len)?;
+        let padded = self.assign_padding(&config, layouter.namespace(|| "padding"), &len)?;
+        arith.assert_equal(
+            layouter.namespace(|| "len = count"),
+            len.clone(),
+            Number(padded.count),
+        )?;
+        for m in padded.msg {
+            byte.check_byte(layouter.namespace(|| "byte"), m)?;
+        }
+
+        // Exactly one block holds the length.
+        let mut ends = padded.ends.iter().cloned();
+        let first = ends.next().expect("BLOCKS > 0");
+        let total = ends.try_fold(first, |sum, end| {
+            arith.add(layouter.namespace(|| "sum end"), sum, end)
+        })?;
+        let one = arith.load_constant(layouter.namespace(|| "1"), F::ONE)?;
+        arith.assert_equal(layouter.namespace(|| "one end"), total, one)?;
+
+        // Hash every block, and keep the chain value after the last one.
+        let mut h = arith.load_constant(layouter.namespace(|| "iv"), F::ZERO)?;
+        let mut digest = None;
+        for (j, (block, end)) in padded.blocks.into_iter().zip(padded.ends).enumerate() {
+            h = mimc.compress(layouter.namespace(|| format!("block {}", j)), h, block)?;
+            let term = arith.mul(layouter.namespace(|| "end * h"), end, h.clone())?;
+            digest = Some(match digest {
+                None => term,
+                Some(sum) => arith.add(layouter.namespace(|| "digest"), sum, term)?,
+            });
+        }
+
+        let digest = digest.expect("BLOCKS > 0");
+        arith.expose_public(layouter.namespace(|| "digest"), digest, config.instance, 0)?;
+        arith.expose_public(layouter.namespace(|| "len"), len, config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn verify(circuit: &VarLenHashCircuit<Fp>, digest: Fp, len: usize) -> bool {
+        let public = vec![digest, Fp::from(len as u64)];
+        let prover = MockProver::run(K, circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn check(msg: &[u8]) -> bool {
+        verify(&VarLenHashCircuit::new(msg), varlen_hash(msg), msg.len())
+    }
+
+    #[test]
+    fn test_varlen_hash_lengths() {
+        // The message and its padding exactly fill a block.
+        assert_eq!(pad(b"hi"), [b'h', b'i', 0x80, 2]);
+        assert!(check(b"hi"));
+        // A message filling a block needs a whole block of padding.
+        assert_eq!(pad(b"abcd").len(), 2 * B);
+        assert!(check(b"abcd"));
+        assert_eq!(pad(b""), [0x80, 0, 0, 0]);
+        assert!(check(b""));
+        assert_eq!(pad(&[7; MAX_LEN]).len(), P);
+        assert!(check(&[7; MAX_LEN]));
+    }
+
+    #[test]
+    fn test_varlen_hash_public_inputs() {
+        let circuit = VarLenHashCircuit::new(b"abc");
+        assert!(!verify(&circuit, varlen_hash(b"abc"), 4));
+        assert!(!verify(&circuit, varlen_hash(b"abd"), 3));
+        // Trailing zeros are part of the message.
+        assert_ne!(varlen_hash::<Fp>(b"ab"), varlen_hash::<Fp>(b"ab\0"));
+        assert!(!verify(&circuit, varlen_hash(b"ab\0"), 3));
+    }
+
+    /// Replace the padded bytes of `circuit` and set `end` on byte `last`.
+    fn repad(circuit: &mut VarLenHashCircuit<Fp>, padded: &[u8], last: usize) {
+        let byte = |b: u8| Value::known(Fp::from(b as u64));
+        circuit.padded = std::array::from_fn(|i| byte(padded.get(i).copied().unwrap_or(0)));
+        circuit.end = std::array::from_fn(|i| Value::known(Fp::from((i == last) as u64)));
+    }
+
+    #[test]
+    fn test_varlen_hash_bad_padding() {
+        // Each forgery is checked against the hash of what it pads to, so
+        // only the padding constraints can catch it.
+        let forge = |padded: &[u8], last: usize| {
+            let mut circuit = VarLenHashCircuit::new(b"hi");
+            repad(&mut circuit, padded, last);
+            verify(&circuit, hash_padded(padded), 2)
+        };
+        // The honest padding passes this way too.
+        assert!(forge(&[b'h', b'i', 0x80, 2], 3));
+        // No 0x80.
+        assert!(!forge(&[b'h', b'i', 0, 2], 3));
+        // The wrong length.
+        assert!(!forge(&[b'h', b'i', 0x80, 3], 3));
+        // An extra block of zeros before the length.
+        assert!(!forge(&[b'h', b'i', 0x80, 0, 0, 0, 0, 2], 7));
+
+        // A message filling its block cannot squeeze the padding in.
+        let mut circuit = VarLenHashCircuit::new(b"abcd");
+        repad(&mut circuit, &[b'a', b'b', b'c', b'd'], 3);
+        assert!(!verify(&circuit, hash_padded(b"abcd"), 4));
+    }
+}
@@ -0,0 +1,287 @@
+/// chap5: histogram
+/// Prove the bin counts of `N` private bytes over `BINS` equal-width bins,
+/// bin `b` holding `[b * W, (b + 1) * W)` with `W = 256 / BINS`. The counts
+/// are public; the values are not.
+///
+/// The repo has no range-to-bin chip and no summing chip, so the bin of a
+/// value comes from comparisons with the inner edges, and the counting from
+/// running totals. With `below_j = (v < j * W)`, and `below_0 = 0`,
+/// `below_BINS = 1` as constants,
+///
+///   in_b = below_{b+1} - below_b
+///
+/// is 1 for the value's bin and 0 for the others: `below_j` only goes up as
+/// `j` does, and `v` is range-checked to a byte first so `LtChip` is sound.
+/// The count of bin `b` is then the last running total of `in_b` over all
+/// values, through `PrefixSumChip`.
+///
+/// | a0 | a1   | a2       | a3   |
+/// |----|------|----------|------|
+/// | v  |      |          |      |   byte check
+/// | v  | edge | below_j  | diff |   LtChip, per inner edge
+/// | .. |      |          |      |   in_b = below_{b+1} - below_b
+/// |in_b| acc  |          |      |   PrefixSumChip, per bin
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::Layouter, pasta::group::ff::PrimeField, plonk::*};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    lt::{LtChip, LtConfig},
+    prefix_sum::{PrefixSumChip, PrefixSumConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct HistogramConfig {
+    byte: ByteConfig,
+    lt: LtConfig,
+    arith: ArithConfig,
+    prefix_sum: PrefixSumConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistogramChip<F: PrimeField, const N: usize, const BINS: usize> {
+    config: HistogramConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N: usize, const BINS: usize> HistogramChip<F, N, BINS> {
+    /// The width of a bin.
+    pub const WIDTH: u64 = 256 / BINS as u64;
+
+    pub fn construct(config: HistogramConfig) -> Self {
+        HistogramChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> HistogramConfig {
+        assert!(
+            BINS > 0 && 256 % BINS == 0,
+            "BINS must divide the 256 byte values"
+        );
+        let byte = ByteChip::configure(meta, advice[0]);
+        let lt = LtChip::configure(meta, advice, &byte);
+        let arith = ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant);
+        let prefix_sum = PrefixSumChip::configure(meta, [advice[0], advice[1]]);
+        HistogramConfig {
+            byte,
+            lt,
+            arith,
+            prefix_sum,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        ByteChip::construct(self.config.byte.clone()).load_table(layouter)
+    }
+
+    pub fn arith(&self) -> ArithChip<F> {
+        ArithChip::construct(self.config.arith.clone())
+    }
+
+    /// The bin a value falls in, natively.
+    pub fn bin_of(value: u8) -> usize {
+        (value as u64 / Self::WIDTH) as usize
+    }
+
+    /// The `BINS` counts of `values`, bin 0 first.
+    pub fn histogram(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Number<F>; N],
+    ) -> Result<Vec<Number<F>>, Error> {
+        let byte = ByteChip::construct(self.config.byte.clone());
+        let lt = LtChip::construct(self.config.lt.clone());
+        let prefix_sum = PrefixSumChip::construct(self.config.prefix_sum.clone());
+        let arith = self.arith();
+
+        let zero = arith.load_constant(layouter.namespace(|| "below_0"), F::ZERO)?;
+        let one = arith.load_constant(layouter.namespace(|| "below_BINS"), F::ONE)?;
+        let edges = (1..BINS as u64)
+            .map(|j| arith.load_constant(layouter.namespace(|| "edge"), F::from(j * Self::WIDTH)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // in_b for every value, indexed [bin][value].
+        let mut ins = vec![Vec::with_capacity(N); BINS];
+        for (i, v) in values.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("value {}", i));
+            byte.check_byte(layouter.namespace(|| "byte"), v.clone())?;
+
+            let mut below = vec![zero.clone()];
+            for edge in &edges {
+                below.push(lt.less_than(
+                    layouter.namespace(|| "v < edge"),
+                    v.clone(),
+                    edge.clone(),
+                )?);
+            }
+            below.push(one.clone());
+
+            for (bin, pair) in below.windows(2).enumerate() {
+                let in_bin = arith.sub(
+                    layouter.namespace(|| "in_b"),
+                    pair[1].clone(),
+                    pair[0].clone(),
+                )?;
+                ins[bin].push(in_bin);
+            }
+        }
+
+        ins.iter()
+            .enumerate()
+            .map(|(bin, ins)| {
+                let totals =
+                    prefix_sum.prefix_sum(layouter.namespace(|| format!("bin {}", bin)), ins)?;
+                Ok(totals.last().expect("N > 0").clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::{metadata, FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+    };
+
+    const N: usize = 8;
+    const BINS: usize = 4;
+
+    type Chip = HistogramChip<Fp, N, BINS>;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        histogram: HistogramConfig,
+        instance: Column<Instance>,
+    }
+
+    /// Load the values privately and expose the counts, bin `b` at row `b`.
+    #[derive(Default)]
+    struct MyCircuit {
+        values: [Value<Fp>; N],
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                histogram: Chip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = Chip::construct(config.histogram);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let arith = chip.arith();
+            let mut values = Vec::with_capacity(N);
+            for v in self.values {
+                values.push(arith.load_private(layouter.namespace(|| "value"), v)?);
+            }
+            let values: [Number<Fp>; N] = values.try_into().unwrap();
+            let counts = chip.histogram(layouter.namespace(|| "histogram"), &values)?;
+            for (row, count) in counts.into_iter().enumerate() {
+                arith.expose_public(layouter.namespace(|| "count"), count, config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    const VALUES: [u8; N] = [3, 70, 200, 64, 63, 130, 255, 0];
+
+    fn circuit(values: [u64; N]) -> MyCircuit {
+        MyCircuit {
+            values: values.map(|v| Value::known(Fp::from(v))),
+        }
+    }
+
+    fn counts(values: [u8; N]) -> Vec<u64> {
+        let mut counts = vec![0; BINS];
+        for v in values {
+            counts[Chip::bin_of(v)] += 1;
+        }
+        counts
+    }
+
+    fn instance(counts: &[u64]) -> Vec<Vec<Fp>> {
+        vec![counts.iter().map(|c| Fp::from(*c)).collect()]
+    }
+
+    #[test]
+    fn test_histogram() {
+        let k = 9;
+        let counts = counts(VALUES);
+        assert_eq!(counts, vec![3, 2, 1, 2]);
+        let circuit = circuit(VALUES.map(u64::from));
+        MockProver::run(k, &circuit, instance(&counts))
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_histogram_wrong_count() {
+        let k = 9;
+        let circuit = circuit(VALUES.map(u64::from));
+        for bin in 0..BINS {
+            let mut wrong = counts(VALUES);
+            wrong[bin] += 1;
+            let prover = MockProver::run(k, &circuit, instance(&wrong)).unwrap();
+            let errors = prover.verify().unwrap_err();
+
+            // Only the copy into the reported bin's instance row breaks.
+            let instance_rows: Vec<usize> = errors
+                .iter()
+                .filter_map(|e| match e {
+                    VerifyFailure::Permutation {
+                        column,
+                        location: FailureLocation::OutsideRegion { row },
+                    } if *column == metadata::Column::from((Any::Instance, 0)) => Some(*row),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(instance_rows, vec![bin]);
+        }
+    }
+
+    #[test]
+    fn test_histogram_not_byte() {
+        let k = 9;
+        // 256 is past the last bin; the byte check rejects it before any
+        // comparison is trusted.
+        let mut values = VALUES.map(u64::from);
+        values[0] = 256;
+        let circuit = circuit(values);
+        let prover = MockProver::run(k, &circuit, instance(&[2, 2, 1, 2])).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,322 @@
+/// chap5: linear constraints
+/// Prove that private `x_0, ..., x_{N-1}` satisfy
+///
+///   a_0 * x_0 + a_1 * x_1 + ... + a_{N-1} * x_{N-1} = b
+///
+/// for coefficients `a_i` fixed by the circuit and a public `b`. Verifiable
+/// inference and linear programming reduce to many such rows.
+///
+/// The coefficients live in a fixed column, so they are part of the
+/// verifying key: a circuit for other coefficients is another circuit. The
+/// products are taken from an advice copy of each coefficient, next to its
+/// input and the running sum, the way `PrefixSumChip` scans:
+///
+///   acc_0 = a_0 * x_0,    acc_i = acc_{i-1} + a_i * x_i
+///
+/// and every row checks its copy against the fixed column. Without that
+/// check the advice `a_i` is just another witness, and the prover solves for
+/// any `b` with the coefficients of their choice.
+///
+/// | a0  | a1  | a2    | coeff | s_first | s_scan | instance |
+/// |-----|-----|-------|-------|---------|--------|----------|
+/// | x_i |     |       |       |         |        |    b     |
+/// | x_0 | a_0 | acc_0 |  a_0  |    1    |   0    |          |
+/// | x_1 | a_1 | acc_1 |  a_1  |    0    |   1    |          |
+/// | ... | ... |  ...  |  ...  |         |        |          |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+pub struct LinearConstraintConfig {
+    advice: [Column<Advice>; 3],
+    coeff: Column<Fixed>,
+    instance: Column<Instance>,
+    s_first: Selector,
+    s_scan: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearConstraintChip<F: PrimeField, const N: usize> {
+    config: LinearConstraintConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N: usize> LinearConstraintChip<F, N> {
+    pub fn construct(config: LinearConstraintConfig) -> Self {
+        LinearConstraintChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        coeff: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> LinearConstraintConfig {
+        assert!(N > 0, "an empty sum has no cell");
+        meta.enable_equality(instance);
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let s_first = meta.selector();
+        let s_scan = meta.selector();
+        let [x, a, acc] = advice;
+
+        meta.create_gate("acc_0 = a_0 * x_0", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let coeff = meta.query_fixed(coeff, Rotation::cur());
+            Constraints::with_selector(s, vec![a.clone() - coeff, acc - a * x])
+        });
+
+        meta.create_gate("acc_i = acc_{i-1} + a_i * x_i", |meta| {
+            let s = meta.query_selector(s_scan);
+            let x = meta.query_advice(x, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let prev = meta.query_advice(acc, Rotation::prev());
+            let coeff = meta.query_fixed(coeff, Rotation::cur());
+            Constraints::with_selector(s, vec![a.clone() - coeff, acc - (prev + a * x)])
+        });
+
+        LinearConstraintConfig {
+            advice,
+            coeff,
+            instance,
+            s_first,
+            s_scan,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "private input", self.config.advice[0], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// `coeffs[0] * xs[0] + ... + coeffs[N - 1] * xs[N - 1]`, with the
+    /// coefficients fixed.
+    pub fn sum(
+        &self,
+        layouter: impl Layouter<F>,
+        coeffs: &[F; N],
+        xs: &[Number<F>; N],
+    ) -> Result<Number<F>, Error> {
+        self.assign_terms(layouter, coeffs, coeffs.map(Value::known), xs)
+    }
+
+    /// Like `sum`, but with the advice copies of the coefficients given
+    /// separately, so the tests can make them disagree with the fixed ones.
+    fn assign_terms(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[F; N],
+        witnesses: [Value<F>; N],
+        xs: &[Number<F>; N],
+    ) -> Result<Number<F>, Error> {
+        let [x_col, a_col, acc_col] = self.config.advice;
+        layouter.assign_region(
+            || "linear combination",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut last = None;
+                for (row, ((coeff, a), x)) in coeffs.iter().zip(witnesses).zip(xs).enumerate() {
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_scan.enable(&mut region, row)?;
+                    }
+                    region.assign_fixed(
+                        || "coeff",
+                        self.config.coeff,
+                        row,
+                        || Value::known(*coeff),
+                    )?;
+                    let a = region.assign_advice(|| "a", a_col, row, || a)?;
+                    let x = x.0.copy_advice(|| "x", &mut region, x_col, row)?;
+                    acc = acc + a.value().copied() * x.value();
+                    last = Some(region.assign_advice(|| "acc", acc_col, row, || acc)?);
+                }
+                Ok(Number(last.unwrap()))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: &Number<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), self.config.instance, row)
+    }
+}
+
+/// `coeffs . xs = b` for private `xs` and the public `b`.
+pub struct LinearConstraintCircuit<F: PrimeField, const N: usize> {
+    pub coeffs: [F; N],
+    pub xs: [Value<F>; N],
+}
+
+impl<F: PrimeField, const N: usize> Circuit<F> for LinearConstraintCircuit<F, N> {
+    type Config = LinearConstraintConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The coefficients are part of the circuit, not of the witness.
+        LinearConstraintCircuit {
+            coeffs: self.coeffs,
+            xs: [Value::unknown(); N],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let coeff = meta.fixed_column();
+        let instance = meta.instance_column();
+        LinearConstraintChip::<F, N>::configure(meta, advice, coeff, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = LinearConstraintChip::<F, N>::construct(config);
+        let mut xs = Vec::with_capacity(N);
+        for x in self.xs {
+            xs.push(chip.load_private(layouter.namespace(|| "x_i"), x)?);
+        }
+        let xs: [Number<F>; N] = xs.try_into().unwrap();
+        let b = chip.sum(layouter.namespace(|| "sum"), &self.coeffs, &xs)?;
+        chip.expose_public(layouter.namespace(|| "b"), &b, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    fn int(x: i64) -> Fp {
+        if x < 0 {
+            -Fp::from(x.unsigned_abs())
+        } else {
+            Fp::from(x as u64)
+        }
+    }
+
+    /// 3 * x_0 - 2 * x_1 + 5 * x_2 + x_3
+    const COEFFS: [i64; 4] = [3, -2, 5, 1];
+
+    fn circuit(xs: [i64; 4]) -> LinearConstraintCircuit<Fp, 4> {
+        LinearConstraintCircuit {
+            coeffs: COEFFS.map(int),
+            xs: xs.map(|x| Value::known(int(x))),
+        }
+    }
+
+    #[test]
+    fn test_linear_constraint() {
+        // 3 * 1 - 2 * 2 + 5 * 3 + 4 = 18
+        let prover = MockProver::run(K, &circuit([1, 2, 3, 4]), vec![vec![int(18)]]).unwrap();
+        prover.assert_satisfied();
+
+        // Negative inputs and sums are field elements like any other.
+        // 3 * -4 - 2 * 7 + 5 * 0 + 9 = -17
+        let prover = MockProver::run(K, &circuit([-4, 7, 0, 9]), vec![vec![int(-17)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(K, &circuit([1, 2, 3, 4]), vec![vec![int(19)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// Sum with the fixed `COEFFS`, but multiply by `witnesses` in advice.
+    struct TamperedCircuit {
+        witnesses: [Value<Fp>; 4],
+        xs: [Value<Fp>; 4],
+    }
+
+    impl Circuit<Fp> for TamperedCircuit {
+        type Config = LinearConstraintConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TamperedCircuit {
+                witnesses: [Value::unknown(); 4],
+                xs: [Value::unknown(); 4],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            LinearConstraintCircuit::<Fp, 4>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = LinearConstraintChip::<Fp, 4>::construct(config);
+            let mut xs = Vec::new();
+            for x in self.xs {
+                xs.push(chip.load_private(layouter.namespace(|| "x_i"), x)?);
+            }
+            let b = chip.assign_terms(
+                layouter.namespace(|| "sum"),
+                &COEFFS.map(int),
+                self.witnesses,
+                &xs.try_into().unwrap(),
+            )?;
+            chip.expose_public(layouter.namespace(|| "b"), &b, 0)
+        }
+    }
+
+    #[test]
+    fn test_linear_constraint_tampered_coefficient() {
+        let xs = [1, 2, 3, 4];
+        let circuit = TamperedCircuit {
+            witnesses: COEFFS.map(|a| Value::known(int(a))),
+            xs: xs.map(|x| Value::known(int(x))),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![int(18)]]).unwrap();
+        prover.assert_satisfied();
+
+        // Each coefficient in turn: the running sum is consistent with the
+        // changed copy and b follows it, only the fixed column disagrees.
+        for i in 0..COEFFS.len() {
+            let mut witnesses = COEFFS;
+            witnesses[i] += 1;
+            let b: i64 = witnesses.iter().zip(xs).map(|(a, x)| a * x).sum();
+            let circuit = TamperedCircuit {
+                witnesses: witnesses.map(|a| Value::known(int(a))),
+                xs: xs.map(|x| Value::known(int(x))),
+            };
+            let prover = MockProver::run(K, &circuit, vec![vec![int(b)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,13 @@
+mod exercise_base64;
+mod exercise_dh;
+mod exercise_lookup_argument_from_scratch;
+mod exercise_recursive_step;
+mod exercise_shamir;
+mod exercise_varlen_hash;
+mod histogram;
+mod linear_constraint;
+mod msm;
+mod scalar_mul;
+pub(crate) mod schnorr;
+pub(crate) mod signed_mul;
+mod xor8;
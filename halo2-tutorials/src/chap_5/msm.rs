@@ -0,0 +1,212 @@
+/// chap5: fixed-base multi-scalar multiplication
+/// `Σ [k_i]B_i` for fixed points `B_i` of Pallas and private scalars `k_i`,
+/// each given as its bits, most significant first. This is the shape of
+/// every commitment check: a Pedersen commitment is `[m]G + [r]H`, and a
+/// polynomial commitment opening sums many more terms.
+///
+/// The bases are fixed, so they are loaded as constants and cost the prover
+/// no witness. Each term is one `scalar_mul` of `WindowMulChip`, and the
+/// terms are summed with its `add`:
+///
+///   acc = [k_0]B_0,    acc = acc + [k_i]B_i
+///
+/// That addition is incomplete, like the one inside `scalar_mul`: no term
+/// may be the identity, so no scalar may be `0`, and no partial sum may be
+/// `±` the next term. For independent bases and scalars chosen before the
+/// bases, that happens with negligible probability.
+///
+/// Sharing the doublings between terms, as Straus' and Pippenger's methods
+/// do, would save most of the work; this gadget keeps the terms apart.
+use halo2_proofs::{
+    circuit::Layouter,
+    pasta::pallas,
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed},
+};
+
+use super::scalar_mul::{WindowMulChip, WindowMulConfig};
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+pub struct MsmConfig {
+    window_mul: WindowMulConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct MsmChip {
+    config: MsmConfig,
+}
+
+impl MsmChip {
+    pub fn construct(config: MsmConfig) -> Self {
+        MsmChip { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 8],
+        constant: Column<Fixed>,
+    ) -> MsmConfig {
+        MsmConfig {
+            window_mul: WindowMulChip::configure(meta, advice, constant),
+        }
+    }
+
+    pub fn window_mul(&self) -> WindowMulChip {
+        WindowMulChip::construct(self.config.window_mul.clone())
+    }
+
+    /// `Σ [scalars[i]]bases[i]`, every scalar as bits, most significant
+    /// first, in whole windows.
+    pub fn msm(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        scalars: &[Vec<Number<pallas::Base>>],
+        bases: &[pallas::Affine],
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        assert_eq!(scalars.len(), bases.len(), "one scalar per base");
+        assert!(!bases.is_empty(), "an empty sum is the identity");
+        let chip = self.window_mul();
+        let mut acc = None;
+        for (i, (bits, base)) in scalars.iter().zip(bases).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("term {}", i));
+            let base = chip.load_constant_point(layouter.namespace(|| "B_i"), *base)?;
+            let term = chip.scalar_mul(layouter.namespace(|| "[k_i]B_i"), bits, base)?;
+            acc = Some(match acc {
+                None => term,
+                Some(acc) => chip.add(layouter.namespace(|| "acc + [k_i]B_i"), acc, term)?,
+            });
+        }
+        Ok(acc.expect("bases is not empty"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_6::exercise_pedersen::coordinates;
+    use halo2_proofs::{
+        arithmetic::CurveExt,
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::group::{prime::PrimeCurveAffine, Curve, Group},
+        plonk::{Circuit, Instance},
+    };
+
+    const K: u32 = 9;
+    const BITS: usize = 8;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        msm: MsmConfig,
+        instance: Column<Instance>,
+    }
+
+    /// `[k_0]G + [k_1]H` exposed as `[x, y]`.
+    #[derive(Default)]
+    struct MyCircuit {
+        scalars: Vec<Vec<Value<pallas::Base>>>,
+    }
+
+    fn bases() -> [pallas::Affine; 2] {
+        let h = pallas::Point::hash_to_curve("halo2-step-by-step:msm")(b"H");
+        [pallas::Affine::generator(), h.to_affine()]
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                scalars: vec![vec![Value::unknown(); BITS]; self.scalars.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = [(); 8].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                msm: MsmChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let chip = MsmChip::construct(config.msm);
+            let arith = chip.window_mul().arith();
+            let scalars = self
+                .scalars
+                .iter()
+                .map(|bits| {
+                    bits.iter()
+                        .map(|b| arith.load_private(layouter.namespace(|| "bit"), *b))
+                        .collect::<Result<Vec<_>, Error>>()
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let [x, y] = chip.msm(layouter.namespace(|| "msm"), &scalars, &bases())?;
+            arith.expose_public(layouter.namespace(|| "x"), x, config.instance, 0)?;
+            arith.expose_public(layouter.namespace(|| "y"), y, config.instance, 1)
+        }
+    }
+
+    /// The `BITS` low bits of `k`, most significant first.
+    fn bits(k: u64) -> Vec<u64> {
+        (0..BITS).rev().map(|i| (k >> i) & 1).collect()
+    }
+
+    fn circuit(scalars: &[Vec<u64>]) -> MyCircuit {
+        MyCircuit {
+            scalars: scalars
+                .iter()
+                .map(|bits| {
+                    bits.iter()
+                        .map(|b| Value::known(pallas::Base::from(*b)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// `[k_0]G + [k_1]H` natively.
+    fn msm(k: [u64; 2]) -> Vec<pallas::Base> {
+        let sum = bases()
+            .iter()
+            .zip(k)
+            .map(|(base, k)| base.to_curve() * pallas::Scalar::from(k))
+            .fold(pallas::Point::identity(), |acc, term| acc + term);
+        coordinates(sum.to_affine()).to_vec()
+    }
+
+    #[test]
+    fn test_msm() {
+        let k = [0b1010_0110, 0b0000_0011];
+        let circuit = circuit(&[bits(k[0]), bits(k[1])]);
+        MockProver::run(K, &circuit, vec![msm(k)])
+            .unwrap()
+            .assert_satisfied();
+
+        // The terms do not commute across bases.
+        let prover = MockProver::run(K, &circuit, vec![msm([k[1], k[0]])]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_msm_wrong_bit() {
+        let k = [0b1010_0110, 0b0000_0011];
+        // One bit of k_1 flipped, checked against the honest sum.
+        let mut wrong = bits(k[1]);
+        wrong[BITS - 1] ^= 1;
+        let circuit = circuit(&[bits(k[0]), wrong]);
+        let prover = MockProver::run(K, &circuit, vec![msm(k)]).unwrap();
+        assert!(prover.verify().is_err());
+        // The flipped scalar's own sum still verifies.
+        let prover = MockProver::run(K, &circuit, vec![msm([k[0], k[1] ^ 1])]).unwrap();
+        prover.assert_satisfied();
+    }
+}
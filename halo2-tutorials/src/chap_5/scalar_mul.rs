@@ -0,0 +1,365 @@
+/// chap5: fixed-window scalar multiplication
+/// `[k]P` for a private point `P` of Pallas and a private scalar `k` given
+/// as its bits, most significant first. The bits are taken `WINDOW` at a
+/// time:
+///
+///   acc = 2^WINDOW * acc + k_i * P,    k_i = the i-th window
+///
+/// so a window costs `WINDOW` doublings but a single addition, of a table
+/// entry `T[k_i] = k_i * P` picked by the window's bits with `CondSwapChip`.
+/// The table is built in-circuit once: `2P` by doubling, `3P = 2P + P`.
+///
+/// Additions go through `EcAddChip`, whose law is incomplete, so the
+/// accumulator must never be the identity, nor `±T[k_i]`. It starts at a
+/// fixed offset point `Q` with no known discrete log instead of at the
+/// identity, and `[2^n]Q` is taken off again at the end, `n` being the
+/// number of bits. A zero window has no table entry: the sum with `P` is
+/// computed anyway and then dropped by a select on `k_i != 0`.
+///
+/// Doubling has a gate of its own. On a curve of odd order no point has
+/// `y = 0`, so the slope below always exists:
+///
+///   λ * 2y = 3x^2,    x3 = λ^2 - 2x,    y3 = λ * (x - x3) - y
+///
+/// | a0 | a1 | a2 | a3 | a4 | s_double |
+/// |----|----|----|----|----|----------|
+/// | x  | y  | λ  | x3 | y3 |    1     |
+use halo2_proofs::{
+    arithmetic::{CurveExt, Field},
+    circuit::{Layouter, Value},
+    pasta::{
+        group::{Curve, Group},
+        pallas,
+    },
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::{
+    chap_6::{
+        exercise_ec_add::{EcAddChip, EcAddConfig},
+        exercise_pedersen::coordinates,
+    },
+    gadgets::{
+        arith::{ArithChip, ArithConfig},
+        cond_swap::{CondSwapChip, CondSwapConfig},
+        Number,
+    },
+};
+
+/// Bits per window; the table of `scalar_mul` is built for two.
+pub const WINDOW: usize = 2;
+
+/// The offset point `Q`.
+pub fn offset_point() -> pallas::Affine {
+    pallas::Point::hash_to_curve("halo2-step-by-step:scalar_mul")(b"Q").to_affine()
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowMulConfig {
+    advice: [Column<Advice>; 8],
+    ec_add: EcAddConfig<pallas::Base>,
+    arith: ArithConfig,
+    select: CondSwapConfig,
+    s_double: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowMulChip {
+    config: WindowMulConfig,
+}
+
+impl WindowMulChip {
+    pub fn construct(config: WindowMulConfig) -> Self {
+        WindowMulChip { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 8],
+        constant: Column<Fixed>,
+    ) -> WindowMulConfig {
+        let ec_add = EcAddChip::configure(meta, advice);
+        let arith = ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant);
+        let select = CondSwapChip::configure(meta, [advice[0], advice[1], advice[2], advice[3]]);
+        let s_double = meta.selector();
+        let [x, y, lambda, x3, y3, ..] = advice;
+
+        meta.create_gate("doubling", |meta| {
+            let s = meta.query_selector(s_double);
+            let [x, y, lambda, x3, y3] =
+                [x, y, lambda, x3, y3].map(|col| meta.query_advice(col, Rotation::cur()));
+            let two = Expression::Constant(pallas::Base::from(2));
+            let three = Expression::Constant(pallas::Base::from(3));
+            Constraints::with_selector(
+                s,
+                vec![
+                    (
+                        "slope",
+                        lambda.clone() * two.clone() * y.clone() - three * x.clone() * x.clone(),
+                    ),
+                    (
+                        "x3",
+                        x3.clone() - (lambda.clone() * lambda.clone() - two * x.clone()),
+                    ),
+                    ("y3", y3 - (lambda * (x - x3) - y)),
+                ],
+            )
+        });
+
+        WindowMulConfig {
+            advice,
+            ec_add,
+            arith,
+            select,
+            s_double,
+        }
+    }
+
+    pub fn arith(&self) -> ArithChip<pallas::Base> {
+        ArithChip::construct(self.config.arith.clone())
+    }
+
+    pub fn load_constant_point(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        point: pallas::Affine,
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        let arith = self.arith();
+        let [x, y] = coordinates(point);
+        Ok([
+            arith.load_constant(layouter.namespace(|| "x"), x)?,
+            arith.load_constant(layouter.namespace(|| "y"), y)?,
+        ])
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<pallas::Base>,
+        p: [Number<pallas::Base>; 2],
+        q: [Number<pallas::Base>; 2],
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        EcAddChip::construct(self.config.ec_add.clone()).add_assigned(layouter, p, q)
+    }
+
+    pub fn double(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        p: [Number<pallas::Base>; 2],
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        let [x, y] = p.clone().map(|c| c.0.value().copied());
+        let lambda = x
+            * x
+            * Value::known(pallas::Base::from(3))
+            * (y + y).map(|dy| dy.invert().unwrap_or(pallas::Base::ZERO));
+        let x3 = lambda * lambda - x - x;
+        let y3 = lambda * (x - x3) - y;
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "2P",
+            |mut region| {
+                self.config.s_double.enable(&mut region, 0)?;
+                p[0].0.copy_advice(|| "x", &mut region, advice[0], 0)?;
+                p[1].0.copy_advice(|| "y", &mut region, advice[1], 0)?;
+                region.assign_advice(|| "λ", advice[2], 0, || lambda)?;
+                let x3 = region.assign_advice(|| "x3", advice[3], 0, || x3)?;
+                let y3 = region.assign_advice(|| "y3", advice[4], 0, || y3)?;
+                Ok([Number(x3), Number(y3)])
+            },
+        )
+    }
+
+    /// `lhs` if `cond = 0`, `rhs` if `cond = 1`, coordinate by coordinate.
+    fn select(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        cond: &Number<pallas::Base>,
+        lhs: &[Number<pallas::Base>; 2],
+        rhs: &[Number<pallas::Base>; 2],
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        let select = CondSwapChip::construct(self.config.select.clone());
+        Ok([
+            select.select(
+                layouter.namespace(|| "x"),
+                cond.clone(),
+                lhs[0].clone(),
+                rhs[0].clone(),
+            )?,
+            select.select(
+                layouter.namespace(|| "y"),
+                cond.clone(),
+                lhs[1].clone(),
+                rhs[1].clone(),
+            )?,
+        ])
+    }
+
+    /// `[k]P` for `k`'s bits, most significant first. The number of bits
+    /// must be a multiple of `WINDOW`, and each bit is checked boolean by the
+    /// selects it drives.
+    pub fn scalar_mul(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        scalar_bits: &[Number<pallas::Base>],
+        point: [Number<pallas::Base>; 2],
+    ) -> Result<[Number<pallas::Base>; 2], Error> {
+        assert!(
+            !scalar_bits.is_empty() && scalar_bits.len() % WINDOW == 0,
+            "pad the scalar to whole windows"
+        );
+        let arith = self.arith();
+
+        let double = self.double(layouter.namespace(|| "2P"), point.clone())?;
+        let triple = self.add(layouter.namespace(|| "3P"), double.clone(), point.clone())?;
+        // T[0] would be the identity; P stands in and the sum is dropped.
+        let table = [point.clone(), point, double, triple];
+
+        let q = offset_point();
+        let mut acc = self.load_constant_point(layouter.namespace(|| "Q"), q)?;
+        for (i, bits) in scalar_bits.chunks(WINDOW).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("window {}", i));
+            for _ in 0..WINDOW {
+                acc = self.double(layouter.namespace(|| "double"), acc)?;
+            }
+
+            let [hi, lo] = [&bits[0], &bits[1]];
+            let low = self.select(layouter.namespace(|| "T[0|1]"), lo, &table[0], &table[1])?;
+            let high = self.select(layouter.namespace(|| "T[2|3]"), lo, &table[2], &table[3])?;
+            let entry = self.select(layouter.namespace(|| "T[k_i]"), hi, &low, &high)?;
+            let sum = self.add(layouter.namespace(|| "acc + T[k_i]"), acc.clone(), entry)?;
+
+            // k_i != 0, as hi or lo = hi + lo - hi * lo.
+            let either = arith.add(layouter.namespace(|| "hi + lo"), hi.clone(), lo.clone())?;
+            let both = arith.mul(layouter.namespace(|| "hi * lo"), hi.clone(), lo.clone())?;
+            let nonzero = arith.sub(layouter.namespace(|| "hi or lo"), either, both)?;
+            acc = self.select(layouter.namespace(|| "skip zero"), &nonzero, &acc, &sum)?;
+        }
+
+        let mut correction = pallas::Point::from(q);
+        for _ in 0..scalar_bits.len() {
+            correction = correction.double();
+        }
+        let correction =
+            self.load_constant_point(layouter.namespace(|| "-[2^n]Q"), (-correction).to_affine())?;
+        self.add(layouter.namespace(|| "remove offset"), acc, correction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, pasta::group::prime::PrimeCurveAffine,
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        window_mul: WindowMulConfig,
+        instance: Column<Instance>,
+    }
+
+    /// `[k]P` exposed as `[x, y]`.
+    #[derive(Default)]
+    struct MyCircuit {
+        bits: Vec<Value<pallas::Base>>,
+        point: [Value<pallas::Base>; 2],
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                bits: vec![Value::unknown(); self.bits.len()],
+                point: [Value::unknown(); 2],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advice = [(); 8].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                window_mul: WindowMulChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let chip = WindowMulChip::construct(config.window_mul);
+            let arith = chip.arith();
+            let bits = self
+                .bits
+                .iter()
+                .map(|b| arith.load_private(layouter.namespace(|| "bit"), *b))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let point = [
+                arith.load_private(layouter.namespace(|| "x"), self.point[0])?,
+                arith.load_private(layouter.namespace(|| "y"), self.point[1])?,
+            ];
+            let [x, y] = chip.scalar_mul(layouter.namespace(|| "[k]P"), &bits, point)?;
+            arith.expose_public(layouter.namespace(|| "x"), x, config.instance, 0)?;
+            arith.expose_public(layouter.namespace(|| "y"), y, config.instance, 1)
+        }
+    }
+
+    /// The `n` low bits of `k`, most significant first.
+    fn bits(k: u64, n: usize) -> Vec<u64> {
+        (0..n).rev().map(|i| (k >> i) & 1).collect()
+    }
+
+    fn circuit(bits: &[u64], point: pallas::Affine) -> MyCircuit {
+        MyCircuit {
+            bits: bits
+                .iter()
+                .map(|b| Value::known(pallas::Base::from(*b)))
+                .collect(),
+            point: coordinates(point).map(Value::known),
+        }
+    }
+
+    fn mul_g(k: u64) -> Vec<pallas::Base> {
+        let point = (pallas::Point::generator() * pallas::Scalar::from(k)).to_affine();
+        coordinates(point).to_vec()
+    }
+
+    #[test]
+    fn test_scalar_mul() {
+        let k = 6;
+        let g = pallas::Affine::generator();
+        let circuit = circuit(&bits(5, 4), g);
+        MockProver::run(k, &circuit, vec![mul_g(5)])
+            .unwrap()
+            .assert_satisfied();
+
+        // Zero windows in the middle and a leading one.
+        let k = 8;
+        let scalar = 0b1000_0000_0011_0001;
+        let circuit = self::circuit(&bits(scalar, 16), g);
+        MockProver::run(k, &circuit, vec![mul_g(scalar)])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_scalar_mul_wrong_bit() {
+        let k = 6;
+        let g = pallas::Affine::generator();
+
+        // 7 = 0b0111: one bit of 5 flipped, checked against 5G.
+        let circuit = circuit(&bits(7, 4), g);
+        let prover = MockProver::run(k, &circuit, vec![mul_g(5)]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A low bit of 2 under a high bit of 0 reads as the window 2 too.
+        let circuit = self::circuit(&[0, 1, 0, 2], g);
+        let prover = MockProver::run(k, &circuit, vec![mul_g(6)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
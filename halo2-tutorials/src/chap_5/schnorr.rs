@@ -0,0 +1,253 @@
+/// chap5: Schnorr signatures
+/// A Schnorr signature on the Pallas curve, with secret key `sk`, public key
+/// `P = sk*G`, nonce `k` and commitment `R = k*G`:
+///
+///   e = Poseidon(R.x, R.y, P.x, P.y, m),    s = k + e * sk
+///
+/// and it verifies when
+///
+///   s*G == R + e*P
+///
+/// The circuit checks exactly that, for a public key and message on the
+/// instance column. `PUBLIC_SIG` also puts `R` there; `s` lives in the scalar
+/// field, which is larger than the circuit's, so it always stays a witness.
+///
+///   instance = [P.x, P.y, m]                  (PUBLIC_SIG = false)
+///   instance = [P.x, P.y, m, R.x, R.y]        (PUBLIC_SIG = true)
+///
+/// `e` comes out of Poseidon as a base field element and is used as a scalar
+/// as is; Pallas' base field is smaller than its scalar field, so that is the
+/// same integer. `G` is the fixed base of `exercise_pedersen`, and Poseidon
+/// shares the ECC chip's columns the same way Orchard does.
+///
+/// | a0 .. a9              | lagrange_coeffs[8] / rc_a, rc_b | table   | instance |
+/// |-----------------------|---------------------------------|---------|----------|
+/// | witness P, R, m       |                                 | 0..2^10 |   P.x    |
+/// | Poseidon rows (a5..a8)|                                 |         |   P.y    |
+/// | s*G: one row per window ...                             |         |    m     |
+/// | e*P: double and add rows ...                            |         |  (R.x)   |
+/// | R + e*P, s*G == R + e*P                                 |         |  (R.y)   |
+use halo2_gadgets::{
+    ecc::{
+        chip::{EccChip, EccConfig},
+        FixedPoint, Point, ScalarFixed, ScalarVar,
+    },
+    poseidon::{
+        primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+        Hash, Pow5Chip, Pow5Config,
+    },
+    sinsemilla,
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{
+        group::{ff::PrimeField, Curve, Group},
+        pallas,
+    },
+    plonk::*,
+};
+
+use crate::chap_6::exercise_pedersen::{coordinates, PedersenBase, PedersenBases};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// The challenge `e`, natively.
+pub fn challenge(r: pallas::Affine, pk: pallas::Affine, m: pallas::Base) -> pallas::Base {
+    let [rx, ry] = coordinates(r);
+    let [px, py] = coordinates(pk);
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<5>, WIDTH, RATE>::init()
+        .hash([rx, ry, px, py, m])
+}
+
+fn to_scalar(e: pallas::Base) -> pallas::Scalar {
+    pallas::Scalar::from_repr(e.to_repr()).unwrap()
+}
+
+pub fn public_key(sk: pallas::Scalar) -> pallas::Affine {
+    (pallas::Point::generator() * sk).to_affine()
+}
+
+/// Sign `m` with nonce `k`, natively, returning `(R, s)`.
+pub fn sign(
+    sk: pallas::Scalar,
+    k: pallas::Scalar,
+    m: pallas::Base,
+) -> (pallas::Affine, pallas::Scalar) {
+    let r = (pallas::Point::generator() * k).to_affine();
+    let e = to_scalar(challenge(r, public_key(sk), m));
+    (r, k + e * sk)
+}
+
+#[derive(Debug, Clone)]
+pub struct SchnorrConfig {
+    advice: [Column<Advice>; 10],
+    ecc: EccConfig<PedersenBases>,
+    poseidon: Pow5Config<pallas::Base, WIDTH, RATE>,
+    range_check: LookupRangeCheckConfig<pallas::Base, { sinsemilla::primitives::K }>,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct SchnorrCircuit<const PUBLIC_SIG: bool> {
+    pub pk: Value<pallas::Affine>,
+    pub r: Value<pallas::Affine>,
+    pub s: Value<pallas::Scalar>,
+}
+
+impl<const PUBLIC_SIG: bool> Circuit<pallas::Base> for SchnorrCircuit<PUBLIC_SIG> {
+    type Config = SchnorrConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice: [Column<Advice>; 10] = (0..10)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lagrange_coeffs: [Column<Fixed>; 8] = (0..8)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advice[9], table);
+        let ecc = EccChip::configure(meta, advice, lagrange_coeffs, range_check);
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(
+            meta,
+            advice[6..9].try_into().unwrap(),
+            advice[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+        SchnorrConfig {
+            advice,
+            ecc,
+            poseidon,
+            range_check,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let ecc = EccChip::construct(config.ecc);
+        config.range_check.load(&mut layouter)?;
+
+        let pk = Point::new(ecc.clone(), layouter.namespace(|| "P"), self.pk)?;
+        let r = Point::new(ecc.clone(), layouter.namespace(|| "R"), self.r)?;
+        let m = layouter.assign_region(
+            || "m",
+            |mut region| {
+                region.assign_advice_from_instance(|| "m", config.instance, 2, config.advice[0], 0)
+            },
+        )?;
+        layouter.constrain_instance(pk.inner().x().cell(), config.instance, 0)?;
+        layouter.constrain_instance(pk.inner().y().cell(), config.instance, 1)?;
+        if PUBLIC_SIG {
+            layouter.constrain_instance(r.inner().x().cell(), config.instance, 3)?;
+            layouter.constrain_instance(r.inner().y().cell(), config.instance, 4)?;
+        }
+
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<5>, WIDTH, RATE>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init"),
+        )?;
+        let e = hasher.hash(
+            layouter.namespace(|| "e"),
+            [
+                r.inner().x(),
+                r.inner().y(),
+                pk.inner().x(),
+                pk.inner().y(),
+                m,
+            ],
+        )?;
+        let e = ScalarVar::from_base(ecc.clone(), layouter.namespace(|| "e as scalar"), &e)?;
+        let (ep, _) = pk.mul(layouter.namespace(|| "e*P"), e)?;
+        let rhs = r.add(layouter.namespace(|| "R + e*P"), &ep)?;
+
+        let s = ScalarFixed::new(ecc.clone(), layouter.namespace(|| "s"), self.s)?;
+        let g = FixedPoint::from_inner(ecc, PedersenBase::g());
+        let (lhs, _) = g.mul(layouter.namespace(|| "s*G"), s)?;
+        lhs.constrain_equal(layouter.namespace(|| "s*G == R + e*P"), &rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    const K: u32 = 11;
+
+    fn keys() -> (pallas::Scalar, pallas::Affine) {
+        let sk = pallas::Scalar::from(0x5eed_5eed);
+        (sk, public_key(sk))
+    }
+
+    fn verify<const PUBLIC_SIG: bool>(
+        pk: pallas::Affine,
+        m: pallas::Base,
+        (r, s): (pallas::Affine, pallas::Scalar),
+    ) -> bool {
+        let circuit = SchnorrCircuit::<PUBLIC_SIG> {
+            pk: Value::known(pk),
+            r: Value::known(r),
+            s: Value::known(s),
+        };
+        let mut instance = [coordinates(pk).to_vec(), vec![m]].concat();
+        if PUBLIC_SIG {
+            instance.extend(coordinates(r));
+        }
+        let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_schnorr_valid() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let sig = sign(sk, pallas::Scalar::from(0xc0ffee), m);
+        assert!(verify::<false>(pk, m, sig));
+        assert!(verify::<true>(pk, m, sig));
+    }
+
+    #[test]
+    fn test_schnorr_forged() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let (r, s) = sign(sk, pallas::Scalar::from(0xc0ffee), m);
+
+        // A tweaked response, a signature on another message, and one by
+        // another key.
+        assert!(!verify::<false>(pk, m, (r, s + pallas::Scalar::one())));
+        assert!(!verify::<false>(pk, m + pallas::Base::one(), (r, s)));
+        let other = sign(
+            sk + pallas::Scalar::one(),
+            pallas::Scalar::from(0xc0ffee),
+            m,
+        );
+        assert!(!verify::<false>(pk, m, other));
+
+        // A forger without sk: their own nonce 7 gives the right challenge
+        // for R, but the response needs sk, and guessing sk = 1 is wrong.
+        let fake_r = public_key(pallas::Scalar::from(7));
+        let e = to_scalar(challenge(fake_r, pk, m));
+        let fake_s = pallas::Scalar::from(7) + e;
+        assert!(!verify::<false>(pk, m, (fake_r, fake_s)));
+    }
+}
@@ -0,0 +1,293 @@
+/// chap5: signed multiplication
+/// The other chips read a field element as an unsigned integer. Here the
+/// inputs are `BITS`-bit two's complement numbers: `x` in `[0, 2^BITS)` with
+/// its top bit as the sign, so that it stands for
+///
+///   x_signed = x - sign * 2^BITS        (-3 is 253 for BITS = 8)
+///
+/// and the product is computed as signs and magnitudes:
+///
+///   |x| = sign ? 2^BITS - x : x
+///   out = (sign_a xor sign_b) ? -(|a| * |b|) : |a| * |b|
+///
+/// `out` is the product as a field element, so negative results are
+/// `p - |out|`; it does not wrap around to `BITS` bits.
+///
+/// None of the repo's chips extracts a sign bit, so this one decomposes each
+/// input into bits, most significant first, with a running sum whose last row
+/// is a copy of the input; that also range-checks it to `BITS` bits. `ArithChip`
+/// does the arithmetic and `CondSwapChip::select` is the ternary.
+///
+/// | a0    | a1    | s_first | s_bit |
+/// |-------|-------|---------|-------|
+/// | sign  | sign  |    1    |   0   |   acc_0 = b_0
+/// | b_1   | acc_1 |    0    |   1   |   acc_i = 2 * acc_{i-1} + b_i
+/// | ...   |  ...  |         |       |
+/// | b_n   |   x   |    0    |   1   |   x copied in
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    cond_swap::{CondSwapChip, CondSwapConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct SignedMulConfig {
+    advice: [Column<Advice>; 4],
+    arith: ArithConfig,
+    select: CondSwapConfig,
+    s_first: Selector,
+    s_bit: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedMulChip<F: PrimeField, const BITS: usize> {
+    config: SignedMulConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const BITS: usize> SignedMulChip<F, BITS> {
+    pub fn construct(config: SignedMulConfig) -> Self {
+        SignedMulChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> SignedMulConfig {
+        let arith = ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant);
+        let select = CondSwapChip::configure(meta, advice);
+        let s_first = meta.selector();
+        let s_bit = meta.selector();
+        let [bit, acc, _, _] = advice;
+
+        meta.create_gate("bit decomposition", |meta| {
+            let s_first = meta.query_selector(s_first);
+            let s_bit = meta.query_selector(s_bit);
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+            vec![
+                (s_first.clone() + s_bit.clone()) * b.clone() * (one - b.clone()),
+                s_first * (acc_cur.clone() - b.clone()),
+                s_bit * (acc_cur - (two * acc_prev + b)),
+            ]
+        });
+
+        SignedMulConfig {
+            advice,
+            arith,
+            select,
+            s_first,
+            s_bit,
+        }
+    }
+
+    pub fn arith(&self) -> ArithChip<F> {
+        ArithChip::construct(self.config.arith.clone())
+    }
+
+    /// The sign bit of `x`, which must fit in `BITS` bits.
+    pub fn sign(&self, mut layouter: impl Layouter<F>, x: &Number<F>) -> Result<Number<F>, Error> {
+        let [bit_col, acc_col, _, _] = self.config.advice;
+        let bits = x.0.value().map(|x| {
+            let repr = x.to_repr();
+            let bytes = repr.as_ref();
+            (0..BITS)
+                .rev()
+                .map(|i| F::from(((bytes[i / 8] >> (i % 8)) & 1) as u64))
+                .collect::<Vec<_>>()
+        });
+        layouter.assign_region(
+            || "bit decomposition",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut sign = None;
+                for row in 0..BITS {
+                    let b = bits.as_ref().map(|bits| bits[row]);
+                    acc = acc * Value::known(F::from(2)) + b;
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_bit.enable(&mut region, row)?;
+                    }
+                    let b = region.assign_advice(|| "bit", bit_col, row, || b)?;
+                    if row == BITS - 1 {
+                        x.0.copy_advice(|| "x", &mut region, acc_col, row)?;
+                    } else {
+                        region.assign_advice(|| "acc", acc_col, row, || acc)?;
+                    }
+                    if row == 0 {
+                        sign = Some(Number(b));
+                    }
+                }
+                Ok(sign.expect("BITS > 0"))
+            },
+        )
+    }
+
+    /// `|x|` for `x` with sign bit `sign`.
+    pub fn magnitude(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Number<F>,
+        sign: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let arith = self.arith();
+        let modulus = arith.load_constant(
+            layouter.namespace(|| "2^BITS"),
+            F::from(2).pow_vartime([BITS as u64]),
+        )?;
+        let neg = arith.sub(layouter.namespace(|| "2^BITS - x"), modulus, x.clone())?;
+        CondSwapChip::construct(self.config.select.clone()).select(
+            layouter.namespace(|| "|x|"),
+            sign,
+            x,
+            neg,
+        )
+    }
+
+    /// The signed product of two `BITS`-bit two's complement numbers.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let arith = self.arith();
+        let sign_a = self.sign(layouter.namespace(|| "sign a"), &a)?;
+        let sign_b = self.sign(layouter.namespace(|| "sign b"), &b)?;
+        let abs_a = self.magnitude(layouter.namespace(|| "|a|"), a, sign_a.clone())?;
+        let abs_b = self.magnitude(layouter.namespace(|| "|b|"), b, sign_b.clone())?;
+        let prod = arith.mul(layouter.namespace(|| "|a| * |b|"), abs_a, abs_b)?;
+
+        // sign_a xor sign_b = sign_a + sign_b - 2 * sign_a * sign_b
+        let both = arith.mul(
+            layouter.namespace(|| "sign_a * sign_b"),
+            sign_a.clone(),
+            sign_b.clone(),
+        )?;
+        let both = arith.add(layouter.namespace(|| "2 * both"), both.clone(), both)?;
+        let sum = arith.add(layouter.namespace(|| "sign_a + sign_b"), sign_a, sign_b)?;
+        let sign = arith.sub(layouter.namespace(|| "xor"), sum, both)?;
+
+        let zero = arith.load_constant(layouter.namespace(|| "zero"), F::ZERO)?;
+        let neg = arith.sub(layouter.namespace(|| "-prod"), zero, prod.clone())?;
+        CondSwapChip::construct(self.config.select.clone()).select(
+            layouter.namespace(|| "apply sign"),
+            sign,
+            prod,
+            neg,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedMulCircuitConfig {
+    signed_mul: SignedMulConfig,
+    instance: Column<Instance>,
+}
+
+/// `out = a * b` for private 8-bit two's complement `a` and `b`.
+#[derive(Default)]
+pub struct SignedMulCircuit<F: PrimeField> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for SignedMulCircuit<F> {
+    type Config = SignedMulCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        SignedMulCircuitConfig {
+            signed_mul: SignedMulChip::<F, 8>::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = SignedMulChip::<F, 8>::construct(config.signed_mul);
+        let arith = chip.arith();
+        let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+        let out = chip.mul(layouter.namespace(|| "a * b"), a, b)?;
+        arith.expose_public(layouter.namespace(|| "out"), out, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 7;
+
+    /// The 8-bit two's complement encoding of `x`.
+    fn encode(x: i64) -> Fp {
+        Fp::from((x as u8) as u64)
+    }
+
+    fn signed(x: i64) -> Fp {
+        if x < 0 {
+            -Fp::from(x.unsigned_abs())
+        } else {
+            Fp::from(x as u64)
+        }
+    }
+
+    fn verify(a: Fp, b: Fp, out: Fp) -> bool {
+        let circuit = SignedMulCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![out]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_signed_mul() {
+        assert_eq!(encode(-3), Fp::from(253));
+        assert!(verify(encode(-3), encode(5), signed(-15)));
+        assert!(verify(encode(-3), encode(-2), signed(6)));
+        assert!(verify(encode(0), encode(-7), signed(0)));
+        assert!(verify(encode(0), encode(100), signed(0)));
+        assert!(verify(encode(-128), encode(-128), signed(16384)));
+
+        // The unsigned reading of the same inputs is rejected.
+        assert!(!verify(encode(-3), encode(5), Fp::from(253 * 5)));
+        assert!(!verify(encode(-3), encode(5), signed(15)));
+    }
+
+    #[test]
+    fn test_signed_mul_out_of_range() {
+        // 256 does not fit in 8 bits, so its decomposition cannot end at it.
+        assert!(!verify(Fp::from(256), encode(1), signed(0)));
+    }
+}
@@ -0,0 +1,365 @@
+/// chap5: 8-bit XOR by nibble lookups
+/// XOR is not a field operation. Bit by bit it is `a + b - 2ab` on booleans,
+/// so an arithmetic XOR of two bytes decomposes both into bits first: eight
+/// rows, each with booleanity checks and a recomposition step. A lookup
+/// table of every `(x, y, x ^ y)` turns that into a single lookup, but for
+/// bytes it would have 65536 rows.
+///
+/// Nibbles are the middle ground: the table of `(x, y, x ^ y)` for 4-bit
+/// `x, y` has 16 * 16 = 256 rows, and XOR works nibble by nibble, so
+///
+///   a ^ b = (a_lo ^ b_lo) | ((a_hi ^ b_hi) << 4)
+///
+/// One row holds both operands, the result, and the nibbles of all three,
+/// one gate recomposes them, and two lookups check the low and the high
+/// triple. The table also bounds every nibble to 4 bits, so the operands
+/// and the result are bytes without a range check of their own.
+///
+/// | a0 | a1 | a2 | a3   | a4   | a5   | a6   | a7   | a8   | q_xor | x | y | x ^ y |
+/// |----|----|----|------|------|------|------|------|------|-------|---|---|-------|
+/// | a  | b  | c  | a_lo | a_hi | b_lo | b_hi | c_lo | c_hi |   1   | 0 | 0 |   0   |
+/// |    |    |    |      |      |      |      |      |      |       | 0 | 1 |   1   |
+/// |    |    |    |      |      |      |      |      |      |       |...|...|  ...  |
+///
+/// Disabled rows look up `(0, 0, 0)`, which is in the table.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+#[derive(Debug, Clone)]
+pub struct Xor8Config {
+    pub advice: [Column<Advice>; 9],
+    table: [TableColumn; 3],
+    q_xor: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct Xor8Chip<F: PrimeField> {
+    config: Xor8Config,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Xor8Chip<F> {
+    pub fn construct(config: Xor8Config) -> Self {
+        Xor8Chip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 9]) -> Xor8Config {
+        for col in &advice[..3] {
+            meta.enable_equality(*col);
+        }
+        let q_xor = meta.complex_selector();
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        let [a, b, c, a_lo, a_hi, b_lo, b_hi, c_lo, c_hi] = advice;
+
+        meta.create_gate("xor8 nibbles", |meta| {
+            let q = meta.query_selector(q_xor);
+            let base = Expression::Constant(F::from(16));
+            let [a, b, c, a_lo, a_hi, b_lo, b_hi, c_lo, c_hi] =
+                [a, b, c, a_lo, a_hi, b_lo, b_hi, c_lo, c_hi]
+                    .map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("a = a_lo + 16 * a_hi", a - a_lo - base.clone() * a_hi),
+                    ("b = b_lo + 16 * b_hi", b - b_lo - base.clone() * b_hi),
+                    ("c = c_lo + 16 * c_hi", c - c_lo - base * c_hi),
+                ],
+            )
+        });
+
+        // One lookup for the low nibbles, one for the high.
+        for [x, y, z] in [[a_lo, b_lo, c_lo], [a_hi, b_hi, c_hi]] {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_xor);
+                let [x, y, z] = [x, y, z].map(|col| meta.query_advice(col, Rotation::cur()));
+                vec![
+                    (q.clone() * x, table[0]),
+                    (q.clone() * y, table[1]),
+                    (q * z, table[2]),
+                ]
+            });
+        }
+
+        Xor8Config {
+            advice,
+            table,
+            q_xor,
+        }
+    }
+
+    /// The 256 rows `(x, y, x ^ y)` for nibbles `x`, `y`.
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "nibble xor table",
+            |mut table| {
+                for x in 0..16u64 {
+                    for y in 0..16u64 {
+                        let row = (x * 16 + y) as usize;
+                        for (col, value) in self.config.table.iter().zip([x, y, x ^ y]) {
+                            table.assign_cell(
+                                || "xor",
+                                *col,
+                                row,
+                                || Value::known(F::from(value)),
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// `a ^ b` for two bytes.
+    pub fn xor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [a_col, b_col, c_col, a_lo, a_hi, b_lo, b_hi, c_lo, c_hi] = self.config.advice;
+        layouter.assign_region(
+            || "xor8",
+            |mut region| {
+                self.config.q_xor.enable(&mut region, 0)?;
+                let a = a.0.copy_advice(|| "a", &mut region, a_col, 0)?;
+                let b = b.0.copy_advice(|| "b", &mut region, b_col, 0)?;
+                let byte = |v: &F| v.to_repr().as_ref()[0] as u64;
+                let a = a.value().map(byte);
+                let b = b.value().map(byte);
+                let c = a.zip(b).map(|(a, b)| a ^ b);
+                for (col, v, name) in [
+                    (a_lo, a.map(|a| a & 15), "a_lo"),
+                    (a_hi, a.map(|a| a >> 4), "a_hi"),
+                    (b_lo, b.map(|b| b & 15), "b_lo"),
+                    (b_hi, b.map(|b| b >> 4), "b_hi"),
+                    (c_lo, c.map(|c| c & 15), "c_lo"),
+                    (c_hi, c.map(|c| c >> 4), "c_hi"),
+                ] {
+                    region.assign_advice(|| name, col, 0, || v.map(F::from))?;
+                }
+                region
+                    .assign_advice(|| "a ^ b", c_col, 0, || c.map(F::from))
+                    .map(Number)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::rows::advice_rows;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        xor8: Xor8Config,
+        instance: Column<Instance>,
+    }
+
+    /// `a ^ b = instance[0]`.
+    #[derive(Default)]
+    struct Xor8Circuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for Xor8Circuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 9].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                xor8: Xor8Chip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = Xor8Chip::construct(config.xor8.clone());
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let [a_col, b_col, ..] = config.xor8.advice;
+            let (a, b) = layouter.assign_region(
+                || "load",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", a_col, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", b_col, 0, || self.b)?;
+                    Ok((Number(a), Number(b)))
+                },
+            )?;
+            let c = chip.xor(layouter.namespace(|| "a ^ b"), a, b)?;
+            layouter.constrain_instance(c.0.cell(), config.instance, 0)
+        }
+    }
+
+    /// The arithmetic XOR, for comparison: one row per bit, most significant
+    /// first, with running recompositions of both operands and the result.
+    ///
+    /// | a0 | a1 | a2 | a3    | a4    | a5    | s_first | s_bit |
+    /// |----|----|----|-------|-------|-------|---------|-------|
+    /// | x  | y  | z  | acc_a | acc_b | acc_c |         |       |
+    #[derive(Default)]
+    struct ArithXorCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for ArithXorCircuit<F> {
+        type Config = ([Column<Advice>; 6], Selector, Selector, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            meta.enable_equality(advice[5]);
+            let s_first = meta.selector();
+            let s_bit = meta.selector();
+            meta.create_gate("bitwise xor", |meta| {
+                let s_first = meta.query_selector(s_first);
+                let s_bit = meta.query_selector(s_bit);
+                let s = s_first.clone() + s_bit.clone();
+                let [x, y, z, acc_a, acc_b, acc_c] =
+                    advice.map(|col| meta.query_advice(col, Rotation::cur()));
+                let [prev_a, prev_b, prev_c] = [advice[3], advice[4], advice[5]]
+                    .map(|col| meta.query_advice(col, Rotation::prev()));
+                let one = Expression::Constant(F::ONE);
+                let two = Expression::Constant(F::from(2));
+                vec![
+                    s.clone() * x.clone() * (one.clone() - x.clone()),
+                    s.clone() * y.clone() * (one - y.clone()),
+                    s * (z.clone() - x.clone() - y.clone() + two.clone() * x.clone() * y.clone()),
+                    s_first.clone() * (acc_a.clone() - x.clone()),
+                    s_first.clone() * (acc_b.clone() - y.clone()),
+                    s_first * (acc_c.clone() - z.clone()),
+                    s_bit.clone() * (acc_a - two.clone() * prev_a - x),
+                    s_bit.clone() * (acc_b - two.clone() * prev_b - y),
+                    s_bit * (acc_c - two * prev_c - z),
+                ]
+            });
+            (advice, s_first, s_bit, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, s_first, s_bit, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let byte = |v: F| v.to_repr().as_ref()[0] as u64;
+            let (a, b) = (self.a.map(byte), self.b.map(byte));
+            let c = layouter.assign_region(
+                || "bitwise xor",
+                |mut region| {
+                    let mut acc = None;
+                    for row in 0..8 {
+                        if row == 0 {
+                            s_first.enable(&mut region, row)?;
+                        } else {
+                            s_bit.enable(&mut region, row)?;
+                        }
+                        let shift = 7 - row;
+                        let bit = |v: u64| (v >> shift) & 1;
+                        let values = [
+                            a.map(bit),
+                            b.map(bit),
+                            a.zip(b).map(|(a, b)| bit(a ^ b)),
+                            a.map(|a| a >> shift),
+                            b.map(|b| b >> shift),
+                            a.zip(b).map(|(a, b)| (a ^ b) >> shift),
+                        ];
+                        for (col, v) in advice.iter().zip(values) {
+                            let cell =
+                                region.assign_advice(|| "xor", *col, row, || v.map(F::from))?;
+                            acc = Some(cell);
+                        }
+                    }
+                    Ok(acc.unwrap())
+                },
+            )?;
+            layouter.constrain_instance(c.cell(), instance, 0)
+        }
+    }
+
+    fn verify(a: u64, b: u64, c: u64) -> bool {
+        let circuit = Xor8Circuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(c)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_xor8() {
+        assert!(verify(0xAB, 0xCD, 0x66));
+        assert!(!verify(0xAB, 0xCD, 0x67));
+        assert!(verify(0x00, 0xFF, 0xFF));
+        assert!(verify(0xFF, 0xFF, 0x00));
+        // Operands must be bytes.
+        assert!(!verify(0x1AB, 0xCD, 0x166));
+    }
+
+    #[test]
+    fn test_xor8_single_bit_flips() {
+        for a in [0x00, 0xAB, 0xFF] {
+            for i in 0..8 {
+                let flipped = a ^ (1 << i);
+                assert!(verify(a, flipped, 1 << i), "{:#x} ^ {:#x}", a, flipped);
+                assert!(!verify(a, flipped, 0), "{:#x} ^ {:#x}", a, flipped);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xor8_against_arithmetic() {
+        let (a, b) = (Value::known(Fp::from(0xAB)), Value::known(Fp::from(0xCD)));
+        let arith = ArithXorCircuit { a, b };
+        let prover = MockProver::run(K, &arith, vec![vec![Fp::from(0x66)]]).unwrap();
+        prover.assert_satisfied();
+
+        // One row for the lookup version, after the row loading the
+        // operands; eight for the arithmetic one.
+        let lookup_rows = advice_rows(K, &Xor8Circuit { a, b }).unwrap();
+        let arith_rows = advice_rows(K, &arith).unwrap();
+        assert_eq!(lookup_rows, 2);
+        assert_eq!(arith_rows, 8);
+
+        // And 3 constraints against 9, with two lookups instead.
+        let mut cs = ConstraintSystem::<Fp>::default();
+        Xor8Circuit::<Fp>::configure(&mut cs);
+        let lookup_constraints: usize = cs.gates().iter().map(|g| g.polynomials().len()).sum();
+        let mut cs = ConstraintSystem::<Fp>::default();
+        ArithXorCircuit::<Fp>::configure(&mut cs);
+        let arith_constraints: usize = cs.gates().iter().map(|g| g.polynomials().len()).sum();
+        assert_eq!((lookup_constraints, arith_constraints), (3, 9));
+    }
+}
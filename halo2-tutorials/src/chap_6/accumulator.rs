@@ -0,0 +1,271 @@
+/// chap6: a multiplicative accumulator
+/// An RSA accumulator commits to a set of primes `S` with one group element
+///
+///   acc = g^(prod S)
+///
+/// and adding `e` raises it: `acc' = acc^e`. The membership witness of
+/// `e` is the accumulator of the others, `w = g^(prod S / e)`, and the check
+/// is `w^e = acc`. Unlike a Merkle root, the witness is one element
+/// whatever the size of the set, and the check one exponentiation.
+///
+/// Here the group is the multiplicative group mod a prime `P < 256`, so the
+/// exponentiation is `ModExpChip`'s, with the element decomposed into bits.
+/// That keeps the shape but not the security: RSA relies on nobody knowing
+/// the group order, and the order of this one is `P - 1`. Anyone can then
+/// take `e`-th roots for any `e` coprime to `P - 1` and forge a witness for a
+/// non-member, see `test_accumulator_known_order`. Only an `e` sharing a
+/// factor with `P - 1` can be a true non-member, when `acc` has no `e`-th
+/// root at all.
+///
+/// Elements are primes for the same reason as in RSA: with `15` in the set,
+/// `5` would have the witness `g^(prod S / 15 * 3)`.
+///
+/// | a0 .. a3                          | constant | instance |
+/// |-----------------------------------|----------|----------|
+/// | w, e, bits of e                   |          |   acc    |
+/// | e = sum bits * 2^i                |   2      |    e     |
+/// | w^e mod P, by ModExpChip          |   P, 1   |          |
+/// | w^e = acc                         |          |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    mod_exp::{ModExpChip, ModExpConfig},
+    Number,
+};
+
+/// The modulus, a prime below 256.
+pub const P: u64 = 251;
+/// A generator of the group mod `P`.
+pub const G: u64 = 6;
+/// Bits of an element.
+pub const E_BITS: usize = 8;
+
+fn pow_mod(base: u64, exp: u64) -> u64 {
+    (0..exp).fold(1, |acc, _| acc * base % P)
+}
+
+/// The accumulator of `elements`.
+pub fn accumulate(elements: &[u64]) -> u64 {
+    elements.iter().fold(G, |acc, e| pow_mod(acc, *e))
+}
+
+/// The membership witness of `e`: the accumulator of the other elements.
+pub fn witness(elements: &[u64], e: u64) -> Option<u64> {
+    let i = elements.iter().position(|x| *x == e)?;
+    let mut others = elements.to_vec();
+    others.remove(i);
+    Some(accumulate(&others))
+}
+
+#[derive(Debug, Clone)]
+pub struct AccumulatorConfig {
+    mod_exp: ModExpConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccumulatorChip<F: PrimeField> {
+    config: AccumulatorConfig,
+    mod_exp: ModExpChip<F>,
+}
+
+impl<F: PrimeField> AccumulatorChip<F> {
+    pub fn construct(config: AccumulatorConfig) -> Self {
+        AccumulatorChip {
+            mod_exp: ModExpChip::construct(config.mod_exp.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> AccumulatorConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        AccumulatorConfig {
+            mod_exp: ModExpChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.mod_exp.load_table(layouter)
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        self.mod_exp.arith().load_private(layouter, value)
+    }
+
+    pub fn load_instance(
+        &self,
+        layouter: impl Layouter<F>,
+        row: usize,
+    ) -> Result<Number<F>, Error> {
+        self.mod_exp
+            .arith()
+            .load_instance(layouter, self.config.instance, row)
+    }
+
+    /// The `E_BITS` bits of `e`, most significant first. `ModExpChip`'s
+    /// select makes them boolean; this checks that they recompose to `e`.
+    pub fn element_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        e: &Number<F>,
+    ) -> Result<Vec<Number<F>>, Error> {
+        let arith = self.mod_exp.arith();
+        let two = arith.load_constant(layouter.namespace(|| "2"), F::from(2))?;
+        let value = e.0.value().map(|e| e.to_repr().as_ref()[0]);
+        let mut bits = Vec::with_capacity(E_BITS);
+        let mut sum: Option<Number<F>> = None;
+        for i in (0..E_BITS).rev() {
+            let bit = value.map(|e| F::from(((e >> i) & 1) as u64));
+            let bit = arith.load_private(layouter.namespace(|| "bit"), bit)?;
+            sum = Some(match sum {
+                None => bit.clone(),
+                Some(sum) => {
+                    let double = arith.mul(layouter.namespace(|| "2 * sum"), sum, two.clone())?;
+                    arith.add(layouter.namespace(|| "+ bit"), double, bit.clone())?
+                }
+            });
+            bits.push(bit);
+        }
+        arith.assert_equal(layouter.namespace(|| "e = bits"), sum.unwrap(), e.clone())?;
+        Ok(bits)
+    }
+
+    /// `acc^e mod P`: the accumulator with `e` added.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        acc: Number<F>,
+        e_bits: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        let p = self
+            .mod_exp
+            .arith()
+            .load_constant(layouter.namespace(|| "P"), F::from(P))?;
+        self.mod_exp
+            .mod_exp(layouter.namespace(|| "acc^e"), acc, e_bits, p, E_BITS)
+    }
+
+    /// Check `w^e = acc`: adding `e` to what `w` accumulates gives `acc`.
+    pub fn check_membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        w: Number<F>,
+        e_bits: &[Number<F>],
+        acc: Number<F>,
+    ) -> Result<(), Error> {
+        let w_e = self.add(layouter.namespace(|| "w^e"), w, e_bits)?;
+        self.mod_exp
+            .arith()
+            .assert_equal(layouter.namespace(|| "w^e = acc"), w_e, acc)
+    }
+}
+
+/// `e` is in the set behind `acc`: `instance = [acc, e]`, the witness stays
+/// private.
+#[derive(Default)]
+pub struct MembershipCircuit<F: PrimeField> {
+    pub witness: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for MembershipCircuit<F> {
+    type Config = AccumulatorConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        AccumulatorChip::configure(meta, advice, constant)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = AccumulatorChip::construct(config);
+        chip.load_table(layouter.namespace(|| "byte table"))?;
+        let acc = chip.load_instance(layouter.namespace(|| "acc"), 0)?;
+        let e = chip.load_instance(layouter.namespace(|| "e"), 1)?;
+        let w = chip.load_private(layouter.namespace(|| "w"), self.witness)?;
+        let bits = chip.element_bits(layouter.namespace(|| "bits of e"), &e)?;
+        chip.check_membership(layouter.namespace(|| "membership"), w, &bits, acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+
+    fn verify(acc: u64, e: u64, w: u64) -> bool {
+        let circuit = MembershipCircuit {
+            witness: Value::known(Fp::from(w)),
+        };
+        let public = vec![Fp::from(acc), Fp::from(e)];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_accumulator_membership() {
+        let mut set = vec![3, 7];
+        let old = accumulate(&set);
+        // Adding 11 raises the accumulator; the witness of the newest element
+        // is the accumulator before it.
+        set.push(11);
+        let acc = accumulate(&set);
+        assert_eq!(acc, pow_mod(old, 11));
+        assert_eq!(witness(&set, 11), Some(old));
+        assert!(verify(acc, 11, old));
+
+        // The older elements' witnesses change with every addition.
+        let w = witness(&set, 7).unwrap();
+        assert!(verify(acc, 7, w));
+        assert!(!verify(old, 7, w));
+        assert!(!verify(acc, 3, w));
+    }
+
+    #[test]
+    fn test_accumulator_non_member() {
+        let set = [3, 7, 11];
+        let acc = accumulate(&set);
+        // 5 divides P - 1 = 250, and acc = G^231 is no 5th power: no witness
+        // exists, so the attempts at one all fail.
+        assert!((0..P).all(|w| pow_mod(w, 5) != acc));
+        assert_eq!(witness(&set, 5), None);
+        assert!(!verify(acc, 5, acc));
+        assert!(!verify(acc, 5, witness(&set, 7).unwrap()));
+        // Nor does a witness for one element pass for another.
+        assert!(!verify(acc, 11, witness(&set, 3).unwrap()));
+    }
+
+    #[test]
+    fn test_accumulator_known_order() {
+        let acc = accumulate(&[3, 7, 11]);
+        // 13 was never added, but 13 is coprime to the public group order:
+        // w = acc^(13^-1 mod 250) is a 13th root of acc.
+        let inv = (1..P - 1).find(|d| d * 13 % (P - 1) == 1).unwrap();
+        let forged = pow_mod(acc, inv);
+        assert!(verify(acc, 13, forged));
+    }
+}
@@ -0,0 +1,144 @@
+/// chap6: public input accumulation
+/// An aggregator checks the public inputs of `N` sub-proofs in one circuit
+/// and commits to a single aggregate over them, here their sum:
+///
+///   instance = [x_0, x_1, ..., x_{N-1}, agg],   agg = Σ x_i
+///
+/// This is not recursion: the sub-proofs themselves are not verified here.
+/// What it shows is the bookkeeping half of it. The values the aggregator
+/// expects are constants of the circuit: they sit in the fixed column, and so
+/// in the verifying key, where no prover can change them. Each is tied to its
+/// instance row by an equality constraint, and the aggregate is built only
+/// out of those tied cells, so neither a single sub-instance nor the
+/// aggregate can be swapped out on its own.
+///
+/// | a0  | a1  | a2   | fixed | instance |
+/// |-----|-----|------|-------|----------|
+/// | x_0 |     |      |  x_0  |   x_0    |
+/// | ... |     |      |  ...  |   ...    |
+/// | acc | x_i | acc' |       |   agg    |   (one `add` row per x_i)
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::*,
+};
+
+use crate::gadgets::arith::{ArithChip, ArithConfig};
+
+#[derive(Debug, Clone)]
+pub struct BatchVerifierConfig {
+    arith: ArithConfig,
+    instance: Column<Instance>,
+}
+
+pub struct BatchVerifierCircuit<F: Field, const N: usize> {
+    /// The value the aggregator expects for each sub-proof's public input.
+    pub expected: [F; N],
+}
+
+impl<F: Field, const N: usize> BatchVerifierCircuit<F, N> {
+    pub fn new(expected: [F; N]) -> Self {
+        BatchVerifierCircuit { expected }
+    }
+}
+
+impl<F: Field, const N: usize> Circuit<F> for BatchVerifierCircuit<F, N> {
+    type Config = BatchVerifierConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    /// The expected values are constants, not witness: keygen needs them.
+    fn without_witnesses(&self) -> Self {
+        BatchVerifierCircuit::new(self.expected)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        BatchVerifierConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ArithChip::construct(config.arith);
+
+        let mut agg = chip.load_constant(layouter.namespace(|| "agg = 0"), F::ZERO)?;
+        for (i, x) in self.expected.iter().enumerate() {
+            let x = chip.load_constant(layouter.namespace(|| format!("x_{}", i)), *x)?;
+            chip.expose_public(
+                layouter.namespace(|| format!("x_{}", i)),
+                x.clone(),
+                config.instance,
+                i,
+            )?;
+            agg = chip.add(layouter.namespace(|| "agg + x_i"), agg, x)?;
+        }
+        chip.expose_public(layouter.namespace(|| "agg"), agg, config.instance, N)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[test]
+    fn test_batch_verify() {
+        let k = 5;
+        let xs = [Fp::from(3), Fp::from(5), Fp::from(7), Fp::from(11)];
+        let circuit = BatchVerifierCircuit::<Fp, 4>::new(xs);
+
+        let mut public_inputs = xs.to_vec();
+        public_inputs.push(Fp::from(26));
+        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        // Any single sub-instance being off breaks the batch, even with the
+        // aggregate patched up to match: the expected value it is copied
+        // from is fixed.
+        for i in 0..4 {
+            let mut wrong = public_inputs.clone();
+            wrong[i] += Fp::one();
+            wrong[4] += Fp::one();
+            let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        let mut wrong = public_inputs;
+        wrong[4] += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_batch_verify_expected_values_are_fixed() {
+        use crate::utils::circuit_hash::hash_circuit;
+
+        let k = 5;
+        let circuit = BatchVerifierCircuit::<Fp, 4>::new([3, 5, 7, 11].map(Fp::from));
+        let other = BatchVerifierCircuit::<Fp, 4>::new([3, 5, 7, 12].map(Fp::from));
+
+        // Keygen sees the expected values, and they change the fixed column.
+        let hash = hash_circuit(k, &circuit).unwrap();
+        assert_eq!(hash, hash_circuit(k, &circuit.without_witnesses()).unwrap());
+        assert_ne!(hash, hash_circuit(k, &other).unwrap());
+
+        // Instances that `other` accepts are not accepted by `circuit`.
+        let public_inputs = [3, 5, 7, 12, 27].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &other, vec![public_inputs.clone()]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
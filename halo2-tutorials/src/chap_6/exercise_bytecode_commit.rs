@@ -0,0 +1,421 @@
+/// chap6: committing to the bytecode
+/// `exercise_mini_vm` keeps the program in a lookup table, i.e. in fixed
+/// columns: the verifier needs the program itself to build the verifying key.
+/// Here the program becomes advice, and all the verifier sees is a public
+/// commitment to it:
+///
+///   C = MiMC(pc_0, encoded_0, arg_0, pc_1, encoded_1, arg_1, ...)
+///
+/// Only the program length is part of the circuit shape.
+///
+/// The natural next move, a lookup into those advice cells, is not available
+/// here: halo2_proofs 0.3 only looks up into fixed `TableColumn`s. So instead
+/// every step row selects its instruction out of the committed cells with a
+/// one-hot `sel` over the `N` program rows, with the committed `encoded`/`arg`
+/// cells copied next to it:
+///
+/// | vm step columns ... | sel[N] | encoded_copy[N] | arg_copy[N] |
+///
+///   Σ sel_j = 1,  pc = Σ j * sel_j,
+///   encoded = Σ sel_j * encoded_copy_j,  arg = Σ sel_j * arg_copy_j
+///
+/// That costs `3N` columns instead of a lookup, which is fine for the handful
+/// of instructions these exercises run.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::exercise_mini_vm::{execute, Instr, MiniVmChip, MiniVmConfig, State};
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    mimc::{mimc_hash, MimcChip, MimcConfig},
+    Number,
+};
+
+/// `(pc, encoded, arg)` of every instruction, in order.
+fn messages<F: PrimeField>(program: &[Instr]) -> Vec<F> {
+    program
+        .iter()
+        .enumerate()
+        .flat_map(|(pc, instr)| [F::from(pc as u64), F::from(instr.encode()), instr.arg()])
+        .collect()
+}
+
+/// Compute the bytecode commitment natively.
+pub fn bytecode_commitment<F: PrimeField>(program: &[Instr]) -> F {
+    mimc_hash(&messages::<F>(program))
+}
+
+/// The committed cells of one instruction.
+#[derive(Debug, Clone)]
+pub struct CommittedInstr<F: PrimeField> {
+    pub encoded: Number<F>,
+    pub arg: Number<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BytecodeCommitConfig {
+    arith: ArithConfig,
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+pub struct BytecodeCommitChip<F: PrimeField> {
+    arith: ArithChip<F>,
+    mimc: MimcChip<F>,
+    instance: Column<Instance>,
+}
+
+impl<F: PrimeField> BytecodeCommitChip<F> {
+    pub fn construct(config: BytecodeCommitConfig) -> Self {
+        BytecodeCommitChip {
+            arith: ArithChip::construct(config.arith),
+            mimc: MimcChip::construct(config.mimc),
+            instance: config.instance,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BytecodeCommitConfig {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        BytecodeCommitConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            mimc: MimcChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    /// Witness the program and hash it, returning its cells and `C`.
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Value<Instr>],
+    ) -> Result<(Vec<CommittedInstr<F>>, Number<F>), Error> {
+        let mut instrs = vec![];
+        let mut msgs = vec![];
+        for (pc, instr) in program.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("pc {}", pc));
+            let pc = self
+                .arith
+                .load_constant(layouter.namespace(|| "pc"), F::from(pc as u64))?;
+            let encoded = self.arith.load_private(
+                layouter.namespace(|| "encoded"),
+                instr.map(|i| F::from(i.encode())),
+            )?;
+            let arg = self
+                .arith
+                .load_private(layouter.namespace(|| "arg"), instr.map(|i| i.arg()))?;
+            msgs.extend([pc, encoded.clone(), arg.clone()]);
+            instrs.push(CommittedInstr { encoded, arg });
+        }
+        let commitment = self.mimc.hash(layouter.namespace(|| "commitment"), &msgs)?;
+        Ok((instrs, commitment))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        commitment: Number<F>,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(commitment.0.cell(), self.instance, 0)
+    }
+}
+
+/// The program of length `N` is private, `C` is public.
+#[derive(Default)]
+pub struct BytecodeCommitCircuit<F: PrimeField, const N: usize> {
+    pub program: Vec<Instr>,
+    _marker: PhantomData<F>,
+}
+
+/// Known instructions, or `N` unknown ones when there is no witness.
+fn program_values<const N: usize>(program: &[Instr]) -> Vec<Value<Instr>> {
+    if program.is_empty() {
+        vec![Value::unknown(); N]
+    } else {
+        assert_eq!(
+            program.len(),
+            N,
+            "program length is part of the circuit shape"
+        );
+        program.iter().map(|i| Value::known(*i)).collect()
+    }
+}
+
+impl<F: PrimeField, const N: usize> Circuit<F> for BytecodeCommitCircuit<F, N> {
+    type Config = BytecodeCommitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        BytecodeCommitChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = BytecodeCommitChip::construct(config);
+        let (_, commitment) = chip.commit(
+            layouter.namespace(|| "commit"),
+            &program_values::<N>(&self.program),
+        )?;
+        chip.expose_public(layouter.namespace(|| "C"), commitment)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommittedFetchConfig<const N: usize> {
+    sel: [Column<Advice>; N],
+    encoded_copy: [Column<Advice>; N],
+    arg_copy: [Column<Advice>; N],
+}
+
+impl<const N: usize> CommittedFetchConfig<N> {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>, vm: &MiniVmConfig<F>) -> Self {
+        let sel = [(); N].map(|_| meta.advice_column());
+        let encoded_copy = [(); N].map(|_| meta.advice_column());
+        let arg_copy = [(); N].map(|_| meta.advice_column());
+        for col in encoded_copy.iter().chain(arg_copy.iter()) {
+            meta.enable_equality(*col);
+        }
+
+        meta.create_gate("committed fetch", |meta| {
+            let q = meta.query_selector(vm.q_step());
+            let one = Expression::Constant(F::ONE);
+            let pc = meta.query_advice(vm.pc(), Rotation::cur());
+            let arg = meta.query_advice(vm.arg(), Rotation::cur());
+            let encoded = vm.encoded(meta);
+            let sel = sel.map(|col| meta.query_advice(col, Rotation::cur()));
+            let encoded_copy = encoded_copy.map(|col| meta.query_advice(col, Rotation::cur()));
+            let arg_copy = arg_copy.map(|col| meta.query_advice(col, Rotation::cur()));
+
+            let sum = |terms: Vec<Expression<F>>| {
+                terms
+                    .into_iter()
+                    .fold(Expression::Constant(F::ZERO), |acc, t| acc + t)
+            };
+            let mut constraints: Vec<_> = sel
+                .iter()
+                .map(|s| ("sel is boolean", s.clone() * (one.clone() - s.clone())))
+                .collect();
+            constraints.push(("Σ sel = 1", sum(sel.to_vec()) - one));
+            constraints.push((
+                "pc = Σ j * sel_j",
+                pc - sum(sel
+                    .iter()
+                    .enumerate()
+                    .map(|(j, s)| s.clone() * Expression::Constant(F::from(j as u64)))
+                    .collect()),
+            ));
+            constraints.push((
+                "encoded = Σ sel_j * encoded_j",
+                encoded
+                    - sum(sel
+                        .iter()
+                        .zip(encoded_copy)
+                        .map(|(s, e)| s.clone() * e)
+                        .collect()),
+            ));
+            constraints.push((
+                "arg = Σ sel_j * arg_j",
+                arg - sum(sel
+                    .iter()
+                    .zip(arg_copy)
+                    .map(|(s, a)| s.clone() * a)
+                    .collect()),
+            ));
+            Constraints::with_selector(q, constraints)
+        });
+
+        CommittedFetchConfig {
+            sel,
+            encoded_copy,
+            arg_copy,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommittedVmConfig<F: PrimeField, const N: usize> {
+    vm: MiniVmConfig<F>,
+    fetch: CommittedFetchConfig<N>,
+    commit: BytecodeCommitConfig,
+}
+
+/// The mini VM, run against a program that is only known through `C`.
+/// Instance column 0 holds the final registers, column 1 holds `C`.
+#[derive(Default)]
+pub struct CommittedVmCircuit<F: PrimeField, const N: usize> {
+    pub program: Vec<Instr>,
+    pub steps: usize,
+    pub trace: Vec<State<F>>,
+}
+
+impl<F: PrimeField, const N: usize> CommittedVmCircuit<F, N> {
+    pub fn new(program: Vec<Instr>, steps: usize) -> Self {
+        let trace = execute(&program, steps);
+        CommittedVmCircuit {
+            program,
+            steps,
+            trace,
+        }
+    }
+}
+
+impl<F: PrimeField, const N: usize> Circuit<F> for CommittedVmCircuit<F, N> {
+    type Config = CommittedVmConfig<F, N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        CommittedVmCircuit {
+            program: vec![],
+            steps: self.steps,
+            trace: vec![],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let vm = MiniVmChip::configure(meta);
+        let fetch = CommittedFetchConfig::configure(meta, &vm);
+        let commit = BytecodeCommitChip::configure(meta);
+        CommittedVmConfig { vm, fetch, commit }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let commit = BytecodeCommitChip::construct(config.commit);
+        let (program, commitment) = commit.commit(
+            layouter.namespace(|| "commit"),
+            &program_values::<N>(&self.program),
+        )?;
+        commit.expose_public(layouter.namespace(|| "C"), commitment)?;
+
+        let fetch = &config.fetch;
+        let vm = MiniVmChip::construct(config.vm);
+        let regs = vm.assign_with(
+            layouter.namespace(|| "run"),
+            &self.program,
+            &self.trace,
+            self.steps,
+            |region, row, state| {
+                for (j, instr) in program.iter().enumerate() {
+                    region.assign_advice(
+                        || "sel",
+                        fetch.sel[j],
+                        row,
+                        || state.map(|s| F::from((s.pc == j as u64) as u64)),
+                    )?;
+                    instr.encoded.0.copy_advice(
+                        || "encoded",
+                        region,
+                        fetch.encoded_copy[j],
+                        row,
+                    )?;
+                    instr
+                        .arg
+                        .0
+                        .copy_advice(|| "arg", region, fetch.arg_copy[j], row)?;
+                }
+                Ok(())
+            },
+        )?;
+        vm.expose_public(layouter.namespace(|| "registers"), regs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    /// r1 = 5!, same as in `exercise_mini_vm`.
+    fn factorial() -> Vec<Instr> {
+        vec![
+            Instr::Mov { dst: 0, imm: 5 },
+            Instr::Mov { dst: 1, imm: 1 },
+            Instr::Mov { dst: 2, imm: -1 },
+            Instr::Mul { dst: 1, a: 1, b: 0 },
+            Instr::Add { dst: 0, a: 0, b: 2 },
+            Instr::Jnz { cond: 0, target: 3 },
+            Instr::Jnz { cond: 1, target: 6 },
+        ]
+    }
+
+    fn commit_circuit<const N: usize>(program: Vec<Instr>) -> BytecodeCommitCircuit<Fp, N> {
+        BytecodeCommitCircuit {
+            program,
+            _marker: PhantomData,
+        }
+    }
+
+    #[test]
+    fn test_bytecode_commitment() {
+        let k = 12;
+        let c = bytecode_commitment::<Fp>(&factorial());
+        let circuit = commit_circuit::<7>(factorial());
+        let prover = MockProver::run(k, &circuit, vec![vec![c]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_bytecode_commitment_altered_instruction() {
+        let k = 12;
+        let c = bytecode_commitment::<Fp>(&factorial());
+        let mut altered = factorial();
+        altered[0] = Instr::Mov { dst: 0, imm: 6 };
+        let circuit = commit_circuit::<7>(altered);
+        let prover = MockProver::run(k, &circuit, vec![vec![c]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_bytecode_commitment_empty_program() {
+        let k = 4;
+        let c = bytecode_commitment::<Fp>(&[]);
+        let circuit = commit_circuit::<0>(vec![]);
+        let prover = MockProver::run(k, &circuit, vec![vec![c]]).unwrap();
+        prover.assert_satisfied();
+
+        let c = bytecode_commitment::<Fp>(&factorial());
+        let prover = MockProver::run(k, &circuit, vec![vec![c]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_committed_mini_vm() {
+        let k = 12;
+        let circuit = CommittedVmCircuit::<Fp, 7>::new(factorial(), 24);
+        let regs = circuit.trace.last().unwrap().regs.to_vec();
+        assert_eq!(regs[1], Fp::from(120));
+        let c = bytecode_commitment::<Fp>(&factorial());
+
+        let prover = MockProver::run(k, &circuit, vec![regs.clone(), vec![c]]).unwrap();
+        prover.assert_satisfied();
+
+        // Same run, but claimed against the commitment of another program.
+        let mut other = factorial();
+        other[0] = Instr::Mov { dst: 0, imm: 6 };
+        let c = bytecode_commitment::<Fp>(&other);
+        let prover = MockProver::run(k, &circuit, vec![regs, vec![c]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
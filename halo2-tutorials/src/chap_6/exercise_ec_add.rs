@@ -0,0 +1,316 @@
+/// chap6: elliptic curve point addition
+/// Add two points of `y^2 = x^3 + 5`, the curve shape both Pasta curves share,
+/// with a hand-written gate instead of `halo2_gadgets::ecc`. For `P = (x1, y1)`
+/// and `Q = (x2, y2)` the prover witnesses the slope `λ`, and
+///
+///   λ * (x2 - x1) = y2 - y1
+///   x3 = λ^2 - x1 - x2
+///   y3 = λ * (x1 - x3) - y1
+///
+/// This is the incomplete addition law: it has no answer for `x1 == x2`,
+/// which is doubling (`Q = P`) or a point and its negation (`Q = -P`). Worse,
+/// with `Q = P` the first constraint reads `λ * 0 = 0` and any `λ` passes, so
+/// the prover could pick the sum. `IsZero` on `x2 - x1` rules that case out.
+/// Both inputs are also checked to be on the curve.
+///
+///   instance = [x3, y3]
+///
+/// | a0 | a1 | a2 | a3 | a4 | a5 | a6 | a7             | s_add |
+/// |----|----|----|----|----|----|----|----------------|-------|
+/// | x1 | y1 | x2 | y2 | λ  | x3 | y3 | 1 / (x2 - x1)  |   1   |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    is_zero::{IsZeroChip, IsZeroConfig},
+    Number,
+};
+
+/// The `b` in `y^2 = x^3 + b` for Pallas and Vesta.
+const B: u64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct EcAddConfig<F: PrimeField> {
+    pub advice: [Column<Advice>; 8],
+    dx_is_zero: IsZeroConfig<F>,
+    s_add: Selector,
+}
+
+pub struct EcAddChip<F: PrimeField> {
+    config: EcAddConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> EcAddChip<F> {
+    pub fn construct(config: EcAddConfig<F>) -> Self {
+        EcAddChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 8],
+    ) -> EcAddConfig<F> {
+        for col in &advice[..7] {
+            meta.enable_equality(*col);
+        }
+        let s_add = meta.selector();
+        let [x1, y1, x2, y2, lambda, x3, y3, dx_inv] = advice;
+
+        let dx_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(s_add),
+            |meta| meta.query_advice(x2, Rotation::cur()) - meta.query_advice(x1, Rotation::cur()),
+            dx_inv,
+        );
+
+        meta.create_gate("incomplete addition", |meta| {
+            let s = meta.query_selector(s_add);
+            let [x1, y1, x2, y2, lambda, x3, y3] =
+                [x1, y1, x2, y2, lambda, x3, y3].map(|col| meta.query_advice(col, Rotation::cur()));
+            let b = Expression::Constant(F::from(B));
+            let on_curve = |x: Expression<F>, y: Expression<F>| {
+                y.clone() * y - (x.clone() * x.clone() * x + b.clone())
+            };
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("P on curve", on_curve(x1.clone(), y1.clone())),
+                    ("Q on curve", on_curve(x2.clone(), y2.clone())),
+                    (
+                        "slope",
+                        lambda.clone() * (x2.clone() - x1.clone()) - (y2 - y1.clone()),
+                    ),
+                    (
+                        "x3",
+                        x3.clone() - (lambda.clone() * lambda.clone() - x1.clone() - x2),
+                    ),
+                    ("y3", y3 - (lambda * (x1 - x3) - y1)),
+                    ("x1 != x2", dx_is_zero.expr()),
+                ],
+            )
+        });
+
+        EcAddConfig {
+            advice,
+            dx_is_zero,
+            s_add,
+        }
+    }
+
+    /// `P + Q`, for points given by their affine coordinates.
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        p: [Value<F>; 2],
+        q: [Value<F>; 2],
+    ) -> Result<[Number<F>; 2], Error> {
+        let [x1, y1] = p;
+        let [x2, y2] = q;
+        let lambda = (y2 - y1) * (x2 - x1).map(|dx| dx.invert().unwrap_or(F::ZERO));
+        self.add_with_slope(layouter, p, q, lambda)
+    }
+
+    /// `P + Q` for points already in the circuit, copied in so that the sum is
+    /// tied to the cells it came from.
+    pub fn add_assigned(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: [Number<F>; 2],
+        q: [Number<F>; 2],
+    ) -> Result<[Number<F>; 2], Error> {
+        let [x1, y1] = p.clone().map(|c| c.0.value().copied());
+        let [x2, y2] = q.clone().map(|c| c.0.value().copied());
+        let lambda = (y2 - y1) * (x2 - x1).map(|dx| dx.invert().unwrap_or(F::ZERO));
+        let x3 = lambda * lambda - x1 - x2;
+        let y3 = lambda * (x1 - x3) - y1;
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "P + Q",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                for (i, cell) in p.iter().chain(&q).enumerate() {
+                    cell.0.copy_advice(|| "input", &mut region, advice[i], 0)?;
+                }
+                region.assign_advice(|| "λ", advice[4], 0, || lambda)?;
+                IsZeroChip::construct(self.config.dx_is_zero.clone()).assign(
+                    &mut region,
+                    0,
+                    x2 - x1,
+                )?;
+                let x3 = region.assign_advice(|| "x3", advice[5], 0, || x3)?;
+                let y3 = region.assign_advice(|| "y3", advice[6], 0, || y3)?;
+                Ok([Number(x3), Number(y3)])
+            },
+        )
+    }
+
+    /// `P + Q` with a prover-supplied slope, which the gate checks.
+    pub fn add_with_slope(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: [Value<F>; 2],
+        q: [Value<F>; 2],
+        lambda: Value<F>,
+    ) -> Result<[Number<F>; 2], Error> {
+        let [x1, y1] = p;
+        let [x2, y2] = q;
+        let x3 = lambda * lambda - x1 - x2;
+        let y3 = lambda * (x1 - x3) - y1;
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "P + Q",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                for (i, (name, value)) in [
+                    ("x1", x1),
+                    ("y1", y1),
+                    ("x2", x2),
+                    ("y2", y2),
+                    ("λ", lambda),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    region.assign_advice(|| name, advice[i], 0, || value)?;
+                }
+                IsZeroChip::construct(self.config.dx_is_zero.clone()).assign(
+                    &mut region,
+                    0,
+                    x2 - x1,
+                )?;
+                let x3 = region.assign_advice(|| "x3", advice[5], 0, || x3)?;
+                let y3 = region.assign_advice(|| "y3", advice[6], 0, || y3)?;
+                Ok([Number(x3), Number(y3)])
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EcAddCircuitConfig<F: PrimeField> {
+    ec_add: EcAddConfig<F>,
+    instance: Column<Instance>,
+}
+
+/// `P + Q`, with `lambda` overriding the honest slope.
+#[derive(Default)]
+pub struct EcAddCircuit<F: PrimeField> {
+    pub p: [Value<F>; 2],
+    pub q: [Value<F>; 2],
+    pub lambda: Option<Value<F>>,
+}
+
+impl<F: PrimeField> Circuit<F> for EcAddCircuit<F> {
+    type Config = EcAddCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 8].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        EcAddCircuitConfig {
+            ec_add: EcAddChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = EcAddChip::construct(config.ec_add);
+        let [x3, y3] = match self.lambda {
+            None => chip.add(layouter.namespace(|| "P + Q"), self.p, self.q)?,
+            Some(lambda) => {
+                chip.add_with_slope(layouter.namespace(|| "P + Q"), self.p, self.q, lambda)?
+            }
+        };
+        layouter.constrain_instance(x3.0.cell(), config.instance, 0)?;
+        layouter.constrain_instance(y3.0.cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_6::exercise_pedersen::coordinates;
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{
+            group::{Curve, Group},
+            pallas, Fp,
+        },
+    };
+    use rand_core::OsRng;
+
+    const K: u32 = 4;
+
+    fn circuit(p: pallas::Affine, q: pallas::Affine) -> EcAddCircuit<Fp> {
+        EcAddCircuit {
+            p: coordinates(p).map(Value::known),
+            q: coordinates(q).map(Value::known),
+            lambda: None,
+        }
+    }
+
+    fn verify(circuit: &EcAddCircuit<Fp>, sum: [Fp; 2]) -> bool {
+        let prover = MockProver::run(K, circuit, vec![sum.to_vec()]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_ec_add_random() {
+        for _ in 0..8 {
+            let p = pallas::Point::random(OsRng);
+            let q = pallas::Point::random(OsRng);
+            let sum = coordinates((p + q).to_affine());
+            let circuit = circuit(p.to_affine(), q.to_affine());
+            assert!(verify(&circuit, sum));
+            assert!(!verify(&circuit, [sum[0], -sum[1]]));
+        }
+    }
+
+    #[test]
+    fn test_ec_add_doubling_rejected() {
+        // 2P is a perfectly good point, but x1 == x2 is out of reach.
+        let p = pallas::Point::random(OsRng);
+        let double = coordinates(p.double().to_affine());
+        assert!(!verify(&circuit(p.to_affine(), p.to_affine()), double));
+
+        // Without IsZero any slope would pass here; with it none does.
+        let mut forged = circuit(p.to_affine(), p.to_affine());
+        forged.lambda = Some(Value::known(Fp::from(3)));
+        let [x, y] = coordinates(p.to_affine());
+        let x3 = Fp::from(9) - x - x;
+        let y3 = Fp::from(3) * (x - x3) - y;
+        assert!(!verify(&forged, [x3, y3]));
+    }
+
+    #[test]
+    fn test_ec_add_forged_slope() {
+        let p = pallas::Point::random(OsRng).to_affine();
+        let q = pallas::Point::random(OsRng).to_affine();
+        let mut forged = circuit(p, q);
+        let lambda = Fp::from(3);
+        forged.lambda = Some(Value::known(lambda));
+        // The sum the forged slope leads to, which is not on the line P Q.
+        let ([x1, y1], [x2, _]) = (coordinates(p), coordinates(q));
+        let x3 = lambda * lambda - x1 - x2;
+        let y3 = lambda * (x1 - x3) - y1;
+        assert!(!verify(&forged, [x3, y3]));
+    }
+}
@@ -0,0 +1,164 @@
+/// chap6: exponential ElGamal
+/// Encryption in the multiplicative group of the field, with the field's
+/// multiplicative generator `g`, public key `pk = g^sk`, private randomness
+/// `r` and a small private message `m`:
+///
+///   c1 = g^r,    c2 = pk^r * g^m
+///
+/// The message sits in the exponent, so decryption only recovers `g^m` and
+/// has to brute-force `m`; that is why it must be small, and why the
+/// ciphertexts add up homomorphically. The circuit proves knowledge of `r`
+/// and `m` behind the public `(pk, c1, c2)`, with the same `r` in both halves.
+///
+///   instance = [pk, c1, c2]
+///
+/// `PowChip` decomposes each exponent into bits, which doubles as the range
+/// checks `r < 2^R_BITS` and `m < 2^M_BITS`.
+///
+/// | a0  | a1   | a2  | a3  | constant | instance |
+/// |-----|------|-----|-----|----------|----------|
+/// |  g  |      |     |     |    g     |    pk    |
+/// | pk  |      |     |     |          |    c1    |
+/// |  r  |      |     |     |          |    c2    |
+/// |  m  |      |     |     |          |          |
+/// |   pow rows: g^r, pk^r, g^m ...   |          |
+/// | pk^r| g^m  | c2  |     |          |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    pow::{PowChip, PowConfig},
+};
+
+const R_BITS: usize = 64;
+const M_BITS: usize = 8;
+
+/// Encrypt `m` with randomness `r` under `pk`, natively.
+pub fn encrypt<F: PrimeField>(pk: F, r: u64, m: u64) -> (F, F) {
+    let g = F::MULTIPLICATIVE_GENERATOR;
+    (g.pow_vartime([r]), pk.pow_vartime([r]) * g.pow_vartime([m]))
+}
+
+#[derive(Debug, Clone)]
+pub struct ElGamalConfig {
+    arith: ArithConfig,
+    pow: PowConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct ElGamalCircuit<F: PrimeField> {
+    pub r: Value<F>,
+    pub m: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for ElGamalCircuit<F> {
+    type Config = ElGamalConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ElGamalConfig {
+            arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+            pow: PowChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith);
+        let pow = PowChip::construct(config.pow);
+
+        let g = arith.load_constant(layouter.namespace(|| "g"), F::MULTIPLICATIVE_GENERATOR)?;
+        let pk = arith.load_instance(layouter.namespace(|| "pk"), config.instance, 0)?;
+        let r = arith.load_private(layouter.namespace(|| "r"), self.r)?;
+        let m = arith.load_private(layouter.namespace(|| "m"), self.m)?;
+
+        let c1 = pow.pow(layouter.namespace(|| "g^r"), g.clone(), r.clone(), R_BITS)?;
+        let pk_r = pow.pow(layouter.namespace(|| "pk^r"), pk, r, R_BITS)?;
+        let g_m = pow.pow(layouter.namespace(|| "g^m"), g, m, M_BITS)?;
+        let c2 = arith.mul(layouter.namespace(|| "pk^r * g^m"), pk_r, g_m)?;
+
+        arith.expose_public(layouter.namespace(|| "c1"), c1, config.instance, 1)?;
+        arith.expose_public(layouter.namespace(|| "c2"), c2, config.instance, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{arithmetic::Field, dev::MockProver, pasta::Fp};
+
+    const K: u32 = 8;
+
+    fn pk() -> Fp {
+        Fp::MULTIPLICATIVE_GENERATOR.pow_vartime([1234u64])
+    }
+
+    fn circuit(r: u64, m: u64) -> ElGamalCircuit<Fp> {
+        ElGamalCircuit {
+            r: Value::known(Fp::from(r)),
+            m: Value::known(Fp::from(m)),
+        }
+    }
+
+    #[test]
+    fn test_elgamal_valid() {
+        let (c1, c2) = encrypt(pk(), 987_654_321, 5);
+        let prover =
+            MockProver::run(K, &circuit(987_654_321, 5), vec![vec![pk(), c1, c2]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_elgamal_wrong_c2() {
+        let (c1, c2) = encrypt(pk(), 987_654_321, 5);
+        let prover = MockProver::run(
+            K,
+            &circuit(987_654_321, 5),
+            vec![vec![pk(), c1, c2 + Fp::one()]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_elgamal_r_mismatch() {
+        // c1 uses one r and c2 another; no single r opens both.
+        let (c1, _) = encrypt(pk(), 987_654_321, 5);
+        let (_, c2) = encrypt(pk(), 123_456_789, 5);
+        for r in [987_654_321, 123_456_789] {
+            let prover = MockProver::run(K, &circuit(r, 5), vec![vec![pk(), c1, c2]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_elgamal_message_out_of_range() {
+        let m = 1 << M_BITS;
+        let (c1, c2) = encrypt(pk(), 987_654_321, m);
+        let prover =
+            MockProver::run(K, &circuit(987_654_321, m), vec![vec![pk(), c1, c2]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,664 @@
+/// chap6: a four-register machine
+/// Prove that running a public program for `T` steps from the all-zero state
+/// ends with public register values.
+///
+/// Opcodes:
+///   ADD dst, a, b   r[dst] = r[a] + r[b]
+///   MUL dst, a, b   r[dst] = r[a] * r[b]
+///   MOV dst, imm    r[dst] = imm
+///   JNZ cond, tgt   pc = r[cond] != 0 ? tgt : pc + 1
+///
+/// There is no HALT: a finished program parks itself in a `JNZ` loop on a
+/// nonzero register, so the rest of the bounded trace repeats its final state.
+///
+/// Unlike the stack machine, the program is not laid out row by row, because
+/// with jumps the `i`-th step does not run the `i`-th instruction. Instead it
+/// sits in a lookup table of `(pc, encoded instruction, arg)` and every step
+/// *fetches* its instruction by looking up its own `pc`:
+///
+///   encoded = op + 4 * dst + 16 * a + 64 * b
+///
+/// `arg` (the MOV immediate or the JNZ target) is a full field element, so it
+/// gets its own table column: packed into `encoded` it would let the prover
+/// trade bits between `arg` and the register indices.
+///
+/// Each step row holds the state *before* the step and the decoded
+/// instruction, every field of which is one-hot over four advice columns:
+/// | pc | r0..r3 | op[4] | dst[4] | a[4] | b[4] | arg | lhs | rhs | out | q_step |
+///
+///   lhs = Σ a_k * r_k,  rhs = Σ b_k * r_k
+///   out = op_add * (lhs + rhs) + op_mul * lhs * rhs + op_mov * arg
+///   r_k' = r_k + dst_k * (op_add + op_mul + op_mov) * (out - r_k)
+///   pc'  = JNZ ? (is_zero(lhs) ? pc + 1 : arg) : pc + 1
+///
+/// The row after the last step holds the final registers, which are exposed
+/// as public inputs 0..4.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::is_zero::{IsZeroChip, IsZeroConfig};
+
+pub const NUM_REGS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Add { dst: usize, a: usize, b: usize },
+    Mul { dst: usize, a: usize, b: usize },
+    Mov { dst: usize, imm: i64 },
+    Jnz { cond: usize, target: u64 },
+}
+
+/// What the trace decodes for a `pc` outside the program. The fetch lookup is
+/// what rejects such a row, whatever instruction it pretends to run.
+const FILLER: Instr = Instr::Add { dst: 0, a: 0, b: 0 };
+
+impl Instr {
+    fn op(&self) -> usize {
+        match self {
+            Instr::Add { .. } => 0,
+            Instr::Mul { .. } => 1,
+            Instr::Mov { .. } => 2,
+            Instr::Jnz { .. } => 3,
+        }
+    }
+
+    fn dst(&self) -> usize {
+        match self {
+            Instr::Add { dst, .. } | Instr::Mul { dst, .. } | Instr::Mov { dst, .. } => *dst,
+            Instr::Jnz { .. } => 0,
+        }
+    }
+
+    fn a(&self) -> usize {
+        match self {
+            Instr::Add { a, .. } | Instr::Mul { a, .. } => *a,
+            Instr::Jnz { cond, .. } => *cond,
+            Instr::Mov { .. } => 0,
+        }
+    }
+
+    fn b(&self) -> usize {
+        match self {
+            Instr::Add { b, .. } | Instr::Mul { b, .. } => *b,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn arg<F: PrimeField>(&self) -> F {
+        match self {
+            Instr::Mov { imm, .. } if *imm < 0 => -F::from(imm.unsigned_abs()),
+            Instr::Mov { imm, .. } => F::from(*imm as u64),
+            Instr::Jnz { target, .. } => F::from(*target),
+            _ => F::ZERO,
+        }
+    }
+
+    pub(crate) fn encode(&self) -> u64 {
+        (self.op() + 4 * self.dst() + 16 * self.a() + 64 * self.b()) as u64
+    }
+}
+
+/// The machine state before a step runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct State<F: PrimeField> {
+    pub pc: u64,
+    pub regs: [F; NUM_REGS],
+}
+
+fn fetch(program: &[Instr], pc: u64) -> Instr {
+    program.get(pc as usize).copied().unwrap_or(FILLER)
+}
+
+/// Run `program` natively for `steps` steps. A `pc` outside the program is not
+/// trapped here, the machine just stays put and leaves it to the circuit to
+/// reject the fetch.
+pub fn execute<F: PrimeField>(program: &[Instr], steps: usize) -> Vec<State<F>> {
+    let mut trace = vec![State::default()];
+    for _ in 0..steps {
+        let s = *trace.last().unwrap();
+        let mut next = State { pc: s.pc + 1, ..s };
+        match program.get(s.pc as usize) {
+            Some(Instr::Add { dst, a, b }) => next.regs[*dst] = s.regs[*a] + s.regs[*b],
+            Some(Instr::Mul { dst, a, b }) => next.regs[*dst] = s.regs[*a] * s.regs[*b],
+            Some(instr @ Instr::Mov { dst, .. }) => next.regs[*dst] = instr.arg(),
+            Some(Instr::Jnz { cond, target }) => {
+                if s.regs[*cond] != F::ZERO {
+                    next.pc = *target;
+                }
+            }
+            None => next.pc = s.pc,
+        }
+        trace.push(next);
+    }
+    trace
+}
+
+#[derive(Debug, Clone)]
+pub struct MiniVmConfig<F: PrimeField> {
+    pc: Column<Advice>,
+    regs: [Column<Advice>; NUM_REGS],
+    op: [Column<Advice>; 4],
+    dst: [Column<Advice>; NUM_REGS],
+    a: [Column<Advice>; NUM_REGS],
+    b: [Column<Advice>; NUM_REGS],
+    arg: Column<Advice>,
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    out: Column<Advice>,
+    q_step: Selector,
+    is_zero: IsZeroConfig<F>,
+    instance: Column<Instance>,
+}
+
+impl<F: PrimeField> MiniVmConfig<F> {
+    pub(crate) fn pc(&self) -> Column<Advice> {
+        self.pc
+    }
+
+    pub(crate) fn arg(&self) -> Column<Advice> {
+        self.arg
+    }
+
+    pub(crate) fn q_step(&self) -> Selector {
+        self.q_step
+    }
+
+    /// `op + 4 * dst + 16 * a + 64 * b`, rebuilt from the one-hot columns.
+    pub(crate) fn encoded(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        [(self.op, 1), (self.dst, 4), (self.a, 16), (self.b, 64)]
+            .into_iter()
+            .flat_map(|(group, weight)| {
+                group
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(k, col)| (col, k * weight))
+            })
+            .map(|(col, weight)| {
+                meta.query_advice(col, Rotation::cur())
+                    * Expression::Constant(F::from(weight as u64))
+            })
+            .reduce(|acc, term| acc + term)
+            .unwrap()
+    }
+}
+
+/// The program as a lookup table of `(active, pc, encoded, arg)` rows.
+#[derive(Debug, Clone)]
+pub struct ProgramTable {
+    t_active: TableColumn,
+    t_pc: TableColumn,
+    t_encoded: TableColumn,
+    t_arg: TableColumn,
+}
+
+impl ProgramTable {
+    /// Fetch: (pc, encoded, arg) must be a row of the program. Disabled rows
+    /// look up the all-zero row, which `t_active` keeps apart from the real
+    /// instructions.
+    pub fn configure<F: PrimeField>(
+        meta: &mut ConstraintSystem<F>,
+        vm: &MiniVmConfig<F>,
+    ) -> ProgramTable {
+        let t_active = meta.lookup_table_column();
+        let t_pc = meta.lookup_table_column();
+        let t_encoded = meta.lookup_table_column();
+        let t_arg = meta.lookup_table_column();
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(vm.q_step);
+            let pc = meta.query_advice(vm.pc, Rotation::cur());
+            let arg = meta.query_advice(vm.arg, Rotation::cur());
+            let encoded = vm.encoded(meta);
+            vec![
+                (q.clone(), t_active),
+                (q.clone() * pc, t_pc),
+                (q.clone() * encoded, t_encoded),
+                (q * arg, t_arg),
+            ]
+        });
+
+        ProgramTable {
+            t_active,
+            t_pc,
+            t_encoded,
+            t_arg,
+        }
+    }
+
+    pub fn load<F: PrimeField>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Instr],
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "program",
+            |mut table| {
+                let rows = std::iter::once([F::ZERO; 4]).chain(program.iter().enumerate().map(
+                    |(pc, instr)| {
+                        [
+                            F::ONE,
+                            F::from(pc as u64),
+                            F::from(instr.encode()),
+                            instr.arg(),
+                        ]
+                    },
+                ));
+                for (offset, row) in rows.enumerate() {
+                    for (col, value) in [self.t_active, self.t_pc, self.t_encoded, self.t_arg]
+                        .into_iter()
+                        .zip(row)
+                    {
+                        table.assign_cell(|| "program", col, offset, || Value::known(value))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+pub struct MiniVmChip<F: PrimeField> {
+    config: MiniVmConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MiniVmChip<F> {
+    pub fn construct(config: MiniVmConfig<F>) -> Self {
+        MiniVmChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MiniVmConfig<F> {
+        let mut advice = || meta.advice_column();
+        let pc = advice();
+        let regs = [advice(), advice(), advice(), advice()];
+        let op = [advice(), advice(), advice(), advice()];
+        let dst = [advice(), advice(), advice(), advice()];
+        let a = [advice(), advice(), advice(), advice()];
+        let b = [advice(), advice(), advice(), advice()];
+        let arg = advice();
+        let lhs = advice();
+        let rhs = advice();
+        let out = advice();
+        let value_inv = advice();
+        let q_step = meta.complex_selector();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        meta.enable_equality(pc);
+        for col in regs {
+            meta.enable_equality(col);
+        }
+
+        let one = || Expression::Constant(F::ONE);
+
+        // Every field of the instruction is one-hot.
+        meta.create_gate("decode", |meta| {
+            let q = meta.query_selector(q_step);
+            let mut constraints = vec![];
+            for (name, group) in [("op", op), ("dst", dst), ("a", a), ("b", b)] {
+                let bits: Vec<_> = group
+                    .iter()
+                    .map(|col| meta.query_advice(*col, Rotation::cur()))
+                    .collect();
+                for bit in &bits {
+                    constraints.push((name, bit.clone() * (one() - bit.clone())));
+                }
+                let sum = bits.into_iter().reduce(|acc, bit| acc + bit).unwrap();
+                constraints.push((name, sum - one()));
+            }
+            Constraints::with_selector(q, constraints)
+        });
+
+        // | pc  | r_k  | op | dst | a | b | arg | lhs | rhs | out | q_step |
+        // | pc' | r_k' |    |     |   |   |     |     |     |     |        |
+        meta.create_gate("execute", |meta| {
+            let q = meta.query_selector(q_step);
+            let r = regs.map(|col| meta.query_advice(col, Rotation::cur()));
+            let r_next = regs.map(|col| meta.query_advice(col, Rotation::next()));
+            let [op_add, op_mul, op_mov, _] = op.map(|col| meta.query_advice(col, Rotation::cur()));
+            let dst = dst.map(|col| meta.query_advice(col, Rotation::cur()));
+            let a = a.map(|col| meta.query_advice(col, Rotation::cur()));
+            let b = b.map(|col| meta.query_advice(col, Rotation::cur()));
+            let arg = meta.query_advice(arg, Rotation::cur());
+            let lhs = meta.query_advice(lhs, Rotation::cur());
+            let rhs = meta.query_advice(rhs, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            let select = |sel: &[Expression<F>; 4]| {
+                sel.iter()
+                    .zip(r.iter())
+                    .map(|(s, r)| s.clone() * r.clone())
+                    .reduce(|acc, term| acc + term)
+                    .unwrap()
+            };
+            let mut constraints = vec![
+                ("lhs = r[a]", lhs.clone() - select(&a)),
+                ("rhs = r[b]", rhs.clone() - select(&b)),
+                (
+                    "out",
+                    out.clone()
+                        - (op_add.clone() * (lhs.clone() + rhs.clone())
+                            + op_mul.clone() * lhs * rhs
+                            + op_mov.clone() * arg),
+                ),
+            ];
+            let is_write = op_add + op_mul + op_mov;
+            for ((r, r_next), dst) in r.into_iter().zip(r_next).zip(dst) {
+                constraints.push((
+                    "r' = r + write * (out - r)",
+                    r_next - r.clone() - dst * is_write.clone() * (out.clone() - r),
+                ));
+            }
+            Constraints::with_selector(q, constraints)
+        });
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_step),
+            |meta| meta.query_advice(lhs, Rotation::cur()),
+            value_inv,
+        );
+
+        meta.create_gate("pc", |meta| {
+            let q = meta.query_selector(q_step);
+            let op_jnz = meta.query_advice(op[3], Rotation::cur());
+            let pc_cur = meta.query_advice(pc, Rotation::cur());
+            let pc_next = meta.query_advice(pc, Rotation::next());
+            let arg = meta.query_advice(arg, Rotation::cur());
+            let is_zero = is_zero.expr();
+            let jump = is_zero.clone() * (pc_cur.clone() + one()) + (one() - is_zero) * arg;
+            Constraints::with_selector(
+                q,
+                vec![
+                    (
+                        "pc' = pc + 1",
+                        (one() - op_jnz.clone()) * (pc_next.clone() - pc_cur - one()),
+                    ),
+                    ("pc' = jnz target", op_jnz * (pc_next - jump)),
+                ],
+            )
+        });
+
+        MiniVmConfig {
+            pc,
+            regs,
+            op,
+            dst,
+            a,
+            b,
+            arg,
+            lhs,
+            rhs,
+            out,
+            q_step,
+            is_zero,
+            instance,
+        }
+    }
+
+    /// Lay out `steps` steps of the trace, returning the final registers.
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        program: &[Instr],
+        trace: &[State<F>],
+        steps: usize,
+    ) -> Result<[AssignedCell<F, F>; NUM_REGS], Error> {
+        self.assign_with(layouter, program, trace, steps, |_, _, _| Ok(()))
+    }
+
+    /// Like `assign`, but also hands every step row to `on_step`, so a caller
+    /// can lay out its own fetch argument next to the decoded instruction.
+    pub fn assign_with(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Instr],
+        trace: &[State<F>],
+        steps: usize,
+        mut on_step: impl FnMut(&mut Region<'_, F>, usize, Value<State<F>>) -> Result<(), Error>,
+    ) -> Result<[AssignedCell<F, F>; NUM_REGS], Error> {
+        let config = &self.config;
+        let is_zero = IsZeroChip::construct(config.is_zero.clone());
+        layouter.assign_region(
+            || "mini vm",
+            |mut region| {
+                let mut last_regs = vec![];
+                for row in 0..=steps {
+                    let state = trace
+                        .get(row)
+                        .map_or(Value::unknown(), |s| Value::known(*s));
+
+                    last_regs = if row == 0 {
+                        // The machine starts from pc = 0 with every register cleared.
+                        region.assign_advice_from_constant(|| "init", config.pc, 0, F::ZERO)?;
+                        config
+                            .regs
+                            .iter()
+                            .map(|col| {
+                                region.assign_advice_from_constant(|| "init", *col, 0, F::ZERO)
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?
+                    } else {
+                        region.assign_advice(
+                            || "pc",
+                            config.pc,
+                            row,
+                            || state.map(|s| F::from(s.pc)),
+                        )?;
+                        config
+                            .regs
+                            .iter()
+                            .enumerate()
+                            .map(|(k, col)| {
+                                region.assign_advice(|| "r", *col, row, || state.map(|s| s.regs[k]))
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?
+                    };
+
+                    if row == steps {
+                        break;
+                    }
+                    config.q_step.enable(&mut region, row)?;
+
+                    let instr = state.map(|s| fetch(program, s.pc));
+                    let one_hot = |field: fn(&Instr) -> usize| {
+                        move |k: usize| instr.map(|i| F::from((field(&i) == k) as u64))
+                    };
+                    for (cols, bit) in [
+                        (config.op, one_hot(Instr::op)),
+                        (config.dst, one_hot(Instr::dst)),
+                        (config.a, one_hot(Instr::a)),
+                        (config.b, one_hot(Instr::b)),
+                    ] {
+                        for (k, col) in cols.into_iter().enumerate() {
+                            region.assign_advice(|| "one-hot", col, row, || bit(k))?;
+                        }
+                    }
+
+                    let arg = instr.map(|i| i.arg());
+                    let lhs = state.zip(instr).map(|(s, i)| s.regs[i.a()]);
+                    let rhs = state.zip(instr).map(|(s, i)| s.regs[i.b()]);
+                    let out = instr
+                        .zip(lhs.zip(rhs))
+                        .zip(arg)
+                        .map(|((i, (l, r)), arg)| match i {
+                            Instr::Add { .. } => l + r,
+                            Instr::Mul { .. } => l * r,
+                            Instr::Mov { .. } => arg,
+                            Instr::Jnz { .. } => F::ZERO,
+                        });
+                    region.assign_advice(|| "arg", config.arg, row, || arg)?;
+                    region.assign_advice(|| "lhs", config.lhs, row, || lhs)?;
+                    region.assign_advice(|| "rhs", config.rhs, row, || rhs)?;
+                    region.assign_advice(|| "out", config.out, row, || out)?;
+                    is_zero.assign(&mut region, row, lhs)?;
+                    on_step(&mut region, row, state)?;
+                }
+                Ok(last_regs.try_into().unwrap())
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        regs: [AssignedCell<F, F>; NUM_REGS],
+    ) -> Result<(), Error> {
+        for (row, cell) in regs.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), self.config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MiniVmCircuit<F: PrimeField> {
+    pub program: Vec<Instr>,
+    pub steps: usize,
+    pub trace: Vec<State<F>>,
+}
+
+impl<F: PrimeField> MiniVmCircuit<F> {
+    pub fn new(program: Vec<Instr>, steps: usize) -> Self {
+        let trace = execute(&program, steps);
+        MiniVmCircuit {
+            program,
+            steps,
+            trace,
+        }
+    }
+
+    /// The final registers, in instance order.
+    pub fn public_inputs(&self) -> Vec<F> {
+        self.trace.last().unwrap().regs.to_vec()
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for MiniVmCircuit<F> {
+    type Config = (MiniVmConfig<F>, ProgramTable);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MiniVmCircuit {
+            program: self.program.clone(),
+            steps: self.steps,
+            trace: vec![],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let vm = MiniVmChip::configure(meta);
+        let table = ProgramTable::configure(meta, &vm);
+        (vm, table)
+    }
+
+    fn synthesize(
+        &self,
+        (vm, table): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        table.load(layouter.namespace(|| "program"), &self.program)?;
+        let chip = MiniVmChip::construct(vm);
+        let regs = chip.assign(
+            layouter.namespace(|| "run"),
+            &self.program,
+            &self.trace,
+            self.steps,
+        )?;
+        chip.expose_public(layouter.namespace(|| "registers"), regs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        pasta::Fp,
+    };
+
+    /// r1 = 5!, counting r0 down to zero.
+    fn factorial() -> Vec<Instr> {
+        vec![
+            Instr::Mov { dst: 0, imm: 5 },
+            Instr::Mov { dst: 1, imm: 1 },
+            Instr::Mov { dst: 2, imm: -1 },
+            Instr::Mul { dst: 1, a: 1, b: 0 },
+            Instr::Add { dst: 0, a: 0, b: 2 },
+            Instr::Jnz { cond: 0, target: 3 },
+            Instr::Jnz { cond: 1, target: 6 },
+        ]
+    }
+
+    fn is_lookup_failure(prover: &MockProver<Fp>) -> bool {
+        prover
+            .verify()
+            .unwrap_err()
+            .iter()
+            .any(|e| matches!(e, VerifyFailure::Lookup { .. }))
+    }
+
+    #[test]
+    fn test_mini_vm_factorial() {
+        let k = 6;
+        let circuit = MiniVmCircuit::<Fp>::new(factorial(), 24);
+        let public_inputs = circuit.public_inputs();
+        assert_eq!(
+            public_inputs,
+            vec![Fp::ZERO, Fp::from(120), -Fp::ONE, Fp::ZERO]
+        );
+
+        let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        let mut wrong = public_inputs;
+        wrong[1] = Fp::from(121);
+        let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_mini_vm_out_of_bounds_jump() {
+        let k = 6;
+        let program = vec![
+            Instr::Mov { dst: 0, imm: 1 },
+            Instr::Jnz { cond: 0, target: 9 },
+        ];
+        let circuit = MiniVmCircuit::<Fp>::new(program, 4);
+        let prover = MockProver::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+        assert!(is_lookup_failure(&prover));
+    }
+
+    #[test]
+    fn test_mini_vm_wrong_register_update() {
+        let k = 6;
+        let mut circuit = MiniVmCircuit::<Fp>::new(factorial(), 24);
+        // Pretend the first MUL produced 2, and carry the lie on as far as r1 goes.
+        for state in circuit.trace.iter_mut().skip(4) {
+            state.regs[1] *= Fp::from(2);
+        }
+        let prover = MockProver::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_mini_vm_fetch_pc_not_in_program() {
+        let k = 6;
+        // Nothing parks the machine, so it runs off the end of the program.
+        let program = vec![Instr::Mov { dst: 0, imm: 2 }];
+        let circuit = MiniVmCircuit::<Fp>::new(program, 2);
+        let prover = MockProver::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+        assert!(is_lookup_failure(&prover));
+    }
+}
@@ -0,0 +1,279 @@
+/// chap6: Pedersen commitment
+/// Commit to a message `m` with blinding `r` on the Pallas curve, with two
+/// fixed generators `G` and `H` whose discrete log relation nobody knows:
+///
+///   C = m*G + r*H
+///
+/// `r` hides `m`, and opening `C` to a second `m'` would reveal `log_G(H)`.
+/// The circuit proves knowledge of an opening `(m, r)` of the public `C`:
+///
+///   instance = [C.x, C.y]
+///
+/// This uses `halo2_gadgets::ecc` instead of hand-written gates. Fixed-base
+/// scalar multiplication splits the scalar into 3-bit windows and looks each
+/// window's multiple of the base up through precomputed Lagrange coefficients.
+/// The per-window tables come from `find_zs_and_us`, computed once for each of
+/// `G` and `H`. The chip represents the identity as `(0, 0)`, and so does the
+/// instance.
+///
+/// | a0 .. a9        | lagrange_coeffs[8] | constant | table    | instance |
+/// |-----------------|--------------------|----------|----------|----------|
+/// |  witness m      |                    |          | 0..2^10  |   C.x    |
+/// |  m*G: one row per window ...         |          |          |   C.y    |
+/// |  witness r, r*H ...                  |          |          |          |
+/// |  m*G + r*H                           |          |          |          |
+use std::sync::OnceLock;
+
+use halo2_gadgets::{
+    ecc::{
+        chip::{
+            find_zs_and_us, BaseFieldElem, EccChip, EccConfig, FixedPoint, FullScalar, ShortScalar,
+            H, NUM_WINDOWS,
+        },
+        FixedPoint as FixedPointGadget, FixedPoints, ScalarFixed,
+    },
+    sinsemilla,
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, CurveExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{
+        group::{ff::PrimeField, Curve, Group},
+        pallas,
+    },
+    plonk::*,
+};
+
+/// A full-width fixed base with its window tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PedersenBase {
+    generator: pallas::Affine,
+    zs_and_us: &'static [(u64, [pallas::Base; H])],
+}
+
+type ZsAndUs = OnceLock<Vec<(u64, [pallas::Base; H])>>;
+
+fn base(point: pallas::Affine, cell: &'static ZsAndUs) -> PedersenBase {
+    PedersenBase {
+        generator: point,
+        zs_and_us: cell.get_or_init(|| find_zs_and_us(point, NUM_WINDOWS).unwrap()),
+    }
+}
+
+impl PedersenBase {
+    pub fn g() -> Self {
+        static ZS_AND_US: ZsAndUs = OnceLock::new();
+        base(pallas::Point::generator().to_affine(), &ZS_AND_US)
+    }
+
+    pub fn h() -> Self {
+        static ZS_AND_US: ZsAndUs = OnceLock::new();
+        base(
+            pallas::Point::hash_to_curve("halo2-step-by-step:pedersen")(b"H").to_affine(),
+            &ZS_AND_US,
+        )
+    }
+}
+
+impl FixedPoint<pallas::Affine> for PedersenBase {
+    type FixedScalarKind = FullScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        self.generator
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        self.zs_and_us
+            .iter()
+            .map(|(_, us)| us.map(|u| u.to_repr()))
+            .collect()
+    }
+
+    fn z(&self) -> Vec<u64> {
+        self.zs_and_us.iter().map(|(z, _)| *z).collect()
+    }
+}
+
+/// Short and base-field fixed-base multiplications are not used here, so
+/// their bases cannot even be constructed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unused {}
+
+impl FixedPoint<pallas::Affine> for Unused {
+    type FixedScalarKind = ShortScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        match *self {}
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        match *self {}
+    }
+
+    fn z(&self) -> Vec<u64> {
+        match *self {}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnusedBase {}
+
+impl FixedPoint<pallas::Affine> for UnusedBase {
+    type FixedScalarKind = BaseFieldElem;
+
+    fn generator(&self) -> pallas::Affine {
+        match *self {}
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        match *self {}
+    }
+
+    fn z(&self) -> Vec<u64> {
+        match *self {}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PedersenBases;
+
+impl FixedPoints<pallas::Affine> for PedersenBases {
+    type FullScalar = PedersenBase;
+    type ShortScalar = Unused;
+    type Base = UnusedBase;
+}
+
+/// The affine coordinates of `point`, with the identity at `(0, 0)`.
+pub fn coordinates(point: pallas::Affine) -> [pallas::Base; 2] {
+    let coordinates: Option<Coordinates<pallas::Affine>> = point.coordinates().into();
+    coordinates
+        .map(|c| [*c.x(), *c.y()])
+        .unwrap_or([pallas::Base::zero(); 2])
+}
+
+/// Commit to `m` with blinding `r`, natively.
+pub fn commit(m: pallas::Scalar, r: pallas::Scalar) -> pallas::Affine {
+    (PedersenBase::g().generator * m + PedersenBase::h().generator * r).to_affine()
+}
+
+#[derive(Debug, Clone)]
+pub struct PedersenConfig {
+    ecc: EccConfig<PedersenBases>,
+    range_check: LookupRangeCheckConfig<pallas::Base, { sinsemilla::primitives::K }>,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct PedersenCircuit {
+    pub m: Value<pallas::Scalar>,
+    pub r: Value<pallas::Scalar>,
+}
+
+impl Circuit<pallas::Base> for PedersenCircuit {
+    type Config = PedersenConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice: [Column<Advice>; 10] = (0..10)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lagrange_coeffs: [Column<Fixed>; 8] = (0..8)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let range_check = LookupRangeCheckConfig::configure(meta, advice[9], table);
+        PedersenConfig {
+            ecc: EccChip::configure(meta, advice, lagrange_coeffs, range_check),
+            range_check,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let ecc = EccChip::construct(config.ecc);
+        config.range_check.load(&mut layouter)?;
+
+        let m = ScalarFixed::new(ecc.clone(), layouter.namespace(|| "m"), self.m)?;
+        let r = ScalarFixed::new(ecc.clone(), layouter.namespace(|| "r"), self.r)?;
+        let g = FixedPointGadget::from_inner(ecc.clone(), PedersenBase::g());
+        let h = FixedPointGadget::from_inner(ecc, PedersenBase::h());
+        let (mg, _) = g.mul(layouter.namespace(|| "m*G"), m)?;
+        let (rh, _) = h.mul(layouter.namespace(|| "r*H"), r)?;
+        let c = mg.add(layouter.namespace(|| "m*G + r*H"), &rh)?;
+
+        layouter.constrain_instance(c.inner().x().cell(), config.instance, 0)?;
+        layouter.constrain_instance(c.inner().y().cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    const K: u32 = 11;
+
+    fn verify(m: pallas::Scalar, r: pallas::Scalar, commitment: pallas::Affine) -> bool {
+        let circuit = PedersenCircuit {
+            m: Value::known(m),
+            r: Value::known(r),
+        };
+        let prover = MockProver::run(K, &circuit, vec![coordinates(commitment).to_vec()]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_pedersen_open() {
+        let (m, r) = (pallas::Scalar::from(42), pallas::Scalar::from(0xdead_beef));
+        assert!(verify(m, r, commit(m, r)));
+    }
+
+    #[test]
+    fn test_pedersen_wrong_message() {
+        let (m, r) = (pallas::Scalar::from(42), pallas::Scalar::from(0xdead_beef));
+        let c = commit(m, r);
+        assert!(!verify(m + pallas::Scalar::one(), r, c));
+        assert!(!verify(m, r + pallas::Scalar::one(), c));
+    }
+
+    #[test]
+    fn test_pedersen_zero_blinding() {
+        // With r = 0 the commitment is just m*G: still a valid opening, but
+        // it hides nothing, since anyone can try candidate messages.
+        let m = pallas::Scalar::from(42);
+        let c = commit(m, pallas::Scalar::zero());
+        assert_eq!(c, (PedersenBase::g().generator * m).to_affine());
+        assert!(verify(m, pallas::Scalar::zero(), c));
+    }
+
+    #[test]
+    fn test_pedersen_identity() {
+        // m = r = 0 commits to the identity, which the chip and the instance
+        // both encode as (0, 0). The only other openings of the identity
+        // would give away log_G(H).
+        let zero = pallas::Scalar::zero();
+        let c = commit(zero, zero);
+        assert_eq!(c, pallas::Point::identity().to_affine());
+        assert_eq!(coordinates(c), [pallas::Base::zero(); 2]);
+        assert!(verify(zero, zero, c));
+        assert!(!verify(pallas::Scalar::one(), zero, c));
+    }
+}
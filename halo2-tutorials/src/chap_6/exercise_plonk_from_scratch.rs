@@ -0,0 +1,347 @@
+/// chap6: a toy PLONK verifier, in a circuit
+/// Recursion means verifying a proof inside a circuit: the verifier's checks
+/// become constraints, and the proof becomes a witness. This is a sketch of
+/// that structure for a toy PLONK over the three rows of chap 1's simple
+/// circuit, `a^2 * b^2 * c = out` with `a = 2, b = 3, c = 1`:
+///
+///   | row | a  | b | c  |
+///   |-----|----|---|----|
+///   |  1  |  2 | 3 |  6 |    2 * 3 = 6
+///   |  2  |  6 | 6 | 36 |    6 * 6 = 36
+///   |  3  | 36 | 1 | 36 |    36 * 1 = 36
+///
+/// The columns are interpolated over `H = {1, 2, 3}` into `a(X), b(X),
+/// c(X)`, and every row satisfies the gate
+///
+///   q_M * a * b + q_L * a + q_R * b + q_O * c + q_C = 0
+///
+/// (all rows multiply: `q_M = 1`, `q_O = -1`) exactly when the left side is
+/// divisible by `Z(X) = (X - 1)(X - 2)(X - 3)`, with a quotient `t(X)`. The
+/// transcript holds
+///
+///   - the commitment `cm = MiMC(a, b, c, t coefficients)`,
+///   - the challenge `z = MiMC(cm)`,
+///   - the opening: `a(z), b(z), c(z), t(z)` and the coefficients.
+///
+/// and the verifier checks, in circuit:
+///
+///   1. `cm` is the hash of the coefficients,
+///   2. `z` is the hash of `cm`, so the prover could not pick it,
+///   3. each claimed evaluation is the polynomial at `z`, by `PolynomialEvalChip`,
+///   4. the gate identity at `z`: `q_M a b + ... + q_C = t Z`,
+///   5. `c(3)`, the last row's output, is the public `out`.
+///
+/// Both sides of 4 are polynomials of degree at most 6 fixed before `z`, so
+/// if they differ they agree at a random `z` with probability `6 / p`.
+///
+/// The toy gives up everything that makes PLONK a proof system rather than
+/// a protocol sketch: the "commitment" is opened by revealing the
+/// coefficients, so nothing is hidden and a real one would be an IPA or KZG
+/// opening, whose check (an MSM, or a pairing) is the expensive part of a
+/// real recursive verifier. There is no permutation argument either, so the
+/// wiring between rows (`c` of row 1 is `a` and `b` of row 2) is not
+/// enforced; each row is checked on its own.
+///
+/// | a0 | a1 | a2 | constant | instance |
+/// |----|----|----|----------|----------|
+/// | transcript loads             |  out  |
+/// | MiMC: cm, then z             |       |
+/// | Horner: openings, selectors, Z(z), c(3)
+/// | q_M a b + q_L a + q_R b + q_O c + q_C = t Z
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    mimc::{MimcChip, MimcConfig},
+    polynomial_eval::{PolynomialEvalChip, PolynomialEvalConfig},
+    Number,
+};
+
+/// Field elements in a transcript.
+const LEN: usize = 19;
+
+fn int<F: PrimeField>(x: i64) -> F {
+    if x < 0 {
+        -F::from(x.unsigned_abs())
+    } else {
+        F::from(x as u64)
+    }
+}
+
+/// `q_M, q_L, q_R, q_O, q_C`, by coefficients: every row multiplies.
+fn selectors<F: PrimeField>() -> [[F; 3]; 5] {
+    [[1, 0, 0], [0, 0, 0], [0, 0, 0], [-1, 0, 0], [0, 0, 0]].map(|q| q.map(int))
+}
+
+/// `Z(X) = (X - 1)(X - 2)(X - 3)`, by coefficients.
+fn vanishing<F: PrimeField>() -> [F; 4] {
+    [-6, 11, -6, 1].map(int)
+}
+
+/// A transcript, each element 32 bytes of little-endian canonical encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToyTranscript<F: PrimeField> {
+    pub cm: F,
+    pub z: F,
+    /// `a(z), b(z), c(z), t(z)`
+    pub evals: [F; 4],
+    pub a: [F; 3],
+    pub b: [F; 3],
+    pub c: [F; 3],
+    pub t: [F; 4],
+}
+
+impl<F: PrimeField> ToyTranscript<F> {
+    /// `None` unless `bytes` are exactly `LEN` canonical field elements.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let size = F::Repr::default().as_ref().len();
+        if bytes.len() != LEN * size {
+            return None;
+        }
+        let mut elems = Vec::with_capacity(LEN);
+        for chunk in bytes.chunks(size) {
+            let mut repr = F::Repr::default();
+            repr.as_mut().copy_from_slice(chunk);
+            elems.push(Option::<F>::from(F::from_repr(repr))?);
+        }
+        let take = |i: usize, n: usize| elems[i..i + n].to_vec();
+        Some(ToyTranscript {
+            cm: elems[0],
+            z: elems[1],
+            evals: take(2, 4).try_into().unwrap(),
+            a: take(6, 3).try_into().unwrap(),
+            b: take(9, 3).try_into().unwrap(),
+            c: take(12, 3).try_into().unwrap(),
+            t: take(15, 4).try_into().unwrap(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.elems()
+            .iter()
+            .flat_map(|x| x.to_repr().as_ref().to_vec())
+            .collect()
+    }
+
+    fn elems(&self) -> Vec<F> {
+        let mut elems = vec![self.cm, self.z];
+        elems.extend(self.evals);
+        elems.extend(self.coeffs());
+        elems
+    }
+
+    /// The committed coefficients, in hashing order.
+    fn coeffs(&self) -> Vec<F> {
+        [&self.a[..], &self.b[..], &self.c[..], &self.t[..]].concat()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ToyPlonkConfig {
+    arith: ArithConfig,
+    mimc: MimcConfig,
+    polynomial_eval: PolynomialEvalConfig,
+    instance: Column<Instance>,
+}
+
+/// Verify a toy PLONK transcript for the three rows above, with the public
+/// `out`.
+#[derive(Default)]
+pub struct ToyPlonkVerifier<F: PrimeField> {
+    pub transcript: Vec<u8>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ToyPlonkVerifier<F> {
+    pub fn new(transcript: Vec<u8>) -> Self {
+        ToyPlonkVerifier {
+            transcript,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for ToyPlonkVerifier<F> {
+    type Config = ToyPlonkConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ToyPlonkConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            mimc: MimcChip::configure(meta, advice, constant),
+            polynomial_eval: PolynomialEvalChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith);
+        let mimc = MimcChip::construct(config.mimc);
+        let poly = PolynomialEvalChip::construct(config.polynomial_eval);
+
+        // Parsing is the prover's business; the circuit only sees elements.
+        let transcript = if self.transcript.is_empty() {
+            Value::unknown()
+        } else {
+            Value::known(ToyTranscript::from_bytes(&self.transcript).ok_or(Error::Synthesis)?)
+        };
+        let elems = transcript
+            .map(|t| t.elems())
+            .transpose_vec(LEN)
+            .into_iter()
+            .map(|x| arith.load_private(layouter.namespace(|| "transcript"), x))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let (cm, z, evals, coeffs) = (&elems[0], &elems[1], &elems[2..6], &elems[6..]);
+        let (a, b, c, t) = (&coeffs[..3], &coeffs[3..6], &coeffs[6..9], &coeffs[9..]);
+
+        // 1. and 2.: the commitment, then the Fiat-Shamir challenge.
+        let cm_ = mimc.hash(layouter.namespace(|| "cm"), coeffs)?;
+        arith.assert_equal(layouter.namespace(|| "cm"), cm_, cm.clone())?;
+        let z_ = mimc.hash(layouter.namespace(|| "z"), &[cm.clone()])?;
+        arith.assert_equal(layouter.namespace(|| "z"), z_, z.clone())?;
+
+        // 3. the openings.
+        for (poly_coeffs, eval) in [a, b, c, t].into_iter().zip(evals) {
+            let value = poly.eval(layouter.namespace(|| "opening"), poly_coeffs, z)?;
+            arith.assert_equal(layouter.namespace(|| "opening"), value, eval.clone())?;
+        }
+
+        // 4. the gate identity at z.
+        let mut eval_constant = |name: &'static str, coeffs: &[F]| -> Result<Number<F>, Error> {
+            let coeffs = coeffs
+                .iter()
+                .map(|q| arith.load_constant(layouter.namespace(|| name), *q))
+                .collect::<Result<Vec<_>, Error>>()?;
+            poly.eval(layouter.namespace(|| name), &coeffs, z)
+        };
+        let [q_m, q_l, q_r, q_o, q_c] = selectors::<F>();
+        let q_m = eval_constant("q_M", &q_m)?;
+        let q_l = eval_constant("q_L", &q_l)?;
+        let q_r = eval_constant("q_R", &q_r)?;
+        let q_o = eval_constant("q_O", &q_o)?;
+        let q_c = eval_constant("q_C", &q_c)?;
+        let z_h = eval_constant("Z", &vanishing::<F>())?;
+
+        let [a_z, b_z, c_z, t_z] = [0, 1, 2, 3].map(|i| evals[i].clone());
+        let ab = arith.mul(layouter.namespace(|| "a * b"), a_z.clone(), b_z.clone())?;
+        let mut lhs = arith.mul(layouter.namespace(|| "q_M a b"), q_m, ab)?;
+        for (q, x) in [(q_l, a_z), (q_r, b_z), (q_o, c_z)] {
+            let term = arith.mul(layouter.namespace(|| "q x"), q, x)?;
+            lhs = arith.add(layouter.namespace(|| "+"), lhs, term)?;
+        }
+        let lhs = arith.add(layouter.namespace(|| "+ q_C"), lhs, q_c)?;
+        let rhs = arith.mul(layouter.namespace(|| "t Z"), t_z, z_h)?;
+        arith.assert_equal(layouter.namespace(|| "gate identity"), lhs, rhs)?;
+
+        // 5. the public output, read from the last row.
+        let three = arith.load_constant(layouter.namespace(|| "3"), F::from(3))?;
+        let out = poly.eval(layouter.namespace(|| "c(3)"), c, &three)?;
+        arith.expose_public(layouter.namespace(|| "out"), out, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    /// A transcript for the three rows, one element per line.
+    const TRANSCRIPT: [&str; LEN] = [
+        // cm
+        "13c24ec0193f3bfdde463ea674b1a16d4a5ebd92249d5ddaf08d708da0adae00",
+        // z
+        "0d9c6b948ec2ac9eb507c307f4117eb7262b50f212b9a9b425937abaadebc71d",
+        // a(z)
+        "7eda70370f4972ee68d80c6ebc5e783cbb7058fd7586b08c318b545ab0f8450c",
+        // b(z)
+        "bf9fdf62c828ee89d425e228fdec3205a5e2fc01a2e50dd12da627f959e71004",
+        // c(z)
+        "e044a8d178d1fa9a8d1571a07401c6b1ff7ad3c6426b62cb2ef6cece8ae6a33d",
+        // t(z)
+        "8c4d24da2e4252bacdc2e6540b4b4913263cb7c72668864d571c1a1fb6206433",
+        // a = 24 - 35X + 13X^2
+        "1800000000000000000000000000000000000000000000000000000000000000",
+        "deffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        "0d00000000000000000000000000000000000000000000000000000000000000",
+        // b = -8 + 15X - 4X^2
+        "f9ffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        "0f00000000000000000000000000000000000000000000000000000000000000",
+        "fdffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        // c = -54 + 75X - 15X^2
+        "cbffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        "4b00000000000000000000000000000000000000000000000000000000000000",
+        "f2ffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        // t = 23 - 52X
+        "1700000000000000000000000000000000000000000000000000000000000000",
+        "cdffffffec302d991bf94c09fc98462200000000000000000000000000000040",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ];
+
+    fn transcript() -> Vec<u8> {
+        TRANSCRIPT
+            .concat()
+            .as_bytes()
+            .chunks(2)
+            .map(|h| u8::from_str_radix(std::str::from_utf8(h).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    fn verify(transcript: Vec<u8>, out: u64) -> bool {
+        let circuit = ToyPlonkVerifier::<Fp>::new(transcript);
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(out)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_toy_plonk_verifier() {
+        let parsed = ToyTranscript::<Fp>::from_bytes(&transcript()).unwrap();
+        assert_eq!(parsed.to_bytes(), transcript());
+        assert!(verify(transcript(), 36));
+        // The statement is about this output.
+        assert!(!verify(transcript(), 37));
+    }
+
+    #[test]
+    fn test_toy_plonk_tampered_opening() {
+        let honest = ToyTranscript::<Fp>::from_bytes(&transcript()).unwrap();
+
+        // Any claimed evaluation off by one.
+        for i in 0..4 {
+            let mut tampered = honest.clone();
+            tampered.evals[i] += Fp::one();
+            assert!(!verify(tampered.to_bytes(), 36));
+        }
+
+        // Another polynomial, even one with a matching output, changes the
+        // commitment it must hash to.
+        let mut tampered = honest.clone();
+        tampered.c[0] += Fp::one();
+        assert!(!verify(tampered.to_bytes(), 37));
+
+        // A challenge of the prover's choosing.
+        let mut tampered = honest;
+        tampered.z += Fp::one();
+        assert!(!verify(tampered.to_bytes(), 36));
+    }
+}
@@ -0,0 +1,211 @@
+/// chap6: RLP, short strings
+/// Prove that a private byte string `s` with `len(s) < 56` RLP-encodes to a
+/// public byte sequence:
+///
+///   len(s) == 1 && s[0] < 0x80   =>  rlp(s) = [s[0]]
+///   otherwise                    =>  rlp(s) = [0x80 + len(s)] ++ s
+///
+/// `len(s)` is a circuit parameter, so the circuit knows statically which rule
+/// applies except for a single byte, where it depends on the private byte
+/// itself. That case is decided in circuit with `small = s[0] < 0x80`:
+///
+///   prefix = small * s[0] + (1 - small) * 0x81
+///   enc[1] = (1 - small) * s[0]
+///   n      = 2 - small
+///
+/// The public input is the encoded length followed by the encoding, zero
+/// padded to `len(s) + 1` bytes:
+///
+///   instance = [n, enc_0, enc_1, ..., enc_len]
+///
+/// Every payload byte is range-checked through the byte table, and the
+/// comparison reuses that table.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    lt::{LtChip, LtConfig},
+};
+
+pub const MAX_SHORT_LEN: usize = 55;
+
+/// RLP-encode a short string natively.
+pub fn rlp_encode(s: &[u8]) -> Vec<u8> {
+    assert!(s.len() <= MAX_SHORT_LEN, "only short strings are supported");
+    if s.len() == 1 && s[0] < 0x80 {
+        return s.to_vec();
+    }
+    let mut out = vec![0x80 + s.len() as u8];
+    out.extend_from_slice(s);
+    out
+}
+
+/// `[n, enc_0, ..., enc_len]`, zero padded, for a payload of length `len`.
+pub fn public_inputs<F: PrimeField>(len: usize, encoding: &[u8]) -> Vec<F> {
+    let mut out = vec![F::from(encoding.len() as u64)];
+    out.extend(encoding.iter().map(|b| F::from(*b as u64)));
+    out.resize(len + 2, F::ZERO);
+    out
+}
+
+#[derive(Debug, Clone)]
+pub struct RlpConfig {
+    arith: ArithConfig,
+    byte: ByteConfig,
+    lt: LtConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct RlpCircuit<F: PrimeField> {
+    pub payload: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> RlpCircuit<F> {
+    pub fn new(s: &[u8]) -> Self {
+        RlpCircuit {
+            payload: s.iter().map(|b| Value::known(F::from(*b as u64))).collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for RlpCircuit<F> {
+    type Config = RlpConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        RlpCircuit {
+            payload: vec![Value::unknown(); self.payload.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let byte = ByteChip::configure(meta, advice[0]);
+        RlpConfig {
+            arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+            lt: LtChip::configure(meta, advice, &byte),
+            byte,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let len = self.payload.len();
+        assert!(len <= MAX_SHORT_LEN, "only short strings are supported");
+        let arith = ArithChip::construct(config.arith);
+        let byte = ByteChip::construct(config.byte);
+        let lt = LtChip::construct(config.lt);
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        let s = self
+            .payload
+            .iter()
+            .map(|b| byte.assign_byte(layouter.namespace(|| "payload byte"), *b))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let exposed = if len == 1 {
+            let s0 = s[0].clone();
+            let threshold = arith.load_constant(layouter.namespace(|| "0x80"), F::from(0x80))?;
+            let small = lt.less_than(layouter.namespace(|| "s_0 < 0x80"), s0.clone(), threshold)?;
+            let one = arith.load_constant(layouter.namespace(|| "1"), F::ONE)?;
+            let two = arith.load_constant(layouter.namespace(|| "2"), F::from(2))?;
+            let long_prefix = arith.load_constant(layouter.namespace(|| "0x81"), F::from(0x81))?;
+            let not_small = arith.sub(layouter.namespace(|| "1 - small"), one, small.clone())?;
+
+            let n = arith.sub(layouter.namespace(|| "n"), two, small.clone())?;
+            let a = arith.mul(layouter.namespace(|| "small * s_0"), small, s0.clone())?;
+            let b = arith.mul(
+                layouter.namespace(|| "(1 - small) * 0x81"),
+                not_small.clone(),
+                long_prefix,
+            )?;
+            let prefix = arith.add(layouter.namespace(|| "prefix"), a, b)?;
+            let enc_1 = arith.mul(layouter.namespace(|| "enc_1"), not_small, s0)?;
+
+            vec![n, prefix, enc_1]
+        } else {
+            let n = arith.load_constant(layouter.namespace(|| "n"), F::from(len as u64 + 1))?;
+            let prefix =
+                arith.load_constant(layouter.namespace(|| "prefix"), F::from(0x80 + len as u64))?;
+            [n, prefix].into_iter().chain(s).collect()
+        };
+
+        for (row, num) in exposed.into_iter().enumerate() {
+            arith.expose_public(
+                layouter.namespace(|| format!("instance {}", row)),
+                num,
+                config.instance,
+                row,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prove(s: &[u8], encoding: &[u8]) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 9;
+        let circuit = RlpCircuit::<Fp>::new(s);
+        let prover = MockProver::run(k, &circuit, vec![public_inputs(s.len(), encoding)]).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_rlp_empty_string() {
+        assert_eq!(rlp_encode(&[]), vec![0x80]);
+        assert!(prove(&[], &[0x80]).is_ok());
+    }
+
+    #[test]
+    fn test_rlp_single_small_byte() {
+        assert_eq!(rlp_encode(&[0x05]), vec![0x05]);
+        assert!(prove(&[0x05], &[0x05]).is_ok());
+        // [0x81, 0x05] decodes to the same string, but is not canonical.
+        assert!(prove(&[0x05], &[0x81, 0x05]).is_err());
+    }
+
+    #[test]
+    fn test_rlp_single_large_byte() {
+        assert_eq!(rlp_encode(&[0x90]), vec![0x81, 0x90]);
+        assert!(prove(&[0x90], &[0x81, 0x90]).is_ok());
+        assert!(prove(&[0x90], &[0x90]).is_err());
+    }
+
+    #[test]
+    fn test_rlp_max_short_string() {
+        let s: Vec<u8> = (0..MAX_SHORT_LEN as u8).map(|i| i * 3).collect();
+        let encoding = rlp_encode(&s);
+        assert_eq!(encoding[0], 0x80 + 55);
+        assert!(prove(&s, &encoding).is_ok());
+    }
+
+    #[test]
+    fn test_rlp_wrong_prefix() {
+        let s = b"dog";
+        let mut encoding = rlp_encode(s);
+        assert_eq!(encoding, vec![0x83, b'd', b'o', b'g']);
+        encoding[0] = 0x84;
+        assert!(prove(s, &encoding).is_err());
+    }
+}
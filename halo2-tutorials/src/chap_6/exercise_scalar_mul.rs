@@ -0,0 +1,222 @@
+/// chap6: fixed-base scalar multiplication
+/// Prove knowledge of a private scalar `k` behind the public point `[k]G`,
+/// for the fixed Pallas generator `G`:
+///
+///   instance = [([k]G).x, ([k]G).y]
+///
+/// Because `G` is known at keygen, `halo2_gadgets::ecc` never doubles. It
+/// splits `k` into 85 windows of 3 bits, `k = sum k_i * 8^i`, and for every
+/// window interpolates its 8 possible multiples of `8^i * G` (offset so that
+/// none is the identity) through fixed Lagrange coefficients, so one row per
+/// window finds its point; the windows are then summed up. The `z`/`u` values
+/// from `find_zs_and_us` prove each interpolated `x` belongs to a point on the
+/// curve. The chip's range checks share a 10-bit lookup table, the one
+/// Sinsemilla uses in Orchard, which has to be loaded even though `k`'s 3-bit
+/// windows do not need it.
+///
+/// A naive double-and-add with our own chips would spend, for each of the
+/// 255 bits of `k`, a doubling, an addition (`EcAddChip`, one row) and a
+/// select, so at least three rows a bit, 765 in all; and `EcAddChip` is
+/// incomplete, so it could not even handle every `k`. The row-count test
+/// measures the windowed version against that.
+///
+/// | a0 .. a9            | lagrange_coeffs[8] | constant | table    | instance |
+/// |---------------------|--------------------|----------|----------|----------|
+/// | k_0 .. k_84 windows |                    |          | 0..2^10  |  [k]G.x  |
+/// | one row per window, interpolating its multiple of G     |  [k]G.y  |
+/// | window sum ...                                          |          |
+use halo2_gadgets::{
+    ecc::{
+        chip::{EccChip, EccConfig},
+        FixedPoint, Point, ScalarFixed,
+    },
+    sinsemilla,
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::pallas,
+    plonk::*,
+};
+
+use super::exercise_pedersen::{PedersenBase, PedersenBases};
+
+#[derive(Debug, Clone)]
+pub struct ScalarMulConfig {
+    ecc: EccConfig<PedersenBases>,
+    range_check: LookupRangeCheckConfig<pallas::Base, { sinsemilla::primitives::K }>,
+}
+
+/// `[k]B` for a private scalar `k` and a fixed base `B`.
+#[derive(Debug, Clone)]
+pub struct ScalarMulChip {
+    config: ScalarMulConfig,
+}
+
+impl ScalarMulChip {
+    pub fn construct(config: ScalarMulConfig) -> Self {
+        ScalarMulChip { config }
+    }
+
+    /// The circuit must have a constant column enabled.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advice: [Column<Advice>; 10],
+        lagrange_coeffs: [Column<Fixed>; 8],
+        table: TableColumn,
+    ) -> ScalarMulConfig {
+        let range_check = LookupRangeCheckConfig::configure(meta, advice[9], table);
+        ScalarMulConfig {
+            ecc: EccChip::configure(meta, advice, lagrange_coeffs, range_check),
+            range_check,
+        }
+    }
+
+    pub fn ecc(&self) -> EccChip<PedersenBases> {
+        EccChip::construct(self.config.ecc.clone())
+    }
+
+    pub fn load_table(&self, layouter: &mut impl Layouter<pallas::Base>) -> Result<(), Error> {
+        self.config.range_check.load(layouter)
+    }
+
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        base: PedersenBase,
+        k: Value<pallas::Scalar>,
+    ) -> Result<Point<pallas::Affine, EccChip<PedersenBases>>, Error> {
+        let k = ScalarFixed::new(self.ecc(), layouter.namespace(|| "k"), k)?;
+        let base = FixedPoint::from_inner(self.ecc(), base);
+        let (point, _) = base.mul(layouter.namespace(|| "[k]B"), k)?;
+        Ok(point)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScalarMulCircuitConfig {
+    scalar_mul: ScalarMulConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct ScalarMulCircuit {
+    pub k: Value<pallas::Scalar>,
+}
+
+impl Circuit<pallas::Base> for ScalarMulCircuit {
+    type Config = ScalarMulCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice: [Column<Advice>; 10] = (0..10)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lagrange_coeffs: [Column<Fixed>; 8] = (0..8)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        ScalarMulCircuitConfig {
+            scalar_mul: ScalarMulChip::configure(meta, advice, lagrange_coeffs, table),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let chip = ScalarMulChip::construct(config.scalar_mul);
+        chip.load_table(&mut layouter)?;
+
+        let kg = chip.mul(layouter.namespace(|| "[k]G"), PedersenBase::g(), self.k)?;
+        layouter.constrain_instance(kg.inner().x().cell(), config.instance, 0)?;
+        layouter.constrain_instance(kg.inner().y().cell(), config.instance, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analysis::rows::advice_rows,
+        chap_6::{exercise_ec_add::EcAddCircuit, exercise_pedersen::coordinates},
+    };
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{
+            group::{ff::Field, Curve, Group},
+            Fp,
+        },
+    };
+    use rand_core::OsRng;
+
+    const K: u32 = 11;
+
+    fn verify(k: pallas::Scalar, point: pallas::Affine) -> bool {
+        let circuit = ScalarMulCircuit { k: Value::known(k) };
+        let prover = MockProver::run(K, &circuit, vec![coordinates(point).to_vec()]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn mul_g(k: pallas::Scalar) -> pallas::Affine {
+        (pallas::Point::generator() * k).to_affine()
+    }
+
+    #[test]
+    fn test_scalar_mul() {
+        let k = pallas::Scalar::random(OsRng);
+        assert!(verify(k, mul_g(k)));
+
+        // [0]G is the identity, which the chip encodes as (0, 0).
+        assert!(verify(
+            pallas::Scalar::zero(),
+            mul_g(pallas::Scalar::zero())
+        ));
+        assert!(verify(
+            pallas::Scalar::one(),
+            pallas::Point::generator().to_affine()
+        ));
+
+        // q - 1 is the largest scalar, and [q - 1]G = -G.
+        let minus_one = -pallas::Scalar::one();
+        assert_eq!(mul_g(minus_one), (-pallas::Point::generator()).to_affine());
+        assert!(verify(minus_one, mul_g(minus_one)));
+    }
+
+    #[test]
+    fn test_scalar_mul_wrong_point() {
+        let k = pallas::Scalar::random(OsRng);
+        assert!(!verify(k, mul_g(k + pallas::Scalar::one())));
+        assert!(!verify(k, -mul_g(k)));
+    }
+
+    #[test]
+    fn test_scalar_mul_rows() {
+        let rows = advice_rows(K, &ScalarMulCircuit::default()).unwrap();
+        let add_rows = advice_rows(4, &EcAddCircuit::<Fp>::default()).unwrap();
+        assert_eq!(add_rows, 1);
+        // A doubling and an addition of one row each, plus a select, per bit.
+        let naive_rows = 255 * (2 * add_rows + 1);
+        assert!(
+            rows < naive_rows,
+            "fixed-base mul uses {} rows, naive double-and-add {}",
+            rows,
+            naive_rows
+        );
+    }
+}
@@ -0,0 +1,241 @@
+/// chap6: Schnorr verification of a private message
+/// The signature of `chap_5::schnorr`,
+///
+///   e = Poseidon(R.x, R.y, P.x, P.y, m),    s = k + e * sk
+///
+/// checked as `s*G == R + e*P`, but now the message and the signature are
+/// both witnesses. The verifier only learns the public key and a hiding
+/// commitment to the message, so the proof says "the owner of P signed the
+/// message behind `cm`" without saying which message it was:
+///
+///   cm = Poseidon(m, rho)
+///   instance = [P.x, P.y, cm]
+///
+/// Both hashes run on the same Poseidon chip, so `m` enters the challenge
+/// from the same cell that was committed to. `s*G` goes through
+/// `ScalarMulChip` and Poseidon shares its columns the way Orchard does.
+///
+/// | a0 .. a9              | lagrange_coeffs[8] / rc_a, rc_b | table   | instance |
+/// |-----------------------|---------------------------------|---------|----------|
+/// | witness P, R, m, rho  |                                 | 0..2^10 |   P.x    |
+/// | Poseidon rows (a5..a8): cm, then e                      |         |   P.y    |
+/// | s*G: one row per window ...                             |         |   cm     |
+/// | e*P: double and add rows ...                            |         |          |
+/// | R + e*P, s*G == R + e*P                                 |         |          |
+use halo2_gadgets::{
+    ecc::{Point, ScalarVar},
+    poseidon::{
+        primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+        Hash, Pow5Chip, Pow5Config,
+    },
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::pallas,
+    plonk::*,
+};
+
+use super::{
+    exercise_pedersen::PedersenBase,
+    exercise_scalar_mul::{ScalarMulChip, ScalarMulConfig},
+};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// The message commitment `cm`, natively.
+pub fn commit_message(m: pallas::Base, rho: pallas::Base) -> pallas::Base {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash([m, rho])
+}
+
+#[derive(Debug, Clone)]
+pub struct PrivateSchnorrConfig {
+    advice: [Column<Advice>; 10],
+    scalar_mul: ScalarMulConfig,
+    poseidon: Pow5Config<pallas::Base, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct PrivateSchnorrCircuit {
+    pub pk: Value<pallas::Affine>,
+    pub m: Value<pallas::Base>,
+    pub rho: Value<pallas::Base>,
+    pub r: Value<pallas::Affine>,
+    pub s: Value<pallas::Scalar>,
+}
+
+impl Circuit<pallas::Base> for PrivateSchnorrCircuit {
+    type Config = PrivateSchnorrConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice: [Column<Advice>; 10] = (0..10)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lagrange_coeffs: [Column<Fixed>; 8] = (0..8)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let scalar_mul = ScalarMulChip::configure(meta, advice, lagrange_coeffs, table);
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(
+            meta,
+            advice[6..9].try_into().unwrap(),
+            advice[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+        PrivateSchnorrConfig {
+            advice,
+            scalar_mul,
+            poseidon,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let chip = ScalarMulChip::construct(config.scalar_mul);
+        chip.load_table(&mut layouter)?;
+        let ecc = chip.ecc();
+
+        let pk = Point::new(ecc.clone(), layouter.namespace(|| "P"), self.pk)?;
+        let r = Point::new(ecc.clone(), layouter.namespace(|| "R"), self.r)?;
+        let (m, rho) = layouter.assign_region(
+            || "m, rho",
+            |mut region| {
+                let m = region.assign_advice(|| "m", config.advice[0], 0, || self.m)?;
+                let rho = region.assign_advice(|| "rho", config.advice[1], 0, || self.rho)?;
+                Ok((m, rho))
+            },
+        )?;
+        layouter.constrain_instance(pk.inner().x().cell(), config.instance, 0)?;
+        layouter.constrain_instance(pk.inner().y().cell(), config.instance, 1)?;
+
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            Pow5Chip::construct(config.poseidon.clone()),
+            layouter.namespace(|| "init cm"),
+        )?;
+        let cm = hasher.hash(layouter.namespace(|| "cm"), [m.clone(), rho])?;
+        layouter.constrain_instance(cm.cell(), config.instance, 2)?;
+
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<5>, WIDTH, RATE>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init e"),
+        )?;
+        let e = hasher.hash(
+            layouter.namespace(|| "e"),
+            [
+                r.inner().x(),
+                r.inner().y(),
+                pk.inner().x(),
+                pk.inner().y(),
+                m,
+            ],
+        )?;
+        let e = ScalarVar::from_base(ecc, layouter.namespace(|| "e as scalar"), &e)?;
+        let (ep, _) = pk.mul(layouter.namespace(|| "e*P"), e)?;
+        let rhs = r.add(layouter.namespace(|| "R + e*P"), &ep)?;
+
+        let lhs = chip.mul(layouter.namespace(|| "s*G"), PedersenBase::g(), self.s)?;
+        lhs.constrain_equal(layouter.namespace(|| "s*G == R + e*P"), &rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chap_5::schnorr::{public_key, sign},
+        chap_6::exercise_pedersen::coordinates,
+    };
+    use halo2_proofs::dev::MockProver;
+
+    const K: u32 = 11;
+
+    fn keys() -> (pallas::Scalar, pallas::Affine) {
+        let sk = pallas::Scalar::from(0x5eed_5eed);
+        (sk, public_key(sk))
+    }
+
+    /// Prove that `(r, s)` signs `m` under `pk`, publishing the commitment to
+    /// `committed` instead of `m`.
+    fn verify(
+        pk: pallas::Affine,
+        m: pallas::Base,
+        committed: pallas::Base,
+        (r, s): (pallas::Affine, pallas::Scalar),
+    ) -> bool {
+        let rho = pallas::Base::from(0xb1ad);
+        let circuit = PrivateSchnorrCircuit {
+            pk: Value::known(pk),
+            m: Value::known(m),
+            rho: Value::known(rho),
+            r: Value::known(r),
+            s: Value::known(s),
+        };
+        let instance = [
+            coordinates(pk).to_vec(),
+            vec![commit_message(committed, rho)],
+        ]
+        .concat();
+        let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_private_schnorr_valid() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let sig = sign(sk, pallas::Scalar::from(0xc0ffee), m);
+        assert!(verify(pk, m, m, sig));
+    }
+
+    #[test]
+    fn test_private_schnorr_tampered_s() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let (r, s) = sign(sk, pallas::Scalar::from(0xc0ffee), m);
+        assert!(!verify(pk, m, m, (r, s + pallas::Scalar::one())));
+    }
+
+    #[test]
+    fn test_private_schnorr_tampered_message() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let other = m + pallas::Base::one();
+        let sig = sign(sk, pallas::Scalar::from(0xc0ffee), m);
+        // The signature does not cover the message behind the commitment,
+        // whether the prover hashes that message or the signed one.
+        assert!(!verify(pk, other, other, sig));
+        assert!(!verify(pk, m, other, sig));
+    }
+
+    #[test]
+    fn test_private_schnorr_other_key() {
+        let (sk, pk) = keys();
+        let m = pallas::Base::from(42);
+        let sig = sign(
+            sk + pallas::Scalar::one(),
+            pallas::Scalar::from(0xc0ffee),
+            m,
+        );
+        assert!(!verify(pk, m, m, sig));
+    }
+}
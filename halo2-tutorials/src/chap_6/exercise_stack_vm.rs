@@ -0,0 +1,516 @@
+/// chap6: a tiny stack machine
+/// Prove that running a public program on an empty stack halts with a
+/// public value on top of the stack.
+///
+/// Opcodes: PUSH imm, ADD, MUL, HALT.
+///
+/// The program lives in fixed columns (so it is part of the verifying key and
+/// thus public), one instruction per row. The prover supplies the execution
+/// trace: row `i` holds the machine state *before* instruction `i` runs.
+///
+/// Circuit design:
+/// | pc | top | second | depth | spill | opcode | imm | q_push | q_add | q_mul | q_halt | q_spill | q_fill |
+/// |----|-----|--------|-------|-------|--------|-----|--------|-------|-------|--------|---------|--------|
+/// | 0  |  0  |   0    |   0   |       |  PUSH  |  2  |   1    |   0   |   0   |   0    |    0    |   0    |
+/// | 1  |  2  |   0    |   1   |       |  PUSH  |  3  |   1    |   0   |   0   |   0    |    0    |   0    |
+/// | 2  |  3  |   2    |   2   |   2   |  PUSH  |  4  |   1    |   0   |   0   |   0    |    1    |   0    |
+/// | 3  |  4  |   3    |   3   |   2   |  ADD   |     |   0    |   1   |   0   |   0    |    0    |   1    |
+/// | 4  |  7  |   2    |   2   |       |  MUL   |     |   0    |   0   |   1   |   0    |    0    |   0    |
+/// | 5  | 14  |   0    |   1   |       |  HALT  |     |   0    |   0   |   0   |   1    |    0    |   0    |
+///
+/// Only the two topmost stack slots live in the trace; everything below them
+/// is in memory. A PUSH on a stack of two or more spills `second` to memory
+/// through the `spill` cell of its row, and an ADD/MUL on a stack of three or
+/// more fills the new `second` back from memory the same way. `opcode` holds
+/// the instruction, and the "decode" gate ties it to the selectors.
+///
+/// Memory consistency is a copy constraint: a fill must read what the last
+/// unmatched spill wrote. The program is public, so which spill that is does
+/// not depend on the witness: the stack depth at every row, and with it
+/// `q_spill` and `q_fill`, follows from the program alone. `assign` computes
+/// them while laying out the program and ties each fill's `spill` cell to the
+/// matching one. Row 0 starts from constants, and every instruction fixes
+/// all of the next row's state, so the empty slots of a shallow stack hold 0
+/// without a gate of their own.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Push(u64),
+    Add,
+    Mul,
+    Halt,
+}
+
+impl Op {
+    /// Rows past the program hold opcode 0, which is no instruction.
+    fn opcode(&self) -> u64 {
+        match self {
+            Op::Push(_) => 1,
+            Op::Add => 2,
+            Op::Mul => 3,
+            Op::Halt => 4,
+        }
+    }
+
+    fn imm(&self) -> u64 {
+        match self {
+            Op::Push(imm) => *imm,
+            _ => 0,
+        }
+    }
+}
+
+/// The machine state before an instruction runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct State {
+    pub pc: u64,
+    pub top: u64,
+    pub second: u64,
+    pub depth: u64,
+}
+
+/// Run `program` natively until HALT. Underflows are not trapped here, the
+/// circuit is the one that has to reject them.
+pub fn execute(program: &[Op]) -> Vec<State> {
+    let mut trace = vec![State::default()];
+    // The stack below `second`, bottom first.
+    let mut memory = vec![];
+    for op in program {
+        let s = *trace.last().unwrap();
+        let next = match op {
+            Op::Push(imm) => {
+                if s.depth >= 2 {
+                    memory.push(s.second);
+                }
+                State {
+                    pc: s.pc + 1,
+                    top: *imm,
+                    second: s.top,
+                    depth: s.depth + 1,
+                }
+            }
+            Op::Add | Op::Mul => {
+                let top = match op {
+                    Op::Add => s.top + s.second,
+                    _ => s.top * s.second,
+                };
+                State {
+                    pc: s.pc + 1,
+                    top,
+                    second: memory.pop().unwrap_or(0),
+                    depth: s.depth.saturating_sub(1),
+                }
+            }
+            Op::Halt => break,
+        };
+        trace.push(next);
+    }
+    trace
+}
+
+#[derive(Debug, Clone)]
+pub struct StackVmConfig {
+    pc: Column<Advice>,
+    top: Column<Advice>,
+    second: Column<Advice>,
+    depth: Column<Advice>,
+    spill: Column<Advice>,
+    opcode: Column<Fixed>,
+    imm: Column<Fixed>,
+    q_push: Column<Fixed>,
+    q_add: Column<Fixed>,
+    q_mul: Column<Fixed>,
+    q_halt: Column<Fixed>,
+    q_spill: Column<Fixed>,
+    q_fill: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+pub struct StackVmChip<F: PrimeField> {
+    config: StackVmConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> StackVmChip<F> {
+    pub fn construct(config: StackVmConfig) -> Self {
+        StackVmChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> StackVmConfig {
+        let pc = meta.advice_column();
+        let top = meta.advice_column();
+        let second = meta.advice_column();
+        let depth = meta.advice_column();
+        let spill = meta.advice_column();
+        let opcode = meta.fixed_column();
+        let imm = meta.fixed_column();
+        let q_push = meta.fixed_column();
+        let q_add = meta.fixed_column();
+        let q_mul = meta.fixed_column();
+        let q_halt = meta.fixed_column();
+        let q_spill = meta.fixed_column();
+        let q_fill = meta.fixed_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        for col in [pc, top, second, depth, spill] {
+            meta.enable_equality(col);
+        }
+
+        let one = || Expression::Constant(F::ONE);
+        let two = || Expression::Constant(F::from(2));
+
+        // Exactly the selector of the row's opcode is on, or none past the
+        // end of the program.
+        meta.create_gate("decode", |meta| {
+            let opcode = meta.query_fixed(opcode, Rotation::cur());
+            let selected = [q_push, q_add, q_mul, q_halt]
+                .into_iter()
+                .zip(1..)
+                .map(|(q, code)| meta.query_fixed(q, Rotation::cur()) * F::from(code))
+                .reduce(|a, b| a + b)
+                .unwrap();
+            vec![opcode - selected]
+        });
+
+        // | pc  | top  | second  | depth  | spill | imm | q_push | q_spill |
+        // | pc' | top' | second' | depth' |       |     |        |         |
+        meta.create_gate("PUSH", |meta| {
+            let q = meta.query_fixed(q_push, Rotation::cur());
+            let q_spill = meta.query_fixed(q_spill, Rotation::cur());
+            let imm = meta.query_fixed(imm, Rotation::cur());
+            let pc_cur = meta.query_advice(pc, Rotation::cur());
+            let pc_next = meta.query_advice(pc, Rotation::next());
+            let top_cur = meta.query_advice(top, Rotation::cur());
+            let top_next = meta.query_advice(top, Rotation::next());
+            let second_cur = meta.query_advice(second, Rotation::cur());
+            let second_next = meta.query_advice(second, Rotation::next());
+            let depth_cur = meta.query_advice(depth, Rotation::cur());
+            let depth_next = meta.query_advice(depth, Rotation::next());
+            let spill = meta.query_advice(spill, Rotation::cur());
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("pc' = pc + 1", pc_next - pc_cur - one()),
+                    ("top' = imm", top_next - imm),
+                    ("second' = top", second_next - top_cur),
+                    ("depth' = depth + 1", depth_next - depth_cur - one()),
+                    ("spill = second", q_spill * (spill - second_cur)),
+                ],
+            )
+        });
+
+        // ADD and MUL only differ in how they combine the two topmost slots.
+        let mut binary_op =
+            |name: &'static str,
+             q: Column<Fixed>,
+             op: fn(Expression<F>, Expression<F>) -> Expression<F>| {
+                meta.create_gate(name, |meta| {
+                    let q = meta.query_fixed(q, Rotation::cur());
+                    let q_fill = meta.query_fixed(q_fill, Rotation::cur());
+                    let pc_cur = meta.query_advice(pc, Rotation::cur());
+                    let pc_next = meta.query_advice(pc, Rotation::next());
+                    let top_cur = meta.query_advice(top, Rotation::cur());
+                    let top_next = meta.query_advice(top, Rotation::next());
+                    let second_cur = meta.query_advice(second, Rotation::cur());
+                    let second_next = meta.query_advice(second, Rotation::next());
+                    let depth_cur = meta.query_advice(depth, Rotation::cur());
+                    let depth_next = meta.query_advice(depth, Rotation::next());
+                    let spill = meta.query_advice(spill, Rotation::cur());
+                    Constraints::with_selector(
+                        q,
+                        vec![
+                            ("pc' = pc + 1", pc_next - pc_cur - one()),
+                            ("top' = top op second", top_next - op(top_cur, second_cur)),
+                            // Without a fill, the stack holds exactly two.
+                            (
+                                "no underflow",
+                                (one() - q_fill.clone()) * (depth_cur.clone() - two()),
+                            ),
+                            ("depth' = depth - 1", depth_next - depth_cur + one()),
+                            ("second' = fill", second_next - q_fill * spill),
+                        ],
+                    )
+                });
+            };
+        binary_op("ADD", q_add, |a, b| a + b);
+        binary_op("MUL", q_mul, |a, b| a * b);
+
+        StackVmConfig {
+            pc,
+            top,
+            second,
+            depth,
+            spill,
+            opcode,
+            imm,
+            q_push,
+            q_add,
+            q_mul,
+            q_halt,
+            q_spill,
+            q_fill,
+            instance,
+        }
+    }
+
+    /// Lay out the program and its trace, returning the stack top at HALT.
+    /// A program without HALT is an `Error::Synthesis`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Op],
+        trace: &[State],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "stack vm",
+            |mut region| {
+                let mut halt_top = None;
+                // The depth before each instruction, and the spill cells
+                // not filled back yet, last on top.
+                let mut depth = 0;
+                let mut spilled: Vec<AssignedCell<F, F>> = vec![];
+                for (row, op) in program.iter().enumerate() {
+                    let spill = matches!(op, Op::Push(_)) && depth >= 2;
+                    let fill = matches!(op, Op::Add | Op::Mul) && depth >= 3;
+                    depth = match op {
+                        Op::Push(_) => depth + 1,
+                        _ => depth.saturating_sub(1),
+                    };
+
+                    let fixed = |v: u64| Value::known(F::from(v));
+                    region.assign_fixed(|| "opcode", config.opcode, row, || fixed(op.opcode()))?;
+                    region.assign_fixed(|| "imm", config.imm, row, || fixed(op.imm()))?;
+                    for (q, on) in [
+                        (config.q_push, matches!(op, Op::Push(_))),
+                        (config.q_add, *op == Op::Add),
+                        (config.q_mul, *op == Op::Mul),
+                        (config.q_halt, *op == Op::Halt),
+                        (config.q_spill, spill),
+                        (config.q_fill, fill),
+                    ] {
+                        region.assign_fixed(|| "selector", q, row, || fixed(on as u64))?;
+                    }
+
+                    // A spill writes this row's `second`, a fill reads the
+                    // next row's.
+                    let witness = |v: u64| Value::known(F::from(v));
+                    if spill {
+                        let s = trace.get(row).copied().unwrap_or_default();
+                        let cell = region.assign_advice(
+                            || "spill",
+                            config.spill,
+                            row,
+                            || witness(s.second),
+                        )?;
+                        spilled.push(cell);
+                    }
+                    if fill {
+                        let s = trace.get(row + 1).copied().unwrap_or_default();
+                        let cell = region.assign_advice(
+                            || "fill",
+                            config.spill,
+                            row,
+                            || witness(s.second),
+                        )?;
+                        let written = spilled.pop().ok_or(Error::Synthesis)?;
+                        region.constrain_equal(written.cell(), cell.cell())?;
+                    }
+
+                    let top = if row == 0 {
+                        // The machine starts from pc = 0 on an empty stack.
+                        for col in [config.pc, config.second, config.depth] {
+                            region.assign_advice_from_constant(|| "init", col, 0, F::ZERO)?;
+                        }
+                        region.assign_advice_from_constant(|| "init", config.top, 0, F::ZERO)?
+                    } else {
+                        let s = trace.get(row).copied().unwrap_or_default();
+                        region.assign_advice(|| "pc", config.pc, row, || witness(s.pc))?;
+                        region.assign_advice(
+                            || "second",
+                            config.second,
+                            row,
+                            || witness(s.second),
+                        )?;
+                        region.assign_advice(|| "depth", config.depth, row, || witness(s.depth))?;
+                        region.assign_advice(|| "top", config.top, row, || witness(s.top))?
+                    };
+
+                    if *op == Op::Halt {
+                        halt_top = Some(top);
+                        break;
+                    }
+                }
+                halt_top.ok_or(Error::Synthesis)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+pub struct StackVmCircuit<F: PrimeField> {
+    pub program: Vec<Op>,
+    pub trace: Vec<State>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> StackVmCircuit<F> {
+    pub fn new(program: Vec<Op>) -> Self {
+        let trace = execute(&program);
+        StackVmCircuit {
+            program,
+            trace,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for StackVmCircuit<F> {
+    type Config = StackVmConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        StackVmCircuit {
+            program: self.program.clone(),
+            trace: vec![],
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        StackVmChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = StackVmChip::construct(config);
+        let out = chip.assign(layouter.namespace(|| "run"), &self.program, &self.trace)?;
+        chip.expose_public(layouter.namespace(|| "stack top"), out, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn program() -> Vec<Op> {
+        // (2 + 3) * 4
+        vec![
+            Op::Push(2),
+            Op::Push(3),
+            Op::Add,
+            Op::Push(4),
+            Op::Mul,
+            Op::Halt,
+        ]
+    }
+
+    #[test]
+    fn test_stack_vm() {
+        let k = 4;
+        let circuit = StackVmCircuit::<Fp>::new(program());
+        assert_eq!(circuit.trace.last().unwrap().top, 20);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(20)]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_stack_vm_underflow() {
+        let k = 4;
+        let circuit = StackVmCircuit::<Fp>::new(vec![Op::Push(2), Op::Add, Op::Halt]);
+        let out = circuit.trace.last().unwrap().top;
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(out)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_stack_vm_wrong_intermediate() {
+        let k = 4;
+        let mut circuit = StackVmCircuit::<Fp>::new(program());
+        // Pretend 2 + 3 = 6, and carry the lie through to the end.
+        circuit.trace[3].top = 6;
+        circuit.trace[4].second = 6;
+        circuit.trace[5].top = 24;
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(24)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    fn deep_program() -> Vec<Op> {
+        // 2 * (3 + 4 * 5), four deep before the first MUL
+        vec![
+            Op::Push(2),
+            Op::Push(3),
+            Op::Push(4),
+            Op::Push(5),
+            Op::Mul,
+            Op::Add,
+            Op::Mul,
+            Op::Halt,
+        ]
+    }
+
+    #[test]
+    fn test_stack_vm_spill() {
+        let k = 5;
+        let circuit = StackVmCircuit::<Fp>::new(deep_program());
+        assert_eq!(circuit.trace.last().unwrap().top, 46);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(46)]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_stack_vm_wrong_fill() {
+        let k = 5;
+        let mut circuit = StackVmCircuit::<Fp>::new(deep_program());
+        // Fill 7 where 3 was spilled, and compute honestly from there.
+        circuit.trace[5].second = 7;
+        circuit.trace[6].top = 27;
+        circuit.trace[7].top = 54;
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(54)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_stack_vm_no_halt() {
+        let k = 4;
+        let circuit = StackVmCircuit::<Fp>::new(vec![Op::Push(2), Op::Push(3), Op::Add]);
+        assert!(MockProver::run(k, &circuit, vec![vec![Fp::from(5)]]).is_err());
+    }
+
+    #[test]
+    fn test_stack_vm_wrong_output() {
+        let k = 4;
+        let circuit = StackVmCircuit::<Fp>::new(program());
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(21)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+/// chap6: UTF-8 validation
+/// Prove that a private byte string is valid UTF-8 by running the validating
+/// DFA over it, one byte per row.
+///
+/// Every byte falls in one of 12 classes that tell the DFA apart:
+///
+///   0: 00..7F  ASCII               6: E1..EC, EE..EF  3-byte lead
+///   1: 80..8F  continuation        7: ED              3-byte lead, no surrogates
+///   2: 90..9F  continuation        8: F0              4-byte lead, no overlong
+///   3: A0..BF  continuation        9: F1..F3          4-byte lead
+///   4: C2..DF  2-byte lead        10: F4              4-byte lead, <= U+10FFFF
+///   5: E0      3-byte lead, no overlong   11: C0, C1, F5..FF  never valid
+///
+/// and the state counts the continuation bytes still expected, plus the four
+/// states where the next one is restricted (after E0, ED, F0 and F4). State 0
+/// is both the start and the only accepting state.
+///
+/// | byte | class | state   | q_step | t_byte | t_class | t_from | t_on | t_to |
+/// |------|-------|---------|--------|--------|---------|--------|------|------|
+/// | 0xE2 |   6   | 0       |   1    |  0x00  |    0    |   0    |  0   |  0   |
+/// | 0x82 |   1   | 2       |   1    |  0x01  |    0    |   0    |  4   |  1   |
+/// | 0xAC |   3   | 1       |   1    |  ...   |   ...   |  ...   | ...  | ...  |
+/// |      |       | 0       |   0    |  0xFF  |   11    |   7    |  1   |  2   |
+///
+///   (q * byte, q * class) ∈ (t_byte, t_class)
+///   (q * state, q * class, q * state_next) ∈ (t_from, t_on, t_to)
+///
+/// Only valid transitions are in the second table, so a byte the DFA rejects
+/// has no row to match. A disabled row looks up all zeros, which is byte 0 of
+/// class 0 and the ASCII self-loop on state 0, so both tables already have it.
+/// The first state is pinned to 0, and so is the last, which rules out a
+/// string that ends in the middle of a sequence.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+pub const ACCEPT: u8 = 0;
+
+/// `(state, class, next_state)` for every transition that does not reject.
+pub const TRANSITIONS: [(u8, u8, u8); 23] = [
+    (0, 0, 0),
+    (0, 4, 1),
+    (0, 5, 3),
+    (0, 6, 2),
+    (0, 7, 4),
+    (0, 8, 6),
+    (0, 9, 5),
+    (0, 10, 7),
+    // one continuation byte left
+    (1, 1, 0),
+    (1, 2, 0),
+    (1, 3, 0),
+    // two left
+    (2, 1, 1),
+    (2, 2, 1),
+    (2, 3, 1),
+    // after E0: A0..BF
+    (3, 3, 1),
+    // after ED: 80..9F
+    (4, 1, 1),
+    (4, 2, 1),
+    // three left
+    (5, 1, 2),
+    (5, 2, 2),
+    (5, 3, 2),
+    // after F0: 90..BF
+    (6, 2, 2),
+    (6, 3, 2),
+    // after F4: 80..8F
+    (7, 1, 2),
+];
+
+pub fn byte_class(b: u8) -> u8 {
+    match b {
+        0x00..=0x7f => 0,
+        0x80..=0x8f => 1,
+        0x90..=0x9f => 2,
+        0xa0..=0xbf => 3,
+        0xc2..=0xdf => 4,
+        0xe0 => 5,
+        0xe1..=0xec | 0xee..=0xef => 6,
+        0xed => 7,
+        0xf0 => 8,
+        0xf1..=0xf3 => 9,
+        0xf4 => 10,
+        0xc0 | 0xc1 | 0xf5..=0xff => 11,
+    }
+}
+
+pub fn next_state(state: u8, class: u8) -> Option<u8> {
+    TRANSITIONS
+        .iter()
+        .find(|(from, on, _)| *from == state && *on == class)
+        .map(|(_, _, to)| *to)
+}
+
+/// Run the DFA natively.
+pub fn is_valid_utf8(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .try_fold(ACCEPT, |state, b| next_state(state, byte_class(*b)))
+        == Some(ACCEPT)
+}
+
+#[derive(Debug, Clone)]
+pub struct Utf8Config {
+    byte: Column<Advice>,
+    class: Column<Advice>,
+    state: Column<Advice>,
+    q_step: Selector,
+    t_byte: TableColumn,
+    t_class: TableColumn,
+    t_from: TableColumn,
+    t_on: TableColumn,
+    t_to: TableColumn,
+}
+
+impl Utf8Config {
+    fn load_tables<F: PrimeField>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "byte classes",
+            |mut table| {
+                for b in 0..=255u8 {
+                    let (row, class) = (b as usize, byte_class(b) as u64);
+                    table.assign_cell(
+                        || "byte",
+                        self.t_byte,
+                        row,
+                        || Value::known(F::from(b as u64)),
+                    )?;
+                    table.assign_cell(
+                        || "class",
+                        self.t_class,
+                        row,
+                        || Value::known(F::from(class)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_table(
+            || "transitions",
+            |mut table| {
+                for (row, (from, on, to)) in TRANSITIONS.iter().enumerate() {
+                    let cell = |v: &u8| Value::known(F::from(*v as u64));
+                    table.assign_cell(|| "from", self.t_from, row, || cell(from))?;
+                    table.assign_cell(|| "on", self.t_on, row, || cell(on))?;
+                    table.assign_cell(|| "to", self.t_to, row, || cell(to))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Utf8Circuit {
+    pub bytes: Vec<Value<u8>>,
+}
+
+impl Utf8Circuit {
+    pub fn new(bytes: &[u8]) -> Self {
+        Utf8Circuit {
+            bytes: bytes.iter().map(|b| Value::known(*b)).collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for Utf8Circuit {
+    type Config = Utf8Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Utf8Circuit {
+            bytes: vec![Value::unknown(); self.bytes.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let byte = meta.advice_column();
+        let class = meta.advice_column();
+        let state = meta.advice_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(state);
+        meta.enable_constant(constant);
+        let q_step = meta.complex_selector();
+        let t_byte = meta.lookup_table_column();
+        let t_class = meta.lookup_table_column();
+        let t_from = meta.lookup_table_column();
+        let t_on = meta.lookup_table_column();
+        let t_to = meta.lookup_table_column();
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_step);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let class = meta.query_advice(class, Rotation::cur());
+            vec![(q.clone() * byte, t_byte), (q * class, t_class)]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_step);
+            let from = meta.query_advice(state, Rotation::cur());
+            let on = meta.query_advice(class, Rotation::cur());
+            let to = meta.query_advice(state, Rotation::next());
+            vec![
+                (q.clone() * from, t_from),
+                (q.clone() * on, t_on),
+                (q * to, t_to),
+            ]
+        });
+
+        Utf8Config {
+            byte,
+            class,
+            state,
+            q_step,
+            t_byte,
+            t_class,
+            t_from,
+            t_on,
+            t_to,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_tables(&mut layouter)?;
+
+        layouter.assign_region(
+            || "dfa",
+            |mut region| {
+                let accept = F::from(ACCEPT as u64);
+                let mut state = Value::known(ACCEPT);
+                let mut end =
+                    region.assign_advice_from_constant(|| "start", config.state, 0, accept)?;
+                for (row, b) in self.bytes.iter().enumerate() {
+                    config.q_step.enable(&mut region, row)?;
+                    let class = b.map(byte_class);
+                    // A rejected byte has no next state; any value will do,
+                    // the transition lookup fails either way.
+                    state = state
+                        .zip(class)
+                        .map(|(s, c)| next_state(s, c).unwrap_or(ACCEPT));
+                    let field = |v: Value<u8>| v.map(|v| F::from(v as u64));
+                    region.assign_advice(|| "byte", config.byte, row, || field(*b))?;
+                    region.assign_advice(|| "class", config.class, row, || field(class))?;
+                    end =
+                        region.assign_advice(|| "state", config.state, row + 1, || field(state))?;
+                }
+                region.constrain_constant(end.cell(), accept)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn prove(bytes: &[u8]) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 9;
+        assert_eq!(is_valid_utf8(bytes), std::str::from_utf8(bytes).is_ok());
+        let circuit = Utf8Circuit::new(bytes);
+        MockProver::<Fp>::run(k, &circuit, vec![]).unwrap().verify()
+    }
+
+    #[test]
+    fn test_utf8_ascii() {
+        assert!(prove(b"hello, world").is_ok());
+    }
+
+    #[test]
+    fn test_utf8_multi_byte() {
+        assert!(prove("é".as_bytes()).is_ok());
+        assert!(prove("€".as_bytes()).is_ok());
+        assert!(prove("𝄞".as_bytes()).is_ok());
+        assert!(prove("aé€𝄞z".as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_utf8_overlong() {
+        // '/' as two and as three bytes.
+        assert!(prove(&[0xc0, 0xaf]).is_err());
+        assert!(prove(&[0xe0, 0x80, 0xaf]).is_err());
+        // A UTF-16 surrogate, U+D800.
+        assert!(prove(&[0xed, 0xa0, 0x80]).is_err());
+    }
+
+    #[test]
+    fn test_utf8_truncated() {
+        assert!(prove(&[b'a', 0xe2, 0x82]).is_err());
+    }
+
+    #[test]
+    fn test_utf8_lone_continuation() {
+        assert!(prove(&[b'a', 0x80, b'b']).is_err());
+    }
+}
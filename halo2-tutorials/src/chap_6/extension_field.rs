@@ -0,0 +1,296 @@
+/// chap6: a quadratic extension
+/// Pairings on BLS12-381 live in a tower of extensions of the base field,
+/// the first floor of which is `F[x]/(x^2 + 1)`: pairs `(a0, a1)` standing
+/// for `a0 + a1 * i` with `i^2 = -1`. Products multiply out as
+///
+///   (a0 + a1 i)(b0 + b1 i) = (a0 b0 - a1 b1) + (a0 b1 + a1 b0) i
+///
+/// which is two gates of degree 2, one per coordinate. An inverse is
+/// witnessed out of circuit, `(a0 - a1 i) / (a0^2 + a1^2)`, and checked by
+/// multiplying it back to `1 + 0 i`.
+///
+/// `x^2 + 1` is only irreducible when -1 is not a square, as in BLS12-381's
+/// base field. The Pasta fields have `p = 1 mod 4`, so there -1 is a square
+/// and this is a ring, not a field: `a0 + a1 i` with `a1 = sqrt(-1) * a0`
+/// has norm `a0^2 + a1^2 = 0` and no inverse, and the check rejects it.
+///
+/// | a0 | a1 | a2 | a3 | s_mul |
+/// |----|----|----|----|-------|
+/// | a0 | a1 | b0 | b1 |   1   |
+/// | c0 | c1 |    |    |   0   |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+/// An element `c0 + c1 i`, as its two coordinates.
+pub type ExtNumber<F> = [Number<F>; 2];
+
+/// Compute `a * b` natively.
+pub fn ext_mul<F: Field>(a: [F; 2], b: [F; 2]) -> [F; 2] {
+    [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]]
+}
+
+/// Compute `a^-1` natively, `None` when `a` has norm zero.
+pub fn ext_invert<F: Field>(a: [F; 2]) -> Option<[F; 2]> {
+    let norm = a[0].square() + a[1].square();
+    Option::from(norm.invert()).map(|n: F| [a[0] * n, -a[1] * n])
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtFieldConfig {
+    advice: [Column<Advice>; 4],
+    s_mul: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtFieldChip<F: Field> {
+    config: ExtFieldConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ExtFieldChip<F> {
+    pub fn construct(config: ExtFieldConfig) -> Self {
+        ExtFieldChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> ExtFieldConfig {
+        meta.enable_constant(constant);
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_mul = meta.selector();
+
+        meta.create_gate("ext mul", |meta| {
+            let a0 = meta.query_advice(advice[0], Rotation::cur());
+            let a1 = meta.query_advice(advice[1], Rotation::cur());
+            let b0 = meta.query_advice(advice[2], Rotation::cur());
+            let b1 = meta.query_advice(advice[3], Rotation::cur());
+            let c0 = meta.query_advice(advice[0], Rotation::next());
+            let c1 = meta.query_advice(advice[1], Rotation::next());
+            let s_mul = meta.query_selector(s_mul);
+            Constraints::with_selector(
+                s_mul,
+                vec![
+                    (
+                        "real",
+                        a0.clone() * b0.clone() - a1.clone() * b1.clone() - c0,
+                    ),
+                    ("imaginary", a0 * b1 + a1 * b0 - c1),
+                ],
+            )
+        });
+
+        ExtFieldConfig { advice, s_mul }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: [Value<F>; 2],
+    ) -> Result<ExtNumber<F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                let c0 = region.assign_advice(|| "c0", config.advice[0], 0, || value[0])?;
+                let c1 = region.assign_advice(|| "c1", config.advice[1], 0, || value[1])?;
+                Ok([Number(c0), Number(c1)])
+            },
+        )
+    }
+
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: ExtNumber<F>,
+        b: ExtNumber<F>,
+    ) -> Result<ExtNumber<F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "ext mul",
+            |mut region| {
+                config.s_mul.enable(&mut region, 0)?;
+                let cells = [&a[0], &a[1], &b[0], &b[1]];
+                let mut values = vec![];
+                for (cell, column) in cells.into_iter().zip(config.advice) {
+                    cell.0.copy_advice(|| "operand", &mut region, column, 0)?;
+                    values.push(cell.0.value().copied());
+                }
+                let c = values[0]
+                    .zip(values[1])
+                    .zip(values[2].zip(values[3]))
+                    .map(|((a0, a1), (b0, b1))| ext_mul([a0, a1], [b0, b1]));
+                let c0 = region.assign_advice(|| "c0", config.advice[0], 1, || c.map(|c| c[0]))?;
+                let c1 = region.assign_advice(|| "c1", config.advice[1], 1, || c.map(|c| c[1]))?;
+                Ok([Number(c0), Number(c1)])
+            },
+        )
+    }
+
+    /// Witness `a^-1` and check that `a * a^-1 = 1`.
+    pub fn invert(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: ExtNumber<F>,
+    ) -> Result<ExtNumber<F>, Error> {
+        // No inverse: witness zero and let the product check reject it.
+        let inv = a[0]
+            .0
+            .value()
+            .zip(a[1].0.value())
+            .map(|(a0, a1)| ext_invert([*a0, *a1]).unwrap_or([F::ZERO; 2]));
+        let a_inv = self.load_private(
+            layouter.namespace(|| "a^-1"),
+            [inv.map(|c| c[0]), inv.map(|c| c[1])],
+        )?;
+        let one = self.mul(layouter.namespace(|| "a * a^-1"), a, a_inv.clone())?;
+        layouter.assign_region(
+            || "a * a^-1 = 1",
+            |mut region| {
+                region.constrain_constant(one[0].0.cell(), F::ONE)?;
+                region.constrain_constant(one[1].0.cell(), F::ZERO)
+            },
+        )?;
+        Ok(a_inv)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtFieldCircuitConfig {
+    ext: ExtFieldConfig,
+    instance: Column<Instance>,
+}
+
+/// Expose `a * b` and `a^-1`.
+#[derive(Default)]
+pub struct ExtFieldCircuit<F: Field> {
+    pub a: [Value<F>; 2],
+    pub b: [Value<F>; 2],
+}
+
+impl<F: Field> Circuit<F> for ExtFieldCircuit<F> {
+    type Config = ExtFieldCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ExtFieldCircuitConfig {
+            ext: ExtFieldChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = ExtFieldChip::construct(config.ext);
+        let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+        let ab = chip.mul(layouter.namespace(|| "a * b"), a.clone(), b)?;
+        let a_inv = chip.invert(layouter.namespace(|| "a^-1"), a)?;
+        for (row, c) in ab.iter().chain(a_inv.iter()).enumerate() {
+            layouter.constrain_instance(c.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+
+    fn known(x: [Fp; 2]) -> [Value<Fp>; 2] {
+        x.map(Value::known)
+    }
+
+    fn fp(x: i64) -> Fp {
+        if x < 0 {
+            -Fp::from(x.unsigned_abs())
+        } else {
+            Fp::from(x as u64)
+        }
+    }
+
+    #[test]
+    fn test_ext_field() {
+        // (2 + 3i)(4 + 5i) = (8 - 15) + (10 + 12)i
+        let (a, b) = ([fp(2), fp(3)], [fp(4), fp(5)]);
+        let ab = [fp(-7), fp(22)];
+        assert_eq!(ext_mul(a, b), ab);
+        // (2 + 3i)^-1 = (2 - 3i) / 13
+        let thirteenth = Fp::from(13).invert().unwrap();
+        let a_inv = [fp(2) * thirteenth, fp(-3) * thirteenth];
+        assert_eq!(ext_invert(a), Some(a_inv));
+
+        let circuit = ExtFieldCircuit {
+            a: known(a),
+            b: known(b),
+        };
+        let public = vec![ab[0], ab[1], a_inv[0], a_inv[1]];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+
+        // The conjugate is not the inverse.
+        let public = vec![ab[0], ab[1], fp(2), fp(-3)];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_ext_field_i() {
+        // i * i = -1, and i^-1 = -i.
+        let i = [fp(0), fp(1)];
+        assert_eq!(ext_mul(i, i), [fp(-1), fp(0)]);
+        let circuit = ExtFieldCircuit {
+            a: known(i),
+            b: known(i),
+        };
+        let public = vec![fp(-1), fp(0), fp(0), fp(-1)];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_ext_field_zero_divisor() {
+        // Over Pallas -1 has a square root, so 1 + sqrt(-1) i has norm zero.
+        let sqrt_minus_one = (-Fp::one()).sqrt().unwrap();
+        let a = [fp(1), sqrt_minus_one];
+        assert_eq!(ext_invert(a), None);
+        assert_eq!(ext_mul(a, [fp(1), -sqrt_minus_one]), [fp(0), fp(0)]);
+
+        let circuit = ExtFieldCircuit {
+            a: known(a),
+            b: known(a),
+        };
+        let ab = ext_mul(a, a);
+        let public = vec![ab[0], ab[1], fp(0), fp(0)];
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,20 @@
+mod accumulator;
+mod batch_verify;
+mod exercise_bytecode_commit;
+pub(crate) mod exercise_ec_add;
+mod exercise_elgamal;
+mod exercise_mini_vm;
+mod exercise_plonk_from_scratch;
+pub(crate) mod exercise_pedersen;
+mod exercise_rlp;
+mod exercise_scalar_mul;
+mod exercise_schnorr;
+mod exercise_stack_vm;
+mod exercise_utf8;
+mod extension_field;
+mod nullifier;
+mod paillier;
+mod pedersen_commitment;
+mod selective_disclosure;
+mod vdf;
+mod vector_commitment;
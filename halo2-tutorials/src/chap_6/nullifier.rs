@@ -0,0 +1,209 @@
+/// chap6: nullifiers
+/// Spending a note reveals its nullifier. Each note has exactly one, so a
+/// second spend of the same note reveals the same value again and can be
+/// rejected, while nothing about the note itself is leaked.
+///
+/// Following Sapling, the nullifier derives from the note's private per-note
+/// randomness `rho` and `psi` and from its commitment `cm`:
+///
+///   nf = Poseidon(rho, psi, cm)
+///
+/// | a0  | a1  | a2 | a3 (partial sbox) | rc_a[3] | rc_b[3] | instance |
+/// |-----|-----|----|-------------------|---------|---------|----------|
+/// | rho | psi | cm |                   |         |         |    nf    |
+/// |      Poseidon permutation rows ...                                |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::poseidon::configure_pow5;
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// Compute the nullifier natively.
+pub fn nullifier<F: PrimeField>(rho: F, psi: F, cm: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<3>, WIDTH, RATE>::init().hash([rho, psi, cm])
+}
+
+#[derive(Debug, Clone)]
+pub struct NullifierConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+pub struct NullifierChip<F: PrimeField> {
+    config: NullifierConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> NullifierChip<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: NullifierConfig<F>) -> Self {
+        NullifierChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> NullifierConfig<F> {
+        let advice: [Column<Advice>; WIDTH] = (0..WIDTH)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        NullifierConfig {
+            advice,
+            poseidon,
+            instance,
+        }
+    }
+
+    /// Witness the note's `rho`, `psi` and `cm` side by side on one row.
+    pub fn load_note(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rho: Value<F>,
+        psi: Value<F>,
+        cm: Value<F>,
+    ) -> Result<[AssignedCell<F, F>; 3], Error> {
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "load note",
+            |mut region| {
+                let rho = region.assign_advice(|| "rho", advice[0], 0, || rho)?;
+                let psi = region.assign_advice(|| "psi", advice[1], 0, || psi)?;
+                let cm = region.assign_advice(|| "cm", advice[2], 0, || cm)?;
+                Ok([rho, psi, cm])
+            },
+        )
+    }
+
+    /// nf = Poseidon(rho, psi, cm)
+    pub fn derive(
+        &self,
+        mut layouter: impl Layouter<F>,
+        note: [AssignedCell<F, F>; 3],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<3>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher.hash(layouter.namespace(|| "nf"), note)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nf: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(nf.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+pub struct NullifierCircuit<F: PrimeField> {
+    pub rho: Value<F>,
+    pub psi: Value<F>,
+    pub cm: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for NullifierCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = NullifierConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        NullifierChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = NullifierChip::construct(config);
+        let note = chip.load_note(layouter.namespace(|| "note"), self.rho, self.psi, self.cm)?;
+        let nf = chip.derive(layouter.namespace(|| "derive"), note)?;
+        chip.expose_public(layouter.namespace(|| "nf"), nf, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn circuit(rho: Fp, psi: Fp, cm: Fp) -> NullifierCircuit<Fp> {
+        NullifierCircuit {
+            rho: Value::known(rho),
+            psi: Value::known(psi),
+            cm: Value::known(cm),
+        }
+    }
+
+    #[test]
+    fn test_nullifier_rho() {
+        let k = 7;
+        let (psi, cm) = (Fp::from(7), Fp::from(42));
+        let nf_1 = nullifier(Fp::from(1), psi, cm);
+        let nf_2 = nullifier(Fp::from(2), psi, cm);
+        assert_ne!(nf_1, nf_2);
+
+        let circuit_1 = circuit(Fp::from(1), psi, cm);
+        let prover = MockProver::run(k, &circuit_1, vec![vec![nf_1]]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit_2 = circuit(Fp::from(2), psi, cm);
+        let prover = MockProver::run(k, &circuit_2, vec![vec![nf_2]]).unwrap();
+        prover.assert_satisfied();
+
+        // A note cannot claim another note's nullifier.
+        let prover = MockProver::run(k, &circuit_2, vec![vec![nf_1]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_nullifier_commitment() {
+        let k = 7;
+        let (rho, psi) = (Fp::from(1), Fp::from(7));
+        let nf = nullifier(rho, psi, Fp::from(42));
+        let nf_other = nullifier(rho, psi, Fp::from(43));
+        assert_ne!(nf, nf_other);
+
+        let circuit = circuit(rho, psi, Fp::from(43));
+        let prover = MockProver::run(k, &circuit, vec![vec![nf_other]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![nf]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
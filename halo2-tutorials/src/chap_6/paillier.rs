@@ -0,0 +1,297 @@
+/// chap6: Paillier
+/// Paillier encryption is additively homomorphic: with `g = n + 1`,
+///
+///   Enc(m, r) = g^m * r^n mod n^2
+///
+/// and multiplying two ciphertexts adds their plaintexts,
+///
+///   Enc(m1, r1) * Enc(m2, r2) mod n^2 = Enc(m1 + m2, r1 * r2).
+///
+/// `PaillierAddChip` proves that `c3 = c1 * c2 mod n^2`. Reduction mod `n^2`
+/// is a quotient `q` and a remainder, with the remainder range-checked below
+/// the modulus:
+///
+///   c1 * c2 = q * n^2 + c3,    c3 + d = n^2 - 1
+///
+/// Every value is split into two byte limbs, checked with `ByteChip`, so all
+/// of them are below 2^16 and neither side of the first equation can wrap
+/// around the Pasta modulus. `d` having limbs is what proves `c3 < n^2`.
+///
+/// | lo    | hi    | x  | n_sq | s_limbs | s_mod |
+/// |-------|-------|----|------|---------|-------|
+/// | c1_lo | c1_hi | c1 | n^2  |    1    |   1   |
+/// | c2_lo | c2_hi | c2 |      |    1    |   0   |
+/// | q_lo  | q_hi  | q  |      |    1    |   0   |
+/// | c3_lo | c3_hi | c3 |      |    1    |   0   |
+/// | d_lo  | d_hi  | d  |      |    1    |   0   |
+///
+/// Real Paillier moduli are thousands of bits, many limbs per value, and the
+/// products need a carry chain across limbs; two limbs limit this chip to
+/// `n^2 < 2^16`, which is enough to show the idea.
+use std::marker::PhantomData;
+
+use halo2_proofs::{circuit::*, pasta::group::ff::PrimeField, plonk::*, poly::Rotation};
+
+use crate::gadgets::{
+    byte::{ByteChip, ByteConfig},
+    Number,
+};
+
+fn pow_mod(base: u64, exp: u64, modulus: u64) -> u64 {
+    (0..exp).fold(1, |acc, _| acc * base % modulus)
+}
+
+/// Encrypt `m` with randomness `r` under the public key `n`.
+pub fn encrypt(n: u64, m: u64, r: u64) -> u64 {
+    let n_sq = n * n;
+    pow_mod(n + 1, m, n_sq) * pow_mod(r, n, n_sq) % n_sq
+}
+
+/// Decrypt `c` with the secret primes `p` and `q`.
+pub fn decrypt(p: u64, q: u64, c: u64) -> u64 {
+    let n = p * q;
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    let lambda = (p - 1) * (q - 1) / gcd(p - 1, q - 1);
+    let l = |x: u64| (x - 1) / n;
+    let mu = (1..n)
+        .find(|mu| l(pow_mod(n + 1, lambda, n * n)) * mu % n == 1)
+        .unwrap();
+    l(pow_mod(c, lambda, n * n)) * mu % n
+}
+
+fn to_u64<F: PrimeField>(x: &F) -> u64 {
+    let repr = x.to_repr();
+    u64::from_le_bytes(repr.as_ref()[..8].try_into().unwrap())
+}
+
+#[derive(Debug, Clone)]
+pub struct PaillierAddConfig {
+    pub advice: [Column<Advice>; 3],
+    n_sq: Column<Fixed>,
+    s_limbs: Selector,
+    s_mod: Selector,
+    byte: ByteConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaillierAddChip<F: PrimeField> {
+    config: PaillierAddConfig,
+    n: u64,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PaillierAddChip<F> {
+    pub fn construct(config: PaillierAddConfig, n: u64) -> Self {
+        assert!(n * n < 1 << 16, "n^2 must fit in two byte limbs");
+        PaillierAddChip {
+            config,
+            n,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+    ) -> PaillierAddConfig {
+        let [lo, hi, x, byte] = advice;
+        for col in [lo, hi, x] {
+            meta.enable_equality(col);
+        }
+        let n_sq = meta.fixed_column();
+        let s_limbs = meta.selector();
+        let s_mod = meta.selector();
+
+        meta.create_gate("x = lo + 256 * hi", |meta| {
+            let s = meta.query_selector(s_limbs);
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            Constraints::with_selector(s, vec![lo + Expression::Constant(F::from(256)) * hi - x])
+        });
+
+        meta.create_gate("c3 = c1 * c2 mod n^2", |meta| {
+            let s = meta.query_selector(s_mod);
+            let n_sq = meta.query_fixed(n_sq, Rotation::cur());
+            let [c1, c2, q, c3, d] = [0, 1, 2, 3, 4].map(|i| meta.query_advice(x, Rotation(i)));
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                s,
+                vec![
+                    ("product", c1 * c2 - q * n_sq.clone() - c3.clone()),
+                    ("c3 < n^2", c3 + d - (n_sq - one)),
+                ],
+            )
+        });
+
+        PaillierAddConfig {
+            advice: [lo, hi, x],
+            n_sq,
+            s_limbs,
+            s_mod,
+            byte: ByteChip::configure(meta, byte),
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        ByteChip::construct(self.config.byte.clone()).load_table(layouter)
+    }
+
+    /// Multiply two ciphertexts, returning `c1 * c2 mod n^2`, which encrypts
+    /// the sum of their plaintexts.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        c1: &Number<F>,
+        c2: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        let n_sq = self.n * self.n;
+        let (c3, limbs) = layouter.assign_region(
+            || "paillier add",
+            |mut region| {
+                config.s_mod.enable(&mut region, 0)?;
+                region.assign_fixed(|| "n^2", config.n_sq, 0, || Value::known(F::from(n_sq)))?;
+
+                let c1 =
+                    c1.0.copy_advice(|| "c1", &mut region, config.advice[2], 0)?;
+                let c2 =
+                    c2.0.copy_advice(|| "c2", &mut region, config.advice[2], 1)?;
+                // The product of two 64-bit witnesses needs 128 bits; for
+                // ciphertexts below n^2 the quotient and remainder fit back.
+                let product = c1
+                    .value()
+                    .zip(c2.value())
+                    .map(|(c1, c2)| u128::from(to_u64(c1)) * u128::from(to_u64(c2)));
+                let q = product.map(|p| (p / u128::from(n_sq)) as u64);
+                let c3 = product.map(|p| (p % u128::from(n_sq)) as u64);
+                let d = c3.map(|c3| n_sq - 1 - c3);
+
+                let mut x = vec![c1, c2];
+                for (row, (name, value)) in [("q", q), ("c3", c3), ("d", d)].into_iter().enumerate()
+                {
+                    let value = value.map(F::from);
+                    x.push(region.assign_advice(|| name, config.advice[2], row + 2, || value)?);
+                }
+
+                let mut limbs = vec![];
+                for (row, x) in x.iter().enumerate() {
+                    config.s_limbs.enable(&mut region, row)?;
+                    let v = x.value().map(to_u64);
+                    let lo = v.map(|v| F::from(v & 0xff));
+                    let hi = v.map(|v| F::from(v >> 8));
+                    limbs.push(region.assign_advice(|| "lo", config.advice[0], row, || lo)?);
+                    limbs.push(region.assign_advice(|| "hi", config.advice[1], row, || hi)?);
+                }
+                Ok((Number(x[3].clone()), limbs))
+            },
+        )?;
+
+        let byte = ByteChip::construct(config.byte.clone());
+        for limb in limbs {
+            byte.check_byte(layouter.namespace(|| "limb"), Number(limb))?;
+        }
+        Ok(c3)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PaillierConfig {
+    add: PaillierAddConfig,
+    instance: Column<Instance>,
+}
+
+/// Prove that instance row 2 is the homomorphic sum of the ciphertexts in
+/// rows 0 and 1.
+#[derive(Default)]
+pub struct PaillierCircuit {
+    pub n: u64,
+}
+
+impl<F: PrimeField> Circuit<F> for PaillierCircuit {
+    type Config = PaillierConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        PaillierCircuit { n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PaillierConfig {
+            add: PaillierAddChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PaillierAddChip::construct(config.add.clone(), self.n);
+        chip.load_table(layouter.namespace(|| "byte table"))?;
+
+        let x = config.add.advice[2];
+        let (c1, c2) = layouter.assign_region(
+            || "load ciphertexts",
+            |mut region| {
+                let load = |region: &mut Region<'_, F>, row| {
+                    region
+                        .assign_advice_from_instance(|| "c", config.instance, row, x, row)
+                        .map(Number)
+                };
+                Ok((load(&mut region, 0)?, load(&mut region, 1)?))
+            },
+        )?;
+        let c3 = chip.add(layouter.namespace(|| "add"), &c1, &c2)?;
+        layouter.constrain_instance(c3.0.cell(), config.instance, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const P: u64 = 5;
+    const Q: u64 = 7;
+    const N: u64 = P * Q;
+
+    fn prove(c1: u64, c2: u64, c3: u64) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 9;
+        let instance = vec![Fp::from(c1), Fp::from(c2), Fp::from(c3)];
+        let prover = MockProver::run(k, &PaillierCircuit { n: N }, vec![instance]).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_paillier_add() {
+        let (m1, m2) = (3, 4);
+        let c1 = encrypt(N, m1, 2);
+        let c2 = encrypt(N, m2, 3);
+        let c3 = c1 * c2 % (N * N);
+        assert_eq!(decrypt(P, Q, c1), m1);
+        assert_eq!(decrypt(P, Q, c3), m1 + m2);
+        assert_eq!(c3, encrypt(N, m1 + m2, 6));
+
+        assert!(prove(c1, c2, c3).is_ok());
+    }
+
+    #[test]
+    fn test_paillier_wrong_product() {
+        let c1 = encrypt(N, 3, 2);
+        let c2 = encrypt(N, 4, 3);
+        let c3 = c1 * c2 % (N * N);
+        assert!(prove(c1, c2, (c3 + 1) % (N * N)).is_err());
+        // The right residue class, but not reduced.
+        assert!(prove(c1, c2, c3 + N * N).is_err());
+    }
+}
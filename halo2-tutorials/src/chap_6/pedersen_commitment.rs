@@ -0,0 +1,128 @@
+/// chap6: Pedersen commitment from scalar multiplications
+/// The same commitment as `exercise_pedersen`,
+///
+///   C = m*G + r*H
+///
+/// but assembled from two `ScalarMulChip` multiplications and one point
+/// addition, and with only the x-coordinate made public:
+///
+///   instance = [C.x]
+///
+/// That is how commitments are usually published on chain, and it comes at a
+/// price: `C` and `-C` share their x-coordinate, so `(-m, -r)` opens the same
+/// instance. Protocols that compress points this way either keep the sign of
+/// `y` elsewhere or do not mind the negated opening.
+///
+/// | a0 .. a9         | lagrange_coeffs[8] | constant | table    | instance |
+/// |------------------|--------------------|----------|----------|----------|
+/// | m*G windows ...  |                    |          | 0..2^10  |   C.x    |
+/// | r*H windows ...  |                    |          |          |          |
+/// | m*G + r*H        |                    |          |          |          |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::pallas,
+    plonk::*,
+};
+
+use super::{
+    exercise_pedersen::PedersenBase,
+    exercise_scalar_mul::{ScalarMulChip, ScalarMulConfig},
+};
+
+#[derive(Debug, Clone)]
+pub struct PedersenCommitConfig {
+    scalar_mul: ScalarMulConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct PedersenCommitCircuit {
+    pub m: Value<pallas::Scalar>,
+    pub r: Value<pallas::Scalar>,
+}
+
+impl Circuit<pallas::Base> for PedersenCommitCircuit {
+    type Config = PedersenCommitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advice: [Column<Advice>; 10] = (0..10)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let lagrange_coeffs: [Column<Fixed>; 8] = (0..8)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        PedersenCommitConfig {
+            scalar_mul: ScalarMulChip::configure(meta, advice, lagrange_coeffs, table),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        let chip = ScalarMulChip::construct(config.scalar_mul);
+        chip.load_table(&mut layouter)?;
+
+        let mg = chip.mul(layouter.namespace(|| "m*G"), PedersenBase::g(), self.m)?;
+        let rh = chip.mul(layouter.namespace(|| "r*H"), PedersenBase::h(), self.r)?;
+        let c = mg.add(layouter.namespace(|| "m*G + r*H"), &rh)?;
+        layouter.constrain_instance(c.inner().x().cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_6::exercise_pedersen::{commit, coordinates};
+    use halo2_proofs::dev::MockProver;
+
+    const K: u32 = 11;
+
+    fn verify(m: pallas::Scalar, r: pallas::Scalar, c_x: pallas::Base) -> bool {
+        let circuit = PedersenCommitCircuit {
+            m: Value::known(m),
+            r: Value::known(r),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![c_x]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn commit_x(m: pallas::Scalar, r: pallas::Scalar) -> pallas::Base {
+        coordinates(commit(m, r))[0]
+    }
+
+    #[test]
+    fn test_pedersen_commit_open() {
+        let (m, r) = (pallas::Scalar::from(1000), pallas::Scalar::from(0xb11d));
+        assert!(verify(m, r, commit_x(m, r)));
+        // The negated opening lands on -C, with the same x.
+        assert!(verify(-m, -r, commit_x(m, r)));
+    }
+
+    #[test]
+    fn test_pedersen_commit_binding() {
+        let (m, r) = (pallas::Scalar::from(1000), pallas::Scalar::from(0xb11d));
+        let one = pallas::Scalar::one();
+        assert_ne!(commit_x(m, r), commit_x(m + one, r));
+        assert_ne!(commit_x(m, r), commit_x(m, r + one));
+        assert!(!verify(m + one, r, commit_x(m, r)));
+        assert!(!verify(m, r + one, commit_x(m, r)));
+    }
+}
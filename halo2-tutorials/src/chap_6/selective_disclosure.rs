@@ -0,0 +1,313 @@
+/// chap6: selective disclosure
+/// A credential is a vector of attributes `v`, committed to with a private
+/// random salt `r` by
+///
+///   commitment = Poseidon(Poseidon(v_0, ..., v_{N-1}), r)
+///
+/// Without the salt the commitment would hide nothing: attributes such as a
+/// birth year take few values, and anyone could hash every candidate for the
+/// hidden ones until the public commitment comes out. The salt makes that
+/// search one over the whole field.
+///
+/// The holder reveals some attributes and keeps the others private: the
+/// verifier sends a mask, and the circuit exposes `v_i * mask_i` for every
+/// `i`. Where `mask_i = 1` that is `v_i`; where it is 0, it is 0 whatever `v_i`
+/// is, so two credentials that differ only there disclose the same values.
+///
+///   instance = [commitment, mask_0, ..., mask_{N-1}, d_0, ..., d_{N-1}]
+///
+/// The mask is not checked to be boolean: it is the verifier's, and any
+/// nonzero `mask_i` reveals `v_i` as `d_i / mask_i` anyway.
+///
+/// | a0  | a1     | a2  | a3 (partial sbox) | rc_a[3] | rc_b[3] | s_disclose |
+/// |-----|--------|-----|-------------------|---------|---------|------------|
+/// | v_i | mask_i | d_i |                   |         |         |     1      |
+/// | r   |        |     |                   |         |         |     0      |
+/// |      Poseidon permutation rows ...                                      |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::poseidon::configure_pow5;
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// Compute the commitment natively.
+pub fn commit<F: PrimeField, const N: usize>(v: [F; N], r: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    let attributes =
+        poseidon::Hash::<_, P128Pow5T3, ConstantLength<N>, WIDTH, RATE>::init().hash(v);
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init().hash([attributes, r])
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectiveDisclosureConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+    s_disclose: Selector,
+}
+
+pub struct SelectiveDisclosureChip<F: PrimeField, const N: usize> {
+    config: SelectiveDisclosureConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N: usize> SelectiveDisclosureChip<F, N>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: SelectiveDisclosureConfig<F>) -> Self {
+        SelectiveDisclosureChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SelectiveDisclosureConfig<F> {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        let s_disclose = meta.selector();
+        meta.create_gate("d = v * mask", |meta| {
+            let s = meta.query_selector(s_disclose);
+            let v = meta.query_advice(advice[0], Rotation::cur());
+            let mask = meta.query_advice(advice[1], Rotation::cur());
+            let d = meta.query_advice(advice[2], Rotation::cur());
+            Constraints::with_selector(s, vec![v * mask - d])
+        });
+
+        SelectiveDisclosureConfig {
+            advice,
+            poseidon,
+            instance,
+            s_disclose,
+        }
+    }
+
+    /// Witness the attributes, one per row.
+    pub fn load_attributes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: [Value<F>; N],
+    ) -> Result<[AssignedCell<F, F>; N], Error> {
+        let col = self.config.advice[0];
+        layouter.assign_region(
+            || "load attributes",
+            |mut region| {
+                let cells = v
+                    .iter()
+                    .enumerate()
+                    .map(|(row, v)| region.assign_advice(|| "v", col, row, || *v))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(cells.try_into().unwrap())
+            },
+        )
+    }
+
+    /// Witness the salt.
+    pub fn load_salt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        r: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load salt",
+            |mut region| region.assign_advice(|| "r", self.config.advice[0], 0, || r),
+        )
+    }
+
+    /// commitment = Poseidon(Poseidon(v_0, ..., v_{N-1}), r)
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: [AssignedCell<F, F>; N],
+        r: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<N>, WIDTH, RATE>::init(
+            Pow5Chip::construct(self.config.poseidon.clone()),
+            layouter.namespace(|| "init attributes"),
+        )?;
+        let attributes = hasher.hash(layouter.namespace(|| "attributes"), v)?;
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, WIDTH, RATE>::init(
+            Pow5Chip::construct(self.config.poseidon.clone()),
+            layouter.namespace(|| "init salted"),
+        )?;
+        hasher.hash(layouter.namespace(|| "commitment"), [attributes, r])
+    }
+
+    /// Constrain `v_i * mask_i` to the instance, with `mask_i` from
+    /// `instance[1 + i]` and the product at `instance[1 + N + i]`.
+    pub fn disclose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: &[AssignedCell<F, F>; N],
+    ) -> Result<(), Error> {
+        let [v_col, mask_col, d_col] = self.config.advice;
+        let disclosed = layouter.assign_region(
+            || "disclose",
+            |mut region| {
+                v.iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        self.config.s_disclose.enable(&mut region, i)?;
+                        let v = v.copy_advice(|| "v", &mut region, v_col, i)?;
+                        let mask = region.assign_advice_from_instance(
+                            || "mask",
+                            self.config.instance,
+                            1 + i,
+                            mask_col,
+                            i,
+                        )?;
+                        let d = v.value().copied() * mask.value();
+                        region.assign_advice(|| "d", d_col, i, || d)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        for (i, d) in disclosed.iter().enumerate() {
+            layouter.constrain_instance(d.cell(), self.config.instance, 1 + N + i)?;
+        }
+        Ok(())
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        commitment: AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(commitment.cell(), self.config.instance, 0)
+    }
+}
+
+pub struct SelectiveDisclosureCircuit<F: PrimeField, const N: usize> {
+    pub v: [Value<F>; N],
+    pub r: Value<F>,
+}
+
+impl<F: PrimeField, const N: usize> Circuit<F> for SelectiveDisclosureCircuit<F, N>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = SelectiveDisclosureConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        SelectiveDisclosureCircuit {
+            v: [Value::unknown(); N],
+            r: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SelectiveDisclosureChip::<F, N>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = SelectiveDisclosureChip::<F, N>::construct(config);
+        let v = chip.load_attributes(layouter.namespace(|| "attributes"), self.v)?;
+        let r = chip.load_salt(layouter.namespace(|| "salt"), self.r)?;
+        chip.disclose(layouter.namespace(|| "disclose"), &v)?;
+        let commitment = chip.commit(layouter.namespace(|| "commit"), v, r)?;
+        chip.expose_public(layouter.namespace(|| "commitment"), commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 8;
+    const N: usize = 4;
+
+    /// Reveal indices 0 and 2.
+    const MASK: [u64; N] = [1, 0, 1, 0];
+
+    fn instance(commitment: Fp, disclosed: [u64; N]) -> Vec<Fp> {
+        let mut instance = vec![commitment];
+        instance.extend(MASK.map(Fp::from));
+        instance.extend(disclosed.map(Fp::from));
+        instance
+    }
+
+    /// The holder's salt.
+    const R: u64 = 0x5a17;
+
+    fn commitment(v: [u64; N]) -> Fp {
+        commit(v.map(Fp::from), Fp::from(R))
+    }
+
+    fn verify(v: [u64; N], instance: Vec<Fp>) -> bool {
+        let circuit = SelectiveDisclosureCircuit::<Fp, N> {
+            v: v.map(|v| Value::known(Fp::from(v))),
+            r: Value::known(Fp::from(R)),
+        };
+        let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_selective_disclosure() {
+        let v = [30, 1990, 7, 123456];
+        assert!(verify(v, instance(commitment(v), [30, 0, 7, 0])));
+
+        // The revealed attributes must be the committed ones.
+        assert!(!verify(v, instance(commitment(v), [31, 0, 7, 0])));
+        assert!(!verify(v, instance(commitment(v), [30, 0, 8, 0])));
+        // And the commitment that of the whole vector.
+        let other = commitment([30, 1991, 7, 123456]);
+        assert!(!verify(v, instance(other, [30, 0, 7, 0])));
+    }
+
+    #[test]
+    fn test_selective_disclosure_hidden_elements() {
+        // Two credentials that differ only outside the mask disclose the same
+        // values: only the commitments tell them apart.
+        let v = [30, 1990, 7, 123456];
+        let w = [30, 2001, 7, 654321];
+        assert!(verify(v, instance(commitment(v), [30, 0, 7, 0])));
+        assert!(verify(w, instance(commitment(w), [30, 0, 7, 0])));
+
+        // Nothing can be claimed about a hidden attribute, true or false.
+        assert!(!verify(v, instance(commitment(v), [30, 1990, 7, 0])));
+    }
+
+    #[test]
+    fn test_selective_disclosure_salt() {
+        let v = [30, 1990, 7, 123456];
+        // Guessing every hidden attribute right does not reproduce the
+        // commitment without the salt.
+        assert_ne!(commitment(v), commit(v.map(Fp::from), Fp::from(0)));
+        assert_ne!(commitment(v), commit(v.map(Fp::from), Fp::from(R + 1)));
+
+        // The commitment is to the salt too: another salt does not open it.
+        let circuit = SelectiveDisclosureCircuit::<Fp, N> {
+            v: v.map(|v| Value::known(Fp::from(v))),
+            r: Value::known(Fp::from(R + 1)),
+        };
+        let instance = instance(commitment(v), [30, 0, 7, 0]);
+        let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
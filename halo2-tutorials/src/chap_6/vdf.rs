@@ -0,0 +1,222 @@
+/// chap6: iterated hashing as a VDF
+/// A verifiable delay function takes a fixed number of sequential steps to
+/// evaluate, and its result is cheap to check. Iterating a hash is sequential,
+/// since no step can start before the previous one is done; the proof makes
+/// the check cheap:
+///
+///   x_0 = seed,    x_{i+1} = Poseidon(x_i),    out = x_STEPS
+///
+/// Each step is one single-input Poseidon sponge. The sponge absorbs its input
+/// by copying it into the permutation's state, so passing `x_i` as the input
+/// of step `i + 1` is a copy constraint: no step can start from anything but
+/// the output of the one before.
+///
+/// The seed is copied in from the instance, and `out` is constrained to it:
+///
+/// | a0   | a1 | a2 | a3 (partial sbox) | rc_a[3] | rc_b[3] | instance |
+/// |------|----|----|-------------------|---------|---------|----------|
+/// | seed |    |    |                   |         |         |   seed   |
+/// |      Poseidon(seed) rows ...                          |   out    |
+/// |      Poseidon(x_1) rows ...                           |          |
+/// |      ...                                              |          |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::poseidon::configure_pow5;
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// One step natively.
+pub fn step<F: PrimeField>(x: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<1>, WIDTH, RATE>::init().hash([x])
+}
+
+/// Evaluate the VDF natively, `steps` hashes from `seed`.
+pub fn vdf<F: PrimeField>(seed: F, steps: usize) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    (0..steps).fold(seed, |x, _| step(x))
+}
+
+#[derive(Debug, Clone)]
+pub struct VDFConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+pub struct VDFChip<F: PrimeField, const STEPS: usize> {
+    config: VDFConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const STEPS: usize> VDFChip<F, STEPS>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: VDFConfig<F>) -> Self {
+        VDFChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> VDFConfig<F> {
+        let advice = [(); WIDTH].map(|_| meta.advice_column());
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        VDFConfig {
+            advice,
+            poseidon,
+            instance,
+        }
+    }
+
+    /// Copy the seed in from `instance[row]`.
+    pub fn load_seed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        row: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load seed",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "seed",
+                    self.config.instance,
+                    row,
+                    self.config.advice[0],
+                    0,
+                )
+            },
+        )
+    }
+
+    /// x_{i+1} = Poseidon(x_i)
+    fn step(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<1>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher.hash(layouter.namespace(|| "hash"), [x])
+    }
+
+    /// `STEPS` hashes from `seed`, each output the input of the next.
+    pub fn eval(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seed: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut x = seed;
+        for i in 0..STEPS {
+            x = self.step(layouter.namespace(|| format!("step {}", i)), x)?;
+        }
+        Ok(x)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        out: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(out.cell(), self.config.instance, row)
+    }
+}
+
+/// Public `[seed, out]`. Both are in the instance, so there is nothing to
+/// witness: the prover's work is the hashing itself.
+#[derive(Default)]
+pub struct VDFCircuit<F: PrimeField, const STEPS: usize> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const STEPS: usize> Circuit<F> for VDFCircuit<F, STEPS>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = VDFConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        VDFChip::<F, STEPS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = VDFChip::<F, STEPS>::construct(config);
+        let seed = chip.load_seed(layouter.namespace(|| "seed"), 0)?;
+        let out = chip.eval(layouter.namespace(|| "eval"), seed)?;
+        chip.expose_public(layouter.namespace(|| "out"), out, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 10;
+    const STEPS: usize = 10;
+
+    fn verify<const N: usize>(seed: Fp, out: Fp) -> bool {
+        let circuit = VDFCircuit::<Fp, N>::default();
+        let prover = MockProver::run(K, &circuit, vec![vec![seed, out]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_vdf() {
+        let seed = Fp::from(42);
+        let out = vdf(seed, STEPS);
+        assert_eq!(out, step(vdf(seed, STEPS - 1)));
+        assert!(verify::<STEPS>(seed, out));
+
+        // Another seed has another output.
+        assert!(!verify::<STEPS>(Fp::from(43), out));
+        assert!(verify::<STEPS>(Fp::from(43), vdf(Fp::from(43), STEPS)));
+    }
+
+    #[test]
+    fn test_vdf_skipped_step() {
+        let seed = Fp::from(42);
+        // One step short, or one too many: the circuit fixes the count.
+        let short = vdf(seed, STEPS - 1);
+        let long = vdf(seed, STEPS + 1);
+        assert!(!verify::<STEPS>(seed, short));
+        assert!(!verify::<STEPS>(seed, long));
+        // Nor can the prover start one step in and claim the seed.
+        assert!(!verify::<STEPS>(seed, vdf(step(seed), STEPS)));
+        assert!(verify::<9>(seed, short));
+    }
+}
@@ -0,0 +1,226 @@
+/// chap6: a vector commitment by evaluation
+/// Read a vector `v` of length `N` as the coefficients of a polynomial, and
+/// commit to it by its value at a challenge `r`:
+///
+///   C = v_0 + v_1 * r + v_2 * r^2 + ... + v_{N-1} * r^{N-1}
+///
+/// Two different vectors give two different polynomials of degree below `N`,
+/// which agree on at most `N - 1` points: for an `r` picked after `v` is
+/// fixed, they collide with probability `(N - 1) / p`. An `r` the prover
+/// knows in advance binds nothing, any `v` can be adjusted to hit any `C`.
+/// Nor does `C` hide anything: it is a linear function of `v`.
+///
+/// `PolynomialEvalChip` does the evaluation. Opening `v_j` publishes it and
+/// shows that it is the `j`-th coefficient of the evaluated vector, so it is
+/// the element that contributes `v_j * r^j` to `C`.
+///
+/// | a0   | a1 | a2    | instance |
+/// |------|----|-------|----------|
+/// | v_i  | r  |       |    r     |
+/// | Horner's rule     |    C     |
+/// |      |    |       |   v_j    |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::*,
+};
+
+use crate::gadgets::{
+    polynomial_eval::{PolynomialEvalChip, PolynomialEvalConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct VectorCommitmentConfig {
+    polynomial_eval: PolynomialEvalConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorCommitmentChip<F: Field, const N: usize> {
+    config: VectorCommitmentConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, const N: usize> VectorCommitmentChip<F, N> {
+    pub fn construct(config: VectorCommitmentConfig) -> Self {
+        VectorCommitmentChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> VectorCommitmentConfig {
+        meta.enable_equality(instance);
+        VectorCommitmentConfig {
+            polynomial_eval: PolynomialEvalChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    pub fn load_vector(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: [Value<F>; N],
+    ) -> Result<[Number<F>; N], Error> {
+        let col = self.config.polynomial_eval.advice[0];
+        layouter.assign_region(
+            || "load vector",
+            |mut region| {
+                let cells = v
+                    .iter()
+                    .enumerate()
+                    .map(|(row, v)| region.assign_advice(|| "v", col, row, || *v).map(Number))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(cells.try_into().unwrap())
+            },
+        )
+    }
+
+    /// The challenge, copied from `instance[row]`.
+    pub fn load_challenge(
+        &self,
+        mut layouter: impl Layouter<F>,
+        row: usize,
+    ) -> Result<Number<F>, Error> {
+        let col = self.config.polynomial_eval.advice[1];
+        layouter.assign_region(
+            || "load challenge",
+            |mut region| {
+                region
+                    .assign_advice_from_instance(|| "r", self.config.instance, row, col, 0)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// `sum_i r^i * v_i`.
+    pub fn commit(
+        &self,
+        layouter: impl Layouter<F>,
+        v: &[Number<F>; N],
+        r: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        PolynomialEvalChip::construct(self.config.polynomial_eval.clone()).eval(layouter, v, r)
+    }
+
+    /// Constrain `v[index]` to `instance[row]`.
+    pub fn open(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: &[Number<F>; N],
+        index: usize,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(v[index].0.cell(), self.config.instance, row)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: Number<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), self.config.instance, row)
+    }
+}
+
+/// Open element `J` of a private vector of length `N`:
+/// `instance = [r, C, v_J]`.
+struct OpeningCircuit<F: Field, const N: usize, const J: usize> {
+    v: [Value<F>; N],
+}
+
+impl<F: Field, const N: usize, const J: usize> Circuit<F> for OpeningCircuit<F, N, J> {
+    type Config = VectorCommitmentConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        OpeningCircuit {
+            v: [Value::unknown(); N],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        VectorCommitmentChip::<F, N>::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = VectorCommitmentChip::<F, N>::construct(config);
+        let v = chip.load_vector(layouter.namespace(|| "v"), self.v)?;
+        let r = chip.load_challenge(layouter.namespace(|| "r"), 0)?;
+        let commitment = chip.commit(layouter.namespace(|| "commit"), &v, &r)?;
+        chip.expose_public(layouter.namespace(|| "C"), commitment, 1)?;
+        chip.open(layouter.namespace(|| "open"), &v, J, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 5;
+    const N: usize = 4;
+
+    fn commit(v: &[Fp; N], r: Fp) -> Fp {
+        v.iter().rev().fold(Fp::zero(), |acc, v| acc * r + v)
+    }
+
+    fn verify<const J: usize>(v: [Fp; N], public: [Fp; 3]) -> bool {
+        let circuit = OpeningCircuit::<Fp, N, J> {
+            v: v.map(Value::known),
+        };
+        let prover = MockProver::run(K, &circuit, vec![public.to_vec()]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_vector_commitment() {
+        let r = Fp::from(0x1234_5678);
+        let v = [3, 1, 4, 1].map(Fp::from);
+        let c = commit(&v, r);
+        assert!(verify::<2>(v, [r, c, v[2]]));
+        assert!(verify::<0>(v, [r, c, v[0]]));
+
+        // Changing any element changes the commitment.
+        for i in 0..N {
+            let mut changed = v;
+            changed[i] += Fp::one();
+            assert_ne!(commit(&changed, r), c);
+            assert!(!verify::<2>(changed, [r, c, changed[2]]));
+        }
+        // The same vector under another challenge is another commitment.
+        assert!(!verify::<2>(v, [r + Fp::one(), c, v[2]]));
+        // And the opened element is the one committed to.
+        assert!(!verify::<2>(v, [r, c, v[1]]));
+    }
+
+    #[test]
+    fn test_vector_commitment_opening_term() {
+        let r = Fp::from(7);
+        let v = [3, 1, 4, 1].map(Fp::from);
+        let c = commit(&v, r);
+
+        // Moving v_2 by delta moves C by delta * r^2, and by no other power.
+        let delta = Fp::from(10);
+        let mut moved = v;
+        moved[2] += delta;
+        let r2 = r.square();
+        assert!(verify::<2>(moved, [r, c + delta * r2, moved[2]]));
+        assert!(!verify::<2>(moved, [r, c + delta * r, moved[2]]));
+        assert!(!verify::<2>(moved, [r, c + delta * r2 * r, moved[2]]));
+    }
+}
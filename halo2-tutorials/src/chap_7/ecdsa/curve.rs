@@ -0,0 +1,580 @@
+/// chap7: secp256k1 points over emulated coordinates
+/// Points of `y^2 = x^3 + 7` in affine form, each coordinate an `Integer` of
+/// the base field chip. The group law needs divisions, which are witnessed
+/// and checked by multiplying back:
+///
+///   add:     lambda * (x2 - x1) = y2 - y1,   x2 - x1 invertible
+///   double:  lambda * 2y = 3x^2
+///   both:    x3 = lambda^2 - x1 - x2,        y3 = lambda * (x1 - x3) - y1
+///
+/// `add` is incomplete: it rejects `P + P`, for which the first check says
+/// nothing about `lambda`, and `P + (-P)`. `double` needs no such check on a
+/// curve of odd order, where no point has `y = 0`.
+///
+/// `mul_add` computes `[u1]G + [u2]P` with one shared run of doublings
+/// (Shamir's trick), most significant bit first, adding `G`, `P` or `G + P`
+/// by the two bits. The accumulator starts at a fixed offset point `Q`
+/// whose discrete log nobody knows, so it is never the identity and an honest
+/// run hits none of `add`'s exceptions; `[2^256]Q` is taken off at the end.
+///
+/// | a0    | a1    | s_first | s_bit |
+/// |-------|-------|---------|-------|
+/// | b_63  | b_63  |    1    |   0   |   acc_0 = b_63
+/// | b_62  | acc_1 |    0    |   1   |   acc_i = 2 * acc_{i-1} + b_{63-i}
+/// | ...   |  ...  |         |       |
+/// | b_0   | limb  |    0    |   1   |   the limb copied in
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+
+use super::field::{limbs_of, secp256k1_p, FieldChip, FieldConfig, Integer, LIMBS, LIMB_BITS};
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    cond_swap::{CondSwapChip, CondSwapConfig},
+    Number,
+};
+
+/// A point natively, `None` being the identity.
+pub type Affine = Option<(BigUint, BigUint)>;
+
+pub fn generator() -> (BigUint, BigUint) {
+    let coordinate = |hex: &[u8]| BigUint::parse_bytes(hex, 16).unwrap();
+    (
+        coordinate(b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+        coordinate(b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"),
+    )
+}
+
+/// The offset point: the first `x` at or above the bytes of its name that
+/// is on the curve.
+pub fn offset_point() -> (BigUint, BigUint) {
+    let p = secp256k1_p();
+    let mut x = BigUint::from_bytes_be(b"halo2-step-by-step ecdsa offset");
+    loop {
+        let rhs = (x.modpow(&BigUint::from(3u32), &p) + 7u32) % &p;
+        // p = 3 mod 4, so a square root is a single power.
+        let y = rhs.modpow(&((&p + 1u32) / 4u32), &p);
+        if &y * &y % &p == rhs {
+            return (x, y);
+        }
+        x += 1u32;
+    }
+}
+
+fn sub_mod(a: &BigUint, b: &BigUint, p: &BigUint) -> BigUint {
+    (a % p + p - b % p) % p
+}
+
+fn div_mod(a: &BigUint, b: &BigUint, p: &BigUint) -> BigUint {
+    a * b.modpow(&(p - 2u32), p) % p
+}
+
+/// The slope of the chord through two points.
+fn add_slope(x1: &BigUint, y1: &BigUint, x2: &BigUint, y2: &BigUint) -> BigUint {
+    let p = secp256k1_p();
+    div_mod(&sub_mod(y2, y1, &p), &sub_mod(x2, x1, &p), &p)
+}
+
+/// The slope of the tangent at a point.
+fn double_slope(x: &BigUint, y: &BigUint) -> BigUint {
+    let p = secp256k1_p();
+    div_mod(&(x * x * 3u32), &(y * 2u32), &p)
+}
+
+/// The third point on the line of slope `lambda`, reflected.
+fn chord(lambda: &BigUint, x1: &BigUint, y1: &BigUint, x2: &BigUint) -> (BigUint, BigUint) {
+    let p = secp256k1_p();
+    let x3 = sub_mod(&sub_mod(&(lambda * lambda), x1, &p), x2, &p);
+    let y3 = sub_mod(&(lambda * sub_mod(x1, &x3, &p)), y1, &p);
+    (x3, y3)
+}
+
+pub fn neg(a: &Affine) -> Affine {
+    let p = secp256k1_p();
+    a.as_ref().map(|(x, y)| (x.clone(), sub_mod(&p, y, &p)))
+}
+
+pub fn add(a: &Affine, b: &Affine) -> Affine {
+    match (a, b) {
+        (None, _) => b.clone(),
+        (_, None) => a.clone(),
+        (Some((x1, y1)), Some((x2, y2))) if x1 == x2 => {
+            if y1 == y2 {
+                double(a)
+            } else {
+                None
+            }
+        }
+        (Some((x1, y1)), Some((x2, y2))) => Some(chord(&add_slope(x1, y1, x2, y2), x1, y1, x2)),
+    }
+}
+
+pub fn double(a: &Affine) -> Affine {
+    a.as_ref().map(|(x, y)| chord(&double_slope(x, y), x, y, x))
+}
+
+pub fn mul(k: &BigUint, a: &Affine) -> Affine {
+    (0..k.bits()).rev().fold(None, |acc, i| {
+        let acc = double(&acc);
+        if k.bit(i) {
+            add(&acc, a)
+        } else {
+            acc
+        }
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignedPoint<F: PrimeField> {
+    pub x: Integer<F>,
+    pub y: Integer<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveConfig {
+    base: FieldConfig,
+    arith: ArithConfig,
+    select: CondSwapConfig,
+    bits: [Column<Advice>; 2],
+    s_first: Selector,
+    s_bit: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveChip<F: PrimeField> {
+    config: CurveConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> CurveChip<F> {
+    pub fn construct(config: CurveConfig) -> Self {
+        CurveChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `base` must be a field chip for secp256k1's `p`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+        base: FieldConfig,
+    ) -> CurveConfig {
+        let arith = ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant);
+        let select = CondSwapChip::configure(meta, advice);
+        let s_first = meta.selector();
+        let s_bit = meta.selector();
+        let [bit, acc, _, _] = advice;
+
+        meta.create_gate("bit decomposition", |meta| {
+            let s_first = meta.query_selector(s_first);
+            let s_bit = meta.query_selector(s_bit);
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+            vec![
+                (s_first.clone() + s_bit.clone()) * b.clone() * (one - b.clone()),
+                s_first * (acc_cur.clone() - b.clone()),
+                s_bit * (acc_cur - (two * acc_prev + b)),
+            ]
+        });
+
+        CurveConfig {
+            base,
+            arith,
+            select,
+            bits: [bit, acc],
+            s_first,
+            s_bit,
+        }
+    }
+
+    pub fn base(&self) -> FieldChip<F> {
+        FieldChip::construct(self.config.base.clone())
+    }
+
+    /// Witness a point and check that it is on the curve.
+    pub fn load_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        point: Value<(BigUint, BigUint)>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let base = self.base();
+        let point = AssignedPoint {
+            x: base.load(
+                layouter.namespace(|| "x"),
+                point.as_ref().map(|(x, _)| x.clone()),
+            )?,
+            y: base.load(layouter.namespace(|| "y"), point.map(|(_, y)| y))?,
+        };
+        self.assert_on_curve(layouter.namespace(|| "on curve"), &point)?;
+        Ok(point)
+    }
+
+    pub fn load_constant_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (x, y): &(BigUint, BigUint),
+    ) -> Result<AssignedPoint<F>, Error> {
+        let base = self.base();
+        Ok(AssignedPoint {
+            x: base.load_constant(layouter.namespace(|| "x"), x)?,
+            y: base.load_constant(layouter.namespace(|| "y"), y)?,
+        })
+    }
+
+    /// `y^2 = x^3 + 7`.
+    pub fn assert_on_curve(
+        &self,
+        mut layouter: impl Layouter<F>,
+        point: &AssignedPoint<F>,
+    ) -> Result<(), Error> {
+        let base = self.base();
+        let (x, y) = (&point.x, &point.y);
+        let x2 = base.mul(layouter.namespace(|| "x^2"), x, x)?;
+        let x3 = base.mul(layouter.namespace(|| "x^3"), &x2, x)?;
+        let seven = base.load_constant(layouter.namespace(|| "7"), &BigUint::from(7u32))?;
+        let rhs = base.add(layouter.namespace(|| "x^3 + 7"), &x3, &seven)?;
+        base.assert_mul_add_equal(layouter.namespace(|| "y^2 = x^3 + 7"), y, y, None, &rhs)
+    }
+
+    /// `a + b` for `a != b, -b`.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedPoint<F>,
+        b: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let base = self.base();
+        let lambda =
+            a.x.value
+                .as_ref()
+                .zip(a.y.value.as_ref())
+                .zip(b.x.value.as_ref().zip(b.y.value.as_ref()))
+                .map(|((x1, y1), (x2, y2))| add_slope(x1, y1, x2, y2));
+        let lambda = base.load(layouter.namespace(|| "lambda"), lambda)?;
+
+        let dx = base.sub(layouter.namespace(|| "x2 - x1"), &b.x, &a.x)?;
+        base.invert(layouter.namespace(|| "x1 != x2"), &dx)?;
+        base.assert_mul_add_equal(
+            layouter.namespace(|| "lambda * (x2 - x1) + y1 = y2"),
+            &lambda,
+            &dx,
+            Some(&a.y),
+            &b.y,
+        )?;
+        let sx = base.add(layouter.namespace(|| "x1 + x2"), &a.x, &b.x)?;
+        self.finish(layouter, &lambda, a, &sx)
+    }
+
+    /// `2a`.
+    pub fn double(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let base = self.base();
+        let lambda =
+            a.x.value
+                .as_ref()
+                .zip(a.y.value.as_ref())
+                .map(|(x, y)| double_slope(x, y));
+        let lambda = base.load(layouter.namespace(|| "lambda"), lambda)?;
+
+        let x2 = base.mul(layouter.namespace(|| "x^2"), &a.x, &a.x)?;
+        let three = base.load_constant(layouter.namespace(|| "3"), &BigUint::from(3u32))?;
+        let x2_3 = base.mul(layouter.namespace(|| "3x^2"), &x2, &three)?;
+        let y_2 = base.add(layouter.namespace(|| "2y"), &a.y, &a.y)?;
+        base.assert_mul_add_equal(
+            layouter.namespace(|| "lambda * 2y = 3x^2"),
+            &lambda,
+            &y_2,
+            None,
+            &x2_3,
+        )?;
+        let sx = base.add(layouter.namespace(|| "2x"), &a.x, &a.x)?;
+        self.finish(layouter, &lambda, a, &sx)
+    }
+
+    /// `x3 = lambda^2 - (x1 + x2)`, `y3 = lambda * (x1 - x3) - y1`.
+    fn finish(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lambda: &Integer<F>,
+        a: &AssignedPoint<F>,
+        sx: &Integer<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let base = self.base();
+        let x = base.mul_sub(layouter.namespace(|| "x3"), lambda, lambda, sx)?;
+        let dx = base.sub(layouter.namespace(|| "x1 - x3"), &a.x, &x)?;
+        let y = base.mul_sub(layouter.namespace(|| "y3"), lambda, &dx, &a.y)?;
+        Ok(AssignedPoint { x, y })
+    }
+
+    fn select_integer(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &Number<F>,
+        lhs: &Integer<F>,
+        rhs: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        let select = CondSwapChip::construct(self.config.select.clone());
+        let mut limbs = vec![];
+        for (l, r) in lhs.limbs.iter().zip(&rhs.limbs) {
+            limbs.push(select.select(
+                layouter.namespace(|| "limb"),
+                cond.clone(),
+                l.clone(),
+                r.clone(),
+            )?);
+        }
+        let value = cond
+            .0
+            .value()
+            .zip(lhs.value.as_ref())
+            .zip(rhs.value.as_ref())
+            .map(|((c, l), r)| if *c == F::ONE { r.clone() } else { l.clone() });
+        Ok(Integer {
+            limbs: limbs.try_into().unwrap(),
+            value,
+        })
+    }
+
+    /// `lhs` if `cond = 0`, `rhs` if `cond = 1`.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &Number<F>,
+        lhs: &AssignedPoint<F>,
+        rhs: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        Ok(AssignedPoint {
+            x: self.select_integer(layouter.namespace(|| "x"), cond, &lhs.x, &rhs.x)?,
+            y: self.select_integer(layouter.namespace(|| "y"), cond, &lhs.y, &rhs.y)?,
+        })
+    }
+
+    /// The 256 bits of `k`, most significant first.
+    pub fn to_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        k: &Integer<F>,
+    ) -> Result<Vec<Number<F>>, Error> {
+        let config = &self.config;
+        let [bit_col, acc_col] = config.bits;
+        let mut bits = vec![];
+        for i in (0..LIMBS).rev() {
+            let value = k.value.as_ref().map(|k| limbs_of(k, LIMBS)[i]);
+            let limb_bits = layouter.assign_region(
+                || "bit decomposition",
+                |mut region| {
+                    let mut out = vec![];
+                    for row in 0..LIMB_BITS {
+                        let shift = LIMB_BITS - 1 - row;
+                        if row == 0 {
+                            config.s_first.enable(&mut region, row)?;
+                        } else {
+                            config.s_bit.enable(&mut region, row)?;
+                        }
+                        let b = value.map(|v| F::from((v >> shift) & 1));
+                        out.push(Number(region.assign_advice(
+                            || "bit",
+                            bit_col,
+                            row,
+                            || b,
+                        )?));
+                        if row == LIMB_BITS - 1 {
+                            k.limbs[i]
+                                .0
+                                .copy_advice(|| "limb", &mut region, acc_col, row)?;
+                        } else {
+                            let acc = value.map(|v| F::from(v >> shift));
+                            region.assign_advice(|| "acc", acc_col, row, || acc)?;
+                        }
+                    }
+                    Ok(out)
+                },
+            )?;
+            bits.extend(limb_bits);
+        }
+        Ok(bits)
+    }
+
+    /// `[u1]g + [u2]p`, which must not be the identity, for `p != g, -g`.
+    pub fn mul_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        u1: &Integer<F>,
+        g: &AssignedPoint<F>,
+        u2: &Integer<F>,
+        p: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let arith = ArithChip::construct(self.config.arith.clone());
+        let u1_bits = self.to_bits(layouter.namespace(|| "u1 bits"), u1)?;
+        let u2_bits = self.to_bits(layouter.namespace(|| "u2 bits"), u2)?;
+        let gp = self.add(layouter.namespace(|| "g + p"), g, p)?;
+
+        let offset = offset_point();
+        let correction = neg(&mul(&(BigUint::from(1u32) << 256), &Some(offset.clone())))
+            .expect("the offset point has odd order");
+        let mut acc = self.load_constant_point(layouter.namespace(|| "Q"), &offset)?;
+        for (i, (b1, b2)) in u1_bits.into_iter().zip(u2_bits).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("bit {}", i));
+            acc = self.double(layouter.namespace(|| "2acc"), &acc)?;
+
+            // (b1, b2) = (1, 0): g, (0, 1): p, (1, 1): g + p, and (0, 0)
+            // adds g too, but throws the sum away.
+            let t = self.select(layouter.namespace(|| "p or g + p"), &b1, p, &gp)?;
+            let t = self.select(layouter.namespace(|| "table"), &b2, g, &t)?;
+            let sum = self.add(layouter.namespace(|| "acc + t"), &acc, &t)?;
+            let both = arith.mul(layouter.namespace(|| "b1 * b2"), b1.clone(), b2.clone())?;
+            let either = arith.add(layouter.namespace(|| "b1 + b2"), b1, b2)?;
+            let any = arith.sub(layouter.namespace(|| "b1 or b2"), either, both)?;
+            acc = self.select(layouter.namespace(|| "acc"), &any, &acc, &sum)?;
+        }
+
+        let correction =
+            self.load_constant_point(layouter.namespace(|| "-[2^256]Q"), &correction)?;
+        self.add(layouter.namespace(|| "remove offset"), &acc, &correction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_7::ecdsa::field::{LimbRangeChip, LimbRangeConfig};
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, pasta::Fp};
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        range: LimbRangeConfig,
+        curve: CurveConfig,
+    }
+
+    /// Loads `a` and `b` and checks `a + b` and `2a` against the native
+    /// results.
+    #[derive(Default)]
+    struct MyCircuit {
+        a: Value<(BigUint, BigUint)>,
+        b: Value<(BigUint, BigUint)>,
+        sum: (BigUint, BigUint),
+        double: (BigUint, BigUint),
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                sum: self.sum.clone(),
+                double: self.double.clone(),
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 9].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let four = advice[..4].try_into().unwrap();
+            let range = LimbRangeChip::configure(meta, advice);
+            let base = FieldChip::configure(meta, four, constant, range.clone(), secp256k1_p());
+            let curve = CurveChip::configure(meta, four, constant, base);
+            TestConfig { range, curve }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            LimbRangeChip::construct(config.range).load_table(layouter.namespace(|| "table"))?;
+            let chip = CurveChip::construct(config.curve);
+            let base = chip.base();
+            let a = chip.load_point(layouter.namespace(|| "a"), self.a.clone())?;
+            let b = chip.load_point(layouter.namespace(|| "b"), self.b.clone())?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let double = chip.double(layouter.namespace(|| "2a"), &a)?;
+            for (point, expected) in [(sum, &self.sum), (double, &self.double)] {
+                let expected =
+                    chip.load_constant_point(layouter.namespace(|| "expected"), expected)?;
+                base.assert_equal(layouter.namespace(|| "x"), &point.x, &expected.x)?;
+                base.assert_equal(layouter.namespace(|| "y"), &point.y, &expected.y)?;
+            }
+            Ok(())
+        }
+    }
+
+    const K: u32 = 12;
+
+    fn circuit(a: &Affine, b: &Affine) -> MyCircuit {
+        MyCircuit {
+            a: Value::known(a.clone().unwrap()),
+            b: Value::known(b.clone().unwrap()),
+            sum: add(a, b).unwrap(),
+            double: double(a).unwrap(),
+        }
+    }
+
+    fn g_mul(k: u64) -> Affine {
+        mul(&BigUint::from(k), &Some(generator()))
+    }
+
+    #[test]
+    fn test_native_matches_k256() {
+        use k256::{elliptic_curve::sec1::ToEncodedPoint, ProjectivePoint, Scalar};
+
+        for k in [1u64, 2, 3, 0xdead_beef] {
+            let expected = (ProjectivePoint::GENERATOR * Scalar::from(k))
+                .to_affine()
+                .to_encoded_point(false);
+            let (x, y) = g_mul(k).unwrap();
+            assert_eq!(x, BigUint::from_bytes_be(expected.x().unwrap()));
+            assert_eq!(y, BigUint::from_bytes_be(expected.y().unwrap()));
+        }
+        assert_eq!(add(&g_mul(5), &neg(&g_mul(5))), None);
+    }
+
+    #[test]
+    fn test_add_double() {
+        let circuit = circuit(&g_mul(3), &g_mul(0xdead_beef));
+        MockProver::run(K, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_add_double_wrong() {
+        let mut wrong = circuit(&g_mul(3), &g_mul(0xdead_beef));
+        wrong.sum = g_mul(3 + 0xdead_bef0).unwrap();
+        let prover = MockProver::run(K, &wrong, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // Off the curve.
+        let mut off = circuit(&g_mul(3), &g_mul(0xdead_beef));
+        off.b = off.b.map(|(x, y)| (x, y + 1u32));
+        let prover = MockProver::run(K, &off, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_add_incomplete() {
+        // a + a has x1 = x2, which `add` does not handle, even with the
+        // doubling as the claimed sum.
+        let a = g_mul(3);
+        let mut same = circuit(&a, &a);
+        same.sum = double(&a).unwrap();
+        let prover = MockProver::run(K, &same, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,339 @@
+/// chap7: ECDSA verification
+/// A secp256k1 signature `(r, s)` on a message hash `z` under the public key
+/// `P` verifies when
+///
+///   w = s^-1 mod n,    u1 = z * w mod n,    u2 = r * w mod n
+///   r = x([u1]G + [u2]P) mod n
+///
+/// with `r` in `[1, n)`. Here `s` must also be at most `(n - 1) / 2`: `(r, s)`
+/// and `(r, n - s)` verify alike, and requiring the low one, as Bitcoin and
+/// Ethereum do, means there is exactly one valid signature per nonce.
+///
+/// The scalar arithmetic runs on a field chip for `n` and the point
+/// arithmetic on the curve chip, over a field chip for `p`. `x(R)` is made
+/// canonical below `p` before it is compared with `r` mod `n`; since `p > n`,
+/// both `x(R)` and `x(R) - n` are fine as `r`, as ECDSA has it.
+///
+/// The signature stays private; the hash and the key are public limbs:
+///
+///   instance = [z_0 .. z_3, P.x_0 .. P.x_3, P.y_0 .. P.y_3]
+///
+/// The two 256-bit scalar multiplications dominate: a doubling, a point
+/// addition and three selects per bit, each field operation a few dozen rows.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+use num_bigint::BigUint;
+
+use super::{
+    curve::{generator, AssignedPoint, CurveChip, CurveConfig},
+    field::{
+        secp256k1_n, secp256k1_p, FieldChip, FieldConfig, Integer, LimbRangeChip, LimbRangeConfig,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct EcdsaConfig {
+    range: LimbRangeConfig,
+    scalar: FieldConfig,
+    curve: CurveConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct EcdsaChip<F: PrimeField> {
+    config: EcdsaConfig,
+    scalar: FieldChip<F>,
+    curve: CurveChip<F>,
+}
+
+impl<F: PrimeField> EcdsaChip<F> {
+    pub fn construct(config: EcdsaConfig) -> Self {
+        EcdsaChip {
+            scalar: FieldChip::construct(config.scalar.clone()),
+            curve: CurveChip::construct(config.curve.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 9],
+        constant: Column<Fixed>,
+    ) -> EcdsaConfig {
+        let four = [advice[0], advice[1], advice[2], advice[3]];
+        let range = LimbRangeChip::configure(meta, advice);
+        let base = FieldChip::configure(meta, four, constant, range.clone(), secp256k1_p());
+        let scalar = FieldChip::configure(meta, four, constant, range.clone(), secp256k1_n());
+        let curve = CurveChip::configure(meta, four, constant, base);
+        EcdsaConfig {
+            range,
+            scalar,
+            curve,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        LimbRangeChip::construct(self.config.range.clone()).load_table(layouter)
+    }
+
+    pub fn scalar(&self) -> &FieldChip<F> {
+        &self.scalar
+    }
+
+    pub fn curve(&self) -> &CurveChip<F> {
+        &self.curve
+    }
+
+    /// Check that `r` is in `[1, n)` and `s` in `[1, (n - 1) / 2]`, and
+    /// return `w = s^-1`. This is all of `verify` that does not touch the
+    /// curve.
+    pub fn check_scalars(
+        &self,
+        mut layouter: impl Layouter<F>,
+        r: &Integer<F>,
+        s: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        let scalar = &self.scalar;
+        let n = scalar.modulus().clone();
+        scalar.assert_le(layouter.namespace(|| "r < n"), r, &(&n - 1u32))?;
+        scalar.assert_le(
+            layouter.namespace(|| "s <= (n - 1) / 2"),
+            s,
+            &((&n - 1u32) / 2u32),
+        )?;
+        scalar.invert(layouter.namespace(|| "r != 0"), r)?;
+        scalar.invert(layouter.namespace(|| "w = s^-1"), s)
+    }
+
+    /// Check that `(r, s)` is a low-s signature on `z` under `pk`, which must
+    /// be on the curve and neither `G` nor `-G`.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<F>,
+        pk: &AssignedPoint<F>,
+        z: &Integer<F>,
+        r: &Integer<F>,
+        s: &Integer<F>,
+    ) -> Result<(), Error> {
+        let scalar = &self.scalar;
+        let w = self.check_scalars(layouter.namespace(|| "r, s"), r, s)?;
+        let u1 = scalar.mul(layouter.namespace(|| "u1 = z * w"), z, &w)?;
+        let u2 = scalar.mul(layouter.namespace(|| "u2 = r * w"), r, &w)?;
+
+        let g = self
+            .curve
+            .load_constant_point(layouter.namespace(|| "G"), &generator())?;
+        let big_r = self
+            .curve
+            .mul_add(layouter.namespace(|| "[u1]G + [u2]P"), &u1, &g, &u2, pk)?;
+
+        let base = self.curve.base();
+        let p = base.modulus().clone();
+        base.assert_le(layouter.namespace(|| "x(R) < p"), &big_r.x, &(p - 1u32))?;
+        scalar.assert_equal(layouter.namespace(|| "x(R) = r mod n"), &big_r.x, r)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EcdsaCircuitConfig {
+    ecdsa: EcdsaConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct EcdsaCircuit {
+    pub pk: Value<(BigUint, BigUint)>,
+    pub z: Value<BigUint>,
+    pub r: Value<BigUint>,
+    pub s: Value<BigUint>,
+}
+
+impl<F: PrimeField> Circuit<F> for EcdsaCircuit {
+    type Config = EcdsaCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 9].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        EcdsaCircuitConfig {
+            ecdsa: EcdsaChip::configure(meta, advice, constant),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaChip::construct(config.ecdsa);
+        chip.load_table(layouter.namespace(|| "table"))?;
+        let scalar = chip.scalar();
+
+        let z = scalar.load(layouter.namespace(|| "z"), self.z.clone())?;
+        let pk = chip
+            .curve()
+            .load_point(layouter.namespace(|| "P"), self.pk.clone())?;
+        let public = z.limbs.iter().chain(&pk.x.limbs).chain(&pk.y.limbs);
+        for (row, limb) in public.enumerate() {
+            layouter.constrain_instance(limb.0.cell(), config.instance, row)?;
+        }
+
+        let r = scalar.load(layouter.namespace(|| "r"), self.r.clone())?;
+        let s = scalar.load(layouter.namespace(|| "s"), self.s.clone())?;
+        chip.verify(layouter.namespace(|| "verify"), &pk, &z, &r, &s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_7::ecdsa::field::{limbs_of, to_field, LIMBS};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use k256::{
+        ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey},
+        elliptic_curve::sec1::ToEncodedPoint,
+    };
+
+    /// The whole verification takes about 2^17 rows.
+    const K: u32 = 18;
+
+    struct Signed {
+        pk: (BigUint, BigUint),
+        z: BigUint,
+        r: BigUint,
+        s: BigUint,
+    }
+
+    /// Sign a fixed prehash with a fixed key, off-circuit.
+    fn signed(key: u8) -> Signed {
+        let sk = SigningKey::from_bytes(&[key; 32].into()).unwrap();
+        let z = [0x42u8; 32];
+        let sig: Signature = sk.sign_prehash(&z).unwrap();
+        let sig = sig.normalize_s().unwrap_or(sig);
+        let (r, s) = sig.split_bytes();
+        let pk = sk.verifying_key().as_affine().to_encoded_point(false);
+        Signed {
+            pk: (
+                BigUint::from_bytes_be(pk.x().unwrap()),
+                BigUint::from_bytes_be(pk.y().unwrap()),
+            ),
+            z: BigUint::from_bytes_be(&z),
+            r: BigUint::from_bytes_be(&r),
+            s: BigUint::from_bytes_be(&s),
+        }
+    }
+
+    fn verify(signed: &Signed, pk: &(BigUint, BigUint)) -> bool {
+        let circuit = EcdsaCircuit {
+            pk: Value::known(pk.clone()),
+            z: Value::known(signed.z.clone()),
+            r: Value::known(signed.r.clone()),
+            s: Value::known(signed.s.clone()),
+        };
+        let instance: Vec<Fp> = [&signed.z, &pk.0, &pk.1]
+            .iter()
+            .flat_map(|x| limbs_of(x, LIMBS))
+            .map(|limb| to_field::<Fp>(&BigUint::from(limb)))
+            .collect();
+        let prover = MockProver::run(K, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    /// Only the scalar checks on `(r, s)`, which need no curve arithmetic and
+    /// fit in far fewer rows than the whole verification.
+    struct ScalarCircuit {
+        r: BigUint,
+        s: BigUint,
+    }
+
+    impl Circuit<Fp> for ScalarCircuit {
+        type Config = EcdsaConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ScalarCircuit {
+                r: BigUint::default(),
+                s: BigUint::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 9].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            EcdsaChip::configure(meta, advice, constant)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = EcdsaChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let scalar = chip.scalar();
+            let r = scalar.load(layouter.namespace(|| "r"), Value::known(self.r.clone()))?;
+            let s = scalar.load(layouter.namespace(|| "s"), Value::known(self.s.clone()))?;
+            chip.check_scalars(layouter.namespace(|| "r, s"), &r, &s)?;
+            Ok(())
+        }
+    }
+
+    fn scalars_pass(r: BigUint, s: BigUint) -> bool {
+        let prover = MockProver::run(12, &ScalarCircuit { r, s }, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_ecdsa_scalars() {
+        let n = secp256k1_n();
+        let valid = signed(1);
+        assert!(scalars_pass(valid.r.clone(), valid.s.clone()));
+
+        // (r, n - s) is the malleated twin, and is not low-s.
+        assert!(!scalars_pass(valid.r.clone(), &n - &valid.s));
+        // Neither is anything between (n - 1) / 2 and n.
+        assert!(scalars_pass(valid.r.clone(), (&n - 1u32) / 2u32));
+        assert!(!scalars_pass(valid.r.clone(), (&n + 1u32) / 2u32));
+
+        // r must be in [1, n), and s nonzero.
+        assert!(!scalars_pass(BigUint::from(0u32), valid.s.clone()));
+        assert!(!scalars_pass(n.clone(), valid.s.clone()));
+        assert!(!scalars_pass(valid.r.clone(), BigUint::from(0u32)));
+    }
+
+    // About a minute in release mode; run with `--release -- --ignored`. The
+    // scalar checks alone run by default, in `test_ecdsa_scalars`.
+    #[test]
+    #[ignore]
+    fn test_ecdsa() {
+        let valid = signed(1);
+        assert!(verify(&valid, &valid.pk));
+
+        // A signature by another key.
+        let other = signed(2);
+        assert!(!verify(&other, &valid.pk));
+
+        // The same signature on a different hash.
+        let wrong_hash = Signed {
+            z: &valid.z + 1u32,
+            ..signed(1)
+        };
+        assert!(!verify(&wrong_hash, &valid.pk));
+
+        // (r, n - s) verifies as plain ECDSA, but is not low-s.
+        let malleated = Signed {
+            s: secp256k1_n() - &valid.s,
+            ..signed(1)
+        };
+        assert!(!verify(&malleated, &valid.pk));
+    }
+}
@@ -0,0 +1,838 @@
+/// chap7: non-native field arithmetic
+/// secp256k1's coordinates and scalars live in two 256-bit prime fields that
+/// are neither of Pasta's. An element is carried as four 64-bit limbs,
+///
+///   x = x_0 + x_1 * 2^64 + x_2 * 2^128 + x_3 * 2^192,
+///
+/// each range-checked by `LimbRangeChip`, and every operation is one
+/// instance of
+///
+///   a * b + c - d + 2p = q * p + r
+///
+/// with the quotient `q` and the remainder `r` witnessed as limbs too. `b`
+/// defaults to 1 and `c`, `d` to 0, which makes the same gate `mul`, `add`,
+/// `sub` and `mul_sub`; `2p` keeps the left side non-negative as long as
+/// `d < 2p`, so `q` never has to be negative.
+///
+/// The identity is checked over the integers, one limb column at a time:
+/// column `k` of both sides must agree up to what it carries into column
+/// `k + 1`. A carry can be negative, so it is stored plus `2^127` and
+/// range-checked to 128 bits. Every term then stays far below the Pasta
+/// modulus, no column can wrap around, and the columns together prove the
+/// integer identity, hence `r = a * b + c - d mod p`.
+///
+/// Results are only loosely reduced: `r < 2^256`, which for secp256k1 may be
+/// `r` or `r + p`. `assert_le` makes a value canonical where that matters,
+/// and equality checks fix the remainder to a constant instead, which no
+/// `r + p` can match.
+///
+/// | a0  | a1  | a2  | a3  | s_op |
+/// |-----|-----|-----|-----|------|
+/// | a_0 | a_1 | a_2 | a_3 |  1   |
+/// | b_0 | b_1 | b_2 | b_3 |      |
+/// | c_0 | c_1 | c_2 | c_3 |      |
+/// | d_0 | d_1 | d_2 | d_3 |      |
+/// | q_0 | q_1 | q_2 | q_3 |      |
+/// | r_0 | r_1 | r_2 | r_3 |      |
+/// | u_0 | u_1 | u_2 | u_3 |      |   u_k = carry_k + 2^127
+/// | u_4 | u_5 |     |     |      |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Region, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+use num_bigint::{BigInt, BigUint};
+
+use crate::gadgets::{
+    byte::{ByteChip, ByteConfig},
+    Number,
+};
+
+pub const LIMB_BITS: usize = 64;
+pub const LIMBS: usize = 4;
+/// Carries out of the limb columns `0 .. 2 * LIMBS - 2` of a product.
+const CARRIES: usize = 2 * LIMBS - 2;
+const CARRY_OFFSET_BITS: usize = 127;
+const CARRY_LIMBS: usize = 2;
+
+pub fn secp256k1_p() -> BigUint {
+    BigUint::parse_bytes(
+        b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        16,
+    )
+    .unwrap()
+}
+
+pub fn secp256k1_n() -> BigUint {
+    BigUint::parse_bytes(
+        b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        16,
+    )
+    .unwrap()
+}
+
+/// The `n` 64-bit limbs of `x`, least significant first.
+pub fn limbs_of(x: &BigUint, n: usize) -> Vec<u64> {
+    let mut limbs = x.to_u64_digits();
+    assert!(limbs.len() <= n, "{} does not fit in {} limbs", x, n);
+    limbs.resize(n, 0);
+    limbs
+}
+
+pub fn to_field<F: PrimeField>(x: &BigUint) -> F {
+    x.to_u64_digits().iter().rev().fold(F::ZERO, |acc, limb| {
+        acc * F::from_u128(1 << LIMB_BITS) + F::from(*limb)
+    })
+}
+
+/// An element of an emulated field, as `LIMBS` range-checked limbs.
+#[derive(Debug, Clone)]
+pub struct Integer<F: PrimeField> {
+    pub limbs: [Number<F>; LIMBS],
+    pub value: Value<BigUint>,
+}
+
+/// | a0 .. a7       | a8    | q_lookup | s_chunk | s_last |
+/// |----------------|-------|----------|---------|--------|
+/// | bytes of x_0   | acc_0 |    1     |    1    |   0    |   acc_0 = x_0 + 2^64 * acc_1
+/// | bytes of x_1   | acc_1 |    1     |    0    |   1    |   acc_1 = x_1
+#[derive(Debug, Clone)]
+pub struct LimbRangeConfig {
+    bytes: [Column<Advice>; 8],
+    acc: Column<Advice>,
+    byte: ByteConfig,
+    q_lookup: Selector,
+    s_chunk: Selector,
+    s_last: Selector,
+}
+
+/// Witness values of a whole number of 64-bit limbs, one limb per row, its
+/// eight bytes looked up in the byte table.
+#[derive(Debug, Clone)]
+pub struct LimbRangeChip<F: PrimeField> {
+    config: LimbRangeConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> LimbRangeChip<F> {
+    pub fn construct(config: LimbRangeConfig) -> Self {
+        LimbRangeChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 9],
+    ) -> LimbRangeConfig {
+        // Only the table is used; `ByteChip`'s own selector stays off.
+        let byte = ByteChip::configure(meta, advice[0]);
+        let bytes: [Column<Advice>; 8] = advice[..8].try_into().unwrap();
+        let acc = advice[8];
+        meta.enable_equality(acc);
+        let q_lookup = meta.complex_selector();
+        let s_chunk = meta.selector();
+        let s_last = meta.selector();
+
+        for col in bytes {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_lookup);
+                let b = meta.query_advice(col, Rotation::cur());
+                vec![(q * b, byte.table)]
+            });
+        }
+
+        meta.create_gate("limb decomposition", |meta| {
+            let s_chunk = meta.query_selector(s_chunk);
+            let s_last = meta.query_selector(s_last);
+            let chunk = bytes
+                .iter()
+                .rev()
+                .map(|col| meta.query_advice(*col, Rotation::cur()))
+                .reduce(|acc, b| acc * Expression::Constant(F::from(256)) + b)
+                .unwrap();
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let base = Expression::Constant(F::from_u128(1 << LIMB_BITS));
+            vec![
+                s_chunk * (acc_cur.clone() - chunk.clone() - base * acc_next),
+                s_last * (acc_cur - chunk),
+            ]
+        });
+
+        LimbRangeConfig {
+            bytes,
+            acc,
+            byte,
+            q_lookup,
+            s_chunk,
+            s_last,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        ByteChip::construct(self.config.byte.clone()).load_table(layouter)
+    }
+
+    /// Witness `value`, which must fit in `limbs` 64-bit limbs.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<BigUint>,
+        limbs: usize,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        let bytes = value.as_ref().map(|v| {
+            let mut bytes = v.to_bytes_le();
+            assert!(
+                bytes.len() <= 8 * limbs,
+                "{} does not fit in {} limbs",
+                v,
+                limbs
+            );
+            bytes.resize(8 * limbs, 0);
+            bytes
+        });
+        layouter.assign_region(
+            || "limb range check",
+            |mut region| {
+                let mut first = None;
+                for row in 0..limbs {
+                    config.q_lookup.enable(&mut region, row)?;
+                    if row + 1 < limbs {
+                        config.s_chunk.enable(&mut region, row)?;
+                    } else {
+                        config.s_last.enable(&mut region, row)?;
+                    }
+                    for (i, col) in config.bytes.iter().enumerate() {
+                        let b = bytes.as_ref().map(|b| F::from(b[8 * row + i] as u64));
+                        region.assign_advice(|| "byte", *col, row, || b)?;
+                    }
+                    let acc = value
+                        .as_ref()
+                        .map(|v| to_field::<F>(&(v >> (LIMB_BITS * row))));
+                    let acc = region.assign_advice(|| "acc", config.acc, row, || acc)?;
+                    if row == 0 {
+                        first = Some(Number(acc));
+                    }
+                }
+                Ok(first.expect("limbs > 0"))
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldConfig {
+    advice: [Column<Advice>; 4],
+    range: LimbRangeConfig,
+    modulus: BigUint,
+    s_op: Selector,
+    s_le: Selector,
+}
+
+/// Arithmetic modulo a 256-bit `modulus`, emulated over `F`.
+#[derive(Debug, Clone)]
+pub struct FieldChip<F: PrimeField> {
+    config: FieldConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> FieldChip<F> {
+    pub fn construct(config: FieldConfig) -> Self {
+        FieldChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `modulus` must be exactly 256 bits, so that anything that fits in the
+    /// limbs is below `2 * modulus`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+        range: LimbRangeConfig,
+        modulus: BigUint,
+    ) -> FieldConfig {
+        assert_eq!(modulus.bits(), (LIMBS * LIMB_BITS) as u64);
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        meta.enable_constant(constant);
+        let s_op = meta.selector();
+        let s_le = meta.selector();
+
+        let p = limbs_of(&modulus, LIMBS);
+        let two_p = limbs_of(&(&modulus * 2u32), LIMBS + 1);
+        let base = Expression::Constant(F::from_u128(1 << LIMB_BITS));
+
+        meta.create_gate("a * b + c - d + 2p = q * p + r", |meta| {
+            let s_op = meta.query_selector(s_op);
+            let [a, b, c, d, q, r, u_lo, u_hi] = [0, 1, 2, 3, 4, 5, 6, 7]
+                .map(|row| advice.map(|col| meta.query_advice(col, Rotation(row))));
+            let offset = Expression::Constant(F::from_u128(1 << CARRY_OFFSET_BITS));
+            let carries: Vec<_> = u_lo
+                .into_iter()
+                .chain(u_hi.into_iter().take(CARRIES - LIMBS))
+                .map(|u| u - offset.clone())
+                .collect();
+
+            let constraints = (0..2 * LIMBS - 1).map(|col| {
+                let mut e = Expression::Constant(F::from(*two_p.get(col).unwrap_or(&0)));
+                for i in 0..LIMBS {
+                    if let Some(j) = col.checked_sub(i).filter(|j| *j < LIMBS) {
+                        e = e + a[i].clone() * b[j].clone()
+                            - q[i].clone() * Expression::Constant(F::from(p[j]));
+                    }
+                }
+                if col < LIMBS {
+                    e = e + c[col].clone() - d[col].clone() - r[col].clone();
+                }
+                if col > 0 {
+                    e = e + carries[col - 1].clone();
+                }
+                if col < CARRIES {
+                    e = e - carries[col].clone() * base.clone();
+                }
+                e
+            });
+            Constraints::with_selector(s_op, constraints.collect::<Vec<_>>())
+        });
+
+        // | a0      | a1      | a2      | a3      | s_le |
+        // |---------|---------|---------|---------|------|
+        // | x_0     | x_1     | x_2     | x_3     |  1   |
+        // | slack_0 | slack_1 | slack_2 | slack_3 |      |
+        // | bound_0 | bound_1 | bound_2 | bound_3 |      |
+        // | carry_0 | carry_1 | carry_2 |         |      |
+        meta.create_gate("x + slack = bound", |meta| {
+            let s_le = meta.query_selector(s_le);
+            let [x, slack, bound, carry] =
+                [0, 1, 2, 3].map(|row| advice.map(|col| meta.query_advice(col, Rotation(row))));
+            let one = Expression::Constant(F::ONE);
+            let mut constraints = vec![];
+            for k in 0..LIMBS {
+                let mut e = x[k].clone() + slack[k].clone() - bound[k].clone();
+                if k > 0 {
+                    e = e + carry[k - 1].clone();
+                }
+                if k < LIMBS - 1 {
+                    e = e - carry[k].clone() * base.clone();
+                    constraints.push(carry[k].clone() * (one.clone() - carry[k].clone()));
+                }
+                constraints.push(e);
+            }
+            Constraints::with_selector(s_le, constraints)
+        });
+
+        FieldConfig {
+            advice,
+            range,
+            modulus,
+            s_op,
+            s_le,
+        }
+    }
+
+    pub fn modulus(&self) -> &BigUint {
+        &self.config.modulus
+    }
+
+    fn range(&self) -> LimbRangeChip<F> {
+        LimbRangeChip::construct(self.config.range.clone())
+    }
+
+    fn assign_limbs(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &Value<BigUint>,
+    ) -> Result<[Number<F>; LIMBS], Error> {
+        let limbs = value.as_ref().map(|v| limbs_of(v, LIMBS));
+        let cells = (0..LIMBS)
+            .map(|i| {
+                let limb = limbs.as_ref().map(|limbs| BigUint::from(limbs[i]));
+                self.range().assign(layouter.namespace(|| "limb"), limb, 1)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(cells.try_into().unwrap())
+    }
+
+    /// Witness an element, which must be below `2^256`.
+    pub fn load(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<BigUint>,
+    ) -> Result<Integer<F>, Error> {
+        let limbs = self.assign_limbs(layouter, &value)?;
+        Ok(Integer { limbs, value })
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        c: &BigUint,
+    ) -> Result<Integer<F>, Error> {
+        let limbs = layouter.assign_region(
+            || "load constant",
+            |mut region| self.assign_constant(&mut region, 0, c),
+        )?;
+        Ok(Integer {
+            limbs,
+            value: Value::known(c.clone()),
+        })
+    }
+
+    fn assign_constant(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        c: &BigUint,
+    ) -> Result<[Number<F>; LIMBS], Error> {
+        let limbs = limbs_of(c, LIMBS);
+        let mut cells = vec![];
+        for (col, limb) in self.config.advice.iter().zip(limbs) {
+            let cell =
+                region.assign_advice_from_constant(|| "constant limb", *col, row, F::from(limb))?;
+            cells.push(Number(cell));
+        }
+        Ok(cells.try_into().unwrap())
+    }
+
+    /// Copy `x` into `row`, or the constant `default` if there is no `x`.
+    fn assign_operand(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        x: Option<&Integer<F>>,
+        default: u64,
+    ) -> Result<(), Error> {
+        match x {
+            Some(x) => {
+                for (limb, col) in x.limbs.iter().zip(self.config.advice) {
+                    limb.0.copy_advice(|| "operand limb", region, col, row)?;
+                }
+            }
+            None => {
+                self.assign_constant(region, row, &BigUint::from(default))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The shifted carries `u_k` of `a * b + c - d + 2p = q * p + r`.
+    fn carries(&self, [a, b, c, d, q, r]: [&BigUint; 6]) -> Vec<BigUint> {
+        let limbs = |x: &BigUint, n| {
+            limbs_of(x, n)
+                .into_iter()
+                .map(BigInt::from)
+                .collect::<Vec<_>>()
+        };
+        let (a, b, c, d, q, r) = (
+            limbs(a, LIMBS),
+            limbs(b, LIMBS),
+            limbs(c, LIMBS),
+            limbs(d, LIMBS),
+            limbs(q, LIMBS),
+            limbs(r, LIMBS),
+        );
+        let p = limbs(&self.config.modulus, LIMBS);
+        let two_p = limbs(&(&self.config.modulus * 2u32), LIMBS + 1);
+
+        let offset = BigInt::from(1) << CARRY_OFFSET_BITS;
+        let mut carry = BigInt::from(0);
+        let mut shifted = vec![];
+        for col in 0..CARRIES {
+            let mut e = two_p.get(col).cloned().unwrap_or_default() + carry;
+            for i in 0..LIMBS {
+                if let Some(j) = col.checked_sub(i).filter(|j| *j < LIMBS) {
+                    e += &a[i] * &b[j] - &q[i] * &p[j];
+                }
+            }
+            if col < LIMBS {
+                e += &c[col] - &d[col] - &r[col];
+            }
+            // Exact for an honest witness; otherwise the gate fails anyway.
+            carry = e >> LIMB_BITS;
+            shifted.push((&carry + &offset).to_biguint().unwrap_or_default());
+        }
+        shifted
+    }
+
+    /// `a * b + c - d mod p`, or, with `rem`, a check that it equals `rem`.
+    fn op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: Option<&Integer<F>>,
+        c: Option<&Integer<F>>,
+        d: Option<&Integer<F>>,
+        rem: Option<u64>,
+    ) -> Result<Integer<F>, Error> {
+        let p = &self.config.modulus;
+        let value = |x: Option<&Integer<F>>, default: u64| {
+            x.map_or(Value::known(BigUint::from(default)), |x| x.value.clone())
+        };
+        let operands = a
+            .value
+            .clone()
+            .zip(value(b, 1))
+            .zip(value(c, 0))
+            .zip(value(d, 0))
+            .map(|(((a, b), c), d)| [a, b, c, d]);
+        let lhs = operands
+            .as_ref()
+            .map(|[a, b, c, d]| a * b + c + p * 2u32 - d);
+        let q = lhs.as_ref().map(|lhs| lhs / p);
+        let r = match rem {
+            None => lhs.as_ref().map(|lhs| lhs % p),
+            Some(rem) => Value::known(BigUint::from(rem)),
+        };
+        let carries = operands
+            .zip(q.clone())
+            .zip(r.clone())
+            .map(|(([a, b, c, d], q), r)| self.carries([&a, &b, &c, &d, &q, &r]));
+
+        let q_limbs = self.assign_limbs(layouter.namespace(|| "q"), &q)?;
+        let r_limbs = match rem {
+            None => Some(self.assign_limbs(layouter.namespace(|| "r"), &r)?),
+            Some(_) => None,
+        };
+        let u = (0..CARRIES)
+            .map(|k| {
+                let carry = carries.as_ref().map(|carries| carries[k].clone());
+                self.range()
+                    .assign(layouter.namespace(|| "carry"), carry, CARRY_LIMBS)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let config = &self.config;
+        let limbs = layouter.assign_region(
+            || "a * b + c - d = q * p + r",
+            |mut region| {
+                config.s_op.enable(&mut region, 0)?;
+                self.assign_operand(&mut region, 0, Some(a), 0)?;
+                self.assign_operand(&mut region, 1, b, 1)?;
+                self.assign_operand(&mut region, 2, c, 0)?;
+                self.assign_operand(&mut region, 3, d, 0)?;
+                for (limb, col) in q_limbs.iter().zip(config.advice) {
+                    limb.0.copy_advice(|| "q", &mut region, col, 4)?;
+                }
+                let r = match (&r_limbs, rem) {
+                    (Some(r_limbs), _) => {
+                        let mut cells = vec![];
+                        for (limb, col) in r_limbs.iter().zip(config.advice) {
+                            cells.push(Number(limb.0.copy_advice(|| "r", &mut region, col, 5)?));
+                        }
+                        cells.try_into().unwrap()
+                    }
+                    (None, rem) => {
+                        self.assign_constant(&mut region, 5, &BigUint::from(rem.unwrap()))?
+                    }
+                };
+                for (k, u) in u.iter().enumerate() {
+                    let col = config.advice[k % LIMBS];
+                    u.0.copy_advice(|| "carry", &mut region, col, 6 + k / LIMBS)?;
+                }
+                Ok(r)
+            },
+        )?;
+        Ok(Integer { limbs, value: r })
+    }
+
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        self.op(layouter, a, Some(b), None, None, None)
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        self.op(layouter, a, None, Some(b), None, None)
+    }
+
+    pub fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        self.op(layouter, a, None, None, Some(b), None)
+    }
+
+    /// `a * b - d`.
+    pub fn mul_sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+        d: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        self.op(layouter, a, Some(b), None, Some(d), None)
+    }
+
+    /// `a = b mod p`.
+    pub fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+    ) -> Result<(), Error> {
+        self.op(layouter, a, None, None, Some(b), Some(0))
+            .map(|_| ())
+    }
+
+    /// `a * b + c = d mod p`, with no `c` meaning 0.
+    pub fn assert_mul_add_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        b: &Integer<F>,
+        c: Option<&Integer<F>>,
+        d: &Integer<F>,
+    ) -> Result<(), Error> {
+        self.op(layouter, a, Some(b), c, Some(d), Some(0))
+            .map(|_| ())
+    }
+
+    /// `a * inv = 1 mod p`, which also proves `a != 0`.
+    pub fn assert_inverse(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Integer<F>,
+        inv: &Integer<F>,
+    ) -> Result<(), Error> {
+        self.op(layouter, a, Some(inv), None, None, Some(1))
+            .map(|_| ())
+    }
+
+    /// Witness `a^-1 mod p` and check it.
+    pub fn invert(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Integer<F>,
+    ) -> Result<Integer<F>, Error> {
+        let p = self.modulus();
+        let inv = a.value.as_ref().map(|a| a.modpow(&(p - 2u32), p));
+        let inv = self.load(layouter.namespace(|| "a^-1"), inv)?;
+        self.assert_inverse(layouter.namespace(|| "a * a^-1 = 1"), a, &inv)?;
+        Ok(inv)
+    }
+
+    /// `x <= bound` as integers; with `bound = p - 1` this makes `x`
+    /// canonical.
+    pub fn assert_le(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &Integer<F>,
+        bound: &BigUint,
+    ) -> Result<(), Error> {
+        let slack = x.value.as_ref().map(|x| {
+            if x <= bound {
+                bound - x
+            } else {
+                BigUint::default()
+            }
+        });
+        let slack_limbs = self.assign_limbs(layouter.namespace(|| "slack"), &slack)?;
+        let bound_limbs = limbs_of(bound, LIMBS);
+        let carries = x.value.as_ref().zip(slack.as_ref()).map(|(x, slack)| {
+            let (x, slack) = (limbs_of(x, LIMBS), limbs_of(slack, LIMBS));
+            let mut carry = 0u128;
+            let mut carries = vec![];
+            for (x, slack) in x.iter().zip(&slack).take(LIMBS - 1) {
+                carry = (*x as u128 + *slack as u128 + carry) >> LIMB_BITS;
+                carries.push(F::from(carry as u64));
+            }
+            carries
+        });
+
+        let config = &self.config;
+        layouter.assign_region(
+            || "x + slack = bound",
+            |mut region| {
+                config.s_le.enable(&mut region, 0)?;
+                self.assign_operand(&mut region, 0, Some(x), 0)?;
+                for (limb, col) in slack_limbs.iter().zip(config.advice) {
+                    limb.0.copy_advice(|| "slack", &mut region, col, 1)?;
+                }
+                for (limb, col) in bound_limbs.iter().zip(config.advice) {
+                    region.assign_advice_from_constant(|| "bound", col, 2, F::from(*limb))?;
+                }
+                for (k, col) in config.advice.iter().enumerate().take(LIMBS - 1) {
+                    let carry = carries.as_ref().map(|carries| carries[k]);
+                    region.assign_advice(|| "carry", *col, 3, || carry)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, pasta::Fp};
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        range: LimbRangeConfig,
+        field: FieldConfig,
+    }
+
+    /// Checks `a * b`, `a + b` and `a - b` mod secp256k1's `p` against the
+    /// expected values, `a * b` once more against `forged` if there is one,
+    /// and that `loose` is canonical.
+    #[derive(Default)]
+    struct MyCircuit {
+        a: Value<BigUint>,
+        b: Value<BigUint>,
+        expected: [BigUint; 3],
+        forged: Option<BigUint>,
+        loose: Option<BigUint>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                expected: self.expected.clone(),
+                forged: self.forged.clone(),
+                loose: self.loose.clone(),
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 9].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let range = LimbRangeChip::configure(meta, advice);
+            let field = FieldChip::configure(
+                meta,
+                advice[..4].try_into().unwrap(),
+                constant,
+                range.clone(),
+                secp256k1_p(),
+            );
+            TestConfig { range, field }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            LimbRangeChip::construct(config.range).load_table(layouter.namespace(|| "table"))?;
+            let chip = FieldChip::construct(config.field);
+            let p = chip.modulus().clone();
+            let a = chip.load(layouter.namespace(|| "a"), self.a.clone())?;
+            let b = chip.load(layouter.namespace(|| "b"), self.b.clone())?;
+
+            let results = [
+                chip.mul(layouter.namespace(|| "a * b"), &a, &b)?,
+                chip.add(layouter.namespace(|| "a + b"), &a, &b)?,
+                chip.sub(layouter.namespace(|| "a - b"), &a, &b)?,
+            ];
+            for (result, expected) in results.iter().zip(&self.expected) {
+                let expected = chip.load_constant(layouter.namespace(|| "expected"), expected)?;
+                chip.assert_le(layouter.namespace(|| "canonical"), result, &(&p - 1u32))?;
+                chip.assert_equal(layouter.namespace(|| "result"), result, &expected)?;
+            }
+            if let Some(forged) = &self.forged {
+                let forged = chip.load_constant(layouter.namespace(|| "forged"), forged)?;
+                chip.assert_mul_add_equal(layouter.namespace(|| "forged"), &a, &b, None, &forged)?;
+            }
+            if let Some(loose) = &self.loose {
+                let loose =
+                    chip.load(layouter.namespace(|| "loose"), Value::known(loose.clone()))?;
+                chip.assert_le(layouter.namespace(|| "canonical"), &loose, &(&p - 1u32))?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(a: &BigUint, b: &BigUint) -> MyCircuit {
+        let p = secp256k1_p();
+        MyCircuit {
+            a: Value::known(a.clone()),
+            b: Value::known(b.clone()),
+            expected: [a * b % &p, (a + b) % &p, (a + &p - b) % &p],
+            forged: None,
+            loose: None,
+        }
+    }
+
+    const K: u32 = 11;
+
+    #[test]
+    fn test_field_ops() {
+        let p = secp256k1_p();
+        let a = &p - 12345u32;
+        let b = BigUint::parse_bytes(
+            b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            16,
+        )
+        .unwrap();
+        for (a, b) in [
+            (&a, &b),
+            (&b, &a),
+            (&b, &b),
+            (&BigUint::from(7u32), &(&p - 1u32)),
+        ] {
+            let prover = MockProver::run(K, &circuit(a, b), vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_field_ops_wrong() {
+        let p = secp256k1_p();
+        let (a, b) = (&p - 12345u32, BigUint::from(3u32));
+        let mut wrong = circuit(&a, &b);
+        wrong.expected[0] += 1u32;
+        let prover = MockProver::run(K, &wrong, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The same product through the fused check.
+        let mut forged = circuit(&a, &b);
+        forged.forged = Some(&a * &b % &p + 1u32);
+        let prover = MockProver::run(K, &forged, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_field_not_canonical() {
+        let p = secp256k1_p();
+        let one = BigUint::from(1u32);
+
+        // p + 1 fits in the limbs and works as an operand: it is 1 mod p.
+        let mut loose = circuit(&one, &one);
+        loose.a = Value::known(&p + 1u32);
+        MockProver::run(K, &loose, vec![])
+            .unwrap()
+            .assert_satisfied();
+
+        // But it is not canonical, while p - 1 is.
+        let mut canonical = circuit(&one, &one);
+        canonical.loose = Some(&p - 1u32);
+        MockProver::run(K, &canonical, vec![])
+            .unwrap()
+            .assert_satisfied();
+        canonical.loose = Some(&p + 1u32);
+        let prover = MockProver::run(K, &canonical, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
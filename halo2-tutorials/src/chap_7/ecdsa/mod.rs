@@ -0,0 +1,8 @@
+/// chap7: ECDSA over secp256k1
+/// Verify a secp256k1 signature with both of its fields emulated over Pasta:
+/// `field` does arithmetic mod a 256-bit prime in 64-bit limbs, `curve`
+/// builds the group law on it, and `ecdsa` puts the verification together.
+mod curve;
+#[allow(clippy::module_inception)]
+mod ecdsa;
+mod field;
@@ -0,0 +1,614 @@
+/// chap7: EdDSA over an embedded curve
+/// The ECDSA exercise emulates secp256k1's fields in 64-bit limbs, and every
+/// field operation costs a dozen rows. A curve defined over the circuit's own
+/// field costs one constraint per field operation instead. Jubjub plays that
+/// role for BLS12-381's scalar field; here it is a twisted Edwards curve over
+/// the Pallas base field,
+///
+///   a * x^2 + y^2 = 1 + d * x^2 * y^2,    a = -1,  d = 5
+///
+/// Its addition law
+///
+///   x3 = (x1 y2 + y1 x2) / (1 + d x1 x2 y1 y2)
+///   y3 = (y1 y2 - a x1 x2) / (1 - d x1 x2 y1 y2)
+///
+/// is complete when `a` is a square and `d` is not, which holds since
+/// `p = 1 mod 4` and 5 generates the multiplicative group: the denominators
+/// never vanish and doubling and the identity `(0, 1)` need no special case.
+/// The gates check the formulas multiplied out, two constraints of degree 5.
+///
+/// A signature on `M` under `A = [sk]B` is `(R, s)` with
+///
+///   R = [r]B,   h = Poseidon(R.x, R.y, A.x, A.y, M),   s = r + h * sk
+///
+/// and verification is `[s]B = R + [h]A`. Ed25519 reduces `s` modulo the
+/// order of `B`; that needs the curve's order, which takes point counting
+/// to find, so this signer keeps `s` as an integer instead, as in the
+/// Girault-Poupard-Stern scheme. The equation holds in any group, and `r`
+/// is drawn from over 120 bits more than `h * sk` so that `s` hides the key.
+///
+/// `A` and `M` are public, `(R, s)` private. `A` and `R` must be on the
+/// curve, and `A.x` must be invertible: the identity `(0, 1)` is the key of
+/// `sk = 0`, and with it `[s]B = R` for any message and `s = r`, so anyone
+/// could sign. `(0, -1)`, of order 2, is rejected with it. The challenge is
+/// decomposed into 255 bits without a canonicity check, so a prover may
+/// also use `h + p` when it fits; that is as hard to exploit as `h` itself.
+///
+/// One row per bit of a scalar multiplication, double and add, MSB first:
+///
+/// | ax  | ay  | dx | dy | qx | qy | b   | k  | px | py | q_ladder |
+/// |-----|-----|----|----|----|----|-----|----|----|----|----------|
+/// | 0   | 1   | D  | D  | Q  | Q  | b_0 | 0  | P  | P  |    1     |
+/// | acc | acc | D  | D  | Q  | Q  | b_1 | k  | P  | P  |    1     |
+/// | ...                                                           |
+/// | out | out |    |    |    |    |     | k  | P  | P  |    0     |
+///
+///   D = acc + acc,  Q = b ? P : (0, 1),  acc' = D + Q,  k' = 2k + b
+///
+/// `q_add` adds `(ax, ay) + (dx, dy) = (qx, qy)` on one row, and `q_on_curve`
+/// checks `(ax, ay)`. Poseidon shares the first four columns.
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::{group::ff::PrimeField, pallas},
+    plonk::*,
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+
+use crate::gadgets::{
+    inverse::{InverseChip, InverseConfig},
+    poseidon::configure_pow5,
+    Number,
+};
+
+type Fp = pallas::Base;
+
+/// The curve's `d`; `a` is -1.
+pub const D: u64 = 5;
+/// Bits of the challenge `h`.
+pub const H_BITS: usize = 255;
+/// Bits of `s = r + h * sk`.
+pub const S_BITS: usize = 640;
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+
+/// Add two points natively.
+pub fn edwards_add<F: PrimeField>(p: [F; 2], q: [F; 2]) -> [F; 2] {
+    let t = F::from(D) * p[0] * q[0] * p[1] * q[1];
+    let x = (p[0] * q[1] + p[1] * q[0]) * (F::ONE + t).invert().unwrap();
+    let y = (p[1] * q[1] + p[0] * q[0]) * (F::ONE - t).invert().unwrap();
+    [x, y]
+}
+
+/// `[k]p` natively, for the bits of `k` MSB first.
+pub fn edwards_mul<F: PrimeField>(p: [F; 2], bits: &[bool]) -> [F; 2] {
+    bits.iter().fold([F::ZERO, F::ONE], |acc, b| {
+        let acc = edwards_add(acc, acc);
+        if *b {
+            edwards_add(acc, p)
+        } else {
+            acc
+        }
+    })
+}
+
+/// The point with the smallest `x = 1, 2, ...`. Its order is not known,
+/// and is never needed.
+pub fn base_point() -> [Fp; 2] {
+    let d = Fp::from(D);
+    (1u64..)
+        .find_map(|x| {
+            let x = Fp::from(x);
+            let y2 = (Fp::ONE + x.square()) * (Fp::ONE - d * x.square()).invert().unwrap();
+            Option::from(y2.sqrt()).map(|y| [x, y])
+        })
+        .unwrap()
+}
+
+/// h = Poseidon(R.x, R.y, A.x, A.y, M)
+pub fn challenge(r: [Fp; 2], a: [Fp; 2], m: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<5>, WIDTH, RATE>::init()
+        .hash([r[0], r[1], a[0], a[1], m])
+}
+
+/// The low `n` bits of `x`, MSB first.
+pub fn bits_be(x: &BigUint, n: usize) -> Vec<bool> {
+    (0..n as u64).rev().map(|i| x.bit(i)).collect()
+}
+
+/// `x` as an integer.
+pub fn to_biguint(x: Fp) -> BigUint {
+    BigUint::from_bytes_le(x.to_repr().as_ref())
+}
+
+#[derive(Debug, Clone)]
+pub struct EcPoint<F: PrimeField> {
+    pub x: Number<F>,
+    pub y: Number<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EdwardsConfig {
+    pub advice: [Column<Advice>; 10],
+    q_ladder: Selector,
+    q_add: Selector,
+    q_on_curve: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct EdwardsChip<F: PrimeField> {
+    config: EdwardsConfig,
+    _marker: PhantomData<F>,
+}
+
+/// `p1 + p2 = p3`, multiplied out.
+fn add_constraints<F: PrimeField>(
+    p1: [Expression<F>; 2],
+    p2: [Expression<F>; 2],
+    p3: [Expression<F>; 2],
+) -> [Expression<F>; 2] {
+    let [x1, y1] = p1;
+    let [x2, y2] = p2;
+    let [x3, y3] = p3;
+    let one = Expression::Constant(F::ONE);
+    let t = Expression::Constant(F::from(D)) * x1.clone() * x2.clone() * y1.clone() * y2.clone();
+    [
+        x3 * (one.clone() + t.clone()) - (x1.clone() * y2.clone() + y1.clone() * x2.clone()),
+        y3 * (one - t) - (y1 * y2 + x1 * x2),
+    ]
+}
+
+impl<F: PrimeField> EdwardsChip<F> {
+    pub fn construct(config: EdwardsConfig) -> Self {
+        EdwardsChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 10],
+        constant: Column<Fixed>,
+    ) -> EdwardsConfig {
+        meta.enable_constant(constant);
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let q_ladder = meta.selector();
+        let q_add = meta.selector();
+        let q_on_curve = meta.selector();
+        let [ax, ay, dx, dy, qx, qy, b, k, px, py] = advice;
+
+        meta.create_gate("double and add", |meta| {
+            let q = meta.query_selector(q_ladder);
+            let cur = |meta: &mut VirtualCells<F>, col| meta.query_advice(col, Rotation::cur());
+            let next = |meta: &mut VirtualCells<F>, col| meta.query_advice(col, Rotation::next());
+            let acc = [cur(meta, ax), cur(meta, ay)];
+            let double = [cur(meta, dx), cur(meta, dy)];
+            let sel = [cur(meta, qx), cur(meta, qy)];
+            let acc_next = [next(meta, ax), next(meta, ay)];
+            let p = [cur(meta, px), cur(meta, py)];
+            let p_next = [next(meta, px), next(meta, py)];
+            let bit = cur(meta, b);
+            let (k_cur, k_next) = (cur(meta, k), next(meta, k));
+            let one = Expression::Constant(F::ONE);
+
+            let [double_x, double_y] = add_constraints(acc.clone(), acc, double.clone());
+            let [add_x, add_y] = add_constraints(double, sel.clone(), acc_next);
+            let [sel_x, sel_y] = sel;
+            let [p_x, p_y] = p;
+            let [p_next_x, p_next_y] = p_next;
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("bool", bit.clone() * (one.clone() - bit.clone())),
+                    ("double x", double_x),
+                    ("double y", double_y),
+                    ("select x", sel_x - bit.clone() * p_x.clone()),
+                    (
+                        "select y",
+                        sel_y - bit.clone() * p_y.clone() - (one - bit.clone()),
+                    ),
+                    ("add x", add_x),
+                    ("add y", add_y),
+                    ("k", k_next - k_cur * Expression::Constant(F::from(2)) - bit),
+                    ("P x", p_next_x - p_x),
+                    ("P y", p_next_y - p_y),
+                ],
+            )
+        });
+
+        meta.create_gate("add", |meta| {
+            let q = meta.query_selector(q_add);
+            let [ax, ay, dx, dy, qx, qy] =
+                [ax, ay, dx, dy, qx, qy].map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(q, add_constraints([ax, ay], [dx, dy], [qx, qy]))
+        });
+
+        meta.create_gate("on curve", |meta| {
+            let q = meta.query_selector(q_on_curve);
+            let x = meta.query_advice(ax, Rotation::cur());
+            let y = meta.query_advice(ay, Rotation::cur());
+            let (x2, y2) = (x.clone() * x, y.clone() * y);
+            let one = Expression::Constant(F::ONE);
+            let d = Expression::Constant(F::from(D));
+            Constraints::with_selector(q, vec![y2.clone() - x2.clone() - one - d * x2 * y2])
+        });
+
+        EdwardsConfig {
+            advice,
+            q_ladder,
+            q_add,
+            q_on_curve,
+        }
+    }
+
+    /// Assign a fixed point, such as the base point.
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: [F; 2],
+    ) -> Result<EcPoint<F>, Error> {
+        let [ax, ay, ..] = self.config.advice;
+        layouter.assign_region(
+            || "load constant point",
+            |mut region| {
+                let x = region.assign_advice_from_constant(|| "x", ax, 0, p[0])?;
+                let y = region.assign_advice_from_constant(|| "y", ay, 0, p[1])?;
+                Ok(EcPoint {
+                    x: Number(x),
+                    y: Number(y),
+                })
+            },
+        )
+    }
+
+    /// Witness a point and check that it is on the curve.
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: Value<[F; 2]>,
+    ) -> Result<EcPoint<F>, Error> {
+        let [ax, ay, ..] = self.config.advice;
+        layouter.assign_region(
+            || "witness point",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                let x = region.assign_advice(|| "x", ax, 0, || p.map(|p| p[0]))?;
+                let y = region.assign_advice(|| "y", ay, 0, || p.map(|p| p[1]))?;
+                Ok(EcPoint {
+                    x: Number(x),
+                    y: Number(y),
+                })
+            },
+        )
+    }
+
+    /// Copy a point from `instance` at `row` and `row + 1`, and check that
+    /// it is on the curve.
+    pub fn load_instance(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<EcPoint<F>, Error> {
+        let [ax, ay, ..] = self.config.advice;
+        layouter.assign_region(
+            || "load instance point",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                let x = region.assign_advice_from_instance(|| "x", instance, row, ax, 0)?;
+                let y = region.assign_advice_from_instance(|| "y", instance, row + 1, ay, 0)?;
+                Ok(EcPoint {
+                    x: Number(x),
+                    y: Number(y),
+                })
+            },
+        )
+    }
+
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EcPoint<F>,
+        q: &EcPoint<F>,
+    ) -> Result<EcPoint<F>, Error> {
+        let [ax, ay, dx, dy, qx, qy, ..] = self.config.advice;
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                let p = [
+                    p.x.0.copy_advice(|| "x1", &mut region, ax, 0)?,
+                    p.y.0.copy_advice(|| "y1", &mut region, ay, 0)?,
+                ];
+                let q = [
+                    q.x.0.copy_advice(|| "x2", &mut region, dx, 0)?,
+                    q.y.0.copy_advice(|| "y2", &mut region, dy, 0)?,
+                ];
+                let sum = point_value(&p[0], &p[1])
+                    .zip(point_value(&q[0], &q[1]))
+                    .map(|(p, q)| edwards_add(p, q));
+                let x = region.assign_advice(|| "x3", qx, 0, || sum.map(|s| s[0]))?;
+                let y = region.assign_advice(|| "y3", qy, 0, || sum.map(|s| s[1]))?;
+                Ok(EcPoint {
+                    x: Number(x),
+                    y: Number(y),
+                })
+            },
+        )
+    }
+
+    /// `[k]p` for the `n` bits of `k`, MSB first. Also returns `k` as a field
+    /// element, for the caller to tie to a cell of its own.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EcPoint<F>,
+        bits: Value<Vec<bool>>,
+        n: usize,
+    ) -> Result<(EcPoint<F>, Number<F>), Error> {
+        let config = &self.config;
+        let [ax, ay, dx, dy, qx, qy, b_col, k_col, px, py] = config.advice;
+        layouter.assign_region(
+            || "scalar mul",
+            |mut region| {
+                let p_value = point_value(&p.x.0, &p.y.0);
+                let mut acc = Value::known([F::ZERO, F::ONE]);
+                let mut k = Value::known(F::ZERO);
+                for i in 0..n {
+                    config.q_ladder.enable(&mut region, i)?;
+                    if i == 0 {
+                        region.assign_advice_from_constant(|| "acc x", ax, 0, F::ZERO)?;
+                        region.assign_advice_from_constant(|| "acc y", ay, 0, F::ONE)?;
+                        region.assign_advice_from_constant(|| "k", k_col, 0, F::ZERO)?;
+                        p.x.0.copy_advice(|| "px", &mut region, px, 0)?;
+                        p.y.0.copy_advice(|| "py", &mut region, py, 0)?;
+                    } else {
+                        region.assign_advice(|| "acc x", ax, i, || acc.map(|a| a[0]))?;
+                        region.assign_advice(|| "acc y", ay, i, || acc.map(|a| a[1]))?;
+                        region.assign_advice(|| "k", k_col, i, || k)?;
+                        region.assign_advice(|| "px", px, i, || p_value.map(|p| p[0]))?;
+                        region.assign_advice(|| "py", py, i, || p_value.map(|p| p[1]))?;
+                    }
+                    let bit = bits.as_ref().map(|bits| bits[i]);
+                    let b = bit.map(|b| F::from(b as u64));
+                    region.assign_advice(|| "b", b_col, i, || b)?;
+                    let double = acc.map(|a| edwards_add(a, a));
+                    region.assign_advice(|| "double x", dx, i, || double.map(|d| d[0]))?;
+                    region.assign_advice(|| "double y", dy, i, || double.map(|d| d[1]))?;
+                    let sel = bit
+                        .zip(p_value)
+                        .map(|(b, p)| if b { p } else { [F::ZERO, F::ONE] });
+                    region.assign_advice(|| "select x", qx, i, || sel.map(|s| s[0]))?;
+                    region.assign_advice(|| "select y", qy, i, || sel.map(|s| s[1]))?;
+                    acc = double.zip(sel).map(|(d, s)| edwards_add(d, s));
+                    k = k * Value::known(F::from(2)) + b;
+                }
+                let x = region.assign_advice(|| "out x", ax, n, || acc.map(|a| a[0]))?;
+                let y = region.assign_advice(|| "out y", ay, n, || acc.map(|a| a[1]))?;
+                let k = region.assign_advice(|| "k", k_col, n, || k)?;
+                region.assign_advice(|| "px", px, n, || p_value.map(|p| p[0]))?;
+                region.assign_advice(|| "py", py, n, || p_value.map(|p| p[1]))?;
+                Ok((
+                    EcPoint {
+                        x: Number(x),
+                        y: Number(y),
+                    },
+                    Number(k),
+                ))
+            },
+        )
+    }
+
+    pub fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EcPoint<F>,
+        q: &EcPoint<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert equal",
+            |mut region| {
+                region.constrain_equal(p.x.0.cell(), q.x.0.cell())?;
+                region.constrain_equal(p.y.0.cell(), q.y.0.cell())
+            },
+        )
+    }
+}
+
+fn point_value<F: PrimeField>(x: &AssignedCell<F, F>, y: &AssignedCell<F, F>) -> Value<[F; 2]> {
+    x.value().zip(y.value()).map(|(x, y)| [*x, *y])
+}
+
+#[derive(Debug, Clone)]
+pub struct EddsaConfig {
+    edwards: EdwardsConfig,
+    inverse: InverseConfig,
+    poseidon: Pow5Config<Fp, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+/// Verify `(R, s)` on the public `[A.x, A.y, M]`.
+#[derive(Default)]
+pub struct EddsaCircuit {
+    pub r: Value<[Fp; 2]>,
+    pub s: Value<BigUint>,
+}
+
+impl Circuit<Fp> for EddsaCircuit {
+    type Config = EddsaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [(); 10].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        // The ladder's start shares Poseidon's constant column.
+        let state = [advice[0], advice[1], advice[2]];
+        let (poseidon, constant) = configure_pow5(meta, state, advice[3]);
+        let edwards = EdwardsChip::configure(meta, advice, constant);
+        let inverse = InverseChip::configure(meta, [advice[0], advice[1]]);
+
+        EddsaConfig {
+            edwards,
+            inverse,
+            poseidon,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = EdwardsChip::construct(config.edwards.clone());
+        let b = chip.load_constant(layouter.namespace(|| "B"), base_point())?;
+        let a = chip.load_instance(layouter.namespace(|| "A"), config.instance, 0)?;
+        let m = layouter.assign_region(
+            || "load M",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "M",
+                    config.instance,
+                    2,
+                    config.edwards.advice[0],
+                    0,
+                )
+            },
+        )?;
+        let r = chip.witness(layouter.namespace(|| "R"), self.r)?;
+
+        // The identity and (0, -1) have no inverse of x.
+        InverseChip::construct(config.inverse)
+            .invert(layouter.namespace(|| "A.x^-1"), a.x.clone())?;
+
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<5>, WIDTH, RATE>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init"),
+        )?;
+        let h = hasher.hash(
+            layouter.namespace(|| "h"),
+            [
+                r.x.0.clone(),
+                r.y.0.clone(),
+                a.x.0.clone(),
+                a.y.0.clone(),
+                m,
+            ],
+        )?;
+
+        let h_bits = h.value().map(|h| bits_be(&to_biguint(*h), H_BITS));
+        let (ha, k) = chip.mul(layouter.namespace(|| "[h]A"), &a, h_bits, H_BITS)?;
+        layouter.assign_region(
+            || "k = h",
+            |mut region| region.constrain_equal(k.0.cell(), h.cell()),
+        )?;
+        let rhs = chip.add(layouter.namespace(|| "R + [h]A"), &r, &ha)?;
+
+        let s_bits = self.s.as_ref().map(|s| bits_be(s, S_BITS));
+        let (sb, _) = chip.mul(layouter.namespace(|| "[s]B"), &b, s_bits, S_BITS)?;
+        chip.assert_equal(layouter.namespace(|| "[s]B = R + [h]A"), &sb, &rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+    use rand_core::{OsRng, RngCore};
+
+    const K: u32 = 11;
+
+    fn random_biguint(bits: usize) -> BigUint {
+        let mut bytes = vec![0u8; bits / 8];
+        OsRng.fill_bytes(&mut bytes);
+        BigUint::from_bytes_le(&bytes)
+    }
+
+    /// The public key of `sk`.
+    fn public_key(sk: &BigUint) -> [Fp; 2] {
+        edwards_mul(base_point(), &bits_be(sk, 256))
+    }
+
+    /// An off-circuit signer: `(R, s)` for `sk` on `m`.
+    fn sign(sk: &BigUint, m: Fp) -> ([Fp; 2], BigUint) {
+        let r = random_biguint(S_BITS - 8);
+        let big_r = edwards_mul(base_point(), &bits_be(&r, S_BITS));
+        let h = challenge(big_r, public_key(sk), m);
+        (big_r, r + to_biguint(h) * sk)
+    }
+
+    fn verify(a: [Fp; 2], m: Fp, r: [Fp; 2], s: BigUint) -> bool {
+        let circuit = EddsaCircuit {
+            r: Value::known(r),
+            s: Value::known(s),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![a[0], a[1], m]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_curve() {
+        // a = -1 is a square and d is not: the addition law is complete.
+        assert!(bool::from((-Fp::ONE).sqrt().is_some()));
+        assert!(bool::from(Fp::from(D).sqrt().is_none()));
+        let b = base_point();
+        let identity = [Fp::ZERO, Fp::ONE];
+        assert_eq!(edwards_add(b, identity), b);
+        assert_eq!(edwards_add(b, [-b[0], b[1]]), identity);
+    }
+
+    #[test]
+    fn test_eddsa() {
+        let sk = random_biguint(256);
+        let a = public_key(&sk);
+        let m = Fp::from(42);
+        let (r, s) = sign(&sk, m);
+        let h = challenge(r, a, m);
+        assert_eq!(
+            edwards_mul(base_point(), &bits_be(&s, S_BITS)),
+            edwards_add(r, edwards_mul(a, &bits_be(&to_biguint(h), H_BITS)))
+        );
+
+        assert!(verify(a, m, r, s.clone()));
+        // Not a signature on another message.
+        assert!(!verify(a, Fp::from(43), r, s));
+    }
+
+    #[test]
+    fn test_eddsa_tampered_r() {
+        let sk = random_biguint(256);
+        let a = public_key(&sk);
+        let m = Fp::from(42);
+        let (r, s) = sign(&sk, m);
+        let tampered = edwards_add(r, base_point());
+        assert!(!verify(a, m, tampered, s));
+    }
+
+    #[test]
+    fn test_eddsa_identity_key() {
+        // sk = 0: A is the identity and s = r signs every message.
+        let sk = BigUint::from(0u64);
+        let a = public_key(&sk);
+        assert_eq!(a, [Fp::ZERO, Fp::ONE]);
+        let m = Fp::from(42);
+        let (r, s) = sign(&sk, m);
+        assert_eq!(edwards_mul(base_point(), &bits_be(&s, S_BITS)), r);
+        assert!(!verify(a, m, r, s));
+    }
+}
@@ -0,0 +1,471 @@
+/// chap7: one round of Keccak-f[1600]
+/// The Keccak state is 25 lanes of 64 bits, lane `(x, y)` at index
+/// `x + 5y`, bit `z` of a lane being `(lane >> z) & 1`. A round is
+///
+///   θ:  C[x] = A[x,0] ^ .. ^ A[x,4],    D[x] = C[x-1] ^ rot(C[x+1], 1),
+///       A[x,y] ^= D[x]
+///   ρπ: B[y, 2x+3y] = rot(A[x,y], r[x,y])
+///   χ:  A[x,y] = B[x,y] ^ (!B[x+1,y] & B[x+2,y])
+///   ι:  A[0,0] ^= RC[round]
+///
+/// Here every bit is a cell. That makes ρ and π free: a rotation or a move
+/// of a lane only changes which cell the next step copies, and costs no
+/// rows at all. XOR is one row, `out = a + b - 2ab`, and χ with ι one more,
+/// the round constant sitting in a fixed column so that ι only touches the
+/// rows of lane `(0, 0)` where it has a 1:
+///
+///   chi = a + (1 - b) * c - 2a * (1 - b) * c,    out = chi ^ rc
+///
+/// The cost, for 1600 state bits:
+///
+///   load + bit checks   400 rows  (4 bits a row)
+///   θ: C                1280      (4 XORs per bit of C)
+///   θ: D                 320
+///   θ: A ^ D            1600
+///   χ and ι             1600
+///                      ~5200 rows, so k = 13
+///
+/// A packed representation would put several bits per cell and do θ and χ
+/// with lookups on the packed chunks, with rotations as re-splitting; it
+/// needs far fewer rows but a table and much more machinery. Bits keep each
+/// step readable. The input state is private and the 1600 output bits are
+/// public, lane by lane, bit 0 first.
+///
+/// | a0 | a1 | a2 | a3  | rc | s_bits | s_xor | s_chi |
+/// |----|----|----|-----|----|--------|-------|-------|
+/// | b  | b  | b  | b   |    |   1    |   0   |   0   |   load
+/// | a  | b  |    | a^b |    |   0    |   1   |   0   |   θ
+/// | a  | b  | c  | out | rc |   0    |   0   |   1   |   χ, ι
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::Number;
+
+/// The rotation offsets of ρ, by lane.
+const RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// The round constants of ι.
+const RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Where π moves lane `(x, y)`: to `(y, 2x + 3y)`.
+fn pi(lane: usize) -> usize {
+    let (x, y) = (lane % 5, lane / 5);
+    y + 5 * ((2 * x + 3 * y) % 5)
+}
+
+/// One round of Keccak-f[1600], natively.
+pub fn keccak_round(a: &mut [u64; 25], round: usize) {
+    let c: [u64; 5] = std::array::from_fn(|x| (0..5).fold(0, |c, y| c ^ a[x + 5 * y]));
+    for (lane, v) in a.iter_mut().enumerate() {
+        let x = lane % 5;
+        *v ^= c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+    }
+    let mut b = [0; 25];
+    for (lane, v) in a.iter().enumerate() {
+        b[pi(lane)] = v.rotate_left(RHO[lane]);
+    }
+    for (lane, v) in a.iter_mut().enumerate() {
+        let (x, y) = (lane % 5, lane / 5);
+        *v = b[lane] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+    }
+    a[0] ^= RC[round];
+}
+
+/// The state as bit cells, `[lane][z]`.
+pub type State<F> = Vec<Vec<Number<F>>>;
+
+#[derive(Debug, Clone)]
+pub struct KeccakRoundConfig {
+    advice: [Column<Advice>; 4],
+    rc: Column<Fixed>,
+    s_bits: Selector,
+    s_xor: Selector,
+    s_chi: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct KeccakRoundChip<F: Field> {
+    config: KeccakRoundConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> KeccakRoundChip<F> {
+    pub fn construct(config: KeccakRoundConfig) -> Self {
+        KeccakRoundChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+    ) -> KeccakRoundConfig {
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let rc = meta.fixed_column();
+        let s_bits = meta.selector();
+        let s_xor = meta.selector();
+        let s_chi = meta.selector();
+        let one = Expression::Constant(F::ONE);
+        let two = Expression::Constant(F::from(2));
+
+        meta.create_gate("bits", |meta| {
+            let s = meta.query_selector(s_bits);
+            let bits = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(
+                s,
+                bits.into_iter()
+                    .map(|b| b.clone() * (one.clone() - b))
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        meta.create_gate("xor", |meta| {
+            let s = meta.query_selector(s_xor);
+            let [a, b, _, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(s, vec![a.clone() + b.clone() - two.clone() * a * b - out])
+        });
+
+        meta.create_gate("chi ^ rc", |meta| {
+            let s = meta.query_selector(s_chi);
+            let [a, b, c, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            let rc = meta.query_fixed(rc, Rotation::cur());
+            let and = (one.clone() - b) * c;
+            let chi = a.clone() + and.clone() - two.clone() * a * and;
+            Constraints::with_selector(
+                s,
+                vec![chi.clone() + rc.clone() - two.clone() * chi * rc - out],
+            )
+        });
+
+        KeccakRoundConfig {
+            advice,
+            rc,
+            s_bits,
+            s_xor,
+            s_chi,
+        }
+    }
+
+    /// Witness the 25 lanes as bits, checking each is a bit.
+    pub fn load_state(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Value<[u64; 25]>,
+    ) -> Result<State<F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "load state",
+            |mut region| {
+                let mut bits = Vec::with_capacity(1600);
+                for i in 0..1600 {
+                    let (row, col) = (i / 4, i % 4);
+                    if col == 0 {
+                        config.s_bits.enable(&mut region, row)?;
+                    }
+                    let bit = state.map(|s| F::from((s[i / 64] >> (i % 64)) & 1));
+                    let cell = region.assign_advice(|| "bit", config.advice[col], row, || bit)?;
+                    bits.push(Number(cell));
+                }
+                Ok(bits.chunks(64).map(|lane| lane.to_vec()).collect())
+            },
+        )
+    }
+
+    fn xor(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        a: &Number<F>,
+        b: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [a_col, b_col, _, out_col] = self.config.advice;
+        self.config.s_xor.enable(region, row)?;
+        let a = a.0.copy_advice(|| "a", region, a_col, row)?;
+        let b = b.0.copy_advice(|| "b", region, b_col, row)?;
+        let out = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| *a + *b - F::from(2) * *a * *b);
+        region
+            .assign_advice(|| "a ^ b", out_col, row, || out)
+            .map(Number)
+    }
+
+    /// θ: every bit XORed with the parities of two neighbouring columns.
+    pub fn theta(&self, mut layouter: impl Layouter<F>, a: &State<F>) -> Result<State<F>, Error> {
+        layouter.assign_region(
+            || "theta",
+            |mut region| {
+                let mut row = 0;
+                let mut c = Vec::with_capacity(5);
+                for x in 0..5 {
+                    let mut lane = Vec::with_capacity(64);
+                    for z in 0..64 {
+                        let mut parity = a[x][z].clone();
+                        for y in 1..5 {
+                            parity = self.xor(&mut region, row, &parity, &a[x + 5 * y][z])?;
+                            row += 1;
+                        }
+                        lane.push(parity);
+                    }
+                    c.push(lane);
+                }
+
+                let mut d = Vec::with_capacity(5);
+                for x in 0..5 {
+                    let mut lane = Vec::with_capacity(64);
+                    for z in 0..64 {
+                        // Bit z of rot(v, 1) is bit z - 1 of v.
+                        let rotated = &c[(x + 1) % 5][(z + 63) % 64];
+                        lane.push(self.xor(&mut region, row, &c[(x + 4) % 5][z], rotated)?);
+                        row += 1;
+                    }
+                    d.push(lane);
+                }
+
+                let mut out = Vec::with_capacity(25);
+                for (i, lane) in a.iter().enumerate() {
+                    let mut bits = Vec::with_capacity(64);
+                    for (z, bit) in lane.iter().enumerate() {
+                        bits.push(self.xor(&mut region, row, bit, &d[i % 5][z])?);
+                        row += 1;
+                    }
+                    out.push(bits);
+                }
+                Ok(out)
+            },
+        )
+    }
+
+    /// ρ and π: only a renaming of cells.
+    pub fn rho_pi(&self, a: &State<F>) -> State<F> {
+        let mut b = vec![vec![]; 25];
+        for (i, lane) in a.iter().enumerate() {
+            // Bit z of rot(v, r) is bit z - r of v.
+            b[pi(i)] = (0..64)
+                .map(|z| lane[(z + 64 - RHO[i] as usize) % 64].clone())
+                .collect();
+        }
+        b
+    }
+
+    /// χ, with ι folded into the same rows.
+    pub fn chi_iota(
+        &self,
+        mut layouter: impl Layouter<F>,
+        b: &State<F>,
+        round: usize,
+    ) -> Result<State<F>, Error> {
+        let config = &self.config;
+        let [a_col, b_col, c_col, out_col] = config.advice;
+        layouter.assign_region(
+            || "chi, iota",
+            |mut region| {
+                let mut out = Vec::with_capacity(25);
+                for i in 0..25 {
+                    let (x, y) = (i % 5, i / 5);
+                    let mut lane = Vec::with_capacity(64);
+                    for z in 0..64 {
+                        let row = 64 * i + z;
+                        config.s_chi.enable(&mut region, row)?;
+                        let a = b[i][z].0.copy_advice(|| "a", &mut region, a_col, row)?;
+                        let b1 = b[(x + 1) % 5 + 5 * y][z].0.copy_advice(
+                            || "b",
+                            &mut region,
+                            b_col,
+                            row,
+                        )?;
+                        let c = b[(x + 2) % 5 + 5 * y][z].0.copy_advice(
+                            || "c",
+                            &mut region,
+                            c_col,
+                            row,
+                        )?;
+                        let rc = if i == 0 { (RC[round] >> z) & 1 } else { 0 };
+                        region.assign_fixed(
+                            || "rc",
+                            config.rc,
+                            row,
+                            || Value::known(F::from(rc)),
+                        )?;
+                        let bit = a.value().zip(b1.value()).zip(c.value()).map(|((a, b), c)| {
+                            let chi =
+                                *a + (F::ONE - *b) * *c - F::from(2) * *a * (F::ONE - *b) * *c;
+                            chi + F::from(rc) - F::from(2) * chi * F::from(rc)
+                        });
+                        let cell = region.assign_advice(|| "out", out_col, row, || bit)?;
+                        lane.push(Number(cell));
+                    }
+                    out.push(lane);
+                }
+                Ok(out)
+            },
+        )
+    }
+
+    /// Round `round` of Keccak-f[1600].
+    pub fn round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &State<F>,
+        round: usize,
+    ) -> Result<State<F>, Error> {
+        let a = self.theta(layouter.namespace(|| "theta"), a)?;
+        let b = self.rho_pi(&a);
+        self.chi_iota(layouter.namespace(|| "chi, iota"), &b, round)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeccakRoundCircuitConfig {
+    keccak: KeccakRoundConfig,
+    instance: Column<Instance>,
+}
+
+/// Round `round` applied to a private state, the result public.
+#[derive(Default)]
+pub struct KeccakRoundCircuit<F: Field> {
+    pub state: Value<[u64; 25]>,
+    pub round: usize,
+    pub _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for KeccakRoundCircuit<F> {
+    type Config = KeccakRoundCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        KeccakRoundCircuit {
+            state: Value::unknown(),
+            round: self.round,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        KeccakRoundCircuitConfig {
+            keccak: KeccakRoundChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = KeccakRoundChip::construct(config.keccak);
+        let state = chip.load_state(layouter.namespace(|| "state"), self.state)?;
+        let out = chip.round(layouter.namespace(|| "round"), &state, self.round)?;
+        for (row, bit) in out.iter().flatten().enumerate() {
+            layouter.constrain_instance(bit.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use rand_core::{OsRng, RngCore};
+
+    const K: u32 = 13;
+
+    fn random_state() -> [u64; 25] {
+        std::array::from_fn(|_| OsRng.next_u64())
+    }
+
+    fn bits(state: &[u64; 25]) -> Vec<Fp> {
+        (0..1600)
+            .map(|i| Fp::from((state[i / 64] >> (i % 64)) & 1))
+            .collect()
+    }
+
+    fn prove(state: [u64; 25], round: usize, public: Vec<Fp>) -> bool {
+        let circuit = KeccakRoundCircuit::<Fp> {
+            state: Value::known(state),
+            round,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_native_round_matches_tiny_keccak() {
+        for mut state in [random_state(), [0; 25]] {
+            let mut expected = state;
+            tiny_keccak::keccakf(&mut expected);
+            for round in 0..24 {
+                keccak_round(&mut state, round);
+            }
+            assert_eq!(state, expected);
+        }
+    }
+
+    #[test]
+    fn test_keccak_round_random() {
+        let state = random_state();
+        let round = 3;
+        let mut out = state;
+        keccak_round(&mut out, round);
+        assert!(prove(state, round, bits(&out)));
+    }
+
+    #[test]
+    fn test_keccak_round_zero() {
+        // θ, ρ, π and χ all keep zero at zero; only ι writes RC[0] = 1.
+        let mut out = [0; 25];
+        out[0] = 1;
+        assert!(prove([0; 25], 0, bits(&out)));
+    }
+
+    #[test]
+    fn test_keccak_round_flipped_bit() {
+        let state = random_state();
+        let mut out = state;
+        keccak_round(&mut out, 0);
+        out[7] ^= 1 << 42;
+        assert!(!prove(state, 0, bits(&out)));
+    }
+}
@@ -0,0 +1,338 @@
+/// chap7: note commitments
+/// A shielded note is a handful of private fields, and what goes on chain is
+/// a commitment to them. Following Zcash, with Poseidon standing in for the
+/// Sinsemilla/Pedersen commitment and the diversified base and transmission
+/// key taken as field elements:
+///
+///   cm = Poseidon(g_d, pk_d, value, rho, psi, rcm)
+///
+/// The circuit proves knowledge of a note behind a public `cm`. `value` is an
+/// amount, and amounts are 64-bit: without a range check a prover could
+/// commit to a field element that wraps around when notes are summed. It is
+/// split into eight bytes, each looked up in the byte table, and recomposed
+/// with a running sum that must end at zero:
+///
+///   z_0 = value,  z_i = b_i + 256 * z_{i+1},  z_8 = 0
+///
+/// The byte check, the running sum and the note fields all share the
+/// Poseidon chip's advice columns.
+///
+/// | a0   | a1   | a2  | a3 (partial sbox) | rc_a[3] | rc_b[3] | q_decompose | instance |
+/// |------|------|-----|-------------------|---------|---------|-------------|----------|
+/// | b_0  | z_0  |     |                   |         |         |      1      |    cm    |
+/// | ...  | ...  |     |                   |         |         |     ...     |          |
+/// | b_7  | z_7  |     |                   |         |         |      1      |          |
+/// |      | z_8  |     |                   |         |    0    |      0      |          |
+/// | g_d  | pk_d | rho |                   |         |         |             |          |
+/// | psi  | rcm  |     |                   |         |         |             |          |
+/// |      Poseidon permutation rows ...                                                |
+use std::marker::PhantomData;
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::gadgets::{
+    byte::{ByteChip, ByteConfig},
+    poseidon::configure_pow5,
+    Number,
+};
+
+const WIDTH: usize = 3;
+const RATE: usize = 2;
+const VALUE_BYTES: usize = 8;
+
+/// Compute the note commitment natively.
+pub fn note_commitment<F: PrimeField>(g_d: F, pk_d: F, value: u64, rho: F, psi: F, rcm: F) -> F
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<6>, WIDTH, RATE>::init().hash([
+        g_d,
+        pk_d,
+        F::from(value),
+        rho,
+        psi,
+        rcm,
+    ])
+}
+
+/// The private fields of a note. `value` is a field element so that the
+/// circuit can be handed one that does not fit in 64 bits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Note<F: PrimeField> {
+    pub g_d: Value<F>,
+    pub pk_d: Value<F>,
+    pub value: Value<F>,
+    pub rho: Value<F>,
+    pub psi: Value<F>,
+    pub rcm: Value<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NoteCommitConfig<F: PrimeField> {
+    advice: [Column<Advice>; WIDTH],
+    byte: ByteConfig,
+    q_decompose: Selector,
+    poseidon: Pow5Config<F, WIDTH, RATE>,
+    instance: Column<Instance>,
+}
+
+pub struct NoteCommitChip<F: PrimeField> {
+    config: NoteCommitConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> NoteCommitChip<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    pub fn construct(config: NoteCommitConfig<F>) -> Self {
+        NoteCommitChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> NoteCommitConfig<F> {
+        let advice: [Column<Advice>; WIDTH] = (0..WIDTH)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let partial_sbox = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        // Poseidon's constant column also holds the end of the running sum.
+        let (poseidon, _) = configure_pow5(meta, advice, partial_sbox);
+
+        let byte = ByteChip::configure(meta, advice[0]);
+        let q_decompose = meta.selector();
+        meta.create_gate("value running sum", |meta| {
+            let q = meta.query_selector(q_decompose);
+            let b = meta.query_advice(advice[0], Rotation::cur());
+            let z_cur = meta.query_advice(advice[1], Rotation::cur());
+            let z_next = meta.query_advice(advice[1], Rotation::next());
+            vec![q * (z_cur - b - z_next * Expression::Constant(F::from(256)))]
+        });
+
+        NoteCommitConfig {
+            advice,
+            byte,
+            q_decompose,
+            poseidon,
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        ByteChip::construct(self.config.byte.clone()).load_table(layouter)
+    }
+
+    /// Witness `value` and check that it fits in 64 bits.
+    pub fn assign_value(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        // The low bytes of `value`; the repr of the Pasta fields is
+        // little-endian. Past 64 bits the running sum does not reach zero.
+        let bytes = value.map(|v| {
+            let repr = v.to_repr();
+            let mut bytes = [F::ZERO; VALUE_BYTES];
+            for (b, r) in bytes.iter_mut().zip(repr.as_ref()) {
+                *b = F::from(*r as u64);
+            }
+            bytes
+        });
+        let inv_256 = F::from(256).invert().unwrap();
+
+        let (value, bytes) = layouter.assign_region(
+            || "value bytes",
+            |mut region| {
+                let mut z = value;
+                let mut z_0 = None;
+                let mut cells = vec![];
+                for i in 0..VALUE_BYTES {
+                    config.q_decompose.enable(&mut region, i)?;
+                    let b = bytes.map(|bytes| bytes[i]);
+                    let b_cell = region.assign_advice(|| "byte", config.advice[0], i, || b)?;
+                    let z_cell = region.assign_advice(|| "z", config.advice[1], i, || z)?;
+                    if i == 0 {
+                        z_0 = Some(z_cell);
+                    }
+                    cells.push(Number(b_cell));
+                    z = (z - b) * Value::known(inv_256);
+                }
+                let z_8 = region.assign_advice(|| "z", config.advice[1], VALUE_BYTES, || z)?;
+                region.constrain_constant(z_8.cell(), F::ZERO)?;
+                Ok((z_0.unwrap(), cells))
+            },
+        )?;
+
+        let byte = ByteChip::construct(config.byte.clone());
+        for b in bytes {
+            byte.check_byte(layouter.namespace(|| "value byte"), b)?;
+        }
+        Ok(value)
+    }
+
+    /// Witness the remaining note fields, in the order they are hashed.
+    pub fn load_note(
+        &self,
+        mut layouter: impl Layouter<F>,
+        note: &Note<F>,
+    ) -> Result<[AssignedCell<F, F>; 5], Error> {
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "load note",
+            |mut region| {
+                let g_d = region.assign_advice(|| "g_d", advice[0], 0, || note.g_d)?;
+                let pk_d = region.assign_advice(|| "pk_d", advice[1], 0, || note.pk_d)?;
+                let rho = region.assign_advice(|| "rho", advice[2], 0, || note.rho)?;
+                let psi = region.assign_advice(|| "psi", advice[0], 1, || note.psi)?;
+                let rcm = region.assign_advice(|| "rcm", advice[1], 1, || note.rcm)?;
+                Ok([g_d, pk_d, rho, psi, rcm])
+            },
+        )
+    }
+
+    /// cm = Poseidon(g_d, pk_d, value, rho, psi, rcm)
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        fields: [AssignedCell<F, F>; 5],
+        value: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [g_d, pk_d, rho, psi, rcm] = fields;
+        let chip = Pow5Chip::construct(self.config.poseidon.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<6>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "init"),
+        )?;
+        hasher.hash(
+            layouter.namespace(|| "cm"),
+            [g_d, pk_d, value, rho, psi, rcm],
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cm: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cm.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+pub struct NoteCommitCircuit<F: PrimeField> {
+    pub note: Note<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for NoteCommitCircuit<F>
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    type Config = NoteCommitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        NoteCommitChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = NoteCommitChip::construct(config);
+        chip.load_table(layouter.namespace(|| "byte table"))?;
+        let value = chip.assign_value(layouter.namespace(|| "value"), self.note.value)?;
+        let fields = chip.load_note(layouter.namespace(|| "note"), &self.note)?;
+        let cm = chip.commit(layouter.namespace(|| "commit"), fields, value)?;
+        chip.expose_public(layouter.namespace(|| "cm"), cm, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 9;
+
+    fn note(value: Fp) -> Note<Fp> {
+        Note {
+            g_d: Value::known(Fp::from(3)),
+            pk_d: Value::known(Fp::from(5)),
+            value: Value::known(value),
+            rho: Value::known(Fp::from(7)),
+            psi: Value::known(Fp::from(11)),
+            rcm: Value::known(Fp::from(13)),
+        }
+    }
+
+    fn cm(value: u64) -> Fp {
+        note_commitment(
+            Fp::from(3),
+            Fp::from(5),
+            value,
+            Fp::from(7),
+            Fp::from(11),
+            Fp::from(13),
+        )
+    }
+
+    #[test]
+    fn test_note_commitment() {
+        for value in [0, 1_000_000, u64::MAX] {
+            let circuit = NoteCommitCircuit {
+                note: note(Fp::from(value)),
+            };
+            let prover = MockProver::run(K, &circuit, vec![vec![cm(value)]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_note_commitment_value_overflow() {
+        // 2^64 is one past the largest amount, and its commitment is
+        // honestly computed: only the range check can reject it.
+        let value = Fp::from(u64::MAX) + Fp::one();
+        let cm = poseidon::Hash::<_, P128Pow5T3, ConstantLength<6>, WIDTH, RATE>::init().hash([
+            Fp::from(3),
+            Fp::from(5),
+            value,
+            Fp::from(7),
+            Fp::from(11),
+            Fp::from(13),
+        ]);
+        let circuit = NoteCommitCircuit { note: note(value) };
+        let prover = MockProver::run(K, &circuit, vec![vec![cm]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_note_commitment_mismatch() {
+        let circuit = NoteCommitCircuit {
+            note: note(Fp::from(100)),
+        };
+        let prover = MockProver::run(K, &circuit, vec![vec![cm(101)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,336 @@
+/// chap7: Sinsemilla with halo2_gadgets
+/// Prove knowledge of a private 500-bit message whose Sinsemilla hash, a
+/// Pallas point, has a public x-coordinate:
+///
+///   instance = [x(hash_to_point(domain, message))]
+///
+/// Sinsemilla cuts the message into `K = 10`-bit words and, starting from a
+/// domain point `Q`, folds each word `m_i` in with one incomplete addition:
+///
+///   acc = Q,    acc = (acc + S(m_i)) + acc   for every word
+///
+/// where `S` maps the 2^10 words to fixed generators. `S(m_i)` is not
+/// computed in circuit: it is looked up, `(m_i, x, y)`, in a table of all
+/// 1024 generators that `SinsemillaChip::load` fills before anything else.
+/// That table alone is 2^10 rows, so even this one hash needs `k >= 11`,
+/// against the `k = 4` or `5` of our own early chapters. The same table's
+/// first column also serves the 10-bit range checks of `LookupRangeCheck`.
+///
+/// The message goes in as `MessagePiece`s, each a whole number of words
+/// packed into one field element. A piece holds at most 25 words, all that
+/// fit in the 255 bits of a Pallas element, so the 500 bits are 2 pieces of 250;
+/// `MessagePiece::from_bitstring` refuses a bit string that is not made of
+/// whole words, by panicking.
+///
+/// `SinsemillaChip` is generic over the ECC chip's fixed bases, because
+/// Sinsemilla commitments blind with a fixed-base multiplication. A hash
+/// never does one, but the types still have to exist: `FixedBases` below
+/// provides them, and computes its window tables only if asked, which this
+/// circuit never does.
+///
+/// | advice 0..10           | fixed 0..8       | table: idx, x, y  |
+/// |------------------------|------------------|-------------------|
+/// | Sinsemilla rows,       | y_Q, constants   | 0, S(0)           |
+/// |   one per word         |                  | 1, S(1)           |
+/// | running sums of pieces |                  | ... 1024 rows     |
+use halo2_gadgets::{
+    ecc::{
+        chip::{
+            find_zs_and_us, BaseFieldElem, EccChip, EccConfig, FixedPoint, FullScalar, ShortScalar,
+            H, NUM_WINDOWS, NUM_WINDOWS_SHORT,
+        },
+        FixedPoints,
+    },
+    sinsemilla::{
+        chip::{SinsemillaChip, SinsemillaConfig},
+        primitives as sinsemilla, CommitDomains, HashDomain, HashDomains, Message, MessagePiece,
+    },
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    arithmetic::CurveExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::{
+        group::{ff::PrimeField, Curve},
+        pallas,
+    },
+    plonk::*,
+};
+
+/// The personalization of our hash domain.
+pub const PERSONALIZATION: &str = "halo2-tutorials:sinsemilla";
+
+/// Bits in the message, and in each of its pieces.
+pub const MESSAGE_BITS: usize = 500;
+pub const PIECE_BITS: usize = 250;
+
+/// Our one hash domain, also standing in as the commit domain the chip's
+/// type asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Domain;
+
+impl HashDomains<pallas::Affine> for Domain {
+    fn Q(&self) -> pallas::Affine {
+        sinsemilla::HashDomain::new(PERSONALIZATION).Q().to_affine()
+    }
+}
+
+impl CommitDomains<pallas::Affine, FixedBases, Domain> for Domain {
+    fn r(&self) -> FullBase {
+        FullBase
+    }
+
+    fn hash_domain(&self) -> Domain {
+        Domain
+    }
+}
+
+/// The fixed bases `EccChip` is generic over, all the same point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedBases;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullBase;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortBase;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseFieldBase;
+
+impl FixedPoints<pallas::Affine> for FixedBases {
+    type FullScalar = FullBase;
+    type ShortScalar = ShortBase;
+    type Base = BaseFieldBase;
+}
+
+fn generator() -> pallas::Affine {
+    pallas::Point::hash_to_curve(PERSONALIZATION)(b"G").to_affine()
+}
+
+/// The `u`s of the window tables for `num_windows` windows.
+fn us(num_windows: usize) -> Vec<[[u8; 32]; H]> {
+    find_zs_and_us(generator(), num_windows)
+        .unwrap()
+        .into_iter()
+        .map(|(_, us)| us.map(|u| u.to_repr()))
+        .collect()
+}
+
+/// The `z`s of the window tables for `num_windows` windows.
+fn zs(num_windows: usize) -> Vec<u64> {
+    find_zs_and_us(generator(), num_windows)
+        .unwrap()
+        .into_iter()
+        .map(|(z, _)| z)
+        .collect()
+}
+
+impl FixedPoint<pallas::Affine> for FullBase {
+    type FixedScalarKind = FullScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS)
+    }
+}
+
+impl FixedPoint<pallas::Affine> for ShortBase {
+    type FixedScalarKind = ShortScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS_SHORT)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS_SHORT)
+    }
+}
+
+impl FixedPoint<pallas::Affine> for BaseFieldBase {
+    type FixedScalarKind = BaseFieldElem;
+
+    fn generator(&self) -> pallas::Affine {
+        generator()
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        us(NUM_WINDOWS)
+    }
+
+    fn z(&self) -> Vec<u64> {
+        zs(NUM_WINDOWS)
+    }
+}
+
+type Chip = SinsemillaChip<Domain, Domain, FixedBases>;
+
+#[derive(Debug, Clone)]
+pub struct SinsemillaHashConfig {
+    ecc: EccConfig<FixedBases>,
+    sinsemilla: SinsemillaConfig<Domain, Domain, FixedBases>,
+    instance: Column<Instance>,
+}
+
+/// The hash of a private message, cut into pieces of `piece_bits` bits.
+pub struct SinsemillaCircuit {
+    pub message: Vec<Value<bool>>,
+    pub piece_bits: usize,
+}
+
+impl Default for SinsemillaCircuit {
+    fn default() -> Self {
+        SinsemillaCircuit {
+            message: vec![Value::unknown(); MESSAGE_BITS],
+            piece_bits: PIECE_BITS,
+        }
+    }
+}
+
+impl Circuit<pallas::Base> for SinsemillaCircuit {
+    type Config = SinsemillaHashConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        SinsemillaCircuit {
+            message: vec![Value::unknown(); self.message.len()],
+            piece_bits: self.piece_bits,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let advices = [(); 10].map(|_| meta.advice_column());
+        for advice in &advices {
+            meta.enable_equality(*advice);
+        }
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+        meta.enable_constant(lagrange_coeffs[0]);
+        let fixed_y_q = meta.fixed_column();
+
+        // The generator table; its index column doubles as the range check's.
+        let table_idx = meta.lookup_table_column();
+        let lookup = (
+            table_idx,
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        );
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+        SinsemillaHashConfig {
+            ecc: EccChip::<FixedBases>::configure(meta, advices, lagrange_coeffs, range_check),
+            sinsemilla: SinsemillaChip::configure(
+                meta,
+                advices[..5].try_into().unwrap(),
+                advices[6],
+                fixed_y_q,
+                lookup,
+                range_check,
+            ),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        Chip::load(config.sinsemilla.clone(), &mut layouter)?;
+        let chip = Chip::construct(config.sinsemilla);
+        let ecc_chip = EccChip::construct(config.ecc);
+
+        let pieces = self
+            .message
+            .chunks(self.piece_bits)
+            .enumerate()
+            .map(|(i, bits)| {
+                MessagePiece::from_bitstring(
+                    chip.clone(),
+                    layouter.namespace(|| format!("piece {}", i)),
+                    bits,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let message = Message::from_pieces(chip.clone(), pieces);
+
+        let domain = HashDomain::new(chip, ecc_chip, &Domain);
+        let (x, _) = domain.hash(layouter.namespace(|| "hash"), message)?;
+        layouter.constrain_instance(x.inner().cell(), config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 11;
+
+    /// A fixed 500-bit test vector.
+    fn message() -> Vec<bool> {
+        (0..MESSAGE_BITS).map(|i| (i * 7 + i / 3) % 5 < 2).collect()
+    }
+
+    /// The x-coordinate of the hash, out of circuit.
+    fn digest(message: &[bool]) -> Fp {
+        sinsemilla::HashDomain::new(PERSONALIZATION)
+            .hash(message.iter().copied())
+            .unwrap()
+    }
+
+    fn circuit(message: &[bool], piece_bits: usize) -> SinsemillaCircuit {
+        SinsemillaCircuit {
+            message: message.iter().map(|b| Value::known(*b)).collect(),
+            piece_bits,
+        }
+    }
+
+    #[test]
+    fn test_sinsemilla() {
+        let message = message();
+        let prover = MockProver::run(
+            K,
+            &circuit(&message, PIECE_BITS),
+            vec![vec![digest(&message)]],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_sinsemilla_wrong_digest() {
+        let message = message();
+        let mut other = message.clone();
+        other[0] = !other[0];
+        let prover = MockProver::run(
+            K,
+            &circuit(&message, PIECE_BITS),
+            vec![vec![digest(&other)]],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    // `from_bitstring` asserts `bitstring.len() % K == 0`.
+    #[should_panic(expected = "left == right")]
+    fn test_sinsemilla_piece_not_whole_words() {
+        // A first piece of 255 bits is 25.5 words.
+        let message = message();
+        let _ = MockProver::run(K, &circuit(&message, 255), vec![vec![digest(&message)]]);
+    }
+}
@@ -0,0 +1,160 @@
+/// chap7: Latin square
+/// Prove knowing a completion of a public 3x3 puzzle into a Latin square:
+/// every row and every column holds `1, 2, 3` in some order. The clues are
+/// the public part, `instance = [clue_0, clue_1, ...]` at positions fixed by
+/// the circuit; the rest of the grid stays private.
+///
+/// A line of three cells is a permutation of `1..=3` exactly when each cell
+/// is one of `1, 2, 3` and no two are equal: three distinct values from a set
+/// of three are the whole set. So every cell goes through `OneOfChip` once,
+/// and every pair within a line through `DistinctChip`, 3 pairs for each of
+/// the 6 lines.
+///
+/// | a0      | a1  | a2          | s_one_of | s_distinct | instance |
+/// |---------|-----|-------------|----------|------------|----------|
+/// | g[i][j] |     |             |    1     |     0      |  clue_0  |   9 cells
+/// | a       |  b  | 1 / (a - b) |    0     |     1      |  clue_1  |   18 pairs
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    distinct::{DistinctChip, DistinctConfig},
+    one_of::{OneOfChip, OneOfConfig},
+    Number,
+};
+
+pub const N: usize = 3;
+
+#[derive(Debug, Clone)]
+struct LatinSquareConfig {
+    one_of: OneOfConfig,
+    distinct: DistinctConfig,
+    instance: Column<Instance>,
+}
+
+struct LatinSquareCircuit<F: PrimeField> {
+    grid: [[Value<F>; N]; N],
+    /// The positions of the clues, in instance order.
+    clues: Vec<(usize, usize)>,
+}
+
+impl<F: PrimeField> Circuit<F> for LatinSquareCircuit<F> {
+    type Config = LatinSquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        LatinSquareCircuit {
+            grid: [[Value::unknown(); N]; N],
+            clues: self.clues.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let symbols = (1..=N as u64).map(F::from).collect::<Vec<_>>();
+        LatinSquareConfig {
+            one_of: OneOfChip::configure(meta, advice[0], &symbols),
+            distinct: DistinctChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let one_of = OneOfChip::construct(config.one_of);
+        let distinct = DistinctChip::construct(config.distinct);
+
+        let mut grid: Vec<Vec<Number<F>>> = Vec::with_capacity(N);
+        for row in &self.grid {
+            let cells = row
+                .iter()
+                .map(|value| one_of.assign(layouter.namespace(|| "cell"), *value))
+                .collect::<Result<Vec<_>, Error>>()?;
+            grid.push(cells);
+        }
+
+        let lines = (0..N)
+            .map(|i| grid[i].clone())
+            .chain((0..N).map(|j| grid.iter().map(|row| row[j].clone()).collect()));
+        for line in lines {
+            for i in 0..N {
+                for j in i + 1..N {
+                    distinct.assert_distinct(
+                        layouter.namespace(|| "distinct"),
+                        line[i].clone(),
+                        line[j].clone(),
+                    )?;
+                }
+            }
+        }
+
+        for (index, (i, j)) in self.clues.iter().enumerate() {
+            layouter.constrain_instance(grid[*i][*j].0.cell(), config.instance, index)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const K: u32 = 6;
+
+    const SOLUTION: [[u64; N]; N] = [[1, 2, 3], [2, 3, 1], [3, 1, 2]];
+
+    /// The puzzle: the diagonal is given.
+    fn clues() -> Vec<(usize, usize)> {
+        vec![(0, 0), (1, 1), (2, 2)]
+    }
+
+    fn verify(grid: [[u64; N]; N], public: [u64; 3]) -> bool {
+        let circuit = LatinSquareCircuit {
+            grid: grid.map(|row| row.map(|v| Value::known(Fp::from(v)))),
+            clues: clues(),
+        };
+        let public = public.map(Fp::from).to_vec();
+        let prover = MockProver::run(K, &circuit, vec![public]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_latin_square() {
+        assert!(verify(SOLUTION, [1, 3, 2]));
+        // The same grid does not solve another puzzle.
+        assert!(!verify(SOLUTION, [1, 2, 2]));
+        // Another square, with the diagonal 1, 2, 3, solves another puzzle.
+        let other = [[1, 3, 2], [3, 2, 1], [2, 1, 3]];
+        assert!(verify(other, [1, 2, 3]));
+        assert!(!verify(other, [1, 3, 2]));
+    }
+
+    #[test]
+    fn test_latin_square_repeated_in_row() {
+        let mut grid = SOLUTION;
+        grid[0][1] = 1;
+        assert!(!verify(grid, [1, 3, 2]));
+    }
+
+    #[test]
+    fn test_latin_square_repeated_in_column() {
+        // Every row is a permutation, but the columns repeat.
+        assert!(!verify([[1, 2, 3]; N], [1, 2, 3]));
+    }
+
+    #[test]
+    fn test_latin_square_symbol_out_of_range() {
+        // Distinct in every line, but 4 is no symbol.
+        let grid = [[4, 2, 3], [2, 3, 4], [3, 4, 2]];
+        assert!(!verify(grid, [4, 3, 2]));
+    }
+}
@@ -0,0 +1,9 @@
+mod ecdsa;
+mod exercise_eddsa;
+mod exercise_keccak_round;
+mod exercise_note_commitment;
+mod exercise_rescue;
+mod exercise_sinsemilla;
+mod latin_square;
+mod sort;
+mod vm;
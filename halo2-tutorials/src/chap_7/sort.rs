@@ -0,0 +1,162 @@
+/// chap7: sorting network
+/// Prove that the public array is the private 4-byte array in ascending
+/// order, with Batcher's odd-even merge network:
+///
+///   x0 ──●──────●──────────
+///        │      │
+///   x1 ──●──────┼──●───●───
+///               │  │   │
+///   x2 ──●──────●──┼───●───
+///        │         │
+///   x3 ──●─────────●───────
+///
+/// Each comparator on wires `(i, j)`, `i < j`, witnesses its decision
+/// `swap = x_j < x_i` with the comparison chip and puts `(min, max)` back on
+/// `(i, j)` with a conditional swap. The network sorts every input as long as
+/// each comparator is right, so the output is sorted; and every swap only
+/// permutes its two wires, so the output is a permutation of the input. Neither
+/// needs a separate check.
+///
+/// Inputs are range-checked to bytes, which the comparison chip requires. The
+/// swap outputs are always one of its inputs, so they stay bytes.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    byte::{ByteChip, ByteConfig},
+    cond_swap::{CondSwapChip, CondSwapConfig},
+    lt::{LtChip, LtConfig},
+    Number,
+};
+
+pub const N: usize = 4;
+
+/// The comparators of the network, in order.
+pub const COMPARATORS: [(usize, usize); 5] = [(0, 1), (2, 3), (0, 2), (1, 3), (1, 2)];
+
+#[derive(Debug, Clone)]
+pub struct SortConfig {
+    byte: ByteConfig,
+    lt: LtConfig,
+    swap: CondSwapConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct SortCircuit<F: PrimeField> {
+    pub values: [Value<F>; N],
+}
+
+impl<F: PrimeField> Circuit<F> for SortCircuit<F> {
+    type Config = SortConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let byte = ByteChip::configure(meta, advice[0]);
+        SortConfig {
+            lt: LtChip::configure(meta, advice, &byte),
+            swap: CondSwapChip::configure(meta, advice),
+            byte,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let byte = ByteChip::construct(config.byte);
+        let lt = LtChip::construct(config.lt);
+        let swap = CondSwapChip::construct(config.swap);
+        byte.load_table(layouter.namespace(|| "byte table"))?;
+
+        let mut wires: Vec<Number<F>> = self
+            .values
+            .iter()
+            .map(|v| byte.assign_byte(layouter.namespace(|| "input"), *v))
+            .collect::<Result<_, Error>>()?;
+
+        for (i, j) in COMPARATORS {
+            let (a, b) = (wires[i].clone(), wires[j].clone());
+            let cond = lt.less_than(
+                layouter.namespace(|| format!("x_{} < x_{}", j, i)),
+                b.clone(),
+                a.clone(),
+            )?;
+            let (lo, hi) = swap.cond_swap(
+                layouter.namespace(|| format!("compare ({}, {})", i, j)),
+                cond,
+                a,
+                b,
+            )?;
+            wires[i] = lo;
+            wires[j] = hi;
+        }
+
+        for (row, out) in wires.iter().enumerate() {
+            layouter.constrain_instance(out.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn circuit(values: [u64; N]) -> SortCircuit<Fp> {
+        SortCircuit {
+            values: values.map(|v| Value::known(Fp::from(v))),
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let k = 9;
+        let sorted = [1u64, 2, 3, 4].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit([3, 1, 4, 2]), vec![sorted.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        // Same multiset in any order, including already sorted and reversed.
+        for input in [[1, 2, 3, 4], [4, 3, 2, 1], [2, 4, 1, 3]] {
+            let prover = MockProver::run(k, &circuit(input), vec![sorted.clone()]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // Duplicates and the ends of the byte range.
+        let sorted = [0u64, 7, 7, 255].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit([7, 255, 7, 0]), vec![sorted]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_sort_not_a_permutation() {
+        let k = 9;
+        // Sorted, but 4 became 5.
+        let claimed = [1u64, 2, 3, 5].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit([3, 1, 4, 2]), vec![claimed]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A permutation, but not sorted.
+        let claimed = [1u64, 3, 2, 4].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit([3, 1, 4, 2]), vec![claimed]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
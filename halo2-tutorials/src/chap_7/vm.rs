@@ -0,0 +1,234 @@
+/// chap7: a tiny zkVM
+/// Prove that some private program, run on an empty stack, leaves the public
+/// value on top of the stack.
+///
+/// Opcodes: PUSH imm, ADD, MUL.
+///
+/// Unlike `chap_6/exercise_stack_vm.rs`, where the program is fixed in the
+/// verifying key, here both the program and the execution trace are witnesses.
+/// Each instruction is witnessed as `(is_push, is_mul, imm)` and each trace
+/// row as the two topmost stack slots after the instruction. A step computes
+/// every possible outcome and picks one with the select gadget:
+///
+///   r       = is_mul  ? top * second : top + second
+///   top'    = is_push ? imm : r
+///   second' = is_push ? top : 0
+///
+/// and the witnessed trace row must match `(top', second')`. The select gate
+/// also forces `is_push` and `is_mul` to be booleans, so every instruction is
+/// one of the three opcodes.
+///
+/// The stack is two slots deep and missing operands read as 0: ADD on a single
+/// value leaves it unchanged, and a PUSH on a full stack drops the bottom slot.
+///
+/// | a0  | a1     | a2      | a3   | s_add | s_mul | s_select |
+/// |-----|--------|---------|------|-------|-------|----------|
+/// | top | second |   sum   |      |   1   |   0   |    0     |
+/// | top | second |  prod   |      |   0   |   1   |    0     |
+/// | sum |  prod  | is_mul  |  r   |   0   |   0   |    1     |
+/// |  r  |  imm   | is_push | top' |   0   |   0   |    1     |
+/// |  0  |  top   | is_push | sec' |   0   |   0   |    1     |
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    pasta::group::ff::PrimeField,
+    plonk::*,
+};
+
+use crate::gadgets::{
+    arith::{ArithChip, ArithConfig},
+    cond_swap::{CondSwapChip, CondSwapConfig},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Push(u64),
+    Add,
+    Mul,
+}
+
+/// The two topmost stack slots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct State {
+    pub top: u64,
+    pub second: u64,
+}
+
+/// Run `program` natively, returning the state after each instruction.
+pub fn execute(program: &[Op]) -> Vec<State> {
+    let mut state = State::default();
+    program
+        .iter()
+        .map(|op| {
+            state = match op {
+                Op::Push(imm) => State {
+                    top: *imm,
+                    second: state.top,
+                },
+                Op::Add => State {
+                    top: state.top + state.second,
+                    second: 0,
+                },
+                Op::Mul => State {
+                    top: state.top * state.second,
+                    second: 0,
+                },
+            };
+            state
+        })
+        .collect()
+}
+
+/// One instruction and the trace row it produces.
+#[derive(Debug, Clone, Default)]
+pub struct Step<F: PrimeField> {
+    pub is_push: Value<F>,
+    pub is_mul: Value<F>,
+    pub imm: Value<F>,
+    pub top: Value<F>,
+    pub second: Value<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VmConfig {
+    arith: ArithConfig,
+    select: CondSwapConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+pub struct VmCircuit<F: PrimeField> {
+    pub steps: Vec<Step<F>>,
+}
+
+impl<F: PrimeField> VmCircuit<F> {
+    pub fn new(program: &[Op]) -> Self {
+        let known = |v: u64| Value::known(F::from(v));
+        let steps = program
+            .iter()
+            .zip(execute(program))
+            .map(|(op, state)| Step {
+                is_push: known(matches!(op, Op::Push(_)) as u64),
+                is_mul: known((*op == Op::Mul) as u64),
+                imm: known(match op {
+                    Op::Push(imm) => *imm,
+                    _ => 0,
+                }),
+                top: known(state.top),
+                second: known(state.second),
+            })
+            .collect();
+        VmCircuit { steps }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for VmCircuit<F> {
+    type Config = VmConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        VmCircuit {
+            steps: vec![Step::default(); self.steps.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        VmConfig {
+            arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+            select: CondSwapChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let arith = ArithChip::construct(config.arith);
+        let select = CondSwapChip::construct(config.select);
+
+        let zero = arith.load_constant(layouter.namespace(|| "zero"), F::ZERO)?;
+        let mut top = zero.clone();
+        let mut second = zero.clone();
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("step {}", i));
+            let is_push = arith.load_private(layouter.namespace(|| "is_push"), step.is_push)?;
+            let is_mul = arith.load_private(layouter.namespace(|| "is_mul"), step.is_mul)?;
+            let imm = arith.load_private(layouter.namespace(|| "imm"), step.imm)?;
+
+            let sum = arith.add(layouter.namespace(|| "ADD"), top.clone(), second.clone())?;
+            let prod = arith.mul(layouter.namespace(|| "MUL"), top.clone(), second)?;
+            let r = select.select(layouter.namespace(|| "ADD or MUL"), is_mul, sum, prod)?;
+            let next_top = select.select(layouter.namespace(|| "top'"), is_push.clone(), r, imm)?;
+            let next_second =
+                select.select(layouter.namespace(|| "second'"), is_push, zero.clone(), top)?;
+
+            // The witnessed trace row must follow from the instruction.
+            let trace_top = arith.load_private(layouter.namespace(|| "trace top"), step.top)?;
+            let trace_second =
+                arith.load_private(layouter.namespace(|| "trace second"), step.second)?;
+            arith.assert_equal(layouter.namespace(|| "top"), next_top, trace_top.clone())?;
+            arith.assert_equal(
+                layouter.namespace(|| "second"),
+                next_second,
+                trace_second.clone(),
+            )?;
+            top = trace_top;
+            second = trace_second;
+        }
+        arith.expose_public(layouter.namespace(|| "stack top"), top, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn program() -> Vec<Op> {
+        vec![Op::Push(2), Op::Push(3), Op::Add]
+    }
+
+    #[test]
+    fn test_vm() {
+        let k = 6;
+        assert_eq!(execute(&program()).last().unwrap().top, 5);
+        let circuit = VmCircuit::<Fp>::new(&program());
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(5)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(6)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // (2 + 3) * 4
+        let program = vec![Op::Push(2), Op::Push(3), Op::Add, Op::Push(4), Op::Mul];
+        let circuit = VmCircuit::<Fp>::new(&program);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(20)]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_vm_corrupted_trace() {
+        let k = 6;
+        // Pretend 2 + 3 = 6.
+        let mut circuit = VmCircuit::<Fp>::new(&program());
+        circuit.steps[2].top = Value::known(Fp::from(6));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(6)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // An instruction that is neither a PUSH nor an ADD/MUL.
+        let mut circuit = VmCircuit::<Fp>::new(&program());
+        circuit.steps[2].is_mul = Value::known(Fp::from(2));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(5)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
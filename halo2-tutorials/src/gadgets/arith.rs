@@ -0,0 +1,278 @@
+/// A general purpose arithmetic chip: one `add`, `sub` or `mul` per row.
+///
+/// Most of the other gadgets are built by composing these instructions, the
+/// same way `chap_1/simple.rs` composes its `mul` helper.
+///
+/// | a0  | a1  | a2  | s_add | s_sub | s_mul |
+/// |-----|-----|-----|-------|-------|-------|
+/// | lhs | rhs | out |   1   |   0   |   0   |   out = lhs + rhs
+/// | lhs | rhs | out |   0   |   1   |   0   |   out = lhs - rhs
+/// | lhs | rhs | out |   0   |   0   |   1   |   out = lhs * rhs
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct ArithConfig {
+    pub advice: [Column<Advice>; 3],
+    s_add: Selector,
+    s_sub: Selector,
+    s_mul: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArithChip<F: Field> {
+    config: ArithConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ArithChip<F> {
+    pub fn construct(config: ArithConfig) -> Self {
+        ArithChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constant: Column<Fixed>,
+    ) -> ArithConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        meta.enable_constant(constant);
+
+        let s_add = meta.selector();
+        let s_sub = meta.selector();
+        let s_mul = meta.selector();
+
+        meta.create_gate("arith", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            let s_add = meta.query_selector(s_add);
+            let s_sub = meta.query_selector(s_sub);
+            let s_mul = meta.query_selector(s_mul);
+            vec![
+                s_add * (lhs.clone() + rhs.clone() - out.clone()),
+                s_sub * (lhs.clone() - rhs.clone() - out.clone()),
+                s_mul * (lhs * rhs - out),
+            ]
+        });
+
+        ArithConfig {
+            advice,
+            s_add,
+            s_sub,
+            s_mul,
+        }
+    }
+
+    pub fn config(&self) -> &ArithConfig {
+        &self.config
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(|| "private input", self.config.advice[0], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    pub fn load_constant(&self, mut layouter: impl Layouter<F>, c: F) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", self.config.advice[0], 0, c)
+                    .map(Number)
+            },
+        )
+    }
+
+    pub fn load_instance(
+        &self,
+        mut layouter: impl Layouter<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load instance",
+            |mut region| {
+                region
+                    .assign_advice_from_instance(
+                        || "public input",
+                        instance,
+                        row,
+                        self.config.advice[0],
+                        0,
+                    )
+                    .map(Number)
+            },
+        )
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let value = a.0.value().copied() + b.0.value().copied();
+        self.binary_op(layouter, "add", self.config.s_add, a, b, value)
+    }
+
+    pub fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let value = a.0.value().copied() - b.0.value().copied();
+        self.binary_op(layouter, "sub", self.config.s_sub, a, b, value)
+    }
+
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let value = a.0.value().copied() * b.0.value().copied();
+        self.binary_op(layouter, "mul", self.config.s_mul, a, b, value)
+    }
+
+    /// Constrain two previously assigned cells to hold the same value.
+    pub fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert equal",
+            |mut region| region.constrain_equal(a.0.cell(), b.0.cell()),
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: Number<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), instance, row)
+    }
+
+    fn binary_op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        name: &'static str,
+        selector: Selector,
+        a: Number<F>,
+        b: Number<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "lhs", &mut region, self.config.advice[0], 0)?;
+                b.0.copy_advice(|| "rhs", &mut region, self.config.advice[1], 0)?;
+                region
+                    .assign_advice(|| "out", self.config.advice[2], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, pasta::Fp, plonk::Circuit};
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        instance: Column<Instance>,
+    }
+
+    /// out = (a + b) * (a - b)
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ArithChip::construct(config.arith);
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let sum = chip.add(layouter.namespace(|| "a + b"), a.clone(), b.clone())?;
+            let diff = chip.sub(layouter.namespace(|| "a - b"), a, b)?;
+            let out = chip.mul(layouter.namespace(|| "sum * diff"), sum, diff)?;
+            chip.expose_public(layouter.namespace(|| "out"), out, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_arith_chip() {
+        let k = 4;
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(7)),
+            b: Value::known(Fp::from(3)),
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(40)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(41)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
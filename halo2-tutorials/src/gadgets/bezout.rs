@@ -0,0 +1,180 @@
+/// Check a claimed Bezout identity `a * s + b * t = gcd`.
+///
+/// The extended Euclidean algorithm loops a data-dependent number of times,
+/// which a circuit cannot do; the prover runs it out of circuit and
+/// witnesses the coefficients `s` and `t`, and the gate only checks that
+/// they combine `a` and `b` into `gcd`. Negative coefficients are field
+/// elements like any other, `-1` being `p - 1`.
+///
+/// This verifies the identity, not that `gcd` is the greatest common
+/// divisor: over a field every `a != 0` is invertible, so some `s` reaches
+/// any `gcd` at all. The identity means something about integers only when
+/// `s` and `t` are also range-checked to be small, which is left to the
+/// caller.
+///
+/// | a0 | a1 | a2  | a3 | a4 | s_bezout |
+/// |----|----|-----|----|----|----------|
+/// | a  | b  | gcd | s  | t  |    1     |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct BezoutConfig {
+    pub advice: [Column<Advice>; 5],
+    s_bezout: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct BezoutChip<F: Field> {
+    config: BezoutConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> BezoutChip<F> {
+    pub fn construct(config: BezoutConfig) -> Self {
+        BezoutChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 5]) -> BezoutConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_bezout = meta.selector();
+
+        meta.create_gate("bezout", |meta| {
+            let [a, b, gcd, s, t] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            let s_bezout = meta.query_selector(s_bezout);
+            Constraints::with_selector(s_bezout, vec![a * s + b * t - gcd])
+        });
+
+        BezoutConfig { advice, s_bezout }
+    }
+
+    /// Check `a * s + b * t = gcd` for the prover's `s` and `t`, and return
+    /// their cells.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+        gcd: Number<F>,
+        s: Value<F>,
+        t: Value<F>,
+    ) -> Result<[Number<F>; 2], Error> {
+        let advice = self.config.advice;
+        layouter.assign_region(
+            || "bezout",
+            |mut region| {
+                self.config.s_bezout.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "a", &mut region, advice[0], 0)?;
+                b.0.copy_advice(|| "b", &mut region, advice[1], 0)?;
+                gcd.0.copy_advice(|| "gcd", &mut region, advice[2], 0)?;
+                let s = region.assign_advice(|| "s", advice[3], 0, || s)?;
+                let t = region.assign_advice(|| "t", advice[4], 0, || t)?;
+                Ok([Number(s), Number(t)])
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        bezout: BezoutConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        a: Value<F>,
+        b: Value<F>,
+        s: Value<F>,
+        t: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 5].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+                bezout: BezoutChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let bezout = BezoutChip::construct(config.bezout);
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+            let gcd = arith.load_instance(layouter.namespace(|| "gcd"), config.instance, 0)?;
+            bezout.verify(
+                layouter.namespace(|| "a s + b t"),
+                a,
+                b,
+                gcd,
+                self.s,
+                self.t,
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_bezout_chip() {
+        let k = 4;
+        // 12 * (-1) + 8 * 2 = 4
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(12)),
+            b: Value::known(Fp::from(8)),
+            s: Value::known(-Fp::one()),
+            t: Value::known(Fp::from(2)),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(4)]]).unwrap();
+        prover.assert_satisfied();
+
+        // 12 * 1 + 8 * 2 = 28
+        let circuit = MyCircuit {
+            s: Value::known(Fp::one()),
+            ..circuit
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(4)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
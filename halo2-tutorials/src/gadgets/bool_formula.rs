@@ -0,0 +1,352 @@
+/// Evaluate a Boolean formula over assigned bits.
+///
+/// A `Formula` is a tree of `And`, `Or` and `Not` over variables. `eval` walks
+/// it bottom-up and spends one row per operator, so the circuit's shape is
+/// generated from the formula itself:
+///
+/// | a0  | a1  | out         | s_bool | s_and | s_or | s_not |
+/// |-----|-----|-------------|--------|-------|------|-------|
+/// |  x  |     |             |   1    |   0   |  0   |   0   |   x ∈ {0, 1}
+/// |  x  |  y  | x * y       |   0    |   1   |  0   |   0   |
+/// |  x  |  y  | x + y - x*y |   0    |   0   |  1   |   0   |
+/// |  x  |     | 1 - x       |   0    |   0   |  0   |   1   |
+///
+/// The inputs are range-checked to bits when loaded, and each operator maps
+/// bits to bits, so every intermediate cell is a bit without further checks.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Var(usize),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn var(i: usize) -> Self {
+        Formula::Var(i)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Formula::Not(Box::new(self))
+    }
+
+    pub fn and(self, rhs: Formula) -> Self {
+        Formula::And(Box::new(self), Box::new(rhs))
+    }
+
+    pub fn or(self, rhs: Formula) -> Self {
+        Formula::Or(Box::new(self), Box::new(rhs))
+    }
+
+    /// Evaluate natively.
+    pub fn evaluate(&self, assignments: &[bool]) -> bool {
+        match self {
+            Formula::Var(i) => assignments[*i],
+            Formula::Not(x) => !x.evaluate(assignments),
+            Formula::And(x, y) => x.evaluate(assignments) && y.evaluate(assignments),
+            Formula::Or(x, y) => x.evaluate(assignments) || y.evaluate(assignments),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoolFormulaConfig {
+    pub advice: [Column<Advice>; 3],
+    s_bool: Selector,
+    s_and: Selector,
+    s_or: Selector,
+    s_not: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoolFormulaChip<F: Field> {
+    config: BoolFormulaConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> BoolFormulaChip<F> {
+    pub fn construct(config: BoolFormulaConfig) -> Self {
+        BoolFormulaChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> BoolFormulaConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_bool = meta.selector();
+        let s_and = meta.selector();
+        let s_or = meta.selector();
+        let s_not = meta.selector();
+
+        meta.create_gate("bool", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let s_bool = meta.query_selector(s_bool);
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(s_bool, vec![x.clone() * (one - x)])
+        });
+
+        meta.create_gate("and", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            let s_and = meta.query_selector(s_and);
+            Constraints::with_selector(s_and, vec![x * y - out])
+        });
+
+        meta.create_gate("or", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let y = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            let s_or = meta.query_selector(s_or);
+            Constraints::with_selector(s_or, vec![x.clone() + y.clone() - x * y - out])
+        });
+
+        meta.create_gate("not", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            let s_not = meta.query_selector(s_not);
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(s_not, vec![one - x - out])
+        });
+
+        BoolFormulaConfig {
+            advice,
+            s_bool,
+            s_and,
+            s_or,
+            s_not,
+        }
+    }
+
+    /// Witness a value and check that it is a bit.
+    pub fn load_bool(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "load bool",
+            |mut region| {
+                self.config.s_bool.enable(&mut region, 0)?;
+                region
+                    .assign_advice(|| "bit", self.config.advice[0], 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// Constrain the value of `formula` with `Var(i)` bound to `assignments[i]`.
+    pub fn eval(
+        &self,
+        mut layouter: impl Layouter<F>,
+        formula: &Formula,
+        assignments: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        self.eval_inner(&mut layouter, formula, assignments)
+    }
+
+    // Recursing on `&mut L` rather than `impl Layouter` keeps the layouter
+    // type from nesting one namespace deeper per level of the tree.
+    fn eval_inner<L: Layouter<F>>(
+        &self,
+        layouter: &mut L,
+        formula: &Formula,
+        assignments: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        match formula {
+            Formula::Var(i) => Ok(assignments[*i].clone()),
+            Formula::Not(x) => {
+                let x = self.eval_inner(layouter, x, assignments)?;
+                self.apply(layouter.namespace(|| "not"), Op::Not, x, None)
+            }
+            Formula::And(x, y) => {
+                let x = self.eval_inner(layouter, x, assignments)?;
+                let y = self.eval_inner(layouter, y, assignments)?;
+                self.apply(layouter.namespace(|| "and"), Op::And, x, Some(y))
+            }
+            Formula::Or(x, y) => {
+                let x = self.eval_inner(layouter, x, assignments)?;
+                let y = self.eval_inner(layouter, y, assignments)?;
+                self.apply(layouter.namespace(|| "or"), Op::Or, x, Some(y))
+            }
+        }
+    }
+
+    fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: Op,
+        x: Number<F>,
+        y: Option<Number<F>>,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "bool op",
+            |mut region| {
+                let selector = match op {
+                    Op::And => config.s_and,
+                    Op::Or => config.s_or,
+                    Op::Not => config.s_not,
+                };
+                selector.enable(&mut region, 0)?;
+                let x = x.0.copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                let x = x.value().copied();
+                let y = match &y {
+                    Some(y) => {
+                        y.0.copy_advice(|| "y", &mut region, config.advice[1], 0)?
+                            .value()
+                            .copied()
+                    }
+                    None => Value::known(F::ZERO),
+                };
+                let out = x.zip(y).map(|(x, y)| match op {
+                    Op::And => x * y,
+                    Op::Or => x + y - x * y,
+                    Op::Not => F::ONE - x,
+                });
+                region
+                    .assign_advice(|| "out", config.advice[2], 0, || out)
+                    .map(Number)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        formula: BoolFormulaConfig,
+        instance: Column<Instance>,
+    }
+
+    struct MyCircuit<F: Field> {
+        formula: Formula,
+        assignments: Vec<Value<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                formula: self.formula.clone(),
+                assignments: vec![Value::unknown(); self.assignments.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                formula: BoolFormulaChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BoolFormulaChip::construct(config.formula);
+            let assignments = self
+                .assignments
+                .iter()
+                .map(|v| chip.load_bool(layouter.namespace(|| "var"), *v))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let out = chip.eval(layouter.namespace(|| "eval"), &self.formula, &assignments)?;
+            layouter.constrain_instance(out.0.cell(), config.instance, 0)
+        }
+    }
+
+    fn circuit(formula: &Formula, assignments: &[u64]) -> MyCircuit<Fp> {
+        MyCircuit {
+            formula: formula.clone(),
+            assignments: assignments
+                .iter()
+                .map(|v| Value::known(Fp::from(*v)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_and_or() {
+        let k = 4;
+        // (a AND b) OR c
+        let formula = Formula::var(0).and(Formula::var(1)).or(Formula::var(2));
+        for bits in 0..8u64 {
+            let assignments = [bits & 1, (bits >> 1) & 1, (bits >> 2) & 1];
+            let expected = formula.evaluate(&assignments.map(|b| b == 1));
+            let circuit = circuit(&formula, &assignments);
+
+            let out = Fp::from(expected as u64);
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::one() - out]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_not() {
+        let k = 4;
+        // NOT (a OR NOT b), true only for a = 0, b = 1
+        let formula = Formula::var(0).or(Formula::var(1).not()).not();
+        let prover =
+            MockProver::run(k, &circuit(&formula, &[0, 1]), vec![vec![Fp::one()]]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit(&formula, &[1, 1]), vec![vec![Fp::ZERO]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_non_boolean_assignment() {
+        // a = 2, b = 1, c = 0 gives (a AND b) OR c = 2 by the gates alone.
+        let k = 4;
+        let formula = Formula::var(0).and(Formula::var(1)).or(Formula::var(2));
+        let circuit = circuit(&formula, &[2, 1, 0]);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(2)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,162 @@
+/// Range-check cells to bytes with a 256-row lookup table.
+///
+/// | a0   | q_byte | table |
+/// |------|--------|-------|
+/// | byte |   1    |   0   |
+/// |      |        |  ...  |
+/// |      |        |  255  |
+///
+/// Disabled rows look up `0`, which is in the table, so they always pass.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct ByteConfig {
+    pub advice: Column<Advice>,
+    pub table: TableColumn,
+    q_byte: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteChip<F: PrimeField> {
+    config: ByteConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ByteChip<F> {
+    pub fn construct(config: ByteConfig) -> Self {
+        ByteChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> ByteConfig {
+        meta.enable_equality(advice);
+        let q_byte = meta.complex_selector();
+        let table = meta.lookup_table_column();
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_byte);
+            let byte = meta.query_advice(advice, Rotation::cur());
+            vec![(q * byte, table)]
+        });
+
+        ByteConfig {
+            advice,
+            table,
+            q_byte,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "byte table",
+            |mut table| {
+                for value in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        self.config.table,
+                        value,
+                        || Value::known(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Witness a value and check that it is a byte.
+    pub fn assign_byte(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "assign byte",
+            |mut region| {
+                self.config.q_byte.enable(&mut region, 0)?;
+                region
+                    .assign_advice(|| "byte", self.config.advice, 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// Check that an already assigned cell holds a byte.
+    pub fn check_byte(&self, mut layouter: impl Layouter<F>, num: Number<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "check byte",
+            |mut region| {
+                self.config.q_byte.enable(&mut region, 0)?;
+                num.0
+                    .copy_advice(|| "byte", &mut region, self.config.advice, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, pasta::Fp, plonk::Circuit};
+
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = ByteConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = meta.advice_column();
+            ByteChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ByteChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            for value in &self.values {
+                chip.assign_byte(layouter.namespace(|| "byte"), *value)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(values: &[u64]) -> MyCircuit<Fp> {
+        MyCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_byte_range_check() {
+        let k = 9;
+        let prover = MockProver::run(k, &circuit(&[0, 1, 127, 255]), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit(&[0, 256]), vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
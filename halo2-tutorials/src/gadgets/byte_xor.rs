@@ -0,0 +1,301 @@
+/// XOR two bytes, bit by bit.
+///
+/// Both bytes are decomposed into bits, most significant first, and every
+/// bit pair is looked up with its XOR in the 4-row table
+///
+///   (0, 0, 0)  (0, 1, 1)  (1, 0, 1)  (1, 1, 0)
+///
+/// which also makes every bit boolean. Three running sums recompose the
+/// bits,
+///
+///   acc_0 = bit_0,    acc_i = 2 * acc_{i-1} + bit_i
+///
+/// and the last ones of `a` and `b` are copy-constrained to the inputs. Eight
+/// boolean bits sum to less than 256, so the inputs are bytes too.
+///
+/// | a0   | a1   | a2   | a3    | a4    | a5    | q_xor | s_first | s_next |
+/// |------|------|------|-------|-------|-------|-------|---------|--------|
+/// | a_7  | b_7  | c_7  | acc_a | acc_b | acc_c |   1   |    1    |   0    |
+/// | a_6  | b_6  | c_6  | acc_a | acc_b | acc_c |   1   |    0    |   1    |
+/// | ...  | ...  | ...  |  ...  |  ...  |  ...  |       |         |        |
+/// | a_0  | b_0  | c_0  |   a   |   b   | a ^ b |   1   |    0    |   1    |
+///
+/// Disabled rows look up `(0, 0, 0)`, the first row of the table.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{
+        Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector, TableColumn,
+    },
+    poly::Rotation,
+};
+
+use super::Number;
+
+/// Bits in a byte.
+const BITS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct ByteXorConfig {
+    pub advice: [Column<Advice>; 6],
+    table: [TableColumn; 3],
+    q_xor: Selector,
+    s_first: Selector,
+    s_next: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteXorChip<F: PrimeField> {
+    config: ByteXorConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ByteXorChip<F> {
+    pub fn construct(config: ByteXorConfig) -> Self {
+        ByteXorChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 6]) -> ByteXorConfig {
+        for col in &advice[3..] {
+            meta.enable_equality(*col);
+        }
+        let q_xor = meta.complex_selector();
+        let s_first = meta.selector();
+        let s_next = meta.selector();
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        let [a, b, c, acc_a, acc_b, acc_c] = advice;
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_xor);
+            let [a, b, c] = [a, b, c].map(|col| meta.query_advice(col, Rotation::cur()));
+            vec![
+                (q.clone() * a, table[0]),
+                (q.clone() * b, table[1]),
+                (q * c, table[2]),
+            ]
+        });
+
+        meta.create_gate("acc_0 = bit_0", |meta| {
+            let s = meta.query_selector(s_first);
+            let constraints = [(a, acc_a), (b, acc_b), (c, acc_c)].map(|(bit, acc)| {
+                let bit = meta.query_advice(bit, Rotation::cur());
+                meta.query_advice(acc, Rotation::cur()) - bit
+            });
+            Constraints::with_selector(s, constraints)
+        });
+
+        meta.create_gate("acc_i = 2 * acc_{i-1} + bit_i", |meta| {
+            let s = meta.query_selector(s_next);
+            let two = Expression::Constant(F::from(2));
+            let constraints = [(a, acc_a), (b, acc_b), (c, acc_c)].map(|(bit, acc)| {
+                let bit = meta.query_advice(bit, Rotation::cur());
+                let prev = meta.query_advice(acc, Rotation::prev());
+                meta.query_advice(acc, Rotation::cur()) - two.clone() * prev - bit
+            });
+            Constraints::with_selector(s, constraints)
+        });
+
+        ByteXorConfig {
+            advice,
+            table,
+            q_xor,
+            s_first,
+            s_next,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "bit xor table",
+            |mut table| {
+                for (row, (a, b)) in [(0, 0), (0, 1), (1, 0), (1, 1)].into_iter().enumerate() {
+                    for (col, value) in self.config.table.iter().zip([a, b, a ^ b]) {
+                        table.assign_cell(|| "xor", *col, row, || Value::known(F::from(value)))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// `a ^ b` for two bytes.
+    pub fn xor_bytes(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let bits = |x: &Number<F>| {
+            let byte = x.0.value().map(|x| x.to_repr().as_ref()[0]);
+            (0..BITS)
+                .rev()
+                .map(|i| byte.map(|byte| F::from(((byte >> i) & 1) as u64)))
+                .collect::<Vec<_>>()
+        };
+        let (a_bits, b_bits) = (bits(&a), bits(&b));
+        self.assign(layouter, a, b, &a_bits, &b_bits)
+    }
+
+    /// Lay out the given bits, most significant first, against `a` and `b`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+        a_bits: &[Value<F>],
+        b_bits: &[Value<F>],
+    ) -> Result<Number<F>, Error> {
+        let [a_col, b_col, c_col, acc_a_col, acc_b_col, acc_c_col] = self.config.advice;
+        layouter.assign_region(
+            || "xor bytes",
+            |mut region| {
+                let two = Value::known(F::from(2));
+                let mut acc = [Value::known(F::ZERO); 3];
+                let mut cells = None;
+                for (row, (a_bit, b_bit)) in a_bits.iter().zip(b_bits).enumerate() {
+                    self.config.q_xor.enable(&mut region, row)?;
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_next.enable(&mut region, row)?;
+                    }
+                    // a + b - 2ab, the XOR of two bits.
+                    let c_bit = *a_bit + *b_bit - two * *a_bit * *b_bit;
+                    region.assign_advice(|| "a bit", a_col, row, || *a_bit)?;
+                    region.assign_advice(|| "b bit", b_col, row, || *b_bit)?;
+                    region.assign_advice(|| "c bit", c_col, row, || c_bit)?;
+                    acc = [0, 1, 2].map(|i| acc[i] * two + [*a_bit, *b_bit, c_bit][i]);
+                    cells = Some((
+                        region.assign_advice(|| "acc a", acc_a_col, row, || acc[0])?,
+                        region.assign_advice(|| "acc b", acc_b_col, row, || acc[1])?,
+                        region.assign_advice(|| "acc c", acc_c_col, row, || acc[2])?,
+                    ));
+                }
+                let (acc_a, acc_b, acc_c) = cells.expect("a byte has bits");
+                region.constrain_equal(a.0.cell(), acc_a.cell())?;
+                region.constrain_equal(b.0.cell(), acc_b.cell())?;
+                Ok(Number(acc_c))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        xor: ByteXorConfig,
+        instance: Column<Instance>,
+    }
+
+    /// `a ^ b = instance[0]`, with the chip's bits or with `bits`.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+        bits: Option<[[u64; BITS]; 2]>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 6].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, [advice[3], advice[4], advice[5]], constant),
+                xor: ByteXorChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let chip = ByteXorChip::construct(config.xor);
+            chip.load_table(layouter.namespace(|| "xor table"))?;
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+            let c = match self.bits {
+                None => chip.xor_bytes(layouter.namespace(|| "a ^ b"), a, b)?,
+                Some([a_bits, b_bits]) => {
+                    let bits = |bits: [u64; BITS]| bits.map(|b| Value::known(F::from(b)));
+                    let (a_bits, b_bits) = (bits(a_bits), bits(b_bits));
+                    chip.assign(layouter.namespace(|| "a ^ b"), a, b, &a_bits, &b_bits)?
+                }
+            };
+            arith.expose_public(layouter.namespace(|| "a ^ b"), c, config.instance, 0)
+        }
+    }
+
+    fn prover(a: u64, b: u64, bits: Option<[[u64; BITS]; 2]>, c: u64) -> MockProver<Fp> {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            bits,
+        };
+        MockProver::run(5, &circuit, vec![vec![Fp::from(c)]]).unwrap()
+    }
+
+    #[test]
+    fn test_byte_xor() {
+        prover(0x0F, 0xF0, None, 0xFF).assert_satisfied();
+        prover(0xAB, 0xCD, None, 0x66).assert_satisfied();
+        prover(0xFF, 0xFF, None, 0x00).assert_satisfied();
+        assert!(prover(0x0F, 0xF0, None, 0xFE).verify().is_err());
+        // 0x10F is no byte: its low eight bits recompose to 0x0F.
+        assert!(prover(0x10F, 0xF0, None, 0x1FF).verify().is_err());
+    }
+
+    #[test]
+    fn test_byte_xor_bits() {
+        // The honest bits of 0x0F and 0xF0, most significant first.
+        let a_bits = [0, 0, 0, 0, 1, 1, 1, 1];
+        let b_bits = [1, 1, 1, 1, 0, 0, 0, 0];
+        prover(0x0F, 0xF0, Some([a_bits, b_bits]), 0xFF).assert_satisfied();
+
+        // Bits that do not recompose to the input fail its copy constraint.
+        let wrong = [0, 0, 0, 0, 1, 1, 1, 0];
+        let errors = prover(0x0F, 0xF0, Some([wrong, b_bits]), 0xFE)
+            .verify()
+            .unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|e| matches!(e, VerifyFailure::Permutation { .. })));
+
+        // 0x100 recomposes from a 2 in the top bit, but the table has no bit
+        // 2: only the lookup of that row fails.
+        let two = [2, 0, 0, 0, 0, 0, 0, 0];
+        let zero = [0; BITS];
+        let errors = prover(0x100, 0x00, Some([two, zero]), 0x100)
+            .verify()
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], VerifyFailure::Lookup { .. }));
+    }
+}
@@ -0,0 +1,227 @@
+/// Conditionally swap two assigned cells.
+///
+/// Built from a select gate, `out = lhs + cond * (rhs - lhs)`, which picks
+/// `lhs` when `cond = 0` and `rhs` when `cond = 1`. The gate also forces `cond`
+/// to be boolean; without that, any `cond` would give some linear blend of the
+/// two inputs. A swap is two selects over the same `cond` with the inputs in
+/// opposite order:
+///
+/// | a0  | a1  | a2   | a3   | s_select |
+/// |-----|-----|------|------|----------|
+/// |  a  |  b  | cond | out0 |    1     |
+/// |  b  |  a  | cond | out1 |    1     |
+///
+/// This is the building block of in-circuit sorting networks, and of Merkle
+/// path hashing, where it orders a node and its sibling by the path bit.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct CondSwapConfig {
+    pub advice: [Column<Advice>; 4],
+    s_select: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct CondSwapChip<F: Field> {
+    config: CondSwapConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> CondSwapChip<F> {
+    pub fn construct(config: CondSwapConfig) -> Self {
+        CondSwapChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+    ) -> CondSwapConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_select = meta.selector();
+
+        meta.create_gate("select", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let cond = meta.query_advice(advice[2], Rotation::cur());
+            let out = meta.query_advice(advice[3], Rotation::cur());
+            let s_select = meta.query_selector(s_select);
+            let one = Expression::Constant(F::ONE);
+            Constraints::with_selector(
+                s_select,
+                vec![
+                    ("cond is boolean", cond.clone() * (one - cond.clone())),
+                    (
+                        "out = lhs + cond * (rhs - lhs)",
+                        out - (lhs.clone() + cond * (rhs - lhs)),
+                    ),
+                ],
+            )
+        });
+
+        CondSwapConfig { advice, s_select }
+    }
+
+    /// `lhs` if `cond = 0`, `rhs` if `cond = 1`.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: Number<F>,
+        lhs: Number<F>,
+        rhs: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [lhs_col, rhs_col, cond_col, out_col] = self.config.advice;
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                self.config.s_select.enable(&mut region, 0)?;
+                let lhs = lhs.0.copy_advice(|| "lhs", &mut region, lhs_col, 0)?;
+                let rhs = rhs.0.copy_advice(|| "rhs", &mut region, rhs_col, 0)?;
+                let cond = cond.0.copy_advice(|| "cond", &mut region, cond_col, 0)?;
+                let out = lhs.value().copied()
+                    + cond.value().copied() * (rhs.value().copied() - lhs.value().copied());
+                region
+                    .assign_advice(|| "out", out_col, 0, || out)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// `(a, b)` if `cond = 0`, `(b, a)` if `cond = 1`.
+    pub fn cond_swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: Number<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let first = self.select(
+            layouter.namespace(|| "first"),
+            cond.clone(),
+            a.clone(),
+            b.clone(),
+        )?;
+        let second = self.select(layouter.namespace(|| "second"), cond, b, a)?;
+        Ok((first, second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        swap: CondSwapConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        cond: Value<F>,
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+                swap: CondSwapChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let swap = CondSwapChip::construct(config.swap);
+            let cond = arith.load_private(layouter.namespace(|| "cond"), self.cond)?;
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+            let (first, second) = swap.cond_swap(layouter.namespace(|| "swap"), cond, a, b)?;
+            arith.expose_public(layouter.namespace(|| "first"), first, config.instance, 0)?;
+            arith.expose_public(layouter.namespace(|| "second"), second, config.instance, 1)
+        }
+    }
+
+    fn circuit(cond: u64) -> MyCircuit<Fp> {
+        MyCircuit {
+            cond: Value::known(Fp::from(cond)),
+            a: Value::known(Fp::from(3)),
+            b: Value::known(Fp::from(7)),
+        }
+    }
+
+    #[test]
+    fn test_cond_swap_keep() {
+        let k = 4;
+        let (a, b) = (Fp::from(3), Fp::from(7));
+        let prover = MockProver::run(k, &circuit(0), vec![vec![a, b]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit(0), vec![vec![b, a]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_cond_swap_swap() {
+        let k = 4;
+        let (a, b) = (Fp::from(3), Fp::from(7));
+        let prover = MockProver::run(k, &circuit(1), vec![vec![b, a]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit(1), vec![vec![a, b]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_cond_swap_non_boolean() {
+        // cond = 2 gives out0 = 2b - a = 11 and out1 = 2a - b = -1, which
+        // satisfies the select equation but not the boolean check.
+        let k = 4;
+        let public_inputs = vec![Fp::from(11), -Fp::one()];
+        let prover = MockProver::run(k, &circuit(2), vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
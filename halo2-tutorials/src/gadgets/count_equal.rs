@@ -0,0 +1,219 @@
+/// Count the elements of a vector equal to a target.
+///
+/// Each element gets an equality flag, `eq_i = (x_i == target)`, as
+/// `is_zero(x_i - target)` from `IsZeroChip`, and `PrefixSumChip` adds the
+/// flags up; the last running total is the count.
+///
+///   eq_i = 1 - (x_i - target) * inv_i,    (x_i - target) * eq_i = 0
+///
+/// The second constraint is `IsZeroChip`'s: for `x_i != target` it forces
+/// `inv_i` to the inverse and `eq_i` to 0, for `x_i = target` the first one
+/// gives `eq_i = 1` whatever `inv_i` is.
+///
+/// | a0  | a1     | a2   | a3    | s_eq |
+/// |-----|--------|------|-------|------|
+/// | x_i | target | eq_i | inv_i |  1   |
+///
+/// followed by the prefix sum of the flags.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::Layouter,
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    is_zero::{IsZeroChip, IsZeroConfig},
+    prefix_sum::{PrefixSumChip, PrefixSumConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct CountEqualConfig<F: PrimeField> {
+    pub advice: [Column<Advice>; 4],
+    is_zero: IsZeroConfig<F>,
+    prefix_sum: PrefixSumConfig,
+    s_eq: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct CountEqualChip<F: PrimeField> {
+    config: CountEqualConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> CountEqualChip<F> {
+    pub fn construct(config: CountEqualConfig<F>) -> Self {
+        CountEqualChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+    ) -> CountEqualConfig<F> {
+        let [x, target, eq, inv] = advice;
+        for col in [x, target, eq] {
+            meta.enable_equality(col);
+        }
+        let s_eq = meta.selector();
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(s_eq),
+            |meta| {
+                meta.query_advice(x, Rotation::cur()) - meta.query_advice(target, Rotation::cur())
+            },
+            inv,
+        );
+
+        meta.create_gate("eq = is_zero(x - target)", |meta| {
+            let s = meta.query_selector(s_eq);
+            let eq = meta.query_advice(eq, Rotation::cur());
+            Constraints::with_selector(s, vec![eq - is_zero.expr()])
+        });
+
+        CountEqualConfig {
+            advice,
+            prefix_sum: PrefixSumChip::configure(meta, [x, target]),
+            is_zero,
+            s_eq,
+        }
+    }
+
+    /// The number of `xs` equal to `target`; `xs` must not be empty.
+    pub fn count_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[Number<F>],
+        target: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        assert!(!xs.is_empty(), "nothing to count");
+        let [x_col, target_col, eq_col, _] = self.config.advice;
+        let flags = layouter.assign_region(
+            || "equality flags",
+            |mut region| {
+                let is_zero = IsZeroChip::construct(self.config.is_zero.clone());
+                xs.iter()
+                    .enumerate()
+                    .map(|(row, x)| {
+                        self.config.s_eq.enable(&mut region, row)?;
+                        let x = x.0.copy_advice(|| "x", &mut region, x_col, row)?;
+                        let target =
+                            target
+                                .0
+                                .copy_advice(|| "target", &mut region, target_col, row)?;
+                        let diff = x.value().copied() - target.value().copied();
+                        is_zero.assign(&mut region, row, diff)?;
+                        let eq = diff.map(|diff| F::from(bool::from(diff.is_zero()) as u64));
+                        region
+                            .assign_advice(|| "eq", eq_col, row, || eq)
+                            .map(Number)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        let totals = PrefixSumChip::construct(self.config.prefix_sum.clone())
+            .prefix_sum(layouter.namespace(|| "count"), &flags)?;
+        Ok(totals.last().unwrap().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        count_equal: CountEqualConfig<Fp>,
+        instance: Column<Instance>,
+    }
+
+    /// Count the `xs` equal to `target`, and expose the count.
+    #[derive(Default)]
+    struct MyCircuit {
+        xs: Vec<Value<Fp>>,
+        target: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                xs: vec![Value::unknown(); self.xs.len()],
+                target: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                count_equal: CountEqualChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = CountEqualChip::construct(config.count_equal.clone());
+            let col = config.count_equal.advice[0];
+            let (xs, target) = layouter.assign_region(
+                || "load",
+                |mut region| {
+                    let target = region
+                        .assign_advice(|| "target", col, 0, || self.target)
+                        .map(Number)?;
+                    let xs = self
+                        .xs
+                        .iter()
+                        .enumerate()
+                        .map(|(row, x)| {
+                            region
+                                .assign_advice(|| "x", col, row + 1, || *x)
+                                .map(Number)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    Ok((xs, target))
+                },
+            )?;
+            let count = chip.count_equal(layouter.namespace(|| "count"), &xs, target)?;
+            layouter.constrain_instance(count.0.cell(), config.instance, 0)
+        }
+    }
+
+    fn verify(xs: &[u64], target: u64, count: u64) -> bool {
+        let circuit = MyCircuit {
+            xs: xs.iter().map(|x| Value::known(Fp::from(*x))).collect(),
+            target: Value::known(Fp::from(target)),
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(count)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_count_equal() {
+        assert!(verify(&[3, 1, 3, 2], 3, 2));
+        assert!(!verify(&[3, 1, 3, 2], 3, 1));
+        assert!(!verify(&[3, 1, 3, 2], 3, 3));
+
+        assert!(verify(&[3, 1, 3, 2], 1, 1));
+        assert!(verify(&[3, 1, 3, 2], 7, 0));
+        assert!(verify(&[0, 0, 0], 0, 3));
+    }
+}
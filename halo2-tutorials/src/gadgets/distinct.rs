@@ -0,0 +1,158 @@
+/// Check that two assigned cells hold different values.
+///
+/// `a != b` exactly when `a - b` has an inverse, so the prover witnesses it
+/// and the gate checks
+///
+///   (a - b) * inv = 1
+///
+/// For `a = b` the left side is zero whatever `inv` is.
+///
+/// | a0 | a1 | a2  | s_distinct |
+/// |----|----|-----|------------|
+/// | a  | b  | inv |     1      |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct DistinctConfig {
+    pub advice: [Column<Advice>; 3],
+    s_distinct: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct DistinctChip<F: Field> {
+    config: DistinctConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> DistinctChip<F> {
+    pub fn construct(config: DistinctConfig) -> Self {
+        DistinctChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> DistinctConfig {
+        meta.enable_equality(advice[0]);
+        meta.enable_equality(advice[1]);
+        let s_distinct = meta.selector();
+
+        meta.create_gate("distinct", |meta| {
+            let s = meta.query_selector(s_distinct);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let inv = meta.query_advice(advice[2], Rotation::cur());
+            Constraints::with_selector(s, vec![(a - b) * inv - Expression::Constant(F::ONE)])
+        });
+
+        DistinctConfig { advice, s_distinct }
+    }
+
+    /// Constrain `a != b`.
+    pub fn assert_distinct(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert distinct",
+            |mut region| {
+                self.config.s_distinct.enable(&mut region, 0)?;
+                let a =
+                    a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+                let b =
+                    b.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+                // Equal values have no inverse: witness 0 and let the gate reject them.
+                let inv = (a.value().copied() - b.value().copied())
+                    .map(|d| d.invert().unwrap_or(F::ZERO));
+                region.assign_advice(|| "1 / (a - b)", self.config.advice[2], 0, || inv)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        distinct: DistinctConfig,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            TestConfig {
+                arith: ArithChip::configure(meta, advice, constant),
+                distinct: DistinctChip::configure(meta, advice),
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let distinct = DistinctChip::construct(config.distinct);
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+            distinct.assert_distinct(layouter.namespace(|| "a != b"), a, b)
+        }
+    }
+
+    fn verify(a: u64, b: u64) -> bool {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+        };
+        let prover = MockProver::run(4, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_distinct() {
+        assert!(verify(1, 2));
+        assert!(verify(2, 1));
+        assert!(verify(0, 7));
+        assert!(!verify(3, 3));
+        assert!(!verify(0, 0));
+    }
+}
@@ -0,0 +1,206 @@
+/// The product of `x_i + gamma` over a vector, as a running product.
+///
+/// This is the shape of the grand products behind the permutation and
+/// lookup arguments: with `gamma` a challenge, two vectors have the same
+/// product exactly when, with high probability, they are the same multiset.
+/// The inputs are copied into one region, one per row, next to `gamma` and
+/// the running product:
+///
+///   acc_0 = x_0 + gamma,    acc_i = acc_{i-1} * (x_i + gamma)
+///
+/// | a0  | a1    | a2    | s_first | s_scan |
+/// |-----|-------|-------|---------|--------|
+/// | x_0 | gamma | acc_0 |    1    |   0    |
+/// | x_1 | gamma | acc_1 |    0    |   1    |
+/// | ... |  ...  |  ...  |         |        |
+/// | x_n | gamma | acc_n |    0    |   1    |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct GrandProductConfig {
+    pub advice: [Column<Advice>; 3],
+    s_first: Selector,
+    s_scan: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct GrandProductChip<F: Field> {
+    config: GrandProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> GrandProductChip<F> {
+    pub fn construct(config: GrandProductConfig) -> Self {
+        GrandProductChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> GrandProductConfig {
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let s_first = meta.selector();
+        let s_scan = meta.selector();
+        let [x, gamma, acc] = advice;
+
+        meta.create_gate("acc_0 = x_0 + gamma", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let gamma = meta.query_advice(gamma, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            Constraints::with_selector(s, vec![acc - (x + gamma)])
+        });
+
+        meta.create_gate("acc_i = acc_{i-1} * (x_i + gamma)", |meta| {
+            let s = meta.query_selector(s_scan);
+            let x = meta.query_advice(x, Rotation::cur());
+            let gamma = meta.query_advice(gamma, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let prev = meta.query_advice(acc, Rotation::prev());
+            Constraints::with_selector(s, vec![acc - prev * (x + gamma)])
+        });
+
+        GrandProductConfig {
+            advice,
+            s_first,
+            s_scan,
+        }
+    }
+
+    /// `(x_0 + gamma) * ... * (x_n + gamma)`; `xs` must not be empty.
+    pub fn product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[Number<F>],
+        gamma: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        assert!(!xs.is_empty(), "the product of nothing has no cell");
+        let [x_col, gamma_col, acc_col] = self.config.advice;
+        layouter.assign_region(
+            || "grand product",
+            |mut region| {
+                let mut acc = Value::known(F::ONE);
+                let mut last = None;
+                for (row, x) in xs.iter().enumerate() {
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_scan.enable(&mut region, row)?;
+                    }
+                    let x = x.0.copy_advice(|| "x", &mut region, x_col, row)?;
+                    let gamma = gamma
+                        .0
+                        .copy_advice(|| "gamma", &mut region, gamma_col, row)?;
+                    acc = acc * (x.value().copied() + gamma.value());
+                    last = Some(region.assign_advice(|| "acc", acc_col, row, || acc)?);
+                }
+                Ok(Number(last.unwrap()))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        grand_product: GrandProductConfig,
+        instance: Column<Instance>,
+    }
+
+    /// Load `xs` privately, `gamma` from the instance, and expose the
+    /// product.
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        xs: Vec<Value<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                xs: vec![Value::unknown(); self.xs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                grand_product: GrandProductChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = GrandProductChip::construct(config.grand_product.clone());
+            let x_col = config.grand_product.advice[0];
+            let (gamma, xs) = layouter.assign_region(
+                || "load",
+                |mut region| {
+                    let gamma = region
+                        .assign_advice_from_instance(|| "gamma", config.instance, 0, x_col, 0)
+                        .map(Number)?;
+                    let xs = self
+                        .xs
+                        .iter()
+                        .enumerate()
+                        .map(|(row, x)| {
+                            region
+                                .assign_advice(|| "x", x_col, row + 1, || *x)
+                                .map(Number)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    Ok((gamma, xs))
+                },
+            )?;
+            let product = chip.product(layouter.namespace(|| "product"), &xs, &gamma)?;
+            layouter.constrain_instance(product.0.cell(), config.instance, 1)
+        }
+    }
+
+    #[test]
+    fn test_grand_product() {
+        let k = 4;
+        let circuit = MyCircuit {
+            xs: [1, 2, 3].map(|x| Value::known(Fp::from(x))).to_vec(),
+        };
+        // (1 + 10)(2 + 10)(3 + 10)
+        let public = vec![Fp::from(10), Fp::from(11 * 12 * 13)];
+        let prover = MockProver::run(k, &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+
+        let public = vec![Fp::from(10), Fp::from(11 * 12 * 13 + 1)];
+        let prover = MockProver::run(k, &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,147 @@
+/// Compute the multiplicative inverse of an assigned cell.
+///
+/// The prover witnesses `a^-1` out of circuit; the gate only has to check
+/// `a * a^-1 = 1`, which also rules out `a = 0` since nothing times zero is one.
+///
+/// | a0  | a1    | s_inv |
+/// |-----|-------|-------|
+/// |  a  | a^-1  |   1   |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct InverseConfig {
+    pub advice: [Column<Advice>; 2],
+    s_inv: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct InverseChip<F: Field> {
+    config: InverseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> InverseChip<F> {
+    pub fn construct(config: InverseConfig) -> Self {
+        InverseChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 2]) -> InverseConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_inv = meta.selector();
+
+        meta.create_gate("inverse", |meta| {
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let a_inv = meta.query_advice(advice[1], Rotation::cur());
+            let s_inv = meta.query_selector(s_inv);
+            Constraints::with_selector(s_inv, vec![a * a_inv - Expression::Constant(F::ONE)])
+        });
+
+        InverseConfig { advice, s_inv }
+    }
+
+    pub fn invert(&self, mut layouter: impl Layouter<F>, a: Number<F>) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "invert",
+            |mut region| {
+                self.config.s_inv.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+                // Zero has no inverse: witness 0 and let the gate reject it.
+                let a_inv = a.0.value().map(|a| a.invert().unwrap_or(F::ZERO));
+                region
+                    .assign_advice(|| "a^-1", self.config.advice[1], 0, || a_inv)
+                    .map(Number)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        inverse: InverseConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        a: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, advice, constant),
+                inverse: InverseChip::configure(meta, [advice[0], advice[1]]),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let inverse = InverseChip::construct(config.inverse);
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let a_inv = inverse.invert(layouter.namespace(|| "1 / a"), a)?;
+            arith.expose_public(layouter.namespace(|| "out"), a_inv, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_inverse_chip() {
+        let k = 4;
+        let a = Fp::from(5);
+        let circuit = MyCircuit { a: Value::known(a) };
+        let prover = MockProver::run(k, &circuit, vec![vec![a.invert().unwrap()]]).unwrap();
+        prover.assert_satisfied();
+
+        // Zero has no inverse, whatever the public input says.
+        let circuit = MyCircuit {
+            a: Value::known(Fp::ZERO),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::ZERO]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,156 @@
+/// Check that two assigned cells are inverses of each other.
+///
+/// Where `InverseChip` computes `a^-1` for a given `a`, this chip only
+/// verifies a claimed pair: both cells come from elsewhere, and are copied in
+/// for the gate `a * b = 1`. Neither can be zero, since nothing times zero is
+/// one.
+///
+/// | a0 | a1 | s_is_inv |
+/// |----|----|----------|
+/// | a  | b  |    1     |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct IsInverseConfig {
+    pub advice: [Column<Advice>; 2],
+    s_is_inv: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsInverseChip<F: Field> {
+    config: IsInverseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> IsInverseChip<F> {
+    pub fn construct(config: IsInverseConfig) -> Self {
+        IsInverseChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+    ) -> IsInverseConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        let s_is_inv = meta.selector();
+
+        meta.create_gate("is inverse", |meta| {
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let s_is_inv = meta.query_selector(s_is_inv);
+            Constraints::with_selector(s_is_inv, vec![a * b - Expression::Constant(F::ONE)])
+        });
+
+        IsInverseConfig { advice, s_is_inv }
+    }
+
+    /// Constrain `a * b = 1`.
+    pub fn assert_inverse(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert inverse",
+            |mut region| {
+                self.config.s_is_inv.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+                b.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        is_inverse: IsInverseConfig,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            TestConfig {
+                arith: ArithChip::configure(meta, advice, constant),
+                is_inverse: IsInverseChip::configure(meta, [advice[0], advice[1]]),
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let is_inverse = IsInverseChip::construct(config.is_inverse);
+            let a = arith.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = arith.load_private(layouter.namespace(|| "b"), self.b)?;
+            is_inverse.assert_inverse(layouter.namespace(|| "a * b = 1"), a, b)
+        }
+    }
+
+    fn verify(a: Fp, b: Fp) -> bool {
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let prover = MockProver::run(4, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_is_inverse_chip() {
+        let two = Fp::from(2);
+        assert!(verify(two, two.invert().unwrap()));
+        assert!(verify(two.invert().unwrap(), two));
+        assert!(verify(-Fp::ONE, -Fp::ONE));
+
+        assert!(!verify(two, Fp::from(3)));
+        assert!(!verify(Fp::ZERO, Fp::ZERO));
+    }
+}
@@ -0,0 +1,257 @@
+/// Check a claimed evaluation of the interpolating polynomial.
+///
+/// Given `n` points `(x_i, y_i)` there is exactly one polynomial `p` of degree
+/// `< n` through all of them. The (first form of the) barycentric formula
+/// evaluates it at `z` without ever computing its coefficients:
+///
+///     l(z) = Π_j (z - x_j)
+///     w_i  = 1 / Π_{j != i} (x_i - x_j)
+///     p(z) = l(z) * Σ_i w_i * y_i / (z - x_i)
+///
+/// Both divisions go through `InverseChip`, so two points sharing the same
+/// `x`, or a `z` landing on one of the `x_i`, make the inverse gate fail
+/// instead of silently producing garbage. Everything else is an `ArithChip`
+/// instruction accumulating the running products and sums.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed},
+};
+
+use super::{
+    arith::{ArithChip, ArithConfig},
+    inverse::{InverseChip, InverseConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct LagrangeConfig {
+    arith: ArithConfig,
+    inverse: InverseConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct LagrangeChip<F: Field> {
+    arith: ArithChip<F>,
+    inverse: InverseChip<F>,
+}
+
+impl<F: Field> LagrangeChip<F> {
+    pub fn construct(config: LagrangeConfig) -> Self {
+        LagrangeChip {
+            arith: ArithChip::construct(config.arith),
+            inverse: InverseChip::construct(config.inverse),
+        }
+    }
+
+    /// Both sub-chips share the same advice columns.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constant: Column<Fixed>,
+    ) -> LagrangeConfig {
+        LagrangeConfig {
+            arith: ArithChip::configure(meta, advice, constant),
+            inverse: InverseChip::configure(meta, [advice[0], advice[1]]),
+        }
+    }
+
+    pub fn arith(&self) -> &ArithChip<F> {
+        &self.arith
+    }
+
+    /// Evaluate the polynomial interpolating `points` at `z`.
+    pub fn evaluate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        points: &[(Number<F>, Number<F>)],
+        z: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        assert!(!points.is_empty(), "need at least one point to interpolate");
+        let arith = &self.arith;
+
+        // z - x_j, for every j
+        let diffs = points
+            .iter()
+            .enumerate()
+            .map(|(j, (x_j, _))| {
+                arith.sub(
+                    layouter.namespace(|| format!("z - x_{}", j)),
+                    z.clone(),
+                    x_j.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // l(z) = Π_j (z - x_j)
+        let mut l = diffs[0].clone();
+        for (j, diff) in diffs.iter().enumerate().skip(1) {
+            l = arith.mul(
+                layouter.namespace(|| format!("l * (z - x_{})", j)),
+                l,
+                diff.clone(),
+            )?;
+        }
+
+        let mut sum: Option<Number<F>> = None;
+        for (i, (x_i, y_i)) in points.iter().enumerate() {
+            // Π_{j != i} (x_i - x_j)
+            let mut denom: Option<Number<F>> = None;
+            for (j, (x_j, _)) in points.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let d = arith.sub(
+                    layouter.namespace(|| format!("x_{} - x_{}", i, j)),
+                    x_i.clone(),
+                    x_j.clone(),
+                )?;
+                denom = Some(match denom {
+                    None => d,
+                    Some(acc) => arith.mul(layouter.namespace(|| "denom"), acc, d)?,
+                });
+            }
+
+            let w_i = match denom {
+                Some(denom) => self
+                    .inverse
+                    .invert(layouter.namespace(|| format!("w_{}", i)), denom)?,
+                None => arith.load_constant(layouter.namespace(|| "w_0"), F::ONE)?,
+            };
+            let inv_diff = self.inverse.invert(
+                layouter.namespace(|| format!("1 / (z - x_{})", i)),
+                diffs[i].clone(),
+            )?;
+            let term = arith.mul(layouter.namespace(|| "w_i * y_i"), w_i, y_i.clone())?;
+            let term = arith.mul(
+                layouter.namespace(|| "w_i * y_i / (z - x_i)"),
+                term,
+                inv_diff,
+            )?;
+
+            sum = Some(match sum {
+                None => term,
+                Some(acc) => arith.add(layouter.namespace(|| "sum"), acc, term)?,
+            });
+        }
+
+        arith.mul(layouter.namespace(|| "l(z) * sum"), l, sum.unwrap())
+    }
+
+    /// Constrain `claimed` to be the evaluation at `z` of the polynomial
+    /// interpolating `points`.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        points: &[(Number<F>, Number<F>)],
+        z: Number<F>,
+        claimed: Number<F>,
+    ) -> Result<(), Error> {
+        let p_z = self.evaluate(layouter.namespace(|| "evaluate"), points, z)?;
+        self.arith
+            .assert_equal(layouter.namespace(|| "p(z) = claimed"), p_z, claimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        lagrange: LagrangeConfig,
+        instance: Column<Instance>,
+    }
+
+    /// Points and `z` are private, the claimed `p(z)` sits in the instance column.
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        points: Vec<(Value<F>, Value<F>)>,
+        z: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                points: vec![(Value::unknown(), Value::unknown()); self.points.len()],
+                z: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                lagrange: LagrangeChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = LagrangeChip::construct(config.lagrange);
+            let arith = chip.arith();
+            let points = self
+                .points
+                .iter()
+                .map(|(x, y)| {
+                    let x = arith.load_private(layouter.namespace(|| "x"), *x)?;
+                    let y = arith.load_private(layouter.namespace(|| "y"), *y)?;
+                    Ok((x, y))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let z = arith.load_private(layouter.namespace(|| "z"), self.z)?;
+            let claimed = arith.load_instance(layouter.namespace(|| "p(z)"), config.instance, 0)?;
+            chip.check(layouter.namespace(|| "interpolate"), &points, z, claimed)
+        }
+    }
+
+    fn circuit(points: &[(u64, u64)], z: u64) -> MyCircuit<Fp> {
+        MyCircuit {
+            points: points
+                .iter()
+                .map(|(x, y)| (Value::known(Fp::from(*x)), Value::known(Fp::from(*y))))
+                .collect(),
+            z: Value::known(Fp::from(z)),
+        }
+    }
+
+    #[test]
+    fn test_lagrange_interpolation() {
+        let k = 7;
+        // p(x) = x^2 + x + 1
+        let circuit = circuit(&[(0, 1), (1, 3), (2, 7)], 5);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(31)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(32)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_lagrange_duplicate_x() {
+        let k = 7;
+        let circuit = circuit(&[(0, 1), (1, 3), (1, 7)], 5);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(31)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,193 @@
+/// `a < b` for two bytes, as a boolean cell.
+///
+/// With `lt` boolean, the gate defines
+///
+///     diff = b - a - 1 + 256 * (1 - lt)
+///
+/// and the byte table checks `diff ∈ [0, 256)`. For bytes `a`, `b` that only
+/// works out with `lt = 1` when `b - a - 1 >= 0`, and with `lt = 0` when
+/// `b - a + 255 < 256`, i.e. exactly when `a < b` and `a >= b` respectively.
+/// Both inputs must already be range-checked to bytes by the caller.
+///
+/// | a0 | a1 | a2 | a3   | q_lt |
+/// |----|----|----|------|------|
+/// | a  | b  | lt | diff |  1   |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::Layouter,
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{byte::ByteConfig, Number};
+
+#[derive(Debug, Clone)]
+pub struct LtConfig {
+    pub advice: [Column<Advice>; 4],
+    q_lt: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct LtChip<F: PrimeField> {
+    config: LtConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> LtChip<F> {
+    pub fn construct(config: LtConfig) -> Self {
+        LtChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Shares the byte table of `byte`, which the caller loads.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        byte: &ByteConfig,
+    ) -> LtConfig {
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        let q_lt = meta.complex_selector();
+        let [a, b, lt, diff] = advice;
+
+        meta.create_gate("less than", |meta| {
+            let q = meta.query_selector(q_lt);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            let base = Expression::Constant(F::from(256));
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("lt is boolean", lt.clone() * (one.clone() - lt.clone())),
+                    (
+                        "diff = b - a - 1 + 256 * (1 - lt)",
+                        diff - (b - a - one.clone() + base * (one - lt)),
+                    ),
+                ],
+            )
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lt);
+            let diff = meta.query_advice(diff, Rotation::cur());
+            vec![(q * diff, byte.table)]
+        });
+
+        LtConfig { advice, q_lt }
+    }
+
+    pub fn less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Number<F>,
+        b: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let [a_col, b_col, lt_col, diff_col] = self.config.advice;
+        layouter.assign_region(
+            || "less than",
+            |mut region| {
+                self.config.q_lt.enable(&mut region, 0)?;
+                let a = a.0.copy_advice(|| "a", &mut region, a_col, 0)?;
+                let b = b.0.copy_advice(|| "b", &mut region, b_col, 0)?;
+                let values = a.value().copied().zip(b.value().copied());
+                let lt = values.map(|(a, b)| {
+                    // Bytes compare the same as their little-endian representations.
+                    let (a, b) = (a.to_repr(), b.to_repr());
+                    F::from((a.as_ref()[0] < b.as_ref()[0]) as u64)
+                });
+                let diff = values
+                    .zip(lt)
+                    .map(|((a, b), lt)| b - a - F::ONE + F::from(256) * (F::ONE - lt));
+                region.assign_advice(|| "diff", diff_col, 0, || diff)?;
+                region.assign_advice(|| "lt", lt_col, 0, || lt).map(Number)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::byte::ByteChip;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        byte: ByteConfig,
+        lt: LtConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let byte = ByteChip::configure(meta, advice[0]);
+            let lt = LtChip::configure(meta, advice, &byte);
+            TestConfig { byte, lt, instance }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let byte = ByteChip::construct(config.byte);
+            let lt = LtChip::construct(config.lt);
+            byte.load_table(layouter.namespace(|| "table"))?;
+            let a = byte.assign_byte(layouter.namespace(|| "a"), self.a)?;
+            let b = byte.assign_byte(layouter.namespace(|| "b"), self.b)?;
+            let out = lt.less_than(layouter.namespace(|| "a < b"), a, b)?;
+            layouter.constrain_instance(out.0.cell(), config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_less_than() {
+        let k = 9;
+        for (a, b) in [(3u64, 200u64), (200, 3), (7, 7), (0, 255), (255, 0)] {
+            let circuit = MyCircuit {
+                a: Value::known(Fp::from(a)),
+                b: Value::known(Fp::from(b)),
+            };
+            let lt = Fp::from((a < b) as u64);
+            let prover = MockProver::run(k, &circuit, vec![vec![lt]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::one() - lt]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
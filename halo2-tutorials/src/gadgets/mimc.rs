@@ -0,0 +1,276 @@
+/// MiMC hashing, one round per row.
+///
+/// The block cipher is `E_k(x) = x_R + k` with
+///
+///     x_0 = x,  x_{i+1} = (x_i + k + c_i)^5
+///
+/// The exponent is 5 rather than MiMC's usual 3 or 7, because `x -> x^e` is
+/// only a permutation when `gcd(e, p - 1) = 1`, which for the Pasta fields it
+/// is for 5 (the same S-box Poseidon uses there) but not for 3. `ROUNDS`
+/// rounds of degree 5 push the algebraic degree past the field size.
+///
+/// `hash` chains the cipher Miyaguchi-Preneel style, from `h = 0`:
+///
+///     h' = E_h(m) + h + m
+///
+/// | x       | k | m | c       | q_round | q_out |
+/// |---------|---|---|---------|---------|-------|
+/// | m       | h |   | c_0     |    1    |   0   |
+/// | x_1     | h |   | c_1     |    1    |   0   |
+/// |  ...    |   |   |         |         |       |
+/// | x_R     | h | m |         |    0    |   1   |
+/// | h'      |   |   |         |    0    |   0   |
+///
+/// The round constants here are just the cubes `i^3`. Fine for a tutorial,
+/// but a real deployment would derive them from a hash of a public seed.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+pub const ROUNDS: usize = 110;
+
+pub fn round_constants<F: PrimeField>() -> Vec<F> {
+    (0..ROUNDS as u64).map(|i| F::from(i * i * i)).collect()
+}
+
+fn pow5<F: PrimeField>(x: F) -> F {
+    x.square().square() * x
+}
+
+/// `E_k(x)` natively.
+pub fn mimc_encrypt<F: PrimeField>(k: F, x: F) -> F {
+    round_constants()
+        .into_iter()
+        .fold(x, |x, c: F| pow5(x + k + c))
+        + k
+}
+
+/// The Miyaguchi-Preneel chain over `msgs` natively.
+pub fn mimc_hash<F: PrimeField>(msgs: &[F]) -> F {
+    msgs.iter()
+        .fold(F::ZERO, |h, m| mimc_encrypt(h, *m) + h + m)
+}
+
+#[derive(Debug, Clone)]
+pub struct MimcConfig {
+    pub advice: [Column<Advice>; 3],
+    round_constant: Column<Fixed>,
+    q_round: Selector,
+    q_out: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct MimcChip<F: PrimeField> {
+    config: MimcConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MimcChip<F> {
+    pub fn construct(config: MimcConfig) -> Self {
+        MimcChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constant: Column<Fixed>,
+    ) -> MimcConfig {
+        for col in advice {
+            meta.enable_equality(col);
+        }
+        meta.enable_constant(constant);
+        let round_constant = meta.fixed_column();
+        let q_round = meta.selector();
+        let q_out = meta.selector();
+        let [x, k, m] = advice;
+
+        meta.create_gate("mimc round", |meta| {
+            let q = meta.query_selector(q_round);
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let k_cur = meta.query_advice(k, Rotation::cur());
+            let k_next = meta.query_advice(k, Rotation::next());
+            let c = meta.query_fixed(round_constant, Rotation::cur());
+            let t = x_cur + k_cur.clone() + c;
+            let t5 = t.clone() * t.clone() * t.clone() * t.clone() * t;
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("x' = (x + k + c)^5", x_next - t5),
+                    ("k' = k", k_next - k_cur),
+                ],
+            )
+        });
+
+        meta.create_gate("mimc out", |meta| {
+            let q = meta.query_selector(q_out);
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let k = meta.query_advice(k, Rotation::cur());
+            let m = meta.query_advice(m, Rotation::cur());
+            let two = Expression::Constant(F::from(2));
+            // E_h(m) + h + m = (x_R + h) + h + m
+            Constraints::with_selector(q, vec![x_next - x_cur - two * k - m])
+        });
+
+        MimcConfig {
+            advice,
+            round_constant,
+            q_round,
+            q_out,
+        }
+    }
+
+    /// h' = E_h(m) + h + m
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: Number<F>,
+        m: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        let [x_col, k_col, m_col] = config.advice;
+        let constants = round_constants::<F>();
+        layouter.assign_region(
+            || "mimc compress",
+            |mut region| {
+                let mut x =
+                    m.0.copy_advice(|| "x_0", &mut region, x_col, 0)?
+                        .value()
+                        .copied();
+                let k =
+                    h.0.copy_advice(|| "k", &mut region, k_col, 0)?
+                        .value()
+                        .copied();
+                for (row, c) in constants.iter().enumerate() {
+                    config.q_round.enable(&mut region, row)?;
+                    region.assign_fixed(|| "c", config.round_constant, row, || Value::known(*c))?;
+                    x = x.zip(k).map(|(x, k)| pow5(x + k + c));
+                    region.assign_advice(|| "x", x_col, row + 1, || x)?;
+                    region.assign_advice(|| "k", k_col, row + 1, || k)?;
+                }
+                config.q_out.enable(&mut region, ROUNDS)?;
+                m.0.copy_advice(|| "m", &mut region, m_col, ROUNDS)?;
+                let out = x
+                    .zip(k)
+                    .zip(m.0.value().copied())
+                    .map(|((x, k), m)| x + k + k + m);
+                region
+                    .assign_advice(|| "h'", x_col, ROUNDS + 1, || out)
+                    .map(Number)
+            },
+        )
+    }
+
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        msgs: &[Number<F>],
+    ) -> Result<Number<F>, Error> {
+        let mut h = layouter.assign_region(
+            || "mimc iv",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "iv", self.config.advice[1], 0, F::ZERO)
+                    .map(Number)
+            },
+        )?;
+        for (i, m) in msgs.iter().enumerate() {
+            h = self.compress(layouter.namespace(|| format!("absorb {}", i)), h, m.clone())?;
+        }
+        Ok(h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        mimc: MimcConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        msgs: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                msgs: vec![Value::unknown(); self.msgs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, advice, constant),
+                mimc: MimcChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let mimc = MimcChip::construct(config.mimc);
+            let msgs = self
+                .msgs
+                .iter()
+                .map(|m| arith.load_private(layouter.namespace(|| "m"), *m))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let h = mimc.hash(layouter.namespace(|| "hash"), &msgs)?;
+            arith.expose_public(layouter.namespace(|| "h"), h, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_mimc_hash() {
+        let k = 9;
+        let msgs = [Fp::from(1), Fp::from(2), Fp::from(3)];
+        let circuit = MyCircuit {
+            msgs: msgs.iter().map(|m| Value::known(*m)).collect(),
+        };
+        let h = mimc_hash(&msgs);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![h]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![mimc_hash(&msgs[..2])]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
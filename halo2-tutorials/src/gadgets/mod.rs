@@ -0,0 +1,31 @@
+/// Reusable gadgets shared by the chapters (and by the benches).
+use halo2_proofs::{arithmetic::Field, circuit::AssignedCell};
+
+pub mod arith;
+pub mod bezout;
+pub mod bool_formula;
+pub mod byte;
+pub mod byte_xor;
+pub mod cond_swap;
+pub mod count_equal;
+pub mod distinct;
+pub mod grand_product;
+pub mod inverse;
+pub mod is_inverse;
+pub mod is_zero;
+pub mod lagrange;
+pub mod lt;
+pub mod mimc;
+pub mod mod_exp;
+pub mod one_of;
+pub mod perm_matrix;
+pub mod polynomial_eval;
+pub mod poseidon;
+pub mod pow;
+pub mod prefix_sum;
+pub mod sorted_lookup;
+pub mod stream_assign;
+
+/// An assigned advice cell holding one field element.
+#[derive(Debug, Clone)]
+pub struct Number<F: Field>(pub AssignedCell<F, F>);
@@ -0,0 +1,326 @@
+/// `base^exp mod p` for a small prime `p < 256`, by square and multiply with a
+/// reduction after every product.
+///
+/// `mod_reduce` witnesses the quotient and remainder of `x` by `p`,
+///
+///     x = q * p + r,    q, r bytes,    r < p
+///
+/// With `q`, `r` and `p` all bytes the right-hand side is below 2^16, so it
+/// cannot wrap around the field and the equation holds over the integers: `r`
+/// really is `x mod p`. An honest `q` is a byte whenever `x < 256 * p`, which
+/// covers the product of two reduced values.
+///
+/// | a0 | a1 | a2 | a3 | s_reduce |
+/// |----|----|----|----|----------|
+/// | x  | q  | r  | p  |    1     |
+///
+/// followed by the byte checks of `q` and `r` and the `r < p` comparison.
+/// Each exponent bit, most significant first, then costs
+///
+///     sq  = acc^2 mod p
+///     mul = sq * base mod p
+///     acc = bit ? mul : sq
+///
+/// where the select also forces the bit to be boolean.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    arith::{ArithChip, ArithConfig},
+    byte::{ByteChip, ByteConfig},
+    cond_swap::{CondSwapChip, CondSwapConfig},
+    lt::{LtChip, LtConfig},
+    Number,
+};
+
+/// A value below 2^64 as an integer.
+fn to_u64<F: PrimeField>(value: F) -> u64 {
+    let repr = value.to_repr();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&repr.as_ref()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[derive(Debug, Clone)]
+pub struct ModExpConfig {
+    pub advice: [Column<Advice>; 4],
+    arith: ArithConfig,
+    byte: ByteConfig,
+    lt: LtConfig,
+    select: CondSwapConfig,
+    s_reduce: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModExpChip<F: PrimeField> {
+    config: ModExpConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ModExpChip<F> {
+    pub fn construct(config: ModExpConfig) -> Self {
+        ModExpChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> ModExpConfig {
+        let arith = ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant);
+        let byte = ByteChip::configure(meta, advice[0]);
+        let lt = LtChip::configure(meta, advice, &byte);
+        let select = CondSwapChip::configure(meta, advice);
+        let s_reduce = meta.selector();
+
+        meta.create_gate("x = q * p + r", |meta| {
+            let s = meta.query_selector(s_reduce);
+            let [x, q, r, p] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+            Constraints::with_selector(s, vec![x - (q * p + r)])
+        });
+
+        ModExpConfig {
+            advice,
+            arith,
+            byte,
+            lt,
+            select,
+            s_reduce,
+        }
+    }
+
+    pub fn arith(&self) -> ArithChip<F> {
+        ArithChip::construct(self.config.arith.clone())
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        ByteChip::construct(self.config.byte.clone()).load_table(layouter)
+    }
+
+    /// `x mod p`, with the quotient and remainder computed honestly. There
+    /// are none for `p = 0`, which is an `Error::Synthesis`.
+    pub fn mod_reduce(
+        &self,
+        layouter: impl Layouter<F>,
+        x: Number<F>,
+        p: Number<F>,
+    ) -> Result<Number<F>, Error> {
+        p.0.value().error_if_known_and(|p| **p == F::ZERO)?;
+        let values = x.0.value().copied().zip(p.0.value().copied());
+        let q = values.map(|(x, p)| F::from(to_u64(x) / to_u64(p)));
+        let r = values.map(|(x, p)| F::from(to_u64(x) % to_u64(p)));
+        self.reduce_with(layouter, x, p, q, r)
+    }
+
+    /// `x mod p` for a prover-supplied quotient `q` and remainder `r`, which
+    /// the constraints check.
+    pub fn reduce_with(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Number<F>,
+        p: Number<F>,
+        q: Value<F>,
+        r: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        let [x_col, q_col, r_col, p_col] = self.config.advice;
+        let (q, r) = layouter.assign_region(
+            || "x = q * p + r",
+            |mut region| {
+                self.config.s_reduce.enable(&mut region, 0)?;
+                x.0.copy_advice(|| "x", &mut region, x_col, 0)?;
+                p.0.copy_advice(|| "p", &mut region, p_col, 0)?;
+                let q = region.assign_advice(|| "q", q_col, 0, || q)?;
+                let r = region.assign_advice(|| "r", r_col, 0, || r)?;
+                Ok((Number(q), Number(r)))
+            },
+        )?;
+
+        let byte = ByteChip::construct(self.config.byte.clone());
+        byte.check_byte(layouter.namespace(|| "q is a byte"), q)?;
+        byte.check_byte(layouter.namespace(|| "r is a byte"), r.clone())?;
+
+        let lt = LtChip::construct(self.config.lt.clone());
+        let r_lt_p = lt.less_than(layouter.namespace(|| "r < p"), r.clone(), p)?;
+        let arith = self.arith();
+        let one = arith.load_constant(layouter.namespace(|| "one"), F::ONE)?;
+        arith.assert_equal(layouter.namespace(|| "r < p holds"), r_lt_p, one)?;
+        Ok(r)
+    }
+
+    /// `base^exp mod p`, with `exp` given by its `n_bits` bits, most
+    /// significant first. `p` must be a prime below 256.
+    pub fn mod_exp(
+        &self,
+        mut layouter: impl Layouter<F>,
+        base: Number<F>,
+        exp_bits: &[Number<F>],
+        p: Number<F>,
+        n_bits: usize,
+    ) -> Result<Number<F>, Error> {
+        assert_eq!(exp_bits.len(), n_bits, "expected {} exponent bits", n_bits);
+        let arith = self.arith();
+        let select = CondSwapChip::construct(self.config.select.clone());
+        ByteChip::construct(self.config.byte.clone())
+            .check_byte(layouter.namespace(|| "p is a byte"), p.clone())?;
+
+        let base = self.mod_reduce(layouter.namespace(|| "base mod p"), base, p.clone())?;
+        let mut acc = arith.load_constant(layouter.namespace(|| "acc = 1"), F::ONE)?;
+        for (i, bit) in exp_bits.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("bit {}", i));
+            let sq = arith.mul(layouter.namespace(|| "acc^2"), acc.clone(), acc)?;
+            let sq = self.mod_reduce(layouter.namespace(|| "acc^2 mod p"), sq, p.clone())?;
+            let mul = arith.mul(
+                layouter.namespace(|| "acc^2 * base"),
+                sq.clone(),
+                base.clone(),
+            )?;
+            let mul =
+                self.mod_reduce(layouter.namespace(|| "acc^2 * base mod p"), mul, p.clone())?;
+            acc = select.select(
+                layouter.namespace(|| "bit ? mul : sq"),
+                bit.clone(),
+                sq,
+                mul,
+            )?;
+        }
+        Ok(acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    const N_BITS: usize = 3;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        mod_exp: ModExpConfig,
+        instance: Column<Instance>,
+    }
+
+    /// out = base^exp mod p, with `forged` replacing the reduction of `base`
+    /// by a prover-chosen `(q, r)`.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        base: Value<F>,
+        exp_bits: [Value<F>; N_BITS],
+        p: Value<F>,
+        forged: Option<(Value<F>, Value<F>)>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                mod_exp: ModExpChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ModExpChip::construct(config.mod_exp);
+            let arith = chip.arith();
+            chip.load_table(layouter.namespace(|| "byte table"))?;
+
+            let base = arith.load_private(layouter.namespace(|| "base"), self.base)?;
+            let p = arith.load_private(layouter.namespace(|| "p"), self.p)?;
+            let bits = self
+                .exp_bits
+                .iter()
+                .map(|bit| arith.load_private(layouter.namespace(|| "bit"), *bit))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let out = match self.forged {
+                None => chip.mod_exp(layouter.namespace(|| "mod exp"), base, &bits, p, N_BITS)?,
+                Some((q, r)) => chip.reduce_with(layouter.namespace(|| "forged"), base, p, q, r)?,
+            };
+            layouter.constrain_instance(out.0.cell(), config.instance, 0)
+        }
+    }
+
+    fn circuit(base: u64, exp: u64, p: u64) -> MyCircuit<Fp> {
+        MyCircuit {
+            base: Value::known(Fp::from(base)),
+            exp_bits: [2, 1, 0].map(|i| Value::known(Fp::from((exp >> i) & 1))),
+            p: Value::known(Fp::from(p)),
+            forged: None,
+        }
+    }
+
+    #[test]
+    fn test_mod_exp() {
+        let k = 9;
+        // 3^4 = 81 = 11 * 7 + 4
+        let prover = MockProver::run(k, &circuit(3, 4, 7), vec![vec![Fp::from(4)]]).unwrap();
+        prover.assert_satisfied();
+
+        let prover = MockProver::run(k, &circuit(3, 4, 7), vec![vec![Fp::from(5)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // 200^7 mod 251, with an unreduced base.
+        let expected = (0..7).fold(1u64, |acc, _| acc * 200 % 251);
+        let prover =
+            MockProver::run(k, &circuit(200, 7, 251), vec![vec![Fp::from(expected)]]).unwrap();
+        prover.assert_satisfied();
+
+        // Nothing reduces mod 0.
+        assert!(MockProver::run(k, &circuit(3, 4, 0), vec![vec![Fp::from(0)]]).is_err());
+    }
+
+    #[test]
+    fn test_mod_reduce_malicious() {
+        let k = 9;
+        // 81 mod 7 = 4, but claim 81 = 10 * 7 + 11: the equation holds and
+        // both are bytes, only r < p catches it.
+        let mut forged = circuit(81, 0, 7);
+        forged.forged = Some((Value::known(Fp::from(10)), Value::known(Fp::from(11))));
+        let prover = MockProver::run(k, &forged, vec![vec![Fp::from(11)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // Overshooting the quotient, 81 = 12 * 7 - 3, holds in the field but
+        // r = -3 is not a byte.
+        forged.forged = Some((Value::known(Fp::from(12)), Value::known(-Fp::from(3))));
+        let prover = MockProver::run(k, &forged, vec![vec![-Fp::from(3)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // The honest pair passes.
+        forged.forged = Some((Value::known(Fp::from(11)), Value::known(Fp::from(4))));
+        let prover = MockProver::run(k, &forged, vec![vec![Fp::from(4)]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
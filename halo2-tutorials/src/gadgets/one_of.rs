@@ -0,0 +1,162 @@
+/// Check that cells hold one of a small fixed set of values.
+///
+/// `x` is in `{v_1, ..., v_n}` exactly when
+///
+///   (x - v_1) * (x - v_2) * ... * (x - v_n) = 0
+///
+/// since a field has no zero divisors. The set is baked into the gate as
+/// constants when the chip is configured, so the gate has degree `n + 1`:
+/// fine for a handful of values, a lookup table beyond that.
+///
+/// | a0 | s_one_of |
+/// |----|----------|
+/// | x  |    1     |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct OneOfConfig {
+    pub advice: Column<Advice>,
+    s_one_of: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct OneOfChip<F: Field> {
+    config: OneOfConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> OneOfChip<F> {
+    pub fn construct(config: OneOfConfig) -> Self {
+        OneOfChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `values` must not be empty.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        values: &[F],
+    ) -> OneOfConfig {
+        assert!(!values.is_empty(), "nothing is one of the empty set");
+        meta.enable_equality(advice);
+        let s_one_of = meta.selector();
+
+        meta.create_gate("one of", |meta| {
+            let s = meta.query_selector(s_one_of);
+            let x = meta.query_advice(advice, Rotation::cur());
+            let product = values
+                .iter()
+                .map(|v| x.clone() - Expression::Constant(*v))
+                .reduce(|acc, factor| acc * factor)
+                .unwrap();
+            Constraints::with_selector(s, vec![product])
+        });
+
+        OneOfConfig { advice, s_one_of }
+    }
+
+    /// Witness a value and check that it is in the set.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "assign one of",
+            |mut region| {
+                self.config.s_one_of.enable(&mut region, 0)?;
+                region
+                    .assign_advice(|| "x", self.config.advice, 0, || value)
+                    .map(Number)
+            },
+        )
+    }
+
+    /// Check that an already assigned cell is in the set.
+    pub fn check(&self, mut layouter: impl Layouter<F>, num: Number<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "check one of",
+            |mut region| {
+                self.config.s_one_of.enable(&mut region, 0)?;
+                num.0
+                    .copy_advice(|| "x", &mut region, self.config.advice, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::{group::ff::PrimeField, Fp},
+        plonk::Circuit,
+    };
+
+    /// Every value must be one of 2, 3 or 5.
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        values: Vec<Value<F>>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = OneOfConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = meta.advice_column();
+            OneOfChip::configure(meta, advice, &[2, 3, 5].map(F::from))
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = OneOfChip::construct(config);
+            for value in &self.values {
+                let x = chip.assign(layouter.namespace(|| "x"), *value)?;
+                chip.check(layouter.namespace(|| "x again"), x)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(values: &[u64]) -> MyCircuit<Fp> {
+        MyCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_one_of() {
+        let k = 4;
+        let prover = MockProver::run(k, &circuit(&[2, 3, 5, 5]), vec![]).unwrap();
+        prover.assert_satisfied();
+
+        for outside in [0, 1, 4, 6] {
+            let prover = MockProver::run(k, &circuit(&[2, outside]), vec![]).unwrap();
+            assert!(prover.verify().is_err(), "{} is not in the set", outside);
+        }
+    }
+}
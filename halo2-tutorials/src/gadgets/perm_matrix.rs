@@ -0,0 +1,222 @@
+/// Assert that an `n x n` matrix is a permutation matrix.
+///
+/// A permutation matrix is boolean with exactly one 1 in every row and every
+/// column. The hamming weight of a boolean vector is just its sum, so each
+/// row and column goes through `PrefixSumChip` and its last running total is
+/// constrained to the constant 1. The entries are checked to be boolean once,
+/// in a region of their own; without that, `[2, -1, 0]` would sum to 1 too.
+///
+/// | a0     | s_bool |
+/// |--------|--------|
+/// | m[0][0]|   1    |   m * (1 - m) = 0
+/// | m[0][1]|   1    |
+/// |  ...   |        |
+///
+/// followed by the `2n` prefix sums and their comparisons with 1.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    arith::{ArithChip, ArithConfig},
+    prefix_sum::{PrefixSumChip, PrefixSumConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct PermMatrixConfig {
+    pub advice: [Column<Advice>; 3],
+    arith: ArithConfig,
+    prefix_sum: PrefixSumConfig,
+    s_bool: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct PermMatrixChip<F: Field> {
+    config: PermMatrixConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> PermMatrixChip<F> {
+    pub fn construct(config: PermMatrixConfig) -> Self {
+        PermMatrixChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+        constant: Column<Fixed>,
+    ) -> PermMatrixConfig {
+        let arith = ArithChip::configure(meta, advice, constant);
+        let prefix_sum = PrefixSumChip::configure(meta, [advice[0], advice[1]]);
+        let s_bool = meta.selector();
+
+        meta.create_gate("entry is boolean", |meta| {
+            let s = meta.query_selector(s_bool);
+            let m = meta.query_advice(advice[0], Rotation::cur());
+            Constraints::with_selector(s, vec![m.clone() * (Expression::Constant(F::ONE) - m)])
+        });
+
+        PermMatrixConfig {
+            advice,
+            arith,
+            prefix_sum,
+            s_bool,
+        }
+    }
+
+    pub fn arith(&self) -> ArithChip<F> {
+        ArithChip::construct(self.config.arith.clone())
+    }
+
+    /// Constrain the square matrix `m` to be a permutation matrix.
+    pub fn assert_perm_matrix(
+        &self,
+        mut layouter: impl Layouter<F>,
+        m: &[Vec<Number<F>>],
+    ) -> Result<(), Error> {
+        let n = m.len();
+        assert!(m.iter().all(|row| row.len() == n), "matrix is not square");
+
+        layouter.assign_region(
+            || "entries are boolean",
+            |mut region| {
+                for (offset, entry) in m.iter().flatten().enumerate() {
+                    self.config.s_bool.enable(&mut region, offset)?;
+                    entry
+                        .0
+                        .copy_advice(|| "entry", &mut region, self.config.advice[0], offset)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let arith = self.arith();
+        let prefix_sum = PrefixSumChip::construct(self.config.prefix_sum.clone());
+        let one = arith.load_constant(layouter.namespace(|| "one"), F::ONE)?;
+        let lines = (0..n)
+            .map(|i| (format!("row {}", i), m[i].clone()))
+            .chain((0..n).map(|j| {
+                (
+                    format!("column {}", j),
+                    m.iter().map(|row| row[j].clone()).collect(),
+                )
+            }));
+        for (name, line) in lines {
+            let mut layouter = layouter.namespace(|| name);
+            let totals = prefix_sum.prefix_sum(layouter.namespace(|| "weight"), &line)?;
+            let weight = totals.last().expect("n > 0").clone();
+            arith.assert_equal(layouter.namespace(|| "weight = 1"), weight, one.clone())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        m: Vec<Vec<Value<F>>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = PermMatrixConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                m: self
+                    .m
+                    .iter()
+                    .map(|row| vec![Value::unknown(); row.len()])
+                    .collect(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            PermMatrixChip::configure(meta, advice, constant)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = PermMatrixChip::construct(config);
+            let arith = chip.arith();
+            let m = self
+                .m
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|v| arith.load_private(layouter.namespace(|| "entry"), *v))
+                        .collect::<Result<Vec<_>, Error>>()
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            chip.assert_perm_matrix(layouter.namespace(|| "perm matrix"), &m)
+        }
+    }
+
+    fn circuit(m: [[u64; 3]; 3]) -> MyCircuit<Fp> {
+        MyCircuit {
+            m: m.iter()
+                .map(|row| row.iter().map(|v| Value::known(Fp::from(*v))).collect())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_perm_matrix() {
+        let k = 6;
+        let valid = circuit([[0, 1, 0], [0, 0, 1], [1, 0, 0]]);
+        MockProver::run(k, &valid, vec![])
+            .unwrap()
+            .assert_satisfied();
+
+        // Every row has weight 1, but column 1 is used twice and column 2
+        // never.
+        let repeated = circuit([[0, 1, 0], [0, 1, 0], [1, 0, 0]]);
+        let prover = MockProver::run(k, &repeated, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_perm_matrix_not_boolean() {
+        let k = 6;
+        // Rows and columns all sum to 1, but with a 2 and a -1.
+        let m = vec![
+            vec![Fp::from(2), -Fp::one(), Fp::zero()],
+            vec![-Fp::one(), Fp::from(2), Fp::zero()],
+            vec![Fp::zero(), Fp::zero(), Fp::one()],
+        ];
+        let circuit = MyCircuit {
+            m: m.into_iter()
+                .map(|row| row.into_iter().map(Value::known).collect())
+                .collect(),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+/// Evaluate a polynomial given by its coefficients, by Horner's rule.
+///
+/// For `p(X) = c_0 + c_1 * X + ... + c_n * X^n` the running value starts
+/// at the leading coefficient and takes one multiply-add per row:
+///
+///   acc_0 = c_n,    acc_i = acc_{i-1} * z + c_{n-i}
+///
+/// so `acc_n = p(z)`. The coefficients and `z` are copied in, so they can be
+/// witnesses, constants or the output of another chip, like a hash.
+///
+/// | a0      | a1 | a2    | s_first | s_step |
+/// |---------|----|-------|---------|--------|
+/// | c_n     | z  | acc_0 |    1    |   0    |
+/// | c_{n-1} | z  | acc_1 |    0    |   1    |
+/// | ...     | .. |  ...  |         |        |
+/// | c_0     | z  | acc_n |    0    |   1    |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct PolynomialEvalConfig {
+    pub advice: [Column<Advice>; 3],
+    s_first: Selector,
+    s_step: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolynomialEvalChip<F: Field> {
+    config: PolynomialEvalConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> PolynomialEvalChip<F> {
+    pub fn construct(config: PolynomialEvalConfig) -> Self {
+        PolynomialEvalChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> PolynomialEvalConfig {
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let s_first = meta.selector();
+        let s_step = meta.selector();
+        let [coeff, z, acc] = advice;
+
+        meta.create_gate("acc_0 = c_n", |meta| {
+            let s = meta.query_selector(s_first);
+            let coeff = meta.query_advice(coeff, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            Constraints::with_selector(s, vec![acc - coeff])
+        });
+
+        meta.create_gate("acc_i = acc_{i-1} * z + c_{n-i}", |meta| {
+            let s = meta.query_selector(s_step);
+            let coeff = meta.query_advice(coeff, Rotation::cur());
+            let z = meta.query_advice(z, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let prev = meta.query_advice(acc, Rotation::prev());
+            Constraints::with_selector(s, vec![acc - (prev * z + coeff)])
+        });
+
+        PolynomialEvalConfig {
+            advice,
+            s_first,
+            s_step,
+        }
+    }
+
+    /// `coeffs[0] + coeffs[1] * z + ...`; `coeffs` must not be empty.
+    pub fn eval(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[Number<F>],
+        z: &Number<F>,
+    ) -> Result<Number<F>, Error> {
+        assert!(!coeffs.is_empty(), "the empty polynomial has no cell");
+        let [coeff_col, z_col, acc_col] = self.config.advice;
+        layouter.assign_region(
+            || "polynomial eval",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut last = None;
+                for (row, coeff) in coeffs.iter().rev().enumerate() {
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_step.enable(&mut region, row)?;
+                    }
+                    let coeff = coeff.0.copy_advice(|| "c", &mut region, coeff_col, row)?;
+                    let z = z.0.copy_advice(|| "z", &mut region, z_col, row)?;
+                    acc = acc * z.value().copied() + coeff.value();
+                    last = Some(region.assign_advice(|| "acc", acc_col, row, || acc)?);
+                }
+                Ok(Number(last.unwrap()))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        polynomial_eval: PolynomialEvalConfig,
+        instance: Column<Instance>,
+    }
+
+    /// Load `coeffs` privately, `z` from the instance, and expose `p(z)`.
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        coeffs: Vec<Value<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                coeffs: vec![Value::unknown(); self.coeffs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                polynomial_eval: PolynomialEvalChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = PolynomialEvalChip::construct(config.polynomial_eval.clone());
+            let col = config.polynomial_eval.advice[0];
+            let (z, coeffs) = layouter.assign_region(
+                || "load",
+                |mut region| {
+                    let z = region
+                        .assign_advice_from_instance(|| "z", config.instance, 0, col, 0)
+                        .map(Number)?;
+                    let coeffs = self
+                        .coeffs
+                        .iter()
+                        .enumerate()
+                        .map(|(row, c)| {
+                            region
+                                .assign_advice(|| "c", col, row + 1, || *c)
+                                .map(Number)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    Ok((z, coeffs))
+                },
+            )?;
+            let value = chip.eval(layouter.namespace(|| "eval"), &coeffs, &z)?;
+            layouter.constrain_instance(value.0.cell(), config.instance, 1)
+        }
+    }
+
+    #[test]
+    fn test_polynomial_eval() {
+        let k = 4;
+        // 1 + 2X + 3X^2
+        let circuit = MyCircuit {
+            coeffs: [1, 2, 3].map(|c| Value::known(Fp::from(c))).to_vec(),
+        };
+        let public = vec![Fp::from(5), Fp::from(1 + 2 * 5 + 3 * 25)];
+        let prover = MockProver::run(k, &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+
+        // The coefficients are read low to high, not the other way around.
+        let public = vec![Fp::from(5), Fp::from(3 + 2 * 5 + 25)];
+        let prover = MockProver::run(k, &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A constant polynomial is its own value.
+        let circuit = MyCircuit {
+            coeffs: vec![Value::known(Fp::from(7))],
+        };
+        let public = vec![Fp::from(5), Fp::from(7)];
+        let prover = MockProver::run(k, &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+    }
+}
@@ -0,0 +1,46 @@
+/// Configure halo2_gadgets' Poseidon chip the way every chapter uses it:
+/// `Pow5Chip` with the P128Pow5T3 spec, a width 3, rate 2 sponge.
+///
+/// Besides the state columns the chip needs a partial S-box column and two
+/// rows of round constant columns, `rc_a` and `rc_b`. The sponge starts with
+/// a constant capacity element, so one of them has to be a constant column
+/// too, and the state columns take part in copy constraints: the inputs are
+/// copied in and the digest out.
+use halo2_gadgets::poseidon::{
+    primitives::{P128Pow5T3, Spec},
+    Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Fixed},
+};
+
+pub const WIDTH: usize = 3;
+pub const RATE: usize = 2;
+
+/// Configure `Pow5Chip` over the `state` columns, with `partial_sbox` for the
+/// partial rounds and fresh round constant columns.
+///
+/// The first `rc_b` column doubles as the circuit's constant column, and is
+/// returned for chips that need one of their own: the layouter fills the
+/// rows Poseidon leaves free there.
+pub fn configure_pow5<F: PrimeField>(
+    meta: &mut ConstraintSystem<F>,
+    state: [Column<Advice>; WIDTH],
+    partial_sbox: Column<Advice>,
+) -> (Pow5Config<F, WIDTH, RATE>, Column<Fixed>)
+where
+    P128Pow5T3: Spec<F, WIDTH, RATE>,
+{
+    let rc_a = [(); WIDTH].map(|_| meta.fixed_column());
+    let rc_b = [(); WIDTH].map(|_| meta.fixed_column());
+
+    // The sponge's initial capacity element is a constant.
+    meta.enable_constant(rc_b[0]);
+    for col in state {
+        meta.enable_equality(col);
+    }
+
+    let config = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+    (config, rc_b[0])
+}
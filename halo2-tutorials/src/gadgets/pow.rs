@@ -0,0 +1,318 @@
+/// `base^exp` for a variable `exp` of at most `bits` bits, by square and
+/// multiply over the exponent's bits, most significant first:
+///
+///   acc_0 = 1,  acc_{i + 1} = acc_i^2 * (bit_i ? base : 1)
+///   exp_0 = 0,  exp_{i + 1} = 2 * exp_i + bit_i
+///
+/// Every `bit_i` is boolean, and the last row's `exp` is copied from the
+/// exponent cell, so the exponent is also range-checked to `[0, 2^bits)`.
+///
+/// | a0    | a1   | a2    | a3    | q_pow |
+/// |-------|------|-------|-------|-------|
+/// | bit_0 | base |   1   |   0   |   1   |
+/// | bit_1 | base | acc_1 | exp_1 |   1   |
+/// |  ...  | ...  |  ...  |  ...  |  ...  |
+/// |       | base |  out  |  exp  |   0   |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+/// The little-endian bits of `value`, `bits` of them.
+fn to_bits<F: PrimeField>(value: F, bits: usize) -> Vec<bool> {
+    let repr = value.to_repr();
+    (0..bits)
+        .map(|i| (repr.as_ref()[i / 8] >> (i % 8)) & 1 == 1)
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct PowConfig {
+    pub advice: [Column<Advice>; 4],
+    q_pow: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct PowChip<F: PrimeField> {
+    config: PowConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PowChip<F> {
+    pub fn construct(config: PowConfig) -> Self {
+        PowChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+    ) -> PowConfig {
+        for c in &advice {
+            meta.enable_equality(*c);
+        }
+        meta.enable_constant(constant);
+        let q_pow = meta.selector();
+        let [bit, base, acc, exp] = advice;
+
+        meta.create_gate("square and multiply", |meta| {
+            let q = meta.query_selector(q_pow);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let base_cur = meta.query_advice(base, Rotation::cur());
+            let base_next = meta.query_advice(base, Rotation::next());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let exp_cur = meta.query_advice(exp, Rotation::cur());
+            let exp_next = meta.query_advice(exp, Rotation::next());
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+            let factor = one.clone() + bit.clone() * (base_cur.clone() - one.clone());
+            Constraints::with_selector(
+                q,
+                vec![
+                    ("bit is boolean", bit.clone() * (one - bit.clone())),
+                    ("base is carried", base_next - base_cur),
+                    (
+                        "acc_next = acc^2 * (bit ? base : 1)",
+                        acc_next - acc_cur.clone() * acc_cur * factor,
+                    ),
+                    ("exp_next = 2 * exp + bit", exp_next - (two * exp_cur + bit)),
+                ],
+            )
+        });
+
+        PowConfig { advice, q_pow }
+    }
+
+    /// `base^exp`, where `exp` must fit in `bits` bits.
+    pub fn pow(
+        &self,
+        layouter: impl Layouter<F>,
+        base: Number<F>,
+        exp: Number<F>,
+        bits: usize,
+    ) -> Result<Number<F>, Error> {
+        self.pow_with_bits(layouter, base, exp, bits)
+            .map(|(out, _)| out)
+    }
+
+    /// Like `pow`, but also return the exponent's bit cells, most significant
+    /// first, so that `pow_by_bits` can raise another base to the same
+    /// exponent.
+    pub fn pow_with_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        base: Number<F>,
+        exp: Number<F>,
+        bits: usize,
+    ) -> Result<(Number<F>, Vec<Number<F>>), Error> {
+        let bit_values = exp.0.value().map(|e| {
+            let mut bits = to_bits(*e, bits);
+            bits.reverse();
+            bits
+        });
+        let (out, _, bit_cells) = self.assign(
+            layouter,
+            base,
+            bits,
+            |region, row| {
+                let bit = bit_values.as_ref().map(|bits| F::from(bits[row] as u64));
+                region.assign_advice(|| "bit", self.config.advice[0], row, || bit)
+            },
+            Some(&exp),
+        )?;
+        Ok((out, bit_cells))
+    }
+
+    /// `base^exp` for an exponent given by its already assigned bits, most
+    /// significant first. Returns the result and the recomposed exponent.
+    pub fn pow_by_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        base: Number<F>,
+        bits: &[Number<F>],
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        let values: Vec<_> = bits.iter().map(|bit| bit.0.value().copied()).collect();
+        self.pow_by_bits_with(layouter, base, bits, &values)
+    }
+
+    /// Like `pow_by_bits`, but with prover-supplied values under the copies
+    /// of `bits`, which the copy constraints check. Tests use it to forge
+    /// those values; it is not part of the gadget's API.
+    pub(crate) fn pow_by_bits_with(
+        &self,
+        layouter: impl Layouter<F>,
+        base: Number<F>,
+        bits: &[Number<F>],
+        values: &[Value<F>],
+    ) -> Result<(Number<F>, Number<F>), Error> {
+        assert_eq!(bits.len(), values.len(), "one value per bit");
+        let (out, exp, _) = self.assign(
+            layouter,
+            base,
+            bits.len(),
+            |region, row| {
+                let bit =
+                    region.assign_advice(|| "bit", self.config.advice[0], row, || values[row])?;
+                region.constrain_equal(bits[row].0.cell(), bit.cell())?;
+                Ok(bit)
+            },
+            None,
+        )?;
+        Ok((out, exp))
+    }
+
+    /// Lay out the square and multiply rows, with `assign_bit` placing the
+    /// bit of each row. If `exp` is given, the recomposed exponent is
+    /// constrained to it.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        base: Number<F>,
+        bits: usize,
+        assign_bit: impl Fn(&mut Region<'_, F>, usize) -> Result<AssignedCell<F, F>, Error>,
+        exp: Option<&Number<F>>,
+    ) -> Result<(Number<F>, Number<F>, Vec<Number<F>>), Error> {
+        let [_, base_col, acc_col, exp_col] = self.config.advice;
+        layouter.assign_region(
+            || "pow",
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(|| "acc_0", acc_col, 0, F::ONE)?;
+                let mut e = region.assign_advice_from_constant(|| "exp_0", exp_col, 0, F::ZERO)?;
+                let base_value = base.0.value().copied();
+                let mut bit_cells = Vec::with_capacity(bits);
+
+                for row in 0..bits {
+                    self.config.q_pow.enable(&mut region, row)?;
+                    base.0.copy_advice(|| "base", &mut region, base_col, row)?;
+                    let bit_cell = assign_bit(&mut region, row)?;
+                    let bit = bit_cell.value().copied();
+                    bit_cells.push(Number(bit_cell));
+
+                    let factor = bit
+                        .zip(base_value)
+                        .map(|(b, base)| F::ONE + b * (base - F::ONE));
+                    let acc_next = acc.value().copied().map(|a| a * a) * factor;
+                    let e_next = e.value().copied().map(|e| e + e) + bit;
+                    acc = region.assign_advice(|| "acc", acc_col, row + 1, || acc_next)?;
+                    e = match exp {
+                        // The recomposed exponent must be the input exponent.
+                        Some(exp) if row + 1 == bits => {
+                            exp.0.copy_advice(|| "exp", &mut region, exp_col, row + 1)?
+                        }
+                        _ => region.assign_advice(|| "exp", exp_col, row + 1, || e_next)?,
+                    };
+                }
+                base.0.copy_advice(|| "base", &mut region, base_col, bits)?;
+                Ok((Number(acc), Number(e), bit_cells))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::arith::{ArithChip, ArithConfig};
+    use halo2_proofs::{
+        arithmetic::Field,
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    const BITS: usize = 8;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        arith: ArithConfig,
+        pow: PowConfig,
+        instance: Column<Instance>,
+    }
+
+    /// out = base^exp
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        base: Value<F>,
+        exp: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                arith: ArithChip::configure(meta, [advice[0], advice[1], advice[2]], constant),
+                pow: PowChip::configure(meta, advice, constant),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let arith = ArithChip::construct(config.arith);
+            let pow = PowChip::construct(config.pow);
+            let base = arith.load_private(layouter.namespace(|| "base"), self.base)?;
+            let exp = arith.load_private(layouter.namespace(|| "exp"), self.exp)?;
+            let out = pow.pow(layouter.namespace(|| "base^exp"), base, exp, BITS)?;
+            layouter.constrain_instance(out.0.cell(), config.instance, 0)
+        }
+    }
+
+    fn circuit(base: u64, exp: u64) -> MyCircuit<Fp> {
+        MyCircuit {
+            base: Value::known(Fp::from(base)),
+            exp: Value::known(Fp::from(exp)),
+        }
+    }
+
+    #[test]
+    fn test_pow() {
+        let k = 5;
+        for (base, exp) in [(3u64, 0u64), (3, 1), (3, 5), (7, 200), (2, 255)] {
+            let out = Fp::from(base).pow_vartime([exp]);
+            let prover = MockProver::run(k, &circuit(base, exp), vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover =
+                MockProver::run(k, &circuit(base, exp), vec![vec![out + Fp::one()]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_pow_exponent_too_large() {
+        // 256 does not fit in 8 bits, so the recomposed exponent stops at 0.
+        let k = 5;
+        let out = Fp::from(3).pow_vartime([256u64]);
+        let prover = MockProver::run(k, &circuit(3, 256), vec![vec![out]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
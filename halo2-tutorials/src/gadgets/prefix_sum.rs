@@ -0,0 +1,201 @@
+/// Running totals of a vector, `acc_i = x_0 + ... + x_i`.
+///
+/// The inputs are copied into one region, one per row, next to their running
+/// total. The first row starts the scan and every later row adds its input to
+/// the total of the row above:
+///
+///   acc_0 = x_0,    acc_i = acc_{i-1} + x_i
+///
+/// | a0  | a1    | s_first | s_scan |
+/// |-----|-------|---------|--------|
+/// | x_0 | acc_0 |    1    |   0    |
+/// | x_1 | acc_1 |    0    |   1    |
+/// | ... |  ...  |         |        |
+/// | x_n | acc_n |    0    |   1    |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Selector},
+    poly::Rotation,
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct PrefixSumConfig {
+    pub advice: [Column<Advice>; 2],
+    s_first: Selector,
+    s_scan: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrefixSumChip<F: Field> {
+    config: PrefixSumConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> PrefixSumChip<F> {
+    pub fn construct(config: PrefixSumConfig) -> Self {
+        PrefixSumChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+    ) -> PrefixSumConfig {
+        for col in &advice {
+            meta.enable_equality(*col);
+        }
+        let s_first = meta.selector();
+        let s_scan = meta.selector();
+        let [x, acc] = advice;
+
+        meta.create_gate("acc_0 = x_0", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            Constraints::with_selector(s, vec![acc - x])
+        });
+
+        meta.create_gate("acc_i = acc_{i-1} + x_i", |meta| {
+            let s = meta.query_selector(s_scan);
+            let x = meta.query_advice(x, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let prev = meta.query_advice(acc, Rotation::prev());
+            Constraints::with_selector(s, vec![acc - (prev + x)])
+        });
+
+        PrefixSumConfig {
+            advice,
+            s_first,
+            s_scan,
+        }
+    }
+
+    /// The running totals of `xs`, one per input.
+    pub fn prefix_sum(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[Number<F>],
+    ) -> Result<Vec<Number<F>>, Error> {
+        let [x_col, acc_col] = self.config.advice;
+        layouter.assign_region(
+            || "prefix sum",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut totals = Vec::with_capacity(xs.len());
+                for (row, x) in xs.iter().enumerate() {
+                    if row == 0 {
+                        self.config.s_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.s_scan.enable(&mut region, row)?;
+                    }
+                    let x = x.0.copy_advice(|| "x", &mut region, x_col, row)?;
+                    acc = acc + x.value();
+                    let total = region.assign_advice(|| "acc", acc_col, row, || acc)?;
+                    totals.push(Number(total));
+                }
+                Ok(totals)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        prefix_sum: PrefixSumConfig,
+        instance: Column<Instance>,
+    }
+
+    /// Load `xs` privately and expose every running total.
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        xs: Vec<Value<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                xs: vec![Value::unknown(); self.xs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [meta.advice_column(), meta.advice_column()];
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                prefix_sum: PrefixSumChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = PrefixSumChip::construct(config.prefix_sum.clone());
+            let xs = layouter.assign_region(
+                || "load xs",
+                |mut region| {
+                    self.xs
+                        .iter()
+                        .enumerate()
+                        .map(|(row, x)| {
+                            region
+                                .assign_advice(|| "x", config.prefix_sum.advice[0], row, || *x)
+                                .map(Number)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                },
+            )?;
+            let totals = chip.prefix_sum(layouter.namespace(|| "scan"), &xs)?;
+            for (row, total) in totals.iter().enumerate() {
+                layouter.constrain_instance(total.0.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(xs: &[u64]) -> MyCircuit<Fp> {
+        MyCircuit {
+            xs: xs.iter().map(|x| Value::known(Fp::from(*x))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_prefix_sum() {
+        let k = 4;
+        let circuit = circuit(&[1, 2, 3, 4]);
+        let totals: Vec<Fp> = [1, 3, 6, 10].map(Fp::from).to_vec();
+        let prover = MockProver::run(k, &circuit, vec![totals.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        // Every intermediate total is checked, not just the last one.
+        for i in 0..totals.len() {
+            let mut wrong = totals.clone();
+            wrong[i] += Fp::one();
+            let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,333 @@
+/// Place a byte in a bucket of a sorted fixed table.
+///
+/// For a table `t_0 < t_1 < ... < t_{n-1}` of bytes, value `v` is in bucket
+/// `i` when
+///
+///     t_i <= v < t_{i+1}
+///
+/// Finding `i` is a search, which a circuit cannot run; the prover does the
+/// binary search out of circuit and witnesses `i`, and the chip only checks
+/// the answer. Two lookups into a table of `(i, t_i)` pairs tie `lo` and `hi`
+/// to entries `i` and `i + 1`, and two `LtChip` comparisons do the rest:
+/// `v < lo` must be false and `v < hi` true. Since the entries come out of
+/// the table, the prover can only pick which bucket, and exactly one fits.
+/// A value at or above the last entry is in no bucket.
+///
+/// A disabled row looks up `(0, 0)`, so the table needs that row too, but an
+/// enabled one must not reach it: with `i = -1` it would read `lo = 0` from
+/// it and `hi = t_0` from entry 0, and put a value below `t_0` in a bucket.
+/// Each table row carries a `real` flag, 0 on that row only, and the enabled
+/// lookups ask for 1. That keeps `i` and `i + 1` both in `0..n`.
+///
+/// | a0 | a1 | a2 | a3 | q_bucket | idx   | entry | real |
+/// |----|----|----|----|----------|-------|-------|------|
+/// | i  | lo | hi | v  |    1     | 0     | 0     | 0    |
+/// |    |    |    |    |          | 0     | t_0   | 1    |
+/// |    |    |    |    |          | ...   | ...   | ...  |
+/// |    |    |    |    |          | n-1   | t_n-1 | 1    |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use super::{
+    byte::ByteConfig,
+    lt::{LtChip, LtConfig},
+    Number,
+};
+
+#[derive(Debug, Clone)]
+pub struct SortedLookupConfig {
+    pub advice: [Column<Advice>; 4],
+    idx: TableColumn,
+    entry: TableColumn,
+    real: TableColumn,
+    lt: LtConfig,
+    q_bucket: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortedLookupChip<F: PrimeField> {
+    config: SortedLookupConfig,
+    table: Vec<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> SortedLookupChip<F> {
+    /// A chip for `table`, which must be strictly increasing bytes.
+    pub fn construct(config: SortedLookupConfig, table: Vec<u64>) -> Self {
+        assert!(
+            table.iter().all(|t| *t < 256),
+            "table entries must be bytes"
+        );
+        assert!(
+            table.windows(2).all(|w| w[0] < w[1]),
+            "table must be strictly increasing"
+        );
+        SortedLookupChip {
+            config,
+            table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Shares the byte table of `byte`, which the caller loads, for the
+    /// comparisons.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        constant: Column<Fixed>,
+        byte: &ByteConfig,
+    ) -> SortedLookupConfig {
+        meta.enable_constant(constant);
+        let lt = LtChip::configure(meta, advice, byte);
+        let idx = meta.lookup_table_column();
+        let entry = meta.lookup_table_column();
+        let real = meta.lookup_table_column();
+        let q_bucket = meta.complex_selector();
+        let [index, lo, hi, _] = advice;
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_bucket);
+            let index = meta.query_advice(index, Rotation::cur());
+            let lo = meta.query_advice(lo, Rotation::cur());
+            vec![(q.clone() * index, idx), (q.clone() * lo, entry), (q, real)]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_bucket);
+            let index = meta.query_advice(index, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                (q.clone() * (index + one), idx),
+                (q.clone() * hi, entry),
+                (q, real),
+            ]
+        });
+
+        SortedLookupConfig {
+            advice,
+            idx,
+            entry,
+            real,
+            lt,
+            q_bucket,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let config = &self.config;
+        layouter.assign_table(
+            || "sorted table",
+            |mut table| {
+                let rows = std::iter::once((0, 0, 0)).chain(
+                    self.table
+                        .iter()
+                        .enumerate()
+                        .map(|(i, t)| (i as u64, *t, 1)),
+                );
+                for (row, (idx, entry, real)) in rows.enumerate() {
+                    let (idx, entry, real) = (F::from(idx), F::from(entry), F::from(real));
+                    table.assign_cell(|| "idx", config.idx, row, || Value::known(idx))?;
+                    table.assign_cell(|| "entry", config.entry, row, || Value::known(entry))?;
+                    table.assign_cell(|| "real", config.real, row, || Value::known(real))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Check that the byte `value` is in bucket `index`, and return the
+    /// index cell.
+    pub fn bucket(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Number<F>,
+        index: Value<usize>,
+    ) -> Result<Number<F>, Error> {
+        // Out of range, the bounds are witnessed as zero and the lookups fail.
+        let entry = |i: usize| F::from(self.table.get(i).copied().unwrap_or(0));
+        let lo = index.map(entry);
+        let hi = index.map(|i| entry(i + 1));
+        let index = index.map(|i| F::from(i as u64));
+        self.bucket_with(layouter, value, index, lo, hi)
+    }
+
+    /// `bucket` with the index and both bounds witnessed as given, which
+    /// need not agree with the table.
+    fn bucket_with(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Number<F>,
+        index: Value<F>,
+        lo: Value<F>,
+        hi: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        let config = &self.config;
+        let [index_col, lo_col, hi_col, value_col] = config.advice;
+        let (index, lo, hi) = layouter.assign_region(
+            || "bucket",
+            |mut region| {
+                config.q_bucket.enable(&mut region, 0)?;
+                let index = region.assign_advice(|| "index", index_col, 0, || index)?;
+                let lo = region.assign_advice(|| "lo", lo_col, 0, || lo)?;
+                let hi = region.assign_advice(|| "hi", hi_col, 0, || hi)?;
+                value.0.copy_advice(|| "value", &mut region, value_col, 0)?;
+                Ok((Number(index), Number(lo), Number(hi)))
+            },
+        )?;
+
+        let lt = LtChip::construct(config.lt.clone());
+        let below_lo = lt.less_than(layouter.namespace(|| "v < lo"), value.clone(), lo)?;
+        let below_hi = lt.less_than(layouter.namespace(|| "v < hi"), value, hi)?;
+        layouter.assign_region(
+            || "lo <= v < hi",
+            |mut region| {
+                region.constrain_constant(below_lo.0.cell(), F::ZERO)?;
+                region.constrain_constant(below_hi.0.cell(), F::ONE)
+            },
+        )?;
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::byte::ByteChip;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    const TABLE: [u64; 4] = [0, 5, 10, 15];
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        byte: ByteConfig,
+        sorted: SortedLookupConfig,
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MyCircuit<F: PrimeField> {
+        table: Vec<u64>,
+        value: Value<F>,
+        index: Value<usize>,
+        /// Index, `lo` and `hi` to witness in place of the honest ones.
+        forged: Option<[F; 3]>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                table: self.table.clone(),
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            let constant = meta.fixed_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let byte = ByteChip::configure(meta, advice[0]);
+            let sorted = SortedLookupChip::configure(meta, advice, constant, &byte);
+            TestConfig {
+                byte,
+                sorted,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let byte = ByteChip::construct(config.byte);
+            let sorted = SortedLookupChip::construct(config.sorted, self.table.clone());
+            byte.load_table(layouter.namespace(|| "byte table"))?;
+            sorted.load_table(layouter.namespace(|| "sorted table"))?;
+            let value = byte.assign_byte(layouter.namespace(|| "value"), self.value)?;
+            let layouter = layouter.namespace(|| "bucket");
+            let index = match self.forged {
+                None => sorted.bucket(layouter, value, self.index)?,
+                Some(forged) => {
+                    let [index, lo, hi] = forged.map(Value::known);
+                    sorted.bucket_with(layouter, value, index, lo, hi)?
+                }
+            };
+            layouter.constrain_instance(index.0.cell(), config.instance, 0)
+        }
+    }
+
+    fn verify(value: u64, index: usize) -> bool {
+        let k = 9;
+        let circuit = MyCircuit {
+            table: TABLE.to_vec(),
+            value: Value::known(Fp::from(value)),
+            index: Value::known(index),
+            forged: None,
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(index as u64)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn verify_forged(table: &[u64], value: u64, [index, lo, hi]: [Fp; 3]) -> bool {
+        let k = 9;
+        let circuit = MyCircuit {
+            table: table.to_vec(),
+            value: Value::known(Fp::from(value)),
+            index: Value::unknown(),
+            forged: Some([index, lo, hi]),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![index]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_sorted_lookup() {
+        // 5 <= 7 < 10
+        assert!(verify(7, 1));
+        // Bucket edges are closed below and open above.
+        assert!(verify(5, 1));
+        assert!(verify(10, 2));
+    }
+
+    #[test]
+    fn test_sorted_lookup_wrong_index() {
+        assert!(!verify(7, 0));
+        assert!(!verify(7, 2));
+        // No bucket above the last entry, nor an index past the table.
+        assert!(!verify(20, 3));
+    }
+
+    #[test]
+    fn test_sorted_lookup_index_below_table() {
+        let table = [3, 5, 10, 15];
+        // The honest bounds, witnessed directly, go through.
+        assert!(verify_forged(
+            &table,
+            7,
+            [Fp::from(1), Fp::from(5), Fp::from(10)]
+        ));
+        // With index = -1, `lo` would be the 0 the disabled rows look up and
+        // `hi` entry 0, so 1 < 3 would land in a bucket below the table.
+        assert!(!verify_forged(
+            &table,
+            1,
+            [-Fp::from(1), Fp::from(0), Fp::from(3)]
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+/// Assign a (possibly very large) stream of witnesses row by row.
+///
+/// The usual way to load a vector is to move a `Vec<Value<F>>` into the
+/// `assign_region` closure. The floor planner calls that closure more than
+/// once (a shape pass and an assignment pass), so chips end up cloning the
+/// whole vector just to be able to read it twice.
+///
+/// `assign_stream` pulls one value at a time from an iterator instead, and
+/// gives every value its own 1-row region. `SimpleFloorPlanner` places each
+/// region at the first free row of `advice`, so the values still land on
+/// consecutive rows:
+///
+/// | row   | advice  |
+/// |-------|---------|
+/// |   0   |   v_0   |
+/// |   1   |   v_1   |
+/// |  ...  |   ...   |
+/// |  n-1  | v_(n-1) |
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+
+use super::Number;
+
+#[derive(Debug, Clone)]
+pub struct StreamAssignConfig {
+    pub advice: Column<Advice>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamAssignChip<F: Field> {
+    config: StreamAssignConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> StreamAssignChip<F> {
+    pub fn construct(config: StreamAssignConfig) -> Self {
+        StreamAssignChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: Column<Advice>) -> StreamAssignConfig {
+        meta.enable_equality(advice);
+        StreamAssignConfig { advice }
+    }
+
+    /// Assign every item of `iter` to the next free row of the advice column.
+    ///
+    /// Only the current item is held in memory while its region is laid out,
+    /// the iterator itself is consumed exactly once.
+    pub fn assign_stream(
+        &self,
+        mut layouter: impl Layouter<F>,
+        iter: impl Iterator<Item = Value<F>>,
+    ) -> Result<Vec<Number<F>>, Error> {
+        let mut cells = Vec::with_capacity(iter.size_hint().0);
+        for (i, value) in iter.enumerate() {
+            let cell = layouter.assign_region(
+                || format!("stream row {}", i),
+                |mut region| {
+                    region
+                        .assign_advice(|| "stream value", self.config.advice, 0, || value)
+                        .map(Number)
+                },
+            )?;
+            cells.push(cell);
+        }
+        Ok(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Constraints, Expression, Instance, Selector},
+        poly::Rotation,
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        stream: StreamAssignConfig,
+        instance: Column<Instance>,
+        q_step: Selector,
+    }
+
+    /// Streams `1, 2, ..., len` without ever materialising them in a `Vec`.
+    #[derive(Default)]
+    struct StreamCircuit {
+        len: u64,
+    }
+
+    impl Circuit<Fp> for StreamCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let q_step = meta.selector();
+            let stream = StreamAssignChip::configure(meta, advice);
+
+            // | advice | q_step |
+            // |--------|--------|
+            // |  prev  |        |
+            // |  cur   |   1    |     cur = prev + 1
+            meta.create_gate("consecutive rows", |meta| {
+                let q_step = meta.query_selector(q_step);
+                let prev = meta.query_advice(advice, Rotation::prev());
+                let cur = meta.query_advice(advice, Rotation::cur());
+                Constraints::with_selector(
+                    q_step,
+                    vec![cur - prev - Expression::Constant(Fp::one())],
+                )
+            });
+
+            TestConfig {
+                stream,
+                instance,
+                q_step,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = StreamAssignChip::construct(config.stream.clone());
+            let values = (1..=self.len).map(|i| Value::known(Fp::from(i)));
+            let cells = chip.assign_stream(layouter.namespace(|| "stream"), values)?;
+            assert_eq!(cells.len() as u64, self.len);
+
+            // This region only touches the selector column, so the floor planner
+            // slots it in at row 0, right alongside the streamed values. The gate
+            // then checks that row r holds exactly one more than row r - 1.
+            layouter.assign_region(
+                || "check consecutive rows",
+                |mut region| {
+                    for offset in 1..cells.len() {
+                        config.q_step.enable(&mut region, offset)?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            let first = &cells[0];
+            let last = &cells[cells.len() - 1];
+            layouter.constrain_instance(first.0.cell(), config.instance, 0)?;
+            layouter.constrain_instance(last.0.cell(), config.instance, 1)
+        }
+    }
+
+    #[test]
+    fn test_assign_stream_consecutive_rows() {
+        let k = 8;
+        let circuit = StreamCircuit { len: 100 };
+
+        let public_inputs = vec![Fp::from(1), Fp::from(100)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        prover.assert_satisfied();
+
+        let public_inputs = vec![Fp::from(1), Fp::from(99)];
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
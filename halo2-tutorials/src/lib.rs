@@ -0,0 +1,18 @@
+#![allow(unused_variables)]
+
+mod chap_1;
+mod chap_2;
+mod chap_3;
+mod chap_4;
+mod chap_5;
+mod chap_6;
+mod chap_7;
+
+pub mod analysis;
+pub mod gadgets;
+pub mod proving;
+pub mod utils;
+
+/// The exercise 5 circuit, for the fuzz target in `fuzz/`.
+#[cfg(feature = "chap_2_exercise_5")]
+pub use chap_2::exercise_5::{MyCircuit as Exercise5Circuit, SimpleConfig};
@@ -1,10 +1,5 @@
 #![allow(unused_variables)]
 
-mod chap_1;
-mod chap_2;
-mod chap_3;
-mod chap_4;
-
 pub mod exercise;
 
 #[macro_use]
@@ -0,0 +1,216 @@
+/// Folding two claims into one, Nova-style, over the exercise 5 statement.
+///
+/// Incrementally verifiable computation proves a long chain of steps without
+/// a proof per step: each step folds its claim into an accumulator, and only
+/// the accumulator is proven at the end. This module shows the folding alone,
+/// with everything in the clear: no commitments, no circuit checking the
+/// fold, no recursion.
+///
+/// The statement is exercise 5's `out = (a^2 * b^2 * c + c)^3` for a constant
+/// `c`, as a rank-1 constraint system over
+///
+///   z = (u, out, a, b, ab, ab2, d, e, e2)
+///
+/// with one row `A z * B z = C z` per product:
+///
+///   a   * b   = ab
+///   ab  * ab  = ab2
+///   c*ab2 * u = d
+///   (d + c*u) * u = e
+///   e   * e   = e2
+///   e2  * e   = out
+///
+/// Every `A z`, `B z`, `C z` is linear in `z`, with `u = 1` standing in for
+/// the constant 1. Folding needs the relaxed form
+///
+///   A z * B z = u * C z + E
+///
+/// with a slack `u` and an error vector `E`; a fresh claim has `u = 1` and
+/// `E = 0`. Two claims fold with a challenge `r` into
+///
+///   z = z_1 + r * z_2,    E = E_1 + r * T + r^2 * E_2
+///
+/// where the cross term `T = A z_1 * B z_2 + A z_2 * B z_1 - u_1 * C z_2 -
+/// u_2 * C z_1` absorbs everything the product mixes. Both sides are then
+/// quadratic in `r`, and agree at every power of it exactly when both claims
+/// held. With `r` chosen after `T` is fixed, a false claim survives the fold
+/// only if `r` hits one of at most two roots.
+///
+/// In Nova the verifier only sees commitments to `z`'s witness part, `E` and
+/// `T`, and folds those; here `r` hashes the claims themselves.
+use halo2_proofs::{
+    arithmetic::Field,
+    pasta::{group::ff::PrimeField, Fp},
+};
+use sha2::{Digest, Sha256};
+
+/// Entries of `z`.
+pub const Z: usize = 9;
+/// Rows of the constraint system.
+pub const ROWS: usize = 6;
+
+const U: usize = 0;
+const OUT: usize = 1;
+const A: usize = 2;
+const B: usize = 3;
+const AB: usize = 4;
+const AB2: usize = 5;
+const D: usize = 6;
+const E: usize = 7;
+const E2: usize = 8;
+
+/// A relaxed claim: `z` satisfies the rows of the statement for `c`, up to
+/// the slack `u = z[0]` and the error `e`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accumulator {
+    pub c: Fp,
+    pub z: [Fp; Z],
+    pub e: [Fp; ROWS],
+}
+
+impl Accumulator {
+    /// The fresh claim of exercise 5 for `a`, `b` and `c`.
+    pub fn new(a: Fp, b: Fp, c: Fp) -> Self {
+        let ab = a * b;
+        let ab2 = ab * ab;
+        let d = ab2 * c;
+        let e = d + c;
+        let e2 = e * e;
+        Accumulator {
+            c,
+            z: [Fp::ONE, e2 * e, a, b, ab, ab2, d, e, e2],
+            e: [Fp::ZERO; ROWS],
+        }
+    }
+
+    pub fn u(&self) -> Fp {
+        self.z[U]
+    }
+
+    /// The public output.
+    pub fn out(&self) -> Fp {
+        self.z[OUT]
+    }
+
+    /// `(A z, B z, C z)` for every row.
+    fn rows(&self) -> [(Fp, Fp, Fp); ROWS] {
+        let z = &self.z;
+        let c = self.c;
+        [
+            (z[A], z[B], z[AB]),
+            (z[AB], z[AB], z[AB2]),
+            (c * z[AB2], z[U], z[D]),
+            (z[D] + c * z[U], z[U], z[E]),
+            (z[E], z[E], z[E2]),
+            (z[E2], z[E], z[OUT]),
+        ]
+    }
+
+    /// `A z * B z = u * C z + E`, row by row.
+    pub fn is_satisfied(&self) -> bool {
+        let u = self.u();
+        self.rows()
+            .iter()
+            .zip(self.e)
+            .all(|((a, b, c), e)| *a * b == u * c + e)
+    }
+}
+
+/// The cross term of folding `acc` with `other`.
+fn cross_term(acc: &Accumulator, other: &Accumulator) -> [Fp; ROWS] {
+    let (u_1, u_2) = (acc.u(), other.u());
+    let (rows_1, rows_2) = (acc.rows(), other.rows());
+    std::array::from_fn(|i| {
+        let (a_1, b_1, c_1) = rows_1[i];
+        let (a_2, b_2, c_2) = rows_2[i];
+        a_1 * b_2 + a_2 * b_1 - u_1 * c_2 - u_2 * c_1
+    })
+}
+
+/// The challenge: 248 bits of SHA-256 over both claims and the cross term.
+fn challenge(acc: &Accumulator, other: &Accumulator, t: &[Fp; ROWS]) -> Fp {
+    let mut hasher = Sha256::new();
+    for claim in [acc, other] {
+        for x in [claim.c].iter().chain(&claim.z).chain(&claim.e) {
+            hasher.update(x.to_repr());
+        }
+    }
+    for x in t {
+        hasher.update(x.to_repr());
+    }
+    let digest = hasher.finalize();
+    let mut repr = [0; 32];
+    repr[..31].copy_from_slice(&digest[..31]);
+    Fp::from_repr(repr).unwrap()
+}
+
+/// One claim for both: the fold holds if `acc` and `other` did, and fails
+/// but with negligible probability otherwise.
+pub fn fold(acc: Accumulator, other: Accumulator) -> Accumulator {
+    assert_eq!(acc.c, other.c, "claims about different statements");
+    let t = cross_term(&acc, &other);
+    let r = challenge(&acc, &other, &t);
+    let r2 = r.square();
+    Accumulator {
+        c: acc.c,
+        z: std::array::from_fn(|i| acc.z[i] + r * other.z[i]),
+        e: std::array::from_fn(|i| acc.e[i] + r * t[i] + r2 * other.e[i]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> [Accumulator; 2] {
+        let c = Fp::from(2);
+        [
+            Accumulator::new(Fp::from(2), Fp::from(3), c),
+            Accumulator::new(Fp::from(5), Fp::from(7), c),
+        ]
+    }
+
+    #[test]
+    fn test_fold() {
+        let [first, second] = claims();
+        assert!(first.is_satisfied() && second.is_satisfied());
+        // exercise 5's output for a = 2, b = 3, c = 2.
+        assert_eq!(first.out(), Fp::from(74u64.pow(3)));
+
+        let t = cross_term(&first, &second);
+        let r = challenge(&first, &second, &t);
+        let folded = fold(first.clone(), second.clone());
+        assert!(folded.is_satisfied());
+        // The accumulated claim is the random combination of the two.
+        assert_eq!(folded.u(), Fp::ONE + r);
+        assert_eq!(folded.out(), first.out() + r * second.out());
+        assert_ne!(folded.e, [Fp::ZERO; ROWS]);
+
+        // An accumulator folds like a fresh claim.
+        let third = Accumulator::new(Fp::from(11), Fp::from(13), first.c);
+        assert!(fold(folded, third).is_satisfied());
+    }
+
+    #[test]
+    fn test_fold_false_claim() {
+        let [first, mut second] = claims();
+        second.z[OUT] += Fp::ONE;
+        assert!(!second.is_satisfied());
+        assert!(!fold(first.clone(), second.clone()).is_satisfied());
+        assert!(!fold(second, first).is_satisfied());
+    }
+
+    #[test]
+    fn test_fold_without_cross_term() {
+        // Without T the fold of two true claims is false: E must absorb the
+        // terms where the claims mix.
+        let [first, second] = claims();
+        let mut folded = fold(first.clone(), second.clone());
+        let t = cross_term(&first, &second);
+        let r = challenge(&first, &second, &t);
+        for (e, t) in folded.e.iter_mut().zip(t) {
+            *e -= r * t;
+        }
+        assert!(!folded.is_satisfied());
+    }
+}
@@ -0,0 +1,119 @@
+/// More blinding rows for any circuit.
+///
+/// A real proof hides the witness by filling the last rows of every advice
+/// column with random values, enough of them that the evaluations the
+/// verifier opens reveal nothing. halo2 sizes that tail from the
+/// `ConstraintSystem`: it reserves `max(3, q) + 2` rows, where `q` is the
+/// largest number of rotations any advice column is queried at. So the only
+/// knob is the configuration itself, and `BlindedCircuit` turns it by adding
+/// an advice column that a gate, never switched on, queries at `QUERIES`
+/// rotations. The wrapped circuit's own layout is left as it is, but has
+/// that many fewer usable rows.
+///
+/// `QUERIES` must be at least 1: a gate with no queries has no constraint,
+/// which `create_gate` rejects, so `BlindedCircuit<C, 0>` fails to compile.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, ConstraintSystem, Constraints, Error},
+    poly::{commitment::Params, Rotation},
+};
+
+use crate::utils::prover::{Prover, RealProverBackend};
+
+/// `C` with `QUERIES` advice queries' worth of blinding rows.
+#[derive(Default)]
+pub struct BlindedCircuit<C, const QUERIES: usize>(pub C);
+
+impl<C, const QUERIES: usize> BlindedCircuit<C, QUERIES> {
+    /// Evaluated in `configure`, so `QUERIES = 0` is a compile error.
+    const HAS_QUERIES: () = assert!(QUERIES > 0, "BlindedCircuit needs QUERIES > 0");
+}
+
+impl<F: Field, C: Circuit<F>, const QUERIES: usize> Circuit<F> for BlindedCircuit<C, QUERIES> {
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        BlindedCircuit(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let _ = Self::HAS_QUERIES;
+        let config = C::configure(meta);
+        let padding = meta.advice_column();
+        let s_pad = meta.selector();
+        meta.create_gate("blinding padding", |meta| {
+            let s_pad = meta.query_selector(s_pad);
+            let queries = (0..QUERIES as i32)
+                .map(|i| meta.query_advice(padding, Rotation(i)))
+                .reduce(|acc, q| acc + q)
+                .expect("QUERIES > 0");
+            Constraints::with_selector(s_pad, Some(queries))
+        });
+        config
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Prove `circuit` with `QUERIES` advice queries' worth of blinding rows and
+/// return the proof bytes. The blinding values come from `OsRng`, so two
+/// calls on the same witness give different proofs.
+pub fn prove_blinded<C: Circuit<Fp>, const QUERIES: usize>(
+    params: &Params<EqAffine>,
+    circuit: C,
+    instances: &[&[Fp]],
+) -> Result<Vec<u8>, Error> {
+    let circuit = BlindedCircuit::<C, QUERIES>(circuit);
+    let backend = RealProverBackend::with_params(params.clone(), &circuit)?;
+    backend.prove(circuit, instances)
+}
+
+#[cfg(all(test, feature = "chap_2_exercise_5"))]
+mod tests {
+    use super::*;
+    use crate::chap_2::exercise_5::MyCircuit;
+    use halo2_proofs::circuit::Value;
+
+    fn circuit() -> (MyCircuit<Fp>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let circuit = MyCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, (c * a.square() * b.square() + c).cube())
+    }
+
+    #[test]
+    fn test_blinded_proofs_differ() {
+        // The padding column is queried at 8 rotations, so 10 rows are
+        // blinding instead of 5.
+        let mut meta = ConstraintSystem::<Fp>::default();
+        BlindedCircuit::<MyCircuit<Fp>, 8>::configure(&mut meta);
+        assert_eq!(meta.blinding_factors(), 10);
+        let mut meta = ConstraintSystem::<Fp>::default();
+        MyCircuit::<Fp>::configure(&mut meta);
+        assert_eq!(meta.blinding_factors(), 5);
+
+        let k = 5;
+        let params: Params<EqAffine> = Params::new(k);
+        let proofs = [(); 2].map(|_| {
+            let (circuit, out) = circuit();
+            prove_blinded::<_, 8>(&params, circuit, &[&[out]]).unwrap()
+        });
+        // Same witness, same statement, different randomness.
+        assert_ne!(proofs[0], proofs[1]);
+
+        let (circuit, out) = circuit();
+        let backend =
+            RealProverBackend::with_params(params, &BlindedCircuit::<_, 8>(circuit)).unwrap();
+        for proof in &proofs {
+            assert!(backend.verify(proof, &[&[out]]).is_ok());
+        }
+    }
+}
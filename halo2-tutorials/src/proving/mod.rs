@@ -0,0 +1,3 @@
+/// Proving techniques beyond a single proof, at toy scale.
+pub mod accumulation;
+pub mod blinding;
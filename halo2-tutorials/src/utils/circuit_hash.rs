@@ -0,0 +1,264 @@
+/// A fingerprint of a circuit's shape, for versioning.
+///
+/// A verifying key is only good for the circuit it was generated from: move
+/// a gate, add a column, and every proof made before no longer verifies. The
+/// key itself takes a full keygen to compare, but its shape is decided in
+/// `Circuit::configure`, before any witness or parameter exists. Hashing
+/// that configuration gives a value to pin in a test, or to ship next to a
+/// deployed key, that changes whenever the layout does.
+///
+/// What goes in, as text fed to SHA-256, one item a line:
+///
+///   k, the number of advice, fixed and instance columns and of selectors,
+///   the number of fixed columns holding constants,
+///   every gate by name, with its polynomials,
+///   every lookup, with the number of its table columns and its expressions
+///
+/// Polynomials are written out in full, columns by index and rotation and
+/// selectors by their index, so a changed coefficient or a query moved by
+/// one row changes the hash.
+///
+/// The contents of fixed columns and lookup tables are only assigned at
+/// synthesis: `lookup 1` is a one-column table whether it holds 256 rows or
+/// 2^16. `hash_circuit` takes a circuit and covers them too. It replays the
+/// synthesis through the recording `Assignment` of
+/// `analysis::lookup_analysis` and adds, after the lines above, every
+/// assigned fixed cell, tables and constants included, and every enabled
+/// selector:
+///
+///   fixed, by column and row, with its value,
+///   selector, by index and row
+///
+/// so a byte table shrunk to 255 rows hashes differently. The copy
+/// constraints are in neither hash; pin the verifying key itself for those.
+/// Constants and values print with their field's `Debug`, so a hash is only
+/// comparable across builds with the same `halo2_proofs`.
+use halo2_proofs::{
+    arithmetic::Field,
+    pasta::group::ff::PrimeField,
+    plonk::{Circuit, ConstraintSystem, Error, Expression},
+};
+use sha2::{Digest, Sha256};
+
+use crate::analysis::lookup_analysis::Recorder;
+
+/// `expr` as text, parenthesised so the tree can be read back.
+fn write_expression<F: Field>(expr: &Expression<F>) -> String {
+    expr.evaluate(
+        &|c| format!("c({:?})", c),
+        &|s| format!("{:?}", s),
+        &|q| format!("f{}@{}", q.column_index(), q.rotation().0),
+        &|q| format!("a{}@{}", q.column_index(), q.rotation().0),
+        &|q| format!("i{}@{}", q.column_index(), q.rotation().0),
+        &|a| format!("-({})", a),
+        &|a, b| format!("({} + {})", a, b),
+        &|a, b| format!("({} * {})", a, b),
+        &|a, c| format!("({} * c({:?}))", a, c),
+    )
+}
+
+/// The configuration of `C`, as the lines that get hashed.
+pub fn describe_constraint_system<F: Field, C: Circuit<F>>(k: u32) -> Vec<String> {
+    let mut cs = ConstraintSystem::<F>::default();
+    C::configure(&mut cs);
+
+    let mut lines = vec![
+        format!("k {}", k),
+        format!("advice {}", cs.num_advice_columns()),
+        format!("fixed {}", cs.num_fixed_columns()),
+        format!("instance {}", cs.num_instance_columns()),
+        format!("selectors {}", cs.num_selectors()),
+        format!("constants {}", cs.constants().len()),
+    ];
+    for gate in cs.gates() {
+        lines.push(format!("gate {}", gate.name()));
+        for poly in gate.polynomials() {
+            lines.push(format!("poly {}", write_expression(poly)));
+        }
+    }
+    for lookup in cs.lookups() {
+        // The table's width, not its number of rows.
+        lines.push(format!("lookup {}", lookup.table_expressions().len()));
+        for expr in lookup.input_expressions() {
+            lines.push(format!("input {}", write_expression(expr)));
+        }
+        for expr in lookup.table_expressions() {
+            lines.push(format!("table {}", write_expression(expr)));
+        }
+    }
+    lines
+}
+
+/// The configuration of `C`, then the fixed cells and selectors that
+/// synthesizing `circuit` over `2^k` rows assigns, as the lines that get
+/// hashed.
+pub fn describe_circuit<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+) -> Result<Vec<String>, Error> {
+    let (recorder, _) = Recorder::synthesize(k, circuit)?;
+    let mut lines = describe_constraint_system::<F, C>(k);
+
+    let mut fixed: Vec<_> = recorder.fixed.iter().collect();
+    fixed.sort_by_key(|(cell, _)| **cell);
+    for ((column, row), value) in fixed {
+        lines.push(format!("fixed {} {} {:?}", column, row, value));
+    }
+    let mut selectors: Vec<_> = recorder
+        .selectors
+        .iter()
+        .map(|(selector, row)| (selector.index(), *row))
+        .collect();
+    selectors.sort();
+    for (selector, row) in selectors {
+        lines.push(format!("selector {} {}", selector, row));
+    }
+    Ok(lines)
+}
+
+fn hash_lines(lines: Vec<String>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finalize().into()
+}
+
+/// SHA-256 of the configuration of `C` at size `k`.
+pub fn hash_constraint_system<F: Field, C: Circuit<F>>(k: u32) -> [u8; 32] {
+    hash_lines(describe_constraint_system::<F, C>(k))
+}
+
+/// SHA-256 of the configuration of `C` at size `k` and of the fixed cells and
+/// selectors `circuit` assigns.
+pub fn hash_circuit<F: PrimeField, C: Circuit<F>>(k: u32, circuit: &C) -> Result<[u8; 32], Error> {
+    describe_circuit(k, circuit).map(hash_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::MyCircuit as SimpleCircuit;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Advice, Column, Selector, TableColumn},
+        poly::Rotation,
+    };
+
+    #[test]
+    fn test_hash_is_deterministic() {
+        let hash = hash_constraint_system::<Fp, SimpleCircuit<Fp>>(5);
+        assert_eq!(hash, hash_constraint_system::<Fp, SimpleCircuit<Fp>>(5));
+        assert_ne!(hash, hash_constraint_system::<Fp, SimpleCircuit<Fp>>(6));
+    }
+
+    /// A value looked up in a table holding `0..rows`.
+    struct TableCircuit {
+        rows: u64,
+    }
+
+    impl Circuit<Fp> for TableCircuit {
+        type Config = (Column<Advice>, Selector, TableColumn);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TableCircuit { rows: self.rows }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let q = meta.complex_selector();
+            let table = meta.lookup_table_column();
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q);
+                let v = meta.query_advice(advice, Rotation::cur());
+                vec![(q * v, table)]
+            });
+            (advice, q, table)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, q, table): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "table",
+                |mut t| {
+                    for row in 0..self.rows {
+                        let value = Value::known(Fp::from(row));
+                        t.assign_cell(|| "entry", table, row as usize, || value)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "value",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "v", advice, 0, || Value::known(Fp::from(7)))
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hash_covers_table_contents() {
+        let k = 9;
+        let (short, full) = (TableCircuit { rows: 255 }, TableCircuit { rows: 256 });
+        // The configuration alone cannot tell the two tables apart...
+        let n = describe_constraint_system::<Fp, TableCircuit>(k).len();
+        let (short_lines, full_lines) = (
+            describe_circuit(k, &short).unwrap(),
+            describe_circuit(k, &full).unwrap(),
+        );
+        assert_eq!(short_lines[..n], full_lines[..n]);
+        // ...but their contents can.
+        let short_hash = hash_circuit(k, &short).unwrap();
+        assert_eq!(short_hash, hash_circuit(k, &short).unwrap());
+        assert_ne!(short_hash, hash_circuit(k, &full).unwrap());
+
+        assert!(full_lines.contains(&format!("fixed 0 255 {:?}", Fp::from(255))));
+        assert!(full_lines.contains(&"selector 0 0".to_string()));
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_hash() {
+        use crate::chap_2::exercise_5::MyCircuit;
+
+        let lines = describe_constraint_system::<Fp, MyCircuit<Fp>>(5);
+        assert_eq!(
+            lines,
+            [
+                "k 5",
+                "advice 3",
+                "fixed 1",
+                "instance 1",
+                "selectors 1",
+                "constants 1",
+                "gate complex_gate",
+                "poly (Selector(0, true) * (((((((a0@0 * a1@0) * (a0@0 * a1@0)) * a2@0) + a2@0) \
+                 * ((((a0@0 * a1@0) * (a0@0 * a1@0)) * a2@0) + a2@0)) \
+                 * ((((a0@0 * a1@0) * (a0@0 * a1@0)) * a2@0) + a2@0)) + -(a0@1)))",
+            ]
+        );
+
+        // If this fails, exercise 5's layout changed: every key made for it
+        // so far is stale. Update the value only if that was intended.
+        let hash = hash_constraint_system::<Fp, MyCircuit<Fp>>(5);
+        assert_eq!(hex(&hash), EXERCISE_5_HASH);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    const EXERCISE_5_HASH: &str =
+        "2974812df228339e68c9dd8fd0a0a8dfc9a08ec43fcd3337cebf8dc4602efef9";
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -0,0 +1,246 @@
+/// Share a `SimpleConfig` between processes as JSON.
+///
+/// A column is only an index plus its type, and a selector an index plus
+/// whether it is simple, so that is all the layout records, together with the
+/// names of the gates `SimpleChip::configure` creates:
+///
+/// ```json
+/// {
+///   "advice": [0, 1],
+///   "instance": 0,
+///   "s_mul": { "index": 0, "simple": true },
+///   "s_add": { "index": 1, "simple": true },
+///   "s_cub": { "index": 2, "simple": true },
+///   "gates": ["mul_gate", "add_gate", "cub_gate"]
+/// }
+/// ```
+///
+/// `halo2_proofs` keeps the column and selector constructors private, but a
+/// `ConstraintSystem` hands them out with consecutive indices, so allocating
+/// on a scratch one rebuilds exactly the same values. The recovered config is
+/// only meaningful against a constraint system built by the same
+/// `SimpleChip::configure`; the gate names are there to catch a peer running
+/// a different version of it.
+///
+/// Indices are checked against the columns and selectors `SimpleChip`
+/// allocates, so a malformed layout is an error rather than a search for a
+/// column that does not exist.
+use halo2_proofs::{
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Instance, Selector},
+};
+use serde::{de::Error as _, Deserialize, Serialize};
+
+use crate::chap_2::simple_chip::{SimpleChip, SimpleConfig};
+
+/// The gates `SimpleChip::configure` creates, in order.
+pub(crate) const SIMPLE_GATES: [&str; 3] = ["mul_gate", "add_gate", "cub_gate"];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SelectorLayout {
+    index: usize,
+    simple: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SimpleLayout {
+    advice: [usize; 2],
+    instance: usize,
+    s_mul: SelectorLayout,
+    s_add: SelectorLayout,
+    s_cub: SelectorLayout,
+    gates: Vec<String>,
+}
+
+fn new_selector(cs: &mut ConstraintSystem<Fp>, simple: bool) -> Selector {
+    if simple {
+        cs.selector()
+    } else {
+        cs.complex_selector()
+    }
+}
+
+/// The constraint system `SimpleChip::configure` builds, for its counts.
+fn simple_cs() -> ConstraintSystem<Fp> {
+    let mut cs = ConstraintSystem::default();
+    SimpleChip::configure(&mut cs);
+    cs
+}
+
+impl TryFrom<Selector> for SelectorLayout {
+    type Error = serde_json::Error;
+
+    fn try_from(selector: Selector) -> Result<Self, Self::Error> {
+        // `Selector` does not expose its index, but it compares by it.
+        let simple = selector.is_simple();
+        let mut cs = ConstraintSystem::default();
+        let index = (0..simple_cs().num_selectors())
+            .find(|_| new_selector(&mut cs, simple) == selector)
+            .ok_or_else(|| serde_json::Error::custom("selector is not one of SimpleChip's"))?;
+        Ok(SelectorLayout { index, simple })
+    }
+}
+
+impl SelectorLayout {
+    fn selector(&self) -> Selector {
+        let mut cs = ConstraintSystem::default();
+        (0..=self.index)
+            .map(|_| new_selector(&mut cs, self.simple))
+            .last()
+            .unwrap()
+    }
+}
+
+fn advice_column(index: usize) -> Column<Advice> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    (0..=index).map(|_| cs.advice_column()).last().unwrap()
+}
+
+fn instance_column(index: usize) -> Column<Instance> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    (0..=index).map(|_| cs.instance_column()).last().unwrap()
+}
+
+impl SimpleConfig {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let layout = SimpleLayout {
+            advice: self.advice.map(|col| col.index()),
+            instance: self.instance.index(),
+            s_mul: self.s_mul.try_into()?,
+            s_add: self.s_add.try_into()?,
+            s_cub: self.s_cub.try_into()?,
+            gates: SIMPLE_GATES.iter().map(|name| name.to_string()).collect(),
+        };
+        serde_json::to_string(&layout)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let layout: SimpleLayout = serde_json::from_str(json)?;
+        if layout.gates != SIMPLE_GATES {
+            return Err(serde_json::Error::custom(format!(
+                "gates {:?} do not match SimpleChip's {:?}",
+                layout.gates, SIMPLE_GATES
+            )));
+        }
+        let cs = simple_cs();
+        let in_range = layout.advice.iter().all(|&i| i < cs.num_advice_columns())
+            && layout.instance < cs.num_instance_columns()
+            && [&layout.s_mul, &layout.s_add, &layout.s_cub]
+                .iter()
+                .all(|s| s.index < cs.num_selectors());
+        if !in_range {
+            return Err(serde_json::Error::custom(
+                "column or selector index out of SimpleChip's range",
+            ));
+        }
+        Ok(SimpleConfig {
+            advice: layout.advice.map(advice_column),
+            instance: instance_column(layout.instance),
+            s_mul: layout.s_mul.selector(),
+            s_add: layout.s_add.selector(),
+            s_cub: layout.s_cub.selector(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::{MyCircuit, SimpleChip};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, Error},
+    };
+
+    fn configured() -> (ConstraintSystem<Fp>, SimpleConfig) {
+        let mut cs = ConstraintSystem::default();
+        let config = SimpleChip::configure(&mut cs);
+        (cs, config)
+    }
+
+    /// `MyCircuit`, synthesized with a config decoded from JSON rather than the
+    /// one `configure` returned.
+    struct RecoveredCircuit {
+        inner: MyCircuit<Fp>,
+        json: String,
+    }
+
+    impl Circuit<Fp> for RecoveredCircuit {
+        type Config = ();
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            RecoveredCircuit {
+                inner: MyCircuit::default(),
+                json: self.json.clone(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SimpleChip::configure(meta);
+        }
+
+        fn synthesize(&self, _: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let config = SimpleConfig::from_json(&self.json).unwrap();
+            self.inner.synthesize(config, layouter)
+        }
+    }
+
+    #[test]
+    fn test_gate_names() {
+        let (cs, _) = configured();
+        let names: Vec<_> = cs.gates().iter().map(|gate| gate.name()).collect();
+        assert_eq!(names, SIMPLE_GATES);
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let (_, config) = configured();
+        let json = config.to_json().unwrap();
+        let recovered = SimpleConfig::from_json(&json).unwrap();
+        assert_eq!(format!("{:?}", recovered), format!("{:?}", config));
+        assert_eq!(recovered.to_json().unwrap(), json);
+
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let circuit = RecoveredCircuit {
+            inner: MyCircuit {
+                c,
+                a: Value::known(a),
+                b: Value::known(b),
+            },
+            json,
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_config_gate_mismatch() {
+        let (_, config) = configured();
+        let json = config.to_json().unwrap().replace("cub_gate", "square_gate");
+        assert!(SimpleConfig::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_config_index_out_of_range() {
+        let (_, config) = configured();
+        let json = config.to_json().unwrap();
+        let huge = json.replace(
+            r#""s_cub":{"index":2"#,
+            &format!(r#""s_cub":{{"index":{}"#, u64::MAX),
+        );
+        assert_ne!(huge, json);
+        assert!(SimpleConfig::from_json(&huge).is_err());
+
+        // A selector SimpleChip never allocates has no index to write.
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let foreign = (0..8).map(|_| cs.selector()).last().unwrap();
+        let config = SimpleConfig {
+            s_cub: foreign,
+            ..config
+        };
+        assert!(config.to_json().is_err());
+    }
+}
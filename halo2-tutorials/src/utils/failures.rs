@@ -0,0 +1,107 @@
+/// Assert which constraints a circuit fails, not just that it fails.
+///
+/// A negative test that checks `verify().is_err()` passes for any failure,
+/// including one the test never meant to cause: a typo in the instance, a
+/// region that ran out of rows. `expect_failures` runs `MockProver` and
+/// matches the failures it reports, as their `Display` text, against a list
+/// of substrings. Every failure must contain one of them, and every one of
+/// them must be in some failure, so an extra failure or a missing one both
+/// fail the test.
+///
+/// The text names gates as `('gate name')`, constraints by index or name and
+/// cells by column and region, e.g.
+///
+///   Constraint 0 in gate 0 ('complex_gate') is not satisfied in Region 0
+///   ('load private & witness') at offset 0
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Circuit};
+
+/// The failures of `circuit` on `instances`, as text. Empty if it verifies.
+pub fn failures<C: Circuit<Fp>>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>) -> Vec<String> {
+    let prover = MockProver::run(k, circuit, instances).expect("synthesis failed");
+    match prover.verify() {
+        Ok(()) => vec![],
+        Err(failures) => failures.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+/// Panic unless the failures of `circuit` are exactly those described by
+/// `expected`, each a substring of one or more failures. An empty `expected`
+/// asserts that the circuit verifies.
+pub fn expect_failures<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<Fp>>,
+    expected: &[&str],
+) {
+    let failures = failures(k, circuit, instances);
+    let unexpected: Vec<_> = failures
+        .iter()
+        .filter(|f| !expected.iter().any(|e| f.contains(e)))
+        .collect();
+    let missing: Vec<_> = expected
+        .iter()
+        .filter(|e| !failures.iter().any(|f| f.contains(*e)))
+        .collect();
+    assert!(
+        unexpected.is_empty() && missing.is_empty(),
+        "unexpected failures: {:#?}\nexpected but missing: {:?}",
+        unexpected,
+        missing
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::MyCircuit as SimpleCircuit;
+    use halo2_proofs::circuit::Value;
+
+    fn simple_circuit() -> (SimpleCircuit<Fp>, Fp) {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let circuit = SimpleCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        (circuit, Fp::from(74u64.pow(3)))
+    }
+
+    #[test]
+    fn test_expect_failures() {
+        let (circuit, out) = simple_circuit();
+        expect_failures(5, &circuit, vec![vec![out]], &[]);
+        // A wrong public output breaks the copy to the instance, and nothing
+        // else.
+        expect_failures(
+            5,
+            &circuit,
+            vec![vec![out + Fp::one()]],
+            &["Equality constraint not satisfied"],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected but missing")]
+    fn test_expect_failures_missing() {
+        let (circuit, out) = simple_circuit();
+        expect_failures(5, &circuit, vec![vec![out]], &["is not satisfied"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected failures")]
+    fn test_expect_failures_unexpected() {
+        let (circuit, out) = simple_circuit();
+        expect_failures(5, &circuit, vec![vec![out + Fp::one()]], &["('mul_gate')"]);
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_expect_failures_exercise_5() {
+        use crate::chap_2::exercise_5_fixtures::corrupted_circuit;
+
+        // `out` is the honest one, so the copy to the instance holds: the
+        // complex gate is all that fails.
+        let (circuit, out) = corrupted_circuit();
+        expect_failures(5, &circuit, vec![vec![out]], &["('complex_gate')"]);
+    }
+}
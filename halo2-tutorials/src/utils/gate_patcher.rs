@@ -0,0 +1,112 @@
+/// Check one gate at one row, from outside the chip that defines it.
+///
+/// When a third-party chip's constraint fails, `MockProver` names the gate
+/// and the row, but the chip's source is not ours to sprinkle with asserts.
+/// `assert_gate_evaluates_to_zero` does the assert from the outside: it
+/// looks the gate up by name in the circuit's `ConstraintSystem` and
+/// evaluates each of its polynomials against the witness at the given row,
+/// so a test can pin down a gate on a circuit that `MockProver` accepts, or
+/// print the offending constraint values of one it rejects.
+///
+/// `MockProver` keeps its cell values to itself, so the witness comes from
+/// replaying synthesis the way it does, through the recording `Assignment`
+/// of `analysis::lookup_analysis`. Instance cells read as zero there, which
+/// only matters for gates that query the instance column. A gate whose
+/// selector is off at `row` evaluates to zero trivially.
+use halo2_proofs::{
+    pasta::group::ff::PrimeField,
+    plonk::{Circuit, Error},
+};
+
+use crate::analysis::lookup_analysis::Recorder;
+
+/// Every constraint of the gate `gate_name`, by name, evaluated at `row`.
+/// Panics if the circuit has no such gate.
+pub fn evaluate_gate<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    gate_name: &str,
+    row: usize,
+) -> Result<Vec<(String, F)>, Error> {
+    let (recorder, cs) = Recorder::synthesize(k, circuit)?;
+    let gate = cs
+        .gates()
+        .iter()
+        .find(|gate| gate.name() == gate_name)
+        .unwrap_or_else(|| panic!("no gate named {:?}", gate_name));
+    Ok(gate
+        .polynomials()
+        .iter()
+        .enumerate()
+        .map(|(i, poly)| {
+            let name = match gate.constraint_name(i) {
+                "" => format!("constraint {}", i),
+                name => name.to_string(),
+            };
+            (name, recorder.evaluate(poly, row))
+        })
+        .collect())
+}
+
+/// Panic, listing the offending constraints, unless every polynomial of the
+/// gate `gate_name` evaluates to zero at `row`.
+pub fn assert_gate_evaluates_to_zero<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    gate_name: &str,
+    row: usize,
+) {
+    let values = evaluate_gate(k, circuit, gate_name, row).expect("synthesis failed");
+    let failing: Vec<_> = values
+        .iter()
+        .filter(|(_, value)| !bool::from(value.is_zero()))
+        .collect();
+    assert!(
+        failing.is_empty(),
+        "gate {:?} at row {}: {:?}",
+        gate_name,
+        row,
+        failing
+    );
+}
+
+#[cfg(all(test, feature = "chap_2_exercise_5"))]
+mod tests {
+    use super::*;
+    use crate::chap_2::{exercise_5::MyCircuit, exercise_5_fixtures::corrupted_circuit};
+    use halo2_proofs::{circuit::Value, pasta::Fp};
+
+    #[test]
+    fn test_gate_patcher_exercise_5() {
+        let k = 5;
+        let circuit = MyCircuit {
+            c: Fp::from(2),
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+        };
+        assert_gate_evaluates_to_zero(k, &circuit, "complex_gate", 0);
+        // Off the selector's row the gate holds trivially.
+        assert_gate_evaluates_to_zero(k, &circuit, "complex_gate", 1);
+    }
+
+    #[test]
+    fn test_gate_patcher_corrupted() {
+        let k = 5;
+        let (circuit, _) = corrupted_circuit();
+        let values = evaluate_gate(k, &circuit, "complex_gate", 0).unwrap();
+        assert_eq!(values.len(), 1);
+        assert_ne!(values[0].1, Fp::zero());
+
+        let result = std::panic::catch_unwind(|| {
+            assert_gate_evaluates_to_zero(k, &circuit, "complex_gate", 0)
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "no gate named")]
+    fn test_gate_patcher_unknown_gate() {
+        let circuit = MyCircuit::<Fp>::default();
+        assert_gate_evaluates_to_zero(5, &circuit, "no such gate", 0);
+    }
+}
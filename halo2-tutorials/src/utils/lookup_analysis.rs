@@ -0,0 +1,73 @@
+/// Lookup usage histograms, one per lookup, keyed by table row.
+///
+/// `analysis::lookup_analysis` keeps every lookup of a circuit in one map
+/// keyed by `(lookup, row)`. Most circuits look up a single table, and then
+/// the lookup index is noise: `lookup_usage_histograms` splits the map so
+/// that each lookup gets a `row -> count` histogram of its own.
+use std::collections::HashMap;
+
+use halo2_proofs::{
+    pasta::group::ff::PrimeField,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+pub use crate::analysis::lookup_analysis::{format_histogram, lookup_usage_histogram};
+
+/// Synthesize `circuit` over `2^k` rows and count how often each table row is
+/// queried. Entry `i` is the histogram of the `i`-th lookup in the constraint
+/// system, with unused rows present with a count of 0.
+pub fn lookup_usage_histograms<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+) -> Result<Vec<HashMap<usize, usize>>, Error> {
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+    let mut histograms = vec![HashMap::new(); cs.lookups().len()];
+    for ((lookup, row), count) in lookup_usage_histogram(k, circuit)? {
+        histograms[lookup].insert(row, count);
+    }
+    Ok(histograms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_4::{circuit_1, circuit_2};
+    use halo2_proofs::{circuit::Value, pasta::Fp, plonk::Assigned};
+
+    #[test]
+    fn test_circuit_1_histogram() {
+        // Checks 0, 1, 2, 3, 4 against a table holding 0..16, one per row.
+        let circuit = circuit_1::MyCircuit::<Fp, 16, 5>::default();
+        let histograms = lookup_usage_histograms(5, &circuit).unwrap();
+
+        let expected: HashMap<usize, usize> = (0..16)
+            .map(|row| (row, if row < 5 { 1 } else { 0 }))
+            .collect();
+        assert_eq!(histograms, vec![expected]);
+    }
+
+    #[test]
+    fn test_circuit_2_histogram() {
+        // The table holds `(1, 0)` in row 0, then `(bits(v), v)` in row `v`.
+        let values = [(1, 1), (2, 3), (2, 3), (4, 9)];
+        let circuit = circuit_2::MyCircuit::<Fp, 4, 15> {
+            num_bits: values.iter().map(|(bits, _)| *bits).collect(),
+            values: values
+                .iter()
+                .map(|(_, v)| Value::known(Assigned::from(Fp::from(*v))))
+                .collect(),
+        };
+        let histograms = lookup_usage_histograms(5, &circuit).unwrap();
+
+        let mut expected: HashMap<usize, usize> = (0..16).map(|row| (row, 0)).collect();
+        expected.insert(1, 1);
+        expected.insert(3, 2);
+        expected.insert(9, 1);
+        assert_eq!(histograms, vec![expected]);
+        assert!(
+            format_histogram(&lookup_usage_histogram(5, &circuit).unwrap())
+                .contains("  0     0: 0 (unused)\n")
+        );
+    }
+}
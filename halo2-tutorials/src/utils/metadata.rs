@@ -0,0 +1,250 @@
+/// Name, version and author of a circuit, kept in its constraint system and
+/// pinned by its verifying key.
+///
+/// An audit needs to know which version of a circuit a key or a proof came
+/// from. The version in `Cargo.toml` says nothing about a circuit whose
+/// layout changed without a release, so the metadata goes where the layout
+/// is, in two places:
+///
+/// - A gate, added by `embed_in_circuit` during `Circuit::configure`: named
+///   after the metadata, with one constraint per field, each named by the
+///   field's value and constrained to `0`. This is what
+///   `from_constraint_system` reads back and what `circuit_hash` sees. The
+///   verifying key does not: it keeps the gates' polynomials, not their
+///   names, so on its own the gate leaves keys and proofs unchanged. Nor is
+///   it free: the prover still evaluates the zero polynomials when it builds
+///   the quotient.
+/// - A fixed column, holding the SHA-256 of the metadata in its first row,
+///   assigned by `assign` during `synthesize`. The verifying key commits to
+///   every fixed column, so keys for two versions differ, and a proof for
+///   one does not verify under the other's key.
+///
+/// This `halo2_proofs` has no column annotations, hence the gate. Gate and
+/// constraint names are `&'static str`: each distinct string is leaked once
+/// and reused by every later `configure`.
+use std::{collections::BTreeSet, fmt, sync::Mutex};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    pasta::group::ff::PrimeField,
+    plonk::{Column, ConstraintSystem, Error, Expression, Fixed},
+};
+use sha2::{Digest, Sha256};
+
+/// The start of the metadata gate's name.
+const GATE: &str = "circuit metadata";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitMetadata {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub description: String,
+}
+
+/// The fixed column holding the metadata's digest.
+#[derive(Debug, Clone)]
+pub struct MetadataConfig {
+    column: Column<Fixed>,
+}
+
+/// `s` as a `&'static str`, leaked the first time it is seen.
+fn intern(s: &str) -> &'static str {
+    static INTERNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut interned = INTERNED.lock().unwrap();
+    match interned.get(s) {
+        Some(s) => *s,
+        None => {
+            let s: &'static str = Box::leak(s.to_owned().into_boxed_str());
+            interned.insert(s);
+            s
+        }
+    }
+}
+
+impl CircuitMetadata {
+    /// Add the metadata gate and the digest column to `meta`. Call it once,
+    /// from `configure`, and `assign` the column in `synthesize`.
+    pub fn embed_in_circuit<F: Field>(&self, meta: &mut ConstraintSystem<F>) -> MetadataConfig {
+        let name = intern(&format!("{}: {}", GATE, self));
+        let fields =
+            [&self.name, &self.version, &self.author, &self.description].map(|field| intern(field));
+        meta.create_gate(name, |_| {
+            fields.map(|field| (field, Expression::Constant(F::ZERO)))
+        });
+        MetadataConfig {
+            column: meta.fixed_column(),
+        }
+    }
+
+    /// SHA-256 of the metadata, as a field element.
+    pub fn digest<F: PrimeField>(&self) -> F {
+        let hash = Sha256::digest(self.to_string().as_bytes());
+        hash.iter().fold(F::ZERO, |acc, byte| {
+            acc * F::from(256) + F::from(u64::from(*byte))
+        })
+    }
+
+    /// Write the digest into the first row of the metadata column.
+    pub fn assign<F: PrimeField>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        config: &MetadataConfig,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "circuit metadata",
+            |mut region| {
+                region.assign_fixed(
+                    || "digest",
+                    config.column,
+                    0,
+                    || Value::known(self.digest()),
+                )?;
+                Ok(())
+            },
+        )
+    }
+
+    /// The metadata embedded in `meta`, if any.
+    pub fn from_constraint_system<F: Field>(meta: &ConstraintSystem<F>) -> Option<Self> {
+        let gate = meta
+            .gates()
+            .iter()
+            .find(|gate| gate.name().starts_with(GATE))?;
+        let field = |i| gate.constraint_name(i).to_owned();
+        Some(CircuitMetadata {
+            name: field(0),
+            version: field(1),
+            author: field(2),
+            description: field(3),
+        })
+    }
+}
+
+impl fmt::Display for CircuitMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} by {}: {}",
+            self.name, self.version, self.author, self.description
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::{MyCircuit as SimpleCircuit, SimpleConfig};
+    use crate::utils::circuit_hash::hash_constraint_system;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        pasta::{EqAffine, Fp},
+        plonk::{keygen_vk, Circuit},
+        poly::commitment::Params,
+    };
+
+    fn metadata(version: u8) -> CircuitMetadata {
+        CircuitMetadata {
+            name: "simple chip".to_owned(),
+            version: format!("0.{}.0", version),
+            author: "halo2 step by step".to_owned(),
+            description: "out = (a^2 * b^2 * c + c)^3".to_owned(),
+        }
+    }
+
+    /// The simple chip circuit, as version `0.V.0`.
+    #[derive(Default)]
+    struct Versioned<const V: u8>(SimpleCircuit<Fp>);
+
+    impl<const V: u8> Circuit<Fp> for Versioned<V> {
+        type Config = (SimpleConfig, MetadataConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let metadata = metadata(V).embed_in_circuit(meta);
+            (SimpleCircuit::configure(meta), metadata)
+        }
+
+        fn synthesize(
+            &self,
+            (config, metadata_config): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            metadata(V).assign(layouter.namespace(|| "metadata"), &metadata_config)?;
+            self.0.synthesize(config, layouter)
+        }
+    }
+
+    #[test]
+    fn test_metadata_after_configure() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        Versioned::<1>::configure(&mut meta);
+        assert_eq!(
+            CircuitMetadata::from_constraint_system(&meta),
+            Some(metadata(1))
+        );
+        assert_eq!(
+            metadata(1).to_string(),
+            "simple chip 0.1.0 by halo2 step by step: out = (a^2 * b^2 * c + c)^3"
+        );
+
+        let mut meta = ConstraintSystem::<Fp>::default();
+        SimpleCircuit::<Fp>::configure(&mut meta);
+        assert_eq!(CircuitMetadata::from_constraint_system(&meta), None);
+    }
+
+    #[test]
+    fn test_metadata_changes_hash() {
+        let v1 = hash_constraint_system::<Fp, Versioned<1>>(5);
+        let v2 = hash_constraint_system::<Fp, Versioned<2>>(5);
+        let bare = hash_constraint_system::<Fp, SimpleCircuit<Fp>>(5);
+        // Same gates and columns otherwise: only the metadata differs.
+        assert_ne!(v1, v2);
+        assert_ne!(v1, bare);
+        assert_eq!(v1, hash_constraint_system::<Fp, Versioned<1>>(5));
+    }
+
+    #[test]
+    fn test_metadata_gate_is_inert() {
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (a * a * b * b * c + c).cube();
+        let circuit = Versioned::<1>(SimpleCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        });
+        let prover = MockProver::run(5, &circuit, vec![vec![out]]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(5, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_metadata_pinned_by_vk() {
+        let params: Params<EqAffine> = Params::new(5);
+        let v1 = keygen_vk(&params, &Versioned::<1>::default()).unwrap();
+        let v2 = keygen_vk(&params, &Versioned::<2>::default()).unwrap();
+        // Only the digest column tells the two keys apart.
+        assert_ne!(v1.fixed_commitments(), v2.fixed_commitments());
+        assert_ne!(metadata(1).digest::<Fp>(), metadata(2).digest::<Fp>());
+    }
+
+    #[test]
+    fn test_metadata_interned() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        Versioned::<1>::configure(&mut meta);
+        let mut again = ConstraintSystem::<Fp>::default();
+        Versioned::<1>::configure(&mut again);
+        // The second `configure` reuses the first one's strings.
+        assert!(std::ptr::eq(
+            meta.gates()[0].name(),
+            again.gates()[0].name()
+        ));
+    }
+}
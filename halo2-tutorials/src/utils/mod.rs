@@ -0,0 +1,16 @@
+/// Helpers for wiring chips together that are not gadgets in their own right.
+pub mod circuit_hash;
+#[cfg(feature = "serde")]
+pub mod config_serde;
+pub mod failures;
+pub mod gate_patcher;
+pub mod lookup_analysis;
+pub mod metadata;
+pub mod parallel;
+#[cfg(feature = "dev-graph")]
+pub mod plot;
+pub mod proof_io;
+pub mod prover;
+pub mod rebind;
+pub mod test_vectors;
+pub mod with_constant;
@@ -0,0 +1,184 @@
+/// Circuit layout plots, as SVG.
+///
+/// The `plot_*` tests each draw their own circuit to a PNG. `registry` lists
+/// the circuits that can be drawn from outside their module, and
+/// `render_all` draws every one of them to `<dir>/<name>.svg`, for the
+/// `render_all` binary:
+///
+/// $ cargo run --bin render_all --features dev-graph,chap_2_exercise_4,chap_2_exercise_5,chap_3_exercise_6
+///
+/// Exercises behind a feature are only in the registry with that feature on.
+/// Chap 1's exercises 1 to 3 keep their circuit inside their test module,
+/// where their hints' line numbers point; each is a copy of `chap_1_simple`
+/// with a piece missing, and `chap_1_simple` stands in for them here.
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use halo2_proofs::{circuit::Value, dev::CircuitLayout, pasta::Fp, plonk::Circuit};
+use plotters::prelude::*;
+
+use crate::{chap_1, chap_2, chap_3, chap_4};
+
+pub type PlotResult<T> = Result<T, Box<dyn Error>>;
+
+/// One circuit of the registry: `render` draws it to the given path.
+pub struct Plot {
+    pub name: &'static str,
+    pub render: Box<dyn Fn(&Path) -> PlotResult<()>>,
+}
+
+impl Plot {
+    fn new<C: Circuit<Fp> + Default + 'static>(
+        name: &'static str,
+        title: &'static str,
+        k: u32,
+    ) -> Self {
+        Self::with(name, title, k, C::default)
+    }
+
+    /// For circuits whose layout depends on their fields, such as a number
+    /// of rows: `circuit` builds the instance to draw.
+    fn with<C: Circuit<Fp> + 'static>(
+        name: &'static str,
+        title: &'static str,
+        k: u32,
+        circuit: impl Fn() -> C + 'static,
+    ) -> Self {
+        Plot {
+            name,
+            render: Box::new(move |path| render_svg(k, &circuit(), title, path)),
+        }
+    }
+}
+
+/// Draw the layout of `circuit` to the SVG file at `path`.
+pub fn render_svg<C: Circuit<Fp>>(k: u32, circuit: &C, title: &str, path: &Path) -> PlotResult<()> {
+    let root = SVGBackend::new(path, (1024, 768)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled(title, ("sans-serif", 60))?;
+    CircuitLayout::default()
+        .show_labels(true)
+        .render(k, circuit, &root)?;
+    root.present()?;
+    Ok(())
+}
+
+/// Every circuit `render_all` draws, by file name.
+pub fn registry() -> Vec<Plot> {
+    #[allow(unused_mut)]
+    let mut plots = vec![
+        Plot::new::<chap_1::simple::MyCircuit<Fp>>(
+            "chap_1_simple",
+            "Simple Circuit without chip",
+            5,
+        ),
+        Plot::new::<chap_1::exercise_line::LineCircuit<Fp>>(
+            "chap_1_exercise_line",
+            "Point on Line",
+            4,
+        ),
+        Plot::new::<chap_2::custom_gate::MyCircuit<Fp>>(
+            "chap_2_custom_gate",
+            "Simple_3gates Circuit without chip",
+            5,
+        ),
+        Plot::new::<chap_2::simple_chip::MyCircuit<Fp>>(
+            "chap_2_simple_chip",
+            "Simple_ship Circuit chip",
+            4,
+        ),
+        Plot::with("chap_3_fibo_1", "Fibo Circuit", 4, || {
+            let mut circuit = chap_3::circuit_1::FiboCircuit::<Fp>::default();
+            circuit.nrow = 10;
+            circuit
+        }),
+        Plot::with("chap_3_fibo_2", "Fibo Circuit", 4, || {
+            let mut circuit = chap_3::circuit_2::FiboCircuit::<Fp>::default();
+            circuit.nrow = 14;
+            circuit
+        }),
+        Plot::new::<chap_3::exercise_conditional::ConditionalCircuit<Fp>>(
+            "chap_3_exercise_conditional",
+            "If-else in one gate",
+            4,
+        ),
+        Plot::new::<chap_3::exercise_copy_constraint_network::NetworkCircuit<Fp>>(
+            "chap_3_copy_constraint_network",
+            "Copy Constraint Network",
+            4,
+        ),
+        Plot::new::<chap_3::exercise_rectangle::RectangleCircuit<Fp, true>>(
+            "chap_3_exercise_rectangle",
+            "Point in Rectangle",
+            9,
+        ),
+        Plot::with(
+            "chap_4_1_col_rangecheck_lookup",
+            "1_col_rangecheck_lookup",
+            5,
+            chap_4::circuit_1::MyCircuit::<Fp, 16, 5>::default,
+        ),
+        Plot::with(
+            "chap_4_multi_cols_rangecheck_lookup",
+            "Lookup2 Circuit",
+            5,
+            || {
+                // Every value of 1 to 4 bits, tagged with its bit length.
+                let num_bits: Vec<u8> = (1..=4u8)
+                    .flat_map(|bits| vec![bits; 1 << (bits - 1)])
+                    .collect();
+                let values = (1..16u64)
+                    .map(|v| Value::known(Fp::from(v)).into())
+                    .collect();
+                chap_4::circuit_2::MyCircuit::<Fp, 4, 15> { num_bits, values }
+            },
+        ),
+        Plot::with(
+            "chap_4_lookup_on_different_rows",
+            "Simple Lookup Circuit",
+            5,
+            || chap_4::circuit_3::MyCircuit::<Fp> {
+                a: [0, 1, 2, 3, 4].map(|v| Value::known(Fp::from(v))).to_vec(),
+                b: [0, 0, 1, 2, 3, 4]
+                    .map(|v| Value::known(Fp::from(v)))
+                    .to_vec(),
+            },
+        ),
+    ];
+    #[cfg(feature = "chap_2_exercise_4")]
+    plots.push(Plot::new::<chap_2::exercise_4::MyCircuit<Fp>>(
+        "chap_2_exercise_4",
+        "Simple_ship Circuit chip",
+        4,
+    ));
+    #[cfg(feature = "chap_2_exercise_5")]
+    plots.push(Plot::new::<chap_2::exercise_5::MyCircuit<Fp>>(
+        "chap_2_exercise_5",
+        "chip-complex-gate",
+        4,
+    ));
+    #[cfg(feature = "chap_3_exercise_6")]
+    plots.push(Plot::with("chap_3_exercise_6", "Fibo Circuit", 4, || {
+        let mut circuit = chap_3::exercise_6::FiboCircuit::<Fp>::default();
+        circuit.nrow = 20;
+        circuit
+    }));
+    plots
+}
+
+/// Draw every circuit of the registry into `dir`, creating it if need be,
+/// and return the files written.
+pub fn render_all(dir: &Path) -> PlotResult<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    registry()
+        .into_iter()
+        .map(|plot| {
+            let path = dir.join(format!("{}.svg", plot.name));
+            (plot.render)(&path)?;
+            Ok(path)
+        })
+        .collect()
+}
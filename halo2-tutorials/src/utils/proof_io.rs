@@ -0,0 +1,93 @@
+/// Create a real proof, save it, and verify it again from disk.
+///
+/// `MockProver` only checks that a witness satisfies the constraints. A real
+/// proof goes through key generation, `create_proof` and `verify_proof`, with
+/// the proof itself being the bytes of the prover's transcript; that is all
+/// that needs to be written to disk. The verifier rebuilds the transcript from
+/// those bytes with the same hash, here Blake2b. Proving goes through
+/// `RealProverBackend`; verifying only needs what a verifier holds, the
+/// parameters and the verifying key, and calls `verify_proof` on them.
+use std::{fs, path::Path};
+
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{verify_proof, Circuit, Error, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+
+use super::prover::{Prover, RealProverBackend};
+
+/// Prove `circuit` with `backend` and write the proof to `path`.
+/// `instances` holds the values of each instance column.
+pub fn create_and_save_proof<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(
+    backend: &RealProverBackend<C, ConcreteCircuit>,
+    circuit: ConcreteCircuit,
+    instances: &[&[C::Scalar]],
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let proof = backend.prove(circuit, instances)?;
+    fs::write(path, proof).map_err(Error::Transcript)
+}
+
+/// Read the proof at `path` and verify it under `params` and `vk` against
+/// `instances`.
+pub fn load_and_verify_proof<C: CurveAffine>(
+    params: &Params<C>,
+    vk: &VerifyingKey<C>,
+    instances: &[&[C::Scalar]],
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let proof = fs::read(path).map_err(Error::Transcript)?;
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(&proof[..]);
+    verify_proof(params, vk, strategy, &[instances], &mut transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_proof_roundtrip() {
+        use crate::chap_2::exercise_5::MyCircuit;
+        use halo2_proofs::{
+            arithmetic::Field,
+            circuit::Value,
+            pasta::{EqAffine, Fp},
+        };
+
+        let k = 5;
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let circuit = MyCircuit {
+            c,
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+
+        let backend = RealProverBackend::<EqAffine, _>::new(k, &circuit).unwrap();
+
+        // One file per process and run, so that parallel runs do not read
+        // each other's proofs.
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let name = format!(
+            "halo2_tutorials_exercise_5_{}_{}.proof",
+            std::process::id(),
+            nanos
+        );
+        let path = std::env::temp_dir().join(name);
+        create_and_save_proof(&backend, circuit, &[&[out]], &path).unwrap();
+        let (params, vk) = (backend.params(), backend.vk());
+        assert!(load_and_verify_proof(params, vk, &[&[out]], &path).is_ok());
+
+        // The same proof does not verify for another public output.
+        let wrong = out + Fp::one();
+        assert!(load_and_verify_proof(params, vk, &[&[wrong]], &path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
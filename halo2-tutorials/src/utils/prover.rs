@@ -0,0 +1,153 @@
+/// One interface for checking a circuit with `MockProver` and for proving it
+/// for real.
+///
+/// Exercises start out on `MockProver`, which takes the witness and checks
+/// each constraint against it. A real proof needs more: parameters, keys from
+/// `keygen_vk`/`keygen_pk`, and a transcript that `create_proof` writes and
+/// `verify_proof` reads (see `proof_io`). `Prover` hides that difference, so
+/// switching a test over is a change of backend:
+///
+///   let backend = MockProverBackend::new(k);
+///   let backend = RealProverBackend::<EqAffine, _>::new(k, &circuit)?;
+///
+/// `RealProverBackend` is the one place in the crate that calls
+/// `create_proof`: proof files, proof sizes and blinded proofs all go
+/// through it.
+///
+/// The halo2 release used here only ships the IPA commitment scheme, with
+/// Pasta curves: `RealProverBackend` is generic over the curve, not the
+/// scheme.
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field},
+    dev::MockProver,
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey,
+        SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+
+/// Prove a circuit and verify the proof. `instances` holds the values of
+/// each instance column.
+pub trait Prover<F: Field, C: Circuit<F>> {
+    type Proof;
+
+    fn prove(&self, circuit: C, instances: &[&[F]]) -> Result<Self::Proof, Error>;
+
+    fn verify(&self, proof: &Self::Proof, instances: &[&[F]]) -> Result<(), Error>;
+}
+
+/// `MockProver` behind `Prover`. There is no commitment scheme, so nothing
+/// stands in for the witness: the "proof" is the circuit itself, and
+/// verifying it runs `MockProver` on it with the instances. Any failing
+/// constraint is `Error::ConstraintSystemFailure`; run `MockProver` directly
+/// to see which.
+#[derive(Debug, Clone)]
+pub struct MockProverBackend<F: Field, C: Circuit<F>> {
+    k: u32,
+    _marker: PhantomData<(F, C)>,
+}
+
+impl<F: Field, C: Circuit<F>> MockProverBackend<F, C> {
+    pub fn new(k: u32) -> Self {
+        MockProverBackend {
+            k,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: Field + Ord, C: Circuit<F>> Prover<F, C> for MockProverBackend<F, C> {
+    type Proof = C;
+
+    fn prove(&self, circuit: C, _: &[&[F]]) -> Result<C, Error> {
+        Ok(circuit)
+    }
+
+    fn verify(&self, circuit: &C, instances: &[&[F]]) -> Result<(), Error> {
+        let instances = instances.iter().map(|column| column.to_vec()).collect();
+        let prover = MockProver::run(self.k, circuit, instances)?;
+        prover.verify().map_err(|_| Error::ConstraintSystemFailure)
+    }
+}
+
+/// `create_proof` and `verify_proof` behind `Prover`, with IPA parameters
+/// for the curve `C` and a Blake2b transcript. The proof is the transcript's
+/// bytes.
+#[derive(Debug)]
+pub struct RealProverBackend<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>> {
+    params: Params<C>,
+    pk: ProvingKey<C>,
+    _marker: PhantomData<ConcreteCircuit>,
+}
+
+impl<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>> RealProverBackend<C, ConcreteCircuit> {
+    /// Set up parameters for `2^k` rows and generate the keys.
+    pub fn new(k: u32, circuit: &ConcreteCircuit) -> Result<Self, Error> {
+        Self::with_params(Params::new(k), circuit)
+    }
+
+    /// Generate the keys for `circuit` under existing parameters.
+    ///
+    /// Keygen drops the witness of `circuit`, but keeps everything that ends
+    /// up fixed, constants included. So the keys come from `circuit` itself,
+    /// not from `circuit.without_witnesses()`: exercise 5's `MyCircuit`
+    /// resets its constant `c` there, and keys for `c = 0` reject every
+    /// proof for its real `c` (see chap 2's `keygen_shape`).
+    pub fn with_params(params: Params<C>, circuit: &ConcreteCircuit) -> Result<Self, Error> {
+        let vk = keygen_vk(&params, circuit)?;
+        let pk = keygen_pk(&params, vk, circuit)?;
+        Ok(RealProverBackend {
+            params,
+            pk,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn params(&self) -> &Params<C> {
+        &self.params
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<C> {
+        self.pk.get_vk()
+    }
+}
+
+impl<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>> Prover<C::Scalar, ConcreteCircuit>
+    for RealProverBackend<C, ConcreteCircuit>
+{
+    type Proof = Vec<u8>;
+
+    fn prove(
+        &self,
+        circuit: ConcreteCircuit,
+        instances: &[&[C::Scalar]],
+    ) -> Result<Vec<u8>, Error> {
+        let mut transcript = Blake2bWrite::<_, C, Challenge255<_>>::init(vec![]);
+        create_proof(
+            &self.params,
+            &self.pk,
+            &[circuit],
+            &[instances],
+            OsRng,
+            &mut transcript,
+        )?;
+        Ok(transcript.finalize())
+    }
+
+    fn verify(&self, proof: &Vec<u8>, instances: &[&[C::Scalar]]) -> Result<(), Error> {
+        let strategy = SingleVerifier::new(&self.params);
+        let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(&proof[..]);
+        verify_proof(
+            &self.params,
+            self.pk.get_vk(),
+            strategy,
+            &[instances],
+            &mut transcript,
+        )
+    }
+}
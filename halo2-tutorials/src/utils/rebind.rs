@@ -0,0 +1,188 @@
+/// Re-bind a cell into a fresh region.
+///
+/// `region.constrain_equal` only sees the cells of its own region. To carry a
+/// value assigned elsewhere into a new region, copy it: `copy_advice` assigns
+/// a fresh cell and adds a copy constraint between the two, which is why every
+/// advice column of `SimpleConfig` has equality enabled.
+///
+/// | a0   | a1 |
+/// |------|----|
+/// | src' |    |   copy of `src`
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{Layouter, Value},
+    plonk::Error,
+};
+
+use crate::chap_2::simple_chip::SimpleConfig;
+use crate::gadgets::Number;
+
+pub(crate) fn rebind<F: Field>(
+    mut layouter: impl Layouter<F>,
+    src: Number<F>,
+    config: &SimpleConfig,
+) -> Result<Number<F>, Error> {
+    let value = src.0.value().copied();
+    rebind_with(layouter, src, config, value)
+}
+
+/// `rebind`, spelled out as `copy_advice` does it, with `value` in the new
+/// cell. Tests put a value other than `src`'s there.
+fn rebind_with<F: Field>(
+    mut layouter: impl Layouter<F>,
+    src: Number<F>,
+    config: &SimpleConfig,
+    value: Value<F>,
+) -> Result<Number<F>, Error> {
+    layouter.assign_region(
+        || "rebind",
+        |mut region| {
+            let dst = region.assign_advice(|| "rebind", config.advice[0], 0, || value)?;
+            region.constrain_equal(src.0.cell(), dst.cell())?;
+            Ok(Number(dst))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chap_2::simple_chip::SimpleChip;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem},
+    };
+    use std::marker::PhantomData;
+
+    /// Load `src` from instance row 0, rebind it, and expose the copy at row 1.
+    #[derive(Default)]
+    struct MyCircuit<F: Field> {
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = SimpleConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            SimpleChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let src = layouter.assign_region(
+                || "load src",
+                |mut region| {
+                    region
+                        .assign_advice_from_instance(
+                            || "src",
+                            config.instance,
+                            0,
+                            config.advice[0],
+                            0,
+                        )
+                        .map(Number)
+                },
+            )?;
+            let dst = rebind(layouter.namespace(|| "rebind"), src.clone(), &config)?;
+            src.0
+                .value()
+                .zip(dst.0.value())
+                .assert_if_known(|(src, dst)| src == dst);
+            layouter.constrain_instance(dst.0.cell(), config.instance, 1)
+        }
+    }
+
+    /// Load `src` from instance row 0 and rebind it with `dst` in the copy.
+    /// The copy is not exposed, so only the constraint `rebind` adds ties
+    /// it to `src`.
+    struct TamperedCircuit {
+        dst: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for TamperedCircuit {
+        type Config = SimpleConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TamperedCircuit {
+                dst: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SimpleChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let src = layouter.assign_region(
+                || "load src",
+                |mut region| {
+                    region
+                        .assign_advice_from_instance(
+                            || "src",
+                            config.instance,
+                            0,
+                            config.advice[0],
+                            0,
+                        )
+                        .map(Number)
+                },
+            )?;
+            rebind_with(layouter.namespace(|| "rebind"), src, &config, self.dst)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rebind() {
+        let k = 4;
+        let circuit = MyCircuit::<Fp>::default();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(7), Fp::from(7)]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_rebind_copy_constraint() {
+        let k = 4;
+        let instance = vec![vec![Fp::from(7)]];
+        let honest = TamperedCircuit {
+            dst: Value::known(Fp::from(7)),
+        };
+        MockProver::run(k, &honest, instance.clone())
+            .unwrap()
+            .assert_satisfied();
+
+        // The copy holds 8 where src is 7.
+        let tampered = TamperedCircuit {
+            dst: Value::known(Fp::from(8)),
+        };
+        let errors = MockProver::run(k, &tampered, instance)
+            .unwrap()
+            .verify()
+            .unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|e| matches!(e, VerifyFailure::Permutation { .. })));
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            VerifyFailure::Permutation {
+                location: location @ FailureLocation::InRegion { .. },
+                ..
+            } if location.to_string().contains("('rebind')")
+        )));
+    }
+}
@@ -0,0 +1,208 @@
+/// Reproducible test cases that can be handed to someone else.
+///
+/// A `TestVector` is everything `MockProver` needs besides the circuit's code:
+/// the private inputs, the public inputs, `k`, and whether the prover should
+/// accept. Field elements are stored as the hex of their little-endian
+/// representation, so a vector survives a JSON round trip exactly:
+///
+/// ```json
+/// {
+///   "private_inputs": ["0200...00", "0300...00", "0200...00"],
+///   "public_inputs": ["e82e0600...00"],
+///   "k": 5,
+///   "expected_pass": true
+/// }
+/// ```
+use halo2_proofs::{dev::MockProver, pasta::group::ff::PrimeField, plonk::Circuit};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TestVector<F: PrimeField> {
+    #[serde(with = "hex_fields")]
+    pub private_inputs: Vec<F>,
+    #[serde(with = "hex_fields")]
+    pub public_inputs: Vec<F>,
+    pub k: u32,
+    pub expected_pass: bool,
+}
+
+impl<F: PrimeField> TestVector<F> {
+    /// Build the circuit from the private inputs with `circuit`, run
+    /// `MockProver` on it, and assert that it passes exactly when expected.
+    pub fn run<C: Circuit<F>>(&self, circuit: impl Fn(&[F]) -> C) {
+        let circuit = circuit(&self.private_inputs);
+        let prover = MockProver::run(self.k, &circuit, vec![self.public_inputs.clone()]).unwrap();
+        let passed = prover.verify().is_ok();
+        assert_eq!(
+            passed, self.expected_pass,
+            "test vector {:?} expected pass = {}",
+            self, self.expected_pass
+        );
+    }
+}
+
+/// Record a test vector from a circuit: build it from `private_inputs` with
+/// `circuit`, run `MockProver` against `public_inputs`, and keep whether it
+/// passed as `expected_pass`.
+pub fn test_vector_from_circuit<F: PrimeField, C: Circuit<F>>(
+    k: u32,
+    private_inputs: Vec<F>,
+    public_inputs: Vec<F>,
+    circuit: impl Fn(&[F]) -> C,
+) -> TestVector<F> {
+    let prover =
+        MockProver::run(k, &circuit(&private_inputs), vec![public_inputs.clone()]).unwrap();
+    TestVector {
+        expected_pass: prover.verify().is_ok(),
+        private_inputs,
+        public_inputs,
+        k,
+    }
+}
+
+mod hex_fields {
+    use halo2_proofs::pasta::group::ff::PrimeField;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<F: PrimeField, S: Serializer>(
+        fields: &[F],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(fields.iter().map(|f| {
+            f.to_repr()
+                .as_ref()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        }))
+    }
+
+    pub fn deserialize<'de, F: PrimeField, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<F>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|hex| {
+                let mut repr = F::Repr::default();
+                let bytes = repr.as_mut();
+                if hex.len() != 2 * bytes.len() {
+                    return Err(D::Error::custom(format!("wrong length: {}", hex)));
+                }
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    // `get` rather than indexing: a multi-byte character
+                    // would put a slice boundary inside it.
+                    let digits = hex
+                        .get(2 * i..2 * i + 2)
+                        .ok_or_else(|| D::Error::custom(format!("not hex: {}", hex)))?;
+                    *byte = u8::from_str_radix(digits, 16).map_err(D::Error::custom)?;
+                }
+                Option::from(F::from_repr(repr))
+                    .ok_or_else(|| D::Error::custom(format!("not a field element: {}", hex)))
+            })
+            .collect()
+    }
+}
+
+/// Test vectors for exercise 5, with private inputs `[a, b, c]`: five honest
+/// ones, and two claiming the wrong output.
+#[cfg(feature = "chap_2_exercise_5")]
+pub fn generate_test_vectors() -> Vec<TestVector<halo2_proofs::pasta::Fp>> {
+    use halo2_proofs::{arithmetic::Field, pasta::Fp};
+
+    let vector = |a: u64, b: u64, c: u64, expected_pass: bool| {
+        let (a, b, c) = (Fp::from(a), Fp::from(b), Fp::from(c));
+        let out = (c * a.square() * b.square() + c).cube();
+        TestVector {
+            private_inputs: vec![a, b, c],
+            public_inputs: vec![if expected_pass { out } else { out + Fp::ONE }],
+            k: 5,
+            expected_pass,
+        }
+    };
+    vec![
+        vector(2, 3, 2, true),
+        vector(0, 0, 0, true),
+        vector(1, 1, 1, true),
+        vector(5, 7, 11, true),
+        vector(1 << 20, 3, 9, true),
+        vector(2, 3, 2, false),
+        vector(4, 4, 4, false),
+    ]
+}
+
+/// Exercise 5's circuit for the private inputs `[a, b, c]`.
+#[cfg(feature = "chap_2_exercise_5")]
+pub(crate) fn exercise_5_circuit(
+    inputs: &[halo2_proofs::pasta::Fp],
+) -> crate::chap_2::exercise_5::MyCircuit<halo2_proofs::pasta::Fp> {
+    use halo2_proofs::circuit::Value;
+
+    crate::chap_2::exercise_5::MyCircuit {
+        a: Value::known(inputs[0]),
+        b: Value::known(inputs[1]),
+        c: inputs[2],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "chap_2_exercise_5")]
+    #[test]
+    fn test_exercise_5_vectors() {
+        use super::*;
+
+        let vectors = generate_test_vectors();
+        assert_eq!(vectors.iter().filter(|v| v.expected_pass).count(), 5);
+        assert_eq!(vectors.iter().filter(|v| !v.expected_pass).count(), 2);
+        for vector in &vectors {
+            vector.run(exercise_5_circuit);
+        }
+    }
+
+    #[cfg(all(feature = "chap_2_exercise_5", feature = "serde"))]
+    #[test]
+    fn test_exercise_5_vectors_json() {
+        use super::*;
+        use halo2_proofs::pasta::Fp;
+
+        let vectors = generate_test_vectors();
+        let json = serde_json::to_string_pretty(&vectors).unwrap();
+        let parsed: Vec<TestVector<Fp>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, vectors);
+        for vector in &parsed {
+            vector.run(exercise_5_circuit);
+        }
+
+        let bad = json.replacen("\"k\": 5", "\"k\": \"five\"", 1);
+        assert!(serde_json::from_str::<Vec<TestVector<Fp>>>(&bad).is_err());
+
+        // "é" is two bytes: the hex keeps its length in bytes, and an error
+        // comes back rather than a panic on a character boundary.
+        let bad = json.replacen("\"02", "\"é", 1);
+        assert!(serde_json::from_str::<Vec<TestVector<Fp>>>(&bad).is_err());
+    }
+
+    #[cfg(all(feature = "chap_2_exercise_5", feature = "serde"))]
+    #[test]
+    fn test_vector_from_exercise_5() {
+        use super::*;
+        use halo2_proofs::{arithmetic::Field, pasta::Fp};
+
+        let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+        let out = (c * a.square() * b.square() + c).cube();
+        let honest = test_vector_from_circuit(5, vec![a, b, c], vec![out], exercise_5_circuit);
+        let forged =
+            test_vector_from_circuit(5, vec![a, b, c], vec![out + Fp::ONE], exercise_5_circuit);
+        assert!(honest.expected_pass);
+        assert!(!forged.expected_pass);
+        assert_eq!(honest, generate_test_vectors()[0]);
+
+        for vector in [honest, forged] {
+            let json = serde_json::to_string(&vector).unwrap();
+            let parsed: TestVector<Fp> = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, vector);
+            parsed.run(exercise_5_circuit);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+/// Pin one of a chip's private inputs to a constant, for debugging.
+///
+/// When a constraint fails it helps to take the variables out of it one at a
+/// time. `WithConstantChip` wraps a chip and takes over one of its advice
+/// columns: cells the inner chip would witness there are assigned with
+/// `assign_advice_from_constant` instead, so the prover's value is ignored and
+/// the cell is copy-constrained to the fixed constant column.
+///
+/// This is not a wrapper for any chip: only witnesses the inner chip routes
+/// through an `AdviceAssigner` are intercepted, and the chip implements the
+/// trait itself for the unwrapped case. Cells it assigns with the region
+/// directly keep the prover's values, in the pinned column too.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Chip, Region, Value},
+    plonk::{Advice, Column, Error},
+};
+
+/// Assigns the advice cells of a chip's regions.
+pub trait AdviceAssigner<F: Field> {
+    fn assign_advice(
+        &self,
+        region: &mut Region<'_, F>,
+        annotation: &'static str,
+        column: Column<Advice>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct WithConstantChip<F: Field, C: Chip<F>> {
+    inner: C,
+    column_index: usize,
+    constant: F,
+}
+
+impl<F: Field, C: Chip<F> + From<C::Config>> WithConstantChip<F, C> {
+    /// Wrap the chip built from `config`, fixing the advice column with index
+    /// `column_index` to `constant`. The circuit must have a constant column
+    /// enabled.
+    pub fn construct(config: C::Config, (column_index, constant): (usize, F)) -> Self {
+        WithConstantChip {
+            inner: C::from(config),
+            column_index,
+            constant,
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<F: Field, C: Chip<F>> Chip<F> for WithConstantChip<F, C> {
+    type Config = C::Config;
+    type Loaded = C::Loaded;
+
+    fn config(&self) -> &Self::Config {
+        self.inner.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        self.inner.loaded()
+    }
+}
+
+impl<F: Field, C: Chip<F> + AdviceAssigner<F>> AdviceAssigner<F> for WithConstantChip<F, C> {
+    fn assign_advice(
+        &self,
+        region: &mut Region<'_, F>,
+        annotation: &'static str,
+        column: Column<Advice>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if column.index() == self.column_index {
+            region.assign_advice_from_constant(|| annotation, column, offset, self.constant)
+        } else {
+            self.inner
+                .assign_advice(region, annotation, column, offset, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, ConstraintSystem, Constraints, Instance, Selector},
+        poly::Rotation,
+    };
+    use std::marker::PhantomData;
+
+    #[derive(Debug, Clone)]
+    struct MulConfig {
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+        s_mul: Selector,
+    }
+
+    /// out = a * b, with `a` and `b` witnessed through an `AdviceAssigner`.
+    #[derive(Debug, Clone)]
+    struct MulChip<F: Field> {
+        config: MulConfig,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> MulChip<F> {
+        fn configure(meta: &mut ConstraintSystem<F>) -> MulConfig {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            let constant = meta.fixed_column();
+            meta.enable_constant(constant);
+            meta.enable_equality(instance);
+            for col in advice {
+                meta.enable_equality(col);
+            }
+            let s_mul = meta.selector();
+            meta.create_gate("mul", |meta| {
+                let s = meta.query_selector(s_mul);
+                let [a, b, out] = advice.map(|col| meta.query_advice(col, Rotation::cur()));
+                Constraints::with_selector(s, vec![a * b - out])
+            });
+            MulConfig {
+                advice,
+                instance,
+                s_mul,
+            }
+        }
+
+        fn mul(
+            &self,
+            mut layouter: impl Layouter<F>,
+            a: Value<F>,
+            b: Value<F>,
+            assigner: &impl AdviceAssigner<F>,
+        ) -> Result<(), Error> {
+            let [a_col, b_col, out_col] = self.config.advice;
+            let out = layouter.assign_region(
+                || "mul",
+                |mut region| {
+                    self.config.s_mul.enable(&mut region, 0)?;
+                    let a = assigner.assign_advice(&mut region, "a", a_col, 0, a)?;
+                    let b = assigner.assign_advice(&mut region, "b", b_col, 0, b)?;
+                    let out = a.value().copied() * b.value();
+                    region.assign_advice(|| "out", out_col, 0, || out)
+                },
+            )?;
+            layouter.constrain_instance(out.cell(), self.config.instance, 0)
+        }
+    }
+
+    impl<F: Field> Chip<F> for MulChip<F> {
+        type Config = MulConfig;
+        type Loaded = ();
+
+        fn config(&self) -> &Self::Config {
+            &self.config
+        }
+
+        fn loaded(&self) -> &Self::Loaded {
+            &()
+        }
+    }
+
+    impl<F: Field> From<MulConfig> for MulChip<F> {
+        fn from(config: MulConfig) -> Self {
+            MulChip {
+                config,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Every input is private.
+    impl<F: Field> AdviceAssigner<F> for MulChip<F> {
+        fn assign_advice(
+            &self,
+            region: &mut Region<'_, F>,
+            annotation: &'static str,
+            column: Column<Advice>,
+            offset: usize,
+            value: Value<F>,
+        ) -> Result<AssignedCell<F, F>, Error> {
+            region.assign_advice(|| annotation, column, offset, || value)
+        }
+    }
+
+    /// `a * b`, with input `pin.0` (0 for `a`, 1 for `b`) pinned to `pin.1`.
+    struct MulCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        pin: Option<(usize, Fp)>,
+    }
+
+    impl Circuit<Fp> for MulCircuit {
+        type Config = MulConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MulCircuit {
+                a: Value::unknown(),
+                b: Value::unknown(),
+                pin: self.pin,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MulChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            match self.pin {
+                None => {
+                    let chip = MulChip::from(config);
+                    chip.mul(layouter, self.a, self.b, &chip)
+                }
+                Some((input, constant)) => {
+                    let index = config.advice[input].index();
+                    let chip =
+                        WithConstantChip::<Fp, MulChip<Fp>>::construct(config, (index, constant));
+                    chip.inner().mul(layouter, self.a, self.b, &chip)
+                }
+            }
+        }
+    }
+
+    fn verifies(a: u64, b: u64, pin: Option<(usize, u64)>, out: u64) -> bool {
+        let circuit = MulCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            pin: pin.map(|(input, constant)| (input, Fp::from(constant))),
+        };
+        let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(out)]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn test_with_constant_isolates_witness() {
+        assert!(verifies(3, 5, None, 15));
+        // A prover that should have witnessed b = 5 but has 6 fails. The
+        // region computes its `out` from 6, so the copy to the instance does.
+        assert!(!verifies(3, 6, None, 15));
+
+        // Pinning `b` to the value it should have replaces the bad witness:
+        // the gate and the copy both see 5.
+        assert!(verifies(3, 6, Some((1, 5)), 15));
+        // Pinning `a` to its own value changes nothing: the culprit is `b`.
+        assert!(!verifies(3, 6, Some((0, 3)), 15));
+    }
+
+    #[test]
+    fn test_with_constant_ignores_prover_value() {
+        // Whatever the prover puts in for the pinned input, 5 is used.
+        for b in [0, 5, 7] {
+            assert!(verifies(3, b, Some((1, 5)), 15));
+        }
+        let circuit = MulCircuit {
+            a: Value::known(Fp::from(3)),
+            b: Value::unknown(),
+            pin: Some((1, Fp::from(5))),
+        };
+        let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(15)]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[cfg(feature = "chap_2_exercise_5")]
+    mod exercise_5 {
+        use super::*;
+        use crate::chap_2::exercise_5::{MyCircuit, SimpleChip, SimpleConfig};
+
+        impl<F: Field> From<SimpleConfig> for SimpleChip<F> {
+            fn from(config: SimpleConfig) -> Self {
+                SimpleChip::construct(config)
+            }
+        }
+
+        impl<F: Field> AdviceAssigner<F> for SimpleChip<F> {
+            fn assign_advice(
+                &self,
+                region: &mut Region<'_, F>,
+                annotation: &'static str,
+                column: Column<Advice>,
+                offset: usize,
+                value: Value<F>,
+            ) -> Result<AssignedCell<F, F>, Error> {
+                region.assign_advice(|| annotation, column, offset, || value)
+            }
+        }
+
+        /// Exercise 5 with `c` a private input like `a` and `b`, pinned to 3
+        /// by wrapping `SimpleChip`.
+        struct PinnedCircuit {
+            a: Value<Fp>,
+            b: Value<Fp>,
+            c: Value<Fp>,
+        }
+
+        impl Circuit<Fp> for PinnedCircuit {
+            type Config = SimpleConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                PinnedCircuit {
+                    a: Value::unknown(),
+                    b: Value::unknown(),
+                    c: Value::unknown(),
+                }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                SimpleChip::configure(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let index = config.advice[2].index();
+                let chip =
+                    WithConstantChip::<Fp, SimpleChip<Fp>>::construct(config, (index, Fp::from(3)));
+                let config = chip.config();
+                let out = layouter.assign_region(
+                    || "load private & witness",
+                    |mut region| {
+                        config.s_cpx.enable(&mut region, 0)?;
+                        let [a_col, b_col, c_col] = config.advice;
+                        let a = chip.assign_advice(&mut region, "a", a_col, 0, self.a)?;
+                        let b = chip.assign_advice(&mut region, "b", b_col, 0, self.b)?;
+                        let c = chip.assign_advice(&mut region, "c", c_col, 0, self.c)?;
+                        let ab = a.value().copied() * b.value();
+                        let e = ab * ab * c.value() + c.value();
+                        region.assign_advice(|| "out", config.advice[0], 1, || e * e * e)
+                    },
+                )?;
+                layouter.constrain_instance(out.cell(), config.instance, 0)
+            }
+        }
+
+        #[test]
+        fn test_with_constant_matches_exercise_5() {
+            for (a, b) in [(2, 3), (0, 5), (7, 11)] {
+                let (a, b, c) = (Fp::from(a), Fp::from(b), Fp::from(3));
+                let out = (c * a.square() * b.square() + c).cube();
+                let original = MyCircuit {
+                    c,
+                    a: Value::known(a),
+                    b: Value::known(b),
+                };
+                // Whatever the prover puts in for `c`, the pinned circuit
+                // computes what the original does with c = 3.
+                for prover_c in [Value::known(c), Value::known(Fp::from(7)), Value::unknown()] {
+                    let pinned = PinnedCircuit {
+                        a: Value::known(a),
+                        b: Value::known(b),
+                        c: prover_c,
+                    };
+                    for claimed in [out, out + Fp::one()] {
+                        let original = MockProver::run(5, &original, vec![vec![claimed]]).unwrap();
+                        let pinned = MockProver::run(5, &pinned, vec![vec![claimed]]).unwrap();
+                        assert_eq!(original.verify().is_ok(), claimed == out);
+                        assert_eq!(pinned.verify().is_ok(), claimed == out);
+                    }
+                }
+            }
+        }
+    }
+}
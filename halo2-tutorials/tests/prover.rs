@@ -0,0 +1,48 @@
+#![cfg(feature = "chap_2_exercise_5")]
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    pasta::{EqAffine, Fp},
+};
+use halo2_tutorials::{
+    utils::prover::{MockProverBackend, Prover, RealProverBackend},
+    Exercise5Circuit,
+};
+
+fn circuit() -> (Exercise5Circuit<Fp>, Fp) {
+    let (a, b, c) = (Fp::from(2), Fp::from(3), Fp::from(2));
+    let out = (c * a.square() * b.square() + c).cube();
+    let circuit = Exercise5Circuit {
+        c,
+        a: Value::known(a),
+        b: Value::known(b),
+    };
+    (circuit, out)
+}
+
+/// The same check, whichever backend.
+fn check(backend: &impl Prover<Fp, Exercise5Circuit<Fp>>) {
+    let (circuit, out) = circuit();
+    let proof = backend.prove(circuit, &[&[out]]).unwrap();
+    assert!(backend.verify(&proof, &[&[out]]).is_ok());
+    assert!(backend.verify(&proof, &[&[out + Fp::ONE]]).is_err());
+}
+
+#[test]
+fn test_mock_prover_backend() {
+    check(&MockProverBackend::new(5));
+}
+
+#[test]
+fn test_real_prover_backend_ipa() {
+    let (circuit, _) = circuit();
+    let backend = RealProverBackend::<EqAffine, _>::new(5, &circuit).unwrap();
+    check(&backend);
+
+    // The proof is the transcript bytes; flipping a bit breaks it.
+    let (circuit, out) = circuit();
+    let mut proof = backend.prove(circuit, &[&[out]]).unwrap();
+    proof[0] ^= 1;
+    assert!(backend.verify(&proof, &[&[out]]).is_err());
+}
@@ -0,0 +1,18 @@
+#![cfg(all(feature = "dev-graph", feature = "chap_2_exercise_5"))]
+
+use halo2_tutorials::utils::plot::render_all;
+
+#[test]
+fn test_render_all() {
+    let dir = std::env::temp_dir().join("halo2_tutorials_render_all");
+    let _ = std::fs::remove_dir_all(&dir);
+    let paths = render_all(&dir).unwrap();
+
+    for name in ["chap_1_simple", "chap_2_simple_chip", "chap_2_exercise_5"] {
+        assert!(paths.contains(&dir.join(format!("{}.svg", name))));
+    }
+    for path in &paths {
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert!(contents.starts_with("<svg"), "{} is no SVG", path.display());
+    }
+}